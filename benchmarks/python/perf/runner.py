@@ -37,6 +37,17 @@ def next_f64(state: int) -> tuple[int, float]:
     return state, state / float(0xFFFFFFFFFFFFFFFF)
 
 
+def worker_seed(seed: int, worker: int) -> int:
+    """Initial `next_f64` state for worker `worker`, matching the Rust
+    runner's `rng::stream`: output `worker + 1` of splitmix64 seeded with
+    `seed`, with 0 mapped to 1."""
+    mask = 0xFFFFFFFFFFFFFFFF
+    z = (seed + (worker + 1) * 0x9E3779B97F4A7C15) & mask
+    z = ((z ^ (z >> 30)) * 0xBF58476D1CE4E5B9) & mask
+    z = ((z ^ (z >> 27)) * 0x94D049BB133111EB) & mask
+    return (z ^ (z >> 31)) or 1
+
+
 def json_parse_transform(records: int) -> int:
     payload = [{"id": i, "value": i % 17, "name": f"row-{i}"} for i in range(records)]
     encoded = json.dumps(payload)
@@ -51,6 +62,12 @@ def git_sha() -> str:
         return "unknown00"
 
 
+def utc_timestamp(at: datetime) -> str:
+    """RFC 3339 in UTC with milliseconds and a `Z` suffix, as the Rust
+    runner's `clock::utc_timestamp` writes it."""
+    return at.astimezone(timezone.utc).isoformat(timespec="milliseconds").replace("+00:00", "Z")
+
+
 def metric(value: float, unit: str) -> dict[str, float | str]:
     return {"value": float(value), "unit": unit}
 
@@ -60,17 +77,20 @@ def make_base(
     category: str,
     metrics: dict[str, dict[str, float | str]],
     run_id: str,
+    parameters: dict[str, object] | None = None,
 ) -> dict:
+    # Parameter keys match the Rust runner's config fields; `{}` when there are none.
     return {
         "benchmark_id": benchmark_id,
         "category": category,
         "language": "python",
         "variant": {"runtime": "cpython", "version": platform.python_version()},
-        "environment": {"os": platform.platform(), "cpu_count": os.cpu_count() or 1},
+        "environment": {"os": platform.platform(), **cpu_count_block()},
         "metrics": metrics,
-        "timestamp": datetime.now(tz=timezone.utc).isoformat(),
+        "timestamp": utc_timestamp(datetime.now(tz=timezone.utc)),
         "commit_sha": git_sha(),
         "run_id": run_id,
+        "parameters": parameters or {},
     }
 
 
@@ -81,12 +101,40 @@ def env_int(name: str, default: int) -> int:
         return default
 
 
+def env_float(name: str, default: float) -> float:
+    try:
+        return float(os.environ.get(name, str(default)))
+    except ValueError:
+        return default
+
+
+def cpu_count_block() -> dict[str, int | str | None]:
+    # Same rules as the Rust runner: a positive BENCHMARK_CPUS wins, then detection, then 1.
+    detected = os.cpu_count()
+    override = env_int("BENCHMARK_CPUS", 0)
+    if override > 0:
+        effective, source = override, "override"
+    elif detected:
+        effective, source = detected, "detected"
+    else:
+        effective, source = 1, "fallback"
+    return {"cpu_count": effective, "cpus_detected": detected, "cpu_count_source": source}
+
+
+def configured_dataset() -> str:
+    return os.environ.get("BENCHMARK_ETL_DATASET", "benchmarks/shared/datasets/etl_input.jsonl.gz")
+
+
 def resolve_dataset_path() -> Path:
-    raw = os.environ.get("BENCHMARK_ETL_DATASET", "benchmarks/shared/datasets/etl_input.jsonl.gz")
-    path = Path(raw)
-    if path.is_absolute():
-        return path
-    return ROOT / path
+    # Relative paths resolve against BENCHMARK_DATA_DIR (default: the repo root).
+    data_dir = ROOT / os.environ.get("BENCHMARK_DATA_DIR", "")
+    return data_dir / configured_dataset()
+
+
+def http_targets(base_url: str) -> list[str]:
+    # Same rule as the Rust config: a comma-separated list, entries trimmed, empty ones dropped.
+    targets = [target.strip() for target in base_url.split(",") if target.strip()]
+    return targets or [base_url]
 
 
 def parse_base_url(base_url: str) -> tuple[str, int]:
@@ -117,6 +165,35 @@ def http_fetch_value(host: str, port: int, item_id: int) -> int:
     return int(payload["value"])
 
 
+def wait_until_ready(host: str, port: int, timeout: float) -> bool:
+    # Same probe as the Rust runner: GET /item/0 with exponential backoff; any response counts.
+    deadline = time.monotonic() + timeout
+    backoff = 0.05
+    while True:
+        try:
+            with socket.create_connection((host, port), timeout=min(10.0, max(timeout, 0.01))) as conn:
+                conn.sendall(f"GET /item/0 HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n".encode("utf-8"))
+                if conn.recv(5) == b"HTTP/":
+                    return True
+        except OSError:
+            pass
+        remaining = deadline - time.monotonic()
+        if remaining <= 0:
+            return False
+        time.sleep(min(backoff, remaining))
+        backoff = min(backoff * 2, 1.0)
+
+
+def target_ready(base_url: str, timeout: float) -> bool:
+    # A URL the client cannot use is reported like a server that never answered.
+    try:
+        host, port = parse_base_url(base_url)
+    except ValueError as err:
+        print(f"warning: {base_url}: {err}", file=sys.stderr)
+        return False
+    return wait_until_ready(host, port, timeout)
+
+
 def fetch_chunk(host: str, port: int, start: int, end: int, rows: int) -> tuple[int, int]:
     completed = 0
     checksum = 0
@@ -422,23 +499,38 @@ def build_startup_metrics() -> dict[str, dict[str, float | str]]:
 
 def run() -> list[dict]:
     run_id = str(uuid.uuid4())
-    base_url = os.environ.get("BENCHMARK_HTTP_BASE_URL", "http://127.0.0.1:8000")
+    targets = http_targets(os.environ.get("BENCHMARK_HTTP_BASE_URL", "http://127.0.0.1:8000"))
+    # Parity checks compare the Rust record for target 0 with this one.
+    base_url = targets[0]
+    if len(targets) > 1:
+        print(f"note: the Python runner measures only the first of {len(targets)} targets, {base_url}", file=sys.stderr)
     requests = env_int("BENCHMARK_HTTP_REQUESTS", 400)
     concurrency = env_int("BENCHMARK_HTTP_CONCURRENCY", 16)
     rows = env_int("BENCHMARK_HTTP_ROWS", 1000)
     dataset = resolve_dataset_path()
-    iterations = max(1, env_int("BENCHMARK_TEST_REPEAT", 3))
+    iterations = max(1, env_int("BENCHMARK_TEST_ITERATIONS", env_int("BENCHMARK_TEST_REPEAT", 3)))
+    samples = 200_000
+    records = 20_000
 
     start = time.perf_counter()
-    pi = monte_carlo_pi(200_000)
+    pi = monte_carlo_pi(samples)
     elapsed = time.perf_counter() - start
 
     start = time.perf_counter()
-    checksum = json_parse_transform(20_000)
+    checksum = json_parse_transform(records)
     parse_elapsed = time.perf_counter() - start
 
+    # Waiting for a server still starting up is kept out of runtime_seconds.
+    ready_start = time.perf_counter()
+    ready = target_ready(base_url, env_float("BENCHMARK_HTTP_READY_TIMEOUT", 30.0))
+    ready_wait = time.perf_counter() - ready_start
     start = time.perf_counter()
-    completed, http_checksum = io_http_benchmark(base_url, requests, rows, concurrency)
+    if ready:
+        completed, http_checksum = io_http_benchmark(base_url, requests, rows, concurrency)
+        attempted = requests
+    else:
+        print(f"warning: {base_url} did not answer; no requests sent", file=sys.stderr)
+        completed, http_checksum, attempted = 0, 0, 0
     http_elapsed = time.perf_counter() - start
 
     start = time.perf_counter()
@@ -455,6 +547,7 @@ def run() -> list[dict]:
                 "pi_estimate": metric(pi, "ratio"),
             },
             run_id,
+            {"samples": samples},
         ),
         make_base(
             "string_json_parse_transform",
@@ -464,6 +557,7 @@ def run() -> list[dict]:
                 "checksum": metric(float(checksum), "count"),
             },
             run_id,
+            {"records": records},
         ),
         make_base(
             "io_concurrent_http_client",
@@ -472,9 +566,12 @@ def run() -> list[dict]:
                 "runtime_seconds": metric(http_elapsed, "s"),
                 "requests_completed": metric(float(completed), "count"),
                 "checksum": metric(float(http_checksum), "count"),
-                "request_errors": metric(float(max(0, requests - completed)), "count"),
+                "request_errors": metric(float(max(0, attempted - completed)), "count"),
+                "ready_wait_seconds": metric(ready_wait, "s"),
+                "server_unavailable": metric(0.0 if ready else 1.0, "flag"),
             },
             run_id,
+            {"base_url": base_url, "request_count": requests, "rows": rows, "concurrency": concurrency},
         ),
         make_base(
             "data_pipeline_etl_minibatch",
@@ -486,6 +583,7 @@ def run() -> list[dict]:
                 "throughput_mb_s": metric(etl_mb / etl_elapsed if etl_elapsed > 0 else 0.0, "mb/s"),
             },
             run_id,
+            {"dataset": configured_dataset()},
         ),
         make_base(
             "dependency_vulnerability_scan_scorecard",
@@ -504,6 +602,7 @@ def run() -> list[dict]:
             "quality",
             test_reliability_metrics(iterations),
             run_id,
+            {"iterations": iterations},
         ),
         make_base(
             "build_startup_feedback_loop",
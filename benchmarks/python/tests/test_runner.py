@@ -1,6 +1,18 @@
+import os
 import unittest
+from datetime import datetime, timedelta, timezone
+from unittest import mock
 
-from benchmarks.python.perf.runner import json_parse_transform, monte_carlo_pi, run
+from benchmarks.python.perf.runner import (
+    http_targets,
+    json_parse_transform,
+    monte_carlo_pi,
+    parse_base_url,
+    run,
+    target_ready,
+    utc_timestamp,
+    worker_seed,
+)
 
 
 class RunnerTests(unittest.TestCase):
@@ -13,8 +25,39 @@ class RunnerTests(unittest.TestCase):
         checksum = json_parse_transform(100)
         self.assertEqual(checksum, sum(i % 17 for i in range(100)))
 
+    def test_worker_seeds_match_rust_vectors(self) -> None:
+        # Pinned in benchmarks/rust/src/rng.rs; both runners must agree.
+        vectors = [
+            (42, 0, 0xBDD732262FEB6E95),
+            (42, 1, 0x28EFE333B266F103),
+            (42, 2, 0x47526757130F9F52),
+            (42, 3, 0x581CE1FF0E4AE394),
+            (0, 0, 0xE220A8397B1DCDAF),
+            (7, 0, 0x63CBE1E459320DD7),
+        ]
+        for seed, worker, state in vectors:
+            self.assertEqual(worker_seed(seed, worker), state)
+
+    def test_timestamps_match_the_rust_format(self) -> None:
+        # benchmarks/rust/src/clock.rs writes the same instant this way.
+        at = datetime(2026, 1, 2, 5, 4, 5, 678_901, tzinfo=timezone(timedelta(hours=2)))
+        self.assertEqual(utc_timestamp(at), "2026-01-02T03:04:05.678Z")
+
+    def test_base_url_lists_split_like_the_rust_config(self) -> None:
+        self.assertEqual(http_targets("http://127.0.0.1:8000"), ["http://127.0.0.1:8000"])
+        targets = http_targets(" http://127.0.0.1:8000 ,, http://10.0.0.2:9000,")
+        self.assertEqual(targets, ["http://127.0.0.1:8000", "http://10.0.0.2:9000"])
+        self.assertEqual([parse_base_url(target) for target in targets], [("127.0.0.1", 8000), ("10.0.0.2", 9000)])
+
+    def test_unusable_base_url_is_not_ready(self) -> None:
+        with mock.patch("sys.stderr"):
+            self.assertFalse(target_ready("https://127.0.0.1:8443", 0.0))
+            self.assertFalse(target_ready("http://127.0.0.1:not-a-port", 0.0))
+
     def test_run_produces_full_matrix(self) -> None:
-        data = run()
+        # No server is expected to be listening; probe once instead of waiting.
+        with mock.patch.dict(os.environ, {"BENCHMARK_HTTP_READY_TIMEOUT": "0"}):
+            data = run()
         self.assertEqual(len(data), 8)
         self.assertEqual(
             {d["benchmark_id"] for d in data},
@@ -29,6 +72,15 @@ class RunnerTests(unittest.TestCase):
                 "build_startup_feedback_loop",
             },
         )
+        parameters = {d["benchmark_id"]: d["parameters"] for d in data}
+        self.assertEqual(parameters["build_startup_feedback_loop"], {})
+        self.assertEqual(
+            set(parameters["io_concurrent_http_client"]), {"base_url", "request_count", "rows", "concurrency"}
+        )
+        http = next(d["metrics"] for d in data if d["benchmark_id"] == "io_concurrent_http_client")
+        if http["server_unavailable"]["value"]:
+            self.assertEqual(http["requests_completed"]["value"], 0.0)
+            self.assertEqual(http["request_errors"]["value"], 0.0)
 
 
 if __name__ == "__main__":
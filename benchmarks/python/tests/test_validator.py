@@ -2,7 +2,7 @@ import json
 import unittest
 from pathlib import Path
 
-from benchmarks.shared.scripts.validate_results import validate
+from benchmarks.shared.scripts.validate_results import fingerprint_skew, thp_skew, validate
 
 
 class ValidatorTests(unittest.TestCase):
@@ -25,6 +25,12 @@ class ValidatorTests(unittest.TestCase):
         path.parent.mkdir(parents=True, exist_ok=True)
         path.write_text(json.dumps(sample), encoding="utf-8")
         self.assertEqual(validate(path, schema), [])
+        sample[0]["parameters"] = {"samples": 10}
+        path.write_text(json.dumps(sample), encoding="utf-8")
+        self.assertEqual(validate(path, schema), [])
+        sample[0]["parameters"] = [10]
+        path.write_text(json.dumps(sample), encoding="utf-8")
+        self.assertEqual(validate(path, schema), ["[0] parameters must be an object"])
         path.unlink()
 
     def test_validate_detects_performance_parity_mismatch(self) -> None:
@@ -158,6 +164,111 @@ class ValidatorTests(unittest.TestCase):
         self.assertTrue(any("string_json_parse_transform" in error for error in errors))
         path.unlink()
 
+    def test_validate_ignores_superseded_retry_attempts(self) -> None:
+        schema = Path("benchmarks/shared/schemas/result.schema.json")
+
+        def record(language: str, checksum: float, **extra: object) -> dict:
+            return {
+                "benchmark_id": "string_json_parse_transform",
+                "category": "performance",
+                "language": language,
+                "variant": {"runtime": language, "version": "1.0"},
+                "environment": {"os": "linux", "cpu_count": 2},
+                "metrics": {"checksum": {"value": checksum, "unit": "count"}},
+                "timestamp": "2026-01-01T00:00:00+00:00",
+                "commit_sha": "abcdef1",
+                "run_id": "run",
+                **extra,
+            }
+
+        sample = [
+            record("python", 100.0),
+            record("rust", 0.0, attempt=1, selected_for_comparison=False),
+            record("rust", 100.0, attempt=2, selected_for_comparison=True),
+        ]
+        path = Path("results/raw/test_result_retries.json")
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(json.dumps(sample), encoding="utf-8")
+        self.assertEqual(validate(path, schema), [])
+        path.unlink()
+
+        # Only the first target of a multi-target run has a Python counterpart.
+        sample = [
+            record("python", 100.0),
+            record("rust", 100.0, target={"index": 0, "base_url": "http://127.0.0.1:8000"}),
+            record("rust", 90.0, target={"index": 1, "base_url": "http://10.0.0.2:8000"}),
+        ]
+        path.write_text(json.dumps(sample), encoding="utf-8")
+        self.assertEqual(validate(path, schema), [])
+        path.unlink()
+
+    def test_validate_checks_sampled_metric_companions(self) -> None:
+        schema = Path("benchmarks/shared/schemas/result.schema.json")
+
+        def record(**metrics: float) -> dict:
+            return {
+                "benchmark_id": "cpu_sleep_precision",
+                "category": "performance",
+                "language": "rust",
+                "variant": {"runtime": "rust", "version": "1.0"},
+                "environment": {"os": "linux", "cpu_count": 2},
+                "metrics": {name: {"value": value, "unit": "us"} for name, value in metrics.items()},
+                "timestamp": "2026-01-01T00:00:00+00:00",
+                "commit_sha": "abcdef1",
+                "run_id": "run",
+            }
+
+        sample = [
+            record(p95_us=120.0, p95_us_n=50.0, mean_us=80.0, mean_us_n=50.0, mean_us_rse=0.02),
+            record(p95_us=120.0, p95_us_rse=0.02, tasks_n=3.0),
+            record(mean_us=80.0, mean_us_n=2.5, mean_us_rse=-1.0),
+        ]
+        path = Path("results/raw/test_result_companions.json")
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(json.dumps(sample), encoding="utf-8")
+        errors = validate(path, schema)
+        path.unlink()
+        self.assertEqual(
+            errors,
+            [
+                "[1] metric p95_us is missing companion p95_us_n",
+                "[2] metric mean_us_n must be a non-negative integer count",
+                "[2] metric mean_us_rse must be non-negative",
+            ],
+        )
+
+    def test_fingerprint_skew_is_reported_per_language(self) -> None:
+        def record(language: str, fingerprint: str | None) -> dict:
+            item = {"benchmark_id": "cpu_monte_carlo_pi", "language": language}
+            if fingerprint is not None:
+                item["harness_fingerprint"] = fingerprint
+            return item
+
+        same = [record("rust", "00000000000000aa"), record("rust", "00000000000000aa"), record("python", None)]
+        self.assertEqual(fingerprint_skew(same), [])
+        mixed = [record("rust", "00000000000000aa"), record("rust", "00000000000000bb")]
+        self.assertEqual(
+            fingerprint_skew(mixed),
+            ["[rust] records come from 2 harness builds: 00000000000000aa, 00000000000000bb"],
+        )
+
+    def test_thp_skew_is_reported_per_benchmark(self) -> None:
+        def record(benchmark_id: str, mode: str | None) -> dict:
+            environment = {"os": "linux"} if mode is None else {"os": "linux", "thp_enabled": mode}
+            return {"benchmark_id": benchmark_id, "environment": environment}
+
+        results = [
+            record("data_pipeline_etl_minibatch", "always"),
+            record("data_pipeline_etl_minibatch", "madvise"),
+            record("data_pipeline_etl_minibatch", None),
+            record("string_json_parse_transform", "never"),
+            record("string_json_parse_transform", "never"),
+        ]
+        self.assertEqual(
+            thp_skew(results),
+            ["[data_pipeline_etl_minibatch] records were measured under THP modes: always, madvise"],
+        )
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -0,0 +1,15 @@
+use std::env;
+use std::time::SystemTime;
+
+#[allow(dead_code)]
+#[path = "src/build_stamp.rs"]
+mod build_stamp;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/build_stamp.rs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let source_date_epoch = env::var("SOURCE_DATE_EPOCH").ok();
+    let epoch = build_stamp::epoch_seconds(source_date_epoch.as_deref(), SystemTime::now());
+    println!("cargo:rustc-env=BENCHMARK_BUILD_EPOCH={epoch}");
+}
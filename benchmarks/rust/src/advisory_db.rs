@@ -0,0 +1,134 @@
+//! Finds the RustSec advisory database `cargo audit` reads and how old it
+//! is. A stale clone reports zero vulnerabilities just as confidently as a
+//! fresh one, so the scorecard reports the age next to the findings.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// `CARGO_HOME`, else `~/.cargo`.
+pub(crate) fn cargo_home() -> Option<PathBuf> {
+    if let Some(home) = env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(home));
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".cargo"))
+}
+
+/// The database directory. A configured path (also passed to `cargo audit
+/// --db`) is used as given; otherwise `advisory-db` under the cargo home
+/// (cargo-audit up to 0.20), then the newest clone under `advisory-dbs/`
+/// (later versions keep one per source URL). `Err` carries the path that
+/// was expected, for the precondition log.
+pub(crate) fn locate(configured: Option<&Path>, cargo_home: Option<&Path>) -> Result<PathBuf, PathBuf> {
+    if let Some(path) = configured {
+        return if path.is_dir() {
+            Ok(path.to_path_buf())
+        } else {
+            Err(path.to_path_buf())
+        };
+    }
+    let Some(home) = cargo_home else {
+        return Err(PathBuf::from("$CARGO_HOME/advisory-db"));
+    };
+    let legacy = home.join("advisory-db");
+    if legacy.is_dir() {
+        return Ok(legacy);
+    }
+    let newest = fs::read_dir(home.join("advisory-dbs"))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| Some((last_modified(&path).ok()?, path)))
+        .max();
+    newest.map(|(_, path)| path).ok_or(legacy)
+}
+
+/// When the clone was last refreshed: the mtime of the last fetch, else of
+/// the checked-out `HEAD`, else of the directory itself.
+pub(crate) fn last_modified(db: &Path) -> io::Result<SystemTime> {
+    let git = db.join(".git");
+    [git.join("FETCH_HEAD"), git.join("HEAD")]
+        .iter()
+        .find_map(|marker| fs::metadata(marker).and_then(|meta| meta.modified()).ok())
+        .map_or_else(|| fs::metadata(db)?.modified(), Ok)
+}
+
+/// Fractional days from `modified` to `now`; 0.0 for a clock skewed into
+/// the future.
+pub(crate) fn age_days(modified: SystemTime, now: SystemTime) -> f64 {
+    let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+    age.as_secs_f64() / 86_400.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn temp_home(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("advisory-db-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A clone whose last fetch was `days` ago.
+    fn clone_fetched(db: &Path, days: u64) {
+        fs::create_dir_all(db.join(".git")).unwrap();
+        let fetch_head = File::create(db.join(".git/FETCH_HEAD")).unwrap();
+        fetch_head.set_modified(SystemTime::now() - DAY * days as u32).unwrap();
+    }
+
+    #[test]
+    fn finds_the_legacy_and_per_source_layouts() {
+        let home = temp_home("layouts");
+        let legacy = home.join("advisory-db");
+        assert_eq!(locate(None, Some(&home)), Err(legacy.clone()));
+
+        let older = home.join("advisory-dbs/github.com-1111");
+        let newer = home.join("advisory-dbs/github.com-2222");
+        clone_fetched(&older, 30);
+        clone_fetched(&newer, 2);
+        assert_eq!(locate(None, Some(&home)), Ok(newer));
+
+        clone_fetched(&legacy, 90);
+        assert_eq!(locate(None, Some(&home)), Ok(legacy));
+
+        let configured = home.join("mirror");
+        assert_eq!(locate(Some(&configured), Some(&home)), Err(configured.clone()));
+        clone_fetched(&configured, 1);
+        assert_eq!(locate(Some(&configured), None), Ok(configured));
+        assert_eq!(locate(None, None), Err(PathBuf::from("$CARGO_HOME/advisory-db")));
+        let _ = fs::remove_dir_all(home);
+    }
+
+    #[test]
+    fn age_comes_from_the_last_fetch() {
+        let home = temp_home("age");
+        let db = home.join("advisory-db");
+        clone_fetched(&db, 10);
+        let age = age_days(last_modified(&db).unwrap(), SystemTime::now());
+        assert!((age - 10.0).abs() < 0.01, "{age}");
+
+        // Without git metadata the directory's own mtime is used.
+        let bare = home.join("bare");
+        fs::create_dir_all(&bare).unwrap();
+        assert!(age_days(last_modified(&bare).unwrap(), SystemTime::now()) < 0.01);
+        let _ = fs::remove_dir_all(home);
+    }
+
+    #[test]
+    fn age_in_fractional_days() {
+        let modified = SystemTime::UNIX_EPOCH + DAY * 100;
+        assert_eq!(age_days(modified, modified + DAY * 3 / 2), 1.5);
+        assert_eq!(age_days(modified + DAY, modified), 0.0);
+    }
+}
@@ -0,0 +1,200 @@
+//! `--anonymize`: one pass over the serialized output that removes what
+//! identifies the machine or its user. It walks every string and key rather
+//! than naming fields, so a field added later cannot slip past it; metric
+//! values and units are numbers and plain words and come through unchanged.
+
+use serde_json::{Map, Value};
+
+use crate::checksum_store::params_hash;
+
+/// Environment keys that identify the machine. None are emitted today; a
+/// fingerprint added later is hashed instead of published.
+const FINGERPRINT_KEYS: [&str; 3] = ["hostname", "machine_id", "fingerprint"];
+
+/// Per-user directory roots; the segment after one is a user name.
+const USER_ROOTS: [&str; 3] = ["/home/", "/Users/", "C:\\Users\\"];
+
+pub(crate) struct Anonymizer {
+    home: Option<String>,
+    /// Hosts to replace, longest first so a host never shadows a longer one
+    /// containing it.
+    hosts: Vec<String>,
+}
+
+impl Anonymizer {
+    pub(crate) fn new(home: Option<String>, hosts: impl IntoIterator<Item = String>) -> Self {
+        let mut hosts: Vec<String> = hosts.into_iter().filter(|host| !host.is_empty()).collect();
+        hosts.sort_by_key(|host| std::cmp::Reverse(host.len()));
+        hosts.dedup();
+        Anonymizer {
+            home: home.filter(|home| home.len() > 1),
+            hosts,
+        }
+    }
+
+    pub(crate) fn apply(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.apply(item)),
+            Value::Object(map) => {
+                let entries = std::mem::take(map);
+                *map = entries
+                    .into_iter()
+                    .map(|(key, mut item)| {
+                        if FINGERPRINT_KEYS.contains(&key.as_str()) {
+                            item = Value::String(stable_hash(&item.to_string()));
+                        } else {
+                            self.apply(&mut item);
+                        }
+                        (self.redact(&key), item)
+                    })
+                    .collect::<Map<String, Value>>();
+            }
+            _ => {}
+        }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut text = match &self.home {
+            Some(home) => replace_prefix(text, home),
+            None => text.to_string(),
+        };
+        for root in USER_ROOTS {
+            text = replace_user_dirs(&text, root);
+        }
+        for host in &self.hosts {
+            text = text.replace(host.as_str(), &format!("host-{}", stable_hash(host)));
+        }
+        text
+    }
+}
+
+/// First eight hex digits of the checksum store's FNV-1a hash; the same
+/// input maps to the same label in every run.
+fn stable_hash(text: &str) -> String {
+    params_hash(&Value::String(text.to_string()))[..8].to_string()
+}
+
+fn ends_path_segment(rest: &str) -> bool {
+    rest.chars()
+        .next()
+        .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+}
+
+/// `home` becomes `~` where it is a whole path prefix (`/home/ann/x`, not
+/// `/home/anna/x` for home `/home/ann`).
+fn replace_prefix(text: &str, home: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(home) {
+        let after = &rest[at + home.len()..];
+        out.push_str(&rest[..at]);
+        out.push_str(if ends_path_segment(after) { "~" } else { home });
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `<root><user>` becomes `~`, for any user, so paths copied from another
+/// machine are covered too.
+fn replace_user_dirs(text: &str, root: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(root) {
+        out.push_str(&rest[..at]);
+        out.push('~');
+        let after = &rest[at + root.len()..];
+        let user_end = after
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+            .unwrap_or(after.len());
+        rest = &after[user_end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record() -> Value {
+        json!({
+            "benchmark_id": "data_pipeline_etl_minibatch",
+            "environment": {"os": "linux", "hostname": "build-07.corp.example", "cpu_count": 8},
+            "metrics": {"runtime_seconds": {"value": 1.5, "unit": "s"}},
+            "preconditions": [
+                {"check": "dataset", "name": "/home/ann/data/etl.jsonl.gz", "found": true, "decision": "run"},
+                {"check": "base_url", "name": "http://bench.corp.example:8080", "found": true, "decision": "run"},
+                {"check": "advisory_db", "name": "/Users/bob/.cargo/advisory-db", "found": false, "decision": "skip"}
+            ],
+            "matrix_cell": {"index": 0, "params": {"dataset": "C:\\Users\\carol\\etl.gz"}},
+            "notes": {"/home/ann/cache": ["bench.corp.example", "/srv/shared/ok"]}
+        })
+    }
+
+    #[test]
+    fn no_home_paths_or_hosts_survive() {
+        let anonymizer = Anonymizer::new(
+            Some("/home/ann".to_string()),
+            ["bench.corp.example".to_string()],
+        );
+        let mut value = record();
+        anonymizer.apply(&mut value);
+        let text = value.to_string();
+        for leak in [
+            "/home/",
+            "/Users/",
+            "Users\\\\",
+            "ann",
+            "bob",
+            "carol",
+            "bench.corp.example",
+            "build-07",
+        ] {
+            assert!(!text.contains(leak), "{leak} survived in {text}");
+        }
+        assert_eq!(value["preconditions"][0]["name"], "~/data/etl.jsonl.gz");
+        assert_eq!(value["notes"]["~/cache"][1], "/srv/shared/ok");
+        let label = format!("host-{}", stable_hash("bench.corp.example"));
+        assert_eq!(
+            value["preconditions"][1]["name"],
+            format!("http://{label}:8080")
+        );
+        assert_eq!(value["notes"]["~/cache"][0], label);
+    }
+
+    #[test]
+    fn comparison_fields_are_untouched() {
+        let anonymizer = Anonymizer::new(Some("/home/ann".to_string()), ["127.0.0.1".to_string()]);
+        let mut value = record();
+        anonymizer.apply(&mut value);
+        let original = record();
+        for key in ["benchmark_id", "metrics"] {
+            assert_eq!(value[key], original[key]);
+        }
+        assert_eq!(value["environment"]["cpu_count"], 8);
+        assert_eq!(value["environment"]["os"], "linux");
+        // Hashed, and the same in every run.
+        let hashed = value["environment"]["hostname"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(hashed.len(), 8);
+        let mut again = record();
+        anonymizer.apply(&mut again);
+        assert_eq!(again["environment"]["hostname"], hashed.as_str());
+    }
+
+    #[test]
+    fn home_prefix_matches_whole_segments_only() {
+        assert_eq!(
+            replace_prefix("/root/x:/rooted/y:/root", "/root"),
+            "~/x:/rooted/y:~"
+        );
+        assert_eq!(replace_user_dirs("a /home/x.y/z b", "/home/"), "a ~/z b");
+        let anonymizer = Anonymizer::new(Some("/".to_string()), []);
+        assert_eq!(anonymizer.redact("/srv/data"), "/srv/data");
+    }
+}
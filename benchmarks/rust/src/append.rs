@@ -0,0 +1,231 @@
+//! `--append`: adding a run's records to an existing results file instead
+//! of overwriting it.
+//!
+//! Retries and orchestrator re-invocations that reuse a `--run-id` would
+//! otherwise leave two sets of records for one run in the file, which
+//! dashboards count twice. The existing file is read into typed records
+//! first, so a file this runner could not have written stops the run
+//! instead of being silently rewritten; what happens to records that share
+//! the new run's id is the `--append-policy`.
+
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::record::BenchmarkRecord;
+
+/// What to do with existing records that have the new run's `run_id`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum AppendPolicy {
+    /// Write the new records next to them.
+    Keep,
+    /// Drop them; the new invocation is authoritative.
+    #[default]
+    Replace,
+    /// Refuse to run.
+    Error,
+}
+
+impl AppendPolicy {
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "keep" => Ok(AppendPolicy::Keep),
+            "replace" => Ok(AppendPolicy::Replace),
+            "error" => Ok(AppendPolicy::Error),
+            _ => Err(format!("expected keep, replace or error, got {value:?}")),
+        }
+    }
+}
+
+/// The records already in `path`; none when it does not exist or is blank.
+pub(crate) fn load(path: &Path) -> Result<Vec<BenchmarkRecord>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("{}: {err}", path.display())),
+    };
+    parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn parse(text: &str) -> Result<Vec<BenchmarkRecord>, String> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let values: Vec<Value> =
+        serde_json::from_str(text).map_err(|err| format!("not a JSON array of records: {err}"))?;
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            serde_json::from_value(value).map_err(|err| format!("record {index}: {err}"))
+        })
+        .collect()
+}
+
+/// How many of `existing` belong to `run_id`.
+pub(crate) fn conflicts(existing: &[BenchmarkRecord], run_id: &str) -> usize {
+    existing.iter().filter(|record| record.run_id == run_id).count()
+}
+
+/// What `merge` writes.
+#[derive(Debug)]
+pub(crate) struct Merged {
+    /// Existing records first, in file order, then the new ones.
+    pub(crate) records: Vec<Value>,
+    /// Existing records dropped because the new run has their `run_id`.
+    pub(crate) replaced: usize,
+}
+
+/// `existing` followed by `records`, the run's new records, with
+/// `policy` applied to existing records from the same run.
+pub(crate) fn merge(
+    mut existing: Vec<BenchmarkRecord>,
+    records: &[Value],
+    policy: AppendPolicy,
+) -> Result<Merged, String> {
+    let mut run_ids: Vec<&str> = records.iter().filter_map(|r| r["run_id"].as_str()).collect();
+    run_ids.dedup();
+    let same_run = |record: &BenchmarkRecord| run_ids.contains(&record.run_id.as_str());
+    let before = existing.len();
+    match policy {
+        AppendPolicy::Keep => {}
+        AppendPolicy::Replace => existing.retain(|record| !same_run(record)),
+        AppendPolicy::Error => {
+            let count = existing.iter().filter(|record| same_run(record)).count();
+            if count > 0 {
+                return Err(format!(
+                    "{count} existing record(s) already have run_id {}",
+                    run_ids.join(", ")
+                ));
+            }
+        }
+    }
+    let replaced = before - existing.len();
+    let mut merged: Vec<Value> = existing.iter().map(BenchmarkRecord::to_value).collect();
+    merged.extend(records.iter().cloned());
+    Ok(Merged {
+        records: merged,
+        replaced,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::record;
+    use serde_json::json;
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Two records from run `old` and one from run `new`, as a results file.
+    fn existing_file() -> String {
+        let mut records: Vec<Value> = serde_json::from_str(fixtures::python_records()).unwrap();
+        records.truncate(3);
+        for (record, run_id) in records.iter_mut().zip(["old", "new", "old"]) {
+            record["run_id"] = json!(run_id);
+        }
+        // Metrics that were not finite are written as null.
+        records[0]["metrics"]["runtime_seconds"]["value"] = Value::Null;
+        record::to_json_pretty(&records)
+    }
+
+    fn new_records() -> Vec<Value> {
+        let mut records: Vec<Value> = serde_json::from_str(fixtures::python_records()).unwrap();
+        records.truncate(2);
+        for record in &mut records {
+            record["run_id"] = json!("new");
+            record["language"] = json!("rust");
+        }
+        records
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("append-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run_ids(merged: &Merged) -> Vec<&str> {
+        merged.records.iter().map(|record| record["run_id"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn policies_parse() {
+        assert_eq!(AppendPolicy::parse("keep"), Ok(AppendPolicy::Keep));
+        assert_eq!(AppendPolicy::parse("replace"), Ok(AppendPolicy::Replace));
+        assert_eq!(AppendPolicy::parse("error"), Ok(AppendPolicy::Error));
+        assert!(AppendPolicy::parse("skip").is_err());
+        assert_eq!(AppendPolicy::default(), AppendPolicy::Replace);
+    }
+
+    #[test]
+    fn replace_drops_the_runs_earlier_records() {
+        let existing = parse(&existing_file()).unwrap();
+        assert_eq!(conflicts(&existing, "new"), 1);
+        let merged = merge(existing, &new_records(), AppendPolicy::Replace).unwrap();
+        assert_eq!(merged.replaced, 1);
+        assert_eq!(run_ids(&merged), ["old", "old", "new", "new"]);
+        // Other runs' records are written back as they were read.
+        let before: Vec<Value> = serde_json::from_str(&existing_file()).unwrap();
+        assert_eq!(merged.records[0], before[0]);
+        assert_eq!(merged.records[1], before[2]);
+    }
+
+    #[test]
+    fn keep_writes_both() {
+        let existing = parse(&existing_file()).unwrap();
+        let merged = merge(existing, &new_records(), AppendPolicy::Keep).unwrap();
+        assert_eq!(merged.replaced, 0);
+        assert_eq!(run_ids(&merged), ["old", "new", "old", "new", "new"]);
+        assert_eq!(merged.records[1]["language"], "python");
+    }
+
+    #[test]
+    fn error_refuses_only_when_the_run_is_already_there() {
+        let existing = parse(&existing_file()).unwrap();
+        let err = merge(existing.clone(), &new_records(), AppendPolicy::Error).unwrap_err();
+        assert_eq!(err, "1 existing record(s) already have run_id new");
+
+        let mut fresh = new_records();
+        for record in &mut fresh {
+            record["run_id"] = json!("newer");
+        }
+        assert_eq!(conflicts(&existing, "newer"), 0);
+        let merged = merge(existing, &fresh, AppendPolicy::Error).unwrap();
+        assert_eq!(run_ids(&merged), ["old", "new", "old", "newer", "newer"]);
+    }
+
+    #[test]
+    fn a_missing_or_blank_file_has_no_records() {
+        let dir = temp_dir("blank");
+        assert!(load(&dir.join("missing.json")).unwrap().is_empty());
+        let blank = dir.join("blank.json");
+        fs::write(&blank, "\n").unwrap();
+        assert!(load(&blank).unwrap().is_empty());
+        let merged = merge(Vec::new(), &new_records(), AppendPolicy::Error).unwrap();
+        assert_eq!((merged.records.len(), merged.replaced), (2, 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_corrupted_file_is_an_error() {
+        let dir = temp_dir("corrupted");
+        let path = dir.join("results.json");
+        let truncated = existing_file();
+        fs::write(&path, &truncated[..truncated.len() / 2]).unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(err.starts_with(&format!("{}: not a JSON array", path.display())), "{err}");
+
+        let mut records: Vec<Value> = serde_json::from_str(&existing_file()).unwrap();
+        records[1].as_object_mut().unwrap().remove("run_id");
+        fs::write(&path, record::to_json_pretty(&records)).unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(err.contains("record 1: missing field `run_id`"), "{err}");
+
+        fs::write(&path, r#"{"run_id": "old"}"#).unwrap();
+        assert!(load(&path).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
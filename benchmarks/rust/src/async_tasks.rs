@@ -0,0 +1,201 @@
+//! `concurrency_async_tasks`: the tokio runtime on its own, with no I/O, as
+//! a comparison point for Python's `asyncio.create_task` and `Queue`
+//! numbers. Three workloads share one multi-threaded runtime:
+//!
+//! - spawn: `tasks` trivial tasks, each awaited through its handle;
+//! - ping-pong: one message bounced `round_trips` times over a pair of
+//!   bounded mpsc channels, timed per round trip;
+//! - fan-in: `producers` tasks each sending `messages_per_producer`
+//!   numbered messages into one channel that a single consumer drains.
+//!
+//! Every workload checks what it got back (every task ran, every echo is
+//! the value sent, every producer's sequence arrives complete and in order)
+//! so an optimizer that drops the work shows up as a count, not a speedup.
+//! Built without the `tokio` feature the benchmark is not registered.
+
+use std::io;
+
+use crate::config::AsyncTasksConfig;
+use crate::stats::Samples;
+
+#[derive(Debug, Default)]
+pub(crate) struct AsyncTasksRun {
+    pub(crate) spawn_seconds: f64,
+    /// Tasks that ran and whose result came back through their handle.
+    pub(crate) tasks_observed: usize,
+    pub(crate) round_trip_us: Samples,
+    /// Echoes that were not the value sent, or never came.
+    pub(crate) echo_errors: usize,
+    pub(crate) fan_in_seconds: f64,
+    pub(crate) messages_observed: usize,
+    /// Fan-in messages out of order within their producer's sequence, or
+    /// missing from it at the end.
+    pub(crate) sequence_errors: usize,
+}
+
+/// Checks each producer's sequence as the consumer sees it.
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+#[derive(Debug)]
+struct SequenceCheck {
+    next: Vec<u64>,
+    errors: usize,
+}
+
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+impl SequenceCheck {
+    fn new(producers: usize) -> Self {
+        SequenceCheck {
+            next: vec![0; producers],
+            errors: 0,
+        }
+    }
+
+    fn observe(&mut self, producer: usize, seq: u64) {
+        match self.next.get_mut(producer) {
+            Some(next) if *next == seq => *next += 1,
+            Some(next) => {
+                self.errors += 1;
+                *next = seq + 1;
+            }
+            None => self.errors += 1,
+        }
+    }
+
+    /// Errors so far plus every message still owed at the end.
+    fn finish(self, per_producer: u64) -> usize {
+        let missing: u64 = self.next.iter().map(|next| per_producer.saturating_sub(*next)).sum();
+        self.errors + missing as usize
+    }
+}
+
+/// True when `run` can work in this build.
+pub(crate) fn supported() -> bool {
+    cfg!(feature = "tokio")
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) fn run(config: &AsyncTasksConfig, worker_threads: usize) -> io::Result<AsyncTasksRun> {
+    use std::time::Instant;
+    use tokio::sync::mpsc;
+
+    use crate::DEFAULT_SEED;
+
+    /// Round-trip latencies kept; longer runs keep a uniform reservoir.
+    const ROUND_TRIP_SAMPLE_CAP: usize = 100_000;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(worker_threads.max(1))
+        .build()?;
+    runtime.block_on(async {
+        let mut run = AsyncTasksRun {
+            round_trip_us: Samples::reservoir(ROUND_TRIP_SAMPLE_CAP, DEFAULT_SEED),
+            ..AsyncTasksRun::default()
+        };
+
+        let start = Instant::now();
+        let handles: Vec<_> = (0..config.tasks)
+            .map(|task| tokio::spawn(async move { std::hint::black_box(task) }))
+            .collect();
+        for (task, handle) in handles.into_iter().enumerate() {
+            if handle.await.is_ok_and(|returned| returned == task) {
+                run.tasks_observed += 1;
+            }
+        }
+        run.spawn_seconds = start.elapsed().as_secs_f64();
+
+        let (ping_tx, mut ping_rx) = mpsc::channel::<u64>(1);
+        let (pong_tx, mut pong_rx) = mpsc::channel::<u64>(1);
+        let echo = tokio::spawn(async move {
+            while let Some(value) = ping_rx.recv().await {
+                if pong_tx.send(value).await.is_err() {
+                    break;
+                }
+            }
+        });
+        for value in 0..config.round_trips as u64 {
+            let sent = Instant::now();
+            let echoed = match ping_tx.send(value).await {
+                Ok(()) => pong_rx.recv().await,
+                Err(_) => None,
+            };
+            run.round_trip_us.push(sent.elapsed().as_secs_f64() * 1e6);
+            if echoed != Some(value) {
+                run.echo_errors += 1;
+            }
+        }
+        drop(ping_tx);
+        let _ = echo.await;
+
+        let per_producer = config.messages_per_producer as u64;
+        let (tx, mut rx) = mpsc::channel::<(usize, u64)>(config.channel_capacity.max(1));
+        let start = Instant::now();
+        for producer in 0..config.producers {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                for seq in 0..per_producer {
+                    if tx.send((producer, seq)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        let mut check = SequenceCheck::new(config.producers);
+        while let Some((producer, seq)) = rx.recv().await {
+            run.messages_observed += 1;
+            check.observe(producer, seq);
+        }
+        run.fan_in_seconds = start.elapsed().as_secs_f64();
+        run.sequence_errors = check.finish(per_producer);
+        Ok(run)
+    })
+}
+
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn run(_config: &AsyncTasksConfig, _worker_threads: usize) -> io::Result<AsyncTasksRun> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "async task benchmarks need the tokio feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_must_arrive_complete_and_in_order() {
+        let mut check = SequenceCheck::new(2);
+        for (producer, seq) in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)] {
+            check.observe(producer, seq);
+        }
+        assert_eq!(check.finish(3), 0);
+
+        // A skipped message, a repeat, a stranger, and one producer short.
+        let mut check = SequenceCheck::new(2);
+        for (producer, seq) in [(0, 0), (0, 2), (0, 2), (2, 0), (1, 0)] {
+            check.observe(producer, seq);
+        }
+        assert_eq!(check.errors, 3);
+        assert_eq!(check.finish(3), 5);
+    }
+
+    #[test]
+    fn every_workload_is_observed_in_full() {
+        let config = AsyncTasksConfig {
+            tasks: 1_000,
+            round_trips: 50,
+            producers: 4,
+            messages_per_producer: 250,
+            channel_capacity: 8,
+            ..AsyncTasksConfig::default()
+        };
+        let Ok(run) = run(&config, 2) else {
+            assert!(!supported());
+            return;
+        };
+        assert_eq!(run.tasks_observed, 1_000);
+        assert_eq!((run.round_trip_us.len(), run.echo_errors), (50, 0));
+        assert_eq!((run.messages_observed, run.sequence_errors), (1_000, 0));
+    }
+}
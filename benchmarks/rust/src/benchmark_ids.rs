@@ -0,0 +1,339 @@
+//! Every benchmark id, defined once. Ids name result series across runs and
+//! languages, so a typo in a threshold file or matrix must fail loudly
+//! instead of starting a new series; everything that accepts an id checks
+//! it here. Renaming one orphans its history; `fixtures/benchmark_ids.txt`
+//! pins the list so a rename shows up in review.
+
+pub(crate) const CPU_MONTE_CARLO_PI: &str = "cpu_monte_carlo_pi";
+pub(crate) const CPU_MONTE_CARLO_PI_PARALLEL: &str = "cpu_monte_carlo_pi_parallel";
+pub(crate) const CPU_SLEEP_PRECISION: &str = "cpu_sleep_precision";
+pub(crate) const STRING_JSON_PARSE_TRANSFORM: &str = "string_json_parse_transform";
+pub(crate) const IO_CONCURRENT_HTTP_CLIENT: &str = "io_concurrent_http_client";
+pub(crate) const IO_CONCURRENT_HTTP_POST: &str = "io_concurrent_http_post";
+pub(crate) const IO_HTTP_CONNECTION_CHURN: &str = "io_http_connection_churn";
+pub(crate) const IO_HTTP_JITTER_SENSITIVITY: &str = "io_http_jitter_sensitivity";
+pub(crate) const IO_HTTP_STREAMING: &str = "io_http_streaming";
+pub(crate) const DATA_PIPELINE_ETL_MINIBATCH: &str = "data_pipeline_etl_minibatch";
+pub(crate) const DATA_PIPELINE_ETL_ASYNC: &str = "data_pipeline_etl_async";
+pub(crate) const CONCURRENCY_ASYNC_TASKS: &str = "concurrency_async_tasks";
+pub(crate) const DEPENDENCY_VULNERABILITY_SCAN_SCORECARD: &str =
+    "dependency_vulnerability_scan_scorecard";
+pub(crate) const STATIC_SECURITY_LINT_BENCHMARK: &str = "static_security_lint_benchmark";
+pub(crate) const TEST_ROBUSTNESS_RELIABILITY: &str = "test_robustness_reliability";
+pub(crate) const BUILD_STARTUP_FEEDBACK_LOOP: &str = "build_startup_feedback_loop";
+
+/// The runner's own stage timings (`--harness-overhead`). Not a registered
+/// benchmark: nothing selects, configures or thresholds it.
+pub(crate) const HARNESS_OVERHEAD: &str = "harness_overhead";
+
+/// In run order.
+pub(crate) const ALL: [&str; 16] = [
+    CPU_MONTE_CARLO_PI,
+    CPU_MONTE_CARLO_PI_PARALLEL,
+    CPU_SLEEP_PRECISION,
+    STRING_JSON_PARSE_TRANSFORM,
+    IO_CONCURRENT_HTTP_CLIENT,
+    IO_CONCURRENT_HTTP_POST,
+    IO_HTTP_CONNECTION_CHURN,
+    IO_HTTP_JITTER_SENSITIVITY,
+    IO_HTTP_STREAMING,
+    DATA_PIPELINE_ETL_MINIBATCH,
+    DATA_PIPELINE_ETL_ASYNC,
+    CONCURRENCY_ASYNC_TASKS,
+    DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    STATIC_SECURITY_LINT_BENCHMARK,
+    TEST_ROBUSTNESS_RELIABILITY,
+    BUILD_STARTUP_FEEDBACK_LOOP,
+];
+
+/// What `--list` reports about a benchmark, and where the runner takes its
+/// category from.
+#[derive(Debug)]
+pub(crate) struct Descriptor {
+    pub(crate) id: &'static str,
+    pub(crate) category: &'static str,
+    /// Variables that change its workload or how it measures, besides its
+    /// `BENCHMARK_PARAMS_<ID>` overrides.
+    pub(crate) env_vars: &'static [&'static str],
+}
+
+/// Every benchmark, in `ALL` order.
+pub(crate) const REGISTRY: [Descriptor; 16] = [
+    Descriptor {
+        id: CPU_MONTE_CARLO_PI,
+        category: "performance",
+        env_vars: &["BENCHMARK_PI_SAMPLES", "BENCHMARK_REPEATS", "BENCHMARK_WARMUP"],
+    },
+    Descriptor {
+        id: CPU_MONTE_CARLO_PI_PARALLEL,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_PI_SAMPLES",
+            "BENCHMARK_CPUS",
+            "BENCHMARK_REPEATS",
+            "BENCHMARK_WARMUP",
+        ],
+    },
+    Descriptor {
+        id: CPU_SLEEP_PRECISION,
+        category: "performance",
+        env_vars: &[],
+    },
+    Descriptor {
+        id: STRING_JSON_PARSE_TRANSFORM,
+        category: "performance",
+        env_vars: &["BENCHMARK_REPEATS", "BENCHMARK_WARMUP"],
+    },
+    Descriptor {
+        id: IO_CONCURRENT_HTTP_CLIENT,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_HTTP_BASE_URL",
+            "BENCHMARK_HTTP_REQUESTS",
+            "BENCHMARK_HTTP_ROWS",
+            "BENCHMARK_HTTP_CONCURRENCY",
+            "BENCHMARK_HTTP_CONCURRENCY_SWEEP",
+            "BENCHMARK_HTTP_KEEPALIVE",
+            "BENCHMARK_HTTP_TARGET_RPS",
+            "BENCHMARK_HTTP_TIMEOUT_MS",
+            "BENCHMARK_HTTP_BUDGET_SECONDS",
+            "BENCHMARK_HTTP_READY_TIMEOUT",
+            "BENCHMARK_REPEATS",
+            "BENCHMARK_WARMUP",
+        ],
+    },
+    Descriptor {
+        id: IO_CONCURRENT_HTTP_POST,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_HTTP_BASE_URL",
+            "BENCHMARK_HTTP_REQUESTS",
+            "BENCHMARK_HTTP_ROWS",
+            "BENCHMARK_HTTP_CONCURRENCY",
+            "BENCHMARK_HTTP_KEEPALIVE",
+            "BENCHMARK_HTTP_TARGET_RPS",
+            "BENCHMARK_HTTP_TIMEOUT_MS",
+            "BENCHMARK_HTTP_BUDGET_SECONDS",
+            "BENCHMARK_HTTP_READY_TIMEOUT",
+        ],
+    },
+    Descriptor {
+        id: IO_HTTP_CONNECTION_CHURN,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_HTTP_BASE_URL",
+            "BENCHMARK_HTTP_REQUESTS",
+            "BENCHMARK_HTTP_ROWS",
+            "BENCHMARK_HTTP_CONCURRENCY",
+        ],
+    },
+    Descriptor {
+        id: IO_HTTP_JITTER_SENSITIVITY,
+        category: "performance",
+        env_vars: &[],
+    },
+    Descriptor {
+        id: IO_HTTP_STREAMING,
+        category: "performance",
+        env_vars: &["BENCHMARK_HTTP_BASE_URL", "BENCHMARK_HTTP_STREAMING_MB"],
+    },
+    Descriptor {
+        id: DATA_PIPELINE_ETL_MINIBATCH,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_ETL_DATASET",
+            "BENCHMARK_IO_BUFFER_KB",
+            "BENCHMARK_IO_BUFFER_SWEEP",
+            "BENCHMARK_MADV_HUGEPAGE",
+            "BENCHMARK_REPEATS",
+            "BENCHMARK_WARMUP",
+        ],
+    },
+    Descriptor {
+        id: DATA_PIPELINE_ETL_ASYNC,
+        category: "performance",
+        env_vars: &[
+            "BENCHMARK_ETL_ASYNC",
+            "BENCHMARK_ETL_DATASET",
+            "BENCHMARK_IO_BUFFER_KB",
+        ],
+    },
+    Descriptor {
+        id: CONCURRENCY_ASYNC_TASKS,
+        category: "performance",
+        env_vars: &["BENCHMARK_ASYNC_WORKER_THREADS"],
+    },
+    Descriptor {
+        id: DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+        category: "security",
+        env_vars: &[
+            "BENCHMARK_ADVISORY_DB",
+            "BENCHMARK_OUTPUT_CAP_MB",
+            "BENCHMARK_MAX_LINE_MB",
+            "BENCHMARK_STRICT_LINE_LENGTH",
+            "BENCHMARK_CHILD_COUNTERS",
+        ],
+    },
+    Descriptor {
+        id: STATIC_SECURITY_LINT_BENCHMARK,
+        category: "security",
+        env_vars: &[
+            "BENCHMARK_VERIFY_FIXES",
+            "BENCHMARK_OUTPUT_CAP_MB",
+            "BENCHMARK_MAX_LINE_MB",
+            "BENCHMARK_STRICT_LINE_LENGTH",
+            "BENCHMARK_CHILD_COUNTERS",
+        ],
+    },
+    Descriptor {
+        id: TEST_ROBUSTNESS_RELIABILITY,
+        category: "quality",
+        env_vars: &[
+            "BENCHMARK_TEST_ITERATIONS",
+            "BENCHMARK_OUTPUT_CAP_MB",
+            "BENCHMARK_MAX_LINE_MB",
+            "BENCHMARK_STRICT_LINE_LENGTH",
+        ],
+    },
+    Descriptor {
+        id: BUILD_STARTUP_FEEDBACK_LOOP,
+        category: "quality",
+        env_vars: &[
+            "BENCHMARK_OUTPUT_CAP_MB",
+            "BENCHMARK_MAX_LINE_MB",
+            "BENCHMARK_STRICT_LINE_LENGTH",
+            "BENCHMARK_CHILD_COUNTERS",
+        ],
+    },
+];
+
+/// The descriptor of a registered id.
+pub(crate) fn descriptor(id: &str) -> Option<&'static Descriptor> {
+    REGISTRY.iter().find(|descriptor| descriptor.id == id)
+}
+
+/// Suggestions further than this many edits away are more confusing than
+/// helpful.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// The registered id `id` is, or an error naming the closest registered one.
+pub(crate) fn check(id: &str) -> Result<&'static str, String> {
+    if let Some(known) = ALL.iter().find(|known| **known == id) {
+        return Ok(known);
+    }
+    Err(match suggest(id) {
+        Some(close) => format!("unknown benchmark {id} (did you mean {close}?)"),
+        None => format!("unknown benchmark {id}"),
+    })
+}
+
+/// The registered id fewest edits away from `id`, if any is close enough.
+pub(crate) fn suggest(id: &str) -> Option<&'static str> {
+    ALL.iter()
+        .map(|known| (edit_distance(id, known), *known))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+/// The ids of a comma-separated `--benchmarks` list, in registry order and
+/// without repeats. Unknown ids fail with the list of valid ones.
+pub(crate) fn parse_selection(raw: &str) -> Result<Vec<&'static str>, String> {
+    let mut requested = Vec::new();
+    for id in raw.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = check(id)
+            .map_err(|err| format!("--benchmarks: {err}; valid benchmarks: {}", ALL.join(", ")))?;
+        requested.push(id);
+    }
+    if requested.is_empty() {
+        return Err("--benchmarks: no benchmark ids given".to_string());
+    }
+    Ok(ALL.into_iter().filter(|id| requested.contains(id)).collect())
+}
+
+/// Whether `id` runs under `selection`; an empty selection runs everything.
+pub(crate) fn is_selected(selection: &[&str], id: &str) -> bool {
+    selection.is_empty() || selection.contains(&id)
+}
+
+/// Levenshtein distance over bytes; ids are ASCII.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_byte) in a.bytes().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_byte) in b.bytes().enumerate() {
+            let substitution = previous[j] + usize::from(a_byte != b_byte);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn ids_are_unique_snake_case_and_pinned() {
+        for (index, id) in ALL.iter().enumerate() {
+            assert!(!ALL[..index].contains(id), "{id} is registered twice");
+            assert!(
+                id.split('_')
+                    .all(|word| !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())),
+                "{id} is not lowercase snake_case"
+            );
+        }
+        let pinned: Vec<&str> = fixtures::benchmark_ids().lines().collect();
+        assert_eq!(ALL.as_slice(), pinned, "benchmark ids changed; a rename orphans history");
+    }
+
+    #[test]
+    fn registry_describes_every_id_in_order() {
+        let ids: Vec<&str> = REGISTRY.iter().map(|descriptor| descriptor.id).collect();
+        assert_eq!(ids, ALL);
+        for descriptor in &REGISTRY {
+            assert!(
+                ["performance", "security", "quality"].contains(&descriptor.category),
+                "{descriptor:?}"
+            );
+            assert!(
+                descriptor.env_vars.iter().all(|var| var.starts_with("BENCHMARK_")),
+                "{descriptor:?}"
+            );
+        }
+        assert_eq!(descriptor(TEST_ROBUSTNESS_RELIABILITY).unwrap().category, "quality");
+        assert!(descriptor("nope").is_none());
+    }
+
+    #[test]
+    fn suggests_close_matches() {
+        assert_eq!(check("io_concurrent_http_client"), Ok(IO_CONCURRENT_HTTP_CLIENT));
+        assert_eq!(
+            check("io_concurent_http_client").unwrap_err(),
+            "unknown benchmark io_concurent_http_client (did you mean io_concurrent_http_client?)"
+        );
+        assert_eq!(suggest("cpu_monte_carlo"), Some(CPU_MONTE_CARLO_PI));
+        assert_eq!(suggest("memory_bandwidth"), None);
+        assert_eq!(check("nope").unwrap_err(), "unknown benchmark nope");
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn parses_benchmark_selections() {
+        assert_eq!(
+            parse_selection("data_pipeline_etl_minibatch, cpu_monte_carlo_pi,cpu_monte_carlo_pi"),
+            Ok(vec![CPU_MONTE_CARLO_PI, DATA_PIPELINE_ETL_MINIBATCH])
+        );
+        let err = parse_selection("cpu_monte_carlo_pi,cpu_montecarlo_pi").unwrap_err();
+        assert!(err.contains("did you mean cpu_monte_carlo_pi?"), "{err}");
+        assert!(err.contains("valid benchmarks: cpu_monte_carlo_pi, "), "{err}");
+        assert!(parse_selection(" , ").is_err());
+
+        assert!(is_selected(&[], TEST_ROBUSTNESS_RELIABILITY));
+        assert!(is_selected(&[CPU_MONTE_CARLO_PI], CPU_MONTE_CARLO_PI));
+        assert!(!is_selected(&[CPU_MONTE_CARLO_PI], TEST_ROBUSTNESS_RELIABILITY));
+    }
+}
@@ -0,0 +1,526 @@
+//! `bisect <good> <bad> --metric <benchmark_id>.<metric> --max|--min <value>`
+//! finds the commit that pushed a metric over its threshold. It drives
+//! `git bisect` in a temporary worktree, so the caller's checkout and its
+//! bisect state are never touched. At each commit git proposes, the harness
+//! is built and runs only that benchmark, with the default preset and this
+//! build's seed named on its command line and no `BENCHMARK_*` env vars, so
+//! the caller's environment cannot change the workload between commits. A
+//! value within the bound marks the commit good, anything else bad.
+//!
+//! A commit whose harness does not build, or that cannot produce the metric
+//! (older commits may predate it or those flags), is skipped the way `git bisect skip`
+//! expects. The worktree and the bisect are removed again whatever the
+//! outcome. Builds share one target dir, so each commit only rebuilds what
+//! changed.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::command_runner::CommandRunner;
+use crate::print_metrics::MetricPath;
+use crate::DEFAULT_SEED;
+
+/// More steps than any history this side of 2^64 commits needs; a bisect
+/// still running after this many is stuck.
+const MAX_STEPS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Bound {
+    /// Good while the metric is at most this.
+    Max(f64),
+    /// Good while the metric is at least this.
+    Min(f64),
+}
+
+impl Bound {
+    fn passes(self, value: f64) -> bool {
+        match self {
+            Bound::Max(max) => value <= max,
+            Bound::Min(min) => value >= min,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BisectArgs {
+    pub(crate) good: String,
+    pub(crate) bad: String,
+    pub(crate) metric: MetricPath,
+    pub(crate) bound: Bound,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Verdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl Verdict {
+    fn git_word(self) -> &'static str {
+        match self {
+            Verdict::Good => "good",
+            Verdict::Bad => "bad",
+            Verdict::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Measurement {
+    pub(crate) commit: String,
+    /// `None` when the commit was skipped before producing a value.
+    pub(crate) value: Option<f64>,
+    pub(crate) verdict: Verdict,
+    /// Why a commit was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) note: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct BisectReport {
+    /// The first bad commit; `None` when skipped commits hide it.
+    pub(crate) culprit: Option<String>,
+    /// With no culprit, the commits it could be, as git lists them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) candidates: Vec<String>,
+    /// In the order they were measured.
+    pub(crate) measurements: Vec<Measurement>,
+}
+
+/// Where `bisect` keeps its worktree and build output.
+pub(crate) struct Workspace<'a> {
+    pub(crate) repo: &'a Path,
+    pub(crate) worktree: PathBuf,
+    pub(crate) target_dir: PathBuf,
+}
+
+/// What a git command printed, or why it failed.
+fn git(runner: &dyn CommandRunner, dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir);
+    let output = runner.output(cmd);
+    if output.code == 0 {
+        Ok(output.stdout)
+    } else {
+        Err(format!("git {} exited with {}", args.join(" "), output.code))
+    }
+}
+
+fn resolve_commit(runner: &dyn CommandRunner, repo: &Path, rev: &str) -> Result<String, String> {
+    let spec = format!("{rev}^{{commit}}");
+    git(runner, repo, &["rev-parse", "--verify", "--quiet", &spec])
+        .map(|stdout| stdout.trim().to_string())
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .ok_or_else(|| format!("bisect: {rev:?} is not a commit"))
+}
+
+/// Bisects between `args.good` and `args.bad` and reports what it found.
+/// Errors only when the bisect cannot start; everything after that ends in
+/// a report, possibly without a culprit.
+pub(crate) fn bisect(
+    runner: &dyn CommandRunner,
+    workspace: &Workspace,
+    args: &BisectArgs,
+    on_measured: &mut dyn FnMut(&Measurement),
+) -> Result<BisectReport, String> {
+    let good = resolve_commit(runner, workspace.repo, &args.good)?;
+    let bad = resolve_commit(runner, workspace.repo, &args.bad)?;
+    let worktree = workspace.worktree.to_string_lossy().into_owned();
+    git(runner, workspace.repo, &["worktree", "add", "--detach", &worktree, &bad])
+        .map_err(|err| format!("bisect: cannot create a worktree at {worktree}: {err}"))?;
+    let result = drive(runner, workspace, args, &good, &bad, on_measured);
+    let _ = git(runner, &workspace.worktree, &["bisect", "reset"]);
+    let _ = git(runner, workspace.repo, &["worktree", "remove", "--force", &worktree]);
+    result
+}
+
+fn drive(
+    runner: &dyn CommandRunner,
+    workspace: &Workspace,
+    args: &BisectArgs,
+    good: &str,
+    bad: &str,
+    on_measured: &mut dyn FnMut(&Measurement),
+) -> Result<BisectReport, String> {
+    let dir = workspace.worktree.as_path();
+    let mut report = BisectReport::default();
+    let mut step = git(runner, dir, &["bisect", "start", bad, good])?;
+    for _ in 0..MAX_STEPS {
+        if let Some(found) = outcome(&step) {
+            report.culprit = found.culprit;
+            report.candidates = found.candidates;
+            return Ok(report);
+        }
+        let head = git(runner, dir, &["rev-parse", "HEAD"])?.trim().to_string();
+        let measurement = measure(runner, workspace, args, head);
+        on_measured(&measurement);
+        step = git(runner, dir, &["bisect", measurement.verdict.git_word()])?;
+        report.measurements.push(measurement);
+    }
+    Err(format!("bisect: no result after {MAX_STEPS} steps"))
+}
+
+/// Builds the harness at the worktree's checkout and reads the metric.
+fn measure(
+    runner: &dyn CommandRunner,
+    workspace: &Workspace,
+    args: &BisectArgs,
+    commit: String,
+) -> Measurement {
+    let skip = |commit, note: String| Measurement {
+        commit,
+        value: None,
+        verdict: Verdict::Skip,
+        note: Some(note),
+    };
+    let mut build = Command::new("cargo");
+    build
+        .args(["build", "--release", "--manifest-path", "benchmarks/rust/Cargo.toml"])
+        .arg("--target-dir")
+        .arg(&workspace.target_dir)
+        .current_dir(&workspace.worktree);
+    let code = runner.output(build).code;
+    if code != 0 {
+        return skip(commit, format!("build exited with {code}"));
+    }
+    let exe = workspace
+        .target_dir
+        .join("release")
+        .join(format!("rust-benchmarks{}", std::env::consts::EXE_SUFFIX));
+    let run = child_run(exe, &workspace.worktree, &args.metric, std::env::vars());
+    let output = runner.output(run);
+    let value = output.stdout.lines().rev().find_map(|line| line.trim().parse::<f64>().ok());
+    match value {
+        Some(value) => Measurement {
+            commit,
+            value: Some(value),
+            verdict: if args.bound.passes(value) { Verdict::Good } else { Verdict::Bad },
+            note: None,
+        },
+        None => skip(commit, format!("no value printed (exit {})", output.code)),
+    }
+}
+
+/// The benchmark run at one commit: `metric`'s benchmark alone, with the
+/// preset and seed spelled out and every `BENCHMARK_*` var of `env` left out.
+fn child_run(
+    exe: PathBuf,
+    worktree: &Path,
+    metric: &MetricPath,
+    env: impl Iterator<Item = (String, String)>,
+) -> Command {
+    let id = metric.benchmark_id;
+    let mut run = Command::new(exe);
+    run.args(["--benchmarks", id, "--no-precheck", "--preset", "default", "--seed"])
+        .arg(DEFAULT_SEED.to_string())
+        .arg("--print")
+        .arg(format!("{id}.{}", metric.metric))
+        .current_dir(worktree)
+        .env_clear()
+        .envs(env.filter(|(name, _)| !name.starts_with("BENCHMARK_")));
+    run
+}
+
+struct Outcome {
+    culprit: Option<String>,
+    candidates: Vec<String>,
+}
+
+/// Whether `git bisect` output ends the bisect. It names the first bad
+/// commit, or when only skipped commits are left, lists the candidates.
+fn outcome(stdout: &str) -> Option<Outcome> {
+    if let Some(line) = stdout.lines().find(|line| line.ends_with(" is the first bad commit")) {
+        return Some(Outcome {
+            culprit: line.split_whitespace().next().map(str::to_string),
+            candidates: Vec::new(),
+        });
+    }
+    if !stdout.contains("only 'skip'ped commits left to test") {
+        return None;
+    }
+    let candidates = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("The first bad commit could be any of:"))
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| !line.is_empty() && line.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_string)
+        .collect();
+    Some(Outcome {
+        culprit: None,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subprocess::{CaptureStats, CommandOutput};
+    use std::collections::BTreeSet;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn sha(index: usize) -> String {
+        format!("{index:040x}")
+    }
+
+    #[derive(Default)]
+    struct Bisection {
+        good: usize,
+        bad: usize,
+        head: usize,
+        skipped: BTreeSet<usize>,
+    }
+
+    /// A linear history of `values.len()` commits behind git and cargo.
+    /// Commit `i` measures `values[i]`, `None` being a commit that does not
+    /// build. Bisect picks the untested commit nearest the middle, as git
+    /// does on a linear history.
+    struct FakeRepo {
+        values: Vec<Option<f64>>,
+        state: Mutex<Bisection>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FakeRepo {
+        fn new(values: Vec<Option<f64>>) -> Self {
+            FakeRepo {
+                values,
+                state: Mutex::default(),
+                calls: Mutex::default(),
+            }
+        }
+
+        fn index(&self, rev: &str) -> Option<usize> {
+            let rev = rev.trim_end_matches("^{commit}");
+            match rev {
+                "v1" => Some(0),
+                "main" => Some(self.values.len() - 1),
+                _ => (0..self.values.len()).find(|index| sha(*index) == rev),
+            }
+        }
+
+        /// Checks out the next commit to test, or says how it ended.
+        fn next(&self, state: &mut Bisection) -> String {
+            if state.bad == state.good + 1 {
+                return format!("{} is the first bad commit\n", sha(state.bad));
+            }
+            let untested: Vec<usize> = (state.good + 1..state.bad)
+                .filter(|index| !state.skipped.contains(index))
+                .collect();
+            if untested.is_empty() {
+                let candidates: Vec<String> = (state.good + 1..=state.bad).map(sha).collect();
+                return format!(
+                    "There are only 'skip'ped commits left to test.\n\
+                     The first bad commit could be any of:\n{}\nWe cannot bisect more!\n",
+                    candidates.join("\n")
+                );
+            }
+            let middle = (state.good + state.bad) / 2;
+            state.head = *untested.iter().min_by_key(|index| index.abs_diff(middle)).unwrap();
+            format!("Bisecting: {} revisions left to test\n", untested.len() - 1)
+        }
+
+        fn answer(&self, argv: &[&str]) -> (i32, String) {
+            let mut state = self.state.lock().unwrap();
+            match argv {
+                ["git", "rev-parse", "--verify", "--quiet", rev] => match self.index(rev) {
+                    Some(index) => (0, sha(index) + "\n"),
+                    None => (1, String::new()),
+                },
+                ["git", "worktree", ..] | ["git", "bisect", "reset"] => (0, String::new()),
+                ["git", "bisect", "start", bad, good] => {
+                    state.bad = self.index(bad).unwrap();
+                    state.good = self.index(good).unwrap();
+                    (0, self.next(&mut state))
+                }
+                ["git", "bisect", word] => {
+                    let head = state.head;
+                    match *word {
+                        "good" => state.good = head,
+                        "bad" => state.bad = head,
+                        _ => {
+                            state.skipped.insert(head);
+                        }
+                    }
+                    (0, self.next(&mut state))
+                }
+                ["git", "rev-parse", "HEAD"] => (0, sha(state.head) + "\n"),
+                ["cargo", "build", ..] => match self.values[state.head] {
+                    Some(_) => (0, String::new()),
+                    None => (101, String::new()),
+                },
+                [exe, ..] if exe.ends_with("rust-benchmarks") => {
+                    (0, format!("{}\n", self.values[state.head].unwrap()))
+                }
+                _ => (1, String::new()),
+            }
+        }
+    }
+
+    impl CommandRunner for FakeRepo {
+        fn stream(
+            &self,
+            cmd: Command,
+            consume: &mut dyn FnMut(&mut dyn Iterator<Item = String>),
+        ) -> CommandOutput {
+            let argv: Vec<String> = std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            self.calls.lock().unwrap().push(argv.join(" "));
+            let argv: Vec<&str> = argv.iter().map(String::as_str).collect();
+            let (code, stdout) = self.answer(&argv);
+            consume(&mut stdout.lines().map(str::to_string));
+            CommandOutput {
+                code,
+                stdout: String::new(),
+                capture: CaptureStats::default(),
+                tool_time: Duration::ZERO,
+                usage: None,
+            }
+        }
+    }
+
+    fn args(bound: Bound) -> BisectArgs {
+        BisectArgs {
+            good: "v1".to_string(),
+            bad: "main".to_string(),
+            metric: MetricPath::parse("cpu_monte_carlo_pi.runtime_seconds").unwrap(),
+            bound,
+        }
+    }
+
+    fn run(repo: &FakeRepo, bound: Bound) -> Result<BisectReport, String> {
+        let workspace = Workspace {
+            repo: Path::new("/repo"),
+            worktree: PathBuf::from("/cache/bisect-1"),
+            target_dir: PathBuf::from("/cache/bisect-target"),
+        };
+        bisect(repo, &workspace, &args(bound), &mut |_| {})
+    }
+
+    #[test]
+    fn finds_the_commit_that_crossed_the_threshold() {
+        let values = [1.0, 1.1, 0.9, 1.0, 1.05, 2.0, 2.1, 1.9, 2.2];
+        let repo = FakeRepo::new(values.iter().copied().map(Some).collect());
+        let report = run(&repo, Bound::Max(1.5)).unwrap();
+        assert_eq!(report.culprit, Some(sha(5)));
+        assert!(report.candidates.is_empty());
+        let measured: Vec<(String, Option<f64>, Verdict)> = report
+            .measurements
+            .iter()
+            .map(|m| (m.commit.clone(), m.value, m.verdict))
+            .collect();
+        assert_eq!(
+            measured,
+            [
+                (sha(4), Some(1.05), Verdict::Good),
+                (sha(6), Some(2.1), Verdict::Bad),
+                (sha(5), Some(2.0), Verdict::Bad),
+            ]
+        );
+
+        // The worktree is created from the bad commit and always removed.
+        let calls = repo.calls.lock().unwrap().clone();
+        assert!(calls.contains(&format!("git worktree add --detach /cache/bisect-1 {}", sha(8))));
+        assert_eq!(calls[calls.len() - 2], "git bisect reset");
+        assert_eq!(calls[calls.len() - 1], "git worktree remove --force /cache/bisect-1");
+        let exe = calls.iter().find(|call| call.contains("rust-benchmarks")).unwrap();
+        assert!(exe.starts_with("/cache/bisect-target/release/rust-benchmarks"));
+        let flags = "--benchmarks cpu_monte_carlo_pi --no-precheck --preset default --seed 42";
+        assert!(exe.ends_with(&format!("{flags} --print cpu_monte_carlo_pi.runtime_seconds")));
+    }
+
+    #[test]
+    fn child_runs_leave_the_callers_benchmark_env_behind() {
+        let env = [
+            ("PATH", "/usr/bin"),
+            ("BENCHMARK_PI_SAMPLES", "1"),
+            ("BENCHMARK_PARAMS_CPU_MONTE_CARLO_PI", "{}"),
+        ];
+        let run = child_run(
+            PathBuf::from("rust-benchmarks"),
+            Path::new("/cache/bisect-1"),
+            &args(Bound::Max(1.0)).metric,
+            env.iter().map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+        let envs: Vec<(String, Option<String>)> = run
+            .get_envs()
+            .map(|(name, value)| {
+                let value = value.map(|value| value.to_string_lossy().into_owned());
+                (name.to_string_lossy().into_owned(), value)
+            })
+            .collect();
+        assert_eq!(envs, [("PATH".to_string(), Some("/usr/bin".to_string()))]);
+    }
+
+    #[test]
+    fn a_min_bound_marks_drops_as_bad() {
+        let values = [900.0, 950.0, 910.0, 400.0, 420.0];
+        let repo = FakeRepo::new(values.iter().copied().map(Some).collect());
+        let report = run(&repo, Bound::Min(800.0)).unwrap();
+        assert_eq!(report.culprit, Some(sha(3)));
+    }
+
+    #[test]
+    fn commits_that_do_not_build_are_skipped() {
+        let values = [Some(1.0), Some(1.0), Some(1.0), None, Some(1.0), Some(3.0), Some(3.0)];
+        let repo = FakeRepo::new(values.to_vec());
+        let report = run(&repo, Bound::Max(2.0)).unwrap();
+        assert_eq!(report.culprit, Some(sha(5)));
+        let skipped = &report.measurements[0];
+        assert_eq!((skipped.commit.clone(), skipped.verdict), (sha(3), Verdict::Skip));
+        assert_eq!(skipped.value, None);
+        assert_eq!(skipped.note.as_deref(), Some("build exited with 101"));
+
+        // When the regression hides behind skipped commits, git lists them.
+        let repo = FakeRepo::new(vec![Some(1.0), None, None, Some(3.0)]);
+        let report = run(&repo, Bound::Max(2.0)).unwrap();
+        assert_eq!(report.culprit, None);
+        assert_eq!(report.candidates, [sha(1), sha(2), sha(3)]);
+        assert_eq!(report.measurements.len(), 2);
+    }
+
+    #[test]
+    fn unknown_revisions_fail_before_any_worktree_exists() {
+        let repo = FakeRepo::new(vec![Some(1.0), Some(2.0)]);
+        let mut args = args(Bound::Max(1.5));
+        args.good = "nope".to_string();
+        let workspace = Workspace {
+            repo: Path::new("/repo"),
+            worktree: PathBuf::from("/cache/bisect-1"),
+            target_dir: PathBuf::from("/cache/bisect-target"),
+        };
+        let err = bisect(&repo, &workspace, &args, &mut |_| {}).unwrap_err();
+        assert_eq!(err, "bisect: \"nope\" is not a commit");
+        let calls = repo.calls.lock().unwrap();
+        assert!(!calls.iter().any(|call| call.contains("worktree")));
+    }
+
+    #[test]
+    fn reports_serialize_without_empty_fields() {
+        let report = BisectReport {
+            culprit: Some(sha(5)),
+            candidates: Vec::new(),
+            measurements: vec![Measurement {
+                commit: sha(4),
+                value: Some(1.05),
+                verdict: Verdict::Good,
+                note: None,
+            }],
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "culprit": sha(5),
+                "measurements": [{"commit": sha(4), "value": 1.05, "verdict": "good"}]
+            })
+        );
+    }
+}
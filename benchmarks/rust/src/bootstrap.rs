@@ -0,0 +1,282 @@
+//! `rust-benchmarks bootstrap`: does the one-off work (dataset generation,
+//! advisory DB fetch, cold clippy and release builds) outside any measured
+//! section, then leaves a marker so records can report `bootstrapped`.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::clock;
+use crate::config::{BenchmarkConfigs, EtlConfig};
+use crate::http_client::{parse_base_url, run_workers, ConnectionMode};
+use crate::subprocess::command_output;
+use crate::data_dir::DataDir;
+use crate::dataset;
+use crate::dataset_cache::DatasetCache;
+use crate::command_runner::{has_cargo_subcommand, SystemRunner};
+use crate::{ensure_etl_dataset, repo_root};
+
+const MARKER_VERSION: u64 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StepStatus {
+    Done,
+    Skipped,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Step {
+    pub(crate) name: String,
+    pub(crate) status: StepStatus,
+    pub(crate) detail: String,
+}
+
+impl Step {
+    fn new(name: &str, status: StepStatus, detail: impl Into<String>) -> Self {
+        Step {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Marker {
+    version: u64,
+    completed_at: String,
+    steps: Vec<Step>,
+}
+
+pub(crate) fn marker_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("bootstrap.json")
+}
+
+/// A marker from another layout version counts as not bootstrapped.
+pub(crate) fn is_bootstrapped(marker: &Path) -> bool {
+    fs::read_to_string(marker)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Marker>(&text).ok())
+        .is_some_and(|marker| marker.version == MARKER_VERSION)
+}
+
+/// Writes the marker when no step failed and removes any stale one
+/// otherwise; returns whether the marker now exists.
+pub(crate) fn update_marker(marker: &Path, steps: &[Step]) -> io::Result<bool> {
+    if steps.iter().any(|step| step.status == StepStatus::Failed) {
+        return match fs::remove_file(marker) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(false),
+        };
+    }
+    if let Some(parent) = marker.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let payload = Marker {
+        version: MARKER_VERSION,
+        completed_at: clock::now(),
+        steps: steps.to_vec(),
+    };
+    let text = serde_json::to_string_pretty(&payload).map_err(io::Error::other)?;
+    fs::write(marker, text)?;
+    Ok(true)
+}
+
+/// Existing datasets are left untouched, so repeated bootstraps are cheap.
+/// A missing one comes from the dataset cache when it holds a match, the
+/// same way the ETL benchmarks get theirs.
+pub(crate) fn prepare_dataset(
+    path: &Path,
+    config: &EtlConfig,
+    cache: Option<&DatasetCache>,
+) -> Step {
+    let path = dataset::canonical(path);
+    if path.exists() {
+        return Step::new("etl_dataset", StepStatus::Done, "already present");
+    }
+    let generation = ensure_etl_dataset(&path, config, cache);
+    if generation.lock_timed_out {
+        return Step::new(
+            "etl_dataset",
+            StepStatus::Failed,
+            format!("timed out waiting for the lock on {}", path.display()),
+        );
+    }
+    if generation.cache_hit {
+        Step::new("etl_dataset", StepStatus::Done, "copied from the dataset cache")
+    } else if generation.generated {
+        let rows = config.rows;
+        Step::new("etl_dataset", StepStatus::Done, format!("generated {rows} rows"))
+    } else if path.exists() {
+        Step::new("etl_dataset", StepStatus::Done, "already present")
+    } else {
+        Step::new(
+            "etl_dataset",
+            StepStatus::Failed,
+            format!("cannot create {}", path.display()),
+        )
+    }
+}
+
+fn run_step(name: &str, mut cmd: Command) -> Step {
+    cmd.current_dir(repo_root());
+    let code = command_output(cmd).code;
+    if code == 0 {
+        Step::new(name, StepStatus::Done, "ok")
+    } else {
+        Step::new(name, StepStatus::Failed, format!("exit code {code}"))
+    }
+}
+
+fn cargo(args: &[&str]) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.args(args);
+    cmd
+}
+
+/// `cargo audit` and `cargo outdated` exit nonzero when they find something;
+/// only their side effect (fetched indexes) matters here.
+fn prime_subcommand(name: &str, args: &[&str]) -> Step {
+    let step_name = format!("prime_cargo_{name}");
+    if !has_cargo_subcommand(&SystemRunner, name) {
+        return Step::new(&step_name, StepStatus::Skipped, "not installed");
+    }
+    let mut cmd = cargo(args);
+    cmd.current_dir(repo_root().join("benchmarks/rust"));
+    let code = command_output(cmd).code;
+    Step::new(&step_name, StepStatus::Done, format!("exit code {code}"))
+}
+
+fn verify_http_fixture(base_url: &str) -> Step {
+    let target = match parse_base_url(base_url) {
+        Ok(target) => target,
+        Err(err) => {
+            return Step::new("http_fixture", StepStatus::Failed, format!("{base_url}: {err}"))
+        }
+    };
+    let stats = run_workers(&target, 1, 1, 1, ConnectionMode::PerRequest);
+    if stats.completed == 1 {
+        Step::new("http_fixture", StepStatus::Done, format!("{base_url} answered"))
+    } else {
+        Step::new("http_fixture", StepStatus::Failed, format!("{base_url} did not answer"))
+    }
+}
+
+pub(crate) fn bootstrap(configs: &BenchmarkConfigs, data_dir: &DataDir) -> Vec<Step> {
+    let mut steps = vec![prepare_dataset(
+        &data_dir.resolve(&configs.etl.dataset),
+        &configs.etl,
+        data_dir.dataset_cache(),
+    )];
+    steps.push(prime_subcommand("audit", &["audit", "--json"]));
+    steps.push(prime_subcommand("outdated", &["outdated", "--format", "json"]));
+    if has_cargo_subcommand(&SystemRunner, "clippy") {
+        for manifest in &configs.static_lint.manifests {
+            steps.push(run_step(
+                "prime_cargo_clippy",
+                cargo(&["clippy", "--workspace", "--manifest-path", manifest]),
+            ));
+        }
+    } else {
+        steps.push(Step::new("prime_cargo_clippy", StepStatus::Skipped, "not installed"));
+    }
+    let manifest = "benchmarks/rust/Cargo.toml";
+    steps.push(run_step("build_debug", cargo(&["build", "--manifest-path", manifest])));
+    steps.push(run_step(
+        "build_release",
+        cargo(&["build", "--release", "--manifest-path", manifest]),
+    ));
+    // One step per target when the base URL is a list.
+    match env::var("BENCHMARK_HTTP_BASE_URL") {
+        Ok(_) => steps.extend(configs.http_client.targets().into_iter().map(verify_http_fixture)),
+        Err(_) => steps.push(Step::new(
+            "http_fixture",
+            StepStatus::Skipped,
+            "BENCHMARK_HTTP_BASE_URL not set",
+        )),
+    }
+    steps
+}
+
+pub(crate) fn print_summary(steps: &[Step], ready: bool) {
+    for step in steps {
+        let status = match step.status {
+            StepStatus::Done => "ok",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Failed => "FAILED",
+        };
+        eprintln!("{:<20} {:<8} {}", step.name, status, step.detail);
+    }
+    if ready {
+        eprintln!("ready: later runs will report bootstrapped=true");
+    } else {
+        eprintln!("not ready: fix the failed steps and rerun bootstrap");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("bootstrap-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn marker_tracks_the_last_bootstrap() {
+        let dir = temp_dir("marker");
+        let marker = marker_path(&dir);
+        assert!(!is_bootstrapped(&marker));
+
+        let ok = [Step::new("etl_dataset", StepStatus::Done, "generated 10 rows")];
+        assert!(update_marker(&marker, &ok).unwrap());
+        assert!(is_bootstrapped(&marker));
+
+        let skipped = [Step::new("prime_cargo_audit", StepStatus::Skipped, "not installed")];
+        assert!(update_marker(&marker, &skipped).unwrap());
+        assert!(is_bootstrapped(&marker));
+
+        let failed = [Step::new("build_release", StepStatus::Failed, "exit code 101")];
+        assert!(!update_marker(&marker, &failed).unwrap());
+        assert!(!is_bootstrapped(&marker));
+
+        fs::write(&marker, r#"{"version": 99, "completed_at": "", "steps": []}"#).unwrap();
+        assert!(!is_bootstrapped(&marker));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn dataset_preparation_is_idempotent() {
+        let dir = temp_dir("dataset");
+        let path = dir.join("etl.jsonl.gz");
+        let config = EtlConfig {
+            rows: 25,
+            ..EtlConfig::default()
+        };
+        let cache = DatasetCache::new(dir.join("cache"), 1024 * 1024);
+        let first = prepare_dataset(&path, &config, Some(&cache));
+        assert_eq!(first.status, StepStatus::Done);
+        assert_eq!(first.detail, "generated 25 rows");
+        let contents = fs::read(&path).unwrap();
+
+        let bigger = EtlConfig {
+            rows: 9_999,
+            ..config.clone()
+        };
+        let second = prepare_dataset(&path, &bigger, Some(&cache));
+        assert_eq!(second.detail, "already present");
+        assert_eq!(fs::read(&path).unwrap(), contents);
+
+        // A fresh data dir is filled from the cache the first run populated.
+        let elsewhere = dir.join("elsewhere.jsonl.gz");
+        let third = prepare_dataset(&elsewhere, &config, Some(&cache));
+        assert_eq!(third.detail, "copied from the dataset cache");
+        assert_eq!(fs::read(&elsewhere).unwrap(), contents);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
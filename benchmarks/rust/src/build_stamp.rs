@@ -0,0 +1,38 @@
+//! The build timestamp. `build.rs` includes this file to embed it as
+//! `BENCHMARK_BUILD_EPOCH`, and the harness parses it back to check that
+//! the system clock is not behind the binary it is running. Kept free of
+//! dependencies, since the build script has none.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch: `SOURCE_DATE_EPOCH` when set, so
+/// reproducible builds embed the same value, else `now`.
+#[allow(dead_code)] // Called by build.rs only.
+pub(crate) fn epoch_seconds(source_date_epoch: Option<&str>, now: SystemTime) -> u64 {
+    source_date_epoch
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| now.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()))
+}
+
+/// The embedded value; `None` if it is not a number of seconds.
+pub(crate) fn parse(embedded: &str) -> Option<u64> {
+    embedded.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn source_date_epoch_wins_over_the_build_clock() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(epoch_seconds(None, now), 1_700_000_000);
+        assert_eq!(epoch_seconds(Some("1600000000"), now), 1_600_000_000);
+        assert_eq!(epoch_seconds(Some("yesterday"), now), 1_700_000_000);
+        assert_eq!(epoch_seconds(None, UNIX_EPOCH - Duration::from_secs(5)), 0);
+        let embedded = epoch_seconds(None, now).to_string();
+        assert_eq!(parse(&embedded), Some(1_700_000_000));
+        assert_eq!(parse(""), None);
+    }
+}
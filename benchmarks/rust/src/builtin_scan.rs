@@ -0,0 +1,547 @@
+//! Fallbacks for runners without `cargo audit` or `cargo outdated`, so the
+//! dependency scorecard still measures something instead of reporting
+//! `scan_errors` forever. Vulnerabilities come from `Cargo.lock` checked
+//! against a RustSec snapshot in OSV JSON; outdated packages from the
+//! versions already in the local cargo registry, so nothing touches the
+//! network. Both are narrower than the real tools: no yanked-crate or
+//! unmaintained warnings, and a crate counts as outdated only when a newer
+//! compatible release happens to be cached locally.
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Package {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    /// `None` for workspace members and path dependencies.
+    pub(crate) source: Option<String>,
+}
+
+impl Package {
+    /// Published on crates.io (through the git or sparse index), so
+    /// advisories and registry versions apply to it.
+    fn is_published(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+    }
+}
+
+/// The `[[package]]` entries of a `Cargo.lock`. Only the keys cargo writes
+/// on one line are read; the `dependencies` arrays are skipped.
+pub(crate) fn parse_lockfile(text: &str) -> Result<Vec<Package>, String> {
+    let mut packages = Vec::new();
+    let mut current: Option<BTreeMap<&str, String>> = None;
+    let mut finish = |fields: Option<BTreeMap<&str, String>>| -> Result<(), String> {
+        let Some(mut fields) = fields else {
+            return Ok(());
+        };
+        let (Some(name), Some(version)) = (fields.remove("name"), fields.remove("version")) else {
+            return Err("Cargo.lock: [[package]] without name or version".to_string());
+        };
+        packages.push(Package {
+            name,
+            version,
+            source: fields.remove("source"),
+        });
+        Ok(())
+    };
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            finish(current.take())?;
+            if line == "[[package]]" {
+                current = Some(BTreeMap::new());
+            }
+            continue;
+        }
+        let Some(fields) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !matches!(key, "name" | "version" | "source") {
+            continue;
+        }
+        let value = value.trim();
+        let Some(value) = value
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        else {
+            return Err(format!("Cargo.lock: {key} is not a string: {value}"));
+        };
+        fields.insert(key, value.to_string());
+    }
+    finish(current)?;
+    Ok(packages)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    // Numeric identifiers sort before alphanumeric ones.
+    Numeric(u64),
+    Alpha(String),
+}
+
+/// A semver version; build metadata is dropped since it never affects
+/// precedence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Vec<Identifier>,
+}
+
+impl Version {
+    /// Strict `MAJOR.MINOR.PATCH[-PRE][+BUILD]`.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let version = Self::parse_lenient(text)?;
+        let core = text.split(['-', '+']).next()?;
+        (core.split('.').count() == 3).then_some(version)
+    }
+
+    /// Also accepts `1` and `1.2`, as OSV events sometimes write them.
+    fn parse_lenient(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.split_once('+').map_or(text, |(version, _)| version);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        let mut numbers = core.split('.');
+        let mut next = || -> Option<u64> {
+            match numbers.next() {
+                Some(part) if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) => {
+                    part.parse().ok()
+                }
+                Some(_) => None,
+                None => Some(0),
+            }
+        };
+        let (major, minor, patch) = (next()?, next()?, next()?);
+        if numbers.next().is_some() {
+            return None;
+        }
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|part| match part.parse::<u64>() {
+                    _ if part.is_empty() => None,
+                    Ok(number) if part.bytes().all(|b| b.is_ascii_digit()) => {
+                        Some(Identifier::Numeric(number))
+                    }
+                    _ => Some(Identifier::Alpha(part.to_string())),
+                })
+                .collect::<Option<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Some(Version {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    /// Cargo's default (caret) compatibility: same leftmost nonzero part.
+    pub(crate) fn compatible_with(&self, other: &Version) -> bool {
+        match (self.major, self.minor) {
+            (0, 0) => other.major == 0 && other.minor == 0 && other.patch == self.patch,
+            (0, minor) => other.major == 0 && other.minor == minor,
+            (major, _) => other.major == major,
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A release outranks its own pre-releases.
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Event {
+    Introduced(Version),
+    Fixed(Version),
+    LastAffected(Version),
+}
+
+/// One crates.io package entry of an OSV advisory.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Advisory {
+    pub(crate) id: String,
+    pub(crate) package: String,
+    /// `SEMVER` ranges, each a list of events in the order given.
+    ranges: Vec<Vec<Event>>,
+    /// Explicitly listed affected versions.
+    versions: Vec<String>,
+}
+
+impl Advisory {
+    /// OSV range evaluation: walking the events in order, `introduced`
+    /// opens an affected span, `fixed` closes it before that version and
+    /// `last_affected` after it.
+    pub(crate) fn affects(&self, version: &str) -> bool {
+        if self.versions.iter().any(|listed| listed == version) {
+            return true;
+        }
+        let Some(version) = Version::parse(version) else {
+            return false;
+        };
+        self.ranges.iter().any(|events| {
+            let mut affected = false;
+            for event in events {
+                match event {
+                    Event::Introduced(start) if version >= *start => affected = true,
+                    Event::Fixed(end) if version >= *end => affected = false,
+                    Event::LastAffected(end) if version > *end => affected = false,
+                    _ => {}
+                }
+            }
+            affected
+        })
+    }
+}
+
+/// The crates.io entries of one OSV record; withdrawn advisories yield
+/// none.
+fn parse_osv_entry(entry: &Value) -> Result<Vec<Advisory>, String> {
+    let id = entry["id"]
+        .as_str()
+        .ok_or_else(|| "OSV entry without an id".to_string())?;
+    if entry
+        .get("withdrawn")
+        .is_some_and(|withdrawn| !withdrawn.is_null())
+    {
+        return Ok(Vec::new());
+    }
+    let mut advisories = Vec::new();
+    for affected in entry["affected"].as_array().into_iter().flatten() {
+        let package = &affected["package"];
+        if package["ecosystem"] != "crates.io" {
+            continue;
+        }
+        let name = package["name"]
+            .as_str()
+            .ok_or_else(|| format!("{id}: affected package without a name"))?;
+        let mut ranges = Vec::new();
+        for range in affected["ranges"].as_array().into_iter().flatten() {
+            if range["type"] != "SEMVER" {
+                continue;
+            }
+            let events = range["events"].as_array().into_iter().flatten();
+            let events = events
+                .map(|event| {
+                    parse_event(event).ok_or_else(|| format!("{id}: bad range event {event}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            ranges.push(events);
+        }
+        let versions = affected["versions"].as_array().into_iter().flatten();
+        advisories.push(Advisory {
+            id: id.to_string(),
+            package: name.to_string(),
+            ranges,
+            versions: versions
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+        });
+    }
+    Ok(advisories)
+}
+
+fn parse_event(event: &Value) -> Option<Event> {
+    let (kind, version) = event.as_object()?.iter().next()?;
+    let version = Version::parse_lenient(version.as_str()?)?;
+    match kind.as_str() {
+        "introduced" => Some(Event::Introduced(version)),
+        "fixed" => Some(Event::Fixed(version)),
+        "last_affected" => Some(Event::LastAffected(version)),
+        _ => None,
+    }
+}
+
+/// Advisories from an OSV snapshot: one JSON file holding an entry or an
+/// array of entries, or a directory of such files (an unpacked OSV
+/// `crates.io/all.zip`).
+pub(crate) fn load_advisories(path: &Path) -> Result<Vec<Advisory>, String> {
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .map_err(|err| format!("cannot read {}: {err}", path.display()))?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|file| file.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut advisories = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file)
+            .map_err(|err| format!("cannot read {}: {err}", file.display()))?;
+        let parsed: Value = serde_json::from_str(&text)
+            .map_err(|err| format!("{} is not valid JSON: {err}", file.display()))?;
+        let entries = match parsed {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+        for entry in &entries {
+            advisories.extend(parse_osv_entry(entry)?);
+        }
+    }
+    Ok(advisories)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Finding {
+    pub(crate) advisory: String,
+    pub(crate) package: String,
+    pub(crate) version: String,
+}
+
+/// Every (advisory, locked registry package) pair that matches.
+pub(crate) fn scan(packages: &[Package], advisories: &[Advisory]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for package in packages.iter().filter(|package| package.is_published()) {
+        for advisory in advisories
+            .iter()
+            .filter(|advisory| advisory.package == package.name)
+        {
+            if advisory.affects(&package.version) {
+                findings.push(Finding {
+                    advisory: advisory.id.clone(),
+                    package: package.name.clone(),
+                    version: package.version.clone(),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Versions of each crate present in the local registry: unpacked sources
+/// (`registry/src/<index>/<name>-<version>`) and downloaded archives
+/// (`registry/cache/<index>/<name>-<version>.crate`).
+pub(crate) fn cached_versions(cargo_home: &Path) -> Vec<String> {
+    let registry = cargo_home.join("registry");
+    let mut entries = Vec::new();
+    for kind in ["src", "cache"] {
+        let indexes = fs::read_dir(registry.join(kind))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok);
+        for index in indexes {
+            let crates = fs::read_dir(index.path())
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok);
+            for entry in crates {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                entries.push(
+                    name.strip_suffix(".crate")
+                        .map_or(name.clone(), str::to_string),
+                );
+            }
+        }
+    }
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct OutdatedReport {
+    /// Locked packages with a newer compatible version cached.
+    pub(crate) outdated: Vec<String>,
+    /// Locked registry packages with no other cached version to compare.
+    pub(crate) unresolved: usize,
+}
+
+/// Compares each locked registry package with the highest compatible
+/// version among `cached` (`<name>-<version>` entries).
+pub(crate) fn outdated(packages: &[Package], cached: &[String]) -> OutdatedReport {
+    let mut report = OutdatedReport::default();
+    for package in packages.iter().filter(|package| package.is_published()) {
+        let Some(locked) = Version::parse(&package.version) else {
+            report.unresolved += 1;
+            continue;
+        };
+        let prefix = format!("{}-", package.name);
+        let newest = cached
+            .iter()
+            .filter_map(|entry| Version::parse(entry.strip_prefix(&prefix)?))
+            .filter(|candidate| locked.compatible_with(candidate) && candidate.pre.is_empty())
+            .max();
+        match newest {
+            Some(newest) if newest > locked => report.outdated.push(package.name.clone()),
+            Some(newest) if newest == locked => {}
+            _ => report.unresolved += 1,
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn reads_packages_from_a_lockfile() {
+        let packages = parse_lockfile(fixtures::scan_lockfile()).unwrap();
+        let names: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|package| (package.name.as_str(), package.version.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("demo", "0.1.0"),
+                ("itoa", "1.0.11"),
+                ("smallvec", "1.6.0"),
+                ("time", "0.1.44")
+            ]
+        );
+        assert!(!packages[0].is_published());
+        assert!(packages[1].is_published());
+        assert!(parse_lockfile("[[package]]\nname = \"x\"\n").is_err());
+        assert!(parse_lockfile("[[package]]\nname = x\nversion = \"1.0.0\"\n").is_err());
+    }
+
+    #[test]
+    fn orders_versions_by_semver_precedence() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0",
+            "1.0.1",
+            "1.10.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert_eq!(version("1.2.3+build.5"), version("1.2.3"));
+        assert_eq!(Version::parse_lenient("0"), Version::parse("0.0.0"));
+        for bad in ["1.2", "1.2.x", "", "1.2.3.4", "1.2.3-"] {
+            assert_eq!(Version::parse(bad), None, "{bad}");
+        }
+        assert!(version("1.2.0").compatible_with(&version("1.9.3")));
+        assert!(!version("1.2.0").compatible_with(&version("2.0.0")));
+        assert!(version("0.3.1").compatible_with(&version("0.3.9")));
+        assert!(!version("0.3.1").compatible_with(&version("0.4.0")));
+        assert!(!version("0.0.3").compatible_with(&version("0.0.4")));
+    }
+
+    #[test]
+    fn evaluates_osv_ranges_in_order() {
+        let advisories = load_advisories(&fixtures::scan_osv_path()).unwrap();
+        let smallvec = advisories
+            .iter()
+            .find(|advisory| advisory.package == "smallvec")
+            .unwrap();
+        // Affected: [0.6.3, 0.6.14) and [1.0.0, 1.6.1).
+        for (candidate, affected) in [
+            ("0.6.2", false),
+            ("0.6.3", true),
+            ("0.6.14", false),
+            ("1.0.0", true),
+            ("1.6.0", true),
+            ("1.6.1", false),
+        ] {
+            assert_eq!(smallvec.affects(candidate), affected, "{candidate}");
+        }
+        let time = advisories
+            .iter()
+            .find(|advisory| advisory.package == "time")
+            .unwrap();
+        assert!(time.affects("0.1.44"));
+        assert!(time.affects("0.2.22"));
+        assert!(!time.affects("0.2.23"));
+        assert!(time.affects("0.0.9"), "listed explicitly");
+        // The withdrawn advisory and the non-crates.io package are dropped.
+        assert_eq!(advisories.len(), 2);
+    }
+
+    #[test]
+    fn scans_a_lockfile_against_the_snapshot() {
+        let packages = parse_lockfile(fixtures::scan_lockfile()).unwrap();
+        let advisories = load_advisories(&fixtures::scan_osv_path()).unwrap();
+        let findings = scan(&packages, &advisories);
+        let found: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|finding| (finding.advisory.as_str(), finding.package.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("TEST-2021-0003", "smallvec"), ("TEST-2020-0001", "time")]
+        );
+    }
+
+    #[test]
+    fn outdated_compares_against_cached_compatible_versions() {
+        let packages = parse_lockfile(fixtures::scan_lockfile()).unwrap();
+        let cached: Vec<String> = [
+            "itoa-1.0.11",
+            "smallvec-1.13.2",
+            "smallvec-2.0.0-alpha.1",
+            "time-0.3.36",
+        ]
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect();
+        let report = outdated(&packages, &cached);
+        assert_eq!(report.outdated, ["smallvec"]);
+        // time 0.1 has no cached 0.1.x release to compare with.
+        assert_eq!(report.unresolved, 1);
+    }
+
+    #[test]
+    fn lists_cached_registry_versions() {
+        let home = std::env::temp_dir().join(format!("builtin-scan-home-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let src = home.join("registry/src/index.crates.io-1949cf8c6b5b557f");
+        fs::create_dir_all(src.join("itoa-1.0.11")).unwrap();
+        let cache = home.join("registry/cache/index.crates.io-1949cf8c6b5b557f");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("itoa-1.0.11.crate"), b"").unwrap();
+        fs::write(cache.join("serde-1.0.200.crate"), b"").unwrap();
+        assert_eq!(cached_versions(&home), ["itoa-1.0.11", "serde-1.0.200"]);
+        assert!(cached_versions(&home.join("missing")).is_empty());
+        let _ = fs::remove_dir_all(home);
+    }
+}
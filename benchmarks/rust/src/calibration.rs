@@ -0,0 +1,365 @@
+//! `calibrate` and `--preset auto`: a few-second machine score from three
+//! fixed micro-kernels, stored in the data dir, that scales workload sizes
+//! so every machine lands near the runtime the defaults were sized for. A
+//! machine twice as fast as the reference scores 2.0 and gets twice the
+//! samples. Scaled records carry the score, the formula and the sizes used,
+//! so they are never compared with a default-sized run unawares.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::hint::black_box;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::benchmark_ids::{CPU_MONTE_CARLO_PI, STRING_JSON_PARSE_TRANSFORM};
+use crate::clock;
+use crate::config::BenchmarkConfigs;
+
+/// Each kernel with its median seconds on the reference machine, which
+/// scores 1.0.
+pub(crate) const KERNELS: [(&str, f64); 3] = [
+    ("integer_loop", 0.050),
+    ("memory_copy", 0.012),
+    ("file_write", 0.006),
+];
+
+/// Runs per kernel after one discarded warmup; the median is scored.
+const REPETITIONS: usize = 5;
+
+/// A kernel whose slowest run took longer than this times its fastest was
+/// disturbed; the calibration is kept but marked unstable.
+const MAX_SPREAD: f64 = 1.5;
+
+/// Kernel scores are clamped into this range, so one pathological kernel
+/// cannot scale a workload by orders of magnitude.
+const SCORE_BOUNDS: (f64, f64) = (0.05, 20.0);
+
+/// A stored calibration older than this is measured again.
+pub(crate) const MAX_AGE: Duration = Duration::from_secs(7 * 86_400);
+
+pub(crate) const FORMULA: &str = "size = clamp(round(default_size * score), min, max)";
+
+/// The workload parameters `--preset auto` scales, with their bounds.
+/// Only in-memory CPU workloads scale with the score; the ETL dataset is
+/// generated once and the HTTP workloads are bound by the server.
+pub(crate) const SCALED_WORKLOADS: [(&str, &str, u64, u64); 2] = [
+    (CPU_MONTE_CARLO_PI, "samples", 20_000, 20_000_000),
+    (STRING_JSON_PARSE_TRANSFORM, "records", 2_000, 400_000),
+];
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct KernelScore {
+    pub(crate) name: String,
+    pub(crate) median_seconds: f64,
+    /// Slowest over fastest repetition.
+    pub(crate) spread: f64,
+    pub(crate) score: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Calibration {
+    /// Geometric mean of the kernel scores.
+    pub(crate) score: f64,
+    /// Every kernel's spread was within `MAX_SPREAD`.
+    pub(crate) stable: bool,
+    pub(crate) measured_at: DateTime<Utc>,
+    pub(crate) kernels: Vec<KernelScore>,
+}
+
+impl Calibration {
+    /// Scores per-kernel repetition timings, in `KERNELS` order.
+    pub(crate) fn from_timings(timings: &[Vec<f64>], measured_at: DateTime<Utc>) -> Calibration {
+        let kernels: Vec<KernelScore> = KERNELS
+            .iter()
+            .zip(timings)
+            .map(|((name, reference), runs)| {
+                let median = median(runs);
+                let fastest = runs.iter().copied().fold(f64::INFINITY, f64::min);
+                let slowest = runs.iter().copied().fold(0.0, f64::max);
+                let score = if median > 0.0 { reference / median } else { SCORE_BOUNDS.1 };
+                KernelScore {
+                    name: name.to_string(),
+                    median_seconds: median,
+                    spread: if fastest > 0.0 { slowest / fastest } else { f64::INFINITY },
+                    score: score.clamp(SCORE_BOUNDS.0, SCORE_BOUNDS.1),
+                }
+            })
+            .collect();
+        let log_mean = kernels.iter().map(|kernel| kernel.score.ln()).sum::<f64>()
+            / kernels.len().max(1) as f64;
+        Calibration {
+            score: log_mean.exp(),
+            stable: kernels.iter().all(|kernel| kernel.spread <= MAX_SPREAD),
+            measured_at,
+            kernels,
+        }
+    }
+
+    pub(crate) fn expires_at(&self) -> DateTime<Utc> {
+        self.measured_at + chrono::Duration::from_std(MAX_AGE).unwrap_or_default()
+    }
+
+    pub(crate) fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at() || now < self.measured_at
+    }
+}
+
+fn median(runs: &[f64]) -> f64 {
+    let mut sorted = runs.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    match sorted.len() {
+        0 => 0.0,
+        len if len % 2 == 1 => sorted[len / 2],
+        len => (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0,
+    }
+}
+
+fn time(kernel: &mut dyn FnMut()) -> f64 {
+    let start = Instant::now();
+    kernel();
+    start.elapsed().as_secs_f64()
+}
+
+/// Wrapping integer mixing, nothing for the optimizer to fold away.
+fn integer_loop() {
+    let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+    for i in 0..black_box(20_000_000_u64) {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state = state.wrapping_add(i);
+    }
+    black_box(state);
+}
+
+/// 16 copies of an 8 MiB buffer.
+fn memory_copy(source: &[u8], target: &mut [u8]) {
+    for _ in 0..16 {
+        target.copy_from_slice(black_box(source));
+        black_box(&mut *target);
+    }
+}
+
+/// 256 files of 32 KiB, written and removed.
+fn file_write(scratch: &Path) -> io::Result<()> {
+    let block = vec![0x5a_u8; 32 * 1024];
+    for index in 0..256 {
+        fs::write(scratch.join(format!("block-{index}")), &block)?;
+    }
+    for index in 0..256 {
+        fs::remove_file(scratch.join(format!("block-{index}")))?;
+    }
+    Ok(())
+}
+
+/// Runs every kernel `REPETITIONS` times, writing files under `scratch`,
+/// which is removed afterwards.
+pub(crate) fn measure(scratch: &Path) -> io::Result<Calibration> {
+    fs::create_dir_all(scratch)?;
+    let source = vec![0xa5_u8; 8 * 1024 * 1024];
+    let mut target = vec![0_u8; source.len()];
+    let mut write_error = None;
+    let mut kernels: [Box<dyn FnMut()>; 3] = [
+        Box::new(integer_loop),
+        Box::new(|| memory_copy(&source, &mut target)),
+        Box::new(|| {
+            if let Err(err) = file_write(scratch) {
+                write_error.get_or_insert(err);
+            }
+        }),
+    ];
+    let timings: Vec<Vec<f64>> = kernels
+        .iter_mut()
+        .map(|kernel| {
+            kernel();
+            (0..REPETITIONS).map(|_| time(kernel.as_mut())).collect()
+        })
+        .collect();
+    drop(kernels);
+    let _ = fs::remove_dir_all(scratch);
+    match write_error {
+        Some(err) => Err(err),
+        None => Ok(Calibration::from_timings(&timings, Utc::now())),
+    }
+}
+
+pub(crate) fn store(path: &Path, calibration: &Calibration) -> io::Result<()> {
+    let text = serde_json::to_string_pretty(calibration).map_err(io::Error::other)?;
+    fs::write(path, text + "\n")
+}
+
+/// The calibration at `path`, unless it is missing, unreadable or stale.
+pub(crate) fn load_fresh(path: &Path, now: DateTime<Utc>) -> Option<Calibration> {
+    let text = fs::read_to_string(path).ok()?;
+    let calibration: Calibration = serde_json::from_str(&text).ok()?;
+    (!calibration.is_stale(now)).then_some(calibration)
+}
+
+pub(crate) fn scaled_size(default_size: u64, score: f64, min: u64, max: u64) -> u64 {
+    let scaled = (default_size as f64 * score).round();
+    if scaled.is_finite() {
+        (scaled as u64).clamp(min, max)
+    } else {
+        max
+    }
+}
+
+/// A calibration applied to one run's configs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Applied {
+    pub(crate) calibration: Calibration,
+    /// Per scaled benchmark, `{param: {default, scaled, min, max}}`.
+    pub(crate) scaled: Map<String, Value>,
+}
+
+impl Applied {
+    /// The `calibration` block of a record for `benchmark_id`.
+    pub(crate) fn record_block(&self, benchmark_id: &str) -> Value {
+        let calibration = &self.calibration;
+        let mut block = json!({
+            "preset": "auto",
+            "score": calibration.score,
+            "stable": calibration.stable,
+            "measured_at": clock::utc_timestamp(calibration.measured_at),
+            "expires_at": clock::utc_timestamp(calibration.expires_at()),
+            "formula": FORMULA,
+            "kernels": calibration.kernels,
+        });
+        if let Some(scaled) = self.scaled.get(benchmark_id) {
+            block["scaled"] = scaled.clone();
+        }
+        block
+    }
+
+    /// The entries a calibrated record adds to its `parameters`.
+    pub(crate) fn record_parameters(&self) -> Map<String, Value> {
+        let mut parameters = Map::new();
+        parameters.insert("preset".to_string(), json!("auto"));
+        parameters.insert("calibration_score".to_string(), json!(self.calibration.score));
+        parameters
+    }
+}
+
+/// `configs` with every `SCALED_WORKLOADS` parameter scaled by the score.
+pub(crate) fn apply(
+    configs: &BenchmarkConfigs,
+    calibration: &Calibration,
+) -> Result<(BenchmarkConfigs, Applied), String> {
+    let mut scaled_configs = configs.clone();
+    let mut scaled = Map::new();
+    for (benchmark_id, param, min, max) in SCALED_WORKLOADS {
+        let default_size = configs.params_for(benchmark_id)[param]
+            .as_u64()
+            .ok_or_else(|| format!("{benchmark_id}.{param} is not a size"))?;
+        let size = scaled_size(default_size, calibration.score, min, max);
+        let mut overrides = Map::new();
+        overrides.insert(param.to_string(), json!(size));
+        scaled_configs = scaled_configs.with_overrides(benchmark_id, &overrides)?;
+        let entry = scaled
+            .entry(benchmark_id)
+            .or_insert_with(|| Value::Object(Map::new()));
+        entry[param] = json!({"default": default_size, "scaled": size, "min": min, "max": max});
+    }
+    let applied = Applied {
+        calibration: calibration.clone(),
+        scaled,
+    };
+    Ok((scaled_configs, applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark_ids;
+    use chrono::TimeZone;
+    use std::env;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).unwrap()
+    }
+
+    /// Timings for a machine `speed` times the reference, every run equal.
+    fn steady(speed: f64) -> Vec<Vec<f64>> {
+        KERNELS
+            .iter()
+            .map(|(_, reference)| vec![reference / speed; REPETITIONS])
+            .collect()
+    }
+
+    #[test]
+    fn scores_are_geometric_means_of_bounded_kernel_scores() {
+        let reference = Calibration::from_timings(&steady(1.0), at(0));
+        assert!((reference.score - 1.0).abs() < 1e-9, "{}", reference.score);
+        assert!(reference.stable);
+        assert_eq!(reference.kernels.len(), KERNELS.len());
+
+        let fast = Calibration::from_timings(&steady(4.0), at(0));
+        assert!((fast.score - 4.0).abs() < 1e-9, "{}", fast.score);
+
+        // One kernel 1000x faster is clamped to 20: (1 * 1 * 20)^(1/3).
+        let mut timings = steady(1.0);
+        timings[2] = vec![KERNELS[2].1 / 1000.0; REPETITIONS];
+        let skewed = Calibration::from_timings(&timings, at(0));
+        assert_eq!(skewed.kernels[2].score, SCORE_BOUNDS.1);
+        assert!((skewed.score - 20f64.cbrt()).abs() < 1e-9, "{}", skewed.score);
+    }
+
+    #[test]
+    fn noisy_repetitions_use_the_median_and_mark_instability() {
+        let mut timings = steady(1.0);
+        // One disturbed run out of five barely moves the median.
+        let reference = KERNELS[0].1;
+        timings[0] = vec![reference, reference, reference * 3.0, reference, reference * 1.1];
+        let calibration = Calibration::from_timings(&timings, at(0));
+        assert_eq!(calibration.kernels[0].median_seconds, reference);
+        assert!((calibration.score - 1.0).abs() < 1e-9);
+        assert!((calibration.kernels[0].spread - 3.0).abs() < 1e-9);
+        assert!(!calibration.stable);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn scaling_is_proportional_within_bounds() {
+        assert_eq!(scaled_size(200_000, 1.0, 20_000, 20_000_000), 200_000);
+        assert_eq!(scaled_size(200_000, 2.5, 20_000, 20_000_000), 500_000);
+        assert_eq!(scaled_size(200_000, 0.05, 20_000, 20_000_000), 20_000);
+        assert_eq!(scaled_size(200_000, 500.0, 20_000, 20_000_000), 20_000_000);
+        assert_eq!(scaled_size(200_000, f64::NAN, 20_000, 20_000_000), 20_000_000);
+
+        let calibration = Calibration::from_timings(&steady(2.0), at(0));
+        let (configs, applied) = apply(&BenchmarkConfigs::default(), &calibration).unwrap();
+        assert_eq!(configs.monte_carlo.samples, 400_000);
+        assert_eq!(configs.json_transform.records, 40_000);
+        assert_eq!(configs.etl, BenchmarkConfigs::default().etl);
+        let block = applied.record_block(CPU_MONTE_CARLO_PI);
+        assert_eq!(block["formula"], FORMULA);
+        assert_eq!(block["expires_at"], clock::utc_timestamp(at(MAX_AGE.as_secs() as i64)));
+        assert_eq!(
+            block["scaled"]["samples"],
+            json!({"default": 200_000, "scaled": 400_000, "min": 20_000, "max": 20_000_000})
+        );
+        assert!(applied.record_block("data_pipeline_etl_minibatch").get("scaled").is_none());
+        let parameters = applied.record_parameters();
+        assert_eq!(parameters["preset"], "auto");
+        assert_eq!(parameters["calibration_score"], block["score"]);
+        for (id, _, min, max) in SCALED_WORKLOADS {
+            assert!(benchmark_ids::ALL.contains(&id) && min < max, "{id}");
+        }
+    }
+
+    #[test]
+    fn stored_calibrations_expire() {
+        let path = env::temp_dir().join(format!("calibration-{}.json", std::process::id()));
+        let calibration = Calibration::from_timings(&steady(1.5), at(1_000_000));
+        store(&path, &calibration).unwrap();
+        let day = 86_400;
+        assert_eq!(load_fresh(&path, at(1_000_000 + day)), Some(calibration));
+        assert_eq!(load_fresh(&path, at(1_000_000 + 7 * day)), None);
+        // A clock that went backwards cannot vouch for the age either.
+        assert_eq!(load_fresh(&path, at(999_000)), None);
+        let _ = fs::remove_file(&path);
+        assert_eq!(load_fresh(&path, at(1_000_000)), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! `compare <results.json> --chart-data <chart.json>`: the "Rust = 1.0,
+//! Python = Nx" bars, precomputed. Each benchmark with a declared headline
+//! metric becomes one series of raw values and values normalized to Rust,
+//! flipped for higher-is-better metrics so a bigger bar always means slower.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::benchmark_ids::{
+    BUILD_STARTUP_FEEDBACK_LOOP, CPU_MONTE_CARLO_PI, DATA_PIPELINE_ETL_MINIBATCH,
+    IO_CONCURRENT_HTTP_CLIENT, STRING_JSON_PARSE_TRANSFORM,
+};
+use crate::retry;
+
+/// Every series is normalized to this language.
+pub(crate) const BASELINE_LANGUAGE: &str = "rust";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    LowerIsBetter,
+    HigherIsBetter,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::LowerIsBetter => "lower_is_better",
+            Direction::HigherIsBetter => "higher_is_better",
+        }
+    }
+
+    /// `value` relative to `baseline`, above 1 when `value` is slower.
+    fn slowdown(self, value: f64, baseline: f64) -> Option<f64> {
+        let ratio = match self {
+            Direction::LowerIsBetter => value / baseline,
+            Direction::HigherIsBetter => baseline / value,
+        };
+        ratio.is_finite().then_some(ratio)
+    }
+}
+
+/// The one metric charted per benchmark. Benchmarks without an entry (the
+/// scorecards, and those only Rust runs) are skipped.
+pub(crate) const HEADLINE_METRICS: [(&str, &str, Direction); 5] = [
+    (CPU_MONTE_CARLO_PI, "runtime_seconds", Direction::LowerIsBetter),
+    (STRING_JSON_PARSE_TRANSFORM, "runtime_seconds", Direction::LowerIsBetter),
+    (IO_CONCURRENT_HTTP_CLIENT, "runtime_seconds", Direction::LowerIsBetter),
+    (DATA_PIPELINE_ETL_MINIBATCH, "throughput_mb_s", Direction::HigherIsBetter),
+    (BUILD_STARTUP_FEEDBACK_LOOP, "startup_seconds", Direction::LowerIsBetter),
+];
+
+fn headline(benchmark_id: &str) -> Option<(&'static str, Direction)> {
+    HEADLINE_METRICS
+        .iter()
+        .find(|(id, _, _)| *id == benchmark_id)
+        .map(|(_, metric, direction)| (*metric, *direction))
+}
+
+/// A language's headline value: from its first selected record, as with
+/// `--print`, so a multi-target benchmark is charted by its first target.
+struct Reading {
+    value: f64,
+    unit: String,
+}
+
+/// The chart document for `records`, in first-seen benchmark order. Notes
+/// explain every benchmark or language left out.
+pub(crate) fn chart(records: &[Value]) -> Value {
+    let mut order: Vec<&str> = Vec::new();
+    let mut readings: BTreeMap<&str, BTreeMap<&str, Option<Reading>>> = BTreeMap::new();
+    for record in records.iter().filter(|record| retry::is_selected(record)) {
+        let (Some(id), Some(language)) =
+            (record["benchmark_id"].as_str(), record["language"].as_str())
+        else {
+            continue;
+        };
+        if !readings.contains_key(id) {
+            order.push(id);
+        }
+        let by_language = readings.entry(id).or_default();
+        if by_language.contains_key(language) {
+            continue;
+        }
+        let reading = headline(id).and_then(|(metric, _)| {
+            let metric = &record["metrics"][metric];
+            Some(Reading {
+                value: metric["value"].as_f64()?,
+                unit: metric["unit"].as_str().unwrap_or_default().to_string(),
+            })
+        });
+        by_language.insert(language, reading);
+    }
+
+    let mut benchmarks = Vec::new();
+    let mut skipped = Vec::new();
+    for id in order {
+        let Some((metric, direction)) = headline(id) else {
+            skipped.push(json!({"benchmark_id": id, "note": "no headline metric declared"}));
+            continue;
+        };
+        let by_language = &readings[id];
+        let baseline = match by_language.get(BASELINE_LANGUAGE) {
+            Some(Some(reading)) => reading,
+            Some(None) => {
+                let note = format!("{BASELINE_LANGUAGE} record has no {metric}");
+                skipped.push(json!({"benchmark_id": id, "note": note}));
+                continue;
+            }
+            None => {
+                let note = format!("no {BASELINE_LANGUAGE} record");
+                skipped.push(json!({"benchmark_id": id, "note": note}));
+                continue;
+            }
+        };
+        if !(baseline.value > 0.0 && baseline.value.is_finite()) {
+            let note = format!("{BASELINE_LANGUAGE} {metric} is {}", baseline.value);
+            skipped.push(json!({"benchmark_id": id, "note": note}));
+            continue;
+        }
+        let mut notes = Vec::new();
+        // The baseline first, then the other languages alphabetically.
+        let languages = std::iter::once(BASELINE_LANGUAGE)
+            .chain(by_language.keys().copied().filter(|lang| *lang != BASELINE_LANGUAGE));
+        let mut series = Vec::new();
+        for language in languages {
+            let Some(reading) = &by_language[language] else {
+                notes.push(format!("{language} record has no {metric}"));
+                continue;
+            };
+            let Some(normalized) = direction.slowdown(reading.value, baseline.value) else {
+                let value = reading.value;
+                notes.push(format!("{language} {metric} of {value} cannot be normalized"));
+                continue;
+            };
+            series.push(json!({
+                "language": language,
+                "value": reading.value,
+                "unit": reading.unit,
+                "normalized": normalized,
+            }));
+        }
+        benchmarks.push(json!({
+            "benchmark_id": id,
+            "metric": metric,
+            "direction": direction.as_str(),
+            "series": series,
+            "notes": notes,
+        }));
+    }
+    json!({
+        "baseline_language": BASELINE_LANGUAGE,
+        "benchmarks": benchmarks,
+        "skipped": skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark_ids;
+
+    fn record(id: &str, language: &str, metric: &str, value: f64, unit: &str) -> Value {
+        json!({
+            "benchmark_id": id,
+            "language": language,
+            "metrics": {metric: {"value": value, "unit": unit}},
+        })
+    }
+
+    #[test]
+    fn headline_metrics_name_registered_benchmarks() {
+        for (id, _, _) in HEADLINE_METRICS {
+            assert!(benchmark_ids::ALL.contains(&id), "{id}");
+        }
+    }
+
+    #[test]
+    fn normalizes_so_bigger_means_slower() {
+        let records = [
+            record("cpu_monte_carlo_pi", "python", "runtime_seconds", 3.0, "s"),
+            record("cpu_monte_carlo_pi", "rust", "runtime_seconds", 0.5, "s"),
+            record("data_pipeline_etl_minibatch", "rust", "throughput_mb_s", 80.0, "mb/s"),
+            record("data_pipeline_etl_minibatch", "python", "throughput_mb_s", 20.0, "mb/s"),
+        ];
+        let chart = chart(&records);
+        assert_eq!(chart["baseline_language"], "rust");
+        let runtime = &chart["benchmarks"][0];
+        assert_eq!(runtime["benchmark_id"], "cpu_monte_carlo_pi");
+        assert_eq!(runtime["direction"], "lower_is_better");
+        assert_eq!(
+            runtime["series"],
+            json!([
+                {"language": "rust", "value": 0.5, "unit": "s", "normalized": 1.0},
+                {"language": "python", "value": 3.0, "unit": "s", "normalized": 6.0},
+            ])
+        );
+        // Python moves a quarter of the bytes per second: 4x slower.
+        let throughput = &chart["benchmarks"][1];
+        assert_eq!(throughput["direction"], "higher_is_better");
+        assert_eq!(throughput["series"][0]["normalized"], 1.0);
+        assert_eq!(throughput["series"][1]["normalized"], 4.0);
+        assert_eq!(throughput["series"][1]["value"], 20.0);
+        assert_eq!(chart["skipped"], json!([]));
+
+        assert_eq!(Direction::HigherIsBetter.slowdown(0.0, 80.0), None);
+    }
+
+    #[test]
+    fn missing_data_is_skipped_with_a_note() {
+        let mut superseded = record("cpu_monte_carlo_pi", "rust", "runtime_seconds", 9.0, "s");
+        superseded["selected_for_comparison"] = json!(false);
+        let records = [
+            record("static_security_lint_benchmark", "rust", "finding_count", 4.0, "count"),
+            record("string_json_parse_transform", "python", "runtime_seconds", 2.0, "s"),
+            record("data_pipeline_etl_minibatch", "rust", "runtime_seconds", 1.0, "s"),
+            superseded,
+            record("cpu_monte_carlo_pi", "rust", "runtime_seconds", 1.0, "s"),
+            record("cpu_monte_carlo_pi", "python", "pi_estimate", 3.0, "ratio"),
+            record("build_startup_feedback_loop", "rust", "startup_seconds", 0.0, "s"),
+            record("build_startup_feedback_loop", "python", "startup_seconds", 0.2, "s"),
+        ];
+        let chart = chart(&records);
+        let skipped: Vec<(&str, &str)> = chart["skipped"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["benchmark_id"].as_str().unwrap(),
+                    entry["note"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("static_security_lint_benchmark", "no headline metric declared"),
+                ("string_json_parse_transform", "no rust record"),
+                ("data_pipeline_etl_minibatch", "rust record has no throughput_mb_s"),
+                ("build_startup_feedback_loop", "rust startup_seconds is 0"),
+            ]
+        );
+        let monte_carlo = &chart["benchmarks"][0];
+        assert_eq!(monte_carlo["series"][0]["value"], 1.0);
+        assert_eq!(monte_carlo["series"].as_array().unwrap().len(), 1);
+        assert_eq!(monte_carlo["notes"], json!(["python record has no runtime_seconds"]));
+    }
+}
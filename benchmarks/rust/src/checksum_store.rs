@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const STORE_VERSION: u64 = 1;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct StoredValue {
+    pub(crate) value: f64,
+    #[serde(default)]
+    pub(crate) first_run_id: String,
+    #[serde(default)]
+    pub(crate) observations: u64,
+    /// Harness that recorded the baseline; empty for stores written before
+    /// fingerprints existed.
+    #[serde(default)]
+    pub(crate) harness_fingerprint: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ChecksumStore {
+    version: u64,
+    entries: BTreeMap<String, StoredValue>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Observation {
+    First,
+    Stable,
+    Diverged { previous: f64 },
+}
+
+/// 64-bit FNV-1a; stable across Rust versions and platforms, unlike
+/// `DefaultHasher`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.update(bytes);
+    hasher.finish()
+}
+
+/// `fnv1a` over bytes that arrive in pieces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+/// FNV-1a over the canonical (key-sorted) JSON encoding.
+pub(crate) fn params_hash(params: &Value) -> String {
+    format!("{:016x}", fnv1a(params.to_string().as_bytes()))
+}
+
+pub(crate) fn store_key(benchmark_id: &str, metric: &str, params_hash: &str, seed: u64) -> String {
+    format!("{benchmark_id}/{metric}/{params_hash}/{seed}")
+}
+
+impl ChecksumStore {
+    /// Entries that no longer deserialize (older or newer layouts) are
+    /// dropped individually instead of discarding the whole store.
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let raw: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+        let mut store = ChecksumStore {
+            version: STORE_VERSION,
+            entries: BTreeMap::new(),
+        };
+        if let Some(entries) = raw.get("entries").and_then(Value::as_object) {
+            for (key, entry) in entries {
+                if let Ok(stored) = serde_json::from_value::<StoredValue>(entry.clone()) {
+                    store.entries.insert(key.clone(), stored);
+                }
+            }
+        }
+        Ok(store)
+    }
+
+    pub(crate) fn load(path: &Path) -> Self {
+        let Ok(text) = fs::read_to_string(path) else {
+            return ChecksumStore::default();
+        };
+        ChecksumStore::parse(&text).unwrap_or_else(|err| {
+            eprintln!(
+                "warning: ignoring unreadable checksum store {}: {err}",
+                path.display()
+            );
+            ChecksumStore::default()
+        })
+    }
+
+    pub(crate) fn save(&mut self, path: &Path) -> io::Result<()> {
+        self.version = STORE_VERSION;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let payload = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, payload)
+    }
+
+    /// The first value seen for a key is the baseline; a diverging value is
+    /// reported but never replaces it.
+    pub(crate) fn observe(
+        &mut self,
+        key: &str,
+        value: f64,
+        run_id: &str,
+        fingerprint: &str,
+    ) -> Observation {
+        match self.entries.get_mut(key) {
+            Some(stored) if stored.value == value => {
+                stored.observations += 1;
+                Observation::Stable
+            }
+            Some(stored) => Observation::Diverged {
+                previous: stored.value,
+            },
+            None => {
+                self.rebaseline(key, value, run_id, fingerprint);
+                Observation::First
+            }
+        }
+    }
+
+    /// The harness fingerprint `key`'s baseline was recorded under, if known.
+    pub(crate) fn baseline_fingerprint(&self, key: &str) -> Option<&str> {
+        self.entries
+            .get(key)
+            .map(|stored| stored.harness_fingerprint.as_str())
+            .filter(|fingerprint| !fingerprint.is_empty())
+    }
+
+    /// Makes `value` the baseline for `key`, replacing any earlier one.
+    pub(crate) fn rebaseline(&mut self, key: &str, value: f64, run_id: &str, fingerprint: &str) {
+        self.entries.insert(
+            key.to_string(),
+            StoredValue {
+                value,
+                first_run_id: run_id.to_string(),
+                observations: 1,
+                harness_fingerprint: fingerprint.to_string(),
+            },
+        );
+    }
+}
+
+pub(crate) fn reset(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+
+    #[test]
+    fn params_hash_ignores_key_order() {
+        let a = json!({"requests": 400, "rows": 1000});
+        let b: Value = serde_json::from_str(r#"{"rows": 1000, "requests": 400}"#).unwrap();
+        assert_eq!(params_hash(&a), params_hash(&b));
+        assert_ne!(params_hash(&a), params_hash(&json!({"requests": 401, "rows": 1000})));
+        assert_eq!(params_hash(&json!({})), "08f44b07b5901a25");
+    }
+
+    #[test]
+    fn detects_divergence_and_keeps_baseline() {
+        let mut store = ChecksumStore::default();
+        let key = store_key("data_pipeline_etl_minibatch", "aggregate_value", "abc", 42);
+        assert_eq!(store.observe(&key, 10.0, "run-1", "h1"), Observation::First);
+        assert_eq!(store.observe(&key, 10.0, "run-2", "h1"), Observation::Stable);
+        assert_eq!(
+            store.observe(&key, 11.0, "run-3", "h1"),
+            Observation::Diverged { previous: 10.0 }
+        );
+        assert_eq!(
+            store.observe(&key, 12.0, "run-4", "h1"),
+            Observation::Diverged { previous: 10.0 }
+        );
+        assert_eq!(store.entries[&key].observations, 2);
+        assert_eq!(store.entries[&key].first_run_id, "run-1");
+    }
+
+    #[test]
+    fn rebaselining_replaces_value_and_fingerprint() {
+        let mut store = ChecksumStore::default();
+        store.observe("k", 1.0, "run-1", "h1");
+        assert_eq!(store.baseline_fingerprint("k"), Some("h1"));
+        assert_eq!(store.baseline_fingerprint("missing"), None);
+        store.rebaseline("k", 2.0, "run-2", "h2");
+        assert_eq!(store.baseline_fingerprint("k"), Some("h2"));
+        assert_eq!(store.observe("k", 2.0, "run-3", "h2"), Observation::Stable);
+        assert_eq!(store.entries["k"].first_run_id, "run-2");
+    }
+
+    #[test]
+    fn tolerates_schema_evolution() {
+        let store = ChecksumStore::parse(
+            r#"{
+                "version": 7,
+                "future_field": true,
+                "entries": {
+                    "a/m/h/42": {"value": 3.5, "first_run_id": "r", "observations": 2, "extra": 1},
+                    "b/m/h/42": {"value": 1.0},
+                    "c/m/h/42": {"observations": 2},
+                    "d/m/h/42": "not-an-entry"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(store.entries.len(), 2);
+        assert_eq!(store.entries["a/m/h/42"].value, 3.5);
+        assert_eq!(store.entries["b/m/h/42"].observations, 0);
+        assert_eq!(store.baseline_fingerprint("a/m/h/42"), None);
+        assert!(ChecksumStore::parse("[]").unwrap().entries.is_empty());
+        assert!(ChecksumStore::parse("{not json").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_disk_and_resets() {
+        let dir = env::temp_dir().join(format!("checksum-store-{}", std::process::id()));
+        let path = dir.join("store.json");
+        let mut store = ChecksumStore::default();
+        store.observe("k", 1.0, "run-1", "h1");
+        store.save(&path).unwrap();
+
+        let mut reloaded = ChecksumStore::load(&path);
+        assert_eq!(
+            reloaded.observe("k", 2.0, "run-2", "h1"),
+            Observation::Diverged { previous: 1.0 }
+        );
+
+        reset(&path).unwrap();
+        reset(&path).unwrap();
+        assert!(ChecksumStore::load(&path).entries.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,218 @@
+//! Resource usage of measured subprocesses. Every child is reaped with
+//! `wait4`, which returns its rusage covering the child and every
+//! descendant it waited for (rustc under cargo); subprocess benchmarks take
+//! their `peak_rss_kb` from it. Wall time alone hides whether cargo was
+//! CPU-bound or waiting on disk and network, so `BENCHMARK_CHILD_COUNTERS=1`
+//! adds the full breakdown per tool. Linux only: elsewhere the counters are
+//! reported as unavailable and children are waited for as before.
+
+use std::env;
+use std::process::Child;
+
+use crate::record::Metrics;
+
+/// `BENCHMARK_CHILD_COUNTERS=1` asks for per-child counters.
+pub(crate) fn enabled() -> bool {
+    env::var("BENCHMARK_CHILD_COUNTERS").is_ok_and(|value| value == "1")
+}
+
+pub(crate) fn supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ChildUsage {
+    /// Largest resident set of any one process in the tree.
+    pub(crate) max_rss_kb: u64,
+    pub(crate) user_seconds: f64,
+    pub(crate) system_seconds: f64,
+    pub(crate) major_faults: u64,
+    pub(crate) voluntary_switches: u64,
+}
+
+impl ChildUsage {
+    /// Folds in another run of the same tool: times and counts add up, the
+    /// resident set is the larger one.
+    pub(crate) fn merge(&mut self, other: ChildUsage) {
+        self.max_rss_kb = self.max_rss_kb.max(other.max_rss_kb);
+        self.user_seconds += other.user_seconds;
+        self.system_seconds += other.system_seconds;
+        self.major_faults += other.major_faults;
+        self.voluntary_switches += other.voluntary_switches;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn seconds(time: libc::timeval) -> f64 {
+    time.tv_sec as f64 + time.tv_usec as f64 / 1e6
+}
+
+/// `ru_maxrss` is in kilobytes on Linux.
+#[cfg(target_os = "linux")]
+pub(crate) fn from_rusage(usage: &libc::rusage) -> ChildUsage {
+    ChildUsage {
+        max_rss_kb: usage.ru_maxrss.max(0) as u64,
+        user_seconds: seconds(usage.ru_utime),
+        system_seconds: seconds(usage.ru_stime),
+        major_faults: usage.ru_majflt.max(0) as u64,
+        voluntary_switches: usage.ru_nvcsw.max(0) as u64,
+    }
+}
+
+/// Reaps `child` with `wait4`, returning its exit code (`1` when it was
+/// killed by a signal, as `ExitStatus::code` reads) and its usage. `None`
+/// when `wait4` fails; the child is then waited for the usual way.
+#[cfg(target_os = "linux")]
+pub(crate) fn wait(child: &mut Child) -> Option<(i32, ChildUsage)> {
+    let pid = libc::pid_t::try_from(child.id()).ok()?;
+    let mut status = 0;
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: `pid` is our own unreaped child, and both out pointers are
+    // valid for the call.
+    let reaped = unsafe { libc::wait4(pid, &mut status, 0, usage.as_mut_ptr()) };
+    if reaped != pid {
+        return None;
+    }
+    // SAFETY: wait4 filled it in on success, and zeroed is a valid rusage.
+    let usage = unsafe { usage.assume_init() };
+    let code = if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else {
+        1
+    };
+    Some((code, from_rusage(&usage)))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn wait(_child: &mut Child) -> Option<(i32, ChildUsage)> {
+    None
+}
+
+/// Usage per tool for one record, with the tool name as the metric prefix
+/// (`cargo_build_user_cpu_seconds`). Does nothing unless counters were asked
+/// for, so records stay as they were by default.
+pub(crate) struct ChildCounters {
+    enabled: bool,
+    tools: Vec<(&'static str, ChildUsage)>,
+}
+
+impl ChildCounters {
+    pub(crate) fn from_env() -> Self {
+        ChildCounters {
+            enabled: enabled(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// Adds one run of `tool`; a run without usage (spawn failed, counters
+    /// unsupported) adds nothing.
+    pub(crate) fn add(&mut self, tool: &'static str, usage: Option<ChildUsage>) {
+        let Some(usage) = usage.filter(|_| self.enabled) else {
+            return;
+        };
+        match self.tools.iter_mut().find(|(name, _)| *name == tool) {
+            Some((_, total)) => total.merge(usage),
+            None => self.tools.push((tool, usage)),
+        }
+    }
+
+    /// `child_counters_available`, then five metrics per tool that ran.
+    pub(crate) fn insert_metrics(&self, map: &mut Metrics) {
+        if !self.enabled {
+            return;
+        }
+        let available = if supported() { 1.0 } else { 0.0 };
+        map.insert("child_counters_available", available, "flag");
+        for (tool, usage) in &self.tools {
+            let counters = [
+                ("max_rss_kb", usage.max_rss_kb as f64, "kb"),
+                ("user_cpu_seconds", usage.user_seconds, "s"),
+                ("system_cpu_seconds", usage.system_seconds, "s"),
+                ("major_page_faults", usage.major_faults as f64, "count"),
+                ("voluntary_context_switches", usage.voluntary_switches as f64, "count"),
+            ];
+            for (name, value, unit) in counters {
+                map.insert(format!("{tool}_{name}"), value, unit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn rusage_fields_convert_to_usage() {
+        // SAFETY: rusage is plain integers.
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        usage.ru_maxrss = 20480;
+        usage.ru_utime = libc::timeval { tv_sec: 1, tv_usec: 250_000 };
+        usage.ru_stime = libc::timeval { tv_sec: 0, tv_usec: 500 };
+        usage.ru_majflt = 3;
+        usage.ru_nvcsw = 42;
+        let expected = ChildUsage {
+            max_rss_kb: 20480,
+            user_seconds: 1.25,
+            system_seconds: 0.0005,
+            major_faults: 3,
+            voluntary_switches: 42,
+        };
+        assert_eq!(from_rusage(&usage), expected);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn wait4_measures_a_child_of_known_behavior() {
+        use std::process::Command;
+        // Spins for CPU time, then sleeps, which is a voluntary switch.
+        let script = "i=0; while [ $i -lt 200000 ]; do i=$((i+1)); done; sleep 0.05; exit 3";
+        let mut child = Command::new("sh").args(["-c", script]).spawn().unwrap();
+        let (code, usage) = wait(&mut child).unwrap();
+        assert_eq!(code, 3);
+        assert!(usage.user_seconds + usage.system_seconds > 0.01, "{usage:?}");
+        assert!(usage.voluntary_switches >= 1, "{usage:?}");
+        assert!(usage.max_rss_kb > 0);
+
+        let mut killed = Command::new("sh").args(["-c", "kill -9 $$"]).spawn().unwrap();
+        assert_eq!(wait(&mut killed).map(|(code, _)| code), Some(1));
+    }
+
+    #[test]
+    fn counters_merge_per_tool_and_stay_off_by_default() {
+        let run = ChildUsage {
+            max_rss_kb: 100,
+            user_seconds: 0.5,
+            system_seconds: 0.25,
+            major_faults: 1,
+            voluntary_switches: 10,
+        };
+        let mut counters = ChildCounters {
+            enabled: true,
+            tools: Vec::new(),
+        };
+        counters.add("clippy", Some(run));
+        counters.add("clippy", Some(ChildUsage { max_rss_kb: 300, ..run }));
+        counters.add("cargo_audit", None);
+        let mut map = Metrics::new();
+        counters.insert_metrics(&mut map);
+        assert_eq!(map["clippy_max_rss_kb"].value, 300.0);
+        assert_eq!(map["clippy_user_cpu_seconds"].value, 1.0);
+        assert_eq!(map["clippy_voluntary_context_switches"].value, 20.0);
+        assert_eq!(map["clippy_major_page_faults"].unit, "count");
+        assert!(!map.contains_key("cargo_audit_max_rss_kb"));
+        let available = if supported() { 1.0 } else { 0.0 };
+        assert_eq!(map["child_counters_available"].value, available);
+        assert_eq!(map.iter().count(), 6);
+
+        let mut off = ChildCounters {
+            enabled: false,
+            tools: Vec::new(),
+        };
+        off.add("clippy", Some(run));
+        let mut map = Metrics::new();
+        off.insert_metrics(&mut map);
+        assert_eq!(map, Metrics::new());
+    }
+}
@@ -0,0 +1,269 @@
+//! HTTP/1.1 chunked transfer coding (RFC 9112, section 7.1), both ways:
+//! the client reads chunked responses with it, the embedded server reads
+//! chunked uploads, and both write chunked bodies through it.
+//!
+//! Readers hand each chunk's data to a callback as it arrives, so a large
+//! body can be hashed without holding it. Chunk extensions are ignored;
+//! trailer fields are returned, since a streamed body's checksum can only
+//! follow it. Framing lines, here and in message heads, are read with a cap
+//! so a peer that never ends a line cannot make the reader allocate.
+
+use std::io::{self, BufRead, Write};
+
+use crate::line_reader::{self, Line};
+
+/// Longest status, header, chunk-size or trailer line, newline excluded.
+pub(crate) const MAX_LINE_BYTES: usize = 8 * 1024;
+/// Most bytes a message head, or a chunked body's trailer section, spans.
+pub(crate) const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// The end of a chunked body: what followed the data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ChunkedEnd {
+    /// Size lines, chunk CRLFs, the last chunk and the trailer section.
+    pub(crate) framing_bytes: usize,
+    /// Trailer fields in order, names as sent.
+    pub(crate) trailers: Vec<(String, String)>,
+}
+
+impl ChunkedEnd {
+    /// The value of trailer `name`, matched case-insensitively.
+    pub(crate) fn trailer(&self, name: &str) -> Option<&str> {
+        self.trailers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Reads one framing line into `line`, replacing its contents; returns the
+/// bytes read, `0` at the end of input. A line over `MAX_LINE_BYTES` is
+/// invalid data, and at most that much of it is ever buffered.
+pub(crate) fn read_framing_line<R: BufRead>(
+    reader: &mut R,
+    line: &mut String,
+) -> io::Result<usize> {
+    let mut buf = std::mem::take(line).into_bytes();
+    let read = match line_reader::read_line(reader, &mut buf, MAX_LINE_BYTES)? {
+        Line::Eof => 0,
+        Line::Complete(read) => read,
+        Line::Oversized(_) => return Err(invalid(format!("line exceeds {MAX_LINE_BYTES} bytes"))),
+    };
+    *line = String::from_utf8(buf).map_err(|_| invalid("line is not UTF-8"))?;
+    Ok(read)
+}
+
+/// Adds a framing line's `read` bytes to a head's running `total`; a head
+/// past `MAX_HEAD_BYTES` is invalid data.
+pub(crate) fn add_head_bytes(total: &mut usize, read: usize) -> io::Result<()> {
+    *total += read;
+    if *total > MAX_HEAD_BYTES {
+        return Err(invalid(format!("header section exceeds {MAX_HEAD_BYTES} bytes")));
+    }
+    Ok(())
+}
+
+/// Reads a chunked body through its zero-size last chunk and trailers,
+/// passing the data to `on_data` piece by piece. A body whose chunks add up
+/// to more than `limit` bytes is invalid data, caught at the size line.
+pub(crate) fn read_body<R: BufRead>(
+    reader: &mut R,
+    limit: usize,
+    mut on_data: impl FnMut(&[u8]),
+) -> io::Result<ChunkedEnd> {
+    let mut end = ChunkedEnd::default();
+    let mut line = String::new();
+    let mut data_bytes = 0usize;
+    loop {
+        let read = read_framing_line(reader, &mut line)?;
+        if read == 0 {
+            return Err(invalid("chunked body ended before the last chunk"));
+        }
+        end.framing_bytes += read;
+        // Chunk extensions after `;` carry nothing the client needs.
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| invalid(format!("invalid chunk size {:?}", line.trim_end())))?;
+        if size == 0 {
+            break;
+        }
+        data_bytes = data_bytes
+            .checked_add(size)
+            .filter(|total| *total <= limit)
+            .ok_or_else(|| invalid(format!("chunked body exceeds {limit} bytes")))?;
+        let mut remaining = size;
+        while remaining > 0 {
+            let available = reader.fill_buf()?;
+            if available.is_empty() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            let take = available.len().min(remaining);
+            on_data(&available[..take]);
+            reader.consume(take);
+            remaining -= take;
+        }
+        let mut crlf = [0u8; 2];
+        reader.read_exact(&mut crlf)?;
+        if &crlf != b"\r\n" {
+            return Err(invalid("chunk not followed by CRLF"));
+        }
+        end.framing_bytes += 2;
+    }
+    // Trailers, up to the blank line that ends the message.
+    let mut trailer_bytes = 0;
+    loop {
+        let read = read_framing_line(reader, &mut line)?;
+        if read == 0 {
+            return Err(invalid("chunked body ended inside its trailers"));
+        }
+        add_head_bytes(&mut trailer_bytes, read)?;
+        end.framing_bytes += read;
+        let field = line.trim_end();
+        if field.is_empty() {
+            return Ok(end);
+        }
+        if let Some((name, value)) = field.split_once(':') {
+            end.trailers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+}
+
+/// Writes `data` as one chunk; returns the bytes written. Empty data writes
+/// nothing, since a zero-size chunk would end the body.
+pub(crate) fn write_chunk<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<usize> {
+    if data.is_empty() {
+        return Ok(0);
+    }
+    let size = format!("{:x}\r\n", data.len());
+    writer.write_all(size.as_bytes())?;
+    writer.write_all(data)?;
+    writer.write_all(b"\r\n")?;
+    Ok(size.len() + data.len() + 2)
+}
+
+/// Ends a chunked body with the last chunk and `trailers`; returns the
+/// bytes written.
+pub(crate) fn write_last_chunk<W: Write>(
+    writer: &mut W,
+    trailers: &[(&str, &str)],
+) -> io::Result<usize> {
+    let mut end = String::from("0\r\n");
+    for (name, value) in trailers {
+        end.push_str(&format!("{name}: {value}\r\n"));
+    }
+    end.push_str("\r\n");
+    writer.write_all(end.as_bytes())?;
+    Ok(end.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read(raw: &[u8]) -> io::Result<(Vec<u8>, ChunkedEnd)> {
+        let mut body = Vec::new();
+        let end = read_body(&mut &raw[..], usize::MAX, |data| body.extend_from_slice(data))?;
+        Ok((body, end))
+    }
+
+    #[test]
+    fn reads_the_rfc_examples() {
+        // The classic example: a chunk holding a CRLF, then a bare last chunk.
+        let raw = b"4\r\nWiki\r\n6\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\n\r\n";
+        let (body, end) = read(raw).unwrap();
+        assert_eq!(body, b"Wikipedia in \r\n\r\nchunks.");
+        assert!(end.trailers.is_empty());
+        assert_eq!(end.framing_bytes + body.len(), raw.len());
+
+        // Extensions on data and last chunks, and a trailer section
+        // announced through `Trailer` (RFC 9110, section 6.6.2).
+        let raw = b"5;name=value\r\nhello\r\n0;final\r\nExpires: Wed, 21 Oct 2015 07:28:00 GMT\r\n\
+                    X-Checksum:  abc123 \r\n\r\nrest";
+        let mut reader = &raw[..];
+        let mut body = Vec::new();
+        let end = read_body(&mut reader, usize::MAX, |data| body.extend_from_slice(data)).unwrap();
+        assert_eq!(body, b"hello");
+        assert_eq!(end.trailer("expires"), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(end.trailer("X-CHECKSUM"), Some("abc123"));
+        assert_eq!(end.trailer("Missing"), None);
+        assert_eq!(reader, b"rest");
+
+        // Only a zero-length last chunk: an empty body.
+        let (body, end) = read(b"0\r\n\r\n").unwrap();
+        assert!(body.is_empty());
+        assert_eq!(end.framing_bytes, 5);
+    }
+
+    #[test]
+    fn chunks_past_the_limit_are_invalid_data() {
+        let raw = b"4\r\nabcd\r\n4\r\nefgh\r\n0\r\n\r\n";
+        let mut body = Vec::new();
+        let end = read_body(&mut &raw[..], 8, |data| body.extend_from_slice(data)).unwrap();
+        assert_eq!((body.as_slice(), end.framing_bytes), (&b"abcdefgh"[..], 15));
+
+        let mut seen = Vec::new();
+        let err = read_body(&mut &raw[..], 7, |data| seen.extend_from_slice(data)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(seen, b"abcd", "the oversized chunk is refused before it is read");
+        let huge = b"ffffffffffffffff\r\nab\r\n0\r\n\r\n";
+        let err = read_body(&mut &huge[..], 1 << 20, |_| ()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn broken_framing_is_invalid_data() {
+        let cases: [&[u8]; 5] = [
+            b"zz\r\nab\r\n0\r\n\r\n",
+            b"2\r\nabXX0\r\n\r\n",
+            b"2\r\nab\r\n",
+            b"2\r\nab\r\n0\r\nX-Trailer: 1\r\n",
+            b"5\r\nab",
+        ];
+        for raw in cases {
+            let err = read(raw).unwrap_err();
+            let expected = [io::ErrorKind::InvalidData, io::ErrorKind::UnexpectedEof];
+            assert!(expected.contains(&err.kind()), "{}: {err}", String::from_utf8_lossy(raw));
+        }
+    }
+
+    #[test]
+    fn long_framing_lines_are_invalid_data() {
+        let long_size = format!("{}1\r\na\r\n0\r\n\r\n", "0".repeat(MAX_LINE_BYTES));
+        let long_trailer = format!("0\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_LINE_BYTES));
+        let many_trailers = format!("0\r\n{}\r\n", "X-Pad: a\r\n".repeat(MAX_HEAD_BYTES / 10 + 1));
+        for raw in [long_size, long_trailer, many_trailers] {
+            let err = read(raw.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{err}");
+        }
+        // A size line with no newline at all is refused at the cap.
+        let mut endless = io::repeat(b'0').take(1 << 20);
+        let err = read_body(&mut io::BufReader::new(&mut endless), usize::MAX, |_| ()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{err}");
+    }
+
+    #[test]
+    fn written_bodies_read_back() {
+        let mut wire = Vec::new();
+        let mut written = write_chunk(&mut wire, b"Wiki").unwrap();
+        written += write_chunk(&mut wire, b"").unwrap();
+        written += write_chunk(&mut wire, &[7u8; 300]).unwrap();
+        written += write_last_chunk(&mut wire, &[("X-Content-Fnv1a", "00ff")]).unwrap();
+        assert_eq!(written, wire.len());
+        assert!(wire.starts_with(b"4\r\nWiki\r\n12c\r\n"));
+        assert!(wire.ends_with(b"\r\n0\r\nX-Content-Fnv1a: 00ff\r\n\r\n"));
+
+        let (body, end) = read(&wire).unwrap();
+        assert_eq!(body.len(), 304);
+        assert_eq!(end.trailer("x-content-fnv1a"), Some("00ff"));
+        assert_eq!(end.framing_bytes + body.len(), wire.len());
+
+        let mut bare = Vec::new();
+        write_last_chunk(&mut bare, &[]).unwrap();
+        assert_eq!(bare, b"0\r\n\r\n");
+    }
+}
@@ -0,0 +1,333 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A workspace member as reported by `cargo metadata --no-deps`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Member {
+    pub(crate) package_id: String,
+    pub(crate) name: String,
+    /// Directory holding the member's `Cargo.toml`.
+    pub(crate) root: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Workspace {
+    pub(crate) root: PathBuf,
+    pub(crate) members: Vec<Member>,
+}
+
+pub(crate) fn parse_metadata(text: &str) -> Result<Workspace, String> {
+    let metadata: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    let root = metadata["workspace_root"]
+        .as_str()
+        .ok_or_else(|| "metadata has no workspace_root".to_string())?;
+    let member_ids: Vec<&str> = metadata["workspace_members"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let members = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            let package_id = package["id"].as_str()?;
+            if !member_ids.contains(&package_id) {
+                return None;
+            }
+            let manifest = Path::new(package["manifest_path"].as_str()?);
+            Some(Member {
+                package_id: package_id.to_string(),
+                name: package["name"].as_str()?.to_string(),
+                root: manifest.parent()?.to_path_buf(),
+            })
+        })
+        .collect();
+    Ok(Workspace {
+        root: PathBuf::from(root),
+        members,
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Findings {
+    pub(crate) high: f64,
+    pub(crate) medium: f64,
+    pub(crate) low: f64,
+}
+
+impl Findings {
+    pub(crate) fn total(&self) -> f64 {
+        self.high + self.medium + self.low
+    }
+
+    fn merge(&mut self, other: Findings) {
+        self.high += other.high;
+        self.medium += other.medium;
+        self.low += other.low;
+    }
+
+    fn add(&mut self, severity: &str) {
+        match severity {
+            "high" => self.high += 1.0,
+            "medium" => self.medium += 1.0,
+            _ => self.low += 1.0,
+        }
+    }
+}
+
+/// Findings by how trustworthy their suggested fix is, using the weakest
+/// `suggestion_applicability` among the diagnostic's suggestion spans.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct FixCounts {
+    /// Applied by `cargo clippy --fix` without review.
+    pub(crate) machine_applicable: f64,
+    pub(crate) maybe_incorrect: f64,
+    pub(crate) has_placeholders: f64,
+    pub(crate) unspecified: f64,
+}
+
+impl FixCounts {
+    fn merge(&mut self, other: FixCounts) {
+        self.machine_applicable += other.machine_applicable;
+        self.maybe_incorrect += other.maybe_incorrect;
+        self.has_placeholders += other.has_placeholders;
+        self.unspecified += other.unspecified;
+    }
+
+    fn add(&mut self, message: &Value) {
+        match weakest_applicability(message) {
+            Some("MachineApplicable") => self.machine_applicable += 1.0,
+            Some("MaybeIncorrect") => self.maybe_incorrect += 1.0,
+            Some("HasPlaceholders") => self.has_placeholders += 1.0,
+            Some(_) => self.unspecified += 1.0,
+            None => {}
+        }
+    }
+}
+
+fn applicability_rank(applicability: &str) -> u8 {
+    match applicability {
+        "MachineApplicable" => 3,
+        "MaybeIncorrect" => 2,
+        "HasPlaceholders" => 1,
+        _ => 0,
+    }
+}
+
+/// Suggestions sit on the spans of the message itself or of its `help`
+/// children; `None` when the diagnostic suggests no replacement at all.
+fn weakest_applicability(message: &Value) -> Option<&str> {
+    let children = message["children"].as_array().into_iter().flatten();
+    std::iter::once(message)
+        .chain(children)
+        .filter_map(|diagnostic| diagnostic["spans"].as_array())
+        .flatten()
+        .filter_map(|span| span["suggestion_applicability"].as_str())
+        .min_by_key(|applicability| applicability_rank(applicability))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct LintReport {
+    pub(crate) totals: Findings,
+    /// Counted findings only, like `totals`.
+    pub(crate) fixes: FixCounts,
+    /// Keyed by member package name.
+    pub(crate) per_crate: BTreeMap<String, Findings>,
+    /// Findings from packages outside the workspace; never counted.
+    pub(crate) excluded: f64,
+    pub(crate) parse_errors: f64,
+}
+
+impl LintReport {
+    pub(crate) fn merge(&mut self, other: LintReport) {
+        self.totals.merge(other.totals);
+        self.fixes.merge(other.fixes);
+        for (name, findings) in other.per_crate {
+            self.per_crate.entry(name).or_default().merge(findings);
+        }
+        self.excluded += other.excluded;
+        self.parse_errors += other.parse_errors;
+    }
+}
+
+fn classify_clippy_finding(code: &str) -> &'static str {
+    let high_patterns = [
+        "unwrap_used",
+        "expect_used",
+        "panic",
+        "todo",
+        "unimplemented",
+        "indexing_slicing",
+    ];
+    if high_patterns.iter().any(|pattern| code.contains(pattern)) {
+        return "high";
+    }
+    let medium_patterns = ["suspicious", "correctness", "perf", "complexity"];
+    if medium_patterns.iter().any(|pattern| code.contains(pattern)) {
+        return "medium";
+    }
+    "low"
+}
+
+/// `package_id` first; `target.src_path` under a member directory (deepest
+/// match wins) covers messages from cargo versions that omit the id.
+fn attribute<'a>(payload: &Value, members: &'a [Member]) -> Option<&'a Member> {
+    if let Some(id) = payload["package_id"].as_str() {
+        if let Some(member) = members.iter().find(|member| member.package_id == id) {
+            return Some(member);
+        }
+    }
+    let src_path = Path::new(payload["target"]["src_path"].as_str()?);
+    members
+        .iter()
+        .filter(|member| src_path.starts_with(&member.root))
+        .max_by_key(|member| member.root.components().count())
+}
+
+/// With no `members`, every finding counts towards the totals only.
+pub(crate) fn parse_clippy_messages<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    members: &[Member],
+) -> LintReport {
+    let mut report = LintReport::default();
+    for member in members {
+        report.per_crate.entry(member.name.clone()).or_default();
+    }
+
+    for line in lines {
+        let trimmed = line.as_ref().trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let Ok(payload) = serde_json::from_str::<Value>(trimmed) else {
+            if trimmed.starts_with('{') {
+                report.parse_errors += 1.0;
+            }
+            continue;
+        };
+        if payload.get("reason").and_then(Value::as_str) != Some("compiler-message") {
+            continue;
+        }
+        let Some(message) = payload.get("message") else {
+            report.parse_errors += 1.0;
+            continue;
+        };
+        if message.get("level").and_then(Value::as_str) != Some("warning") {
+            continue;
+        }
+
+        let code = message
+            .get("code")
+            .and_then(|c| c.get("code"))
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        if !code.is_empty() && !code.starts_with("clippy::") {
+            continue;
+        }
+
+        let severity = classify_clippy_finding(code);
+        if members.is_empty() {
+            report.totals.add(severity);
+            report.fixes.add(message);
+            continue;
+        }
+        match attribute(&payload, members) {
+            Some(member) => {
+                report.totals.add(severity);
+                report.fixes.add(message);
+                report
+                    .per_crate
+                    .entry(member.name.clone())
+                    .or_default()
+                    .add(severity);
+            }
+            None => report.excluded += 1.0,
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn fixture_workspace() -> Workspace {
+        parse_metadata(fixtures::clippy_metadata()).unwrap()
+    }
+
+    #[test]
+    fn metadata_lists_only_workspace_members() {
+        let workspace = fixture_workspace();
+        assert_eq!(workspace.root, PathBuf::from("/work/demo"));
+        let names: Vec<&str> = workspace.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["bench-core", "bench-cli", "bench-http"]);
+        assert_eq!(workspace.members[1].root, PathBuf::from("/work/demo/crates/cli"));
+        assert!(parse_metadata("{}").is_err());
+    }
+
+    #[test]
+    fn attributes_findings_and_drops_dependencies() {
+        let workspace = fixture_workspace();
+        let report = parse_clippy_messages(
+            fixtures::clippy_workspace_messages().lines(),
+            &workspace.members,
+        );
+        assert_eq!(
+            report.per_crate["bench-core"],
+            Findings {
+                high: 1.0,
+                medium: 1.0,
+                low: 0.0
+            }
+        );
+        // Attributed by src_path: that message has no package_id.
+        assert_eq!(report.per_crate["bench-cli"].total(), 2.0);
+        assert_eq!(report.per_crate["bench-http"].total(), 0.0);
+        assert_eq!(report.totals.total(), 4.0);
+        assert_eq!(report.excluded, 2.0);
+        assert_eq!(report.parse_errors, 0.0);
+    }
+
+    #[test]
+    fn counts_fix_applicability_from_suggestion_spans() {
+        let report = parse_clippy_messages(fixtures::clippy_suggestions().lines(), &[]);
+        assert_eq!(report.totals.total(), 14.0);
+        assert_eq!(
+            report.fixes,
+            FixCounts {
+                machine_applicable: 8.0,
+                maybe_incorrect: 2.0,
+                has_placeholders: 2.0,
+                unspecified: 2.0,
+            }
+        );
+    }
+
+    #[test]
+    fn clippy_lines_are_classified() {
+        let lines = [
+            r#"{"reason":"compiler-artifact","target":{"name":"serde"}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","code":{"code":"clippy::unwrap_used"}}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","code":{"code":"clippy::suspicious_else_formatting"}}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","code":{"code":"clippy::needless_return"}}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","code":{"code":"unused_variables"}}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"error","code":{"code":"clippy::panic"}}}"#,
+            r#"{"reason":"compiler-message""#,
+            "    Checking rust-benchmarks v0.1.0",
+        ];
+        let report = parse_clippy_messages(lines, &[]);
+        assert_eq!(
+            report.totals,
+            Findings {
+                high: 1.0,
+                medium: 1.0,
+                low: 1.0
+            }
+        );
+        assert_eq!(report.parse_errors, 1.0);
+        assert!(report.per_crate.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! Wall-clock handling. Every timestamp the harness writes is UTC with an
+//! explicit `Z`, whatever `TZ` or the container's time zone database say,
+//! so records from different runners line up. The local time zone is only
+//! recorded in the environment block, for forensics.
+//!
+//! At startup the clock is checked against the build timestamp embedded by
+//! `build.rs`: a clock behind the binary it runs (a container without an
+//! RTC, a VM restored from a snapshot) makes every timestamp of the run
+//! wrong, so the run summary flags `clock_suspect`.
+
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::build_stamp;
+
+/// RFC 3339 in UTC with millisecond precision: `2026-10-16T08:00:00.000Z`.
+pub(crate) fn utc_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// `utc_timestamp` of the current time.
+pub(crate) fn now() -> String {
+    utc_timestamp(Utc::now())
+}
+
+/// When this binary was built; `None` if the embedded value is unreadable.
+pub(crate) fn build_time() -> Option<DateTime<Utc>> {
+    let seconds = build_stamp::parse(env!("BENCHMARK_BUILD_EPOCH"))?;
+    DateTime::from_timestamp(i64::try_from(seconds).ok()?, 0)
+}
+
+/// A clock reading earlier than the build cannot be right.
+pub(crate) fn clock_suspect(now: DateTime<Utc>, built: Option<DateTime<Utc>>) -> bool {
+    built.is_some_and(|built| now < built)
+}
+
+/// `time_zone` (`null` when nothing names it) and `utc_offset_seconds`,
+/// as the system reports them at `local`.
+pub(crate) fn time_zone_fields(
+    local: DateTime<FixedOffset>,
+    name: Option<String>,
+) -> Map<String, Value> {
+    let mut fields = Map::new();
+    fields.insert("time_zone".to_string(), json!(name));
+    fields.insert(
+        "utc_offset_seconds".to_string(),
+        json!(local.offset().local_minus_utc()),
+    );
+    fields
+}
+
+/// `time_zone_fields` for this system right now.
+pub(crate) fn local_time_zone_fields() -> Map<String, Value> {
+    let name = time_zone_name(
+        env::var("TZ").ok(),
+        fs::read_to_string("/etc/timezone").ok(),
+        fs::read_link("/etc/localtime").ok().as_deref(),
+    );
+    time_zone_fields(Local::now().fixed_offset(), name)
+}
+
+/// `TZ` first (with the optional leading `:`), then `/etc/timezone`, then
+/// the zone `/etc/localtime` links to.
+fn time_zone_name(
+    tz: Option<String>,
+    etc_timezone: Option<String>,
+    localtime: Option<&Path>,
+) -> Option<String> {
+    let from_link = localtime.and_then(|target| {
+        let target = target.to_str()?;
+        let (_, zone) = target.split_once("zoneinfo/")?;
+        Some(zone.to_string())
+    });
+    [tz.map(|tz| tz.trim_start_matches(':').to_string()), etc_timezone]
+        .into_iter()
+        .flatten()
+        .map(|name| name.trim().to_string())
+        .chain(from_link)
+        .find(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn timestamps_are_utc_whatever_the_offset() {
+        let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+        let local = berlin.with_ymd_and_hms(2026, 10, 16, 10, 0, 0).unwrap();
+        assert_eq!(utc_timestamp(local.to_utc()), "2026-10-16T08:00:00.000Z");
+        assert!(now().ends_with('Z'));
+    }
+
+    #[test]
+    fn a_clock_behind_the_build_is_suspect() {
+        let built = Some(at(1_700_000_000));
+        assert!(clock_suspect(at(0), built));
+        assert!(clock_suspect(at(1_699_999_999), built));
+        assert!(!clock_suspect(at(1_700_000_000), built));
+        assert!(!clock_suspect(at(1_800_000_000), built));
+        assert!(!clock_suspect(at(0), None));
+        // This binary was built before this test ran.
+        assert!(build_time().is_some());
+        assert!(!clock_suspect(Utc::now(), build_time()));
+    }
+
+    #[test]
+    fn time_zone_fields_report_the_offset_and_name() {
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let local = tokyo.with_ymd_and_hms(2026, 10, 16, 17, 0, 0).unwrap();
+        let fields = time_zone_fields(local, Some("Asia/Tokyo".to_string()));
+        assert_eq!(fields["time_zone"], "Asia/Tokyo");
+        assert_eq!(fields["utc_offset_seconds"], 32400);
+        let fields = time_zone_fields(at(0).fixed_offset(), None);
+        assert_eq!(fields["time_zone"], Value::Null);
+        assert_eq!(fields["utc_offset_seconds"], 0);
+
+        let link = Path::new("/usr/share/zoneinfo/Europe/Berlin");
+        let name = |tz: Option<&str>, file: Option<&str>, link| {
+            time_zone_name(tz.map(str::to_string), file.map(str::to_string), link)
+        };
+        assert_eq!(name(Some(":UTC"), Some("Etc/UTC\n"), Some(link)).unwrap(), "UTC");
+        assert_eq!(name(Some(""), Some("Etc/UTC\n"), Some(link)).unwrap(), "Etc/UTC");
+        assert_eq!(name(None, None, Some(link)).unwrap(), "Europe/Berlin");
+        assert_eq!(name(None, None, Some(Path::new("/tmp/localtime"))), None);
+    }
+}
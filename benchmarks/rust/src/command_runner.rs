@@ -0,0 +1,186 @@
+//! The scorecards run cargo and rustc through a `CommandRunner` rather than
+//! spawning them directly, so tests can answer each invocation from a
+//! script and reach every error branch without a toolchain installed.
+
+use std::process::Command;
+
+use crate::subprocess::{self, CommandOutput};
+
+pub(crate) trait CommandRunner: Sync {
+    /// Runs `cmd`, handing its stdout lines to `consume` as they arrive.
+    fn stream(
+        &self,
+        cmd: Command,
+        consume: &mut dyn FnMut(&mut dyn Iterator<Item = String>),
+    ) -> CommandOutput;
+
+    /// Runs `cmd` to completion with its stdout collected.
+    fn output(&self, cmd: Command) -> CommandOutput {
+        let mut stdout = String::new();
+        let mut output = self.stream(cmd, &mut |lines| {
+            for line in lines {
+                stdout.push_str(&line);
+                stdout.push('\n');
+            }
+        });
+        output.stdout = stdout;
+        output
+    }
+}
+
+/// `CommandRunner::stream` for a consumer that returns a value.
+pub(crate) fn stream<T: Default>(
+    runner: &dyn CommandRunner,
+    cmd: Command,
+    consume: impl FnOnce(&mut dyn Iterator<Item = String>) -> T,
+) -> (CommandOutput, T) {
+    let mut consume = Some(consume);
+    let mut consumed = T::default();
+    let output = runner.stream(cmd, &mut |lines| {
+        if let Some(consume) = consume.take() {
+            consumed = consume(lines);
+        }
+    });
+    (output, consumed)
+}
+
+/// Spawns real processes through `subprocess`.
+pub(crate) struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn stream(
+        &self,
+        cmd: Command,
+        consume: &mut dyn FnMut(&mut dyn Iterator<Item = String>),
+    ) -> CommandOutput {
+        subprocess::stream_command(cmd, |lines| consume(lines)).0
+    }
+
+    fn output(&self, cmd: Command) -> CommandOutput {
+        subprocess::command_output(cmd)
+    }
+}
+
+/// `cargo <name>` is installed: cargo exits nonzero for unknown subcommands.
+pub(crate) fn has_cargo_subcommand(runner: &dyn CommandRunner, name: &str) -> bool {
+    let mut cmd = Command::new("cargo");
+    cmd.args([name, "--version"]).current_dir(crate::repo_root());
+    runner.output(cmd).code == 0
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::subprocess::CaptureStats;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct Rule {
+        pattern: Vec<String>,
+        code: i32,
+        stdout: String,
+        stderr: String,
+    }
+
+    impl Rule {
+        fn matches(&self, argv: &[String]) -> bool {
+            self.pattern.len() <= argv.len()
+                && self
+                    .pattern
+                    .iter()
+                    .zip(argv)
+                    .all(|(word, arg)| word == "*" || word == arg)
+        }
+    }
+
+    /// Answers each command from the first rule whose pattern matches the
+    /// start of its argv. Commands no rule matches fail the way a program
+    /// that cannot be spawned does, so an empty script has no tools at all.
+    #[derive(Default)]
+    pub(crate) struct ScriptedRunner {
+        rules: Vec<Rule>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedRunner {
+        /// `pattern` is space-separated words, program first; `*` matches
+        /// any one argument.
+        pub(crate) fn on(mut self, pattern: &str, code: i32, stdout: &str, stderr: &str) -> Self {
+            self.rules.push(Rule {
+                pattern: pattern.split_whitespace().map(str::to_string).collect(),
+                code,
+                stdout: stdout.to_string(),
+                stderr: stderr.to_string(),
+            });
+            self
+        }
+
+        /// Every command run so far, as space-joined argv.
+        pub(crate) fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl CommandRunner for ScriptedRunner {
+        fn stream(
+            &self,
+            cmd: Command,
+            consume: &mut dyn FnMut(&mut dyn Iterator<Item = String>),
+        ) -> CommandOutput {
+            let argv: Vec<String> = std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            self.calls.lock().unwrap().push(argv.join(" "));
+            let Some(rule) = self.rules.iter().find(|rule| rule.matches(&argv)) else {
+                consume(&mut std::iter::empty());
+                return CommandOutput {
+                    code: 1,
+                    stdout: String::new(),
+                    capture: CaptureStats::default(),
+                    tool_time: Duration::ZERO,
+                    usage: None,
+                };
+            };
+            consume(&mut rule.stdout.lines().map(str::to_string));
+            CommandOutput {
+                code: rule.code,
+                stdout: String::new(),
+                capture: CaptureStats {
+                    bytes_captured: (rule.stdout.len() + rule.stderr.len()) as u64,
+                    ..CaptureStats::default()
+                },
+                tool_time: Duration::ZERO,
+                usage: None,
+            }
+        }
+    }
+
+    #[test]
+    fn scripted_runner_matches_argv_prefixes() {
+        let runner = ScriptedRunner::default()
+            .on("cargo audit --version", 0, "cargo-audit 0.21.0\n", "")
+            .on("cargo * --version", 101, "", "error: no such command\n")
+            .on("cargo audit", 1, "{\"found\": true}\n", "warning\n");
+        assert!(has_cargo_subcommand(&runner, "audit"));
+        assert!(!has_cargo_subcommand(&runner, "outdated"));
+
+        let mut cmd = Command::new("cargo");
+        cmd.args(["audit", "--json"]);
+        let output = runner.output(cmd);
+        assert_eq!((output.code, output.stdout.as_str()), (1, "{\"found\": true}\n"));
+        assert_eq!(output.capture.bytes_captured, 24);
+
+        let (output, lines) = stream(&runner, Command::new("rustc"), |lines| lines.count());
+        assert_eq!((output.code, lines), (1, 0));
+        assert_eq!(
+            runner.calls(),
+            [
+                "cargo audit --version",
+                "cargo outdated --version",
+                "cargo audit --json",
+                "rustc"
+            ]
+        );
+    }
+}
@@ -0,0 +1,1432 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benchmark_ids::{
+    self, CONCURRENCY_ASYNC_TASKS, CPU_MONTE_CARLO_PI, CPU_SLEEP_PRECISION, DATA_PIPELINE_ETL_ASYNC,
+    DATA_PIPELINE_ETL_MINIBATCH, DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    IO_CONCURRENT_HTTP_CLIENT, IO_CONCURRENT_HTTP_POST, IO_HTTP_CONNECTION_CHURN,
+    IO_HTTP_JITTER_SENSITIVITY, IO_HTTP_STREAMING, STATIC_SECURITY_LINT_BENCHMARK,
+    STRING_JSON_PARSE_TRANSFORM, TEST_ROBUSTNESS_RELIABILITY,
+};
+use crate::cpus;
+use crate::deprecations::{self, Notice};
+use crate::env_usize;
+use crate::etl_async::{DEFAULT_CHUNK_KB, DEFAULT_READ_AHEAD_DEPTH};
+use crate::http_client::{ConnectionMode, RequestLimits, DEFAULT_TIMEOUT};
+use crate::line_reader::DEFAULT_MAX_LINE_BYTES;
+use crate::mock_server::ChaosConfig;
+use crate::sampling::LatencyDistribution;
+use crate::timeline;
+
+const PARAMS_PREFIX: &str = "BENCHMARK_PARAMS_";
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MonteCarloConfig {
+    pub(crate) samples: usize,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        MonteCarloConfig { samples: 200_000 }
+    }
+}
+
+impl MonteCarloConfig {
+    fn with_env(self) -> Self {
+        MonteCarloConfig {
+            samples: env_usize("BENCHMARK_PI_SAMPLES", self.samples),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SleepPrecisionConfig {
+    pub(crate) durations_ms: Vec<u64>,
+    /// Measured samples per duration, after warmup.
+    pub(crate) samples: usize,
+    /// Leading samples per duration that are discarded.
+    pub(crate) warmup: usize,
+}
+
+impl Default for SleepPrecisionConfig {
+    fn default() -> Self {
+        SleepPrecisionConfig {
+            durations_ms: vec![1, 5, 10],
+            samples: 200,
+            warmup: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct JsonTransformConfig {
+    pub(crate) records: usize,
+}
+
+impl Default for JsonTransformConfig {
+    fn default() -> Self {
+        JsonTransformConfig { records: 20_000 }
+    }
+}
+
+/// Which server the HTTP benchmarks send their requests to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum HttpServer {
+    /// Whatever listens at `base_url`, normally the Python server.
+    #[default]
+    External,
+    /// A server started in-process for the run (`--serve`), answering
+    /// `/item/{id}` like the Python one; `base_url` is not used.
+    Embedded,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct HttpClientConfig {
+    /// One URL, or for `io_concurrent_http_client` a comma-separated list
+    /// of targets run one after another.
+    pub(crate) base_url: String,
+    /// `request_count` in records and overrides, as the Python runner names
+    /// it; overrides may still say `requests` (see `RENAMED_KEYS`).
+    #[serde(rename = "request_count")]
+    pub(crate) requests: usize,
+    pub(crate) rows: usize,
+    pub(crate) concurrency: usize,
+    /// `io_concurrent_http_client` only: concurrency levels run one after
+    /// another, each with all `requests`, in place of `concurrency`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) concurrency_sweep: Vec<usize>,
+    /// Open loop: request starts paced to this many per second across all
+    /// workers. 0, the default, is closed loop: each worker sends its next
+    /// request as soon as the last one is answered.
+    #[serde(skip_serializing_if = "is_closed_loop")]
+    pub(crate) target_rps: f64,
+    /// One keep-alive connection per worker; `false` opens a connection per
+    /// request. The churn benchmark runs both modes regardless.
+    pub(crate) keep_alive: bool,
+    /// Per connect, read and write, before the request counts as a timeout.
+    pub(crate) timeout_ms: u64,
+    /// Wall-clock budget for the whole workload; requests not started
+    /// within it are skipped. 0 means no budget.
+    pub(crate) budget_seconds: f64,
+    /// How long to wait for the server to answer before giving up on the
+    /// run; the wait is not part of its timing.
+    pub(crate) ready_timeout_seconds: f64,
+    /// Equal time slices of the run that completions and errors are counted
+    /// in, for `timeline_completed` and `timeline_errors`.
+    pub(crate) timeline_buckets: usize,
+    pub(crate) server: HttpServer,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        HttpClientConfig {
+            base_url: "http://127.0.0.1:8000".to_string(),
+            requests: 400,
+            rows: 1000,
+            concurrency: 16,
+            concurrency_sweep: Vec::new(),
+            target_rps: 0.0,
+            keep_alive: true,
+            timeout_ms: 5000,
+            budget_seconds: 300.0,
+            ready_timeout_seconds: 30.0,
+            timeline_buckets: timeline::DEFAULT_BUCKETS,
+            server: HttpServer::External,
+        }
+    }
+}
+
+impl HttpClientConfig {
+    fn with_env(self) -> Result<Self, String> {
+        let concurrency_sweep = match env::var(CONCURRENCY_SWEEP_VAR) {
+            Ok(levels) => parse_levels(&levels)
+                .map_err(|err| format!("{CONCURRENCY_SWEEP_VAR}: {err}"))?,
+            Err(_) => self.concurrency_sweep,
+        };
+        Ok(HttpClientConfig {
+            base_url: env::var("BENCHMARK_HTTP_BASE_URL").unwrap_or(self.base_url),
+            requests: env_usize("BENCHMARK_HTTP_REQUESTS", self.requests),
+            rows: env_usize("BENCHMARK_HTTP_ROWS", self.rows),
+            concurrency: env_usize("BENCHMARK_HTTP_CONCURRENCY", self.concurrency),
+            concurrency_sweep,
+            target_rps: env::var("BENCHMARK_HTTP_TARGET_RPS")
+                .ok()
+                .and_then(|rps| rps.trim().parse::<f64>().ok())
+                .unwrap_or(self.target_rps),
+            keep_alive: env_flag("BENCHMARK_HTTP_KEEPALIVE", self.keep_alive),
+            timeout_ms: env::var("BENCHMARK_HTTP_TIMEOUT_MS")
+                .ok()
+                .and_then(|ms| ms.trim().parse::<u64>().ok())
+                .filter(|ms| *ms > 0)
+                .unwrap_or(self.timeout_ms),
+            budget_seconds: env::var("BENCHMARK_HTTP_BUDGET_SECONDS")
+                .ok()
+                .and_then(|seconds| seconds.trim().parse::<f64>().ok())
+                .unwrap_or(self.budget_seconds),
+            ready_timeout_seconds: env::var("BENCHMARK_HTTP_READY_TIMEOUT")
+                .ok()
+                .and_then(|seconds| seconds.trim().parse::<f64>().ok())
+                .unwrap_or(self.ready_timeout_seconds),
+            timeline_buckets: self.timeline_buckets,
+            server: self.server,
+        })
+    }
+
+    /// The targets in `base_url`, in order; a list with no entries is kept
+    /// whole so the base URL check reports it. An embedded server is the
+    /// only target.
+    pub(crate) fn targets(&self) -> Vec<&str> {
+        if self.server == HttpServer::Embedded {
+            return vec![self.base_url.as_str()];
+        }
+        let targets: Vec<&str> = self
+            .base_url
+            .split(',')
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+            .collect();
+        if targets.is_empty() {
+            vec![self.base_url.as_str()]
+        } else {
+            targets
+        }
+    }
+
+    pub(crate) fn connection_mode(&self) -> ConnectionMode {
+        if self.keep_alive {
+            ConnectionMode::Persistent
+        } else {
+            ConnectionMode::PerRequest
+        }
+    }
+
+    pub(crate) fn request_limits(&self) -> RequestLimits {
+        RequestLimits {
+            timeout: request_timeout(self.timeout_ms),
+            budget: Duration::try_from_secs_f64(self.budget_seconds)
+                .ok()
+                .filter(|budget| !budget.is_zero()),
+            pace: if is_closed_loop(&self.target_rps) {
+                None
+            } else {
+                Duration::try_from_secs_f64(1.0 / self.target_rps).ok()
+            },
+        }
+    }
+
+    pub(crate) fn ready_timeout(&self) -> Duration {
+        ready_timeout(self.ready_timeout_seconds)
+    }
+
+    /// One config per concurrency level of the sweep, or this one alone
+    /// when there is no sweep.
+    pub(crate) fn sweep(&self) -> Vec<Self> {
+        if self.concurrency_sweep.is_empty() {
+            return vec![self.clone()];
+        }
+        self.concurrency_sweep
+            .iter()
+            .map(|&concurrency| HttpClientConfig {
+                concurrency,
+                concurrency_sweep: Vec::new(),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// This workload against `target` alone.
+    pub(crate) fn for_target(&self, target: &str) -> Self {
+        HttpClientConfig {
+            base_url: target.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A `target_rps` that is not a positive rate.
+fn is_closed_loop(target_rps: &f64) -> bool {
+    !target_rps.is_finite() || *target_rps <= 0.0
+}
+
+/// A `timeout_ms` as a duration. Zero would mean no timeout at all, so it
+/// keeps the default.
+fn request_timeout(ms: u64) -> Duration {
+    match ms {
+        0 => DEFAULT_TIMEOUT,
+        ms => Duration::from_millis(ms),
+    }
+}
+
+/// A `ready_timeout_seconds` as a duration. A negative or non-finite one
+/// probes once without waiting.
+fn ready_timeout(seconds: f64) -> Duration {
+    Duration::try_from_secs_f64(seconds).unwrap_or(Duration::ZERO)
+}
+
+const CONCURRENCY_SWEEP_VAR: &str = "BENCHMARK_HTTP_CONCURRENCY_SWEEP";
+
+/// `1,2,4,8`: the levels in order; empty entries are ignored. Any other
+/// entry that is not a positive integer is an error, since running the
+/// remaining levels would look like the sweep that was asked for.
+fn parse_levels(levels: &str) -> Result<Vec<usize>, String> {
+    levels
+        .split(',')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .map(|level| {
+            level
+                .parse::<usize>()
+                .ok()
+                .filter(|level| *level > 0)
+                .ok_or_else(|| format!("invalid level {level:?}; expected positive integers"))
+        })
+        .collect()
+}
+
+/// `io_http_streaming`: one chunked upload to `/upload` and one chunked
+/// download from `/stream/{bytes}`, each of `megabytes` MiB.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct HttpStreamingConfig {
+    pub(crate) base_url: String,
+    pub(crate) megabytes: usize,
+    /// Data bytes per upload chunk, in KiB.
+    pub(crate) chunk_kb: usize,
+    pub(crate) timeout_ms: u64,
+    pub(crate) ready_timeout_seconds: f64,
+    pub(crate) server: HttpServer,
+}
+
+impl Default for HttpStreamingConfig {
+    fn default() -> Self {
+        HttpStreamingConfig {
+            base_url: "http://127.0.0.1:8000".to_string(),
+            megabytes: 64,
+            chunk_kb: 64,
+            timeout_ms: 5000,
+            ready_timeout_seconds: 30.0,
+            server: HttpServer::External,
+        }
+    }
+}
+
+impl HttpStreamingConfig {
+    fn with_env(self) -> Self {
+        HttpStreamingConfig {
+            base_url: env::var("BENCHMARK_HTTP_BASE_URL").unwrap_or(self.base_url),
+            megabytes: env_usize("BENCHMARK_HTTP_STREAMING_MB", self.megabytes),
+            ..self
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> u64 {
+        self.megabytes as u64 * 1024 * 1024
+    }
+
+    pub(crate) fn timeout(&self) -> Duration {
+        request_timeout(self.timeout_ms)
+    }
+
+    pub(crate) fn ready_timeout(&self) -> Duration {
+        ready_timeout(self.ready_timeout_seconds)
+    }
+}
+
+/// Runs the standard per-request client against two in-process mock
+/// servers that differ only in their chaos settings.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct JitterSensitivityConfig {
+    #[serde(rename = "request_count")]
+    pub(crate) requests: usize,
+    pub(crate) rows: usize,
+    pub(crate) concurrency: usize,
+    pub(crate) low_jitter: ChaosConfig,
+    pub(crate) high_jitter: ChaosConfig,
+}
+
+impl Default for JitterSensitivityConfig {
+    fn default() -> Self {
+        JitterSensitivityConfig {
+            requests: 200,
+            rows: 1000,
+            concurrency: 8,
+            low_jitter: ChaosConfig {
+                latency: LatencyDistribution::Uniform {
+                    min_ms: 0.9,
+                    max_ms: 1.1,
+                },
+                error_rate: 0.0,
+                ..ChaosConfig::default()
+            },
+            high_jitter: ChaosConfig {
+                latency: LatencyDistribution::Pareto {
+                    scale_ms: 0.5,
+                    shape: 1.2,
+                    cap_ms: 50.0,
+                },
+                error_rate: 0.0,
+                ..ChaosConfig::default()
+            },
+        }
+    }
+}
+
+/// How the ETL benchmark's timed pass reads the dataset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum InputMode {
+    /// Streams the gzip file through a buffered decoder.
+    #[default]
+    Gzip,
+    /// Reads a decompressed copy through a buffered reader.
+    Buffered,
+    /// Maps a decompressed copy and parses straight from the mapping.
+    Mmap,
+}
+
+fn is_gzip(mode: &InputMode) -> bool {
+    *mode == InputMode::Gzip
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct EtlConfig {
+    /// Relative paths resolve against the data dir (`BENCHMARK_DATA_DIR`).
+    pub(crate) dataset: String,
+    /// Rows generated when the dataset does not exist yet.
+    pub(crate) rows: usize,
+    /// Time the phases of one row in every this many; 0 turns the phase
+    /// breakdown off.
+    pub(crate) phase_sample_every: usize,
+    /// Longer lines are skipped and counted rather than buffered.
+    pub(crate) max_line_bytes: usize,
+    /// Fail the run (as an I/O error) at the first oversized line instead
+    /// of skipping it.
+    pub(crate) strict_line_length: bool,
+    /// KiB for the line reader and the gzip stream buffers alike; unset
+    /// keeps the library defaults. From `BENCHMARK_IO_BUFFER_KB`.
+    pub(crate) io_buffer_kb: Option<usize>,
+    /// Also time the read pass at each sweep buffer size, in the same
+    /// record; set by `BENCHMARK_IO_BUFFER_SWEEP=1`.
+    pub(crate) buffer_sweep: bool,
+    /// Back the parse-comparison buffer with `madvise(MADV_HUGEPAGE)`
+    /// memory; set by `BENCHMARK_MADV_HUGEPAGE=1`.
+    pub(crate) madvise_hugepage: bool,
+    /// How long to wait for another harness that is generating the same
+    /// dataset before giving up on it.
+    pub(crate) dataset_lock_timeout_seconds: f64,
+    /// `buffered` and `mmap` decompress the dataset to a scratch file
+    /// first, outside the timing.
+    #[serde(skip_serializing_if = "is_gzip")]
+    pub(crate) input_mode: InputMode,
+}
+
+impl Default for EtlConfig {
+    fn default() -> Self {
+        EtlConfig {
+            dataset: "benchmarks/shared/datasets/etl_input.jsonl.gz".to_string(),
+            rows: 20_000,
+            phase_sample_every: 64,
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+            strict_line_length: false,
+            io_buffer_kb: None,
+            buffer_sweep: false,
+            madvise_hugepage: false,
+            dataset_lock_timeout_seconds: 300.0,
+            input_mode: InputMode::Gzip,
+        }
+    }
+}
+
+impl EtlConfig {
+    fn with_env(self) -> Self {
+        EtlConfig {
+            dataset: env::var("BENCHMARK_ETL_DATASET").unwrap_or(self.dataset),
+            io_buffer_kb: match env::var("BENCHMARK_IO_BUFFER_KB") {
+                Ok(kb) => kb.parse::<usize>().ok().filter(|kb| *kb > 0),
+                Err(_) => self.io_buffer_kb,
+            },
+            buffer_sweep: env_flag("BENCHMARK_IO_BUFFER_SWEEP", self.buffer_sweep),
+            madvise_hugepage: env_flag("BENCHMARK_MADV_HUGEPAGE", self.madvise_hugepage),
+            ..self
+        }
+    }
+
+    pub(crate) fn dataset_lock_timeout(&self) -> Duration {
+        Duration::try_from_secs_f64(self.dataset_lock_timeout_seconds).unwrap_or_default()
+    }
+}
+
+/// `data_pipeline_etl_async` reads the `EtlConfig` dataset with these
+/// settings.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct EtlAsyncConfig {
+    /// Opt-in; set by `BENCHMARK_ETL_ASYNC=1`.
+    pub(crate) enabled: bool,
+    /// Chunk reads kept in flight ahead of the parser.
+    pub(crate) read_ahead_depth: usize,
+    /// KiB per chunk read.
+    pub(crate) chunk_kb: usize,
+}
+
+impl Default for EtlAsyncConfig {
+    fn default() -> Self {
+        EtlAsyncConfig {
+            enabled: false,
+            read_ahead_depth: DEFAULT_READ_AHEAD_DEPTH,
+            chunk_kb: DEFAULT_CHUNK_KB,
+        }
+    }
+}
+
+/// `concurrency_async_tasks` workload sizes; see `async_tasks.rs`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AsyncTasksConfig {
+    /// Trivial tasks spawned and awaited.
+    pub(crate) tasks: usize,
+    /// Ping-pong round trips over the channel pair.
+    pub(crate) round_trips: usize,
+    /// Fan-in senders, each sending `messages_per_producer` messages.
+    pub(crate) producers: usize,
+    pub(crate) messages_per_producer: usize,
+    /// Bound of the fan-in channel.
+    pub(crate) channel_capacity: usize,
+    /// Runtime worker threads; 0 means one per CPU (`BENCHMARK_CPUS`).
+    pub(crate) worker_threads: usize,
+}
+
+impl Default for AsyncTasksConfig {
+    fn default() -> Self {
+        AsyncTasksConfig {
+            tasks: 1_000_000,
+            round_trips: 10_000,
+            producers: 64,
+            messages_per_producer: 10_000,
+            channel_capacity: 1024,
+            worker_threads: 0,
+        }
+    }
+}
+
+impl AsyncTasksConfig {
+    fn with_env(self) -> Self {
+        AsyncTasksConfig {
+            worker_threads: env_usize("BENCHMARK_ASYNC_WORKER_THREADS", self.worker_threads),
+            ..self
+        }
+    }
+
+    /// The configured count, else the effective CPU count.
+    pub(crate) fn worker_threads(&self) -> usize {
+        match self.worker_threads {
+            0 => cpus::cpu_count().effective,
+            threads => threads,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DependencyScanConfig {
+    /// Advisory database passed to `cargo audit --db`; the cargo home's
+    /// default clone when unset.
+    pub(crate) advisory_db_path: Option<String>,
+    /// Older databases get an `advisory_db_stale` warning.
+    pub(crate) advisory_db_max_age_days: f64,
+    /// RustSec advisories in OSV JSON (a file, or a directory of them) for
+    /// the built-in scanner used when `cargo audit` is missing; relative to
+    /// the repository root. From `BENCHMARK_ADVISORY_DB`. No snapshot ships
+    /// with the repository, so without one that scan is skipped.
+    pub(crate) osv_snapshot: Option<String>,
+}
+
+impl Default for DependencyScanConfig {
+    fn default() -> Self {
+        DependencyScanConfig {
+            advisory_db_path: None,
+            advisory_db_max_age_days: 7.0,
+            osv_snapshot: None,
+        }
+    }
+}
+
+impl DependencyScanConfig {
+    fn with_env(self) -> Self {
+        DependencyScanConfig {
+            osv_snapshot: env::var("BENCHMARK_ADVISORY_DB").ok().or(self.osv_snapshot),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct StaticLintConfig {
+    /// Lint groups passed to clippy as `-W <lint>`.
+    pub(crate) lints: Vec<String>,
+    /// Manifests to lint, relative to the repository root; each one's whole
+    /// workspace is scanned once.
+    pub(crate) manifests: Vec<String>,
+    /// Apply `cargo clippy --fix` to a scratch copy and count the findings
+    /// that disappear; set by `BENCHMARK_VERIFY_FIXES=1`.
+    pub(crate) verify_fixes: bool,
+}
+
+impl Default for StaticLintConfig {
+    fn default() -> Self {
+        StaticLintConfig {
+            lints: vec![
+                "clippy::suspicious".to_string(),
+                "clippy::correctness".to_string(),
+            ],
+            manifests: vec!["benchmarks/rust/Cargo.toml".to_string()],
+            verify_fixes: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct TestReliabilityConfig {
+    pub(crate) iterations: usize,
+}
+
+impl Default for TestReliabilityConfig {
+    fn default() -> Self {
+        TestReliabilityConfig { iterations: 3 }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BenchmarkConfigs {
+    pub(crate) monte_carlo: MonteCarloConfig,
+    pub(crate) sleep_precision: SleepPrecisionConfig,
+    pub(crate) json_transform: JsonTransformConfig,
+    pub(crate) http_client: HttpClientConfig,
+    pub(crate) http_post: HttpClientConfig,
+    pub(crate) http_churn: HttpClientConfig,
+    pub(crate) http_jitter: JitterSensitivityConfig,
+    pub(crate) http_streaming: HttpStreamingConfig,
+    pub(crate) etl: EtlConfig,
+    pub(crate) etl_async: EtlAsyncConfig,
+    pub(crate) async_tasks: AsyncTasksConfig,
+    pub(crate) dependency_scan: DependencyScanConfig,
+    pub(crate) static_lint: StaticLintConfig,
+    pub(crate) test_reliability: TestReliabilityConfig,
+    /// Deprecated env var names the resolution read.
+    pub(crate) deprecations: Vec<Notice>,
+}
+
+const CONFIGURABLE_BENCHMARKS: [&str; 14] = [
+    CPU_MONTE_CARLO_PI,
+    CPU_SLEEP_PRECISION,
+    STRING_JSON_PARSE_TRANSFORM,
+    IO_CONCURRENT_HTTP_CLIENT,
+    IO_CONCURRENT_HTTP_POST,
+    IO_HTTP_CONNECTION_CHURN,
+    IO_HTTP_JITTER_SENSITIVITY,
+    IO_HTTP_STREAMING,
+    DATA_PIPELINE_ETL_MINIBATCH,
+    DATA_PIPELINE_ETL_ASYNC,
+    CONCURRENCY_ASYNC_TASKS,
+    DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    STATIC_SECURITY_LINT_BENCHMARK,
+    TEST_ROBUSTNESS_RELIABILITY,
+];
+
+/// Whether `benchmark_id` takes `BENCHMARK_PARAMS_<ID>` overrides.
+pub(crate) fn is_configurable(benchmark_id: &str) -> bool {
+    CONFIGURABLE_BENCHMARKS.contains(&benchmark_id)
+}
+
+pub(crate) fn params_env_var(benchmark_id: &str) -> String {
+    format!("{PARAMS_PREFIX}{}", benchmark_id.to_ascii_uppercase())
+}
+
+/// An on/off variable: `1` is on, any other value off, unset keeps `base`.
+fn env_flag(name: &str, base: bool) -> bool {
+    env::var(name).map_or(base, |value| value == "1")
+}
+
+/// A `--config` TOML file: one table per configurable benchmark, with the
+/// keys of its `BENCHMARK_PARAMS_<ID>` object.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ConfigFile {
+    path: String,
+    tables: Map<String, Value>,
+}
+
+impl ConfigFile {
+    /// A missing, unreadable or malformed file is an error naming `path`.
+    pub(crate) fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("--config {}: {err}", path.display()))?;
+        ConfigFile::parse(&path.display().to_string(), &text)
+    }
+
+    fn parse(path: &str, text: &str) -> Result<Self, String> {
+        let table: toml::Table =
+            toml::from_str(text).map_err(|err| format!("--config {path}: {}", err.message()))?;
+        let Ok(Value::Object(tables)) = serde_json::to_value(table) else {
+            return Err(format!("--config {path}: not a table"));
+        };
+        for (id, value) in &tables {
+            if !is_configurable(id) {
+                let hint = benchmark_ids::suggest(id)
+                    .filter(|close| is_configurable(close))
+                    .map(|close| format!(" (did you mean {close}?)"))
+                    .unwrap_or_default();
+                return Err(format!(
+                    "--config {path}: no configurable benchmark named {id}{hint}"
+                ));
+            }
+            if !value.is_object() {
+                return Err(format!("--config {path}: `{id}` must be a table"));
+            }
+        }
+        Ok(ConfigFile {
+            path: path.to_string(),
+            tables,
+        })
+    }
+
+    /// `base` with this file's `[benchmark_id]` table applied.
+    fn apply<T: Serialize + DeserializeOwned>(
+        &self,
+        benchmark_id: &str,
+        base: T,
+    ) -> Result<T, String> {
+        match self.tables.get(benchmark_id) {
+            Some(Value::Object(table)) => {
+                let source = format!("--config {} [{benchmark_id}]", self.path);
+                overlay(base, &source, table.clone())
+            }
+            _ => Ok(base),
+        }
+    }
+}
+
+/// Overlays the JSON object in `raw` onto `base`. Keys not present in the
+/// config and values of the wrong type are rejected with the key named.
+pub(crate) fn apply_overrides<T: Serialize + DeserializeOwned>(
+    base: T,
+    benchmark_id: &str,
+    raw: &str,
+) -> Result<T, String> {
+    let source = params_env_var(benchmark_id);
+    let overrides: Map<String, Value> = match serde_json::from_str(raw) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) => return Err(format!("{source}: expected a JSON object")),
+        Err(err) => return Err(format!("{source}: invalid JSON: {err}")),
+    };
+    overlay(base, &source, overrides)
+}
+
+/// Override keys that were renamed, old name first. The old name is still
+/// read wherever the config has the new one.
+const RENAMED_KEYS: [(&str, &str); 1] = [("requests", "request_count")];
+
+/// Overlays `overrides` onto `base`; errors are prefixed with `source`.
+fn overlay<T: Serialize + DeserializeOwned>(
+    base: T,
+    source: &str,
+    overrides: Map<String, Value>,
+) -> Result<T, String> {
+    let Ok(Value::Object(base_map)) = serde_json::to_value(&base) else {
+        return Err(format!("{source}: config is not an object"));
+    };
+
+    let mut merged = base_map.clone();
+    for (key, value) in overrides {
+        let key = match RENAMED_KEYS.iter().find(|(old, _)| *old == key) {
+            Some((_, new)) if base_map.contains_key(*new) => new.to_string(),
+            _ => key,
+        };
+        let mut single = base_map.clone();
+        single.insert(key.clone(), value.clone());
+        if let Err(err) = serde_json::from_value::<T>(Value::Object(single)) {
+            // Fields left out of the serialized default while unset, such as
+            // a concurrency sweep, are only known once deserialized.
+            let unknown = err.to_string().starts_with("unknown field");
+            if unknown && !base_map.contains_key(&key) {
+                let known: Vec<&str> = base_map.keys().map(String::as_str).collect();
+                return Err(format!(
+                    "{source}: unknown key `{key}` (expected one of: {})",
+                    known.join(", ")
+                ));
+            }
+            return Err(format!("{source}: invalid value for `{key}`: {err}"));
+        }
+        merged.insert(key, value);
+    }
+    serde_json::from_value(Value::Object(merged)).map_err(|err| format!("{source}: {err}"))
+}
+
+/// Rejects a `BENCHMARK_PARAMS_*` name that matches no configurable
+/// benchmark, suggesting the closest registered one.
+fn check_params_var(name: &str) -> Result<(), String> {
+    let Some(suffix) = name.strip_prefix(PARAMS_PREFIX) else {
+        return Ok(());
+    };
+    if CONFIGURABLE_BENCHMARKS
+        .iter()
+        .any(|id| id.eq_ignore_ascii_case(suffix))
+    {
+        return Ok(());
+    }
+    let hint = benchmark_ids::suggest(&suffix.to_ascii_lowercase())
+        .filter(|close| CONFIGURABLE_BENCHMARKS.contains(close))
+        .map(|close| format!(" (did you mean {}?)", params_env_var(close)))
+        .unwrap_or_default();
+    Err(format!(
+        "{name}: no configurable benchmark named {suffix}{hint}"
+    ))
+}
+
+fn load<T: Serialize + DeserializeOwned>(benchmark_id: &str, base: T) -> Result<T, String> {
+    match env::var(params_env_var(benchmark_id)) {
+        Ok(raw) => apply_overrides(base, benchmark_id, &raw),
+        Err(_) => Ok(base),
+    }
+}
+
+/// `load` for benchmarks that no flat env var configures.
+fn load_with_file<T: Serialize + DeserializeOwned>(
+    file: &ConfigFile,
+    benchmark_id: &str,
+    base: T,
+) -> Result<T, String> {
+    load(benchmark_id, file.apply(benchmark_id, base)?)
+}
+
+impl BenchmarkConfigs {
+    /// Defaults, then the `--config` file's tables, then flat `BENCHMARK_*`
+    /// env vars, then `BENCHMARK_PARAMS_<ID>` JSON objects, each overriding
+    /// individual fields of the one before.
+    pub(crate) fn from_env(file: &ConfigFile) -> Result<Self, String> {
+        for (name, _) in env::vars() {
+            check_params_var(&name)?;
+        }
+        let mut deprecations = Vec::new();
+        let test_iterations = deprecations::env_var("BENCHMARK_TEST_ITERATIONS", &mut deprecations)
+            .and_then(|iterations| iterations.parse::<usize>().ok());
+
+        let http_file = file.apply(IO_CONCURRENT_HTTP_CLIENT, HttpClientConfig::default())?;
+        let http = http_file.clone().with_env()?;
+        if !http.concurrency_sweep.is_empty() && env::var("BENCHMARK_HTTP_CONCURRENCY").is_ok() {
+            eprintln!(
+                "warning: {CONCURRENCY_SWEEP_VAR} is set; BENCHMARK_HTTP_CONCURRENCY is ignored"
+            );
+        }
+        // Only the concurrent client runs per target and sweeps; POST and
+        // churn use the first target at `concurrency`.
+        let single = |config: HttpClientConfig| HttpClientConfig {
+            concurrency_sweep: Vec::new(),
+            ..config.for_target(config.targets()[0])
+        };
+        let post = single(file.apply(IO_CONCURRENT_HTTP_POST, http_file.clone())?.with_env()?);
+        // Churn times connection setup back to back; pacing would hide it.
+        let churn = HttpClientConfig {
+            target_rps: 0.0,
+            ..single(file.apply(IO_HTTP_CONNECTION_CHURN, http_file)?.with_env()?)
+        };
+        let etl_async = file.apply(DATA_PIPELINE_ETL_ASYNC, EtlAsyncConfig::default())?;
+        let static_lint =
+            file.apply(STATIC_SECURITY_LINT_BENCHMARK, StaticLintConfig::default())?;
+        let test_reliability = file.apply(
+            TEST_ROBUSTNESS_RELIABILITY,
+            TestReliabilityConfig::default(),
+        )?;
+        Ok(BenchmarkConfigs {
+            monte_carlo: load(
+                CPU_MONTE_CARLO_PI,
+                file.apply(CPU_MONTE_CARLO_PI, MonteCarloConfig::default())?.with_env(),
+            )?,
+            sleep_precision: load_with_file(
+                file,
+                CPU_SLEEP_PRECISION,
+                SleepPrecisionConfig::default(),
+            )?,
+            json_transform: load_with_file(
+                file,
+                STRING_JSON_PARSE_TRANSFORM,
+                JsonTransformConfig::default(),
+            )?,
+            http_client: load(IO_CONCURRENT_HTTP_CLIENT, http)?,
+            http_post: load(IO_CONCURRENT_HTTP_POST, post)?,
+            http_churn: load(IO_HTTP_CONNECTION_CHURN, churn)?,
+            http_jitter: load_with_file(
+                file,
+                IO_HTTP_JITTER_SENSITIVITY,
+                JitterSensitivityConfig::default(),
+            )?,
+            http_streaming: load(
+                IO_HTTP_STREAMING,
+                file.apply(IO_HTTP_STREAMING, HttpStreamingConfig::default())?
+                    .with_env(),
+            )?,
+            etl: load(
+                DATA_PIPELINE_ETL_MINIBATCH,
+                file.apply(DATA_PIPELINE_ETL_MINIBATCH, EtlConfig::default())?
+                    .with_env(),
+            )?,
+            etl_async: load(
+                DATA_PIPELINE_ETL_ASYNC,
+                EtlAsyncConfig {
+                    enabled: env_flag("BENCHMARK_ETL_ASYNC", etl_async.enabled),
+                    ..etl_async
+                },
+            )?,
+            async_tasks: load(
+                CONCURRENCY_ASYNC_TASKS,
+                file.apply(CONCURRENCY_ASYNC_TASKS, AsyncTasksConfig::default())?
+                    .with_env(),
+            )?,
+            dependency_scan: load(
+                DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+                file.apply(
+                    DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+                    DependencyScanConfig::default(),
+                )?
+                .with_env(),
+            )?,
+            static_lint: load(
+                STATIC_SECURITY_LINT_BENCHMARK,
+                StaticLintConfig {
+                    verify_fixes: env_flag("BENCHMARK_VERIFY_FIXES", static_lint.verify_fixes),
+                    ..static_lint
+                },
+            )?,
+            test_reliability: load(
+                TEST_ROBUSTNESS_RELIABILITY,
+                TestReliabilityConfig {
+                    iterations: test_iterations.unwrap_or(test_reliability.iterations),
+                },
+            )?,
+            deprecations,
+        })
+    }
+
+    /// `--serve`: the HTTP client benchmarks talk to an embedded server.
+    pub(crate) fn with_embedded_server(self) -> Self {
+        let embedded = |config: HttpClientConfig| HttpClientConfig {
+            server: HttpServer::Embedded,
+            ..config
+        };
+        BenchmarkConfigs {
+            http_client: embedded(self.http_client),
+            http_post: embedded(self.http_post),
+            http_churn: embedded(self.http_churn),
+            http_streaming: HttpStreamingConfig {
+                server: HttpServer::Embedded,
+                ..self.http_streaming
+            },
+            ..self
+        }
+    }
+
+    /// A copy with `overrides` applied to `benchmark_id`'s config, validated
+    /// like a `BENCHMARK_PARAMS_<ID>` object.
+    pub(crate) fn with_overrides(
+        &self,
+        benchmark_id: &str,
+        overrides: &Map<String, Value>,
+    ) -> Result<Self, String> {
+        let raw = Value::Object(overrides.clone()).to_string();
+        let mut configs = self.clone();
+        match benchmark_id {
+            CPU_MONTE_CARLO_PI => {
+                configs.monte_carlo = apply_overrides(configs.monte_carlo, benchmark_id, &raw)?
+            }
+            CPU_SLEEP_PRECISION => {
+                configs.sleep_precision =
+                    apply_overrides(configs.sleep_precision, benchmark_id, &raw)?
+            }
+            STRING_JSON_PARSE_TRANSFORM => {
+                configs.json_transform =
+                    apply_overrides(configs.json_transform, benchmark_id, &raw)?
+            }
+            IO_CONCURRENT_HTTP_CLIENT => {
+                configs.http_client = apply_overrides(configs.http_client, benchmark_id, &raw)?
+            }
+            IO_CONCURRENT_HTTP_POST => {
+                configs.http_post = apply_overrides(configs.http_post, benchmark_id, &raw)?
+            }
+            IO_HTTP_CONNECTION_CHURN => {
+                configs.http_churn = apply_overrides(configs.http_churn, benchmark_id, &raw)?
+            }
+            IO_HTTP_JITTER_SENSITIVITY => {
+                configs.http_jitter = apply_overrides(configs.http_jitter, benchmark_id, &raw)?
+            }
+            IO_HTTP_STREAMING => {
+                configs.http_streaming =
+                    apply_overrides(configs.http_streaming, benchmark_id, &raw)?
+            }
+            DATA_PIPELINE_ETL_MINIBATCH => {
+                configs.etl = apply_overrides(configs.etl, benchmark_id, &raw)?
+            }
+            DATA_PIPELINE_ETL_ASYNC => {
+                configs.etl_async = apply_overrides(configs.etl_async, benchmark_id, &raw)?
+            }
+            CONCURRENCY_ASYNC_TASKS => {
+                configs.async_tasks = apply_overrides(configs.async_tasks, benchmark_id, &raw)?
+            }
+            DEPENDENCY_VULNERABILITY_SCAN_SCORECARD => {
+                configs.dependency_scan =
+                    apply_overrides(configs.dependency_scan, benchmark_id, &raw)?
+            }
+            STATIC_SECURITY_LINT_BENCHMARK => {
+                configs.static_lint = apply_overrides(configs.static_lint, benchmark_id, &raw)?
+            }
+            TEST_ROBUSTNESS_RELIABILITY => {
+                configs.test_reliability =
+                    apply_overrides(configs.test_reliability, benchmark_id, &raw)?
+            }
+            _ if overrides.is_empty() => {}
+            _ => return Err(format!("{benchmark_id}: benchmark takes no parameters")),
+        }
+        Ok(configs)
+    }
+
+    /// Resolved config of `benchmark_id` as JSON; `{}` for benchmarks
+    /// without parameters.
+    pub(crate) fn params_for(&self, benchmark_id: &str) -> Value {
+        let params = match benchmark_id {
+            CPU_MONTE_CARLO_PI => serde_json::to_value(&self.monte_carlo),
+            CPU_SLEEP_PRECISION => serde_json::to_value(&self.sleep_precision),
+            STRING_JSON_PARSE_TRANSFORM => serde_json::to_value(&self.json_transform),
+            IO_CONCURRENT_HTTP_CLIENT => serde_json::to_value(&self.http_client),
+            IO_CONCURRENT_HTTP_POST => serde_json::to_value(&self.http_post),
+            IO_HTTP_CONNECTION_CHURN => serde_json::to_value(&self.http_churn),
+            IO_HTTP_JITTER_SENSITIVITY => serde_json::to_value(&self.http_jitter),
+            IO_HTTP_STREAMING => serde_json::to_value(&self.http_streaming),
+            DATA_PIPELINE_ETL_MINIBATCH => serde_json::to_value(&self.etl),
+            DATA_PIPELINE_ETL_ASYNC => serde_json::to_value(&self.etl_async),
+            CONCURRENCY_ASYNC_TASKS => serde_json::to_value(&self.async_tasks),
+            DEPENDENCY_VULNERABILITY_SCAN_SCORECARD => serde_json::to_value(&self.dependency_scan),
+            STATIC_SECURITY_LINT_BENCHMARK => serde_json::to_value(&self.static_lint),
+            TEST_ROBUSTNESS_RELIABILITY => serde_json::to_value(&self.test_reliability),
+            _ => Ok(Value::Object(Map::new())),
+        };
+        params.unwrap_or_else(|_| Value::Object(Map::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_var_name_is_uppercased_id() {
+        assert_eq!(
+            params_env_var("io_concurrent_http_client"),
+            "BENCHMARK_PARAMS_IO_CONCURRENT_HTTP_CLIENT"
+        );
+    }
+
+    #[test]
+    fn params_var_typos_suggest_the_registered_name() {
+        assert_eq!(check_params_var("BENCHMARK_PARAMS_CPU_MONTE_CARLO_PI"), Ok(()));
+        assert_eq!(check_params_var("BENCHMARK_TIMEOUT"), Ok(()));
+        assert_eq!(
+            check_params_var("BENCHMARK_PARAMS_CPU_MONTECARLO_PI").unwrap_err(),
+            "BENCHMARK_PARAMS_CPU_MONTECARLO_PI: no configurable benchmark named \
+             CPU_MONTECARLO_PI (did you mean BENCHMARK_PARAMS_CPU_MONTE_CARLO_PI?)"
+        );
+        // Registered but not configurable: nothing to suggest.
+        assert_eq!(
+            check_params_var("BENCHMARK_PARAMS_BUILD_STARTUP_FEEDBACK_LOOP").unwrap_err(),
+            "BENCHMARK_PARAMS_BUILD_STARTUP_FEEDBACK_LOOP: no configurable benchmark named \
+             BUILD_STARTUP_FEEDBACK_LOOP"
+        );
+    }
+
+    #[test]
+    fn defaults_match_historical_workloads() {
+        assert_eq!(MonteCarloConfig::default().samples, 200_000);
+        assert_eq!(SleepPrecisionConfig::default().durations_ms, vec![1, 5, 10]);
+        assert_eq!(JsonTransformConfig::default().records, 20_000);
+        let http = HttpClientConfig::default();
+        assert_eq!((http.requests, http.rows, http.concurrency), (400, 1000, 16));
+        assert_eq!(JitterSensitivityConfig::default().requests, 200);
+        assert_eq!(EtlConfig::default().rows, 20_000);
+        assert_eq!(DependencyScanConfig::default().advisory_db_max_age_days, 7.0);
+        assert_eq!(StaticLintConfig::default().lints.len(), 2);
+        assert_eq!(TestReliabilityConfig::default().iterations, 3);
+    }
+
+    #[test]
+    fn params_for_every_configurable_benchmark() {
+        let configs = BenchmarkConfigs::default();
+        for id in CONFIGURABLE_BENCHMARKS {
+            assert!(!configs.params_for(id).as_object().unwrap().is_empty(), "{id}");
+        }
+        assert_eq!(configs.params_for("build_startup_feedback_loop"), Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn request_count_is_the_parameter_name() {
+        let configs = BenchmarkConfigs::default();
+        for id in [IO_CONCURRENT_HTTP_CLIENT, IO_CONCURRENT_HTTP_POST, IO_HTTP_JITTER_SENSITIVITY] {
+            let params = configs.params_for(id);
+            assert!(params["request_count"].is_u64(), "{id}: {params}");
+            assert!(params.get("requests").is_none(), "{id}: {params}");
+        }
+        let base = HttpClientConfig::default();
+        let id = "io_concurrent_http_client";
+        let renamed = apply_overrides(base.clone(), id, r#"{"request_count": 7}"#).unwrap();
+        assert_eq!(renamed.requests, 7);
+        let legacy = apply_overrides(base, id, r#"{"requests": 9}"#).unwrap();
+        assert_eq!(legacy.requests, 9);
+    }
+
+    #[test]
+    fn empty_object_keeps_base() {
+        let base = HttpClientConfig {
+            requests: 10,
+            ..HttpClientConfig::default()
+        };
+        let merged = apply_overrides(base.clone(), "io_concurrent_http_client", "{}").unwrap();
+        assert_eq!(merged, base);
+    }
+
+    #[test]
+    fn overrides_replace_only_given_fields() {
+        let merged = apply_overrides(
+            HttpClientConfig::default(),
+            "io_concurrent_http_client",
+            r#"{"concurrency": 4, "base_url": "http://10.0.0.2:9000"}"#,
+        )
+        .unwrap();
+        assert_eq!(merged.concurrency, 4);
+        assert_eq!(merged.base_url, "http://10.0.0.2:9000");
+        assert_eq!(merged.requests, 400);
+
+        let lint = apply_overrides(
+            StaticLintConfig::default(),
+            "static_security_lint_benchmark",
+            r#"{"lints": ["clippy::pedantic"]}"#,
+        )
+        .unwrap();
+        assert_eq!(lint.lints, vec!["clippy::pedantic"]);
+    }
+
+    #[test]
+    fn a_concurrency_sweep_runs_each_level() {
+        assert_eq!(parse_levels("1, 2,4,,8"), Ok(vec![1, 2, 4, 8]));
+        assert_eq!(
+            parse_levels("0,16"),
+            Err("invalid level \"0\"; expected positive integers".to_string())
+        );
+        assert!(parse_levels("4,x").unwrap_err().contains("\"x\""));
+        assert_eq!(parse_levels(""), Ok(Vec::new()));
+
+        let config = HttpClientConfig::default();
+        assert_eq!(config.sweep(), std::slice::from_ref(&config));
+        let swept = HttpClientConfig {
+            concurrency_sweep: vec![1, 32],
+            ..config.clone()
+        };
+        let levels = swept.sweep();
+        let concurrency: Vec<usize> = levels.iter().map(|level| level.concurrency).collect();
+        assert_eq!(concurrency, [1, 32]);
+        assert!(levels.iter().all(|level| level.requests == config.requests));
+        assert!(levels.iter().all(|level| level.concurrency_sweep.is_empty()));
+        // Only a sweep shows up in the parameters.
+        assert!(serde_json::to_value(&config).unwrap().get("concurrency_sweep").is_none());
+        assert_eq!(serde_json::to_value(&swept).unwrap()["concurrency_sweep"][1], 32);
+        let overridden = apply_overrides(
+            config,
+            "io_concurrent_http_client",
+            r#"{"concurrency_sweep": [2, 4], "target_rps": 50.0}"#,
+        )
+        .unwrap();
+        assert_eq!((overridden.concurrency_sweep, overridden.target_rps), (vec![2, 4], 50.0));
+    }
+
+    #[test]
+    fn base_url_lists_split_into_targets() {
+        let config = HttpClientConfig {
+            base_url: " http://127.0.0.1:8000, http://10.0.0.2:9000,".to_string(),
+            ..HttpClientConfig::default()
+        };
+        assert_eq!(config.targets(), ["http://127.0.0.1:8000", "http://10.0.0.2:9000"]);
+        let remote = config.for_target("http://10.0.0.2:9000");
+        assert_eq!(remote.targets(), ["http://10.0.0.2:9000"]);
+        assert_eq!(remote.requests, config.requests);
+        assert_eq!(HttpClientConfig::default().targets(), ["http://127.0.0.1:8000"]);
+        let blank = HttpClientConfig {
+            base_url: " , ".to_string(),
+            ..HttpClientConfig::default()
+        };
+        assert_eq!(blank.targets(), [" , "]);
+
+        let served = BenchmarkConfigs {
+            http_client: config,
+            ..BenchmarkConfigs::default()
+        }
+        .with_embedded_server();
+        assert_eq!(served.http_client.targets().len(), 1);
+        assert_eq!(served.http_churn.server, HttpServer::Embedded);
+        let params = served.params_for(IO_CONCURRENT_HTTP_CLIENT);
+        assert_eq!(params["server"], "embedded");
+        let params = BenchmarkConfigs::default().params_for(IO_CONCURRENT_HTTP_CLIENT);
+        assert_eq!(params["server"], "external");
+    }
+
+    #[test]
+    fn http_limits_fall_back_on_zero() {
+        let config = HttpClientConfig::default();
+        let limits = config.request_limits();
+        assert_eq!(limits.timeout, Duration::from_millis(5000));
+        assert_eq!(limits.budget, Some(Duration::from_secs(300)));
+        let unlimited = HttpClientConfig {
+            timeout_ms: 0,
+            budget_seconds: 0.0,
+            ..config.clone()
+        };
+        assert_eq!(unlimited.request_limits().timeout, DEFAULT_TIMEOUT);
+        assert_eq!(unlimited.request_limits().budget, None);
+        let tight = HttpClientConfig {
+            timeout_ms: 250,
+            budget_seconds: 1.5,
+            ..config.clone()
+        };
+        assert_eq!(tight.request_limits().timeout, Duration::from_millis(250));
+        assert_eq!(tight.request_limits().budget, Some(Duration::from_millis(1500)));
+        assert_eq!(limits.pace, None);
+        let paced = HttpClientConfig {
+            target_rps: 200.0,
+            ..config.clone()
+        };
+        assert_eq!(paced.request_limits().pace, Some(Duration::from_millis(5)));
+        for closed in [-1.0, f64::NAN, f64::INFINITY] {
+            let config = HttpClientConfig {
+                target_rps: closed,
+                ..config.clone()
+            };
+            assert_eq!(config.request_limits().pace, None, "{closed}");
+        }
+
+        assert_eq!(config.ready_timeout(), Duration::from_secs(30));
+        let impatient = HttpClientConfig {
+            ready_timeout_seconds: -1.0,
+            ..config
+        };
+        assert_eq!(impatient.ready_timeout(), Duration::ZERO);
+
+        // Streaming reads the same fields by the same rules.
+        let streaming = HttpStreamingConfig {
+            timeout_ms: 0,
+            ready_timeout_seconds: f64::NAN,
+            ..HttpStreamingConfig::default()
+        };
+        assert_eq!(streaming.timeout(), DEFAULT_TIMEOUT);
+        assert_eq!(streaming.ready_timeout(), Duration::ZERO);
+    }
+
+    #[test]
+    fn with_overrides_changes_one_benchmark() {
+        let base = BenchmarkConfigs::default();
+        let mut overrides = Map::new();
+        overrides.insert("concurrency".into(), Value::from(4));
+        let configs = base
+            .with_overrides("io_concurrent_http_client", &overrides)
+            .unwrap();
+        assert_eq!(configs.http_client.concurrency, 4);
+        assert_eq!(configs.http_churn, base.http_churn);
+        assert!(base
+            .with_overrides("io_http_connection_churn", &Map::new())
+            .is_ok());
+        assert!(base
+            .with_overrides("cpu_monte_carlo_pi", &overrides)
+            .unwrap_err()
+            .contains("unknown key `concurrency`"));
+        assert!(base
+            .with_overrides("build_startup_time", &Map::new())
+            .is_ok());
+        assert!(base
+            .with_overrides("build_startup_time", &overrides)
+            .unwrap_err()
+            .contains("takes no parameters"));
+    }
+
+    #[test]
+    fn unknown_key_is_named() {
+        let err = apply_overrides(
+            MonteCarloConfig::default(),
+            "cpu_monte_carlo_pi",
+            r#"{"sample": 5}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("BENCHMARK_PARAMS_CPU_MONTE_CARLO_PI"), "{err}");
+        assert!(err.contains("unknown key `sample`"), "{err}");
+        assert!(err.contains("samples"), "{err}");
+    }
+
+    #[test]
+    fn latency_distribution_is_tagged_by_kind() {
+        let config = apply_overrides(
+            JitterSensitivityConfig::default(),
+            "io_http_jitter_sensitivity",
+            r#"{"high_jitter": {"latency": {"kind": "fixed", "ms": 3.0}, "error_rate": 0.1}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.high_jitter.latency, LatencyDistribution::Fixed { ms: 3.0 });
+        assert_eq!(config.high_jitter.error_rate, 0.1);
+        assert_eq!(config.low_jitter, JitterSensitivityConfig::default().low_jitter);
+        assert!(apply_overrides(
+            JitterSensitivityConfig::default(),
+            "io_http_jitter_sensitivity",
+            r#"{"low_jitter": {"latency": {"kind": "lognormal"}}}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn etl_input_mode_is_named_and_omitted_by_default() {
+        let etl = apply_overrides(
+            EtlConfig::default(),
+            "data_pipeline_etl_minibatch",
+            r#"{"input_mode": "mmap"}"#,
+        )
+        .unwrap();
+        assert_eq!(etl.input_mode, InputMode::Mmap);
+        assert_eq!(serde_json::to_value(&etl).unwrap()["input_mode"], "mmap");
+        let default = serde_json::to_value(EtlConfig::default()).unwrap();
+        assert!(default.get("input_mode").is_none());
+        assert!(apply_overrides(
+            EtlConfig::default(),
+            "data_pipeline_etl_minibatch",
+            r#"{"input_mode": "direct"}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn wrong_type_is_named() {
+        let err = apply_overrides(
+            EtlConfig::default(),
+            "data_pipeline_etl_minibatch",
+            r#"{"dataset": "x.jsonl.gz", "rows": "many"}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("invalid value for `rows`"), "{err}");
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let base = TestReliabilityConfig::default;
+        assert!(apply_overrides(base(), "test_robustness_reliability", "[1]")
+            .unwrap_err()
+            .contains("expected a JSON object"));
+        assert!(apply_overrides(base(), "test_robustness_reliability", "{iterations: 2")
+            .unwrap_err()
+            .contains("invalid JSON"));
+        assert!(apply_overrides(base(), "test_robustness_reliability", r#"{"iterations": -1}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn config_file_tables_apply_per_benchmark() {
+        let file = ConfigFile::parse(
+            "bench.toml",
+            r#"
+            [cpu_monte_carlo_pi]
+            samples = 5_000
+
+            [io_concurrent_http_client]
+            requests = 20
+            concurrency = 2
+
+            [data_pipeline_etl_minibatch]
+            dataset = "small.jsonl.gz"
+            "#,
+        )
+        .unwrap();
+        let monte_carlo = file.apply(CPU_MONTE_CARLO_PI, MonteCarloConfig::default()).unwrap();
+        assert_eq!(monte_carlo.samples, 5_000);
+        let http = file.apply(IO_CONCURRENT_HTTP_CLIENT, HttpClientConfig::default()).unwrap();
+        assert_eq!((http.requests, http.rows, http.concurrency), (20, 1000, 2));
+        let etl = file.apply(DATA_PIPELINE_ETL_MINIBATCH, EtlConfig::default()).unwrap();
+        assert_eq!(etl.dataset, "small.jsonl.gz");
+        let json = file.apply(STRING_JSON_PARSE_TRANSFORM, JsonTransformConfig::default());
+        assert_eq!(json.unwrap(), JsonTransformConfig::default());
+    }
+
+    #[test]
+    fn config_file_errors_name_the_path() {
+        let err = ConfigFile::parse("bench.toml", "[cpu_monte_carlo_pi\nsamples = 1").unwrap_err();
+        assert!(err.starts_with("--config bench.toml: "), "{err}");
+        let err = ConfigFile::parse("bench.toml", "[cpu_monte_carlo]\nsamples = 1").unwrap_err();
+        assert_eq!(
+            err,
+            "--config bench.toml: no configurable benchmark named cpu_monte_carlo \
+             (did you mean cpu_monte_carlo_pi?)"
+        );
+        let err = ConfigFile::parse("bench.toml", "cpu_monte_carlo_pi = 3").unwrap_err();
+        assert!(err.contains("`cpu_monte_carlo_pi` must be a table"), "{err}");
+
+        let file = ConfigFile::parse("bench.toml", "[cpu_monte_carlo_pi]\nsample = 1").unwrap();
+        let err = file.apply(CPU_MONTE_CARLO_PI, MonteCarloConfig::default()).unwrap_err();
+        assert!(err.starts_with("--config bench.toml [cpu_monte_carlo_pi]: unknown key"), "{err}");
+
+        let missing = env::temp_dir().join(format!("missing-{}.toml", std::process::id()));
+        let err = ConfigFile::read(&missing).unwrap_err();
+        assert!(err.starts_with(&format!("--config {}: ", missing.display())), "{err}");
+    }
+
+    #[test]
+    fn registry_lists_every_env_var_a_config_reads() {
+        // Churn ignores pacing, keep-alive, the budget and the ready wait, so
+        // it lists only the HTTP vars it honours by hand.
+        let readers: [(&str, &[&str]); 6] = [
+            (
+                "MonteCarloConfig",
+                &[CPU_MONTE_CARLO_PI, benchmark_ids::CPU_MONTE_CARLO_PI_PARALLEL],
+            ),
+            ("HttpClientConfig", &[IO_CONCURRENT_HTTP_CLIENT, IO_CONCURRENT_HTTP_POST]),
+            ("HttpStreamingConfig", &[IO_HTTP_STREAMING]),
+            ("EtlConfig", &[DATA_PIPELINE_ETL_MINIBATCH]),
+            ("AsyncTasksConfig", &[CONCURRENCY_ASYNC_TASKS]),
+            ("DependencyScanConfig", &[DEPENDENCY_VULNERABILITY_SCAN_SCORECARD]),
+        ];
+        let source = include_str!("config.rs");
+        let mut seen = 0;
+        for block in source.split("\nimpl ").skip(1) {
+            let block = block.split("\n}\n").next().unwrap();
+            let (config, body) = block.split_once(" {\n").unwrap_or_default();
+            let Some((_, with_env)) = body.split_once("    fn with_env(self) -> ") else {
+                continue;
+            };
+            let with_env = with_env.split("\n    }\n").next().unwrap();
+            let ids = readers
+                .iter()
+                .find(|(reader, _)| *reader == config)
+                .unwrap_or_else(|| panic!("{config}::with_env has no benchmarks listed here"))
+                .1;
+            seen += 1;
+            let literals = with_env.split('"').skip(1).step_by(2);
+            for var in literals.filter(|literal| literal.starts_with("BENCHMARK_")) {
+                for id in ids {
+                    let listed = benchmark_ids::descriptor(id).unwrap().env_vars;
+                    assert!(listed.contains(&var), "{id} reads {var} but does not list it");
+                }
+            }
+        }
+        assert_eq!(seen, readers.len());
+    }
+}
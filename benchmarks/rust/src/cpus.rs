@@ -0,0 +1,114 @@
+//! The one place the runner decides how many CPUs it has. Every consumer
+//! (the environment block, default worker counts) goes through
+//! `cpu_count()`, so a failed probe cannot yield different answers in one run.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::io;
+use std::num::NonZeroUsize;
+use std::sync::OnceLock;
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CpuSource {
+    /// `BENCHMARK_CPUS` was set to a positive integer.
+    Override,
+    /// `available_parallelism` answered.
+    Detected,
+    /// The probe failed and nothing overrode it; one CPU is assumed.
+    Fallback,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CpuCount {
+    /// The count every consumer uses.
+    pub(crate) effective: usize,
+    /// What the probe reported, even when overridden.
+    pub(crate) detected: Option<usize>,
+    pub(crate) source: CpuSource,
+}
+
+/// An unparseable or zero override is ignored, like other flat
+/// `BENCHMARK_*` variables.
+pub(crate) fn resolve(
+    override_value: Option<&str>,
+    probe: impl FnOnce() -> io::Result<NonZeroUsize>,
+) -> CpuCount {
+    let detected = probe().ok().map(NonZeroUsize::get);
+    let overridden = override_value
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|&cpus| cpus > 0);
+    match (overridden, detected) {
+        (Some(cpus), _) => CpuCount {
+            effective: cpus,
+            detected,
+            source: CpuSource::Override,
+        },
+        (None, Some(cpus)) => CpuCount {
+            effective: cpus,
+            detected,
+            source: CpuSource::Detected,
+        },
+        (None, None) => CpuCount {
+            effective: 1,
+            detected,
+            source: CpuSource::Fallback,
+        },
+    }
+}
+
+/// Resolved once per process.
+pub(crate) fn cpu_count() -> CpuCount {
+    static CPUS: OnceLock<CpuCount> = OnceLock::new();
+    *CPUS.get_or_init(|| {
+        resolve(
+            env::var("BENCHMARK_CPUS").ok().as_deref(),
+            thread::available_parallelism,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpus(n: usize) -> impl FnOnce() -> io::Result<NonZeroUsize> {
+        move || Ok(NonZeroUsize::new(n).unwrap())
+    }
+
+    fn failing() -> io::Result<NonZeroUsize> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    #[test]
+    fn override_wins_but_detection_is_kept() {
+        let count = resolve(Some("4"), cpus(64));
+        assert_eq!(count.effective, 4);
+        assert_eq!(count.detected, Some(64));
+        assert_eq!(count.source, CpuSource::Override);
+        assert_eq!(resolve(Some("2"), failing).effective, 2);
+    }
+
+    #[test]
+    fn invalid_override_falls_through_to_detection() {
+        for raw in ["0", "many", ""] {
+            let count = resolve(Some(raw), cpus(8));
+            assert_eq!(count.effective, 8, "{raw}");
+            assert_eq!(count.source, CpuSource::Detected);
+        }
+    }
+
+    #[test]
+    fn failed_probe_falls_back_to_one() {
+        let count = resolve(None, failing);
+        assert_eq!(
+            count,
+            CpuCount {
+                effective: 1,
+                detected: None,
+                source: CpuSource::Fallback,
+            }
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Every file the runner writes (generated datasets, the checksum store, the
+//! bootstrap marker) lives under one data dir, so the repo itself can be
+//! mounted read-only. The default is the repo root, matching the original
+//! repo-relative layout. The data dir also keeps the list of paths a run
+//! created, for disk accounting and `--clean`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::dataset_cache::DatasetCache;
+
+#[derive(Clone, Debug)]
+pub(crate) struct DataDir {
+    root: PathBuf,
+    cache: PathBuf,
+    /// Generated datasets shared across worktrees; `None` when disabled.
+    datasets: Option<DatasetCache>,
+    /// Paths created during this run with their size when recorded; shared
+    /// by clones.
+    created: Arc<Mutex<BTreeMap<PathBuf, u64>>>,
+}
+
+impl PartialEq for DataDir {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.cache == other.cache
+    }
+}
+
+impl Eq for DataDir {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct DiskUsage {
+    pub(crate) files_created: usize,
+    /// Size of each created path when it was recorded.
+    pub(crate) bytes_written: u64,
+    /// What is still on disk when the summary is written.
+    pub(crate) bytes_retained: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Removed {
+    pub(crate) paths: usize,
+    pub(crate) bytes: u64,
+}
+
+impl DataDir {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        let cache = root.join(".cache/benchmarks");
+        DataDir {
+            root,
+            cache,
+            datasets: None,
+            created: Arc::default(),
+        }
+    }
+
+    /// `BENCHMARK_DATA_DIR` (relative to `repo_root`) or `repo_root`;
+    /// `BENCHMARK_CACHE_DIR` still overrides the cache location alone. The
+    /// dataset cache is configured by `DatasetCache::from_env`.
+    pub(crate) fn from_env(repo_root: &Path) -> Self {
+        DataDir::from_vars(
+            repo_root,
+            env::var("BENCHMARK_DATA_DIR").ok(),
+            env::var("BENCHMARK_CACHE_DIR").ok(),
+        )
+        .with_dataset_cache(DatasetCache::from_env())
+    }
+
+    pub(crate) fn with_dataset_cache(mut self, datasets: Option<DatasetCache>) -> Self {
+        self.datasets = datasets;
+        self
+    }
+
+    pub(crate) fn from_vars(repo_root: &Path, data_dir: Option<String>, cache: Option<String>) -> Self {
+        let root = data_dir
+            .map(|raw| repo_root.join(raw))
+            .unwrap_or_else(|| repo_root.to_path_buf());
+        let mut data_dir = DataDir::new(root);
+        if let Some(cache) = cache {
+            data_dir.cache = PathBuf::from(cache);
+        }
+        data_dir
+    }
+
+    pub(crate) fn cache_dir(&self) -> &Path {
+        &self.cache
+    }
+
+    pub(crate) fn dataset_cache(&self) -> Option<&DatasetCache> {
+        self.datasets.as_ref()
+    }
+
+    /// Relative dataset paths land under the data dir; absolute ones are
+    /// used as given.
+    pub(crate) fn resolve(&self, raw: &str) -> PathBuf {
+        self.root.join(raw)
+    }
+
+    /// Creates the data and cache dirs and proves both accept a write, so a
+    /// read-only mount fails here instead of halfway through a run.
+    pub(crate) fn ensure_writable(&self) -> Result<(), String> {
+        for dir in [&self.root, &self.cache] {
+            let probe = dir.join(format!(".write-probe-{}", std::process::id()));
+            fs::create_dir_all(dir)
+                .and_then(|()| fs::write(&probe, b""))
+                .and_then(|()| fs::remove_file(&probe))
+                .map_err(|err| {
+                    format!(
+                        "data dir {} is not writable ({err}); set BENCHMARK_DATA_DIR to a writable path",
+                        dir.display()
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// True for paths strictly below the data or cache dir. The check is
+    /// lexical, so any `..` component is refused outright.
+    pub(crate) fn manages(&self, path: &Path) -> bool {
+        if path.components().any(|part| part == Component::ParentDir) {
+            return false;
+        }
+        [&self.root, &self.cache]
+            .iter()
+            .any(|dir| path != dir.as_path() && path.starts_with(dir))
+    }
+
+    /// Records a file or directory the run created.
+    pub(crate) fn track(&self, path: &Path) {
+        let size = disk_size(path);
+        if let Ok(mut created) = self.created.lock() {
+            created.insert(path.to_path_buf(), size);
+        }
+    }
+
+    pub(crate) fn usage(&self) -> DiskUsage {
+        let created = self.created.lock().map(|created| created.clone()).unwrap_or_default();
+        DiskUsage {
+            files_created: created.len(),
+            bytes_written: created.values().sum(),
+            bytes_retained: created.keys().map(|path| disk_size(path)).sum(),
+        }
+    }
+
+    /// `--clean`: deletes every tracked path. Nothing is deleted when any
+    /// of them lies outside the data dir.
+    pub(crate) fn remove_created(&self) -> Result<Removed, String> {
+        let created: Vec<PathBuf> = self
+            .created
+            .lock()
+            .map(|created| created.keys().cloned().collect())
+            .unwrap_or_default();
+        if let Some(outside) = created.iter().find(|path| !self.manages(path)) {
+            return Err(format!(
+                "refusing to delete {}: outside the data dir {}",
+                outside.display(),
+                self.root.display()
+            ));
+        }
+        let mut removed = Removed::default();
+        for path in created {
+            let bytes = disk_size(&path);
+            if remove_path(&path).is_ok() {
+                removed.paths += 1;
+                removed.bytes += bytes;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// `clean`: deletes files under the data and cache dirs last modified
+    /// more than `max_age` before `now`, then the directories that leaves
+    /// empty. A data dir that contains `repo_root` (the default) is refused,
+    /// since the sweep would reach the source tree.
+    pub(crate) fn remove_older_than(
+        &self,
+        max_age: Duration,
+        now: SystemTime,
+        repo_root: &Path,
+    ) -> Result<Removed, String> {
+        let repo_root = fs::canonicalize(repo_root).unwrap_or_else(|_| repo_root.to_path_buf());
+        for dir in [&self.root, &self.cache] {
+            let resolved = fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+            if repo_root.starts_with(&resolved) {
+                return Err(format!(
+                    "refusing to clean {}: it contains the repository; set BENCHMARK_DATA_DIR to a dedicated directory",
+                    dir.display()
+                ));
+            }
+        }
+        let cutoff = now.checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut removed = Removed::default();
+        for dir in [&self.root, &self.cache] {
+            sweep(dir, cutoff, &mut removed);
+        }
+        Ok(removed)
+    }
+}
+
+/// Bytes under `path`; symlinks count as themselves and are not followed.
+fn disk_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| disk_size(&entry.path()))
+        .sum()
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Removes files older than `cutoff` below `dir`, pruning subdirectories
+/// that end up empty because of it (or were already old and empty). True
+/// when `dir` itself is left empty.
+fn sweep(dir: &Path, cutoff: SystemTime, removed: &mut Removed) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    let mut empty = true;
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            empty = false;
+            continue;
+        };
+        let old = meta.modified().is_ok_and(|modified| modified < cutoff);
+        if meta.is_dir() {
+            let before = removed.paths;
+            let prune = sweep(&path, cutoff, removed) && (old || removed.paths > before);
+            if !(prune && fs::remove_dir(&path).is_ok()) {
+                empty = false;
+            }
+        } else if old && fs::remove_file(&path).is_ok() {
+            removed.paths += 1;
+            removed.bytes += meta.len();
+        } else {
+            empty = false;
+        }
+    }
+    empty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_paths_under_the_data_dir() {
+        let data_dir = DataDir::new(PathBuf::from("/data"));
+        assert_eq!(
+            data_dir.resolve("benchmarks/shared/datasets/etl_input.jsonl.gz"),
+            PathBuf::from("/data/benchmarks/shared/datasets/etl_input.jsonl.gz")
+        );
+        assert_eq!(data_dir.resolve("/mnt/etl.jsonl.gz"), PathBuf::from("/mnt/etl.jsonl.gz"));
+        assert_eq!(data_dir.cache_dir(), Path::new("/data/.cache/benchmarks"));
+
+        let repo = Path::new("/repo");
+        assert_eq!(DataDir::from_vars(repo, None, None), DataDir::new(repo.to_path_buf()));
+        assert_eq!(
+            DataDir::from_vars(repo, Some("scratch".to_string()), None),
+            DataDir::new(PathBuf::from("/repo/scratch"))
+        );
+        let overridden = DataDir::from_vars(repo, Some("/data".to_string()), Some("/cache".to_string()));
+        assert_eq!(overridden.resolve("etl.jsonl.gz"), PathBuf::from("/data/etl.jsonl.gz"));
+        assert_eq!(overridden.cache_dir(), Path::new("/cache"));
+    }
+
+    #[test]
+    fn unwritable_data_dir_names_the_path() {
+        let file = env::temp_dir().join(format!("data-dir-file-{}", std::process::id()));
+        fs::write(&file, b"not a directory").unwrap();
+        let err = DataDir::new(file.join("data")).ensure_writable().unwrap_err();
+        assert!(err.contains(&file.join("data").display().to_string()), "{err}");
+        let _ = fs::remove_file(file);
+    }
+
+    fn temp_data_dir(name: &str) -> DataDir {
+        let root = env::temp_dir().join(format!("data-dir-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        DataDir::new(root)
+    }
+
+    fn write_aged(path: &Path, bytes: usize, age: Duration) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = fs::File::create(path).unwrap();
+        fs::write(path, vec![b'x'; bytes]).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    #[test]
+    fn only_paths_below_the_data_dir_are_managed() {
+        let data_dir = DataDir::from_vars(Path::new("/data"), None, Some("/cache".to_string()));
+        assert!(data_dir.manages(Path::new("/data/datasets/etl.jsonl.gz")));
+        assert!(data_dir.manages(Path::new("/cache/clippy-fix-0")));
+        assert!(!data_dir.manages(Path::new("/data")));
+        assert!(!data_dir.manages(Path::new("/data/../etc/passwd")));
+        assert!(!data_dir.manages(Path::new("/database/etl.jsonl.gz")));
+    }
+
+    #[test]
+    fn tracks_and_removes_created_paths() {
+        let data_dir = temp_data_dir("track");
+        let dataset = data_dir.resolve("datasets/etl.jsonl.gz");
+        let scratch = data_dir.cache_dir().join("clippy-fix-0");
+        write_aged(&dataset, 100, Duration::ZERO);
+        write_aged(&scratch.join("src/lib.rs"), 30, Duration::ZERO);
+        write_aged(&scratch.join("Cargo.toml"), 20, Duration::ZERO);
+        data_dir.clone().track(&dataset);
+        data_dir.track(&scratch);
+        fs::write(&dataset, [b'x'; 10]).unwrap();
+        assert_eq!(
+            data_dir.usage(),
+            DiskUsage {
+                files_created: 2,
+                bytes_written: 150,
+                bytes_retained: 60,
+            }
+        );
+
+        assert_eq!(data_dir.remove_created(), Ok(Removed { paths: 2, bytes: 60 }));
+        assert!(!dataset.exists() && !scratch.exists());
+        assert_eq!(data_dir.usage().bytes_retained, 0);
+
+        let outside = env::temp_dir().join(format!("data-dir-outside-{}", std::process::id()));
+        fs::write(&outside, b"keep").unwrap();
+        data_dir.track(&outside);
+        assert!(data_dir.remove_created().unwrap_err().contains("refusing"));
+        assert!(outside.exists());
+        let _ = fs::remove_file(outside);
+    }
+
+    #[test]
+    fn removes_files_older_than_the_cutoff() {
+        let data_dir = temp_data_dir("age");
+        let day = Duration::from_secs(86_400);
+        let stale = data_dir.resolve("datasets/old/etl.jsonl.gz");
+        let fresh = data_dir.resolve("datasets/etl.jsonl.gz");
+        let store = data_dir.cache_dir().join("checksum_store.json");
+        write_aged(&stale, 40, day * 30);
+        write_aged(&fresh, 10, day);
+        write_aged(&store, 5, day * 8);
+
+        let repo = env::temp_dir().join(format!("data-dir-repo-{}", std::process::id()));
+        let removed = data_dir.remove_older_than(day * 7, SystemTime::now(), &repo).unwrap();
+        assert_eq!(removed, Removed { paths: 2, bytes: 45 });
+        assert!(!stale.exists() && !store.exists());
+        assert!(!data_dir.resolve("datasets/old").exists());
+        assert!(fresh.exists());
+
+        let inside_repo = DataDir::new(repo.clone());
+        let err = inside_repo
+            .remove_older_than(day, SystemTime::now(), &repo.join("checkout"))
+            .unwrap_err();
+        assert!(err.contains("contains the repository"), "{err}");
+        let _ = fs::remove_dir_all(data_dir.root);
+    }
+}
@@ -0,0 +1,265 @@
+//! Where a dataset really lives, and who may generate it. Paths are
+//! canonicalized before use, so harnesses that reach the same file through
+//! different symlinks agree on one file and one lock. Generation takes an
+//! advisory `flock` on a `.lock` file beside the dataset and writes through
+//! a temporary file, so concurrent runs never interleave writes and readers
+//! never see a half-written dataset.
+
+use serde_json::{json, Value};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a blocked harness retries the lock.
+const LOCK_POLL: Duration = Duration::from_millis(20);
+
+/// `path` with symlinks resolved. A dataset that does not exist yet is
+/// resolved through its parent dir, so it is generated where it will be
+/// found; with no parent either, `path` is returned unchanged.
+pub(crate) fn canonical(path: &Path) -> PathBuf {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return resolved;
+    }
+    let parent = path.parent().and_then(|parent| fs::canonicalize(parent).ok());
+    match (parent, path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The `dataset` block of a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Provenance {
+    /// As set in the config, before the data dir is applied.
+    pub(crate) configured: String,
+    pub(crate) canonical: PathBuf,
+    /// Device and inode of the canonical file; unix only, and only once the
+    /// file exists.
+    pub(crate) file_id: Option<(u64, u64)>,
+}
+
+impl Provenance {
+    pub(crate) fn of(configured: &str, resolved: &Path) -> Self {
+        let canonical = canonical(resolved);
+        Provenance {
+            file_id: file_id(&canonical),
+            configured: configured.to_string(),
+            canonical,
+        }
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        let mut value = json!({
+            "configured_path": self.configured,
+            "canonical_path": self.canonical.display().to_string(),
+        });
+        if let Some((device, inode)) = self.file_id {
+            value["device"] = json!(device);
+            value["inode"] = json!(inode);
+        }
+        value
+    }
+}
+
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|meta| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// What `ensure` did about the dataset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Generation {
+    /// This call wrote the dataset.
+    pub(crate) generated: bool,
+    /// Time spent waiting for another harness's lock.
+    pub(crate) lock_wait: Duration,
+    /// The lock was still held after the timeout; nothing was generated.
+    pub(crate) lock_timed_out: bool,
+    /// What this call wrote came from the dataset cache; set by the caller,
+    /// whose generator knows.
+    pub(crate) cache_hit: bool,
+}
+
+/// An exclusive `flock` on `<dataset>.lock`, released on drop.
+struct GenerationLock {
+    _file: File,
+}
+
+impl GenerationLock {
+    /// Polls for the lock until `timeout` has passed; `None` on timeout or
+    /// when the lock file cannot be created.
+    fn acquire(dataset: &Path, timeout: Duration) -> Option<GenerationLock> {
+        let mut lock_path = OsString::from(dataset.as_os_str());
+        lock_path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(lock_path))
+            .ok()?;
+        let start = Instant::now();
+        while !try_lock(&file) {
+            if start.elapsed() >= timeout {
+                return None;
+            }
+            thread::sleep(LOCK_POLL);
+        }
+        Some(GenerationLock { _file: file })
+    }
+}
+
+#[cfg(unix)]
+fn try_lock(file: &File) -> bool {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: the descriptor stays open for the lifetime of `file`; closing
+    // it releases the lock.
+    unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) == 0 }
+}
+
+/// Elsewhere generation is unlocked, as it was before locking existed.
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> bool {
+    true
+}
+
+/// Distinguishes temporary files of generators in the same process.
+static TEMP_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Makes sure the dataset at `path` (already canonical) exists, calling
+/// `generate` with a temporary path to write it to when it does not. Only
+/// one harness generates at a time; the others wait up to `timeout` and
+/// then find the finished file.
+pub(crate) fn ensure(path: &Path, timeout: Duration, generate: impl FnOnce(&Path)) -> Generation {
+    let mut generation = Generation::default();
+    if path.exists() {
+        return generation;
+    }
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let start = Instant::now();
+    let lock = GenerationLock::acquire(path, timeout);
+    generation.lock_wait = start.elapsed();
+    if lock.is_none() {
+        generation.lock_timed_out = true;
+        return generation;
+    }
+    // Whoever held the lock before us may have generated it already.
+    if path.exists() {
+        return generation;
+    }
+    let sequence = TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let mut temp = OsString::from(path.as_os_str());
+    temp.push(format!(".{}-{sequence}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+    let _ = fs::remove_file(&temp);
+    generate(&temp);
+    generation.generated = fs::rename(&temp, path).is_ok();
+    if !generation.generated {
+        let _ = fs::remove_file(&temp);
+    }
+    generation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Barrier;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("dataset-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_paths_resolve_to_one_file() {
+        let dir = temp_dir("symlink");
+        let real = dir.join("real");
+        fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, dir.join("link")).unwrap();
+
+        // Not generated yet: resolved through the parent.
+        let via_link = dir.join("link/etl.jsonl.gz");
+        let expected = fs::canonicalize(&real).unwrap().join("etl.jsonl.gz");
+        assert_eq!(canonical(&via_link), expected);
+        assert_eq!(Provenance::of("link/etl.jsonl.gz", &via_link).file_id, None);
+
+        fs::write(real.join("etl.jsonl.gz"), b"rows").unwrap();
+        let linked = Provenance::of("link/etl.jsonl.gz", &via_link);
+        let direct = Provenance::of("real/etl.jsonl.gz", &real.join("etl.jsonl.gz"));
+        assert_eq!(linked.canonical, direct.canonical);
+        assert!(linked.file_id.is_some());
+        assert_eq!(linked.file_id, direct.file_id);
+        let value = linked.to_value();
+        assert_eq!(value["configured_path"], "link/etl.jsonl.gz");
+        assert_eq!(value["canonical_path"], expected.display().to_string());
+        assert!(value["inode"].is_u64());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn racing_generators_write_the_dataset_once() {
+        let dir = temp_dir("race");
+        let path = canonical(&dir.join("etl.jsonl.gz"));
+        let writes = AtomicUsize::new(0);
+        let barrier = Barrier::new(2);
+        let results: Vec<Generation> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        ensure(&path, Duration::from_secs(10), |temp| {
+                            writes.fetch_add(1, Ordering::SeqCst);
+                            // Long enough for the other thread to block on the lock.
+                            thread::sleep(Duration::from_millis(100));
+                            fs::write(temp, b"complete").unwrap();
+                        })
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert_eq!(writes.load(Ordering::SeqCst), 1);
+        assert_eq!(results.iter().filter(|result| result.generated).count(), 1);
+        assert!(results.iter().all(|result| !result.lock_timed_out));
+        assert_eq!(fs::read(&path).unwrap(), b"complete");
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn held_lock_times_out_without_generating() {
+        let dir = temp_dir("timeout");
+        let path = dir.join("etl.jsonl.gz");
+        let held = GenerationLock::acquire(&path, Duration::ZERO).unwrap();
+        let result = ensure(&path, Duration::from_millis(60), |_| panic!("generated while locked"));
+        assert!(result.lock_timed_out);
+        assert!(!result.generated);
+        assert!(result.lock_wait >= Duration::from_millis(60));
+        assert!(!path.exists());
+        drop(held);
+
+        let result = ensure(&path, Duration::ZERO, |temp| fs::write(temp, b"rows").unwrap());
+        assert!(result.generated && !result.lock_timed_out);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
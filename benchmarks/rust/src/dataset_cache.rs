@@ -0,0 +1,267 @@
+//! A content-addressed cache of generated datasets, shared by every
+//! worktree on the machine. CI creates a fresh worktree per job, and each
+//! would otherwise regenerate the same deterministic datasets. An entry is
+//! keyed by the generator, its parameters and the harness fingerprint, so a
+//! change to any of them misses instead of reusing stale content.
+//!
+//! Hits are hard-linked into the data dir, or copied when the cache lives
+//! on another filesystem. The datasets are never written after generation,
+//! so sharing an inode is safe. The cache is capped by total size: after
+//! each store the least recently used entries (by access time, set
+//! explicitly on every hit since `relatime` mounts rarely update it) are
+//! deleted until it fits.
+
+use serde_json::{json, Value};
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::checksum_store::params_hash;
+
+pub(crate) const DEFAULT_CACHE_MB: u64 = 4096;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DatasetCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+/// How a cached entry reached its destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Placement {
+    Linked,
+    Copied,
+}
+
+impl DatasetCache {
+    pub(crate) fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        DatasetCache { dir, max_bytes }
+    }
+
+    /// `BENCHMARK_DATASET_CACHE_DIR`, else `datasets` under the user's
+    /// cache dir (`$XDG_CACHE_HOME` or `~/.cache`), capped at
+    /// `BENCHMARK_DATASET_CACHE_MB` (default 4096). A cap of `0`, or no
+    /// home directory to put it in, disables the cache.
+    pub(crate) fn from_env() -> Option<Self> {
+        let max_mb = env::var("BENCHMARK_DATASET_CACHE_MB")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_CACHE_MB);
+        if max_mb == 0 {
+            return None;
+        }
+        let dir = match env::var_os("BENCHMARK_DATASET_CACHE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?
+                .join("rust-vs-python-demo/datasets"),
+        };
+        Some(DatasetCache::new(dir, max_mb * 1024 * 1024))
+    }
+
+    fn entry(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// Places the entry for `key` at `dest`; `None` on a miss.
+    pub(crate) fn fetch(&self, key: &str, dest: &Path) -> Option<Placement> {
+        let entry = self.entry(key);
+        let placement = link_or_copy(&entry, dest, |src, dest| fs::hard_link(src, dest)).ok()?;
+        let now = FileTimes::new().set_accessed(SystemTime::now());
+        let _ = File::open(&entry).and_then(|file| file.set_times(now));
+        Some(placement)
+    }
+
+    /// Adds the dataset at `path` under `key`, then evicts down to the cap.
+    /// The entry is written through a temporary name, so a concurrent
+    /// fetch never sees it half-copied.
+    pub(crate) fn store(&self, key: &str, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut temp = OsString::from(self.entry(key).as_os_str());
+        temp.push(format!(".{}.tmp", std::process::id()));
+        let temp = PathBuf::from(temp);
+        let _ = fs::remove_file(&temp);
+        let stored = link_or_copy(path, &temp, |src, dest| fs::hard_link(src, dest))
+            .and_then(|_| fs::rename(&temp, self.entry(key)));
+        if stored.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        stored?;
+        self.evict();
+        Ok(())
+    }
+
+    /// Deletes least recently used entries until the cache fits its cap;
+    /// returns how many were deleted. Temporary files are left alone.
+    pub(crate) fn evict(&self) -> usize {
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_none_or(|ext| ext != "tmp"))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                let accessed = meta.accessed().or_else(|_| meta.modified()).ok()?;
+                meta.is_file().then(|| (accessed, meta.len(), entry.path()))
+            })
+            .collect();
+        entries.sort();
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        let mut evicted = 0;
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+}
+
+/// The cache key of a dataset: which generator wrote it, from what
+/// parameters, under which harness.
+pub(crate) fn key(generator: &str, params: &Value, harness_fingerprint: &str) -> String {
+    params_hash(&json!({
+        "generator": generator,
+        "params": params,
+        "harness_fingerprint": harness_fingerprint,
+    }))
+}
+
+/// Hard-links `src` to `dest` with `link`, copying when linking fails (a
+/// different filesystem, or one without hard links).
+fn link_or_copy(
+    src: &Path,
+    dest: &Path,
+    link: impl FnOnce(&Path, &Path) -> io::Result<()>,
+) -> io::Result<Placement> {
+    if !src.is_file() {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
+    if link(src, dest).is_ok() {
+        return Ok(Placement::Linked);
+    }
+    fs::copy(src, dest).map(|_| Placement::Copied)
+}
+
+/// Fills `dest` from `cache` under `key`, or runs `generate` on it and
+/// stores the result; true on a hit. Without a cache this is `generate`.
+pub(crate) fn fetch_or_generate(
+    cache: Option<&DatasetCache>,
+    key: &str,
+    dest: &Path,
+    generate: impl FnOnce(&Path),
+) -> bool {
+    if let Some(cache) = cache {
+        if cache.fetch(key, dest).is_some() {
+            return true;
+        }
+    }
+    generate(dest);
+    if let Some(cache) = cache {
+        if dest.is_file() {
+            let _ = cache.store(key, dest);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("dataset-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keys_change_with_generator_params_and_fingerprint() {
+        let base = key("etl", &json!({"rows": 1000}), "abc");
+        assert_eq!(base, key("etl", &json!({"rows": 1000}), "abc"));
+        assert_ne!(base, key("etl", &json!({"rows": 1001}), "abc"));
+        assert_ne!(base, key("etl", &json!({"rows": 1000}), "abd"));
+        assert_ne!(base, key("json", &json!({"rows": 1000}), "abc"));
+        assert_eq!(base.len(), 16);
+    }
+
+    #[test]
+    fn a_miss_generates_and_stores_then_a_hit_links() {
+        let dir = temp_dir("hit");
+        let cache = DatasetCache::new(dir.join("cache"), 1024 * 1024);
+        let first = dir.join("worktree-a/etl.jsonl.gz");
+        fs::create_dir_all(first.parent().unwrap()).unwrap();
+        let hit = fetch_or_generate(Some(&cache), "k1", &first, |dest| {
+            fs::write(dest, b"rows").unwrap()
+        });
+        assert!(!hit);
+        assert_eq!(fs::read(cache.entry("k1")).unwrap(), b"rows");
+
+        let second = dir.join("worktree-b/etl.jsonl.gz");
+        fs::create_dir_all(second.parent().unwrap()).unwrap();
+        let hit = fetch_or_generate(Some(&cache), "k1", &second, |_| panic!("regenerated"));
+        assert!(hit);
+        assert_eq!(fs::read(&second).unwrap(), b"rows");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let linked = fs::metadata(&second).unwrap();
+            assert_eq!(linked.ino(), fs::metadata(cache.entry("k1")).unwrap().ino());
+        }
+
+        let uncached = dir.join("uncached");
+        assert!(!fetch_or_generate(None, "k1", &uncached, |dest| fs::write(dest, b"x").unwrap()));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_failed_link_falls_back_to_a_copy() {
+        let dir = temp_dir("copy");
+        let src = dir.join("src");
+        fs::write(&src, b"rows").unwrap();
+        let cross_device = |_: &Path, _: &Path| Err(io::Error::from(io::ErrorKind::CrossesDevices));
+        let placed = link_or_copy(&src, &dir.join("dest"), cross_device).unwrap();
+        assert_eq!(placed, Placement::Copied);
+        assert_eq!(fs::read(dir.join("dest")).unwrap(), b"rows");
+        let linked = link_or_copy(&src, &dir.join("linked"), |src, dest| fs::hard_link(src, dest));
+        assert_eq!(linked.unwrap(), Placement::Linked);
+        let missing = link_or_copy(&dir.join("missing"), &dir.join("never"), |_, _| Ok(()));
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(!dir.join("never").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn eviction_drops_least_recently_used_entries_first() {
+        let dir = temp_dir("evict");
+        let cache = DatasetCache::new(dir.clone(), 250);
+        let epoch = SystemTime::UNIX_EPOCH;
+        for (name, age) in [("old", 100), ("recent", 300), ("middle", 200)] {
+            fs::write(dir.join(name), [0u8; 100]).unwrap();
+            let accessed = FileTimes::new().set_accessed(epoch + Duration::from_secs(age));
+            File::open(dir.join(name)).unwrap().set_times(accessed).unwrap();
+        }
+        fs::write(dir.join("k9.123.tmp"), [0u8; 100]).unwrap();
+        assert_eq!(cache.evict(), 1);
+        assert!(!dir.join("old").exists());
+        assert!(dir.join("middle").exists() && dir.join("recent").exists());
+        assert!(dir.join("k9.123.tmp").exists());
+
+        // A hit refreshes the entry, so the other one goes next.
+        assert!(cache.fetch("middle", &dir.join("fetched.tmp")).is_some());
+        let tighter = DatasetCache::new(dir.clone(), 150);
+        assert_eq!(tighter.evict(), 1);
+        assert!(dir.join("middle").exists() && !dir.join("recent").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
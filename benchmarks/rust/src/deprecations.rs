@@ -0,0 +1,201 @@
+//! Renamed environment variables and flags. An old name keeps working
+//! until its removal version, but every run that uses it says so: a
+//! `warning:` line on stderr and an entry in the run summary's
+//! `deprecations` array. `--strict-config` makes any such entry an error,
+//! for CI that wants to catch stale names before they stop working.
+//!
+//! When both names are set the new one wins; the entry is marked as a
+//! `conflict` if the two values differ, since the old one was then silently
+//! ignored before.
+
+use serde::Serialize;
+use std::env;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    EnvVar,
+    Flag,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Deprecation {
+    pub(crate) kind: Kind,
+    pub(crate) old: &'static str,
+    pub(crate) new: &'static str,
+    /// The release that drops `old`.
+    pub(crate) removal: &'static str,
+}
+
+/// Every renamed name. `BENCHMARK_TEST_REPEAT` read too much like
+/// `--repeat`, which repeats the timed benchmarks instead.
+pub(crate) const DEPRECATIONS: [Deprecation; 1] = [Deprecation {
+    kind: Kind::EnvVar,
+    old: "BENCHMARK_TEST_REPEAT",
+    new: "BENCHMARK_TEST_ITERATIONS",
+    removal: "0.3.0",
+}];
+
+/// One use of a deprecated name, as the run summary lists it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct Notice {
+    pub(crate) old: String,
+    pub(crate) new: String,
+    pub(crate) removal: String,
+    /// Both names were set, to different values; the new one was used.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) conflict: bool,
+}
+
+impl Notice {
+    fn of(deprecation: &Deprecation, conflict: bool) -> Self {
+        Notice {
+            old: deprecation.old.to_string(),
+            new: deprecation.new.to_string(),
+            removal: deprecation.removal.to_string(),
+            conflict,
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        let conflict = if self.conflict {
+            format!("; both are set and {} wins", self.new)
+        } else {
+            String::new()
+        };
+        format!(
+            "{} is deprecated and will be removed in {}; use {}{conflict}",
+            self.old, self.removal, self.new
+        )
+    }
+}
+
+/// The value of `new`, else of the name it replaced, read through `get`.
+/// A use of the old name is added to `notices`.
+pub(crate) fn lookup(
+    table: &[Deprecation],
+    new: &str,
+    get: impl Fn(&str) -> Option<String>,
+    notices: &mut Vec<Notice>,
+) -> Option<String> {
+    let current = get(new);
+    let renamed = table
+        .iter()
+        .filter(|deprecation| deprecation.kind == Kind::EnvVar && deprecation.new == new);
+    for deprecation in renamed {
+        let Some(old) = get(deprecation.old) else {
+            continue;
+        };
+        let conflict = current.as_ref().is_some_and(|current| *current != old);
+        notices.push(Notice::of(deprecation, conflict));
+        if current.is_none() {
+            return Some(old);
+        }
+    }
+    current
+}
+
+/// `lookup` in the process environment.
+pub(crate) fn env_var(new: &str, notices: &mut Vec<Notice>) -> Option<String> {
+    lookup(&DEPRECATIONS, new, |name| env::var(name).ok(), notices)
+}
+
+/// The current name of command-line flag `flag`, with a notice when it is
+/// a deprecated one; `None` when it was never renamed.
+pub(crate) fn renamed_flag(table: &[Deprecation], flag: &str) -> Option<(&'static str, Notice)> {
+    table
+        .iter()
+        .find(|deprecation| deprecation.kind == Kind::Flag && deprecation.old == flag)
+        .map(|deprecation| (deprecation.new, Notice::of(deprecation, false)))
+}
+
+/// Prints every notice; under `--strict-config` they are an error instead.
+pub(crate) fn report(notices: &[Notice], strict: bool) -> Result<(), String> {
+    if strict && !notices.is_empty() {
+        let messages: Vec<String> = notices.iter().map(Notice::message).collect();
+        return Err(format!("--strict-config: {}", messages.join("; ")));
+    }
+    for notice in notices {
+        eprintln!("warning: {}", notice.message());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const TABLE: [Deprecation; 2] = [
+        Deprecation {
+            kind: Kind::EnvVar,
+            old: "OLD_ITERATIONS",
+            new: "NEW_ITERATIONS",
+            removal: "0.3.0",
+        },
+        Deprecation {
+            kind: Kind::Flag,
+            old: "--old-flag",
+            new: "--new-flag",
+            removal: "0.4.0",
+        },
+    ];
+
+    fn resolve(vars: &[(&str, &str)]) -> (Option<String>, Vec<Notice>) {
+        let vars: BTreeMap<&str, &str> = vars.iter().copied().collect();
+        let mut notices = Vec::new();
+        let get = |name: &str| vars.get(name).map(|value| value.to_string());
+        let value = lookup(&TABLE, "NEW_ITERATIONS", get, &mut notices);
+        (value, notices)
+    }
+
+    #[test]
+    fn old_names_still_resolve_with_a_notice() {
+        let (value, notices) = resolve(&[("OLD_ITERATIONS", "7")]);
+        assert_eq!(value.as_deref(), Some("7"));
+        assert_eq!(notices, [Notice::of(&TABLE[0], false)]);
+        assert_eq!(
+            notices[0].message(),
+            "OLD_ITERATIONS is deprecated and will be removed in 0.3.0; use NEW_ITERATIONS"
+        );
+
+        let (value, notices) = resolve(&[("NEW_ITERATIONS", "5")]);
+        assert_eq!(value.as_deref(), Some("5"));
+        assert!(notices.is_empty());
+
+        assert_eq!(resolve(&[]), (None, Vec::new()));
+    }
+
+    #[test]
+    fn the_new_name_wins_when_both_are_set() {
+        let (value, notices) = resolve(&[("NEW_ITERATIONS", "5"), ("OLD_ITERATIONS", "7")]);
+        assert_eq!(value.as_deref(), Some("5"));
+        assert!(notices[0].conflict);
+        assert!(notices[0].message().ends_with("; both are set and NEW_ITERATIONS wins"));
+        let summary = serde_json::to_value(&notices).unwrap();
+        assert_eq!(summary[0]["conflict"], true);
+        assert_eq!(summary[0]["removal"], "0.3.0");
+
+        // Still deprecated, but nothing was ignored.
+        let (_, notices) = resolve(&[("NEW_ITERATIONS", "5"), ("OLD_ITERATIONS", "5")]);
+        assert!(!notices[0].conflict);
+        assert!(serde_json::to_value(&notices).unwrap()[0].get("conflict").is_none());
+    }
+
+    #[test]
+    fn flags_are_renamed_and_strict_mode_fails() {
+        let (new, notice) = renamed_flag(&TABLE, "--old-flag").unwrap();
+        assert_eq!((new, notice.removal.as_str()), ("--new-flag", "0.4.0"));
+        assert!(renamed_flag(&TABLE, "--new-flag").is_none());
+        // Env var entries do not rename flags, nor flag entries env vars.
+        assert!(renamed_flag(&TABLE, "OLD_ITERATIONS").is_none());
+        let mut notices = Vec::new();
+        let get = |name: &str| (name == "--old-flag").then(|| "1".to_string());
+        assert_eq!(lookup(&TABLE, "--new-flag", get, &mut notices), None);
+
+        assert_eq!(report(&[], true), Ok(()));
+        let notices = [notice];
+        assert_eq!(report(&notices, false), Ok(()));
+        let err = report(&notices, true).unwrap_err();
+        assert!(err.starts_with("--strict-config: --old-flag is deprecated"), "{err}");
+    }
+}
@@ -0,0 +1,419 @@
+//! Read-ahead file reading for `data_pipeline_etl_async`. `ReadAhead` keeps
+//! up to `depth` chunk reads in flight on a `ReadQueue` and hands the bytes
+//! out in file order as an ordinary `BufRead`, so the gzip decoder, line
+//! reader, and aggregation are the code the sync benchmark runs. Reads may
+//! complete in any order; each lands in its own slot buffer and waits there
+//! until everything before it has been consumed, then the slot is reused
+//! for the next chunk.
+//!
+//! The queue is io_uring, behind the Linux-only `io-uring` feature. In other
+//! builds `open` reports the platform unsupported and the benchmark emits a
+//! skipped record.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+
+/// Reads in flight when the benchmark is not told otherwise.
+pub(crate) const DEFAULT_READ_AHEAD_DEPTH: usize = 8;
+/// Size of one read, in KiB.
+pub(crate) const DEFAULT_CHUNK_KB: usize = 64;
+
+/// Somewhere to submit positioned reads and collect their completions.
+pub(crate) trait ReadQueue {
+    /// Starts reading up to `len` bytes at `offset` into `buf`, tagged with
+    /// `slot`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for `len` bytes, and untouched, until `wait`
+    /// has returned `slot`.
+    unsafe fn submit(
+        &mut self,
+        slot: usize,
+        buf: *mut u8,
+        len: usize,
+        offset: u64,
+    ) -> io::Result<()>;
+
+    /// Blocks until some submitted read finishes; its slot and byte count.
+    fn wait(&mut self) -> io::Result<(usize, io::Result<usize>)>;
+}
+
+struct Slot {
+    buf: Box<[u8]>,
+    /// File offset of `buf[0]`.
+    offset: u64,
+    /// Bytes this chunk covers.
+    wanted: usize,
+    filled: usize,
+    in_flight: bool,
+}
+
+pub(crate) struct ReadAhead<Q: ReadQueue> {
+    queue: Q,
+    slots: Vec<Slot>,
+    /// Slots holding or awaiting data, in file order.
+    order: VecDeque<usize>,
+    free: Vec<usize>,
+    file_len: u64,
+    next_offset: u64,
+    /// The slot being consumed and the position in it.
+    current: Option<(usize, usize)>,
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+impl<Q: ReadQueue> ReadAhead<Q> {
+    /// Reads `file_len` bytes through `queue`, `depth` chunks of `chunk`
+    /// bytes ahead of the consumer.
+    pub(crate) fn new(queue: Q, file_len: u64, depth: usize, chunk: usize) -> io::Result<Self> {
+        let depth = depth.max(1);
+        let slots = (0..depth)
+            .map(|_| Slot {
+                buf: vec![0; chunk.max(1)].into_boxed_slice(),
+                offset: 0,
+                wanted: 0,
+                filled: 0,
+                in_flight: false,
+            })
+            .collect();
+        let mut reader = ReadAhead {
+            queue,
+            slots,
+            order: VecDeque::with_capacity(depth),
+            free: (0..depth).rev().collect(),
+            file_len,
+            next_offset: 0,
+            current: None,
+            in_flight: 0,
+            max_in_flight: 0,
+        };
+        reader.refill()?;
+        Ok(reader)
+    }
+
+    /// The most reads that were in flight at once: the queue depth the run
+    /// actually reached.
+    pub(crate) fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Puts every free slot to work on the next unread chunk.
+    fn refill(&mut self) -> io::Result<()> {
+        while self.next_offset < self.file_len {
+            let Some(index) = self.free.pop() else {
+                break;
+            };
+            let slot = &mut self.slots[index];
+            slot.offset = self.next_offset;
+            slot.wanted = (self.file_len - self.next_offset).min(slot.buf.len() as u64) as usize;
+            slot.filled = 0;
+            self.next_offset += slot.wanted as u64;
+            self.order.push_back(index);
+            self.submit(index)?;
+        }
+        Ok(())
+    }
+
+    /// Submits the unfilled rest of slot `index`.
+    fn submit(&mut self, index: usize) -> io::Result<()> {
+        let slot = &mut self.slots[index];
+        let len = slot.wanted - slot.filled;
+        let offset = slot.offset + slot.filled as u64;
+        // SAFETY: the slot's buffer is a boxed slice that is neither moved
+        // nor touched while `in_flight` is set, and `Drop` waits for every
+        // in-flight read before the buffers are freed.
+        let buf = unsafe { slot.buf.as_mut_ptr().add(slot.filled) };
+        // SAFETY: see above; `len` stays within the buffer.
+        unsafe { self.queue.submit(index, buf, len, offset) }?;
+        slot.in_flight = true;
+        self.in_flight += 1;
+        self.max_in_flight = self.max_in_flight.max(self.in_flight);
+        Ok(())
+    }
+
+    /// Collects completions, resubmitting short reads, until slot `index`
+    /// is full.
+    fn wait_for(&mut self, index: usize) -> io::Result<()> {
+        while self.slots[index].in_flight {
+            let (done, result) = self.queue.wait()?;
+            self.in_flight -= 1;
+            let slot = &mut self.slots[done];
+            slot.in_flight = false;
+            match result? {
+                0 => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "file ended before its reported length",
+                    ))
+                }
+                read => slot.filled += read,
+            }
+            if slot.filled < slot.wanted {
+                self.submit(done)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits out every read in flight, discarding the data.
+    fn reap(&mut self) {
+        while self.in_flight > 0 {
+            if self.queue.wait().is_err() {
+                // Nothing more will complete; leaking beats a use after free.
+                std::mem::forget(std::mem::take(&mut self.slots));
+                return;
+            }
+            self.in_flight -= 1;
+        }
+    }
+}
+
+impl<Q: ReadQueue> BufRead for ReadAhead<Q> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            if let Some((index, pos)) = self.current {
+                if pos < self.slots[index].filled {
+                    break;
+                }
+                self.current = None;
+                self.free.push(index);
+                self.refill()?;
+            }
+            let Some(index) = self.order.pop_front() else {
+                return Ok(&[]);
+            };
+            self.wait_for(index)?;
+            self.current = Some((index, 0));
+        }
+        let (index, pos) = self.current.expect("set by the loop above");
+        let slot = &self.slots[index];
+        Ok(&slot.buf[pos..slot.filled])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some((_, pos)) = &mut self.current {
+            *pos += amount;
+        }
+    }
+}
+
+impl<Q: ReadQueue> Read for ReadAhead<Q> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<Q: ReadQueue> Drop for ReadAhead<Q> {
+    /// A consumer that stops early (a gzip trailer, an error) leaves reads
+    /// in flight; their buffers must outlive them.
+    fn drop(&mut self) {
+        self.reap();
+    }
+}
+
+/// Opens `path` for read-ahead on this platform's queue.
+pub(crate) fn open(
+    path: &Path,
+    depth: usize,
+    chunk: usize,
+) -> io::Result<ReadAhead<PlatformQueue>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    ReadAhead::new(PlatformQueue::new(file, depth)?, file_len, depth, chunk)
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub(crate) use uring::UringQueue as PlatformQueue;
+
+/// True when `open` can work here.
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub(crate) fn supported() -> bool {
+    // Kernels before 5.1, and sandboxes that filter the syscalls, refuse.
+    io_uring::IoUring::new(2).is_ok()
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring {
+    use io_uring::{opcode, types, IoUring};
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    use super::ReadQueue;
+
+    pub(crate) struct UringQueue {
+        ring: IoUring,
+        file: File,
+    }
+
+    impl UringQueue {
+        pub(crate) fn new(file: File, depth: usize) -> io::Result<Self> {
+            let entries = depth.max(1).next_power_of_two() as u32;
+            Ok(UringQueue {
+                ring: IoUring::new(entries)?,
+                file,
+            })
+        }
+    }
+
+    impl ReadQueue for UringQueue {
+        unsafe fn submit(
+            &mut self,
+            slot: usize,
+            buf: *mut u8,
+            len: usize,
+            offset: u64,
+        ) -> io::Result<()> {
+            let entry = opcode::Read::new(types::Fd(self.file.as_raw_fd()), buf, len as u32)
+                .offset(offset)
+                .build()
+                .user_data(slot as u64);
+            // SAFETY: the caller keeps `buf` valid until the completion is
+            // reaped; the ring has a submission entry per slot.
+            unsafe { self.ring.submission().push(&entry) }
+                .map_err(|_| io::Error::other("io_uring submission queue full"))?;
+            self.ring.submit()?;
+            Ok(())
+        }
+
+        fn wait(&mut self) -> io::Result<(usize, io::Result<usize>)> {
+            loop {
+                if let Some(completion) = self.ring.completion().next() {
+                    let result = completion.result();
+                    let read = if result < 0 {
+                        Err(io::Error::from_raw_os_error(-result))
+                    } else {
+                        Ok(result as usize)
+                    };
+                    return Ok((completion.user_data() as usize, read));
+                }
+                self.ring.submit_and_wait(1)?;
+            }
+        }
+    }
+}
+
+/// No queue exists in this build; `new` always fails, so nothing is ever
+/// submitted.
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+pub(crate) enum PlatformQueue {}
+
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+impl PlatformQueue {
+    fn new(_file: File, _depth: usize) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "async file reads need Linux and the io-uring feature",
+        ))
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+impl ReadQueue for PlatformQueue {
+    unsafe fn submit(&mut self, _: usize, _: *mut u8, _: usize, _: u64) -> io::Result<()> {
+        match *self {}
+    }
+
+    fn wait(&mut self) -> io::Result<(usize, io::Result<usize>)> {
+        match *self {}
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+pub(crate) fn supported() -> bool {
+    false
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Completes the most recent submission first, and at most `max_read`
+    /// bytes of it, so every chunk arrives out of order and in pieces.
+    pub(crate) struct ShuffledQueue {
+        pub(crate) data: Vec<u8>,
+        pub(crate) max_read: usize,
+        pending: Vec<(usize, *mut u8, usize, u64)>,
+    }
+
+    impl ShuffledQueue {
+        pub(crate) fn new(data: Vec<u8>, max_read: usize) -> Self {
+            ShuffledQueue {
+                data,
+                max_read,
+                pending: Vec::new(),
+            }
+        }
+    }
+
+    impl ReadQueue for ShuffledQueue {
+        unsafe fn submit(
+            &mut self,
+            slot: usize,
+            buf: *mut u8,
+            len: usize,
+            offset: u64,
+        ) -> io::Result<()> {
+            self.pending.push((slot, buf, len, offset));
+            Ok(())
+        }
+
+        fn wait(&mut self) -> io::Result<(usize, io::Result<usize>)> {
+            let (slot, buf, len, offset) = self.pending.pop().expect("a read is in flight");
+            let start = (offset as usize).min(self.data.len());
+            let read = len.min(self.max_read).min(self.data.len() - start);
+            // SAFETY: `submit`'s contract keeps `buf` valid for `len` bytes.
+            unsafe { std::ptr::copy_nonoverlapping(self.data[start..].as_ptr(), buf, read) };
+            Ok((slot, Ok(read)))
+        }
+    }
+
+    fn read_all<Q: ReadQueue>(mut reader: ReadAhead<Q>) -> (Vec<u8>, usize) {
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        (out, reader.max_in_flight())
+    }
+
+    #[test]
+    fn delivers_out_of_order_completions_in_file_order() {
+        let data: Vec<u8> = (0..10_000u32).map(|idx| (idx % 251) as u8).collect();
+        let len = data.len() as u64;
+        for (depth, chunk, max_read) in [
+            (1, 4096, 4096),
+            (4, 1000, 1000),
+            (8, 333, 100),
+            (16, 64 * 1024, 7),
+        ] {
+            let queue = ShuffledQueue::new(data.clone(), max_read);
+            let (out, in_flight) = read_all(ReadAhead::new(queue, len, depth, chunk).unwrap());
+            assert_eq!(out, data, "depth {depth}, chunk {chunk}");
+            assert_eq!(in_flight, depth.min(data.len().div_ceil(chunk)));
+        }
+        let empty = ReadAhead::new(ShuffledQueue::new(Vec::new(), 10), 0, 4, 10).unwrap();
+        assert_eq!(read_all(empty), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn a_file_shorter_than_reported_is_an_error() {
+        let queue = ShuffledQueue::new(vec![1; 100], 100);
+        let mut reader = ReadAhead::new(queue, 250, 2, 100).unwrap();
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn stopping_early_reaps_reads_in_flight() {
+        let queue = ShuffledQueue::new(vec![7; 1000], 1000);
+        let mut reader = ReadAhead::new(queue, 1000, 4, 100).unwrap();
+        assert_eq!((reader.in_flight, reader.queue.pending.len()), (4, 4));
+        reader.reap();
+        assert_eq!((reader.in_flight, reader.queue.pending.len()), (0, 0));
+    }
+}
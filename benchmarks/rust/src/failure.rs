@@ -0,0 +1,106 @@
+//! Context for failed benchmark records, so a failure reported from the
+//! field carries enough to file a bug. A record with a failed phase, or
+//! whose benchmark panicked, gets a `failure` block: the phase it failed
+//! in, its effective parameters, the last live progress samples, and for a
+//! panic the message and, with `--backtraces`, a backtrace.
+//!
+//! Backtraces are captured by a panic hook, since the unwound stack is gone
+//! by the time `catch_unwind` returns. The hook is only installed on
+//! request, so a run without the flag pays nothing and `RUST_BACKTRACE` in
+//! the environment does not change what records contain.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic;
+use std::sync::Once;
+
+thread_local! {
+    /// The backtrace of this thread's latest panic, until it is taken.
+    static BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// `--backtraces`: every later panic captures its backtrace before the
+/// default hook reports it as usual. Installing twice is harmless.
+pub(crate) fn capture_backtraces() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let trace = Backtrace::force_capture().to_string();
+            BACKTRACE.with(|slot| *slot.borrow_mut() = Some(trace));
+            previous(info);
+        }));
+    });
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct PanicReport {
+    pub(crate) message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) backtrace: Option<String>,
+}
+
+impl PanicReport {
+    /// Reads a `catch_unwind` payload; call it on the thread that panicked.
+    pub(crate) fn from_payload(payload: &(dyn Any + Send)) -> PanicReport {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "non-string panic payload".to_string());
+        PanicReport {
+            message,
+            backtrace: BACKTRACE.with(|slot| slot.borrow_mut().take()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FailureContext<'a> {
+    pub(crate) phase: &'a str,
+    pub(crate) parameters: &'a Map<String, Value>,
+    /// Oldest first; empty without `--live-metrics`.
+    pub(crate) recent_progress: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) panic: Option<PanicReport>,
+}
+
+impl FailureContext<'_> {
+    pub(crate) fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("failure context serializes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_payloads_become_messages_with_a_captured_backtrace() {
+        capture_backtraces();
+        let payload = panic::catch_unwind(|| panic!("dataset {} missing", 3)).unwrap_err();
+        let report = PanicReport::from_payload(payload.as_ref());
+        assert_eq!(report.message, "dataset 3 missing");
+        assert!(!report.backtrace.unwrap().is_empty());
+        // Taken with the report, so it cannot leak into the next one.
+        assert!(BACKTRACE.with(|slot| slot.borrow().is_none()));
+        let payload = panic::catch_unwind(|| std::panic::panic_any(7)).unwrap_err();
+        let report = PanicReport::from_payload(payload.as_ref());
+        assert_eq!(report.message, "non-string panic payload");
+        assert!(report.backtrace.is_some());
+
+        let parameters = Map::new();
+        let context = FailureContext {
+            phase: "measure",
+            parameters: &parameters,
+            recent_progress: Vec::new(),
+            panic: None,
+        };
+        let value = context.to_value();
+        assert_eq!(value["phase"], "measure");
+        assert!(value.get("panic").is_none());
+    }
+}
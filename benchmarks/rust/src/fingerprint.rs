@@ -0,0 +1,115 @@
+//! The harness fingerprint: one hash over what makes results from two
+//! builds comparable. It covers every registered benchmark id, the
+//! compiled-in default parameters, and a per-benchmark logic version, so a
+//! changed default or a bumped version shows up as a different fingerprint
+//! on every record. Overrides from the environment are deliberately left
+//! out; `params_hash` already tells those runs apart.
+
+use serde_json::{json, Value};
+
+use crate::benchmark_ids::{
+    self, BUILD_STARTUP_FEEDBACK_LOOP, CONCURRENCY_ASYNC_TASKS, CPU_MONTE_CARLO_PI,
+    CPU_MONTE_CARLO_PI_PARALLEL, CPU_SLEEP_PRECISION, DATA_PIPELINE_ETL_ASYNC,
+    DATA_PIPELINE_ETL_MINIBATCH, DEPENDENCY_VULNERABILITY_SCAN_SCORECARD, IO_CONCURRENT_HTTP_CLIENT,
+    IO_CONCURRENT_HTTP_POST, IO_HTTP_CONNECTION_CHURN, IO_HTTP_JITTER_SENSITIVITY,
+    IO_HTTP_STREAMING, STATIC_SECURITY_LINT_BENCHMARK, STRING_JSON_PARSE_TRANSFORM,
+    TEST_ROBUSTNESS_RELIABILITY,
+};
+use crate::checksum_store::params_hash;
+use crate::config::BenchmarkConfigs;
+
+/// Bump a benchmark's version when its measurement logic changes in a way
+/// that makes older results incomparable: a new RNG, a different
+/// aggregation formula, a changed unit. Parameters are hashed separately.
+pub(crate) const LOGIC_VERSIONS: [(&str, u32); 16] = [
+    (CPU_MONTE_CARLO_PI, 1),
+    (CPU_MONTE_CARLO_PI_PARALLEL, 1),
+    (CPU_SLEEP_PRECISION, 1),
+    (STRING_JSON_PARSE_TRANSFORM, 1),
+    (IO_CONCURRENT_HTTP_CLIENT, 2),
+    (IO_CONCURRENT_HTTP_POST, 1),
+    (IO_HTTP_CONNECTION_CHURN, 1),
+    (IO_HTTP_JITTER_SENSITIVITY, 1),
+    (IO_HTTP_STREAMING, 1),
+    (DATA_PIPELINE_ETL_MINIBATCH, 1),
+    (DATA_PIPELINE_ETL_ASYNC, 1),
+    (CONCURRENCY_ASYNC_TASKS, 1),
+    (DEPENDENCY_VULNERABILITY_SCAN_SCORECARD, 1),
+    (STATIC_SECURITY_LINT_BENCHMARK, 1),
+    (TEST_ROBUSTNESS_RELIABILITY, 1),
+    (BUILD_STARTUP_FEEDBACK_LOOP, 1),
+];
+
+/// What to do when a baseline was recorded by a different harness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum MismatchPolicy {
+    /// Compare anyway, with a warning.
+    #[default]
+    Warn,
+    /// Do not compare; the current run becomes the new baseline.
+    Refuse,
+}
+
+impl MismatchPolicy {
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "warn" => Ok(MismatchPolicy::Warn),
+            "refuse" => Ok(MismatchPolicy::Refuse),
+            _ => Err(format!("expected warn or refuse, got {value:?}")),
+        }
+    }
+}
+
+/// The fingerprint of this build, from `BenchmarkConfigs::default()`.
+pub(crate) fn harness_fingerprint() -> String {
+    fingerprint(&BenchmarkConfigs::default(), &LOGIC_VERSIONS)
+}
+
+fn fingerprint(defaults: &BenchmarkConfigs, versions: &[(&str, u32)]) -> String {
+    let entries: Vec<Value> = benchmark_ids::ALL
+        .iter()
+        .map(|id| {
+            let version = versions
+                .iter()
+                .find(|(versioned, _)| versioned == id)
+                .map_or(0, |(_, version)| *version);
+            json!({"id": id, "logic_version": version, "defaults": defaults.params_for(id)})
+        })
+        .collect();
+    params_hash(&Value::Array(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_benchmark_has_one_logic_version() {
+        let versioned: Vec<&str> = LOGIC_VERSIONS.iter().map(|(id, _)| *id).collect();
+        assert_eq!(versioned, benchmark_ids::ALL);
+        assert!(LOGIC_VERSIONS.iter().all(|(_, version)| *version > 0));
+    }
+
+    #[test]
+    fn defaults_and_versions_change_the_fingerprint() {
+        let defaults = BenchmarkConfigs::default();
+        let current = fingerprint(&defaults, &LOGIC_VERSIONS);
+        assert_eq!(current, harness_fingerprint());
+        assert_eq!(current.len(), 16);
+
+        let mut changed = BenchmarkConfigs::default();
+        changed.etl.rows += 1;
+        assert_ne!(fingerprint(&changed, &LOGIC_VERSIONS), current);
+
+        let mut bumped = LOGIC_VERSIONS;
+        bumped[0].1 += 1;
+        assert_ne!(fingerprint(&defaults, &bumped), current);
+    }
+
+    #[test]
+    fn parses_mismatch_policies() {
+        assert_eq!(MismatchPolicy::parse("warn"), Ok(MismatchPolicy::Warn));
+        assert_eq!(MismatchPolicy::parse("refuse"), Ok(MismatchPolicy::Refuse));
+        assert!(MismatchPolicy::parse("ignore").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Where the HTTP fixture sits relative to the client, measured before the
+//! timed phase: the same requests per second mean different things over
+//! loopback, across a rack, and through a VPN. The probe opens its own TCP
+//! connections with the client's connect code and reports through its own
+//! `fixture_*` metrics, so nothing it does reaches the benchmark's counts.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+use crate::http_client::{connect_tcp, ParsedUrl};
+use crate::platform;
+use crate::stats::{Rank, Samples};
+
+/// Connects timed per probe; the RTT reported is their median.
+pub(crate) const RTT_CONNECTS: usize = 10;
+
+/// How far the fixture's address is from this machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Locality {
+    /// This machine: loopback, or the unspecified address that connects
+    /// to it.
+    Loopback,
+    /// A private, link-local, unique local, or carrier-grade NAT address,
+    /// the last covering most VPN overlays.
+    Private,
+    Public,
+}
+
+impl Locality {
+    pub(crate) fn of(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => Self::of_v4(ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => Self::of_v4(ip),
+                None => Self::of_v6(ip),
+            },
+        }
+    }
+
+    fn of_v4(ip: Ipv4Addr) -> Self {
+        let [first, second, ..] = ip.octets();
+        let shared = first == 100 && (64..128).contains(&second);
+        if ip.is_loopback() || ip.is_unspecified() {
+            Locality::Loopback
+        } else if ip.is_private() || ip.is_link_local() || shared {
+            Locality::Private
+        } else {
+            Locality::Public
+        }
+    }
+
+    fn of_v6(ip: Ipv6Addr) -> Self {
+        let first = ip.segments()[0];
+        let unique_local = first & 0xfe00 == 0xfc00;
+        let link_local = first & 0xffc0 == 0xfe80;
+        if ip.is_loopback() || ip.is_unspecified() {
+            Locality::Loopback
+        } else if unique_local || link_local {
+            Locality::Private
+        } else {
+            Locality::Public
+        }
+    }
+
+    /// The record's `diagnostics.fixture_locality`.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Locality::Loopback => "loopback",
+            Locality::Private => "private",
+            Locality::Public => "public",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct FixtureProbe {
+    /// Of the address the first connect reached.
+    pub(crate) locality: Locality,
+    /// One per successful connect.
+    pub(crate) rtt_ms: Samples,
+    pub(crate) mtu_bytes: Option<u32>,
+}
+
+impl FixtureProbe {
+    pub(crate) fn median_rtt_ms(&self) -> f64 {
+        self.rtt_ms.percentile(50.0, Rank::Linear)
+    }
+}
+
+/// Times `connects` TCP connects to `target`; `None` when none succeeds.
+/// The host is resolved once, so name lookups stay out of the timings.
+pub(crate) fn probe(
+    target: &ParsedUrl,
+    connects: usize,
+    timeout: Duration,
+) -> Option<FixtureProbe> {
+    let stream = connect_tcp(&target.host, target.port, timeout).ok()?;
+    let address = stream.peer_addr().ok()?;
+    let mtu_bytes = platform::socket_mtu(&stream);
+    drop(stream);
+    let host = address.ip().to_string();
+    let mut rtt_ms = Vec::with_capacity(connects);
+    for _ in 0..connects {
+        let start = Instant::now();
+        if connect_tcp(&host, address.port(), timeout).is_ok() {
+            rtt_ms.push(start.elapsed().as_secs_f64() * 1e3);
+        }
+    }
+    if rtt_ms.is_empty() {
+        return None;
+    }
+    Some(FixtureProbe {
+        locality: Locality::of(address.ip()),
+        rtt_ms: Samples::from(rtt_ms),
+        mtu_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::http_client::{parse_base_url, DEFAULT_TIMEOUT};
+    use crate::mock_server::{ChaosConfig, MockServer};
+    use crate::DEFAULT_SEED;
+
+    #[test]
+    fn classifies_addresses() {
+        let cases = [
+            ("127.0.0.1", Locality::Loopback),
+            ("127.8.9.10", Locality::Loopback),
+            ("0.0.0.0", Locality::Loopback),
+            ("::1", Locality::Loopback),
+            ("::", Locality::Loopback),
+            ("::ffff:127.0.0.1", Locality::Loopback),
+            ("10.1.2.3", Locality::Private),
+            ("172.16.0.1", Locality::Private),
+            ("172.31.255.255", Locality::Private),
+            ("192.168.1.20", Locality::Private),
+            ("169.254.10.10", Locality::Private),
+            ("100.64.0.1", Locality::Private),
+            ("100.127.255.254", Locality::Private),
+            ("::ffff:192.168.0.1", Locality::Private),
+            ("fd12:3456::1", Locality::Private),
+            ("fc00::1", Locality::Private),
+            ("fe80::1", Locality::Private),
+            ("febf::1", Locality::Private),
+            ("8.8.8.8", Locality::Public),
+            ("172.32.0.1", Locality::Public),
+            ("100.128.0.1", Locality::Public),
+            ("192.169.0.1", Locality::Public),
+            ("2001:4860:4860::8888", Locality::Public),
+            ("fec0::1", Locality::Public),
+        ];
+        for (ip, expected) in cases {
+            assert_eq!(Locality::of(ip.parse().unwrap()), expected, "{ip}");
+        }
+        assert_eq!(Locality::Loopback.label(), "loopback");
+        assert_eq!(Locality::Public.label(), "public");
+    }
+
+    #[test]
+    fn samples_connect_rtt_against_the_mock_server() {
+        let server = MockServer::start(ChaosConfig::default(), DEFAULT_SEED).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        let probe = probe(&target, RTT_CONNECTS, DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(probe.rtt_ms.len(), RTT_CONNECTS);
+        assert_eq!(probe.locality, Locality::Loopback);
+        let median = probe.median_rtt_ms();
+        assert!(median > 0.0 && median <= probe.rtt_ms.max(), "{median}");
+        if cfg!(target_os = "linux") {
+            assert!(probe.mtu_bytes.is_some_and(|mtu| mtu >= 1280), "{:?}", probe.mtu_bytes);
+        }
+
+        let closed = format!("http://127.0.0.1:{}", fixtures::closed_port());
+        let closed = parse_base_url(&closed).unwrap();
+        assert!(super::probe(&closed, RTT_CONNECTS, DEFAULT_TIMEOUT).is_none());
+    }
+}
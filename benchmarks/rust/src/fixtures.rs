@@ -0,0 +1,291 @@
+//! Test fixtures in one place. Text captured from real tools is embedded at
+//! compile time, so tests pass from any working directory; everything
+//! binary or parameterized (gzip streams, HTTP byte streams, audit reports)
+//! is generated here rather than checked in as opaque blobs. `MANIFEST`
+//! pins a checksum per fixture, so a changed generator or edited file fails
+//! one named test instead of drifting silently.
+//!
+//! Compiled for tests only; a runtime self-check would reuse the same
+//! accessors rather than carry its own copies.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::checksum_store::fnv1a;
+use crate::etl_row;
+
+/// `cargo metadata` for a three-member workspace rooted at `/work/demo`.
+pub(crate) fn clippy_metadata() -> &'static str {
+    include_str!("../fixtures/clippy/metadata.json")
+}
+
+/// Clippy messages for the `clippy_metadata` workspace plus two from
+/// dependencies.
+pub(crate) fn clippy_workspace_messages() -> &'static str {
+    include_str!("../fixtures/clippy/workspace_messages.jsonl")
+}
+
+/// Clippy messages covering every suggestion applicability.
+pub(crate) fn clippy_suggestions() -> &'static str {
+    include_str!("../fixtures/clippy/suggestions.jsonl")
+}
+
+/// Registered benchmark ids, one per line in run order.
+pub(crate) fn benchmark_ids() -> &'static str {
+    include_str!("../fixtures/benchmark_ids.txt")
+}
+
+/// A lockfile with a workspace member and three registry packages, two of
+/// them in `scan_osv`'s affected ranges.
+pub(crate) fn scan_lockfile() -> &'static str {
+    include_str!("../fixtures/builtin_scan/Cargo.lock")
+}
+
+/// An OSV snapshot of made-up advisories: two live, one withdrawn, and a
+/// non-crates.io package.
+pub(crate) fn scan_osv() -> &'static str {
+    include_str!("../fixtures/builtin_scan/osv.json")
+}
+
+/// `scan_osv` on disk, for code that loads snapshots by path.
+pub(crate) fn scan_osv_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/builtin_scan/osv.json")
+}
+
+/// Records from one run of the Python harness: CPU, HTTP (nothing
+/// listening), a dependency scan and the build loop.
+pub(crate) fn python_records() -> &'static str {
+    include_str!("../fixtures/python/records.json")
+}
+
+pub(crate) fn proc_vmstat() -> &'static str {
+    include_str!("../fixtures/proc/vmstat")
+}
+
+pub(crate) fn proc_meminfo() -> &'static str {
+    include_str!("../fixtures/proc/meminfo")
+}
+
+/// `/sys/kernel/mm/transparent_hugepage/enabled` in madvise mode.
+pub(crate) fn proc_thp_enabled() -> &'static str {
+    include_str!("../fixtures/proc/thp_enabled")
+}
+
+pub(crate) fn proc_smaps_rollup() -> &'static str {
+    include_str!("../fixtures/proc/smaps_rollup")
+}
+
+/// `/proc/self/status` after a benchmark peaked at 183 MB and freed most of it.
+pub(crate) fn proc_status() -> &'static str {
+    include_str!("../fixtures/proc/status")
+}
+
+/// Three mappings of `/proc/self/smaps`; only the second is THP-eligible.
+pub(crate) fn proc_smaps() -> &'static str {
+    include_str!("../fixtures/proc/smaps")
+}
+
+/// Stable `cargo test` stdout for a unit-test binary, an integration test
+/// binary with one failure, and empty doc tests.
+pub(crate) fn cargo_test_stable() -> &'static str {
+    include_str!("../fixtures/cargo_test/stable.txt")
+}
+
+/// The same run as nightly `--format json --report-time` prints it, doc
+/// tests left out.
+pub(crate) fn cargo_test_json() -> &'static str {
+    include_str!("../fixtures/cargo_test/json.jsonl")
+}
+
+/// A `cargo audit --json` report with `findings` vulnerabilities; `count`
+/// overrides the reported count as a string, the way a tool ignoring the C
+/// locale prints it.
+pub(crate) fn audit_report(findings: usize, count: Option<&str>) -> String {
+    let list: Vec<_> = (0..findings)
+        .map(|idx| json!({"advisory": {"id": format!("RUSTSEC-2024-{:04}", idx + 1)}}))
+        .collect();
+    let count = count.map_or_else(|| json!(findings), |raw| json!(raw));
+    json!({
+        "database": {"advisory-count": 600},
+        "vulnerabilities": {"found": findings > 0, "count": count, "list": list},
+        "warnings": {}
+    })
+    .to_string()
+}
+
+/// A complete `Content-Length` response.
+pub(crate) fn http_response(status: u16, reason: &str, body: &str) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .into_bytes()
+}
+
+/// A local port that nothing listens on: one the kernel just handed out
+/// and took back, so a connect to it is refused rather than relying on a
+/// well-known port being closed.
+pub(crate) fn closed_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Whether file permissions bind this process. Root writes through a
+/// read-only directory, so tests that rely on one being refused skip.
+pub(crate) fn permissions_enforced() -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        unsafe { libc::geteuid() != 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        true
+    }
+}
+
+/// A `Transfer-Encoding: chunked` response carrying `chunks` in order.
+pub(crate) fn chunked_response(chunks: &[&str]) -> Vec<u8> {
+    let mut bytes = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    for chunk in chunks {
+        bytes.extend_from_slice(format!("{:x}\r\n{chunk}\r\n", chunk.len()).as_bytes());
+    }
+    bytes.extend_from_slice(b"0\r\n\r\n");
+    bytes
+}
+
+/// ETL rows as `build_etl_dataset` writes them, gzip-compressed; with
+/// `truncate_to`, the stream is cut after that many bytes, as a download or
+/// disk-full would leave it.
+pub(crate) fn etl_gzip(rows: usize, truncate_to: Option<usize>) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for idx in 0..rows {
+        writeln!(encoder, "{}", etl_row(idx)).expect("write to memory");
+    }
+    let mut bytes = encoder.finish().expect("finish in memory");
+    if let Some(len) = truncate_to {
+        bytes.truncate(len);
+    }
+    bytes
+}
+
+/// `etl_gzip` with row `at` padded to `len` bytes (newline excluded); the
+/// row stays valid, so only a line cap keeps it out of the aggregate.
+pub(crate) fn etl_gzip_with_giant_line(rows: usize, at: usize, len: usize) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for idx in 0..rows {
+        let mut row = etl_row(idx);
+        if idx == at {
+            let unpadded = row.to_string().len() + ",\"pad\":\"\"".len();
+            row["pad"] = json!("x".repeat(len.saturating_sub(unpadded)));
+        }
+        writeln!(encoder, "{row}").expect("write to memory");
+    }
+    encoder.finish().expect("finish in memory")
+}
+
+/// Every fixture by name, in `MANIFEST` order.
+pub(crate) fn catalog() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("clippy_metadata", clippy_metadata().into()),
+        ("clippy_workspace_messages", clippy_workspace_messages().into()),
+        ("clippy_suggestions", clippy_suggestions().into()),
+        ("benchmark_ids", benchmark_ids().into()),
+        ("scan_lockfile", scan_lockfile().into()),
+        ("scan_osv", scan_osv().into()),
+        ("python_records", python_records().into()),
+        ("proc_vmstat", proc_vmstat().into()),
+        ("proc_meminfo", proc_meminfo().into()),
+        ("proc_thp_enabled", proc_thp_enabled().into()),
+        ("proc_smaps_rollup", proc_smaps_rollup().into()),
+        ("proc_smaps", proc_smaps().into()),
+        ("proc_status", proc_status().into()),
+        ("cargo_test_stable", cargo_test_stable().into()),
+        ("cargo_test_json", cargo_test_json().into()),
+        ("audit_report", audit_report(3, None).into_bytes()),
+        ("http_response", http_response(200, "OK", "{\"value\":42}")),
+        ("chunked_response", chunked_response(&["{\"val", "ue\":42}"])),
+        ("etl_gzip", etl_gzip(200, None)),
+        ("etl_gzip_truncated", etl_gzip(200, Some(600))),
+        ("etl_gzip_giant_line", etl_gzip_with_giant_line(200, 100, 4096)),
+    ]
+}
+
+/// FNV-1a of each catalog entry.
+pub(crate) const MANIFEST: [(&str, u64); 21] = [
+    ("clippy_metadata", 0xac0f_d41a_f4cc_23bf),
+    ("clippy_workspace_messages", 0x0873_68b3_18a1_9557),
+    ("clippy_suggestions", 0xfee8_4580_81be_aa82),
+    ("benchmark_ids", 0x9468_c702_d7fa_cd90),
+    ("scan_lockfile", 0x75fd_ce76_4b69_a656),
+    ("scan_osv", 0x889a_46c3_5739_258e),
+    ("python_records", 0xe289_442d_dc65_5384),
+    ("proc_vmstat", 0x5cf8_dc91_638f_10b5),
+    ("proc_meminfo", 0xe242_452c_d987_90b7),
+    ("proc_thp_enabled", 0x602d_3542_58de_63dd),
+    ("proc_smaps_rollup", 0xecae_db9e_6472_c518),
+    ("proc_smaps", 0x99a4_b4b6_613b_6b08),
+    ("proc_status", 0x8b5a_cd3c_19fb_76ef),
+    ("cargo_test_stable", 0x8c64_026f_aa84_14e9),
+    ("cargo_test_json", 0x712c_b18b_ad5b_5798),
+    ("audit_report", 0x0249_a657_f6ee_7e69),
+    ("http_response", 0x79d7_1d6e_1266_bac1),
+    ("chunked_response", 0xc7e8_97bc_d7bb_8467),
+    ("etl_gzip", 0x1c0f_d195_bb42_a8ca),
+    ("etl_gzip_truncated", 0x737b_7601_b923_537d),
+    ("etl_gzip_giant_line", 0xd845_5c01_cd00_3446),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn catalog_matches_manifest() {
+        let catalog = catalog();
+        let names: Vec<&str> = catalog.iter().map(|(name, _)| *name).collect();
+        let pinned: Vec<&str> = MANIFEST.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, pinned);
+        for ((name, bytes), (_, checksum)) in catalog.iter().zip(MANIFEST) {
+            assert_eq!(fnv1a(bytes), checksum, "{name} changed; update MANIFEST if intended");
+        }
+    }
+
+    #[test]
+    fn generators_are_deterministic() {
+        assert_eq!(catalog(), catalog());
+    }
+
+    #[test]
+    fn truncated_gzip_fails_partway() {
+        let mut text = String::new();
+        GzDecoder::new(&etl_gzip(200, None)[..]).read_to_string(&mut text).unwrap();
+        assert_eq!(text.lines().count(), 200);
+        let truncated = etl_gzip(200, Some(600));
+        assert!(GzDecoder::new(&truncated[..]).read_to_string(&mut String::new()).is_err());
+    }
+
+    #[test]
+    fn giant_line_has_the_requested_length() {
+        let mut text = String::new();
+        GzDecoder::new(&etl_gzip_with_giant_line(10, 4, 5000)[..])
+            .read_to_string(&mut text)
+            .unwrap();
+        let lengths: Vec<usize> = text.lines().map(str::len).collect();
+        assert_eq!(lengths.len(), 10);
+        assert_eq!(lengths[4], 5000);
+        assert!(lengths.iter().filter(|len| **len != 5000).all(|len| *len < 100));
+    }
+
+    #[test]
+    fn chunked_stream_frames_each_chunk() {
+        let bytes = chunked_response(&["ab", "cdefghijklmnopq"]);
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.ends_with("\r\n\r\n2\r\nab\r\nf\r\ncdefghijklmnopq\r\n0\r\n\r\n"));
+    }
+}
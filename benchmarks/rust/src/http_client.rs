@@ -0,0 +1,1770 @@
+use serde_json::{json, Map, Value};
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::chunked::{self, ChunkedEnd};
+use crate::stats::{Micros, Samples};
+use crate::timeline::Tally;
+use crate::{rng, tls, DEFAULT_SEED};
+
+/// Schedule lags kept per worker; longer runs keep a uniform reservoir
+/// sample.
+const LAG_SAMPLE_CAP: usize = 100_000;
+
+/// The largest response body the client holds in memory. The fixture's
+/// items are a few hundred bytes; a body anywhere near this is a broken or
+/// hostile server, and reading it fails instead of allocating it.
+const MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// How long a connect, read or write may take before the request fails.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_millis(5000);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Scheme {
+    Http,
+    /// Needs the `tls` feature.
+    Https,
+}
+
+impl Scheme {
+    fn default_port(self) -> u16 {
+        match self {
+            Scheme::Http => 80,
+            Scheme::Https => 443,
+        }
+    }
+
+    /// True when this build can speak the scheme.
+    pub(crate) fn supported(self) -> bool {
+        self == Scheme::Http || tls::supported()
+    }
+}
+
+/// Why an `https://` target cannot be reached in this build.
+pub(crate) const HTTPS_NEEDS_TLS: &str = "https:// needs a build with the tls feature";
+
+/// Where a base URL sends its requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ParsedUrl {
+    pub(crate) scheme: Scheme,
+    /// An IPv6 literal without its brackets.
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// The URL's path without its trailing slash, prepended to every
+    /// request path; empty for none.
+    pub(crate) base_path: String,
+}
+
+impl ParsedUrl {
+    /// The `Host` header value: IPv6 literals in brackets, and the port
+    /// unless it is the scheme's default.
+    pub(crate) fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == self.scheme.default_port() {
+            host
+        } else {
+            format!("{host}:{}", self.port)
+        }
+    }
+
+    /// `path`, which starts with `/`, under the base path.
+    pub(crate) fn request_path(&self, path: &str) -> String {
+        format!("{}{path}", self.base_path)
+    }
+}
+
+/// Splits an `http://` or `https://` URL into where its requests go: IPv6
+/// hosts in brackets, a missing port defaulting to the scheme's, and any
+/// path kept as the base path. The error says what is wrong with the URL,
+/// without repeating it.
+pub(crate) fn parse_base_url(base_url: &str) -> Result<ParsedUrl, String> {
+    let (scheme, rest) = match base_url.split_once("://") {
+        Some(("http", rest)) => (Scheme::Http, rest),
+        Some(("https", rest)) => (Scheme::Https, rest),
+        Some((other, _)) => {
+            return Err(format!("unsupported scheme {other}://, expected http:// or https://"))
+        }
+        None => return Err("no http:// or https:// scheme".to_string()),
+    };
+    if rest.contains(['?', '#']) {
+        return Err("a base URL takes no query or fragment".to_string());
+    }
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, ""),
+    };
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, after) = bracketed
+                .split_once(']')
+                .ok_or("IPv6 host is missing its closing ]")?;
+            match after {
+                "" => (host, None),
+                _ => {
+                    let port = after.strip_prefix(':').ok_or_else(|| {
+                        format!("expected :port after the IPv6 host, got {after:?}")
+                    })?;
+                    (host, Some(port))
+                }
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, _)) if host.contains(':') => {
+                return Err("IPv6 hosts go in brackets, as in [::1]".to_string())
+            }
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err("no host".to_string());
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port {port:?}"))?,
+        None => scheme.default_port(),
+    };
+    Ok(ParsedUrl {
+        scheme,
+        host: host.to_string(),
+        port,
+        base_path: path.trim_end_matches('/').to_string(),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ConnectionMode {
+    /// New TCP connection per request, `Connection: close`.
+    PerRequest,
+    /// One keep-alive connection per worker, reopened only when the server closes it.
+    Persistent,
+}
+
+/// Histogram key for one response's status line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum StatusBucket {
+    Code(u16),
+    /// Unparsable status line, or a code outside 100-599.
+    Malformed,
+}
+
+impl StatusBucket {
+    pub(crate) fn parse(status_line: &str) -> Self {
+        let mut parts = status_line.split_whitespace();
+        let code = parts
+            .next()
+            .filter(|version| version.starts_with("HTTP/"))
+            .and(parts.next())
+            .filter(|code| code.len() == 3)
+            .and_then(|code| code.parse::<u16>().ok());
+        match code {
+            Some(code) if (100..=599).contains(&code) => StatusBucket::Code(code),
+            _ => StatusBucket::Malformed,
+        }
+    }
+
+    /// Key of this bucket in the `status_counts` histogram.
+    pub(crate) fn label(self) -> String {
+        match self {
+            StatusBucket::Code(code) => code.to_string(),
+            StatusBucket::Malformed => "malformed".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct WorkerStats {
+    pub(crate) completed: usize,
+    pub(crate) checksum: u64,
+    /// Every failed request; the eight counts below split all but a worker
+    /// that panicked.
+    pub(crate) errors: usize,
+    /// Connects, reads and writes that ran past the timeout.
+    pub(crate) timeout_errors: usize,
+    /// TLS handshakes that failed on the server's certificate.
+    pub(crate) certificate_errors: usize,
+    /// Other failures to resolve, connect or finish a TLS handshake.
+    pub(crate) connect_errors: usize,
+    /// Other failures to send a request.
+    pub(crate) write_errors: usize,
+    /// Other failures to read a response, truncated bodies included.
+    pub(crate) read_errors: usize,
+    /// Responses with a status outside `2xx` and `3xx`.
+    pub(crate) http_errors: usize,
+    pub(crate) redirects: usize,
+    /// Malformed status lines, and `2xx` bodies that are not an item.
+    pub(crate) parse_errors: usize,
+    pub(crate) connections_opened: usize,
+    pub(crate) connections_closed: usize,
+    /// Keep-alive connections opened after a worker's first, because the
+    /// server closed the previous one.
+    pub(crate) reconnects: usize,
+    /// Requests never sent because the run's budget ran out first; not
+    /// counted in `errors`.
+    pub(crate) skipped_requests: usize,
+    pub(crate) bytes_sent: u64,
+    /// Status line, headers, and body of every successful response.
+    pub(crate) bytes_received: u64,
+    pub(crate) body_bytes_received: u64,
+    /// Per successful request, including connect time when one was opened.
+    pub(crate) latencies_us: Micros,
+    /// Open loop only: how late each request started against its slot.
+    pub(crate) schedule_lag_us: Samples,
+    /// Most minus fewest requests sent by any one worker of a run; merged
+    /// runs keep the largest.
+    pub(crate) worker_completion_skew: usize,
+    /// Every response that arrived, by status; connect and read failures
+    /// have no status and only show up in `errors`.
+    pub(crate) statuses: BTreeMap<StatusBucket, usize>,
+    /// The first error message of each category that occurred, by
+    /// `ErrorCategory::name`, cut to `SAMPLE_CHARS` characters.
+    pub(crate) error_samples: BTreeMap<&'static str, String>,
+    /// When the requests that were sent ended, and whether they failed,
+    /// counted as they end; what `Timeline` buckets.
+    pub(crate) outcomes: Tally,
+}
+
+impl WorkerStats {
+    /// Counts a request that got no response.
+    fn record_transport_error(&mut self, err: &TransportError) {
+        self.record_failure(err.category(), err);
+    }
+
+    /// Counts a request whose response was not used.
+    fn record_error(&mut self, err: ResponseError) {
+        self.record_failure(err.category(), &err);
+    }
+
+    fn record_failure(&mut self, category: ErrorCategory, err: &dyn fmt::Display) {
+        self.errors += 1;
+        *self.category_count(category) += 1;
+        self.error_samples.entry(category.name()).or_insert_with(|| sample(err));
+    }
+
+    fn category_count(&mut self, category: ErrorCategory) -> &mut usize {
+        match category {
+            ErrorCategory::Connect => &mut self.connect_errors,
+            ErrorCategory::Write => &mut self.write_errors,
+            ErrorCategory::Read => &mut self.read_errors,
+            ErrorCategory::Timeout => &mut self.timeout_errors,
+            ErrorCategory::Certificate => &mut self.certificate_errors,
+            ErrorCategory::Status => &mut self.http_errors,
+            ErrorCategory::Redirect => &mut self.redirects,
+            ErrorCategory::Parse => &mut self.parse_errors,
+        }
+    }
+
+    /// Connect, write and read failures that were not timeouts or
+    /// certificate errors.
+    pub(crate) fn transport_errors(&self) -> usize {
+        self.connect_errors + self.write_errors + self.read_errors
+    }
+
+    /// `statuses` as a JSON object from status code (or `malformed`) to
+    /// response count.
+    pub(crate) fn status_counts(&self) -> Value {
+        let counts: Map<String, Value> =
+            self.statuses.iter().map(|(bucket, count)| (bucket.label(), json!(count))).collect();
+        Value::Object(counts)
+    }
+
+    pub(crate) fn merge(&mut self, other: WorkerStats) {
+        self.completed += other.completed;
+        self.checksum += other.checksum;
+        self.errors += other.errors;
+        self.timeout_errors += other.timeout_errors;
+        self.certificate_errors += other.certificate_errors;
+        self.connect_errors += other.connect_errors;
+        self.write_errors += other.write_errors;
+        self.read_errors += other.read_errors;
+        self.http_errors += other.http_errors;
+        self.redirects += other.redirects;
+        self.parse_errors += other.parse_errors;
+        self.connections_opened += other.connections_opened;
+        self.connections_closed += other.connections_closed;
+        self.reconnects += other.reconnects;
+        self.skipped_requests += other.skipped_requests;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.body_bytes_received += other.body_bytes_received;
+        self.latencies_us.merge(other.latencies_us);
+        self.schedule_lag_us.merge(other.schedule_lag_us);
+        self.worker_completion_skew = self.worker_completion_skew.max(other.worker_completion_skew);
+        for (bucket, count) in other.statuses {
+            *self.statuses.entry(bucket).or_default() += count;
+        }
+        for (category, message) in other.error_samples {
+            self.error_samples.entry(category).or_insert(message);
+        }
+        self.outcomes.merge(other.outcomes);
+    }
+}
+
+/// Longest error message `WorkerStats` keeps per category, so a record
+/// stays small whatever the server sent.
+pub(crate) const SAMPLE_CHARS: usize = 200;
+
+fn sample(err: &dyn fmt::Display) -> String {
+    err.to_string().chars().take(SAMPLE_CHARS).collect()
+}
+
+/// What went wrong with a failed request; each category has its own count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorCategory {
+    Connect,
+    Write,
+    Read,
+    Timeout,
+    Certificate,
+    Status,
+    Redirect,
+    Parse,
+}
+
+impl ErrorCategory {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ErrorCategory::Connect => "connect",
+            ErrorCategory::Write => "write",
+            ErrorCategory::Read => "read",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Certificate => "certificate",
+            ErrorCategory::Status => "status",
+            ErrorCategory::Redirect => "redirect",
+            ErrorCategory::Parse => "json-parse",
+        }
+    }
+}
+
+struct Response {
+    status: StatusBucket,
+    /// Everything on the wire but the body: status line, headers, and for a
+    /// chunked body its framing and trailers.
+    head_bytes: usize,
+    body: Vec<u8>,
+    keep_alive: bool,
+}
+
+/// Why a response that arrived did not yield a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ResponseError {
+    /// A `3xx`; the client does not follow redirects.
+    Redirect(u16),
+    /// Any other status outside `2xx`.
+    Status(u16),
+    /// A status line that does not parse, or a `2xx` whose body is not an
+    /// item.
+    Parse(String),
+}
+
+impl ResponseError {
+    pub(crate) fn category(&self) -> ErrorCategory {
+        match self {
+            ResponseError::Redirect(_) => ErrorCategory::Redirect,
+            ResponseError::Status(_) => ErrorCategory::Status,
+            ResponseError::Parse(_) => ErrorCategory::Parse,
+        }
+    }
+
+    fn for_status(status: StatusBucket) -> Option<Self> {
+        match status {
+            StatusBucket::Code(200..=299) => None,
+            StatusBucket::Code(code @ 300..=399) => Some(ResponseError::Redirect(code)),
+            StatusBucket::Code(code) => Some(ResponseError::Status(code)),
+            StatusBucket::Malformed => Some(ResponseError::Parse("malformed status line".into())),
+        }
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseError::Redirect(code) => write!(f, "redirected with status {code}"),
+            ResponseError::Status(code) => write!(f, "status {code}"),
+            ResponseError::Parse(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Why a request got no response off the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TransportError {
+    /// A connect, read or write ran past the timeout.
+    TimedOut,
+    /// The server's TLS certificate did not verify.
+    Certificate(String),
+    /// Resolving the host, connecting or the TLS handshake failed.
+    Connect(String),
+    /// Sending the request failed.
+    Write(String),
+    /// Reading the response failed, or it ended early.
+    Read(String),
+}
+
+impl TransportError {
+    /// `err` as a failure of the step `failed` wraps, unless it was a
+    /// timeout or a rejected certificate.
+    pub(crate) fn from_io(err: io::Error, failed: fn(String) -> Self) -> Self {
+        if let Some(reason) = tls::certificate_error(&err) {
+            return TransportError::Certificate(reason);
+        }
+        // A read timeout surfaces as `WouldBlock` on Unix.
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => TransportError::TimedOut,
+            _ => failed(err.to_string()),
+        }
+    }
+
+    pub(crate) fn category(&self) -> ErrorCategory {
+        match self {
+            TransportError::TimedOut => ErrorCategory::Timeout,
+            TransportError::Certificate(_) => ErrorCategory::Certificate,
+            TransportError::Connect(_) => ErrorCategory::Connect,
+            TransportError::Write(_) => ErrorCategory::Write,
+            TransportError::Read(_) => ErrorCategory::Read,
+        }
+    }
+}
+
+/// Reading is the default: it is what most of a request's I/O does.
+impl From<io::Error> for TransportError {
+    fn from(err: io::Error) -> Self {
+        TransportError::from_io(err, TransportError::Read)
+    }
+}
+
+fn connect_failed(err: io::Error) -> TransportError {
+    TransportError::from_io(err, TransportError::Connect)
+}
+
+fn write_failed(err: io::Error) -> TransportError {
+    TransportError::from_io(err, TransportError::Write)
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransportError::TimedOut => f.write_str("timed out"),
+            TransportError::Certificate(reason) => write!(f, "certificate rejected: {reason}"),
+            TransportError::Connect(message)
+            | TransportError::Write(message)
+            | TransportError::Read(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<TransportError> for String {
+    fn from(err: TransportError) -> Self {
+        err.to_string()
+    }
+}
+
+/// One completed request/response exchange.
+pub(crate) struct Exchange {
+    status: StatusBucket,
+    /// The item's value; an error for anything but a `2xx` with a valid body.
+    value: Result<u64, ResponseError>,
+    reusable: bool,
+    bytes_sent: usize,
+    bytes_received: usize,
+    body_bytes: usize,
+}
+
+/// A response's status line and headers, as far as reading its body needs.
+struct Head {
+    status: StatusBucket,
+    head_bytes: usize,
+    content_length: Option<usize>,
+    chunked: bool,
+    keep_alive: bool,
+}
+
+fn read_head<R: BufRead>(reader: &mut R) -> Result<Head, TransportError> {
+    let mut line = String::new();
+    let mut head_bytes = 0;
+    let read = chunked::read_framing_line(reader, &mut line)?;
+    chunked::add_head_bytes(&mut head_bytes, read)?;
+    if line.is_empty() {
+        return Err(TransportError::Read("connection closed before response".to_string()));
+    }
+    let status = StatusBucket::parse(&line);
+    let mut keep_alive = line.starts_with("HTTP/1.1");
+    let mut content_length = None;
+    let mut chunked = false;
+    loop {
+        let read = chunked::read_framing_line(reader, &mut line)?;
+        if read == 0 {
+            return Err(TransportError::Read("response body missing".to_string()));
+        }
+        chunked::add_head_bytes(&mut head_bytes, read)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            let length = parse_content_length(value)?;
+            if content_length.is_some_and(|previous| previous != length) {
+                return Err(TransportError::Read("conflicting Content-Length headers".to_string()));
+            }
+            content_length = Some(length);
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            // `chunked` is always the last coding applied.
+            let last = value.rsplit(',').next().unwrap_or_default();
+            chunked = last.trim().eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("connection") {
+            keep_alive = value.eq_ignore_ascii_case("keep-alive");
+        }
+    }
+    Ok(Head {
+        status,
+        head_bytes,
+        content_length,
+        chunked,
+        keep_alive,
+    })
+}
+
+/// A `Content-Length` value. A list of identical values counts as one; an
+/// invalid value is an error rather than a body read to the close (RFC 9112,
+/// section 6.3).
+fn parse_content_length(value: &str) -> Result<usize, TransportError> {
+    let invalid = || TransportError::Read(format!("invalid Content-Length {value:?}"));
+    let mut lengths = value.split(',').map(|length| {
+        let length = length.trim();
+        if length.is_empty() || !length.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        length.parse::<usize>().map_err(|_| invalid())
+    });
+    let first = lengths.next().ok_or_else(invalid)??;
+    for length in lengths {
+        if length? != first {
+            return Err(invalid());
+        }
+    }
+    Ok(first)
+}
+
+/// Passes the body that follows `head` to `on_data` as it arrives. A
+/// chunked body's framing is added to `head_bytes`; a body that runs to the
+/// close leaves the connection unusable. A body longer than `limit` bytes is
+/// a read error, found before any of it is read where the head declares it.
+fn read_body<R: BufRead>(
+    reader: &mut R,
+    head: &mut Head,
+    limit: usize,
+    mut on_data: impl FnMut(&[u8]),
+) -> Result<ChunkedEnd, TransportError> {
+    // A chunked body ignores any `Content-Length` (RFC 9112, section 6.3).
+    if head.chunked {
+        let end = chunked::read_body(reader, limit, on_data)?;
+        head.head_bytes += end.framing_bytes;
+        return Ok(end);
+    }
+    let too_long = || TransportError::Read(format!("response body exceeds {limit} bytes"));
+    if head.content_length.is_some_and(|length| length > limit) {
+        return Err(too_long());
+    }
+    let mut read = 0usize;
+    let mut remaining = head.content_length;
+    while remaining != Some(0) {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            if remaining.is_some() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            head.keep_alive = false;
+            break;
+        }
+        let take = remaining.map_or(available.len(), |left| left.min(available.len()));
+        read += take;
+        if read > limit {
+            return Err(too_long());
+        }
+        on_data(&available[..take]);
+        reader.consume(take);
+        remaining = remaining.map(|left| left - take);
+    }
+    Ok(ChunkedEnd::default())
+}
+
+fn read_response<R: BufRead>(reader: &mut R) -> Result<Response, TransportError> {
+    let mut head = read_head(reader)?;
+    let mut body = Vec::new();
+    read_body(reader, &mut head, MAX_RESPONSE_BYTES, |data| body.extend_from_slice(data))?;
+    body.shrink_to_fit();
+    Ok(Response {
+        status: head.status,
+        head_bytes: head.head_bytes,
+        body,
+        keep_alive: head.keep_alive,
+    })
+}
+
+fn item_value(body: &[u8]) -> Result<u64, String> {
+    let payload: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    payload["value"]
+        .as_u64()
+        .ok_or_else(|| "missing value".to_string())
+}
+
+/// A TCP connection to the first address `host` resolves to that accepts
+/// one within `timeout`, with that timeout set for reads and writes.
+pub(crate) fn connect_tcp(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+) -> Result<TcpStream, TransportError> {
+    let mut last_err = TransportError::Connect(format!("{host} resolved to no address"));
+    for addr in (host, port).to_socket_addrs().map_err(connect_failed)? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout)).map_err(connect_failed)?;
+                stream.set_write_timeout(Some(timeout)).map_err(connect_failed)?;
+                return Ok(stream);
+            }
+            Err(err) => last_err = connect_failed(err),
+        }
+    }
+    Err(last_err)
+}
+
+/// The socket under a connection, wrapped in TLS for `https://`.
+enum Stream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tls::TlsStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+pub(crate) struct HttpConnection {
+    reader: BufReader<Stream>,
+}
+
+impl HttpConnection {
+    /// Tries each address the target's host resolves to in turn, then for
+    /// `https://` runs the TLS handshake; every connect, and every later
+    /// read and write, fails after `timeout`.
+    pub(crate) fn connect(target: &ParsedUrl, timeout: Duration) -> Result<Self, TransportError> {
+        let stream = match target.scheme {
+            Scheme::Http => Stream::Plain(connect_tcp(&target.host, target.port, timeout)?),
+            #[cfg(feature = "tls")]
+            Scheme::Https => {
+                let tcp = connect_tcp(&target.host, target.port, timeout)?;
+                Stream::Tls(Box::new(tls::handshake(&target.host, tcp)?))
+            }
+            #[cfg(not(feature = "tls"))]
+            Scheme::Https => return Err(TransportError::Connect(HTTPS_NEEDS_TLS.to_string())),
+        };
+        Ok(HttpConnection {
+            reader: BufReader::new(stream),
+        })
+    }
+
+    /// `GET {base_path}/item/{item_id}`.
+    pub(crate) fn get_item(&mut self, item: &ItemRequest) -> Result<Exchange, TransportError> {
+        let ItemRequest {
+            host,
+            base_path,
+            item_id,
+            ..
+        } = item;
+        let request = format!(
+            "GET {base_path}/item/{item_id} HTTP/1.1\r\nHost: {host}\r\n\
+             Connection: {}\r\n\r\n",
+            item.connection_header()
+        );
+        self.exchange(&request, item.keep_alive, None)
+    }
+
+    /// `POST {base_path}/items` with the JSON `body` of an item whose value
+    /// is `sent_value`; the response must echo the created item with that
+    /// value, or it counts as a parse error.
+    pub(crate) fn post_item(
+        &mut self,
+        item: &ItemRequest,
+        body: &str,
+        sent_value: u64,
+    ) -> Result<Exchange, TransportError> {
+        let ItemRequest {
+            host, base_path, ..
+        } = item;
+        let request = format!(
+            "POST {base_path}/items HTTP/1.1\r\nHost: {host}\r\nConnection: {}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            item.connection_header(),
+            body.len()
+        );
+        self.exchange(&request, item.keep_alive, Some(sent_value))
+    }
+
+    /// Sends `request` and reads the item out of its response, checking its
+    /// value against `expected` when given.
+    fn exchange(
+        &mut self,
+        request: &str,
+        keep_alive: bool,
+        expected: Option<u64>,
+    ) -> Result<Exchange, TransportError> {
+        self.reader.get_mut().write_all(request.as_bytes()).map_err(write_failed)?;
+        let response = read_response(&mut self.reader)?;
+        let value = match ResponseError::for_status(response.status) {
+            Some(err) => Err(err),
+            None => item_value(&response.body)
+                .and_then(|value| match expected {
+                    Some(sent) if sent != value => {
+                        Err(format!("echoed value {value}, sent {sent}"))
+                    }
+                    _ => Ok(value),
+                })
+                .map_err(ResponseError::Parse),
+        };
+        Ok(Exchange {
+            status: response.status,
+            value,
+            reusable: keep_alive && response.keep_alive,
+            bytes_sent: request.len(),
+            bytes_received: response.head_bytes + response.body.len(),
+            body_bytes: response.body.len(),
+        })
+    }
+
+    /// POSTs a JSON `body` on this keep-alive connection. Returns the status
+    /// and whether the connection can carry another request.
+    pub(crate) fn post_json(
+        &mut self,
+        host: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<(StatusBucket, bool), TransportError> {
+        let mut request = format!(
+            "POST {path} HTTP/1.1\r\nHost: {host}\r\nConnection: keep-alive\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n",
+            body.len()
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        self.reader.get_mut().write_all(request.as_bytes()).map_err(write_failed)?;
+        let response = read_response(&mut self.reader)?;
+        Ok((response.status, response.keep_alive))
+    }
+
+    /// `POST`s a chunked body to `path`: `next_chunk` refills the buffer for
+    /// each chunk, and the body ends when it leaves it empty. The response
+    /// body goes to `on_data` as it arrives.
+    pub(crate) fn post_chunked(
+        &mut self,
+        host: &str,
+        path: &str,
+        mut next_chunk: impl FnMut(&mut Vec<u8>),
+        on_data: impl FnMut(&[u8]),
+    ) -> Result<Streamed, TransportError> {
+        let head = format!(
+            "POST {path} HTTP/1.1\r\nHost: {host}\r\nConnection: keep-alive\r\n\
+             Content-Type: application/octet-stream\r\nTransfer-Encoding: chunked\r\n\r\n"
+        );
+        let stream = self.reader.get_mut();
+        stream.write_all(head.as_bytes()).map_err(write_failed)?;
+        let mut data = Vec::new();
+        let mut frame = Vec::new();
+        loop {
+            data.clear();
+            next_chunk(&mut data);
+            frame.clear();
+            // One write per chunk, framing included.
+            if data.is_empty() {
+                chunked::write_last_chunk(&mut frame, &[]).map_err(write_failed)?;
+                stream.write_all(&frame).map_err(write_failed)?;
+                break;
+            }
+            chunked::write_chunk(&mut frame, &data).map_err(write_failed)?;
+            stream.write_all(&frame).map_err(write_failed)?;
+        }
+        self.receive_streamed(on_data)
+    }
+
+    /// `GET`s `path`, passing the response body to `on_data` as it arrives.
+    pub(crate) fn get_streamed(
+        &mut self,
+        host: &str,
+        path: &str,
+        on_data: impl FnMut(&[u8]),
+    ) -> Result<Streamed, TransportError> {
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: {host}\r\nConnection: keep-alive\r\n\r\n");
+        self.reader.get_mut().write_all(request.as_bytes()).map_err(write_failed)?;
+        self.receive_streamed(on_data)
+    }
+
+    fn receive_streamed(&mut self, on_data: impl FnMut(&[u8])) -> Result<Streamed, TransportError> {
+        let mut head = read_head(&mut self.reader)?;
+        // Streamed bodies are hashed as they arrive, never held.
+        let end = read_body(&mut self.reader, &mut head, usize::MAX, on_data)?;
+        Ok(Streamed {
+            status: head.status,
+            end,
+        })
+    }
+}
+
+/// A response whose body was streamed rather than kept.
+#[derive(Debug)]
+pub(crate) struct Streamed {
+    pub(crate) status: StatusBucket,
+    /// The trailers of a chunked body; empty otherwise.
+    pub(crate) end: ChunkedEnd,
+}
+
+/// Bounds on how long the client waits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RequestLimits {
+    /// For each connect, and each read or write on a connection.
+    pub(crate) timeout: Duration,
+    /// Wall-clock budget for the whole run; requests not started by then
+    /// are skipped.
+    pub(crate) budget: Option<Duration>,
+    /// Open loop: the time between scheduled request starts across all
+    /// workers. `None` is closed loop.
+    pub(crate) pace: Option<Duration>,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        RequestLimits {
+            timeout: DEFAULT_TIMEOUT,
+            budget: None,
+            pace: None,
+        }
+    }
+}
+
+/// Pause after the first failed readiness probe; each further failure
+/// doubles it, up to `READY_MAX_BACKOFF`.
+const READY_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+const READY_MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Probes `GET {base_path}/item/0` until the server answers, backing off exponentially
+/// between attempts. Any complete response counts, error statuses included:
+/// those are the benchmark's to report. False once `wait` has passed
+/// without one; a zero `wait` probes once.
+pub(crate) fn wait_until_ready(target: &ParsedUrl, wait: Duration, timeout: Duration) -> bool {
+    let deadline = Instant::now() + wait;
+    let mut backoff = READY_INITIAL_BACKOFF;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let probe_timeout = timeout.min(remaining).max(Duration::from_millis(10));
+        let answered = HttpConnection::connect(target, probe_timeout)
+            .and_then(|mut connection| {
+                connection.get_item(&ItemRequest {
+                    host: &target.host_header(),
+                    base_path: &target.base_path,
+                    item_id: 0,
+                    keep_alive: false,
+                })
+            })
+            .is_ok();
+        if answered {
+            return true;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return false;
+        }
+        thread::sleep(backoff.min(remaining));
+        backoff = (backoff * 2).min(READY_MAX_BACKOFF);
+    }
+}
+
+/// One worker request, for the item `item_id`.
+pub(crate) struct ItemRequest<'a> {
+    /// As the `Host` header carries it.
+    pub(crate) host: &'a str,
+    pub(crate) base_path: &'a str,
+    pub(crate) item_id: usize,
+    pub(crate) keep_alive: bool,
+}
+
+impl ItemRequest<'_> {
+    fn connection_header(&self) -> &'static str {
+        if self.keep_alive {
+            "keep-alive"
+        } else {
+            "close"
+        }
+    }
+}
+
+/// Sends one worker request on an open connection and reads its response:
+/// `HttpConnection::get_item`, or a write such as `post_item`. Workers
+/// share everything else, connections and counting included.
+pub(crate) type SendItem =
+    dyn Fn(&mut HttpConnection, &ItemRequest) -> Result<Exchange, TransportError> + Sync;
+
+/// `RequestLimits` with the budget turned into a point in time, and the
+/// pace into this worker's share of the schedule.
+#[derive(Clone, Copy)]
+struct WorkerLimits {
+    /// When the run started, for the outcome tally; the first outcome's
+    /// time when not given.
+    origin: Option<Instant>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    schedule: Option<Schedule>,
+}
+
+/// One worker's request slots in an open-loop run: with `n` workers paced
+/// `p` apart overall, worker `w` starts at `w * p` and then every `n * p`,
+/// so together they start a request every `p`.
+#[derive(Clone, Copy, Debug)]
+struct Schedule {
+    next: Instant,
+    every: Duration,
+}
+
+impl Schedule {
+    /// Sleeps until the next slot unless it has passed, and returns the
+    /// slot. A worker that fell behind sends at once instead of skipping
+    /// slots, so the run still ends after its request count.
+    fn wait(&mut self) -> Instant {
+        let slot = self.next;
+        self.next += self.every;
+        let now = Instant::now();
+        if slot > now {
+            thread::sleep(slot - now);
+        }
+        slot
+    }
+}
+
+fn run_worker(
+    target: &ParsedUrl,
+    mut ids: impl Iterator<Item = usize>,
+    mode: ConnectionMode,
+    seed: u64,
+    limits: WorkerLimits,
+    send: &SendItem,
+    on_complete: &dyn Fn(),
+) -> WorkerStats {
+    let mut stats = WorkerStats {
+        schedule_lag_us: Samples::reservoir(LAG_SAMPLE_CAP, seed),
+        outcomes: Tally::starting(limits.origin),
+        ..WorkerStats::default()
+    };
+    let keep_alive = mode == ConnectionMode::Persistent;
+    let host = target.host_header();
+    let mut open: Option<HttpConnection> = None;
+    let mut schedule = limits.schedule;
+    // Requests answered on the open connection.
+    let mut served = 0;
+    while let Some(item_id) = ids.next() {
+        // Open loop times each request from its slot, so the time it spent
+        // waiting on a server that fell behind counts as latency.
+        let start = match schedule.as_mut() {
+            Some(schedule) => {
+                let slot = schedule.wait();
+                stats.schedule_lag_us.push(slot.elapsed().as_secs_f64() * 1e6);
+                slot
+            }
+            None => Instant::now(),
+        };
+        if limits.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            stats.skipped_requests = 1 + ids.count();
+            break;
+        }
+        let errors_before = stats.errors;
+        let mut retried = false;
+        let result = loop {
+            let connection = match open.take() {
+                Some(connection) => connection,
+                None => match HttpConnection::connect(target, limits.timeout) {
+                    Ok(connection) => {
+                        if keep_alive && stats.connections_opened > 0 {
+                            stats.reconnects += 1;
+                        }
+                        stats.connections_opened += 1;
+                        served = 0;
+                        connection
+                    }
+                    Err(err) => break Err(err),
+                },
+            };
+            let connection = open.insert(connection);
+            let item = ItemRequest {
+                host: &host,
+                base_path: &target.base_path,
+                item_id,
+                keep_alive,
+            };
+            match send(connection, &item) {
+                // The server closed a connection we had already used; the
+                // request goes out again on a new one.
+                Err(_) if keep_alive && served > 0 && !retried => {
+                    open = None;
+                    stats.connections_closed += 1;
+                    retried = true;
+                }
+                result => break result,
+            }
+        };
+        if open.is_none() {
+            // Connecting failed.
+            if let Err(err) = &result {
+                stats.record_transport_error(err);
+            }
+            stats.outcomes.record(Instant::now(), true);
+            continue;
+        }
+        served += 1;
+        let reusable = match result {
+            Ok(exchange) => {
+                *stats.statuses.entry(exchange.status).or_default() += 1;
+                match exchange.value {
+                    Ok(value) => {
+                        stats.completed += 1;
+                        stats.checksum += value;
+                        stats.bytes_sent += exchange.bytes_sent as u64;
+                        stats.bytes_received += exchange.bytes_received as u64;
+                        stats.body_bytes_received += exchange.body_bytes as u64;
+                        stats.latencies_us.push(start.elapsed());
+                        on_complete();
+                    }
+                    Err(err) => stats.record_error(err),
+                }
+                exchange.reusable
+            }
+            Err(err) => {
+                stats.record_transport_error(&err);
+                false
+            }
+        };
+        stats.outcomes.record(Instant::now(), stats.errors > errors_before);
+        if !reusable {
+            open = None;
+            stats.connections_closed += 1;
+        }
+    }
+    if open.take().is_some() {
+        stats.connections_closed += 1;
+    }
+    stats
+}
+
+/// Runs `requests` on `concurrency` threads that take the next request id
+/// from a shared counter until none are left, each fetching
+/// `/item/{request_id % rows}` under the default limits.
+pub(crate) fn run_workers(
+    target: &ParsedUrl,
+    requests: usize,
+    rows: usize,
+    concurrency: usize,
+    mode: ConnectionMode,
+) -> WorkerStats {
+    run_workers_with(target, requests, rows, concurrency, mode, &WorkerHooks::default())
+}
+
+/// What `run_workers_with` calls out to from its worker threads, and how
+/// long they may wait.
+pub(crate) struct WorkerHooks<'a> {
+    /// Initial RNG state for each worker index.
+    pub(crate) rng_for: &'a (dyn Fn(usize) -> u64 + Sync),
+    /// Called with the run-wide completed count after every successful
+    /// request.
+    pub(crate) on_progress: &'a (dyn Fn(usize) + Sync),
+    pub(crate) limits: RequestLimits,
+    pub(crate) send: &'a SendItem,
+}
+
+impl Default for WorkerHooks<'_> {
+    /// Streams from the default seed, no progress reporting, the default
+    /// timeout and no budget, sending `GET`s.
+    fn default() -> Self {
+        WorkerHooks {
+            rng_for: &|worker| rng::stream(DEFAULT_SEED, worker as u64),
+            on_progress: &|_| {},
+            limits: RequestLimits::default(),
+            send: &HttpConnection::get_item,
+        }
+    }
+}
+
+/// `run_workers` with each worker seeded, reporting and limited through
+/// `hooks`. The budget starts now.
+pub(crate) fn run_workers_with(
+    target: &ParsedUrl,
+    requests: usize,
+    rows: usize,
+    concurrency: usize,
+    mode: ConnectionMode,
+    hooks: &WorkerHooks,
+) -> WorkerStats {
+    let run_start = Instant::now();
+    let workers = concurrency.max(1);
+    let limits = |worker: usize| WorkerLimits {
+        origin: Some(run_start),
+        timeout: hooks.limits.timeout,
+        deadline: hooks.limits.budget.map(|budget| run_start + budget),
+        schedule: hooks.limits.pace.map(|pace| Schedule {
+            next: run_start + pace * worker as u32,
+            every: pace * workers as u32,
+        }),
+    };
+    let completed = AtomicUsize::new(0);
+    let on_complete = || (hooks.on_progress)(completed.fetch_add(1, Ordering::Relaxed) + 1);
+    // A worker held up by slow responses takes fewer requests instead of
+    // holding the run up with a fixed share of them.
+    let next_request = AtomicUsize::new(0);
+    let take = || {
+        let request_id = next_request.fetch_add(1, Ordering::Relaxed);
+        (request_id < requests).then(|| request_id % rows.max(1))
+    };
+
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for worker in 0..min(workers, requests) {
+            let (take, on_complete) = (&take, &on_complete);
+            handles.push(scope.spawn(move || {
+                let seed = (hooks.rng_for)(worker);
+                let ids = std::iter::from_fn(take);
+                run_worker(target, ids, mode, seed, limits(worker), hooks.send, on_complete)
+            }));
+        }
+
+        let mut stats = WorkerStats::default();
+        let mut handled: Vec<usize> = Vec::new();
+        for handle in handles {
+            match handle.join() {
+                Ok(worker_stats) => {
+                    handled.push(worker_stats.completed + worker_stats.errors);
+                    stats.merge(worker_stats);
+                }
+                Err(_) => stats.errors += 1,
+            }
+        }
+        let most = handled.iter().max().unwrap_or(&0);
+        stats.worker_completion_skew = most - handled.iter().min().unwrap_or(&0);
+        stats
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::timeline::Timeline;
+
+    const UNLIMITED: WorkerLimits = WorkerLimits {
+        origin: None,
+        timeout: DEFAULT_TIMEOUT,
+        deadline: None,
+        schedule: None,
+    };
+    const GET: &SendItem = &HttpConnection::get_item;
+
+    fn local(port: u16) -> ParsedUrl {
+        ParsedUrl {
+            scheme: Scheme::Http,
+            host: "127.0.0.1".to_string(),
+            port,
+            base_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn parses_scheme_host_port_and_base_path() {
+        let parsed = |url: &str| {
+            let target = parse_base_url(url).unwrap_or_else(|err| panic!("{url}: {err}"));
+            (target.scheme, target.host, target.port, target.base_path)
+        };
+        let cases = [
+            ("http://host", Scheme::Http, "host", 80, ""),
+            ("http://host/", Scheme::Http, "host", 80, ""),
+            ("http://host:80", Scheme::Http, "host", 80, ""),
+            ("http://127.0.0.1:8000/api/", Scheme::Http, "127.0.0.1", 8000, "/api"),
+            ("http://host:8080/api/v1", Scheme::Http, "host", 8080, "/api/v1"),
+            ("https://host:443/", Scheme::Https, "host", 443, ""),
+            ("https://host:8443/api", Scheme::Https, "host", 8443, "/api"),
+            ("http://[::1]:8000", Scheme::Http, "::1", 8000, ""),
+            ("http://[::1]/mock//", Scheme::Http, "::1", 80, "/mock"),
+            ("https://[2001:db8::7]/x", Scheme::Https, "2001:db8::7", 443, "/x"),
+        ];
+        for (url, scheme, host, port, base_path) in cases {
+            let expected = (scheme, host.to_string(), port, base_path.to_string());
+            assert_eq!(parsed(url), expected, "{url}");
+        }
+
+        let errors = [
+            ("ftp://host", "unsupported scheme ftp://, expected http:// or https://"),
+            ("host:8000", "no http:// or https:// scheme"),
+            ("http://", "no host"),
+            ("http://:8000/api", "no host"),
+            ("http://host:port", "invalid port \"port\""),
+            ("http://host:70000", "invalid port \"70000\""),
+            ("http://[::1", "IPv6 host is missing its closing ]"),
+            ("http://[::1]8000", "expected :port after the IPv6 host, got \"8000\""),
+            ("http://::1:8000", "IPv6 hosts go in brackets, as in [::1]"),
+            ("http://host/api?key=1", "a base URL takes no query or fragment"),
+        ];
+        for (url, reason) in errors {
+            assert_eq!(parse_base_url(url), Err(reason.to_string()), "{url}");
+        }
+
+        let ipv6 = parse_base_url("http://[::1]:8000/api").unwrap();
+        assert_eq!(ipv6.host_header(), "[::1]:8000");
+        assert_eq!(ipv6.request_path("/item/3"), "/api/item/3");
+        assert_eq!(parse_base_url("https://host").unwrap().host_header(), "host");
+        assert_eq!(parse_base_url("https://host:80").unwrap().host_header(), "host:80");
+        assert_eq!(parse_base_url("http://[::1]:80").unwrap().host_header(), "[::1]");
+        assert_eq!(local(80).request_path("/item/3"), "/item/3");
+    }
+
+    #[test]
+    fn item_requests_go_under_the_base_path() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let response = fixtures::http_response(200, "OK", "{\"value\":5}");
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            reader.get_mut().write_all(&response).unwrap();
+            request_line
+        });
+        let target = parse_base_url(&format!("http://127.0.0.1:{port}/api/v1/")).unwrap();
+        let ids = [7].into_iter();
+        let stats = run_worker(&target, ids, ConnectionMode::PerRequest, 1, UNLIMITED, GET, &|| {});
+        assert_eq!((stats.completed, stats.checksum), (1, 5));
+        assert_eq!(server.join().unwrap(), "GET /api/v1/item/7 HTTP/1.1\r\n");
+    }
+
+    #[test]
+    fn https_without_tls_says_why() {
+        if tls::supported() {
+            return;
+        }
+        let target = parse_base_url("https://127.0.0.1:9").unwrap();
+        assert!(!target.scheme.supported());
+        // Nothing listens on the discard port; the error comes before any
+        // attempt to connect.
+        let err = HttpConnection::connect(&target, DEFAULT_TIMEOUT).map(|_| ()).unwrap_err();
+        assert_eq!(err.to_string(), "https:// needs a build with the tls feature");
+    }
+
+    #[test]
+    fn reads_content_length_body_and_keep_alive() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 11\r\n\r\n{\"value\":7}HTTP/1.1";
+        let mut reader = &raw[..];
+        let response = read_response(&mut reader).unwrap();
+        assert_eq!(item_value(&response.body), Ok(7));
+        assert!(response.keep_alive);
+        assert_eq!(reader, b"HTTP/1.1");
+    }
+
+    #[test]
+    fn reassembles_chunked_bodies_with_or_without_trailers() {
+        let raw = fixtures::chunked_response(&["{\"val", "ue\":42}"]);
+        let mut reader = &raw[..];
+        let response = read_response(&mut reader).unwrap();
+        assert_eq!(item_value(&response.body), Ok(42));
+        assert!(response.keep_alive);
+        assert!(reader.is_empty());
+        assert_eq!(response.head_bytes + response.body.len(), raw.len());
+
+        // Case-insensitive headers, an extension, trailers, and a stray
+        // Content-Length that the chunking overrides.
+        let raw = b"HTTP/1.1 200 OK\r\ntransfer-encoding: gzip, CHUNKED\r\n\
+                    Content-Length: 99\r\n\r\nb;name=x\r\n{\"value\":7}\r\n0\r\nX-Checksum: 7\r\n\r\nnext";
+        let mut reader = &raw[..];
+        let response = read_response(&mut reader).unwrap();
+        assert_eq!(item_value(&response.body), Ok(7));
+        assert_eq!(reader, b"next");
+
+        // Content-Length responses still read as before.
+        let raw = fixtures::http_response(200, "OK", "{\"value\":42}");
+        assert_eq!(item_value(&read_response(&mut &raw[..]).unwrap().body), Ok(42));
+    }
+
+    #[test]
+    fn broken_chunked_bodies_are_errors() {
+        let mut truncated = fixtures::chunked_response(&["{\"value\":42}"]);
+        truncated.truncate(truncated.len() - 5);
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let cases = [
+            truncated,
+            format!("{head}zz\r\n{{}}\r\n0\r\n\r\n").into_bytes(),
+            format!("{head}2\r\n{{}}XX0\r\n\r\n").into_bytes(),
+            format!("{head}2\r\n{{}}\r\n0\r\nX-Trailer: 1\r\n").into_bytes(),
+        ];
+        for raw in cases {
+            let err = read_response(&mut &raw[..]).map(|_| ()).unwrap_err();
+            assert!(matches!(err, TransportError::Read(_)), "{}", String::from_utf8_lossy(&raw));
+        }
+    }
+
+    #[test]
+    fn invalid_or_conflicting_content_lengths_are_errors() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2, 2\r\n\r\n{}";
+        assert_eq!(read_head(&mut &raw[..]).unwrap().content_length, Some(2));
+        for lengths in ["abc", "-1", "+2", "2 2", "", "2, 3", "99999999999999999999999"] {
+            let raw = format!("HTTP/1.1 200 OK\r\nContent-Length: {lengths}\r\n\r\n{{}}");
+            let err = read_response(&mut raw.as_bytes()).map(|_| ()).unwrap_err();
+            assert!(matches!(err, TransportError::Read(_)), "{lengths:?}: {err}");
+        }
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nContent-Length: 11\r\n\r\n{}";
+        let err = read_response(&mut raw.as_bytes()).map(|_| ()).unwrap_err();
+        assert!(matches!(err, TransportError::Read(_)), "{err}");
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\ncontent-length: 2\r\n\r\n{}";
+        assert!(read_response(&mut raw.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn oversized_bodies_are_read_errors() {
+        let over = MAX_RESPONSE_BYTES + 1;
+        let raw = format!("HTTP/1.1 200 OK\r\nContent-Length: {over}\r\n\r\n{{}}");
+        let err = read_response(&mut raw.as_bytes()).map(|_| ()).unwrap_err();
+        assert!(matches!(err, TransportError::Read(_)), "{err}");
+        let raw = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{over:x}\r\n");
+        let err = read_response(&mut raw.as_bytes()).map(|_| ()).unwrap_err();
+        assert!(matches!(err, TransportError::Read(_)), "{err}");
+
+        // Heads: one header line past the cap, or too many short ones.
+        let long = "a".repeat(chunked::MAX_LINE_BYTES);
+        let many = "X-Pad: a\r\n".repeat(chunked::MAX_HEAD_BYTES / 10 + 1);
+        for headers in [format!("X-Pad: {long}\r\n"), many] {
+            let raw = format!("HTTP/1.1 200 OK\r\n{headers}Content-Length: 2\r\n\r\n{{}}");
+            let err = read_response(&mut raw.as_bytes()).map(|_| ()).unwrap_err();
+            assert!(matches!(err, TransportError::Read(_)), "{err}");
+        }
+
+        // A body that runs to the close is counted as it arrives.
+        let raw = b"HTTP/1.0 200 OK\r\n\r\n0123456789";
+        let mut head = read_head(&mut &raw[..]).unwrap();
+        let mut reader = &raw[raw.len() - 10..];
+        let err = read_body(&mut reader, &mut head, 4, |_| ()).unwrap_err();
+        assert!(matches!(err, TransportError::Read(_)), "{err}");
+    }
+
+    #[test]
+    fn http_10_and_close_are_not_reusable() {
+        let raw = b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        assert!(!read_response(&mut &raw[..]).unwrap().keep_alive);
+        let raw = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{\"value\":1}";
+        let response = read_response(&mut &raw[..]).unwrap();
+        assert!(!response.keep_alive);
+        assert_eq!(item_value(&response.body), Ok(1));
+    }
+
+    /// Serves `count` canned keep-alive responses on one connection and
+    /// returns the address to connect to.
+    fn serve_canned(response: Vec<u8>, count: usize) -> u16 {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            for _ in 0..count {
+                let mut request = Vec::new();
+                // A request's head; a small body arrives with it.
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).unwrap();
+                    if read == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+                stream.write_all(&response).unwrap();
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn waits_for_a_server_that_starts_late() {
+        use std::net::TcpListener;
+
+        let closed = local(fixtures::closed_port());
+        let start = Instant::now();
+        assert!(!wait_until_ready(&closed, Duration::ZERO, DEFAULT_TIMEOUT));
+        assert!(!wait_until_ready(&closed, Duration::from_millis(200), DEFAULT_TIMEOUT));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // Reserve a port, free it, and only listen on it again later.
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let response = fixtures::http_response(503, "Service Unavailable", "");
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(150));
+            let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            reader.get_mut().write_all(&response).unwrap();
+        });
+        let start = Instant::now();
+        assert!(wait_until_ready(&local(port), Duration::from_secs(10), DEFAULT_TIMEOUT));
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn counts_bytes_against_a_canned_server() {
+        let response = fixtures::http_response(200, "OK", "{\"value\":42}");
+        let port = serve_canned(response.clone(), 3);
+        let ids = [1, 2, 13].into_iter();
+        let persistent = ConnectionMode::Persistent;
+        let stats = run_worker(&local(port), ids, persistent, 1, UNLIMITED, GET, &|| {});
+
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.checksum, 126);
+        assert_eq!(stats.connections_opened, 1);
+        // The fixture is not on port 80, so the Host header names its port.
+        let host = format!("127.0.0.1:{port}");
+        let request_bytes: usize = [1, 2, 13]
+            .iter()
+            .map(|id| {
+                format!(
+                    "GET /item/{id} HTTP/1.1\r\nHost: {host}\r\n\
+                     Connection: keep-alive\r\n\r\n"
+                )
+                .len()
+            })
+            .sum();
+        assert_eq!(stats.bytes_sent, request_bytes as u64);
+        assert_eq!(stats.bytes_received, 3 * response.len() as u64);
+        assert_eq!(stats.body_bytes_received, 36);
+    }
+
+    #[test]
+    fn posts_count_only_when_the_echo_matches() {
+        let response = fixtures::http_response(201, "Created", "{\"id\":1,\"value\":4}");
+        let port = serve_canned(response, 2);
+        // Claims value 4 for item 0 and 9 for item 1; the server echoes 4.
+        let post: &SendItem = &|connection, item| {
+            let sent = [4, 9][item.item_id];
+            connection.post_item(item, &format!("{{\"value\":{sent}}}"), sent)
+        };
+        let persistent = ConnectionMode::Persistent;
+        let stats = run_worker(&local(port), 0..2, persistent, 1, UNLIMITED, post, &|| {});
+        assert_eq!((stats.completed, stats.checksum), (1, 4));
+        assert_eq!((stats.errors, stats.parse_errors), (1, 1));
+        assert_eq!(stats.statuses.get(&StatusBucket::Code(201)), Some(&2));
+        assert_eq!(stats.connections_opened, 1);
+    }
+
+    #[test]
+    fn failed_requests_are_split_by_cause() {
+        let error_body = "{\"error\":\"not found\",\"value\":9}";
+        let mut truncated = fixtures::http_response(200, "OK", "{\"value\":42}");
+        truncated.truncate(truncated.len() - 4);
+        let cases = [
+            (fixtures::http_response(200, "OK", "{\"value\":42}"), [0, 0, 0, 0]),
+            (fixtures::http_response(404, "Not Found", error_body), [0, 1, 0, 0]),
+            (fixtures::http_response(500, "Internal Server Error", error_body), [0, 1, 0, 0]),
+            (fixtures::http_response(301, "Moved Permanently", ""), [0, 0, 1, 0]),
+            (fixtures::http_response(200, "OK", "<html>"), [0, 0, 0, 1]),
+            (b"HTTP/1.1 OK\r\nContent-Length: 2\r\n\r\n{}".to_vec(), [0, 0, 0, 1]),
+            (truncated, [1, 0, 0, 0]),
+        ];
+        for (response, expected) in cases {
+            let port = serve_canned(response, 1);
+            let persistent = ConnectionMode::Persistent;
+            let stats = run_worker(&local(port), 0..1, persistent, 1, UNLIMITED, GET, &|| {});
+            let split = [stats.read_errors, stats.http_errors, stats.redirects, stats.parse_errors];
+            assert_eq!(split, expected, "{:?}", stats.statuses);
+            assert_eq!(stats.errors, expected.iter().sum::<usize>());
+            assert_eq!(stats.error_samples.len(), stats.errors);
+            // An error body never reaches the checksum.
+            assert_eq!(stats.checksum, if stats.errors == 0 { 42 } else { 0 });
+            let ended = Timeline::bucket(&stats.outcomes, Instant::now(), Duration::ZERO, 1);
+            assert_eq!((ended.completed[0], ended.errors[0]), (1 - stats.errors, stats.errors));
+        }
+        let per_request = ConnectionMode::PerRequest;
+        let closed = local(fixtures::closed_port());
+        let stats = run_worker(&closed, 0..2, per_request, 1, UNLIMITED, GET, &|| {});
+        assert_eq!((stats.errors, stats.connect_errors, stats.transport_errors()), (2, 2, 2));
+        assert_eq!(stats.error_samples.keys().copied().collect::<Vec<_>>(), ["connect"]);
+        // Failed connects end their request too.
+        let ended = Timeline::bucket(&stats.outcomes, Instant::now(), Duration::ZERO, 1);
+        assert_eq!((ended.completed, ended.errors), (vec![0], vec![2]));
+    }
+
+    #[test]
+    fn error_samples_keep_the_first_message_per_category_cut_short() {
+        let mut stats = WorkerStats::default();
+        stats.record_error(ResponseError::Status(503));
+        stats.record_error(ResponseError::Status(404));
+        stats.record_error(ResponseError::Parse("é".repeat(SAMPLE_CHARS + 50)));
+        stats.record_transport_error(&TransportError::Write("broken pipe".to_string()));
+        assert_eq!((stats.errors, stats.http_errors, stats.parse_errors), (4, 2, 1));
+        assert_eq!(stats.error_samples["status"], "status 503");
+        assert_eq!(stats.error_samples["json-parse"].chars().count(), SAMPLE_CHARS);
+
+        let mut later = WorkerStats::default();
+        later.record_error(ResponseError::Status(500));
+        later.record_error(ResponseError::Redirect(302));
+        stats.merge(later);
+        assert_eq!(stats.error_samples["status"], "status 503");
+        assert_eq!(stats.error_samples["redirect"], "redirected with status 302");
+        let categories: Vec<&str> = stats.error_samples.keys().copied().collect();
+        assert_eq!(categories, ["json-parse", "redirect", "status", "write"]);
+    }
+
+    #[test]
+    fn reconnects_when_the_server_drops_a_keep_alive_connection() {
+        use std::net::TcpListener;
+
+        // Answers two requests per connection, then hangs up without saying so.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let response = fixtures::http_response(200, "OK", "{\"value\":7}");
+        thread::spawn(move || {
+            for stream in listener.incoming().take(3) {
+                let mut reader = BufReader::new(stream.unwrap());
+                for _ in 0..2 {
+                    let mut line = String::new();
+                    while line != "\r\n" {
+                        line.clear();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                    }
+                    reader.get_mut().write_all(&response).unwrap();
+                }
+            }
+        });
+        let persistent = ConnectionMode::Persistent;
+        let stats = run_worker(&local(port), 0..5, persistent, 1, UNLIMITED, GET, &|| {});
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.completed, 5);
+        assert_eq!(stats.checksum, 35);
+        assert_eq!(stats.connections_opened, 3);
+        assert_eq!(stats.reconnects, 2);
+        assert_eq!(stats.connections_closed, 3);
+    }
+
+    #[test]
+    fn a_silent_server_times_out_and_a_spent_budget_skips_the_rest() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Accepts, then never answers.
+        thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(5));
+        });
+        let limits = WorkerLimits {
+            timeout: Duration::from_millis(50),
+            ..UNLIMITED
+        };
+        let persistent = ConnectionMode::Persistent;
+        let start = Instant::now();
+        let stats = run_worker(&local(port), 0..1, persistent, 1, limits, GET, &|| {});
+        assert!(start.elapsed() < Duration::from_secs(2), "{:?}", start.elapsed());
+        assert_eq!((stats.errors, stats.timeout_errors, stats.transport_errors()), (1, 1, 0));
+
+        let spent = WorkerLimits {
+            deadline: Some(Instant::now()),
+            ..limits
+        };
+        let stats = run_worker(&local(port), 0..5, persistent, 1, spent, GET, &|| {});
+        assert_eq!((stats.skipped_requests, stats.errors), (5, 0));
+        assert_eq!(stats.connections_opened, 0);
+    }
+
+    #[test]
+    fn open_loop_paces_starts_and_still_ends_when_behind() {
+        use crate::mock_server::{ChaosConfig, MockServer};
+
+        let server = MockServer::start(ChaosConfig::default(), DEFAULT_SEED).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        let paced = |pace: Duration| WorkerHooks {
+            limits: RequestLimits {
+                pace: Some(pace),
+                ..RequestLimits::default()
+            },
+            ..WorkerHooks::default()
+        };
+        let persistent = ConnectionMode::Persistent;
+        // 12 slots 10 ms apart over 3 workers: the last starts at 110 ms.
+        let start = Instant::now();
+        let hooks = paced(Duration::from_millis(10));
+        let stats = run_workers_with(&target, 12, 10, 3, persistent, &hooks);
+        assert!(start.elapsed() >= Duration::from_millis(110), "{:?}", start.elapsed());
+        assert_eq!((stats.completed, stats.schedule_lag_us.len()), (12, 12));
+
+        // A rate no server keeps up with: every request is sent once, late.
+        let start = Instant::now();
+        let hooks = paced(Duration::from_nanos(1));
+        let stats = run_workers_with(&target, 200, 10, 2, persistent, &hooks);
+        assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+        assert_eq!((stats.completed, stats.errors), (200, 0));
+        assert!(stats.schedule_lag_us.percentile(99.0, crate::stats::Rank::Nearest) > 0.0);
+
+        // Closed loop keeps no lag.
+        let stats = run_workers_with(&target, 4, 10, 2, persistent, &WorkerHooks::default());
+        assert!(stats.schedule_lag_us.is_empty());
+    }
+
+    #[test]
+    fn workers_pull_requests_until_none_are_left() {
+        // Accepted by the kernel and never read from: the mock send below
+        // answers without touching the connection.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = local(listener.local_addr().unwrap().port());
+        // The first six ids are slow. With fixed shares of ten they would
+        // all land on the first worker, which would then run for 300 ms.
+        // `SendItem` borrows nothing, so the log of pulled ids is a static.
+        static PULLED: std::sync::Mutex<Vec<usize>> = std::sync::Mutex::new(Vec::new());
+        let send = |_: &mut HttpConnection, item: &ItemRequest| {
+            PULLED.lock().unwrap().push(item.item_id);
+            let delay_ms = if item.item_id < 6 { 50 } else { 1 };
+            thread::sleep(Duration::from_millis(delay_ms));
+            Ok(Exchange {
+                status: StatusBucket::Code(200),
+                value: Ok(item.item_id as u64),
+                reusable: true,
+                bytes_sent: 0,
+                bytes_received: 0,
+                body_bytes: 0,
+            })
+        };
+        let hooks = WorkerHooks {
+            send: &send,
+            ..WorkerHooks::default()
+        };
+        let stats = run_workers_with(&target, 40, 40, 4, ConnectionMode::Persistent, &hooks);
+        assert_eq!((stats.completed, stats.errors), (40, 0));
+        // Every id is pulled exactly once. How long that takes is left to
+        // the scheduler; only the skew below says the queue was shared.
+        let mut pulled = PULLED.lock().unwrap().clone();
+        pulled.sort_unstable();
+        assert_eq!(pulled, (0..40).collect::<Vec<_>>());
+        assert_eq!(stats.checksum, (0..40).sum::<u64>());
+        assert_eq!(stats.connections_opened, 4);
+        // The workers that drew slow ids sent fewer requests.
+        assert!(stats.worker_completion_skew > 0, "{}", stats.worker_completion_skew);
+        assert!(stats.worker_completion_skew < 40);
+
+        // More workers than requests: the extra ones are never started.
+        let stats = run_workers_with(&target, 2, 40, 4, ConnectionMode::Persistent, &hooks);
+        assert_eq!((stats.completed, stats.connections_opened), (2, 2));
+        assert_eq!(stats.worker_completion_skew, 0);
+    }
+
+    #[test]
+    fn schedule_slots_interleave_workers() {
+        let start = Instant::now();
+        let pace = Duration::from_millis(2);
+        let mut schedules: Vec<Schedule> = (0..3u32)
+            .map(|worker| Schedule {
+                next: start + pace * worker,
+                every: pace * 3,
+            })
+            .collect();
+        let mut slots: Vec<Duration> = Vec::new();
+        for _ in 0..2 {
+            for schedule in &mut schedules {
+                slots.push(schedule.wait() - start);
+            }
+        }
+        let expected: Vec<Duration> = (0..6u32).map(|slot| pace * slot).collect();
+        assert_eq!(slots, expected);
+    }
+
+    #[test]
+    fn buckets_status_lines() {
+        assert_eq!(StatusBucket::parse("HTTP/1.1 200 OK\r\n"), StatusBucket::Code(200));
+        assert_eq!(StatusBucket::parse("HTTP/1.0 503"), StatusBucket::Code(503));
+        for line in ["HTTP/1.1 999 Nope", "HTTP/1.1 099 Early", "HTTP/1.1 2000 OK", "ICY 200 OK", "garbage", ""] {
+            assert_eq!(StatusBucket::parse(line), StatusBucket::Malformed, "{line:?}");
+        }
+        assert_eq!(StatusBucket::Code(404).label(), "404");
+        assert_eq!(StatusBucket::Malformed.label(), "malformed");
+    }
+
+    #[test]
+    fn merges_status_histograms_across_workers() {
+        let worker = |entries: &[(StatusBucket, usize)]| WorkerStats {
+            statuses: entries.iter().copied().collect(),
+            ..WorkerStats::default()
+        };
+        let mut total = worker(&[(StatusBucket::Code(200), 3), (StatusBucket::Code(500), 1)]);
+        total.merge(worker(&[(StatusBucket::Code(200), 2), (StatusBucket::Malformed, 4)]));
+        total.merge(worker(&[]));
+        assert_eq!(total.status_counts(), json!({"200": 5, "500": 1, "malformed": 4}));
+        let merged: Vec<_> = total.statuses.into_iter().collect();
+        assert_eq!(
+            merged,
+            [
+                (StatusBucket::Code(200), 5),
+                (StatusBucket::Code(500), 1),
+                (StatusBucket::Malformed, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_head_is_an_error() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n";
+        assert!(read_response(&mut &raw[..]).is_err());
+    }
+}
@@ -0,0 +1,460 @@
+//! `--interactive`: pick benchmarks and a preset in the terminal, then watch
+//! one progress bar per benchmark while the suite runs. The picker only
+//! fills in what `--benchmarks` and `--preset` would, so the results file is
+//! the same as a scripted run with that selection. Key handling, the
+//! selection and the estimates are plain state; the crossterm glue at the
+//! bottom only reads keys and draws the lines `render` returns.
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+use serde_json::Value;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::matrix;
+
+const BAR_WIDTH: usize = 24;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Both ends are terminals; otherwise `--interactive` falls back to the
+/// usual run.
+pub(crate) fn available() -> bool {
+    io::stdout().is_terminal() && io::stdin().is_terminal()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Key {
+    Up,
+    Down,
+    Toggle,
+    ToggleAll,
+    CyclePreset,
+    Confirm,
+    Cancel,
+}
+
+impl Key {
+    /// Arrows or `j`/`k` move, space toggles, `a` toggles everything, `p`
+    /// cycles the preset, enter runs, and `q`, escape or ctrl-c quit.
+    pub(crate) fn from_event(key: KeyEvent) -> Option<Key> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Key::Cancel)
+            }
+            KeyCode::Up | KeyCode::Char('k') => Some(Key::Up),
+            KeyCode::Down | KeyCode::Char('j') => Some(Key::Down),
+            KeyCode::Char(' ') => Some(Key::Toggle),
+            KeyCode::Char('a') => Some(Key::ToggleAll),
+            KeyCode::Char('p') => Some(Key::CyclePreset),
+            KeyCode::Enter => Some(Key::Confirm),
+            KeyCode::Esc | KeyCode::Char('q') => Some(Key::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// What the picker hands back: the `--benchmarks` and `--preset auto` a
+/// scripted run would pass.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Selection {
+    pub(crate) benchmarks: Vec<&'static str>,
+    pub(crate) auto_preset: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Run(Selection),
+    Cancelled,
+}
+
+struct Entry {
+    id: &'static str,
+    category: &'static str,
+    selected: bool,
+    /// `runtime_seconds` from the previous results file, if it has one.
+    estimate: Option<f64>,
+}
+
+pub(crate) struct Picker {
+    entries: Vec<Entry>,
+    cursor: usize,
+    auto_preset: bool,
+    /// Shown under the list until the next key.
+    notice: Option<&'static str>,
+}
+
+impl Picker {
+    /// `benchmarks` are `(id, category)` in run order; `preselected` is a
+    /// `--benchmarks` list, empty for all of them.
+    pub(crate) fn new(
+        benchmarks: &[(&'static str, &'static str)],
+        preselected: &[&str],
+        auto_preset: bool,
+        previous: &[Value],
+    ) -> Self {
+        let entries = benchmarks
+            .iter()
+            .map(|&(id, category)| Entry {
+                id,
+                category,
+                selected: preselected.is_empty() || preselected.contains(&id),
+                estimate: matrix::previous_runtime(id, previous),
+            })
+            .collect();
+        Picker {
+            entries,
+            cursor: 0,
+            auto_preset,
+            notice: None,
+        }
+    }
+
+    /// Applies `key`; `Some` once the user confirmed or quit.
+    pub(crate) fn handle(&mut self, key: Key) -> Option<Outcome> {
+        self.notice = None;
+        let last = self.entries.len().saturating_sub(1);
+        match key {
+            Key::Up => self.cursor = self.cursor.checked_sub(1).unwrap_or(last),
+            Key::Down => self.cursor = if self.cursor >= last { 0 } else { self.cursor + 1 },
+            Key::Toggle => {
+                if let Some(entry) = self.entries.get_mut(self.cursor) {
+                    entry.selected = !entry.selected;
+                }
+            }
+            Key::ToggleAll => {
+                let select = !self.entries.iter().all(|entry| entry.selected);
+                self.entries.iter_mut().for_each(|entry| entry.selected = select);
+            }
+            Key::CyclePreset => self.auto_preset = !self.auto_preset,
+            Key::Confirm if self.selection().benchmarks.is_empty() => {
+                self.notice = Some("select at least one benchmark");
+            }
+            Key::Confirm => return Some(Outcome::Run(self.selection())),
+            Key::Cancel => return Some(Outcome::Cancelled),
+        }
+        None
+    }
+
+    pub(crate) fn selection(&self) -> Selection {
+        Selection {
+            benchmarks: self
+                .entries
+                .iter()
+                .filter(|entry| entry.selected)
+                .map(|entry| entry.id)
+                .collect(),
+            auto_preset: self.auto_preset,
+        }
+    }
+
+    /// Summed previous runtimes of the selected benchmarks, and how many of
+    /// them have none.
+    pub(crate) fn estimate_seconds(&self) -> (f64, usize) {
+        let selected = self.entries.iter().filter(|entry| entry.selected);
+        selected.fold((0.0, 0), |(seconds, unknown), entry| match entry.estimate {
+            Some(estimate) => (seconds + estimate, unknown),
+            None => (seconds, unknown + 1),
+        })
+    }
+
+    pub(crate) fn render(&self) -> Vec<String> {
+        let width = self.entries.iter().map(|entry| entry.id.len()).max().unwrap_or(0);
+        let mut lines = vec![
+            "Select benchmarks: up/down move, space toggle, a all, p preset, enter run, q quit"
+                .to_string(),
+            String::new(),
+        ];
+        for (index, entry) in self.entries.iter().enumerate() {
+            let pointer = if index == self.cursor { '>' } else { ' ' };
+            let check = if entry.selected { 'x' } else { ' ' };
+            lines.push(format!(
+                "{pointer} [{check}] {:width$}  {:11}  {}",
+                entry.id,
+                entry.category,
+                estimate_label(entry.estimate)
+            ));
+        }
+        let preset = if self.auto_preset { "auto" } else { "default" };
+        let (seconds, unknown) = self.estimate_seconds();
+        let mut summary = format!("preset: {preset}    estimated total: {seconds:.1}s");
+        if unknown > 0 {
+            summary.push_str(&format!(" (+{unknown} without a previous runtime)"));
+        }
+        lines.push(String::new());
+        lines.push(summary);
+        if let Some(notice) = self.notice {
+            lines.push(notice.to_string());
+        }
+        lines
+    }
+}
+
+fn estimate_label(estimate: Option<f64>) -> String {
+    estimate.map_or_else(|| "~?".to_string(), |seconds| format!("~{seconds:.1}s"))
+}
+
+/// Progress of one run: benchmarks finish in order, so the first unfinished
+/// row is the running one. Its bar follows the elapsed share of its
+/// previous runtime and stops short of full until it really finishes.
+pub(crate) struct ProgressState {
+    rows: Vec<(&'static str, Option<f64>)>,
+    finished: usize,
+    started: Instant,
+}
+
+impl ProgressState {
+    /// `rows` are the benchmarks about to run, each with its estimate.
+    pub(crate) fn new(rows: Vec<(&'static str, Option<f64>)>) -> Self {
+        ProgressState {
+            rows,
+            finished: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// The running benchmark finished; the next one starts now.
+    pub(crate) fn advance(&mut self) {
+        self.finished = (self.finished + 1).min(self.rows.len());
+        self.started = Instant::now();
+    }
+
+    /// Fill of row `index` in `0.0..=1.0`, `elapsed` into the running one.
+    pub(crate) fn fraction(&self, index: usize, elapsed: Duration) -> f64 {
+        if index < self.finished {
+            return 1.0;
+        }
+        if index > self.finished {
+            return 0.0;
+        }
+        match self.rows.get(index).and_then(|(_, estimate)| *estimate) {
+            Some(estimate) if estimate > 0.0 => (elapsed.as_secs_f64() / estimate).min(0.95),
+            _ => 0.0,
+        }
+    }
+
+    pub(crate) fn render(&self, elapsed: Duration) -> Vec<String> {
+        let width = self.rows.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(index, (id, estimate))| {
+                let filled = (self.fraction(index, elapsed) * BAR_WIDTH as f64).round() as usize;
+                let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+                let status = match index.cmp(&self.finished) {
+                    std::cmp::Ordering::Less => "done".to_string(),
+                    std::cmp::Ordering::Equal => format!(
+                        "running {:.1}s / {}",
+                        elapsed.as_secs_f64(),
+                        estimate_label(*estimate)
+                    ),
+                    std::cmp::Ordering::Greater => "pending".to_string(),
+                };
+                format!("{id:width$}  [{bar}]  {status}")
+            })
+            .collect()
+    }
+}
+
+/// Runs the picker on stderr's alternate screen until the user confirms or
+/// quits.
+pub(crate) fn pick(mut picker: Picker) -> io::Result<Outcome> {
+    let mut out = io::stderr();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let outcome = loop {
+        if let Err(err) = draw(&mut out, &picker.render()) {
+            break Err(err);
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) => Key::from_event(key),
+            Ok(_) => None,
+            Err(err) => break Err(err),
+        };
+        if let Some(outcome) = key.and_then(|key| picker.handle(key)) {
+            break Ok(outcome);
+        }
+    };
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    outcome
+}
+
+fn draw(out: &mut impl Write, lines: &[String]) -> io::Result<()> {
+    execute!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+    for line in lines {
+        write!(out, "{line}\r\n")?;
+    }
+    out.flush()
+}
+
+/// Redraws the progress rows on stderr until `finish`. Lines other code
+/// prints meanwhile scroll them up; the next redraw starts below them.
+pub(crate) struct Progress {
+    state: Arc<Mutex<ProgressState>>,
+    stop: Arc<Mutex<bool>>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    pub(crate) fn start(state: ProgressState) -> Self {
+        let state = Arc::new(Mutex::new(state));
+        let stop = Arc::new(Mutex::new(false));
+        let ticker = {
+            let (state, stop) = (Arc::clone(&state), Arc::clone(&stop));
+            thread::spawn(move || {
+                let mut drawn = 0;
+                loop {
+                    let done = *stop.lock().unwrap_or_else(|err| err.into_inner());
+                    drawn = redraw(&state, drawn);
+                    if done {
+                        break;
+                    }
+                    thread::sleep(REDRAW_INTERVAL);
+                }
+            })
+        };
+        Progress {
+            state,
+            stop,
+            ticker: Some(ticker),
+        }
+    }
+
+    pub(crate) fn advance(&self) {
+        self.state.lock().unwrap_or_else(|err| err.into_inner()).advance();
+    }
+
+    /// Draws the final state and stops redrawing.
+    pub(crate) fn finish(mut self) {
+        *self.stop.lock().unwrap_or_else(|err| err.into_inner()) = true;
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+        }
+    }
+}
+
+/// Overwrites the `drawn` lines of the previous redraw; returns how many
+/// lines this one drew.
+fn redraw(state: &Mutex<ProgressState>, drawn: usize) -> usize {
+    let lines = {
+        let state = state.lock().unwrap_or_else(|err| err.into_inner());
+        state.render(state.started.elapsed())
+    };
+    let mut out = io::stderr().lock();
+    if drawn > 0 {
+        let _ = execute!(out, cursor::MoveUp(drawn as u16));
+    }
+    for line in &lines {
+        let _ = execute!(out, terminal::Clear(terminal::ClearType::CurrentLine));
+        let _ = writeln!(out, "{line}");
+    }
+    let _ = out.flush();
+    lines.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const BENCHMARKS: [(&str, &str); 3] = [
+        ("cpu_monte_carlo_pi", "performance"),
+        ("io_concurrent_http_client", "performance"),
+        ("build_startup_feedback_loop", "quality"),
+    ];
+
+    fn previous() -> Vec<Value> {
+        let record = |id: &str, seconds: f64| {
+            json!({"benchmark_id": id, "metrics": {"runtime_seconds": {"value": seconds}}})
+        };
+        vec![record("cpu_monte_carlo_pi", 1.5), record("build_startup_feedback_loop", 8.0)]
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn keys_map_from_presses_only() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(Key::from_event(press(KeyCode::Up, none)), Some(Key::Up));
+        assert_eq!(Key::from_event(press(KeyCode::Char('j'), none)), Some(Key::Down));
+        assert_eq!(Key::from_event(press(KeyCode::Char(' '), none)), Some(Key::Toggle));
+        assert_eq!(Key::from_event(press(KeyCode::Enter, none)), Some(Key::Confirm));
+        assert_eq!(Key::from_event(press(KeyCode::Esc, none)), Some(Key::Cancel));
+        let ctrl_c = press(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(Key::from_event(ctrl_c), Some(Key::Cancel));
+        assert_eq!(Key::from_event(press(KeyCode::Char('c'), none)), None);
+        // Windows reports releases too; only the press counts.
+        let mut release = press(KeyCode::Char(' '), none);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(Key::from_event(release), None);
+    }
+
+    #[test]
+    fn keys_move_toggle_and_confirm_a_selection() {
+        let mut picker = Picker::new(&BENCHMARKS, &[], false, &previous());
+        assert_eq!(picker.selection().benchmarks.len(), 3);
+        // Up from the top wraps to the last row.
+        for key in [Key::Up, Key::Toggle, Key::Down, Key::Down, Key::Toggle, Key::CyclePreset] {
+            assert_eq!(picker.handle(key), None);
+        }
+        let expected = Selection {
+            benchmarks: vec!["cpu_monte_carlo_pi"],
+            auto_preset: true,
+        };
+        assert_eq!(picker.handle(Key::Confirm), Some(Outcome::Run(expected)));
+
+        // Nothing selected: confirming only shows a notice.
+        assert_eq!(picker.handle(Key::ToggleAll), None);
+        assert_eq!(picker.handle(Key::ToggleAll), None);
+        assert!(picker.selection().benchmarks.is_empty());
+        assert_eq!(picker.handle(Key::Confirm), None);
+        assert!(picker.render().last().unwrap().contains("at least one"));
+        assert_eq!(picker.handle(Key::Cancel), Some(Outcome::Cancelled));
+    }
+
+    #[test]
+    fn preselection_and_estimates_come_from_the_flags_and_previous_results() {
+        let preselected = ["build_startup_feedback_loop", "io_concurrent_http_client"];
+        let picker = Picker::new(&BENCHMARKS, &preselected, true, &previous());
+        // Registry order, not the order they were named in.
+        let selection = picker.selection();
+        assert_eq!(
+            selection.benchmarks,
+            ["io_concurrent_http_client", "build_startup_feedback_loop"]
+        );
+        assert!(selection.auto_preset);
+        assert_eq!(picker.estimate_seconds(), (8.0, 1));
+        let lines = picker.render();
+        assert!(lines[2].starts_with("> [ ] cpu_monte_carlo_pi"), "{}", lines[2]);
+        assert!(lines[2].ends_with("~1.5s"));
+        assert!(lines[3].ends_with("~?"));
+        assert!(lines.last().unwrap().contains("estimated total: 8.0s (+1 without"));
+    }
+
+    #[test]
+    fn progress_fills_finished_rows_and_caps_the_running_one() {
+        let mut state = ProgressState::new(vec![("a", Some(2.0)), ("b", None), ("c", Some(1.0))]);
+        let second = Duration::from_secs(1);
+        assert_eq!(state.fraction(0, second), 0.5);
+        assert_eq!(state.fraction(0, second * 10), 0.95);
+        assert_eq!(state.fraction(1, second), 0.0);
+        state.advance();
+        assert_eq!(state.fraction(0, second), 1.0);
+        assert_eq!(state.fraction(1, second), 0.0);
+        let lines = state.render(second);
+        assert!(lines[0].ends_with("done"));
+        assert!(lines[1].ends_with("running 1.0s / ~?"));
+        assert!(lines[2].ends_with("pending"));
+        state.advance();
+        state.advance();
+        state.advance();
+        assert!(state.render(second).iter().all(|line| line.contains(&"#".repeat(BAR_WIDTH))));
+    }
+}
@@ -0,0 +1,279 @@
+//! Where the binary was started relative to the repository.
+//!
+//! The runner is meant to start at the repo root, but people also start it
+//! from inside `benchmarks/rust/` or from a release tarball with no repo
+//! around it. The root is the nearest directory at or above the working
+//! directory that holds the Rust sources or the shared fixtures; what it
+//! holds decides the execution context, and benchmarks that need a missing
+//! part are skipped with a reason instead of failing one by one.
+
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::benchmark_ids::{
+    self, BUILD_STARTUP_FEEDBACK_LOOP, DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    STATIC_SECURITY_LINT_BENCHMARK, TEST_ROBUSTNESS_RELIABILITY,
+};
+
+/// The crate the cargo-driven benchmarks build, lint and scan.
+const SOURCES: &str = "benchmarks/rust/Cargo.toml";
+/// Datasets, schemas and advisory snapshots shared with the Python runner.
+const FIXTURES: &str = "benchmarks/shared";
+const GIT: &str = ".git";
+
+/// Benchmarks that run cargo against `SOURCES`.
+const NEEDS_SOURCES: [&str; 4] = [
+    DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    STATIC_SECURITY_LINT_BENCHMARK,
+    TEST_ROBUSTNESS_RELIABILITY,
+    BUILD_STARTUP_FEEDBACK_LOOP,
+];
+
+/// Each part of a checkout and the benchmarks that cannot run without it.
+const NEEDED_BY: [(&str, &[&str]); 3] = [
+    (SOURCES, &NEEDS_SOURCES),
+    // The crate's own tests, which `cargo test` builds, read the shared
+    // result schema.
+    (FIXTURES, &[TEST_ROBUSTNESS_RELIABILITY]),
+    // History only names the recorded commit; without it that reads
+    // `unknown00`, and every benchmark still runs.
+    (GIT, &[]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExecutionContext {
+    /// A checkout: sources, fixtures and git history.
+    Full,
+    /// A repo root with some of those missing, e.g. a source export.
+    Partial,
+    /// No repo at or above the working directory.
+    Standalone,
+}
+
+impl ExecutionContext {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ExecutionContext::Full => "full",
+            ExecutionContext::Partial => "partial",
+            ExecutionContext::Standalone => "standalone",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Layout {
+    /// The repo root, or the working directory when there is none.
+    pub(crate) root: PathBuf,
+    pub(crate) context: ExecutionContext,
+    /// Parts of a full checkout the root lacks, as repo-relative paths.
+    pub(crate) missing: Vec<&'static str>,
+}
+
+/// A benchmark left out of the run, and why.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct Skipped {
+    pub(crate) benchmark_id: &'static str,
+    pub(crate) reason: String,
+}
+
+impl Layout {
+    /// Detects the layout around `start`, searching its ancestors.
+    pub(crate) fn detect(start: &Path) -> Self {
+        let Some(root) = start.ancestors().find(|dir| is_root(dir)) else {
+            return Layout {
+                root: start.to_path_buf(),
+                context: ExecutionContext::Standalone,
+                missing: vec![SOURCES, FIXTURES, GIT],
+            };
+        };
+        let missing: Vec<&'static str> =
+            [SOURCES, FIXTURES, GIT].into_iter().filter(|part| !root.join(part).exists()).collect();
+        let context = if missing.is_empty() {
+            ExecutionContext::Full
+        } else {
+            ExecutionContext::Partial
+        };
+        Layout {
+            root: root.to_path_buf(),
+            context,
+            missing,
+        }
+    }
+
+    /// Why `benchmark_id` cannot run here, naming every missing part it
+    /// needs; `None` when it can.
+    pub(crate) fn skip_reason(&self, benchmark_id: &str) -> Option<String> {
+        let parts: Vec<&str> = NEEDED_BY
+            .iter()
+            .filter(|(part, needed_by)| {
+                needed_by.contains(&benchmark_id) && self.missing.contains(part)
+            })
+            .map(|(part, _)| *part)
+            .collect();
+        if parts.is_empty() {
+            return None;
+        }
+        let parts = parts.join(" and ");
+        Some(match self.context {
+            ExecutionContext::Standalone => format!(
+                "needs the repository's {parts}; no repository at or above {}",
+                self.root.display()
+            ),
+            _ => format!("needs {parts}, which {} does not have", self.root.display()),
+        })
+    }
+
+    /// `selection` (every benchmark when empty) without the benchmarks that
+    /// cannot run here, and those left out. An empty selection stays empty
+    /// when nothing is left out.
+    pub(crate) fn narrow(&self, selection: &[&'static str]) -> (Vec<&'static str>, Vec<Skipped>) {
+        let mut runnable = Vec::new();
+        let mut skipped = Vec::new();
+        for benchmark_id in benchmark_ids::ALL {
+            if !benchmark_ids::is_selected(selection, benchmark_id) {
+                continue;
+            }
+            match self.skip_reason(benchmark_id) {
+                Some(reason) => skipped.push(Skipped {
+                    benchmark_id,
+                    reason,
+                }),
+                None => runnable.push(benchmark_id),
+            }
+        }
+        if skipped.is_empty() {
+            return (selection.to_vec(), skipped);
+        }
+        (runnable, skipped)
+    }
+}
+
+fn is_root(dir: &Path) -> bool {
+    dir.join(SOURCES).is_file() || dir.join(FIXTURES).is_dir()
+}
+
+/// The layout around the working directory, detected once per process.
+pub(crate) fn current() -> &'static Layout {
+    static LAYOUT: OnceLock<Layout> = OnceLock::new();
+    LAYOUT.get_or_init(|| {
+        Layout::detect(&env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("layout-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Creates the given parts of a checkout under `root`.
+    fn populate(root: &Path, parts: &[&str]) {
+        for part in parts {
+            match *part {
+                SOURCES => {
+                    fs::create_dir_all(root.join("benchmarks/rust/src")).unwrap();
+                    fs::write(root.join(SOURCES), "[package]\n").unwrap();
+                }
+                dir => fs::create_dir_all(root.join(dir)).unwrap(),
+            }
+        }
+    }
+
+    #[test]
+    fn a_checkout_is_full_from_the_root_or_inside_it() {
+        let root = temp_dir("full");
+        populate(&root, &[SOURCES, FIXTURES, GIT]);
+        let inside = [root.join("benchmarks/rust"), root.join("benchmarks/rust/src")];
+        for start in [&root].into_iter().chain(&inside) {
+            let layout = Layout::detect(start);
+            assert_eq!(layout.context, ExecutionContext::Full, "{}", start.display());
+            assert_eq!(layout.root, root);
+            assert!(layout.missing.is_empty());
+        }
+        assert_eq!(Layout::detect(&root).skip_reason(BUILD_STARTUP_FEEDBACK_LOOP), None);
+        assert_eq!(Layout::detect(&root).narrow(&[]), (Vec::new(), Vec::new()));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn an_export_without_git_or_fixtures_skips_only_what_needs_the_fixtures() {
+        let root = temp_dir("export");
+        populate(&root, &[SOURCES]);
+        let layout = Layout::detect(&root.join("benchmarks/rust"));
+        assert_eq!(layout.context, ExecutionContext::Partial);
+        assert_eq!(layout.root, root);
+        assert_eq!(layout.missing, [FIXTURES, GIT]);
+        let reason = layout.skip_reason(TEST_ROBUSTNESS_RELIABILITY).unwrap();
+        assert_eq!(
+            reason,
+            format!("needs {FIXTURES}, which {} does not have", root.display())
+        );
+        let (runnable, skipped) = layout.narrow(&[]);
+        assert_eq!(runnable.len(), benchmark_ids::ALL.len() - 1);
+        assert_eq!(skipped.len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn every_part_of_a_checkout_says_what_needs_it() {
+        let parts: Vec<&str> = NEEDED_BY.iter().map(|(part, _)| *part).collect();
+        assert_eq!(parts, [SOURCES, FIXTURES, GIT]);
+        for (part, needed_by) in NEEDED_BY {
+            for benchmark_id in needed_by {
+                assert!(benchmark_ids::ALL.contains(benchmark_id), "{part}: {benchmark_id}");
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures_without_sources_skip_the_cargo_benchmarks() {
+        let root = temp_dir("fixtures");
+        populate(&root, &[FIXTURES, GIT]);
+        let layout = Layout::detect(&root);
+        assert_eq!(layout.context, ExecutionContext::Partial);
+        assert_eq!(layout.missing, [SOURCES]);
+        let reason = layout.skip_reason(TEST_ROBUSTNESS_RELIABILITY).unwrap();
+        assert_eq!(
+            reason,
+            format!("needs {SOURCES}, which {} does not have", root.display())
+        );
+        assert_eq!(layout.skip_reason("cpu_monte_carlo_pi"), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn no_repo_is_standalone() {
+        let dir = temp_dir("standalone");
+        // A `.git` alone does not make a directory the benchmarks' repo.
+        populate(&dir, &[GIT]);
+        let layout = Layout::detect(&dir);
+        assert_eq!(layout.context, ExecutionContext::Standalone);
+        assert_eq!(layout.root, dir);
+        for benchmark_id in NEEDS_SOURCES {
+            let reason = layout.skip_reason(benchmark_id).unwrap();
+            assert!(reason.contains("no repository at or above"), "{reason}");
+        }
+        let reason = layout.skip_reason(TEST_ROBUSTNESS_RELIABILITY).unwrap();
+        assert!(reason.starts_with(&format!("needs the repository's {SOURCES} and {FIXTURES};")));
+        assert_eq!(layout.skip_reason("io_concurrent_http_client"), None);
+
+        let (runnable, skipped) = layout.narrow(&[]);
+        assert_eq!(runnable.len(), benchmark_ids::ALL.len() - NEEDS_SOURCES.len());
+        assert!(!runnable.contains(&BUILD_STARTUP_FEEDBACK_LOOP));
+        let skipped: Vec<&str> = skipped.iter().map(|skip| skip.benchmark_id).collect();
+        assert_eq!(skipped, NEEDS_SOURCES);
+        let (runnable, skipped) = layout.narrow(&[TEST_ROBUSTNESS_RELIABILITY]);
+        assert!(runnable.is_empty());
+        assert_eq!(skipped.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
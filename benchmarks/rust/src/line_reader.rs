@@ -0,0 +1,120 @@
+//! Line reading with a per-line size cap. `BufRead::lines` grows its buffer
+//! until it finds a newline, so one pathological line (a corrupt dataset
+//! row, a clippy diagnostic with an enormous rendered snippet) can allocate
+//! hundreds of MB. Here a line past the cap is skipped in place: at most
+//! `max + 1` bytes of it are ever buffered.
+
+use std::io::{self, BufRead, Read};
+
+/// Generous for any real dataset row or tool message.
+pub(crate) const DEFAULT_MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Line {
+    Eof,
+    /// `buf` holds the line, newline included; the count is bytes read.
+    Complete(usize),
+    /// A line longer than the cap was read past and dropped; `buf` is
+    /// empty and the count is the bytes skipped, newline included.
+    Oversized(u64),
+}
+
+/// Reads one line into `buf` (cleared first) unless its content, newline
+/// excluded, exceeds `max` bytes.
+pub(crate) fn read_line(
+    reader: &mut impl BufRead,
+    buf: &mut Vec<u8>,
+    max: usize,
+) -> io::Result<Line> {
+    buf.clear();
+    let limit = max as u64 + 1;
+    let read = reader.by_ref().take(limit).read_until(b'\n', buf)?;
+    if read == 0 {
+        return Ok(Line::Eof);
+    }
+    if buf.ends_with(b"\n") || (read as u64) < limit {
+        return Ok(Line::Complete(read));
+    }
+    buf.clear();
+    Ok(Line::Oversized(read as u64 + skip_line(reader)?))
+}
+
+/// Consumes input up to and including the next newline without buffering
+/// it; returns the bytes consumed.
+pub(crate) fn skip_line(reader: &mut impl BufRead) -> io::Result<u64> {
+    let mut skipped = 0u64;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(skipped);
+        }
+        let (used, found) = match available.iter().position(|b| *b == b'\n') {
+            Some(idx) => (idx + 1, true),
+            None => (available.len(), false),
+        };
+        reader.consume(used);
+        skipped += used as u64;
+        if found {
+            return Ok(skipped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn read_all(input: &[u8], max: usize) -> Vec<Line> {
+        // A small buffer so the skip spans several refills.
+        let mut reader = BufReader::with_capacity(4, input);
+        let mut buf = Vec::new();
+        let mut lines = Vec::new();
+        loop {
+            let line = read_line(&mut reader, &mut buf, max).unwrap();
+            if line == Line::Eof {
+                return lines;
+            }
+            lines.push(line);
+        }
+    }
+
+    #[test]
+    fn skips_only_lines_over_the_cap() {
+        let input = b"abcd\nabcde\n0123456789abcdef\nxy";
+        assert_eq!(
+            read_all(input, 5),
+            vec![
+                Line::Complete(5),
+                Line::Complete(6),
+                Line::Oversized(17),
+                Line::Complete(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn final_line_without_newline_is_capped_too() {
+        assert_eq!(
+            read_all(b"ok\n123456", 5),
+            vec![Line::Complete(3), Line::Oversized(6)]
+        );
+        assert_eq!(read_all(b"12345", 5), vec![Line::Complete(5)]);
+    }
+
+    #[test]
+    fn keeps_reading_after_an_oversized_line() {
+        let mut reader = BufReader::new(&b"aaaaaaaa\nb\n"[..]);
+        let mut buf = Vec::new();
+        assert_eq!(
+            read_line(&mut reader, &mut buf, 3).unwrap(),
+            Line::Oversized(9)
+        );
+        assert!(buf.is_empty());
+        assert_eq!(
+            read_line(&mut reader, &mut buf, 3).unwrap(),
+            Line::Complete(2)
+        );
+        assert_eq!(buf, b"b\n");
+    }
+}
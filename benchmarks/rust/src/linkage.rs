@@ -0,0 +1,266 @@
+//! How an executable is linked, read straight from its headers: ELF program
+//! headers (`PT_INTERP`, `DT_NEEDED`) or Mach-O load commands. No `ldd`, so
+//! nothing from the inspected binary is ever run.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LinkInfo {
+    /// No program interpreter / dynamic linker is requested.
+    pub(crate) statically_linked: bool,
+    /// Direct dependencies only (`DT_NEEDED` entries or dylib load commands).
+    pub(crate) shared_libraries: usize,
+}
+
+/// `None` for formats this parser does not understand (PE, fat Mach-O) or
+/// truncated headers.
+pub(crate) fn inspect(bytes: &[u8]) -> Option<LinkInfo> {
+    if bytes.starts_with(b"\x7fELF") {
+        return inspect_elf(bytes);
+    }
+    inspect_macho(bytes)
+}
+
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+
+/// Fixed-width reads in the file's byte order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn uint(&self, offset: usize, width: usize) -> Option<u64> {
+        let field = self.bytes.get(offset..offset.checked_add(width)?)?;
+        let fold = |acc: u64, byte: &u8| (acc << 8) | u64::from(*byte);
+        Some(if self.little_endian {
+            field.iter().rev().fold(0, fold)
+        } else {
+            field.iter().fold(0, fold)
+        })
+    }
+
+    fn u16(&self, offset: usize) -> Option<usize> {
+        self.uint(offset, 2).map(|value| value as usize)
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        self.uint(offset, 4).map(|value| value as u32)
+    }
+
+    /// An offset or size field; `None` if it does not fit in `usize`.
+    fn size(&self, offset: usize, width: usize) -> Option<usize> {
+        usize::try_from(self.uint(offset, width)?).ok()
+    }
+}
+
+/// `base + index * stride`, or `None` on overflow. Header fields come from
+/// the file, so garbage must not wrap or panic.
+fn offset_of(base: usize, index: usize, stride: usize) -> Option<usize> {
+    base.checked_add(index.checked_mul(stride)?)
+}
+
+fn inspect_elf(bytes: &[u8]) -> Option<LinkInfo> {
+    let wide = match bytes.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let reader = Reader {
+        bytes,
+        little_endian: *bytes.get(5)? == 1,
+    };
+    // (e_phoff, e_phentsize, e_phnum) offsets and the address width.
+    let (phoff, phentsize, phnum, word) = if wide {
+        (reader.size(0x20, 8)?, reader.u16(0x36)?, reader.u16(0x38)?, 8)
+    } else {
+        (reader.size(0x1c, 4)?, reader.u16(0x2a)?, reader.u16(0x2c)?, 4)
+    };
+    // p_offset and p_filesz sit after p_flags on ELF64, before it on ELF32.
+    let (offset_at, filesz_at) = if wide { (8, 32) } else { (4, 16) };
+
+    let mut info = LinkInfo {
+        statically_linked: true,
+        shared_libraries: 0,
+    };
+    for index in 0..phnum {
+        let header = offset_of(phoff, index, phentsize)?;
+        match reader.u32(header)? {
+            PT_INTERP => info.statically_linked = false,
+            PT_DYNAMIC => {
+                let start = reader.size(header.checked_add(offset_at)?, word)?;
+                let size = reader.size(header.checked_add(filesz_at)?, word)?;
+                info.shared_libraries = needed_entries(&reader, start, size, word)?;
+            }
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/// Counts `DT_NEEDED` tags in the dynamic segment at `start`.
+fn needed_entries(reader: &Reader, start: usize, size: usize, word: usize) -> Option<usize> {
+    let mut needed = 0;
+    for entry in (start..start.checked_add(size)?).step_by(2 * word) {
+        match reader.uint(entry, word)? {
+            DT_NULL => break,
+            DT_NEEDED => needed += 1,
+            _ => {}
+        }
+    }
+    Some(needed)
+}
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const LC_LOAD_DYLINKER: u32 = 0xe;
+const DYLIB_COMMANDS: [u32; 5] = [
+    0xc,         // LC_LOAD_DYLIB
+    0x20,        // LC_LAZY_LOAD_DYLIB
+    0x8000_0018, // LC_LOAD_WEAK_DYLIB
+    0x8000_001f, // LC_REEXPORT_DYLIB
+    0x8000_0023, // LC_LOAD_UPWARD_DYLIB
+];
+
+fn inspect_macho(bytes: &[u8]) -> Option<LinkInfo> {
+    let reader = Reader {
+        bytes,
+        little_endian: true,
+    };
+    let commands_start = match reader.u32(0)? {
+        MH_MAGIC => 28,
+        MH_MAGIC_64 => 32,
+        _ => return None,
+    };
+    let ncmds = reader.u32(16)?;
+    let mut info = LinkInfo {
+        statically_linked: true,
+        shared_libraries: 0,
+    };
+    let mut command = commands_start;
+    for _ in 0..ncmds {
+        let kind = reader.u32(command)?;
+        let size = reader.size(command.checked_add(4)?, 4)?;
+        if kind == LC_LOAD_DYLINKER {
+            info.statically_linked = false;
+        } else if DYLIB_COMMANDS.contains(&kind) {
+            info.shared_libraries += 1;
+        }
+        if size == 0 {
+            return None;
+        }
+        command = command.checked_add(size)?;
+    }
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(bytes: &mut Vec<u8>, offset: usize, value: u64, width: usize) {
+        if bytes.len() < offset + width {
+            bytes.resize(offset + width, 0);
+        }
+        bytes[offset..offset + width].copy_from_slice(&value.to_le_bytes()[..width]);
+    }
+
+    /// A little-endian ELF64 with the given program header types; a
+    /// `PT_DYNAMIC` header points at `needed` `DT_NEEDED` entries.
+    fn elf64(segments: &[u32], needed: usize) -> Vec<u8> {
+        let mut bytes = b"\x7fELF\x02\x01\x01".to_vec();
+        let phoff = 64;
+        let dynamic = phoff + 56 * segments.len();
+        put(&mut bytes, 0x20, phoff as u64, 8);
+        put(&mut bytes, 0x36, 56, 2);
+        put(&mut bytes, 0x38, segments.len() as u64, 2);
+        for (index, kind) in segments.iter().enumerate() {
+            let header = phoff + index * 56;
+            put(&mut bytes, header, u64::from(*kind), 4);
+            put(&mut bytes, header + 8, dynamic as u64, 8);
+            put(&mut bytes, header + 32, 16 * (needed as u64 + 2), 8);
+        }
+        // DT_NEEDED entries, one unrelated tag (DT_STRTAB), then DT_NULL.
+        for entry in 0..needed {
+            put(&mut bytes, dynamic + entry * 16, DT_NEEDED, 8);
+        }
+        put(&mut bytes, dynamic + needed * 16, 5, 8);
+        put(&mut bytes, dynamic + (needed + 1) * 16 + 15, 0, 1);
+        bytes
+    }
+
+    #[test]
+    fn reads_elf_program_headers() {
+        let dynamic = elf64(&[6, PT_INTERP, 1, PT_DYNAMIC], 3);
+        assert_eq!(
+            inspect(&dynamic),
+            Some(LinkInfo {
+                statically_linked: false,
+                shared_libraries: 3,
+            })
+        );
+        // Static PIE: a dynamic segment for relocations, no interpreter.
+        let static_pie = elf64(&[1, PT_DYNAMIC], 0);
+        assert_eq!(
+            inspect(&static_pie),
+            Some(LinkInfo {
+                statically_linked: true,
+                shared_libraries: 0,
+            })
+        );
+        assert_eq!(inspect(&elf64(&[1], 0)).map(|info| info.statically_linked), Some(true));
+    }
+
+    #[test]
+    fn reads_macho_load_commands() {
+        let mut bytes = Vec::new();
+        put(&mut bytes, 0, u64::from(MH_MAGIC_64), 4);
+        let commands = [LC_LOAD_DYLINKER, 0xc, 0x19, 0x8000_0018];
+        put(&mut bytes, 16, commands.len() as u64, 4);
+        for (index, kind) in commands.iter().enumerate() {
+            put(&mut bytes, 32 + index * 24, u64::from(*kind), 4);
+            put(&mut bytes, 32 + index * 24 + 4, 24, 4);
+        }
+        assert_eq!(
+            inspect(&bytes),
+            Some(LinkInfo {
+                statically_linked: false,
+                shared_libraries: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_truncated_input() {
+        assert_eq!(inspect(b"MZ\x90\x00"), None);
+        assert_eq!(inspect(b""), None);
+        let elf = elf64(&[PT_INTERP, PT_DYNAMIC], 2);
+        assert_eq!(inspect(&elf[..100]), None);
+        // Offsets and sizes near the top of the address space.
+        let mut wrapping = elf.clone();
+        put(&mut wrapping, 0x20, u64::MAX - 8, 8);
+        assert_eq!(inspect(&wrapping), None);
+        let mut huge_dynamic = elf.clone();
+        put(&mut huge_dynamic, 64 + 56 + 8, u64::MAX, 8);
+        assert_eq!(inspect(&huge_dynamic), None);
+        let mut huge_size = elf;
+        put(&mut huge_size, 64 + 56 + 32, u64::MAX, 8);
+        assert_eq!(inspect(&huge_size), None);
+        let mut macho = Vec::new();
+        put(&mut macho, 0, u64::from(MH_MAGIC_64), 4);
+        put(&mut macho, 16, 2, 4);
+        put(&mut macho, 32, 0x19, 4);
+        put(&mut macho, 36, u64::from(u32::MAX), 4);
+        assert_eq!(inspect(&macho), None);
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    fn test_binary_links_against_glibc() {
+        let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let info = inspect(&exe).unwrap();
+        assert!(!info.statically_linked);
+        assert!(info.shared_libraries >= 1);
+    }
+}
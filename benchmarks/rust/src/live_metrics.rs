@@ -0,0 +1,381 @@
+//! `--live-metrics`: long-running benchmarks publish interim progress as
+//! compact JSON lines while they run, so a soak can be watched before the
+//! final record exists. Benchmarks only call `LiveContext::progress`; the
+//! throttling and the transport live here. The context also collects the
+//! attempt's `warnings` and `diagnostics`, with or without `--live-metrics`.
+
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::warnings::{Warning, WarningCode, Warnings};
+use crate::{rng, DEFAULT_SEED};
+
+/// Published samples an attempt keeps for its failure context.
+pub(crate) const RECENT_SAMPLES: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// Lines are appended; the file is created when missing.
+    File(PathBuf),
+    /// A listening Unix domain stream socket (e.g. a Telegraf socket input).
+    Unix(PathBuf),
+}
+
+impl Target {
+    /// `unix:<path>` or `file:<path>`; a bare path means a file.
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let target = match raw.split_once(':') {
+            Some(("unix", path)) => Target::Unix(PathBuf::from(path)),
+            Some(("file", path)) => Target::File(PathBuf::from(path)),
+            _ => Target::File(PathBuf::from(raw)),
+        };
+        let (Target::File(path) | Target::Unix(path)) = &target;
+        if path.as_os_str().is_empty() {
+            return Err(format!("--live-metrics: missing path in {raw:?}"));
+        }
+        Ok(target)
+    }
+
+    fn open(&self) -> io::Result<Box<dyn Write + Send>> {
+        match self {
+            Target::File(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Ok(Box::new(file))
+            }
+            #[cfg(unix)]
+            Target::Unix(path) => Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?)),
+            #[cfg(not(unix))]
+            Target::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unix sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+/// The open transport, shared by every benchmark of a run.
+pub(crate) struct LiveMetrics {
+    interval: Duration,
+    /// `None` once a write failed; the run continues without live metrics.
+    sink: Mutex<Option<Box<dyn Write + Send>>>,
+}
+
+impl LiveMetrics {
+    pub(crate) fn open(target: &Target, interval: Duration) -> io::Result<Self> {
+        Ok(LiveMetrics {
+            interval,
+            sink: Mutex::new(Some(target.open()?)),
+        })
+    }
+
+    fn publish(&self, sample: &Value) -> bool {
+        let Ok(mut sink) = self.sink.lock() else {
+            return false;
+        };
+        let Some(writer) = sink.as_mut() else {
+            return false;
+        };
+        let line = format!("{sample}\n");
+        match writer.write_all(line.as_bytes()).and_then(|()| writer.flush()) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("warning: live metrics disabled: {err}");
+                *sink = None;
+                false
+            }
+        }
+    }
+}
+
+struct Pending {
+    values: Map<String, Value>,
+    last_emit: Instant,
+    emitted: usize,
+    recent: VecDeque<Value>,
+}
+
+/// Handed to one benchmark attempt. Safe to share with worker threads.
+pub(crate) struct LiveContext<'a> {
+    live: Option<&'a LiveMetrics>,
+    run_id: &'a str,
+    benchmark_id: &'a str,
+    /// The run seed that `rng_for` derives worker streams from.
+    seed: u64,
+    start: Instant,
+    pending: Mutex<Pending>,
+    warnings: Warnings,
+    diagnostics: Mutex<Map<String, Value>>,
+    resolved: Mutex<Map<String, Value>>,
+}
+
+impl<'a> LiveContext<'a> {
+    pub(crate) fn new(live: Option<&'a LiveMetrics>, run_id: &'a str, benchmark_id: &'a str) -> Self {
+        let start = Instant::now();
+        LiveContext {
+            live,
+            run_id,
+            benchmark_id,
+            seed: DEFAULT_SEED,
+            start,
+            pending: Mutex::new(Pending {
+                values: Map::new(),
+                last_emit: start,
+                emitted: 0,
+                recent: VecDeque::new(),
+            }),
+            warnings: Warnings::default(),
+            diagnostics: Mutex::new(Map::new()),
+            resolved: Mutex::new(Map::new()),
+        }
+    }
+
+    /// Initial RNG state for worker `worker` of this attempt. Depends only
+    /// on the run seed and the index, so each worker draws the same values
+    /// in every run whatever the thread scheduling.
+    pub(crate) fn rng_for(&self, worker: usize) -> u64 {
+        rng::stream(self.seed, worker as u64)
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.live.is_some()
+    }
+
+    /// Seconds since the attempt started, for rate metrics.
+    pub(crate) fn elapsed_s(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// Records the latest `value` for `key`. At most one sample per interval
+    /// is published, carrying every key reported so far; a no-op without
+    /// `--live-metrics`.
+    pub(crate) fn progress(&self, key: &str, value: f64) {
+        if self.live.is_some() {
+            self.progress_at(key, value, Instant::now());
+        }
+    }
+
+    fn progress_at(&self, key: &str, value: f64, now: Instant) {
+        let Some(live) = self.live else {
+            return;
+        };
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        pending.values.insert(key.to_string(), json!(value));
+        if now.saturating_duration_since(pending.last_emit) < live.interval {
+            return;
+        }
+        pending.last_emit = now;
+        let sample = json!({
+            "run_id": self.run_id,
+            "benchmark_id": self.benchmark_id,
+            "elapsed_s": now.saturating_duration_since(self.start).as_secs_f64(),
+            "progress": pending.values,
+        });
+        if live.publish(&sample) {
+            pending.emitted += 1;
+            if pending.recent.len() == RECENT_SAMPLES {
+                pending.recent.pop_front();
+            }
+            pending.recent.push_back(sample);
+        }
+    }
+
+    /// Adds a warning to this attempt's record.
+    pub(crate) fn warn(&self, code: WarningCode, message: impl Into<String>, details: Value) {
+        self.warnings.warn(Warning::new(code, message, details));
+    }
+
+    /// The record's `warnings` array; `None` when nothing was raised.
+    pub(crate) fn warnings(&self) -> Option<Value> {
+        self.warnings.to_value()
+    }
+
+    /// Sets `key` in this attempt's `diagnostics` block: detail that
+    /// explains the metrics without being one. A later repeat's value
+    /// replaces an earlier one.
+    pub(crate) fn diagnose(&self, key: &str, value: Value) {
+        if let Ok(mut diagnostics) = self.diagnostics.lock() {
+            diagnostics.insert(key.to_string(), value);
+        }
+    }
+
+    /// The record's `diagnostics` block; `None` when nothing was set.
+    pub(crate) fn diagnostics(&self) -> Option<Value> {
+        let diagnostics = self.diagnostics.lock().ok()?;
+        (!diagnostics.is_empty()).then(|| Value::Object(diagnostics.clone()))
+    }
+
+    /// Replaces the configured `key` in this attempt's `parameters` with
+    /// what the benchmark actually used, such as the embedded server's URL.
+    pub(crate) fn resolve_parameter(&self, key: &str, value: Value) {
+        if let Ok(mut resolved) = self.resolved.lock() {
+            resolved.insert(key.to_string(), value);
+        }
+    }
+
+    /// Parameters set with `resolve_parameter`.
+    pub(crate) fn resolved_parameters(&self) -> Map<String, Value> {
+        self.resolved.lock().map(|resolved| resolved.clone()).unwrap_or_default()
+    }
+
+    pub(crate) fn samples_emitted(&self) -> usize {
+        self.pending.lock().map(|pending| pending.emitted).unwrap_or(0)
+    }
+
+    /// The last `RECENT_SAMPLES` samples published, oldest first; empty
+    /// without `--live-metrics`.
+    pub(crate) fn recent_samples(&self) -> Vec<Value> {
+        self.pending
+            .lock()
+            .map(|pending| pending.recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("live-metrics-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            Target::parse("unix:/tmp/bench.sock"),
+            Ok(Target::Unix(PathBuf::from("/tmp/bench.sock")))
+        );
+        assert_eq!(
+            Target::parse("file:results/live.jsonl"),
+            Ok(Target::File(PathBuf::from("results/live.jsonl")))
+        );
+        assert_eq!(
+            Target::parse("results/live.jsonl"),
+            Ok(Target::File(PathBuf::from("results/live.jsonl")))
+        );
+        assert!(Target::parse("unix:").is_err());
+    }
+
+    #[test]
+    fn throttles_to_one_sample_per_interval() {
+        let path = temp_path("throttle");
+        let _ = fs::remove_file(&path);
+        let live = LiveMetrics::open(&Target::File(path.clone()), Duration::from_secs(1)).unwrap();
+        let ctx = LiveContext::new(Some(&live), "run-1", "data_pipeline_etl_minibatch");
+        for (ms, rows) in [(100, 1.0), (600, 2.0), (1_100, 3.0), (1_500, 4.0), (2_200, 5.0)] {
+            ctx.progress_at("rows_processed", rows, ctx.start + Duration::from_millis(ms));
+        }
+        ctx.progress_at("rows_per_second", 9.0, ctx.start + Duration::from_millis(2_300));
+        assert_eq!(ctx.samples_emitted(), 2);
+
+        let text = fs::read_to_string(&path).unwrap();
+        let samples: Vec<Value> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0]["benchmark_id"], "data_pipeline_etl_minibatch");
+        assert_eq!(samples[0]["run_id"], "run-1");
+        assert_eq!(samples[0]["elapsed_s"], 1.1);
+        assert_eq!(samples[0]["progress"], json!({"rows_processed": 3.0}));
+        assert_eq!(samples[1]["progress"], json!({"rows_processed": 5.0}));
+        assert_eq!(ctx.recent_samples(), samples);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn disabled_context_emits_nothing() {
+        let ctx = LiveContext::new(None, "run-1", "cpu_monte_carlo_pi");
+        ctx.progress_at("samples", 1.0, ctx.start + Duration::from_secs(5));
+        assert!(!ctx.enabled());
+        assert_eq!(ctx.samples_emitted(), 0);
+        assert!(ctx.recent_samples().is_empty());
+    }
+
+    #[test]
+    fn diagnostics_keep_the_latest_value_per_key() {
+        let ctx = LiveContext::new(None, "run-1", "io_concurrent_http_client");
+        assert_eq!(ctx.diagnostics(), None);
+        ctx.diagnose("timeline_errors", json!([1, 0]));
+        ctx.diagnose("error_samples", json!({"read": "reset"}));
+        ctx.diagnose("timeline_errors", json!([0, 0]));
+        let expected = json!({"error_samples": {"read": "reset"}, "timeline_errors": [0, 0]});
+        assert_eq!(ctx.diagnostics(), Some(expected));
+    }
+
+    #[test]
+    fn worker_streams_depend_only_on_seed_and_index() {
+        let first = LiveContext::new(None, "run-1", "io_concurrent_http_client");
+        let second = LiveContext::new(None, "run-2", "io_concurrent_http_client");
+        for worker in 0..8 {
+            assert_eq!(first.rng_for(worker), second.rng_for(worker));
+            assert_eq!(first.rng_for(worker), rng::stream(DEFAULT_SEED, worker as u64));
+        }
+        assert_ne!(first.rng_for(0), first.rng_for(1));
+    }
+
+    #[test]
+    fn file_target_appends() {
+        let path = temp_path("append");
+        fs::write(&path, "earlier\n").unwrap();
+        let live = LiveMetrics::open(&Target::File(path.clone()), Duration::ZERO).unwrap();
+        let ctx = LiveContext::new(Some(&live), "run-2", "io_concurrent_http_client");
+        ctx.progress("requests_completed", 10.0);
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("earlier\n"));
+        assert_eq!(text.lines().count(), 2);
+        let _ = fs::remove_file(path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_target_streams_lines() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixListener;
+
+        let path = temp_path("socket");
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let live = LiveMetrics::open(&Target::Unix(path.clone()), Duration::ZERO).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let ctx = LiveContext::new(Some(&live), "run-3", "io_concurrent_http_client");
+        ctx.progress("requests_completed", 4.0);
+        ctx.progress("requests_completed", 8.0);
+        let lines: Vec<Value> = BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(lines[1]["progress"]["requests_completed"], 8.0);
+        assert_eq!(ctx.samples_emitted(), 2);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn failed_writes_disable_the_sink() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let live = LiveMetrics {
+            interval: Duration::ZERO,
+            sink: Mutex::new(Some(Box::new(Broken))),
+        };
+        let ctx = LiveContext::new(Some(&live), "run-4", "io_concurrent_http_client");
+        ctx.progress("requests_completed", 1.0);
+        ctx.progress("requests_completed", 2.0);
+        assert_eq!(ctx.samples_emitted(), 0);
+        assert!(live.sink.lock().unwrap().is_none());
+    }
+}
@@ -1,18 +1,147 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
 use chrono::Utc;
+use flate2::bufread;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
-use std::cmp::min;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Read, Write};
-use std::net::TcpStream;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::TcpListener;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
+
+mod advisory_db;
+mod anonymize;
+mod append;
+mod async_tasks;
+mod benchmark_ids;
+mod bisect;
+mod bootstrap;
+mod build_stamp;
+mod builtin_scan;
+mod calibration;
+mod child_usage;
+mod chart_data;
+mod checksum_store;
+mod chunked;
+mod clippy;
+mod clock;
+mod command_runner;
+mod config;
+mod cpus;
+mod data_dir;
+mod dataset;
+mod dataset_cache;
+mod deprecations;
+mod etl_async;
+mod failure;
+mod fingerprint;
+#[cfg(test)]
+mod fixtures;
+mod fixture_probe;
+mod http_client;
+mod interactive;
+mod layout;
+mod line_reader;
+mod linkage;
+mod live_metrics;
+mod matrix;
+mod mmap;
+mod mock_server;
+mod phase_timer;
+mod phases;
+mod platform;
+mod precheck;
+mod preconditions;
+mod print_metrics;
+mod procfs;
+mod record;
+mod repeats;
+mod repro;
+mod retry;
+mod rng;
+mod sampling;
+mod sinks;
+mod stages;
+mod stats;
+mod streaming;
+mod subprocess;
+mod test_timing;
+mod thresholds;
+mod timeline;
+mod tls;
+mod tool_exit;
+mod upload;
+mod viewer;
+mod warnings;
+
+use anonymize::Anonymizer;
+use append::AppendPolicy;
+use async_tasks::AsyncTasksRun;
+use benchmark_ids::{
+    BUILD_STARTUP_FEEDBACK_LOOP, CONCURRENCY_ASYNC_TASKS, CPU_MONTE_CARLO_PI,
+    CPU_MONTE_CARLO_PI_PARALLEL, CPU_SLEEP_PRECISION, DATA_PIPELINE_ETL_ASYNC,
+    DATA_PIPELINE_ETL_MINIBATCH, DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    HARNESS_OVERHEAD, IO_CONCURRENT_HTTP_CLIENT, IO_CONCURRENT_HTTP_POST,
+    IO_HTTP_CONNECTION_CHURN, IO_HTTP_JITTER_SENSITIVITY, IO_HTTP_STREAMING,
+    STATIC_SECURITY_LINT_BENCHMARK, STRING_JSON_PARSE_TRANSFORM, TEST_ROBUSTNESS_RELIABILITY,
+};
+use bisect::{BisectArgs, Bound};
+use checksum_store::{ChecksumStore, Observation};
+use child_usage::{ChildCounters, ChildUsage};
+use clippy::{parse_clippy_messages, LintReport, Workspace};
+use command_runner::{CommandRunner, SystemRunner};
+use config::{
+    AsyncTasksConfig, BenchmarkConfigs, ConfigFile, DependencyScanConfig, EtlAsyncConfig, EtlConfig,
+    HttpClientConfig, HttpServer, HttpStreamingConfig, InputMode, JitterSensitivityConfig,
+    JsonTransformConfig, MonteCarloConfig, SleepPrecisionConfig, StaticLintConfig,
+};
+use data_dir::{DataDir, DiskUsage};
+use dataset_cache::DatasetCache;
+use deprecations::{Notice, DEPRECATIONS};
+use etl_async::{ReadAhead, ReadQueue};
+use failure::{FailureContext, PanicReport};
+use fingerprint::MismatchPolicy;
+use fixture_probe::{FixtureProbe, RTT_CONNECTS};
+use http_client::{
+    parse_base_url, run_workers, run_workers_with, wait_until_ready, ConnectionMode, Exchange,
+    HttpConnection, ItemRequest, SendItem, TransportError, WorkerHooks, WorkerStats,
+};
+use layout::{ExecutionContext, Skipped};
+use line_reader::Line;
+use live_metrics::{LiveContext, LiveMetrics};
+use print_metrics::MetricPath;
+use matrix::{BestCell, Cell, Matrix};
+use mmap::Mapping;
+use mock_server::{ChaosConfig, MockServer};
+use phase_timer::{Phase, PhaseTimer, SystemClock};
+use phases::{PhaseLog, PhaseStatus};
+use preconditions::{Precondition, Preconditions, Probe};
+use repeats::{Repeated, Repeats};
+use repro::Repro;
+use retry::RetryPolicy;
+use rng::next_f64;
+use sinks::{Fanout, SinkSpec, SinkStatus};
+use stats::{insert_sampled, Rank, Samples, Trim};
+use procfs::SwapCounters;
+use record::{BenchmarkRecord, Environment, Language, Metric, Metrics, Variant};
+use subprocess::{parse_count, CaptureStats, NumberError};
+use stages::{Stage, StageClock};
+use test_timing::{parse_test_output, TimingSummary};
+use thresholds::Evaluation;
+use timeline::Timeline;
+use tool_exit::{ExitClass, ExitTally, Tool};
+use upload::{HttpCollector, UploadConfig, UploadCounts, UploadQueue};
+use warnings::{Warning, WarningCode};
 
 #[derive(Deserialize, Serialize)]
 struct JsonRow {
@@ -27,8 +156,10 @@ struct EtlRow {
     value: u64,
 }
 
+/// The repo root found at or above the working directory, or the working
+/// directory when there is none.
 fn repo_root() -> PathBuf {
-    env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+    layout::current().root.clone()
 }
 
 fn env_usize(name: &str, default: usize) -> usize {
@@ -38,20 +169,17 @@ fn env_usize(name: &str, default: usize) -> usize {
         .unwrap_or(default)
 }
 
-fn metric(value: f64, unit: &str) -> Value {
-    json!({"value": value, "unit": unit})
-}
+/// Seed shared with the Python runner; part of every checksum store key.
+const DEFAULT_SEED: u64 = 42;
 
-fn next_f64(state: &mut u64) -> f64 {
-    *state ^= *state << 13;
-    *state ^= *state >> 7;
-    *state ^= *state << 17;
-    (*state as f64) / (u64::MAX as f64)
+fn monte_carlo_pi(samples: usize) -> f64 {
+    4.0 * monte_carlo_inside(DEFAULT_SEED, samples) as f64 / samples as f64
 }
 
-fn monte_carlo_pi(samples: usize) -> f64 {
+/// Points inside the unit circle among `samples` (x, y) pairs drawn from
+/// the `next_f64` stream starting at `state`.
+fn monte_carlo_inside(mut state: u64, samples: usize) -> usize {
     let mut inside = 0usize;
-    let mut state: u64 = 42;
     for _ in 0..samples {
         let x = next_f64(&mut state);
         let y = next_f64(&mut state);
@@ -59,9 +187,160 @@ fn monte_carlo_pi(samples: usize) -> f64 {
             inside += 1;
         }
     }
+    inside
+}
+
+/// Each of `streams` streams' share of `samples`: an even split, with the
+/// remainder going one each to the first streams.
+fn stream_shares(samples: usize, streams: usize) -> Vec<usize> {
+    let streams = streams.max(1);
+    (0..streams)
+        .map(|stream| samples / streams + usize::from(stream < samples % streams))
+        .collect()
+}
+
+/// `monte_carlo_pi` split over `streams` streams of the default seed
+/// (`rng::stream`), each on its own thread when `parallel` and one after
+/// another otherwise. Every stream draws a fixed share, so both give the
+/// same estimate however the threads are scheduled.
+fn monte_carlo_pi_streams(samples: usize, streams: usize, parallel: bool) -> f64 {
+    let shares = stream_shares(samples, streams);
+    let inside_of = |stream: usize| {
+        monte_carlo_inside(rng::stream(DEFAULT_SEED, stream as u64), shares[stream])
+    };
+    let inside: usize = if parallel {
+        thread::scope(|scope| {
+            let inside_of = &inside_of;
+            let handles: Vec<_> = (0..shares.len())
+                .map(|stream| scope.spawn(move || inside_of(stream)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload)))
+                .sum()
+        })
+    } else {
+        (0..shares.len()).map(inside_of).sum()
+    };
+    4.0 * inside as f64 / samples as f64
+}
+
+const MONTE_CARLO_LANES: usize = 4;
+
+/// Points inside the unit circle among four (x, y) pairs; `std::simd` with
+/// the nightly `simd` feature, a fixed-width loop the compiler can vectorize
+/// otherwise.
+#[cfg(not(feature = "simd"))]
+fn lanes_inside(xs: &[f64; MONTE_CARLO_LANES], ys: &[f64; MONTE_CARLO_LANES]) -> usize {
+    let mut inside = 0;
+    for lane in 0..MONTE_CARLO_LANES {
+        inside += (xs[lane] * xs[lane] + ys[lane] * ys[lane] <= 1.0) as usize;
+    }
+    inside
+}
+
+#[cfg(feature = "simd")]
+fn lanes_inside(xs: &[f64; MONTE_CARLO_LANES], ys: &[f64; MONTE_CARLO_LANES]) -> usize {
+    use std::simd::cmp::SimdPartialOrd;
+    use std::simd::f64x4;
+
+    let (x, y) = (f64x4::from_array(*xs), f64x4::from_array(*ys));
+    (x * x + y * y)
+        .simd_le(f64x4::splat(1.0))
+        .to_bitmask()
+        .count_ones() as usize
+}
+
+/// Same estimate as `monte_carlo_pi`, bit for bit: the RNG is still drawn
+/// serially as x0, y0, x1, y1, ... and only the inside test runs per lane.
+fn monte_carlo_pi_vectorized(samples: usize) -> f64 {
+    let mut inside = 0usize;
+    let mut state: u64 = DEFAULT_SEED;
+    let mut xs = [0.0; MONTE_CARLO_LANES];
+    let mut ys = [0.0; MONTE_CARLO_LANES];
+    for _ in 0..samples / MONTE_CARLO_LANES {
+        for lane in 0..MONTE_CARLO_LANES {
+            xs[lane] = next_f64(&mut state);
+            ys[lane] = next_f64(&mut state);
+        }
+        inside += lanes_inside(&xs, &ys);
+    }
+    for _ in 0..samples % MONTE_CARLO_LANES {
+        let x = next_f64(&mut state);
+        let y = next_f64(&mut state);
+        if x * x + y * y <= 1.0 {
+            inside += 1;
+        }
+    }
     4.0 * inside as f64 / samples as f64
 }
 
+/// Overshoot (actual minus requested) in microseconds for each measured
+/// sleep; the first `warmup` samples are taken but discarded.
+fn sleep_overshoots_us(duration_ms: u64, samples: usize, warmup: usize) -> Samples {
+    let requested = Duration::from_millis(duration_ms);
+    let mut overshoots = Samples::new();
+    for idx in 0..warmup + samples {
+        let start = Instant::now();
+        thread::sleep(requested);
+        let actual = start.elapsed();
+        if idx >= warmup {
+            overshoots.push(actual.saturating_sub(requested).as_secs_f64() * 1e6);
+        }
+    }
+    overshoots
+}
+
+fn sleep_precision_metrics(config: &SleepPrecisionConfig, checks: &mut Preconditions) -> Metrics {
+    let start = Instant::now();
+    let mut map = Metrics::new();
+    for &duration_ms in &config.durations_ms {
+        let overshoots = sleep_overshoots_us(duration_ms, config.samples, config.warmup);
+        let summaries = [
+            ("mean", overshoots.mean()),
+            ("stddev", overshoots.stddev()),
+            ("min", overshoots.min()),
+            ("median", overshoots.percentile(50.0, Rank::Linear)),
+            ("p95", overshoots.percentile(95.0, Rank::Nearest)),
+            ("max", overshoots.max()),
+        ];
+        for (summary, value) in summaries {
+            let key = format!("sleep_{duration_ms}ms_{summary}_overshoot_us");
+            insert_sampled(&mut map, &key, value, "us", &overshoots);
+        }
+    }
+    map.insert("runtime_seconds", start.elapsed().as_secs_f64(), "s");
+    map.insert("samples_per_duration", config.samples as f64, "count");
+    map.insert("warmup_discarded", config.warmup as f64, "count");
+    let resolution = if checks.check(Precondition::Platform(Probe::TimerResolution)) {
+        platform::timer_resolution_ns()
+    } else {
+        None
+    };
+    map.insert(
+        "timer_resolution_supported",
+        resolution.is_some() as u8 as f64,
+        "flag",
+    );
+    if let Some(ns) = resolution {
+        map.insert("timer_resolution_ns", ns, "ns");
+    }
+    let policy = if checks.check(Precondition::Platform(Probe::SchedulerPolicy)) {
+        platform::scheduler_policy()
+    } else {
+        None
+    };
+    map.insert(
+        "scheduler_policy_supported",
+        policy.is_some() as u8 as f64,
+        "flag",
+    );
+    if let Some(policy) = policy {
+        map.insert("scheduler_policy", policy as f64, "code");
+    }
+    map
+}
+
 fn json_parse_transform(records: usize) -> u64 {
     let payload: Vec<JsonRow> = (0..records)
         .map(|i| JsonRow {
@@ -98,120 +377,370 @@ fn rust_runtime_version() -> String {
         .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string())
 }
 
-fn make_record(benchmark_id: &str, category: &str, metrics: Map<String, Value>, run_id: &str) -> Value {
-    json!({
-        "benchmark_id": benchmark_id,
-        "category": category,
-        "language": "rust",
-        "variant": {
-            "runtime": "rust",
-            "version": rust_runtime_version(),
-        },
-        "environment": {
-            "os": env::consts::OS,
-            "cpu_count": thread::available_parallelism().map(|x| x.get()).unwrap_or(1),
+struct RunContext {
+    run_id: String,
+    environment: Environment,
+    /// `fingerprint::harness_fingerprint()`, computed once per run.
+    harness_fingerprint: String,
+    /// Root for every file the run writes.
+    data_dir: DataDir,
+    live: Option<LiveMetrics>,
+    /// Set by `--preset auto`; attached to every record.
+    calibration: Option<calibration::Applied>,
+    /// How often the repeated benchmarks run; `BENCHMARK_REPEATS`.
+    repeats: Repeats,
+    /// `--namespace`; attached to every record.
+    namespace: Option<String>,
+    /// The system clock read earlier than the build timestamp at startup.
+    clock_suspect: bool,
+    /// `--replay` settings taken from this machine; each warns on the
+    /// records of its benchmark.
+    replay_overrides: Vec<repro::Override>,
+}
+
+fn environment_block(mem_available_kb: Option<u64>, bootstrapped: bool) -> Environment {
+    let cpus = cpus::cpu_count();
+    let mut extra = Map::new();
+    extra.insert("bootstrapped".to_string(), json!(bootstrapped));
+    extra.insert("locale".to_string(), json!(subprocess::parent_locale()));
+    if let Some(kb) = mem_available_kb {
+        extra.insert("mem_available_mb".to_string(), json!(kb as f64 / 1024.0));
+    }
+    extra.insert("cpu_features".to_string(), json!(platform::cpu_features()));
+    // Effective values, whether or not `--low-priority` asked for them, so
+    // runs niced by the caller are recognizable too.
+    extra.insert("nice".to_string(), json!(platform::nice_value()));
+    extra.insert("scheduler_policy".to_string(), json!(platform::scheduler_policy_name()));
+    extra.insert("priority_class".to_string(), json!(platform::priority_class()));
+    extra.insert("thp_enabled".to_string(), json!(platform::thp_mode()));
+    extra.extend(clock::local_time_zone_fields());
+    Environment {
+        os: env::consts::OS.to_string(),
+        cpu_count: cpus.effective,
+        cpus_detected: cpus.detected,
+        cpu_count_source: cpus.source,
+        extra,
+    }
+}
+
+/// `parameters` are the inputs the metrics were measured with; empty for
+/// benchmarks that take none, never omitted.
+fn make_record(
+    benchmark_id: &str,
+    category: &str,
+    metrics: Metrics,
+    parameters: Map<String, Value>,
+    ctx: &RunContext,
+) -> BenchmarkRecord {
+    BenchmarkRecord {
+        namespace: ctx.namespace.clone(),
+        benchmark_id: benchmark_id.to_string(),
+        category: category.to_string(),
+        language: Language::Rust,
+        variant: Variant {
+            runtime: "rust".to_string(),
+            version: rust_runtime_version(),
         },
-        "metrics": metrics,
-        "timestamp": Utc::now().to_rfc3339(),
-        "commit_sha": git_sha(),
-        "run_id": run_id,
-    })
+        environment: ctx.environment.clone(),
+        metrics,
+        timestamp: clock::now(),
+        commit_sha: git_sha(),
+        harness_fingerprint: Some(ctx.harness_fingerprint.clone()),
+        run_id: ctx.run_id.clone(),
+        attempt: None,
+        selected_for_comparison: None,
+        preconditions: None,
+        target: None,
+        dataset: None,
+        calibration: None,
+        matrix_cell: None,
+        phases: None,
+        failure: None,
+        diagnostics: None,
+        warnings: None,
+        trim: None,
+        samples: None,
+        parameters,
+    }
 }
 
-fn parse_base_url(base_url: &str) -> Option<(String, u16)> {
-    let trimmed = base_url.strip_prefix("http://")?;
-    let host_port = trimmed.split('/').next()?;
-    if let Some((host, port)) = host_port.rsplit_once(':') {
-        return Some((host.to_string(), port.parse::<u16>().ok()?));
+fn io_http_benchmark(
+    base_url: &str,
+    requests: usize,
+    rows: usize,
+    concurrency: usize,
+    mode: ConnectionMode,
+    hooks: &WorkerHooks,
+) -> WorkerStats {
+    let Ok(target) = parse_base_url(base_url) else {
+        return WorkerStats {
+            errors: 1,
+            ..WorkerStats::default()
+        };
+    };
+    run_workers_with(&target, requests, rows, concurrency, mode, hooks)
+}
+
+/// `io_concurrent_http_post`'s request: creates the item's row, built as
+/// `string_json_parse_transform` builds it, with `POST /items`.
+fn post_json_row(
+    connection: &mut HttpConnection,
+    item: &ItemRequest,
+) -> Result<Exchange, TransportError> {
+    let row = JsonRow {
+        id: item.item_id,
+        value: (item.item_id % 17) as u64,
+        name: format!("row-{}", item.item_id),
+    };
+    let body = serde_json::to_string(&row).unwrap_or_default();
+    connection.post_item(item, &body, row.value)
+}
+
+/// Goodput counts response bodies only, so header overhead shows up as the
+/// gap between `bytes_received` and body bytes.
+fn insert_transfer_metrics(map: &mut Metrics, stats: &WorkerStats, elapsed: f64) {
+    map.insert("bytes_sent", stats.bytes_sent as f64, "bytes");
+    map.insert("bytes_received", stats.bytes_received as f64, "bytes");
+    map.insert(
+        "goodput_mb_s",
+        stats.body_bytes_received as f64 / (1024.0 * 1024.0) / elapsed.max(1e-9),
+        "mb/s",
+    );
+}
+
+/// Tail latency of successful requests in milliseconds: p50/p95/p99 by
+/// nearest rank, and the maximum, over every request's latency. With no
+/// successful request every value is `-1`.
+fn insert_latency_metrics(map: &mut Metrics, stats: &mut WorkerStats) {
+    let latencies = &mut stats.latencies_us;
+    let pcts = [50, 95, 99];
+    let values = latencies.percentiles(&pcts.map(f64::from));
+    for (pct, us) in pcts.into_iter().zip(values) {
+        let ms = if latencies.is_empty() {
+            -1.0
+        } else {
+            us / 1000.0
+        };
+        insert_sampled(map, &format!("latency_p{pct}_ms"), ms, "ms", latencies);
     }
-    Some((host_port.to_string(), 80))
+    let max = if latencies.is_empty() { -1.0 } else { latencies.max() / 1000.0 };
+    map.insert("latency_max_ms", max, "ms");
 }
 
-fn http_get_value(host: &str, port: u16, item_id: usize) -> Result<u64, String> {
-    let mut stream = TcpStream::connect((host, port)).map_err(|e| e.to_string())?;
-    let request = format!(
-        "GET /item/{item_id} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+fn insert_churn_mode_metrics(map: &mut Metrics, prefix: &str, stats: &WorkerStats, elapsed: f64) {
+    map.insert(format!("{prefix}_seconds"), elapsed, "s");
+    map.insert(
+        format!("{prefix}_rps"),
+        stats.completed as f64 / elapsed.max(1e-9),
+        "req/s",
+    );
+    map.insert(
+        format!("{prefix}_connections_opened"),
+        stats.connections_opened as f64,
+        "count",
     );
-    stream
-        .write_all(request.as_bytes())
-        .map_err(|e| e.to_string())?;
+    // Every close leaves a TIME_WAIT socket on whichever side closed first.
+    map.insert(
+        format!("{prefix}_time_wait_closes"),
+        stats.connections_closed as f64,
+        "count",
+    );
+    map.insert(
+        format!("{prefix}_request_errors"),
+        stats.errors as f64,
+        "count",
+    );
+}
 
-    let mut raw = Vec::new();
-    stream.read_to_end(&mut raw).map_err(|e| e.to_string())?;
-    let body_offset = raw
-        .windows(4)
-        .position(|window| window == b"\r\n\r\n")
-        .map(|idx| idx + 4)
-        .ok_or_else(|| "response body missing".to_string())?;
-    let body = &raw[body_offset..];
-    let payload: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
-    payload["value"]
-        .as_u64()
-        .ok_or_else(|| "missing value".to_string())
+/// Where a benchmark sends its requests: `base_url`, or for an embedded
+/// server one started here, which shuts down when the returned server
+/// drops and replaces `base_url` in the record's parameters. A server that
+/// fails to start leaves an empty URL, which fails the base URL check.
+fn http_base_url(
+    base_url: &str,
+    server: HttpServer,
+    live: &LiveContext,
+) -> (String, Option<MockServer>) {
+    if server == HttpServer::External {
+        return (base_url.to_string(), None);
+    }
+    match MockServer::start(ChaosConfig::default(), DEFAULT_SEED) {
+        Ok(server) => {
+            live.resolve_parameter("base_url", json!(server.base_url()));
+            (server.base_url(), Some(server))
+        }
+        Err(err) => {
+            eprintln!("warning: embedded server failed to start: {err}");
+            (String::new(), None)
+        }
+    }
 }
 
-fn io_http_benchmark(base_url: &str, requests: usize, rows: usize, concurrency: usize) -> (usize, u64, usize) {
-    let Some((host, port)) = parse_base_url(base_url) else {
-        return (0, 0, 1);
+fn http_connection_churn_metrics(
+    config: &HttpClientConfig,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+) -> Metrics {
+    let total_start = Instant::now();
+    let mut map = Metrics::new();
+    let (requests, rows, concurrency) = (config.requests, config.rows, config.concurrency);
+    let (base_url, _server) = http_base_url(&config.base_url, config.server, live);
+    let target = if checks.check(Precondition::BaseUrl(&base_url)) {
+        parse_base_url(&base_url).ok()
+    } else {
+        None
+    };
+    let Some(target) = target else {
+        map.insert("runtime_seconds", 0.0, "s");
+        map.insert("request_errors", 1.0, "count");
+        return map;
     };
+    let fixture = fixture_probe::probe(&target, RTT_CONNECTS, config.request_limits().timeout);
+    insert_fixture_metrics(&mut map, live, fixture.as_ref());
 
-    let workers = concurrency.max(1);
-    let chunk = (requests + workers - 1) / workers;
-    let mut handles = Vec::new();
+    let start = Instant::now();
+    let fresh = run_workers(&target, requests, rows, concurrency, ConnectionMode::PerRequest);
+    let fresh_elapsed = start.elapsed().as_secs_f64();
 
-    for worker in 0..workers {
-        let start = worker * chunk;
-        let end = min(start + chunk, requests);
-        if start >= end {
-            continue;
+    let start = Instant::now();
+    let reused = run_workers(&target, requests, rows, concurrency, ConnectionMode::Persistent);
+    let reused_elapsed = start.elapsed().as_secs_f64();
+
+    let fresh_rps = fresh.completed as f64 / fresh_elapsed.max(1e-9);
+    let reused_rps = reused.completed as f64 / reused_elapsed.max(1e-9);
+    map.insert("runtime_seconds", total_start.elapsed().as_secs_f64(), "s");
+    insert_churn_mode_metrics(&mut map, "per_request", &fresh, fresh_elapsed);
+    insert_churn_mode_metrics(&mut map, "persistent", &reused, reused_elapsed);
+    map.insert(
+        "connection_reuse_speedup",
+        reused_rps / fresh_rps.max(1e-9),
+        "ratio",
+    );
+    map.insert(
+        "request_errors",
+        (fresh.errors + reused.errors) as f64,
+        "count",
+    );
+    map.insert(
+        "checksum_mismatch",
+        (fresh.checksum != reused.checksum) as u8 as f64,
+        "flag",
+    );
+    map
+}
+
+/// One client run against a fresh mock server; a server that fails to bind
+/// counts as a single request error.
+fn run_against_chaos(config: &JitterSensitivityConfig, chaos: &ChaosConfig) -> (WorkerStats, f64) {
+    let server = match MockServer::start(chaos.clone(), DEFAULT_SEED) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("warning: mock server failed to start: {err}");
+            let stats = WorkerStats {
+                errors: 1,
+                ..WorkerStats::default()
+            };
+            return (stats, 0.0);
         }
-        let host = host.clone();
-        handles.push(thread::spawn(move || {
-            let mut completed = 0usize;
-            let mut checksum = 0u64;
-            let mut errors = 0usize;
-            for request_id in start..end {
-                match http_get_value(&host, port, request_id % rows.max(1)) {
-                    Ok(value) => {
-                        completed += 1;
-                        checksum += value;
-                    }
-                    Err(_) => {
-                        errors += 1;
-                    }
-                }
-            }
-            (completed, checksum, errors)
-        }));
+    };
+    let start = Instant::now();
+    let stats = io_http_benchmark(
+        &server.base_url(),
+        config.requests,
+        config.rows,
+        config.concurrency,
+        ConnectionMode::PerRequest,
+        &WorkerHooks::default(),
+    );
+    (stats, start.elapsed().as_secs_f64())
+}
+
+/// Degradation is low/high throughput and inflation is high/low p99, so both
+/// read as "how many times worse" under the jittery server.
+fn jitter_sensitivity_metrics(config: &JitterSensitivityConfig) -> Metrics {
+    let total_start = Instant::now();
+    let (mut low, low_elapsed) = run_against_chaos(config, &config.low_jitter);
+    let (mut high, high_elapsed) = run_against_chaos(config, &config.high_jitter);
+
+    let mut map = Metrics::new();
+    let mut rates = Vec::new();
+    let phases = [
+        ("low_jitter", &mut low, low_elapsed),
+        ("high_jitter", &mut high, high_elapsed),
+    ];
+    for (prefix, stats, elapsed) in phases {
+        let rps = stats.completed as f64 / elapsed.max(1e-9);
+        let p99 = stats.latencies_us.percentiles(&[99.0])[0];
+        map.insert(format!("{prefix}_rps"), rps, "req/s");
+        let key = format!("{prefix}_p99_latency_us");
+        insert_sampled(&mut map, &key, p99, "us", &stats.latencies_us);
+        map.insert(
+            format!("{prefix}_request_errors"),
+            stats.errors as f64,
+            "count",
+        );
+        rates.push((rps, p99));
     }
+    map.insert("runtime_seconds", total_start.elapsed().as_secs_f64(), "s");
+    map.insert(
+        "throughput_degradation_ratio",
+        rates[0].0 / rates[1].0.max(1e-9),
+        "ratio",
+    );
+    map.insert(
+        "p99_inflation_ratio",
+        rates[1].1 / rates[0].1.max(1e-9),
+        "ratio",
+    );
+    map.insert("request_errors", (low.errors + high.errors) as f64, "count");
+    map
+}
 
-    let mut completed = 0usize;
-    let mut checksum = 0u64;
-    let mut errors = 0usize;
-    for handle in handles {
-        if let Ok((c, s, e)) = handle.join() {
-            completed += c;
-            checksum += s;
-            errors += e;
-        } else {
-            errors += 1;
+/// `BufReader`'s default capacity.
+const DEFAULT_READ_BUFFER_KB: usize = 8;
+/// flate2's internal stream buffer, in both directions.
+const DEFAULT_GZIP_BUFFER_KB: usize = 32;
+/// Buffer sizes the ETL sweep times, in KiB.
+const BUFFER_SWEEP_KB: [usize; 4] = [8, 64, 256, 1024];
+
+/// Buffer sizes around the ETL gzip stream: `read_kb` for the decompressed
+/// lines, `gzip_kb` for the compressed bytes on either side of flate2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct IoBuffers {
+    read_kb: usize,
+    gzip_kb: usize,
+}
+
+impl Default for IoBuffers {
+    fn default() -> Self {
+        IoBuffers {
+            read_kb: DEFAULT_READ_BUFFER_KB,
+            gzip_kb: DEFAULT_GZIP_BUFFER_KB,
         }
     }
-    (completed, checksum, errors)
 }
 
-fn resolve_dataset() -> PathBuf {
-    let raw = env::var("BENCHMARK_ETL_DATASET")
-        .unwrap_or_else(|_| "benchmarks/shared/datasets/etl_input.jsonl.gz".to_string());
-    let path = PathBuf::from(raw);
-    if path.is_absolute() {
-        path
-    } else {
-        repo_root().join(path)
+impl IoBuffers {
+    /// One configured size for both buffers, or the library defaults.
+    fn from_kb(kb: Option<usize>) -> Self {
+        match kb {
+            Some(kb) => IoBuffers {
+                read_kb: kb.max(1),
+                gzip_kb: kb.max(1),
+            },
+            None => IoBuffers::default(),
+        }
+    }
+
+    /// Same layering as `BufReader::new(read::GzDecoder::new(file))`, with
+    /// the two buffers sized explicitly.
+    fn gzip_lines(&self, file: File) -> BufReader<bufread::GzDecoder<BufReader<File>>> {
+        let compressed = BufReader::with_capacity(self.gzip_kb * 1024, file);
+        BufReader::with_capacity(self.read_kb * 1024, bufread::GzDecoder::new(compressed))
     }
 }
 
-fn build_etl_dataset(path: &Path, rows: usize) {
+fn build_etl_dataset(path: &Path, rows: usize, buffers: IoBuffers) {
     if path.exists() {
         return;
     }
@@ -221,66 +750,442 @@ fn build_etl_dataset(path: &Path, rows: usize) {
     let Ok(file) = File::create(path) else {
         return;
     };
+    let file = BufWriter::with_capacity(buffers.gzip_kb * 1024, file);
     let mut encoder = GzEncoder::new(file, Compression::default());
     for idx in 0..rows {
-        let record = json!({
-            "id": idx,
-            "group": idx % 50,
-            "value": (idx * 7 + 11) % 10_000,
-            "score": (idx * 13 + 17) % 10_000
+        let _ = writeln!(encoder, "{}", etl_row(idx));
+    }
+    let _ = encoder.finish().and_then(|mut file| file.flush());
+}
+
+/// Row `idx` of the generated ETL dataset.
+fn etl_row(idx: usize) -> Value {
+    json!({
+        "id": idx,
+        "group": idx % 50,
+        "value": (idx * 7 + 11) % 10_000,
+        "score": (idx * 13 + 17) % 10_000
+    })
+}
+
+/// Rows between live progress reports; keeps the check off the per-row path.
+const ETL_PROGRESS_ROWS: usize = 1024;
+
+#[derive(Debug, Default)]
+struct EtlRun {
+    rows: usize,
+    aggregate: u64,
+    size_bytes: u64,
+    /// An unopenable file, a read that failed partway through, or with
+    /// `strict_line_length` an oversized line.
+    io_errors: usize,
+    /// Lines over `max_line_bytes`, skipped unparsed.
+    oversized_lines: usize,
+    /// Read (decompress included), parse, and aggregate shares of the
+    /// sampled rows' time.
+    phase_pct: [f64; 3],
+    phase_sampled_rows: usize,
+    /// Whether this run wrote the dataset, and how long it waited to.
+    generation: dataset::Generation,
+}
+
+/// Generates the dataset at `path` (already canonical) if needed, then
+/// reads it once.
+fn etl_benchmark(
+    path: &Path,
+    config: &EtlConfig,
+    cache: Option<&DatasetCache>,
+    live: &LiveContext,
+) -> EtlRun {
+    let buffers = IoBuffers::from_kb(config.io_buffer_kb);
+    EtlRun {
+        generation: ensure_etl_dataset(path, config, cache),
+        ..etl_read(path, config, buffers, live)
+    }
+}
+
+/// Generates the ETL dataset at `path` if it does not exist, taking it
+/// from the dataset cache when an identical one was generated before.
+fn ensure_etl_dataset(
+    path: &Path,
+    config: &EtlConfig,
+    cache: Option<&DatasetCache>,
+) -> dataset::Generation {
+    let buffers = IoBuffers::from_kb(config.io_buffer_kb);
+    let params = json!({"rows": config.rows});
+    let key = dataset_cache::key("etl_rows", &params, &fingerprint::harness_fingerprint());
+    let mut cache_hit = false;
+    let mut generation = dataset::ensure(path, config.dataset_lock_timeout(), |temp| {
+        cache_hit = dataset_cache::fetch_or_generate(cache, &key, temp, |temp| {
+            build_etl_dataset(temp, config.rows, buffers)
         });
-        let _ = writeln!(encoder, "{record}");
+    });
+    generation.cache_hit = generation.generated && cache_hit;
+    generation
+}
+
+fn insert_dataset_generation_metrics(map: &mut Metrics, generation: dataset::Generation) {
+    map.insert(
+        "dataset_lock_wait_seconds",
+        generation.lock_wait.as_secs_f64(),
+        "s",
+    );
+    map.insert(
+        "dataset_lock_timeouts",
+        generation.lock_timed_out as u8 as f64,
+        "count",
+    );
+    map.insert(
+        "dataset_cache_hit",
+        generation.cache_hit as u8 as f64,
+        "flag",
+    );
+}
+
+/// One pass over an existing dataset.
+fn etl_read(path: &Path, config: &EtlConfig, buffers: IoBuffers, live: &LiveContext) -> EtlRun {
+    let mut run = EtlRun {
+        size_bytes: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        ..EtlRun::default()
+    };
+    let Ok(file) = File::open(path) else {
+        run.io_errors = 1;
+        return run;
+    };
+    etl_consume(buffers.gzip_lines(file), config, run, live)
+}
+
+/// The `buffered` and `mmap` input modes: the dataset, generated first if
+/// needed, decompressed to a scratch file, then one pass over that file.
+/// Returns the run, how long the pass took, and the page faults taken
+/// during it where the OS counts them.
+fn etl_uncompressed(
+    path: &Path,
+    config: &EtlConfig,
+    data_dir: &DataDir,
+    live: &LiveContext,
+) -> (EtlRun, Duration, Option<platform::PageFaults>) {
+    let mut run = EtlRun {
+        generation: ensure_etl_dataset(path, config, data_dir.dataset_cache()),
+        ..EtlRun::default()
+    };
+    let scratch = data_dir
+        .cache_dir()
+        .join(format!("etl-decompressed-{}.jsonl", std::process::id()));
+    match decompress(path, &scratch) {
+        Ok(bytes) => run.size_bytes = bytes,
+        Err(err) => {
+            eprintln!("warning: cannot decompress {}: {err}", path.display());
+            let _ = fs::remove_file(&scratch);
+            run.io_errors = 1;
+            return (run, Duration::ZERO, None);
+        }
+    }
+    let buffers = IoBuffers::from_kb(config.io_buffer_kb);
+    let faults_before = platform::page_faults();
+    let start = Instant::now();
+    // Opening, and for `mmap` mapping and unmapping, count as part of the pass.
+    let run = match config.input_mode {
+        InputMode::Mmap => match Mapping::open(&scratch) {
+            Ok(mapping) => etl_consume(mapping.reader(), config, run, live),
+            Err(_) => EtlRun { io_errors: 1, ..run },
+        },
+        InputMode::Buffered | InputMode::Gzip => match File::open(&scratch) {
+            Ok(file) => {
+                let reader = BufReader::with_capacity(buffers.read_kb * 1024, file);
+                etl_consume(reader, config, run, live)
+            }
+            Err(_) => EtlRun { io_errors: 1, ..run },
+        },
+    };
+    let elapsed = start.elapsed();
+    let faults = platform::page_faults()
+        .zip(faults_before)
+        .map(|(after, before)| after.since(before));
+    let _ = fs::remove_file(&scratch);
+    (run, elapsed, faults)
+}
+
+/// Writes the decompressed contents of the gzip file `from` to `to`;
+/// returns the bytes written.
+fn decompress(from: &Path, to: &Path) -> std::io::Result<u64> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut reader = GzDecoder::new(BufReader::new(File::open(from)?));
+    let mut writer = BufWriter::new(File::create(to)?);
+    let bytes = std::io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    Ok(bytes)
+}
+
+/// Parses and aggregates every line of a decompressed dataset into `run`.
+fn etl_consume(
+    mut reader: impl BufRead,
+    config: &EtlConfig,
+    mut run: EtlRun,
+    live: &LiveContext,
+) -> EtlRun {
+    let mut phases = PhaseTimer::new(SystemClock::new(), config.phase_sample_every);
+    let mut line = Vec::new();
+    loop {
+        phases.begin_row();
+        match line_reader::read_line(&mut reader, &mut line, config.max_line_bytes) {
+            Ok(Line::Eof) => break,
+            Ok(Line::Complete(_)) => phases.end(Phase::Read),
+            Ok(Line::Oversized(_)) => {
+                run.oversized_lines += 1;
+                if config.strict_line_length {
+                    run.io_errors = 1;
+                    break;
+                }
+                continue;
+            }
+            Err(_) => {
+                run.io_errors = 1;
+                break;
+            }
+        }
+        if let Ok(row) = serde_json::from_slice::<EtlRow>(&line) {
+            phases.end(Phase::Parse);
+            run.aggregate += etl_transform(row.group, row.value);
+            run.rows += 1;
+            phases.end(Phase::Aggregate);
+            if live.enabled() && run.rows.is_multiple_of(ETL_PROGRESS_ROWS) {
+                live.progress("rows_processed", run.rows as f64);
+            }
+        }
     }
-    let _ = encoder.finish();
+
+    run.phase_pct = phases.percentages();
+    run.phase_sampled_rows = phases.sampled_rows();
+    run
+}
+
+/// `etl_read` with the compressed bytes coming from `read_ahead` instead of
+/// a buffered file; also returns the most reads it had in flight.
+fn etl_read_ahead<Q: ReadQueue>(
+    read_ahead: ReadAhead<Q>,
+    config: &EtlConfig,
+    live: &LiveContext,
+) -> (EtlRun, usize) {
+    let buffers = IoBuffers::from_kb(config.io_buffer_kb);
+    let mut lines =
+        BufReader::with_capacity(buffers.read_kb * 1024, bufread::GzDecoder::new(read_ahead));
+    let run = etl_consume(&mut lines, config, EtlRun::default(), live);
+    (run, lines.into_inner().into_inner().max_in_flight())
 }
 
-fn etl_benchmark(path: &Path) -> (usize, u64, u64) {
-    build_etl_dataset(path, 20_000);
-    let size_bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return (0, 0, size_bytes),
+/// Read passes over the same dataset at each `BUFFER_SWEEP_KB` size, as
+/// (KiB, seconds, run). Phase timing and progress are off so the passes
+/// differ only in buffering.
+fn etl_buffer_sweep(path: &Path, config: &EtlConfig) -> Vec<(usize, f64, EtlRun)> {
+    let config = EtlConfig {
+        phase_sample_every: 0,
+        ..config.clone()
     };
+    let quiet = LiveContext::new(None, "", "");
+    BUFFER_SWEEP_KB
+        .iter()
+        .map(|kb| {
+            let start = Instant::now();
+            let run = etl_read(path, &config, IoBuffers::from_kb(Some(*kb)), &quiet);
+            (*kb, start.elapsed().as_secs_f64(), run)
+        })
+        .collect()
+}
+
+/// Throughput per sweep size, and a flag if any pass disagreed with the
+/// main run; buffering must never change what is read.
+fn insert_buffer_sweep_metrics(map: &mut Metrics, main: &EtlRun, sweep: &[(usize, f64, EtlRun)]) {
+    let mut mismatch = false;
+    for (kb, seconds, run) in sweep {
+        map.insert(
+            format!("buffer_sweep_{kb}kb_throughput_mb_s"),
+            run.size_bytes as f64 / (1024.0 * 1024.0) / seconds.max(1e-9),
+            "mb/s",
+        );
+        let outcome = (run.rows, run.aggregate, run.io_errors);
+        mismatch |= outcome != (main.rows, main.aggregate, main.io_errors);
+    }
+    map.insert(
+        "buffer_sweep_aggregate_mismatch",
+        mismatch as u8 as f64,
+        "flag",
+    );
+}
+
+fn etl_transform(group: u64, value: u64) -> u64 {
+    (value * 3 + group) % 1000
+}
 
-    let decoder = GzDecoder::new(file);
-    let reader = BufReader::new(decoder);
+fn etl_aggregate_typed(buffer: &str) -> (usize, u64) {
     let mut rows = 0usize;
     let mut aggregate = 0u64;
+    for line in buffer.lines() {
+        if let Ok(row) = serde_json::from_str::<EtlRow>(line) {
+            aggregate += etl_transform(row.group, row.value);
+            rows += 1;
+        }
+    }
+    (rows, aggregate)
+}
 
-    for line in reader.lines().map_while(Result::ok) {
-        if let Ok(row) = serde_json::from_str::<EtlRow>(&line) {
-            aggregate += (row.value * 3 + row.group) % 1000;
+fn etl_aggregate_untyped(buffer: &str) -> (usize, u64) {
+    let mut rows = 0usize;
+    let mut aggregate = 0u64;
+    for line in buffer.lines() {
+        let Ok(row) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let group = row.get("group").and_then(Value::as_u64);
+        let value = row.get("value").and_then(Value::as_u64);
+        if let (Some(group), Some(value)) = (group, value) {
+            aggregate += etl_transform(group, value);
             rows += 1;
         }
     }
+    (rows, aggregate)
+}
 
-    (rows, aggregate, size_bytes)
+struct EtlParseComparison {
+    typed_seconds: f64,
+    untyped_seconds: f64,
+    typed_aggregate: u64,
+    untyped_aggregate: u64,
+    /// The buffer's mapping is THP-eligible after `MADV_HUGEPAGE`.
+    hugepage_advice_applied: bool,
 }
 
-fn command_output(mut cmd: Command) -> (i32, String, String) {
-    match cmd.output() {
-        Ok(output) => {
-            let code = output.status.code().unwrap_or(1);
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            (code, stdout, stderr)
-        }
-        Err(err) => (1, String::new(), err.to_string()),
+/// `bytes` copied into memory advised `MADV_HUGEPAGE` before its first
+/// touch, starting on a hugepage boundary so whole hugepages fit. Returns
+/// the storage, where `bytes` sit in it, and whether the advice applied.
+fn hugepage_copy(bytes: &[u8]) -> (Vec<u8>, Range<usize>, bool) {
+    let advised = bytes.len().next_multiple_of(platform::HUGEPAGE_BYTES);
+    // Zeroed allocations this large are fresh mappings, not yet faulted in.
+    let mut storage = vec![0u8; advised + platform::HUGEPAGE_BYTES];
+    let start = storage.as_ptr().align_offset(platform::HUGEPAGE_BYTES);
+    let applied = platform::advise_hugepages(&mut storage[start..start + advised]);
+    storage[start..start + bytes.len()].copy_from_slice(bytes);
+    (storage, start..start + bytes.len(), applied == Some(true))
+}
+
+/// Both parse passes read the same pre-decompressed buffer so that the
+/// comparison isolates typed vs untyped deserialization from gzip cost.
+/// Oversized lines are left out of the buffer, as the main pass skips them.
+fn etl_parse_comparison(path: &Path, config: &EtlConfig) -> Option<EtlParseComparison> {
+    let mut reader = BufReader::new(GzDecoder::new(File::open(path).ok()?));
+    let mut bytes = Vec::new();
+    let mut line = Vec::new();
+    while line_reader::read_line(&mut reader, &mut line, config.max_line_bytes).ok()? != Line::Eof {
+        bytes.extend_from_slice(&line);
     }
+    let (storage, range, hugepage_advice_applied) = if config.madvise_hugepage {
+        hugepage_copy(&bytes)
+    } else {
+        let len = bytes.len();
+        (bytes, 0..len, false)
+    };
+    let buffer = std::str::from_utf8(&storage[range]).ok()?;
+
+    let start = Instant::now();
+    let (_, typed_aggregate) = etl_aggregate_typed(buffer);
+    let typed_seconds = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let (_, untyped_aggregate) = etl_aggregate_untyped(buffer);
+    let untyped_seconds = start.elapsed().as_secs_f64();
+
+    Some(EtlParseComparison {
+        typed_seconds,
+        untyped_seconds,
+        typed_aggregate,
+        untyped_aggregate,
+        hugepage_advice_applied,
+    })
 }
 
-fn has_cargo_subcommand(name: &str) -> bool {
-    let mut cmd = Command::new("cargo");
-    cmd.args([name, "--version"]).current_dir(repo_root());
-    let (code, _, _) = command_output(cmd);
-    code == 0
+fn insert_etl_parse_metrics(map: &mut Metrics, comparison: Option<EtlParseComparison>) {
+    map.insert("input_mode_predecompressed", 1.0, "flag");
+    let Some(comparison) = comparison else {
+        map.insert("parse_comparison_errors", 1.0, "count");
+        return;
+    };
+    map.insert("typed_parse_seconds", comparison.typed_seconds, "s");
+    map.insert("untyped_parse_seconds", comparison.untyped_seconds, "s");
+    map.insert(
+        "typed_speedup_ratio",
+        comparison.untyped_seconds / comparison.typed_seconds.max(1e-9),
+        "ratio",
+    );
+    map.insert(
+        "untyped_aggregate_value",
+        comparison.untyped_aggregate as f64,
+        "count",
+    );
+    map.insert(
+        "parse_comparison_errors",
+        (comparison.typed_aggregate != comparison.untyped_aggregate) as u8 as f64,
+        "count",
+    );
+}
+
+fn insert_capture_metrics(map: &mut Metrics, prefix: &str, capture: CaptureStats) {
+    map.insert(
+        format!("{prefix}output_bytes_captured"),
+        capture.bytes_captured as f64,
+        "bytes",
+    );
+    map.insert(
+        format!("{prefix}output_truncated"),
+        capture.truncated() as u8 as f64,
+        "flag",
+    );
+    map.insert(
+        format!("{prefix}oversized_lines"),
+        capture.oversized_lines as f64,
+        "count",
+    );
+}
+
+/// `runtime_seconds` split into `tool_runtime_seconds` (child processes)
+/// and `processing_seconds` (everything else: parsing, aggregation, file
+/// copies, precondition probes), so slower parsing does not read as a
+/// slower tool.
+fn insert_runtime_split(map: &mut Metrics, runtime: Duration, tool_time: Duration) {
+    let tool_time = tool_time.min(runtime);
+    map.insert("runtime_seconds", runtime.as_secs_f64(), "s");
+    map.insert("tool_runtime_seconds", tool_time.as_secs_f64(), "s");
+    map.insert(
+        "processing_seconds",
+        (runtime - tool_time).as_secs_f64(),
+        "s",
+    );
 }
 
-fn count_from_section(section: &Value) -> Option<u64> {
-    if let Some(count) = section.get("count").and_then(Value::as_u64) {
+/// A count field as a JSON number or a digit string. A string that only
+/// parses under another locale's separators sets `locale_suspect` rather than
+/// being misread.
+fn count_field(section: &Value, key: &str, locale_suspect: &mut bool) -> Option<u64> {
+    let field = section.get(key)?;
+    if let Some(count) = field.as_u64() {
         return Some(count);
     }
-    if let Some(count) = section.get("found_count").and_then(Value::as_u64) {
+    match parse_count(field.as_str()?) {
+        Ok(count) => Some(count),
+        Err(NumberError::LocaleSuspect) => {
+            *locale_suspect = true;
+            None
+        }
+        Err(NumberError::Invalid) => None,
+    }
+}
+
+fn count_from_section(section: &Value, locale_suspect: &mut bool) -> Option<u64> {
+    if let Some(count) = count_field(section, "count", locale_suspect) {
+        return Some(count);
+    }
+    if let Some(count) = count_field(section, "found_count", locale_suspect) {
         return Some(count);
     }
     if let Some(arr) = section.get("list").and_then(Value::as_array) {
@@ -295,21 +1200,24 @@ fn count_from_section(section: &Value) -> Option<u64> {
     None
 }
 
-fn count_vulnerability_findings(payload: &Value) -> u64 {
+fn count_vulnerability_findings(payload: &Value, locale_suspect: &mut bool) -> u64 {
     for key in ["vulnerabilities", "advisories"] {
         if let Some(section) = payload.get(key) {
-            if let Some(count) = count_from_section(section) {
+            if let Some(count) = count_from_section(section, locale_suspect) {
                 return count;
             }
         }
     }
-    count_from_section(payload).unwrap_or(0)
+    count_from_section(payload, locale_suspect).unwrap_or(0)
 }
 
-fn count_outdated_dependencies(payload: &Value) -> u64 {
+fn count_outdated_dependencies(payload: &Value, locale_suspect: &mut bool) -> u64 {
     if let Some(arr) = payload.as_array() {
         return arr.len() as u64;
     }
+    if let Some(count) = count_field(payload, "count", locale_suspect) {
+        return count;
+    }
     if let Some(obj) = payload.as_object() {
         for key in ["outdated", "dependencies", "packages", "results"] {
             if let Some(arr) = obj.get(key).and_then(Value::as_array) {
@@ -323,101 +1231,192 @@ fn count_outdated_dependencies(payload: &Value) -> u64 {
     0
 }
 
-fn classify_clippy_finding(code: &str) -> &'static str {
-    let high_patterns = [
-        "unwrap_used",
-        "expect_used",
-        "panic",
-        "todo",
-        "unimplemented",
-        "indexing_slicing",
-    ];
-    if high_patterns.iter().any(|pattern| code.contains(pattern)) {
-        return "high";
-    }
-    let medium_patterns = ["suspicious", "correctness", "perf", "complexity"];
-    if medium_patterns.iter().any(|pattern| code.contains(pattern)) {
-        return "medium";
-    }
-    "low"
+fn warn_locale_suspect(live: &LiveContext, tool: &str) {
+    live.warn(
+        WarningCode::LocaleSuspect,
+        format!("{tool} printed a count that only parses with another locale's separators"),
+        json!({"tool": tool}),
+    );
 }
 
-fn parse_clippy_messages(output: &str) -> (f64, f64, f64, f64) {
-    let mut high = 0.0;
-    let mut medium = 0.0;
-    let mut low = 0.0;
-    let mut parse_errors = 0.0;
+/// Which halves of the dependency scorecard ran the built-in scanners.
+#[derive(Debug, Default)]
+struct BuiltinScan {
+    vulnerabilities: bool,
+    outdated: bool,
+    advisories_loaded: usize,
+    outdated_unresolved: usize,
+}
 
-    for line in output.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let Ok(payload) = serde_json::from_str::<Value>(trimmed) else {
-            if trimmed.starts_with('{') {
-                parse_errors += 1.0;
-            }
-            continue;
-        };
-        if payload.get("reason").and_then(Value::as_str) != Some("compiler-message") {
-            continue;
+impl BuiltinScan {
+    /// The `*_scanner_builtin` flags always; the built-in scanners' own
+    /// counts only when they ran.
+    fn insert_metrics(&self, map: &mut Metrics) {
+        map.insert(
+            "vulnerability_scanner_builtin",
+            self.vulnerabilities as u8 as f64,
+            "flag",
+        );
+        map.insert(
+            "outdated_scanner_builtin",
+            self.outdated as u8 as f64,
+            "flag",
+        );
+        if self.vulnerabilities {
+            map.insert(
+                "builtin_advisories_loaded",
+                self.advisories_loaded as f64,
+                "count",
+            );
         }
-        let Some(message) = payload.get("message") else {
-            parse_errors += 1.0;
-            continue;
-        };
-        if message.get("level").and_then(Value::as_str) != Some("warning") {
-            continue;
+        if self.outdated {
+            map.insert(
+                "outdated_unresolved",
+                self.outdated_unresolved as f64,
+                "count",
+            );
         }
+    }
+}
 
-        let code = message
-            .get("code")
-            .and_then(|c| c.get("code"))
-            .and_then(Value::as_str)
-            .unwrap_or("");
-        if !code.is_empty() && !code.starts_with("clippy::") {
-            continue;
-        }
+fn read_lockfile(rust_dir: &Path) -> Result<Vec<builtin_scan::Package>, String> {
+    let path = rust_dir.join("Cargo.lock");
+    let text =
+        fs::read_to_string(&path).map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+    builtin_scan::parse_lockfile(&text)
+}
 
-        match classify_clippy_finding(code) {
-            "high" => high += 1.0,
-            "medium" => medium += 1.0,
-            _ => low += 1.0,
-        }
-    }
+/// Findings for the locked packages, and how many advisories were checked.
+fn builtin_vulnerability_scan(
+    rust_dir: &Path,
+    snapshot: &Path,
+) -> Result<(Vec<builtin_scan::Finding>, usize), String> {
+    let packages = read_lockfile(rust_dir)?;
+    let advisories = builtin_scan::load_advisories(snapshot)?;
+    Ok((builtin_scan::scan(&packages, &advisories), advisories.len()))
+}
 
-    (high, medium, low, parse_errors)
+fn builtin_outdated_scan(
+    rust_dir: &Path,
+    cargo_home: &Path,
+) -> Result<builtin_scan::OutdatedReport, String> {
+    let packages = read_lockfile(rust_dir)?;
+    Ok(builtin_scan::outdated(&packages, &builtin_scan::cached_versions(cargo_home)))
 }
 
-fn dependency_scan_metrics() -> Map<String, Value> {
+/// Phases: `vulnerabilities` and `outdated`, each through its cargo
+/// subcommand or the built-in fallback.
+fn dependency_scan_metrics(
+    config: &DependencyScanConfig,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+    phases: &mut PhaseLog,
+    runner: &dyn CommandRunner,
+) -> Metrics {
     let start = Instant::now();
+    phases.enter("vulnerabilities");
     let rust_dir = repo_root().join("benchmarks/rust");
-    let mut map = Map::new();
+    let mut map = Metrics::new();
     let mut vulnerability_findings = 0.0;
     let mut outdated_dependencies = 0.0;
     let mut audit_exit_code = -1.0;
     let mut outdated_exit_code = -1.0;
     let mut scan_errors = 0.0;
-    let tool_available = if has_cargo_subcommand("audit") { 1.0 } else { 0.0 };
+    let mut exits = ExitTally::default();
+    let mut audit_capture = CaptureStats::default();
+    let mut outdated_capture = CaptureStats::default();
+    let mut advisory_db_found = 0.0;
+    let mut advisory_db_age_days = -1.0;
+    let mut locale_suspect = false;
+    let mut tool_time = Duration::ZERO;
+    let mut counters = ChildCounters::from_env();
+    let mut builtin = BuiltinScan::default();
+    let mut vulnerability_scan_skipped = false;
+    let audit = Precondition::ReplaceableSubcommand("audit");
+    let tool_available = if checks.check_with(audit, runner) { 1.0 } else { 0.0 };
 
+    let mut phase_errors: Vec<String> = Vec::new();
     if tool_available > 0.0 {
         let mut audit_cmd = Command::new("cargo");
         audit_cmd.args(["audit", "--json"]).current_dir(&rust_dir);
-        let (code, stdout, _) = command_output(audit_cmd);
+        let configured_db = config.advisory_db_path.as_ref().map(|path| repo_root().join(path));
+        if let Some(db) = &configured_db {
+            audit_cmd.arg("--db").arg(db);
+        }
+        let output = runner.output(audit_cmd);
+        tool_time += output.tool_time;
+        counters.add("cargo_audit", output.usage);
+        let code = output.code;
         audit_exit_code = code as f64;
-        if let Ok(parsed) = serde_json::from_str::<Value>(&stdout) {
-            vulnerability_findings = count_vulnerability_findings(&parsed) as f64;
+        audit_capture = output.capture;
+        if let Ok(parsed) = serde_json::from_str::<Value>(&output.stdout) {
+            let mut suspect = false;
+            vulnerability_findings = count_vulnerability_findings(&parsed, &mut suspect) as f64;
+            if suspect {
+                warn_locale_suspect(live, "cargo audit");
+                locale_suspect = true;
+            }
         } else {
             scan_errors += 1.0;
+            phase_errors.push("cargo audit printed no JSON report".to_string());
         }
-        if code != 0 {
-            scan_errors += 1.0;
+        let class = Tool::CargoAudit.classify(code, vulnerability_findings > 0.0);
+        if class == ExitClass::Unexpected {
+            phase_errors.push(format!("cargo audit exited with {code}"));
+        }
+        exits.add(class);
+
+        let located = advisory_db::locate(configured_db.as_deref(), advisory_db::cargo_home().as_deref());
+        let (Ok(db) | Err(db)) = &located;
+        if checks.record(Precondition::AdvisoryDb(db).outcome(located.is_ok())) {
+            if let Ok(modified) = advisory_db::last_modified(db) {
+                advisory_db_found = 1.0;
+                advisory_db_age_days = advisory_db::age_days(modified, SystemTime::now());
+                let max_age_days = config.advisory_db_max_age_days;
+                if advisory_db_age_days > max_age_days {
+                    live.warn(
+                        WarningCode::AdvisoryDbStale,
+                        format!("advisory database is {advisory_db_age_days:.1} days old"),
+                        json!({"age_days": advisory_db_age_days, "max_age_days": max_age_days}),
+                    );
+                }
+            }
+        }
+    } else if let Some(snapshot) = &config.osv_snapshot {
+        builtin.vulnerabilities = true;
+        let snapshot = repo_root().join(snapshot);
+        let scanned = checks
+            .check(Precondition::AdvisorySnapshot(&snapshot))
+            .then(|| builtin_vulnerability_scan(&rust_dir, &snapshot));
+        match scanned {
+            Some(Ok((findings, advisories))) => {
+                vulnerability_findings = findings.len() as f64;
+                builtin.advisories_loaded = advisories;
+            }
+            Some(Err(err)) => {
+                eprintln!("warning: built-in vulnerability scan: {err}");
+                scan_errors += 1.0;
+                phase_errors.push(format!("built-in vulnerability scan: {err}"));
+            }
+            None => {
+                scan_errors += 1.0;
+                phase_errors.push(format!("advisory snapshot {} not found", snapshot.display()));
+            }
         }
     } else {
-        scan_errors += 1.0;
+        vulnerability_scan_skipped = true;
+    }
+    if vulnerability_scan_skipped {
+        phases.skip("vulnerabilities", "no cargo audit and no BENCHMARK_ADVISORY_DB snapshot");
+    } else {
+        phases.record("vulnerabilities", start.elapsed(), joined(&phase_errors));
     }
 
-    if has_cargo_subcommand("outdated") {
+    let outdated_start = Instant::now();
+    phases.enter("outdated");
+    phase_errors.clear();
+
+    if checks.check_with(Precondition::ReplaceableSubcommand("outdated"), runner) {
         let mut outdated_cmd = Command::new("cargo");
         outdated_cmd
             .args([
@@ -426,139 +1425,497 @@ fn dependency_scan_metrics() -> Map<String, Value> {
                 "json",
             ])
             .current_dir(&rust_dir);
-        let (code, stdout, _) = command_output(outdated_cmd);
+        let output = runner.output(outdated_cmd);
+        tool_time += output.tool_time;
+        counters.add("cargo_outdated", output.usage);
+        let code = output.code;
         outdated_exit_code = code as f64;
+        outdated_capture = output.capture;
         if code == 0 {
-            if let Ok(parsed) = serde_json::from_str::<Value>(&stdout) {
-                outdated_dependencies = count_outdated_dependencies(&parsed) as f64;
+            if let Ok(parsed) = serde_json::from_str::<Value>(&output.stdout) {
+                let mut suspect = false;
+                outdated_dependencies = count_outdated_dependencies(&parsed, &mut suspect) as f64;
+                if suspect {
+                    warn_locale_suspect(live, "cargo outdated");
+                    locale_suspect = true;
+                }
             } else {
                 scan_errors += 1.0;
+                phase_errors.push("cargo outdated printed no JSON report".to_string());
+            }
+        }
+        let class = Tool::CargoOutdated.classify(code, outdated_dependencies > 0.0);
+        if class == ExitClass::Unexpected {
+            phase_errors.push(format!("cargo outdated exited with {code}"));
+        }
+        exits.add(class);
+    } else {
+        builtin.outdated = true;
+        let home = advisory_db::cargo_home().unwrap_or_default();
+        match builtin_outdated_scan(&rust_dir, &home) {
+            Ok(report) => {
+                outdated_dependencies = report.outdated.len() as f64;
+                builtin.outdated_unresolved = report.unresolved;
+            }
+            Err(err) => {
+                eprintln!("warning: built-in outdated check: {err}");
+                scan_errors += 1.0;
+                phase_errors.push(format!("built-in outdated check: {err}"));
             }
-        } else {
-            scan_errors += 1.0;
         }
     }
+    phases.record("outdated", outdated_start.elapsed(), joined(&phase_errors));
 
-    let runtime = start.elapsed().as_secs_f64();
-    map.insert("runtime_seconds".to_string(), metric(runtime, "s"));
-    map.insert(
-        "vulnerability_findings".to_string(),
-        metric(vulnerability_findings, "count"),
-    );
-    map.insert(
-        "outdated_dependencies".to_string(),
-        metric(outdated_dependencies, "count"),
-    );
-    map.insert("audit_exit_code".to_string(), metric(audit_exit_code, "code"));
+    insert_runtime_split(&mut map, start.elapsed(), tool_time);
+    map.insert("vulnerability_findings", vulnerability_findings, "count");
     map.insert(
-        "outdated_exit_code".to_string(),
-        metric(outdated_exit_code, "code"),
+        "vulnerability_scan_skipped",
+        vulnerability_scan_skipped as u8 as f64,
+        "flag",
     );
-    map.insert("tool_available".to_string(), metric(tool_available, "flag"));
-    map.insert("scan_errors".to_string(), metric(scan_errors, "count"));
+    map.insert("outdated_dependencies", outdated_dependencies, "count");
+    map.insert("audit_exit_code", audit_exit_code, "code");
+    map.insert("outdated_exit_code", outdated_exit_code, "code");
+    map.insert("tool_available", tool_available, "flag");
+    builtin.insert_metrics(&mut map);
+    map.insert("scan_errors", scan_errors + exits.unexpected, "count");
+    map.insert("expected_nonzero_exits", exits.expected_nonzero, "count");
+    map.insert("advisory_db_found", advisory_db_found, "flag");
+    map.insert("advisory_db_age_days", advisory_db_age_days, "days");
+    // Deprecated: the `locale_suspect` and `advisory_db_stale` warnings
+    // replace these flags, which are dropped in 0.3.0.
+    map.insert("locale_suspect", locale_suspect as u8 as f64, "flag");
+    let advisory_db_stale = advisory_db_age_days > config.advisory_db_max_age_days;
+    map.insert("advisory_db_stale", advisory_db_stale as u8 as f64, "flag");
+    insert_capture_metrics(&mut map, "audit_", audit_capture);
+    insert_capture_metrics(&mut map, "outdated_", outdated_capture);
+    counters.insert_metrics(&mut map);
     map
 }
 
-fn static_lint_metrics() -> Map<String, Value> {
+fn workspace_metadata(
+    runner: &dyn CommandRunner,
+    manifest: &Path,
+    tool_time: &mut Duration,
+) -> Result<Workspace, String> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["metadata", "--no-deps", "--format-version", "1", "--manifest-path"])
+        .arg(manifest)
+        .current_dir(repo_root());
+    let output = runner.output(cmd);
+    *tool_time += output.tool_time;
+    if output.code != 0 {
+        return Err(format!("cargo metadata exited with {}", output.code));
+    }
+    clippy::parse_metadata(&output.stdout)
+}
+
+/// Member names become metric keys, so anything but `[a-z0-9_]` maps to `_`.
+fn crate_metric_key(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    format!("crate_{name}_finding_count")
+}
+
+fn clippy_command(manifest: &Path, lints: &[String], fix: bool) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("clippy");
+    if fix {
+        cmd.args(["--fix", "--allow-dirty", "--allow-no-vcs"]);
+    }
+    cmd.args(["--workspace", "--manifest-path"])
+        .arg(manifest)
+        .args(["--message-format", "json", "--"])
+        .current_dir(repo_root());
+    for lint in lints {
+        cmd.args(["-W", lint]);
+    }
+    cmd
+}
+
+/// Copies `from` into `to`, skipping build output and VCS metadata.
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "target" || name == ".git" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &to.join(&name))?;
+        } else {
+            fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Lints a scratch copy of `workspace` after `cargo clippy --fix` and
+/// returns the findings that remain. The original tree is never touched.
+fn findings_after_fix(
+    runner: &dyn CommandRunner,
+    workspace: &Workspace,
+    lints: &[String],
+    scratch: &Path,
+    tool_time: &mut Duration,
+) -> Result<f64, String> {
+    let _ = fs::remove_dir_all(scratch);
+    copy_tree(&workspace.root, scratch)
+        .map_err(|err| format!("cannot copy {}: {err}", workspace.root.display()))?;
+    let manifest = scratch.join("Cargo.toml");
+    let fix = runner.output(clippy_command(&manifest, lints, true));
+    *tool_time += fix.tool_time;
+    if fix.code != 0 {
+        return Err(format!("cargo clippy --fix exited with {}", fix.code));
+    }
+    let copy = workspace_metadata(runner, &manifest, tool_time)?;
+    let cmd = clippy_command(&manifest, lints, false);
+    let (output, report) = command_runner::stream(runner, cmd, |lines| {
+        parse_clippy_messages(lines, &copy.members)
+    });
+    *tool_time += output.tool_time;
+    if output.code != 0 {
+        return Err(format!("cargo clippy exited with {} after --fix", output.code));
+    }
+    Ok(report.totals.total())
+}
+
+/// Phases: `clippy` over every workspace, then `verify_fixes` when asked
+/// for and clippy ran cleanly.
+fn static_lint_metrics(
+    config: &StaticLintConfig,
+    data_dir: &DataDir,
+    checks: &mut Preconditions,
+    phases: &mut PhaseLog,
+    runner: &dyn CommandRunner,
+) -> Metrics {
     let start = Instant::now();
-    let mut map = Map::new();
-    let mut high_findings = 0.0;
-    let mut medium_findings = 0.0;
-    let mut low_findings = 0.0;
-    let mut finding_count = 0.0;
-    let mut lint_exit_code = -1.0;
+    // Fix verification runs inside the clippy loop, so it shares this phase.
+    phases.enter("clippy");
+    let mut map = Metrics::new();
+    let mut report = LintReport::default();
+    // The first nonzero exit across workspaces; -1 when clippy never ran.
+    let mut lint_exit_code: Option<i32> = None;
     let mut scan_errors = 0.0;
-    let tool_available = if has_cargo_subcommand("clippy") { 1.0 } else { 0.0 };
+    let mut exits = ExitTally::default();
+    let mut capture = CaptureStats::default();
+    let mut fix_verified = 0.0;
+    let mut workspaces: Vec<Workspace> = Vec::new();
+    let mut tool_time = Duration::ZERO;
+    let mut counters = ChildCounters::from_env();
+    let mut clippy_errors: Vec<String> = Vec::new();
+    let mut verify_errors: Vec<String> = Vec::new();
+    let mut verify_time = Duration::ZERO;
+    let tool_available = if checks.check_with(Precondition::CargoSubcommand("clippy"), runner) {
+        1.0
+    } else {
+        0.0
+    };
 
     if tool_available > 0.0 {
-        let mut cmd = Command::new("cargo");
-        cmd.args([
-            "clippy",
-            "--manifest-path",
-            "benchmarks/rust/Cargo.toml",
-            "--message-format",
-            "json",
-            "--",
-            "-W",
-            "clippy::suspicious",
-            "-W",
-            "clippy::correctness",
-        ])
-        .current_dir(repo_root());
-        let (code, stdout, stderr) = command_output(cmd);
-        lint_exit_code = code as f64;
-        let joined = format!("{stdout}\n{stderr}");
-        let (high, medium, low, parse_errors) = parse_clippy_messages(&joined);
-        high_findings = high;
-        medium_findings = medium;
-        low_findings = low;
-        finding_count = high + medium + low;
-        scan_errors += parse_errors;
-        if code != 0 {
-            scan_errors += 1.0;
+        // Manifests of the same workspace share one clippy run.
+        for manifest in &config.manifests {
+            match workspace_metadata(runner, &repo_root().join(manifest), &mut tool_time) {
+                Ok(workspace) => {
+                    if !workspaces.iter().any(|seen| seen.root == workspace.root) {
+                        workspaces.push(workspace);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("warning: skipping {manifest}: {err}");
+                    scan_errors += 1.0;
+                    clippy_errors.push(format!("{manifest}: {err}"));
+                }
+            }
         }
     } else {
         scan_errors += 1.0;
     }
 
-    let runtime = start.elapsed().as_secs_f64();
-    map.insert("runtime_seconds".to_string(), metric(runtime, "s"));
-    map.insert("finding_count".to_string(), metric(finding_count, "count"));
-    map.insert("high_findings".to_string(), metric(high_findings, "count"));
-    map.insert("medium_findings".to_string(), metric(medium_findings, "count"));
-    map.insert("low_findings".to_string(), metric(low_findings, "count"));
-    map.insert("lint_exit_code".to_string(), metric(lint_exit_code, "code"));
-    map.insert("tool_available".to_string(), metric(tool_available, "flag"));
-    map.insert("scan_errors".to_string(), metric(scan_errors, "count"));
+    for (index, workspace) in workspaces.iter().enumerate() {
+        let cmd = clippy_command(&workspace.root.join("Cargo.toml"), &config.lints, false);
+        // With --message-format json every compiler message arrives on stdout,
+        // so it is parsed line by line as cargo emits it.
+        let (output, workspace_report) = command_runner::stream(runner, cmd, |lines| {
+            parse_clippy_messages(lines, &workspace.members)
+        });
+        tool_time += output.tool_time;
+        counters.add("clippy", output.usage);
+        let code = output.code;
+        if matches!(lint_exit_code, None | Some(0)) {
+            lint_exit_code = Some(code);
+        }
+        capture.add(output.capture);
+        if config.verify_fixes && code == 0 {
+            let verify_start = Instant::now();
+            let scratch = data_dir.cache_dir().join(format!("clippy-fix-{index}"));
+            match findings_after_fix(runner, workspace, &config.lints, &scratch, &mut tool_time) {
+                Ok(remaining) => fix_verified += (workspace_report.totals.total() - remaining).max(0.0),
+                Err(err) => {
+                    eprintln!("warning: fix verification failed for {}: {err}", workspace.root.display());
+                    scan_errors += 1.0;
+                    verify_errors.push(format!("{}: {err}", workspace.root.display()));
+                }
+            }
+            data_dir.track(&scratch);
+            let _ = fs::remove_dir_all(scratch);
+            verify_time += verify_start.elapsed();
+        }
+        let class = Tool::Clippy.classify(code, workspace_report.totals.total() > 0.0);
+        if class == ExitClass::Unexpected {
+            let root = workspace.root.display();
+            clippy_errors.push(format!("clippy exited with {code} in {root}"));
+        }
+        exits.add(class);
+        report.merge(workspace_report);
+    }
+    scan_errors += report.parse_errors;
+    if report.parse_errors > 0.0 {
+        clippy_errors.push(format!("{} unparsable clippy messages", report.parse_errors));
+    }
+    if tool_available > 0.0 {
+        let clippy_time = start.elapsed().saturating_sub(verify_time);
+        phases.record("clippy", clippy_time, joined(&clippy_errors));
+    } else {
+        phases.skip("clippy", "cargo clippy not installed");
+    }
+    if !config.verify_fixes {
+        phases.skip("verify_fixes", "not requested");
+    } else if phases.status("clippy") != Some(PhaseStatus::Ok) {
+        phases.skip("verify_fixes", "clippy did not run cleanly");
+    } else {
+        phases.record("verify_fixes", verify_time, joined(&verify_errors));
+    }
+
+    let totals = report.totals;
+    insert_runtime_split(&mut map, start.elapsed(), tool_time);
+    map.insert("finding_count", totals.total(), "count");
+    map.insert("high_findings", totals.high, "count");
+    map.insert("medium_findings", totals.medium, "count");
+    map.insert("low_findings", totals.low, "count");
+    map.insert(
+        "lint_exit_code",
+        lint_exit_code.map_or(-1.0, f64::from),
+        "code",
+    );
+    map.insert("tool_available", tool_available, "flag");
+    map.insert("scan_errors", scan_errors + exits.unexpected, "count");
+    map.insert("expected_nonzero_exits", exits.expected_nonzero, "count");
+    map.insert("crates_scanned", report.per_crate.len() as f64, "count");
+    map.insert("dependency_findings_excluded", report.excluded, "count");
+    let fixes = report.fixes;
+    map.insert("auto_fixable_findings", fixes.machine_applicable, "count");
+    map.insert(
+        "maybe_incorrect_fix_findings",
+        fixes.maybe_incorrect,
+        "count",
+    );
+    map.insert("placeholder_fix_findings", fixes.has_placeholders, "count");
+    map.insert("unspecified_fix_findings", fixes.unspecified, "count");
+    map.insert(
+        "fix_verification_enabled",
+        if config.verify_fixes { 1.0 } else { 0.0 },
+        "flag",
+    );
+    map.insert("fix_verified_count", fix_verified, "count");
+    for (name, findings) in &report.per_crate {
+        map.insert(crate_metric_key(name), findings.total(), "count");
+    }
+    insert_capture_metrics(&mut map, "", capture);
+    counters.insert_metrics(&mut map);
     map
 }
 
-fn test_reliability_metrics(iterations: usize) -> Map<String, Value> {
+fn test_reliability_metrics(
+    runner: &dyn CommandRunner,
+    iterations: usize,
+    live: &LiveContext,
+) -> Metrics {
     let start = Instant::now();
     let mut failures = 0.0;
+    let mut capture = CaptureStats::default();
+    let mut tool_time = Duration::ZERO;
+    let mut peak_rss = None;
+    let mut timing = TimingSummary::default();
+    let per_test_times = nightly_toolchain(runner, &mut tool_time);
     for _ in 0..iterations {
         let mut cmd = Command::new("cargo");
         cmd.args(["test", "--manifest-path", "benchmarks/rust/Cargo.toml"])
             .current_dir(repo_root());
-        let (code, _, _) = command_output(cmd);
-        if code != 0 {
+        if per_test_times {
+            cmd.args(["--", "-Z", "unstable-options", "--format", "json", "--report-time"]);
+        }
+        let output = runner.output(cmd);
+        tool_time += output.tool_time;
+        fold_child_peak_rss(&mut peak_rss, output.usage);
+        capture.add(output.capture);
+        timing.add(parse_test_output(&output.stdout));
+        if output.code != 0 {
             failures += 1.0;
         }
     }
-    let runtime = start.elapsed().as_secs_f64();
-    let mut map = Map::new();
-    map.insert("runtime_seconds".to_string(), metric(runtime, "s"));
-    map.insert("iterations".to_string(), metric(iterations as f64, "count"));
-    map.insert("failed_iterations".to_string(), metric(failures, "count"));
-    map.insert(
-        "flaky_rate".to_string(),
-        metric(failures / iterations.max(1) as f64, "ratio"),
-    );
+    let mut map = Metrics::new();
+    insert_runtime_split(&mut map, start.elapsed(), tool_time);
+    map.insert("iterations", iterations as f64, "count");
+    map.insert("failed_iterations", failures, "count");
+    map.insert("flaky_rate", failures / iterations.max(1) as f64, "ratio");
+    timing.insert_metrics(&mut map);
+    live.diagnose("test_timing", timing.diagnostics());
+    insert_capture_metrics(&mut map, "", capture);
+    insert_child_peak_rss(&mut map, peak_rss);
     map
 }
 
-fn build_startup_metrics() -> Map<String, Value> {
+/// Whether `rustc` is a nightly, whose libtest reports per-test times.
+fn nightly_toolchain(runner: &dyn CommandRunner, tool_time: &mut Duration) -> bool {
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--version").current_dir(repo_root());
+    let output = runner.output(cmd);
+    *tool_time += output.tool_time;
+    output.code == 0 && output.stdout.contains("-nightly")
+}
+
+/// A phase result from every problem it ran into, if any.
+fn joined(errors: &[String]) -> Result<(), String> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// A phase result from a tool's exit code.
+fn exit_result(what: &str, code: i32) -> Result<(), String> {
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(format!("{what} exited with {code}"))
+    }
+}
+
+/// Wall time of `exe --noop`, and its exit code.
+fn noop_startup(exe: &Path) -> (f64, i32) {
+    let start = Instant::now();
+    let code = Command::new(exe)
+        .arg("--noop")
+        .output()
+        .ok()
+        .and_then(|output| output.status.code())
+        .unwrap_or(1);
+    (start.elapsed().as_secs_f64(), code)
+}
+
+/// The fully static musl triple for this architecture, when its standard
+/// library is installed in the active toolchain.
+fn static_target(runner: &dyn CommandRunner, tool_time: &mut Duration) -> Option<String> {
+    if env::consts::OS != "linux" {
+        return None;
+    }
+    let triple = format!("{}-unknown-linux-musl", env::consts::ARCH);
+    let mut cmd = Command::new("rustc");
+    cmd.args(["--print", "sysroot"]);
+    let output = runner.output(cmd);
+    *tool_time += output.tool_time;
+    let sysroot = output.stdout;
+    Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(&triple)
+        .is_dir()
+        .then_some(triple)
+}
+
+/// Builds the `triple` variant into the same target dir and profile as
+/// `exe`, then times its startup; `None` when the build fails.
+fn static_variant_startup(
+    runner: &dyn CommandRunner,
+    exe: &Path,
+    triple: &str,
+    tool_time: &mut Duration,
+) -> Option<(f64, i32)> {
+    let profile_dir = exe.parent()?;
+    let target_dir = profile_dir.parent()?;
+    let profile = profile_dir.file_name()?;
+    let mut cmd = Command::new("cargo");
+    cmd.args(["build", "--manifest-path", "benchmarks/rust/Cargo.toml", "--target", triple])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .current_dir(repo_root());
+    if profile == "release" {
+        cmd.arg("--release");
+    }
+    let build = runner.output(cmd);
+    *tool_time += build.tool_time;
+    if build.code != 0 {
+        return None;
+    }
+    let static_exe = target_dir.join(triple).join(profile).join(exe.file_name()?);
+    let (seconds, code) = noop_startup(&static_exe);
+    *tool_time += Duration::from_secs_f64(seconds);
+    Some((seconds, code))
+}
+
+/// Phases: `build`, `startup` and `static_variant`; a failed static variant
+/// leaves the dynamic build's metrics valid.
+fn build_startup_metrics(runner: &dyn CommandRunner, phases: &mut PhaseLog) -> Metrics {
     let total_start = Instant::now();
 
     let build_start = Instant::now();
+    phases.enter("build");
     let mut build_cmd = Command::new("cargo");
     build_cmd
         .args(["build", "--manifest-path", "benchmarks/rust/Cargo.toml"])
         .current_dir(repo_root());
-    let (build_code, _, _) = command_output(build_cmd);
+    let build_output = runner.output(build_cmd);
+    let build_code = build_output.code;
     let build_elapsed = build_start.elapsed().as_secs_f64();
+    let mut tool_time = build_output.tool_time;
+    let mut counters = ChildCounters::from_env();
+    counters.add("cargo_build", build_output.usage);
+    let peak_rss = build_output.usage.map(|usage| usage.max_rss_kb);
+    phases.record("build", build_start.elapsed(), exit_result("cargo build", build_code));
 
-    let startup_start = Instant::now();
-    let startup_code = match env::current_exe() {
-        Ok(exe) => Command::new(exe)
-            .arg("--noop")
-            .output()
-            .ok()
-            .and_then(|output| output.status.code())
-            .unwrap_or(1),
-        Err(_) => 1,
+    phases.enter("startup");
+    let exe = env::current_exe().ok();
+    let (startup_elapsed, startup_code) = match &exe {
+        Some(exe) => noop_startup(exe),
+        None => (0.0, 1),
+    };
+    tool_time += Duration::from_secs_f64(startup_elapsed);
+    let startup_result = match &exe {
+        Some(_) => exit_result("startup", startup_code),
+        None => Err("current executable not found".to_string()),
+    };
+    phases.record("startup", Duration::from_secs_f64(startup_elapsed), startup_result);
+    let link_info = exe
+        .as_ref()
+        .and_then(|exe| fs::read(exe).ok())
+        .and_then(|bytes| linkage::inspect(&bytes));
+    phases.enter("static_variant");
+    let static_triple = static_target(runner, &mut tool_time);
+    // A static variant that fails to build or start counts as an operation error.
+    let static_start = Instant::now();
+    let (static_startup_seconds, static_failed) = match exe.as_deref().zip(static_triple.as_deref()) {
+        Some((exe, triple)) => {
+            let outcome = static_variant_startup(runner, exe, triple, &mut tool_time);
+            let result = match outcome {
+                Some((_, 0)) => Ok(()),
+                Some((_, code)) => exit_result("static startup", code),
+                None => Err(format!("static build for {triple} failed")),
+            };
+            phases.record("static_variant", static_start.elapsed(), result);
+            match outcome {
+                Some((seconds, 0)) => (Some(seconds), false),
+                _ => (None, true),
+            }
+        }
+        None => {
+            phases.skip("static_variant", "no static target installed");
+            (None, false)
+        }
     };
-    let startup_elapsed = startup_start.elapsed().as_secs_f64();
 
     let artifact_size_kb = env::current_exe()
         .ok()
@@ -566,167 +1923,4710 @@ fn build_startup_metrics() -> Map<String, Value> {
         .map(|m| m.len() as f64 / 1024.0)
         .unwrap_or(0.0);
 
-    let mut map = Map::new();
+    let mut map = Metrics::new();
+    insert_runtime_split(&mut map, total_start.elapsed(), tool_time);
+    map.insert("build_seconds", build_elapsed, "s");
+    map.insert("startup_seconds", startup_elapsed, "s");
+    map.insert("artifact_size_kb", artifact_size_kb, "kb");
+    map.insert(
+        "operation_errors",
+        ((build_code != 0) as u8 + (startup_code != 0) as u8 + static_failed as u8) as f64,
+        "count",
+    );
+    map.insert(
+        "binary_inspection_supported",
+        link_info.is_some() as u8 as f64,
+        "flag",
+    );
+    let info = link_info.unwrap_or(linkage::LinkInfo {
+        statically_linked: false,
+        shared_libraries: 0,
+    });
+    map.insert(
+        "statically_linked",
+        info.statically_linked as u8 as f64,
+        "flag",
+    );
     map.insert(
-        "runtime_seconds".to_string(),
-        metric(total_start.elapsed().as_secs_f64(), "s"),
+        "shared_library_count",
+        info.shared_libraries as f64,
+        "count",
     );
-    map.insert("build_seconds".to_string(), metric(build_elapsed, "s"));
-    map.insert("startup_seconds".to_string(), metric(startup_elapsed, "s"));
-    map.insert("artifact_size_kb".to_string(), metric(artifact_size_kb, "kb"));
     map.insert(
-        "operation_errors".to_string(),
-        metric(((build_code != 0) as u8 + (startup_code != 0) as u8) as f64, "count"),
+        "static_variant_available",
+        static_triple.is_some() as u8 as f64,
+        "flag",
     );
+    if let Some(seconds) = static_startup_seconds {
+        map.insert("static_startup_seconds", seconds, "s");
+    }
+    insert_capture_metrics(&mut map, "build_", build_output.capture);
+    insert_child_peak_rss(&mut map, peak_rss);
+    counters.insert_metrics(&mut map);
     map
 }
 
-fn run() -> Vec<Value> {
-    let run_id = format!(
-        "rust-{}-{}",
-        std::process::id(),
-        Utc::now().timestamp_millis()
-    );
-    let base_url =
-        env::var("BENCHMARK_HTTP_BASE_URL").unwrap_or_else(|_| "http://127.0.0.1:8000".to_string());
-    let requests = env_usize("BENCHMARK_HTTP_REQUESTS", 400);
-    let rows = env_usize("BENCHMARK_HTTP_ROWS", 1000);
-    let concurrency = env_usize("BENCHMARK_HTTP_CONCURRENCY", 16);
-    let iterations = env_usize("BENCHMARK_TEST_REPEAT", 3);
-    let dataset = resolve_dataset();
+/// The process's resident high-water mark after a benchmark, and how far
+/// the benchmark raised it; `-1` where the platform cannot tell, so
+/// unsupported never reads as zero.
+fn insert_peak_rss_metrics(map: &mut Metrics, before: Option<u64>, after: Option<u64>) {
+    let peak = after.map_or(-1.0, |kb| kb as f64);
+    let delta = before
+        .zip(after)
+        .map_or(-1.0, |(before, after)| after.saturating_sub(before) as f64);
+    map.insert("peak_rss_kb", peak, "kb");
+    map.insert("peak_rss_delta_kb", delta, "kb");
+}
 
-    let mut records = Vec::new();
+/// CPU time the process spent during a benchmark, across all its threads,
+/// and that time over the wall time it took; above 1.0 means threads ran in
+/// parallel, well below it means waiting. `-1` where the platform cannot
+/// tell. Subprocess CPU is not included; see `BENCHMARK_CHILD_COUNTERS`.
+fn insert_cpu_time_metrics(
+    map: &mut Metrics,
+    before: Option<f64>,
+    after: Option<f64>,
+    wall: Duration,
+) {
+    let cpu = before.zip(after).map(|(before, after)| (after - before).max(0.0));
+    let wall = wall.as_secs_f64();
+    let utilization = cpu.filter(|_| wall > 0.0).map_or(-1.0, |cpu| cpu / wall);
+    map.insert("cpu_time_seconds", cpu.unwrap_or(-1.0), "s");
+    map.insert("cpu_utilization", utilization, "ratio");
+}
+
+/// Keeps the largest child resident set seen so far in `peak`.
+fn fold_child_peak_rss(peak: &mut Option<u64>, usage: Option<ChildUsage>) {
+    if let Some(usage) = usage {
+        *peak = Some(peak.unwrap_or(0).max(usage.max_rss_kb));
+    }
+}
+
+/// `peak_rss_kb` of a subprocess benchmark: its largest child, or `-1`.
+fn insert_child_peak_rss(map: &mut Metrics, peak: Option<u64>) {
+    let peak = peak.map_or(-1.0, |kb| kb as f64);
+    map.insert("peak_rss_kb", peak, "kb");
+}
+
+/// Swap activity while the benchmark ran; any at all is a `swapped`
+/// warning, since the run measured the disk as much as the code.
+fn insert_swap_metrics(
+    map: &mut Metrics,
+    live: &LiveContext,
+    before: Option<SwapCounters>,
+    after: Option<SwapCounters>,
+) {
+    let Some((before, after)) = before.zip(after) else {
+        map.insert("swap_sampling_supported", 0.0, "flag");
+        return;
+    };
+    let delta = before.delta(&after);
+    map.insert("swap_sampling_supported", 1.0, "flag");
+    map.insert("swap_in_pages", delta.swap_in_pages as f64, "count");
+    map.insert("swap_out_pages", delta.swap_out_pages as f64, "count");
+    let swapped = delta.swap_in_pages > 0 || delta.swap_out_pages > 0;
+    // Deprecated: the `swapped` warning replaces this flag; dropped in 0.3.0.
+    map.insert("swapped", swapped as u8 as f64, "flag");
+    if swapped {
+        live.warn(
+            WarningCode::Swapped,
+            format!(
+                "swapped {} page(s) in and {} out",
+                delta.swap_in_pages, delta.swap_out_pages
+            ),
+            json!({"swap_in_pages": delta.swap_in_pages, "swap_out_pages": delta.swap_out_pages}),
+        );
+    }
+}
+
+/// Hugepage-backed anonymous memory once a memory-heavy benchmark is done;
+/// it grows with the run, so it is a high-water mark rather than a delta.
+fn insert_thp_metrics(map: &mut Metrics) {
+    let Some(kb) = platform::anon_huge_pages_kb() else {
+        map.insert("thp_supported", 0.0, "flag");
+        return;
+    };
+    map.insert("thp_supported", 1.0, "flag");
+    map.insert("anon_huge_pages_kb", kb as f64, "kb");
+}
+
+fn estimated_workload_bytes(json_records: usize, etl_rows: usize) -> u64 {
+    // Decoded rows plus the encoded payload held alongside them.
+    let json_bytes = json_records as u64 * 160;
+    let etl_bytes = etl_rows as u64 * 64;
+    json_bytes + etl_bytes
+}
+
+fn warn_on_memory_pressure(mem_available_kb: Option<u64>, workload_bytes: u64) {
+    let Some(kb) = mem_available_kb else {
+        return;
+    };
+    let fraction = env::var("BENCHMARK_MEMORY_WARN_FRACTION")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.5);
+    let available_bytes = kb as f64 * 1024.0;
+    if workload_bytes as f64 > available_bytes * fraction {
+        eprintln!(
+            "warning: configured workloads need ~{:.1} MB, more than {:.0}% of MemAvailable ({:.1} MB); timings may include swapping",
+            workload_bytes as f64 / (1024.0 * 1024.0),
+            fraction * 100.0,
+            available_bytes / (1024.0 * 1024.0)
+        );
+    }
+}
 
+fn monte_carlo_metrics(config: &MonteCarloConfig) -> Metrics {
     let start = Instant::now();
-    let pi = monte_carlo_pi(200_000);
+    let pi = monte_carlo_pi(config.samples);
     let elapsed = start.elapsed().as_secs_f64();
-    let mut cpu_metrics = Map::new();
-    cpu_metrics.insert("runtime_seconds".to_string(), metric(elapsed, "s"));
-    cpu_metrics.insert("pi_estimate".to_string(), metric(pi, "ratio"));
-    records.push(make_record(
-        "cpu_monte_carlo_pi",
-        "performance",
-        cpu_metrics,
-        &run_id,
-    ));
+    let start = Instant::now();
+    let vectorized_pi = monte_carlo_pi_vectorized(config.samples);
+    let vectorized_elapsed = start.elapsed().as_secs_f64();
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("pi_estimate", pi, "ratio");
+    map.insert("absolute_error", (pi - std::f64::consts::PI).abs(), "ratio");
+    map.insert(
+        "samples_per_second",
+        config.samples as f64 / elapsed.max(1e-9),
+        "samples/s",
+    );
+    map.insert("scalar_runtime_seconds", elapsed, "s");
+    map.insert("vectorized_runtime_seconds", vectorized_elapsed, "s");
+    map.insert(
+        "simd_speedup",
+        elapsed / vectorized_elapsed.max(1e-9),
+        "ratio",
+    );
+    map.insert(
+        "portable_simd_enabled",
+        cfg!(feature = "simd") as u8 as f64,
+        "flag",
+    );
+    map.insert(
+        "vectorized_mismatch",
+        (pi != vectorized_pi) as u8 as f64,
+        "flag",
+    );
+    map
+}
 
+/// `cpu_monte_carlo_pi_parallel`: the samples split over one stream per
+/// thread, timed in parallel and then one stream after another on this
+/// thread for `parallel_speedup`.
+fn monte_carlo_parallel_metrics(config: &MonteCarloConfig, threads: usize) -> Metrics {
     let start = Instant::now();
-    let checksum = json_parse_transform(20_000) as f64;
+    let pi = monte_carlo_pi_streams(config.samples, threads, true);
     let elapsed = start.elapsed().as_secs_f64();
-    let mut json_metrics = Map::new();
-    json_metrics.insert("runtime_seconds".to_string(), metric(elapsed, "s"));
-    json_metrics.insert("checksum".to_string(), metric(checksum, "count"));
-    records.push(make_record(
-        "string_json_parse_transform",
-        "performance",
-        json_metrics,
-        &run_id,
-    ));
+    let start = Instant::now();
+    let serial_pi = monte_carlo_pi_streams(config.samples, threads, false);
+    let serial_elapsed = start.elapsed().as_secs_f64();
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("parallel_runtime_seconds", elapsed, "s");
+    map.insert("single_thread_runtime_seconds", serial_elapsed, "s");
+    map.insert(
+        "parallel_speedup",
+        serial_elapsed / elapsed.max(1e-9),
+        "ratio",
+    );
+    map.insert("threads", threads as f64, "count");
+    map.insert("pi_estimate", pi, "ratio");
+    map.insert("absolute_error", (pi - std::f64::consts::PI).abs(), "ratio");
+    map.insert(
+        "samples_per_second",
+        config.samples as f64 / elapsed.max(1e-9),
+        "samples/s",
+    );
+    map.insert("parallel_mismatch", (pi != serial_pi) as u8 as f64, "flag");
+    map
+}
+
+/// Swap counters are only read when the probe precondition holds.
+fn sample_swap(sampling: bool) -> Option<SwapCounters> {
+    sampling.then(platform::swap_counters).flatten()
+}
 
+fn json_transform_metrics(
+    config: &JsonTransformConfig,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+) -> Metrics {
+    let sampling = checks.check(Precondition::Platform(Probe::SwapCounters));
+    let swap_before = sample_swap(sampling);
     let start = Instant::now();
-    let (completed, http_checksum, http_errors) =
-        io_http_benchmark(&base_url, requests, rows, concurrency);
+    let checksum = json_parse_transform(config.records) as f64;
     let elapsed = start.elapsed().as_secs_f64();
-    let mut io_metrics = Map::new();
-    io_metrics.insert("runtime_seconds".to_string(), metric(elapsed, "s"));
-    io_metrics.insert(
-        "requests_completed".to_string(),
-        metric(completed as f64, "count"),
-    );
-    io_metrics.insert("checksum".to_string(), metric(http_checksum as f64, "count"));
-    io_metrics.insert("request_errors".to_string(), metric(http_errors as f64, "count"));
-    records.push(make_record(
-        "io_concurrent_http_client",
-        "performance",
-        io_metrics,
-        &run_id,
-    ));
+    let swap_after = sample_swap(sampling);
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("checksum", checksum, "count");
+    insert_swap_metrics(&mut map, live, swap_before, swap_after);
+    insert_thp_metrics(&mut map);
+    map
+}
 
+/// The concurrent client's metrics, with each request sent by `send`.
+fn io_http_metrics(
+    config: &HttpClientConfig,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+    send: &SendItem,
+) -> Metrics {
+    let on_progress = |completed: usize| {
+        live.progress("requests_completed", completed as f64);
+        live.progress("requests_per_second", completed as f64 / live.elapsed_s().max(1e-9));
+    };
+    let hooks = WorkerHooks {
+        rng_for: &|worker| live.rng_for(worker),
+        on_progress: &on_progress,
+        limits: config.request_limits(),
+        send,
+    };
+    let (base_url, _server) = http_base_url(&config.base_url, config.server, live);
+    let usable = checks.check(Precondition::BaseUrl(&base_url));
+    // A server still starting up would fail the first requests for nothing,
+    // so the wait comes first and stays out of the timing.
+    let ready_start = Instant::now();
+    let ready = !usable
+        || parse_base_url(&base_url).is_ok_and(|target| {
+            let timeout = hooks.limits.timeout;
+            wait_until_ready(&target, config.ready_timeout(), timeout)
+        });
+    let ready_wait = ready_start.elapsed().as_secs_f64();
+    if !ready {
+        eprintln!(
+            "warning: {} did not answer within {}s; no requests sent",
+            base_url, config.ready_timeout_seconds
+        );
+    }
+    let fixture = parse_base_url(&base_url)
+        .ok()
+        .filter(|_| usable && ready)
+        .and_then(|target| fixture_probe::probe(&target, RTT_CONNECTS, hooks.limits.timeout));
     let start = Instant::now();
-    let (etl_rows, etl_aggregate, etl_bytes) = etl_benchmark(&dataset);
-    let elapsed = start.elapsed().as_secs_f64();
-    let mut etl_metrics = Map::new();
-    etl_metrics.insert("runtime_seconds".to_string(), metric(elapsed, "s"));
-    etl_metrics.insert(
-        "records_processed".to_string(),
-        metric(etl_rows as f64, "count"),
-    );
-    etl_metrics.insert(
-        "aggregate_value".to_string(),
-        metric(etl_aggregate as f64, "count"),
-    );
-    etl_metrics.insert(
-        "throughput_mb_s".to_string(),
-        metric(
-            ((etl_bytes as f64) / (1024.0 * 1024.0)) / elapsed.max(1e-9),
-            "mb/s",
-        ),
+    let mut stats = if !usable {
+        WorkerStats {
+            errors: 1,
+            ..WorkerStats::default()
+        }
+    } else if ready {
+        io_http_benchmark(
+            &base_url,
+            config.requests,
+            config.rows,
+            config.concurrency,
+            config.connection_mode(),
+            &hooks,
+        )
+    } else {
+        WorkerStats::default()
+    };
+    let span = start.elapsed();
+    let elapsed = span.as_secs_f64();
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("ready_wait_seconds", ready_wait, "s");
+    map.insert("server_unavailable", !ready as u8 as f64, "flag");
+    insert_fixture_metrics(&mut map, live, fixture.as_ref());
+    map.insert("requests_completed", stats.completed as f64, "count");
+    map.insert("checksum", stats.checksum as f64, "count");
+    map.insert("request_errors", stats.errors as f64, "count");
+    map.insert(
+        "requests_per_second",
+        stats.completed as f64 / elapsed.max(1e-9),
+        "req/s",
+    );
+    insert_throughput_metrics(&mut map, &stats, elapsed);
+    insert_request_error_metrics(&mut map, live, &stats);
+    insert_timeline_metrics(&mut map, live, &stats, start, span, config.timeline_buckets);
+    map.insert("http_keep_alive", config.keep_alive as u8 as f64, "flag");
+    map.insert(
+        "connections_opened",
+        stats.connections_opened as f64,
+        "count",
+    );
+    map.insert("reconnects", stats.reconnects as f64, "count");
+    map.insert("skipped_requests", stats.skipped_requests as f64, "count");
+    map.insert(
+        "worker_completion_skew",
+        stats.worker_completion_skew as f64,
+        "count",
     );
-    records.push(make_record(
-        "data_pipeline_etl_minibatch",
-        "performance",
-        etl_metrics,
-        &run_id,
-    ));
-
-    records.push(make_record(
-        "dependency_vulnerability_scan_scorecard",
-        "security",
-        dependency_scan_metrics(),
-        &run_id,
-    ));
-    records.push(make_record(
-        "static_security_lint_benchmark",
-        "security",
-        static_lint_metrics(),
-        &run_id,
-    ));
-    records.push(make_record(
-        "test_robustness_reliability",
-        "quality",
-        test_reliability_metrics(iterations),
-        &run_id,
-    ));
-    records.push(make_record(
-        "build_startup_feedback_loop",
-        "quality",
-        build_startup_metrics(),
-        &run_id,
-    ));
+    insert_transfer_metrics(&mut map, &stats, elapsed);
+    insert_latency_metrics(&mut map, &mut stats);
+    if hooks.limits.pace.is_some() {
+        insert_schedule_metrics(&mut map, config.target_rps, &stats, elapsed);
+    }
+    live.diagnose("status_counts", stats.status_counts());
+    map
+}
 
-    records
+/// Open loop: the rate asked for, the rate that completed, and how late
+/// request starts fell behind their slots (p99 by nearest rank, `-1` when
+/// nothing was sent).
+fn insert_schedule_metrics(map: &mut Metrics, target_rps: f64, stats: &WorkerStats, elapsed: f64) {
+    map.insert("target_rps", target_rps, "req/s");
+    map.insert(
+        "achieved_rps",
+        stats.completed as f64 / elapsed.max(1e-9),
+        "req/s",
+    );
+    let lags = &stats.schedule_lag_us;
+    let p99 = if lags.is_empty() { -1.0 } else { lags.percentile(99.0, Rank::Nearest) / 1000.0 };
+    insert_sampled(map, "schedule_lag_p99_ms", p99, "ms", lags);
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 2 && args[1] == "--noop" {
+fn http_streaming_metrics(
+    config: &HttpStreamingConfig,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+) -> Metrics {
+    let total_start = Instant::now();
+    let mut map = Metrics::new();
+    let (base_url, _server) = http_base_url(&config.base_url, config.server, live);
+    let target = if checks.check(Precondition::BaseUrl(&base_url)) {
+        parse_base_url(&base_url).ok()
+    } else {
+        None
+    };
+    let timeout = config.timeout();
+    let ready = target
+        .as_ref()
+        .is_some_and(|target| wait_until_ready(target, config.ready_timeout(), timeout));
+    let (Some(target), true) = (target, ready) else {
+        map.insert("runtime_seconds", 0.0, "s");
+        map.insert("server_unavailable", 1.0, "flag");
+        map.insert("request_errors", 1.0, "count");
+        return map;
+    };
+    let fixture = fixture_probe::probe(&target, RTT_CONNECTS, timeout);
+    insert_fixture_metrics(&mut map, live, fixture.as_ref());
+
+    let bytes = config.bytes();
+    let chunk_bytes = config.chunk_kb.max(1) * 1024;
+    let mut errors = 0;
+    let mut mismatches = 0;
+    let upload = streaming::upload(&target, bytes, chunk_bytes, timeout);
+    let download = streaming::download(&target, bytes, timeout);
+    for (rate, count, transfer) in [
+        ("upload_mb_s", "bytes_uploaded", upload),
+        ("download_mb_s", "bytes_downloaded", download),
+    ] {
+        match transfer {
+            Ok(transfer) => {
+                mismatches += transfer.mismatch as u32;
+                map.insert(rate, transfer.mb_per_second(), "mb/s");
+                map.insert(count, transfer.bytes as f64, "bytes");
+            }
+            Err(err) => {
+                eprintln!("warning: {IO_HTTP_STREAMING}: {err}");
+                errors += 1;
+            }
+        }
+    }
+    map.insert("runtime_seconds", total_start.elapsed().as_secs_f64(), "s");
+    map.insert("server_unavailable", 0.0, "flag");
+    map.insert("hash_mismatches", mismatches as f64, "count");
+    map.insert("request_errors", errors as f64, "count");
+    map
+}
+
+/// Where the fixture sits, from the probe before timing: the median TCP
+/// connect time, its address's locality (a `diagnostics` label) and, on
+/// Linux, the route's MTU. Nothing when it was not reached.
+fn insert_fixture_metrics(map: &mut Metrics, live: &LiveContext, probe: Option<&FixtureProbe>) {
+    let Some(probe) = probe else {
         return;
+    };
+    map.insert("fixture_rtt_ms", probe.median_rtt_ms(), "ms");
+    live.diagnose("fixture_locality", json!(probe.locality.label()));
+    let supported = probe.mtu_bytes.is_some();
+    map.insert("fixture_mtu_supported", supported as u8 as f64, "flag");
+    if let Some(mtu) = probe.mtu_bytes {
+        map.insert("fixture_mtu_bytes", mtu as f64, "bytes");
+    }
+}
+
+/// `request_errors` split by cause. Their sum falls short of it only when
+/// the target was unusable (a bad base URL) or a worker panicked. The first
+/// message of each category goes to the record's `diagnostics`.
+fn insert_request_error_metrics(map: &mut Metrics, live: &LiveContext, stats: &WorkerStats) {
+    let counts = [
+        ("timeout_errors", stats.timeout_errors),
+        ("certificate_errors", stats.certificate_errors),
+        ("transport_error_count", stats.transport_errors()),
+        ("connect_error_count", stats.connect_errors),
+        ("write_error_count", stats.write_errors),
+        ("read_error_count", stats.read_errors),
+        ("http_error_count", stats.http_errors),
+        ("redirect_count", stats.redirects),
+        ("parse_error_count", stats.parse_errors),
+    ];
+    for (name, count) in counts {
+        map.insert(name, count as f64, "count");
     }
-    if args.len() != 3 || args[1] != "--output" {
-        eprintln!("Usage: rust-benchmarks --output <path>");
-        std::process::exit(2);
+    if !stats.error_samples.is_empty() {
+        live.diagnose("error_samples", json!(stats.error_samples));
     }
+}
 
-    let out = PathBuf::from(&args[2]);
-    let records = run();
-    let payload = serde_json::to_string_pretty(&records).expect("serialize results");
-    fs::write(out, payload).expect("write output");
+/// `max_error_burst`, and in the record's `diagnostics` the per-bucket
+/// counts it is the peak of.
+fn insert_timeline_metrics(
+    map: &mut Metrics,
+    live: &LiveContext,
+    stats: &WorkerStats,
+    start: Instant,
+    span: Duration,
+    buckets: usize,
+) {
+    let timeline = Timeline::bucket(&stats.outcomes, start, span, buckets);
+    map.insert(
+        "max_error_burst",
+        timeline.max_error_burst() as f64,
+        "count",
+    );
+    if let Some(rps) = timeline.steady_state_rps() {
+        map.insert("steady_state_rps", rps, "req/s");
+    }
+    live.diagnose("timeline_bucket_seconds", json!(timeline.bucket_seconds));
+    live.diagnose("timeline_completed", json!(timeline.completed));
+    live.diagnose("timeline_errors", json!(timeline.errors));
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// `throughput_rps` under the Python harness's name, and `error_rate` as
+/// the share of attempted requests that failed (`0` when none were tried).
+fn insert_throughput_metrics(map: &mut Metrics, stats: &WorkerStats, elapsed: f64) {
+    map.insert(
+        "throughput_rps",
+        stats.completed as f64 / elapsed.max(1e-9),
+        "req/s",
+    );
+    let attempted = stats.completed + stats.errors;
+    map.insert(
+        "error_rate",
+        stats.errors as f64 / attempted.max(1) as f64,
+        "ratio",
+    );
+}
 
-    #[test]
-    fn monte_carlo_reasonable() {
-        let estimate = monte_carlo_pi(10_000);
-        assert!(estimate > 3.0 && estimate < 3.3);
+fn etl_metrics(
+    config: &EtlConfig,
+    data_dir: &DataDir,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+) -> Metrics {
+    let resolved = data_dir.resolve(&config.dataset);
+    // A missing dataset is generated by `etl_benchmark`; the check only logs it.
+    checks.check(Precondition::Dataset(&resolved));
+    let dataset = dataset::canonical(&resolved);
+    let sampling = checks.check(Precondition::Platform(Probe::SwapCounters));
+    let swap_before = sample_swap(sampling);
+    let start = Instant::now();
+    let (run, elapsed, faults) = match config.input_mode {
+        InputMode::Gzip => {
+            let run = etl_benchmark(&dataset, config, data_dir.dataset_cache(), live);
+            (run, start.elapsed(), None)
+        }
+        InputMode::Buffered | InputMode::Mmap => {
+            etl_uncompressed(&dataset, config, data_dir, live)
+        }
+    };
+    let elapsed = elapsed.as_secs_f64();
+    // Tracked by its data dir path, which `--clean` can vouch for.
+    if run.generation.generated {
+        data_dir.track(&resolved);
+    }
+    let swap_after = sample_swap(sampling);
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("records_processed", run.rows as f64, "count");
+    map.insert("aggregate_value", run.aggregate as f64, "count");
+    map.insert(
+        "throughput_mb_s",
+        ((run.size_bytes as f64) / (1024.0 * 1024.0)) / elapsed.max(1e-9),
+        "mb/s",
+    );
+    map.insert("io_errors", run.io_errors as f64, "count");
+    map.insert("oversized_lines", run.oversized_lines as f64, "count");
+    if let Some(faults) = faults {
+        map.insert("page_faults_major", faults.major as f64, "count");
+        map.insert("page_faults_minor", faults.minor as f64, "count");
+    }
+    insert_dataset_generation_metrics(&mut map, run.generation);
+    if config.buffer_sweep {
+        insert_buffer_sweep_metrics(&mut map, &run, &etl_buffer_sweep(&dataset, config));
+    }
+    let [read, parse, aggregate] = run.phase_pct;
+    map.insert("phase_read_pct", read, "percent");
+    map.insert("phase_parse_pct", parse, "percent");
+    map.insert("phase_aggregate_pct", aggregate, "percent");
+    map.insert("phase_sampled_rows", run.phase_sampled_rows as f64, "count");
+    insert_swap_metrics(&mut map, live, swap_before, swap_after);
+    let comparison = etl_parse_comparison(&dataset, config);
+    map.insert(
+        "madvise_hugepage",
+        config.madvise_hugepage as u8 as f64,
+        "flag",
+    );
+    if config.madvise_hugepage {
+        let applied = comparison.as_ref().is_some_and(|c| c.hugepage_advice_applied);
+        map.insert("hugepage_advice_applied", applied as u8 as f64, "flag");
     }
+    insert_etl_parse_metrics(&mut map, comparison);
+    insert_thp_metrics(&mut map);
+    map
+}
 
-    #[test]
-    fn parse_checksum() {
-        let checksum = json_parse_transform(100);
-        let expected: u64 = (0..100).map(|i| (i % 17) as u64).sum();
-        assert_eq!(checksum, expected);
+/// Spawn, ping-pong and fan-in throughput of the tokio runtime. A runtime
+/// that fails to build does no work, so every check counts all of it as
+/// missing.
+fn async_tasks_metrics(config: &AsyncTasksConfig) -> Metrics {
+    let worker_threads = config.worker_threads();
+    let start = Instant::now();
+    let run = async_tasks::run(config, worker_threads).unwrap_or_else(|err| {
+        eprintln!("warning: {CONCURRENCY_ASYNC_TASKS}: {err}");
+        AsyncTasksRun {
+            echo_errors: config.round_trips,
+            sequence_errors: config.producers * config.messages_per_producer,
+            ..AsyncTasksRun::default()
+        }
+    });
+    let mut map = Metrics::new();
+    map.insert("runtime_seconds", start.elapsed().as_secs_f64(), "s");
+    map.insert("worker_threads", worker_threads as f64, "count");
+    map.insert(
+        "tasks_per_second",
+        run.tasks_observed as f64 / run.spawn_seconds.max(1e-9),
+        "tasks/s",
+    );
+    map.insert(
+        "tasks_missing",
+        config.tasks.saturating_sub(run.tasks_observed) as f64,
+        "count",
+    );
+    let round_trips = &run.round_trip_us;
+    let (mean, p99) = if round_trips.is_empty() {
+        (-1.0, -1.0)
+    } else {
+        (round_trips.mean(), round_trips.percentile(99.0, Rank::Nearest))
+    };
+    map.insert("round_trip_us", mean, "us");
+    insert_sampled(&mut map, "round_trip_p99_us", p99, "us", round_trips);
+    map.insert("echo_errors", run.echo_errors as f64, "count");
+    map.insert(
+        "messages_per_second",
+        run.messages_observed as f64 / run.fan_in_seconds.max(1e-9),
+        "msg/s",
+    );
+    map.insert("sequence_errors", run.sequence_errors as f64, "count");
+    map
+}
+
+/// The ETL pass over the same dataset, read through io_uring with
+/// `read_ahead_depth` reads in flight. Skipped where io_uring is missing.
+fn etl_async_metrics(
+    etl: &EtlConfig,
+    config: &EtlAsyncConfig,
+    data_dir: &DataDir,
+    checks: &mut Preconditions,
+    live: &LiveContext,
+) -> Metrics {
+    let resolved = data_dir.resolve(&etl.dataset);
+    checks.check(Precondition::Dataset(&resolved));
+    let dataset = dataset::canonical(&resolved);
+    let supported = checks.check(Precondition::Platform(Probe::IoUring));
+    let mut map = Metrics::new();
+    map.insert("async_io_supported", supported as u8 as f64, "flag");
+    map.insert("read_ahead_depth", config.read_ahead_depth as f64, "count");
+    map.insert("chunk_kb", config.chunk_kb as f64, "kb");
+    if !supported {
+        return map;
+    }
+    let generation = ensure_etl_dataset(&dataset, etl, data_dir.dataset_cache());
+    if generation.generated {
+        data_dir.track(&resolved);
+    }
+    insert_dataset_generation_metrics(&mut map, generation);
+
+    let start = Instant::now();
+    let opened = etl_async::open(&dataset, config.read_ahead_depth, config.chunk_kb * 1024);
+    let (mut run, max_in_flight) = match opened {
+        Ok(read_ahead) => etl_read_ahead(read_ahead, etl, live),
+        Err(_) => (
+            EtlRun {
+                io_errors: 1,
+                ..EtlRun::default()
+            },
+            0,
+        ),
+    };
+    let elapsed = start.elapsed().as_secs_f64();
+    run.size_bytes = fs::metadata(&dataset).map(|m| m.len()).unwrap_or(0);
+    map.insert("runtime_seconds", elapsed, "s");
+    map.insert("records_processed", run.rows as f64, "count");
+    map.insert("aggregate_value", run.aggregate as f64, "count");
+    map.insert(
+        "throughput_mb_s",
+        run.size_bytes as f64 / (1024.0 * 1024.0) / elapsed.max(1e-9),
+        "mb/s",
+    );
+    map.insert("io_errors", run.io_errors as f64, "count");
+    map.insert("max_reads_in_flight", max_in_flight as f64, "count");
+    map
+}
+
+type Measure<'a> = Box<dyn Fn(&mut Preconditions, &LiveContext, &mut PhaseLog) -> Metrics + 'a>;
+
+struct Benchmark<'a> {
+    id: &'static str,
+    category: &'static str,
+    measure: Measure<'a>,
+    /// Position and URL within a multi-target run, recorded as `target`.
+    target: Option<(usize, String)>,
+    /// Configured and data-dir-resolved dataset path, recorded as `dataset`.
+    dataset: Option<(String, PathBuf)>,
+    /// The effective config it measures with, recorded as `parameters`.
+    parameters: Map<String, Value>,
+    /// Correctness metric of a benchmark that runs `ctx.repeats` times.
+    repeat_checksum: Option<&'static str>,
+}
+
+impl<'a> Benchmark<'a> {
+    /// `id` must be in `benchmark_ids::REGISTRY`, which supplies the category.
+    fn new(
+        id: &'static str,
+        measure: impl Fn(&mut Preconditions, &LiveContext, &mut PhaseLog) -> Metrics + 'a,
+    ) -> Self {
+        let descriptor = benchmark_ids::descriptor(id).expect("benchmark is registered");
+        Benchmark {
+            id,
+            category: descriptor.category,
+            measure: Box::new(measure),
+            target: None,
+            dataset: None,
+            parameters: Map::new(),
+            repeat_checksum: None,
+        }
+    }
+
+    fn repeated(mut self, checksum: &'static str) -> Self {
+        self.repeat_checksum = Some(checksum);
+        self
+    }
+
+    fn with_target(mut self, index: usize, base_url: &str) -> Self {
+        self.target = Some((index, base_url.to_string()));
+        self
+    }
+
+    fn with_dataset(mut self, configured: &str, data_dir: &DataDir) -> Self {
+        self.dataset = Some((configured.to_string(), data_dir.resolve(configured)));
+        self
+    }
+
+    fn with_parameters(mut self, parameters: Value) -> Self {
+        if let Value::Object(parameters) = parameters {
+            self.parameters = parameters;
+        }
+        self
+    }
+
+    /// One complete run, setup included. Benchmarks that evaluated
+    /// preconditions carry their log as `preconditions`; with live metrics
+    /// on, every record counts the progress samples it published. Dataset
+    /// benchmarks describe the file they read as `dataset`, and every
+    /// benchmark its effective config as `parameters`. A repeated benchmark
+    /// logs preconditions for its first repeat only. A panic fails the phase
+    /// it happened in and leaves only the metrics measured around it; that
+    /// record, like any with a failed phase, carries a `failure` context.
+    /// What the benchmark passed to `LiveContext::diagnose` becomes
+    /// `diagnostics`, and what it passed to `LiveContext::warn`, with the
+    /// replay overrides for this benchmark, `warnings`.
+    fn record(&self, ctx: &RunContext) -> Value {
+        let mut checks = Preconditions::default();
+        let live = LiveContext::new(ctx.live.as_ref(), &ctx.run_id, self.id);
+        let mut phases = PhaseLog::default();
+        let rss_before = platform::peak_rss_kb();
+        let cpu_before = platform::process_cpu_seconds();
+        let started = Instant::now();
+        let measured = panic::catch_unwind(AssertUnwindSafe(|| match self.repeat_checksum {
+            Some(checksum) => ctx.repeats.run(checksum, |first| {
+                let mut later = Preconditions::default();
+                let checks = if first { &mut checks } else { &mut later };
+                (self.measure)(checks, &live, &mut phases)
+            }),
+            None => Repeated {
+                metrics: (self.measure)(&mut checks, &live, &mut phases),
+                ..Repeated::default()
+            },
+        }));
+        let (repeated, panicked) = match measured {
+            Ok(repeated) => (repeated, None),
+            Err(payload) => {
+                let report = PanicReport::from_payload(payload.as_ref());
+                phases.fail_unfinished(format!("panicked: {}", report.message));
+                let mut metrics = Metrics::new();
+                metrics.insert("benchmark_panicked", 1.0, "flag");
+                let repeated = Repeated {
+                    metrics,
+                    ..Repeated::default()
+                };
+                (repeated, Some(report))
+            }
+        };
+        let mut metrics = repeated.metrics;
+        let wall = started.elapsed();
+        insert_cpu_time_metrics(&mut metrics, cpu_before, platform::process_cpu_seconds(), wall);
+        // Subprocess benchmarks report their children's peak instead.
+        if !metrics.contains_key("peak_rss_kb") {
+            insert_peak_rss_metrics(&mut metrics, rss_before, platform::peak_rss_kb());
+        }
+        if live.enabled() {
+            metrics.insert(
+                "progress_samples_emitted",
+                live.samples_emitted() as f64,
+                "count",
+            );
+        }
+        let parameters = self.parameters.clone();
+        let mut record = make_record(self.id, self.category, metrics, parameters, ctx);
+        if !checks.is_empty() {
+            record.preconditions = Some(checks.to_value());
+        }
+        if let Some((index, base_url)) = &self.target {
+            record.target = Some(json!({"index": index, "base_url": base_url}));
+        }
+        if let Some((configured, resolved)) = &self.dataset {
+            record.dataset = Some(dataset::Provenance::of(configured, resolved).to_value());
+        }
+        if let Some(calibration) = &ctx.calibration {
+            record.calibration = Some(calibration.record_block(self.id));
+            record.parameters.extend(calibration.record_parameters());
+        }
+        record.parameters.extend(live.resolved_parameters());
+        record.diagnostics = live.diagnostics();
+        for applied in ctx.replay_overrides.iter().filter(|o| o.benchmark_id == self.id) {
+            let warning = applied.warning();
+            live.warn(warning.code, warning.message, warning.details);
+        }
+        if ctx.clock_suspect {
+            let built = clock::build_time().map(clock::utc_timestamp);
+            live.warn(
+                WarningCode::ClockSuspect,
+                "the system clock was behind this binary's build time at startup",
+                json!({"built_at": built}),
+            );
+        }
+        record.warnings = live.warnings();
+        record.trim = repeated.trim;
+        record.samples = repeated.samples;
+        if !phases.is_empty() {
+            record.phases = Some(phases.to_value());
+        }
+        if let Some(phase) = phases.first_failed() {
+            let context = FailureContext {
+                phase,
+                parameters: &self.parameters,
+                recent_progress: live.recent_samples(),
+                panic: panicked,
+            };
+            record.failure = Some(context.to_value());
+        }
+        record.to_value()
+    }
+}
+
+/// One `io_concurrent_http_client` run per target in `base_url`, in list
+/// order; each carries its own preconditions and error counts. A single
+/// target is not labelled, so its record is unchanged.
+fn http_client_benchmarks<'a>(config: &HttpClientConfig) -> Vec<Benchmark<'a>> {
+    let targets = config.targets();
+    let labelled = targets.len() > 1;
+    let mut registry = Vec::new();
+    for (index, target) in targets.into_iter().enumerate() {
+        // One record per sweep level, each with the level as `concurrency`.
+        for config in config.for_target(target).sweep() {
+            let parameters = json!(config);
+            let measure = move |checks: &mut Preconditions, live: &LiveContext, _: &mut PhaseLog| {
+                io_http_metrics(&config, checks, live, &HttpConnection::get_item)
+            };
+            let benchmark =
+                Benchmark::new(IO_CONCURRENT_HTTP_CLIENT, measure).with_parameters(parameters);
+            registry.push(if labelled {
+                benchmark.with_target(index, target)
+            } else {
+                benchmark
+            });
+        }
+    }
+    registry
+}
+
+/// Metric compared across the targets of a multi-target run: throughput
+/// without the ramp-up and drain, which a short run is dominated by.
+const TARGET_RPS_METRIC: &str = "steady_state_rps";
+
+/// Adds `rps_ratio_to_first_target` to every selected targeted record: its
+/// `steady_state_rps` over that of target 0 for the same benchmark (and
+/// matrix cell and sweep level). Below 1 means slower than the first target.
+fn insert_target_ratios(records: &mut [Value]) {
+    let group = |record: &Value| {
+        (
+            record["benchmark_id"].clone(),
+            record["matrix_cell"]["index"].clone(),
+            record["parameters"]["concurrency"].clone(),
+        )
+    };
+    let rps = |record: &Value| record["metrics"][TARGET_RPS_METRIC]["value"].as_f64();
+    let references: Vec<_> = records
+        .iter()
+        .filter(|record| record["target"]["index"] == 0 && retry::is_selected(record))
+        .filter_map(|record| Some((group(record), rps(record)?)))
+        .collect();
+    for record in records.iter_mut() {
+        if record.get("target").is_none() || !retry::is_selected(record) {
+            continue;
+        }
+        let reference = references.iter().find(|(key, _)| *key == group(record));
+        if let (Some((_, reference)), Some(value)) = (reference, rps(record)) {
+            if *reference > 0.0 {
+                let ratio = Metric::new(value / reference, "ratio");
+                record["metrics"]["rps_ratio_to_first_target"] = ratio.into();
+            }
+        }
+    }
+}
+
+fn benchmarks<'a>(configs: &'a BenchmarkConfigs, data_dir: &'a DataDir) -> Vec<Benchmark<'a>> {
+    let threads = cpus::cpu_count().effective;
+    let mut registry = vec![
+        Benchmark::new(CPU_MONTE_CARLO_PI, |_, _, _| {
+            monte_carlo_metrics(&configs.monte_carlo)
+        }),
+        Benchmark::new(CPU_MONTE_CARLO_PI_PARALLEL, move |_, _, _| {
+            monte_carlo_parallel_metrics(&configs.monte_carlo, threads)
+        })
+        .with_parameters(json!({"samples": configs.monte_carlo.samples, "threads": threads})),
+        Benchmark::new(CPU_SLEEP_PRECISION, |checks, _, _| {
+            sleep_precision_metrics(&configs.sleep_precision, checks)
+        }),
+        Benchmark::new(STRING_JSON_PARSE_TRANSFORM, |checks, live, _| {
+            json_transform_metrics(&configs.json_transform, checks, live)
+        }),
+    ];
+    registry.extend(http_client_benchmarks(&configs.http_client));
+    registry.extend([
+        Benchmark::new(IO_CONCURRENT_HTTP_POST, |checks, live, _| {
+            io_http_metrics(&configs.http_post, checks, live, &post_json_row)
+        }),
+        Benchmark::new(IO_HTTP_CONNECTION_CHURN, |checks, live, _| {
+            http_connection_churn_metrics(&configs.http_churn, checks, live)
+        }),
+        Benchmark::new(IO_HTTP_JITTER_SENSITIVITY, |_, _, _| {
+            jitter_sensitivity_metrics(&configs.http_jitter)
+        }),
+        Benchmark::new(IO_HTTP_STREAMING, |checks, live, _| {
+            http_streaming_metrics(&configs.http_streaming, checks, live)
+        }),
+        Benchmark::new(DATA_PIPELINE_ETL_MINIBATCH, |checks, live, _| {
+            etl_metrics(&configs.etl, data_dir, checks, live)
+        })
+        .with_dataset(&configs.etl.dataset, data_dir)
+        .with_parameters(etl_parameters(configs)),
+    ]);
+    if configs.etl_async.enabled {
+        let etl_async = Benchmark::new(DATA_PIPELINE_ETL_ASYNC, |checks, live, _| {
+            etl_async_metrics(&configs.etl, &configs.etl_async, data_dir, checks, live)
+        });
+        registry.push(etl_async.with_dataset(&configs.etl.dataset, data_dir));
+    }
+    if async_tasks::supported() {
+        registry.push(Benchmark::new(CONCURRENCY_ASYNC_TASKS, |_, _, _| {
+            async_tasks_metrics(&configs.async_tasks)
+        }));
+    }
+    registry.extend([
+        Benchmark::new(DEPENDENCY_VULNERABILITY_SCAN_SCORECARD, |checks, live, phases| {
+            dependency_scan_metrics(&configs.dependency_scan, checks, live, phases, &SystemRunner)
+        }),
+        Benchmark::new(STATIC_SECURITY_LINT_BENCHMARK, |checks, _, phases| {
+            static_lint_metrics(&configs.static_lint, data_dir, checks, phases, &SystemRunner)
+        }),
+        Benchmark::new(TEST_ROBUSTNESS_RELIABILITY, |_, live, _| {
+            test_reliability_metrics(&SystemRunner, configs.test_reliability.iterations, live)
+        }),
+        Benchmark::new(BUILD_STARTUP_FEEDBACK_LOOP, |_, _, phases| {
+            build_startup_metrics(&SystemRunner, phases)
+        }),
+    ]);
+    registry
+        .into_iter()
+        .map(|benchmark| {
+            if benchmark.parameters.is_empty() {
+                let parameters = configs.params_for(benchmark.id);
+                benchmark.with_parameters(parameters)
+            } else {
+                benchmark
+            }
+        })
+        .map(|benchmark| {
+            // The benchmarks with a deterministic correctness metric are
+            // the timed kernels compared against Python; they repeat.
+            let checksum = CORRECTNESS_METRICS.iter().find(|(id, _)| *id == benchmark.id);
+            match checksum {
+                Some((_, checksum)) => benchmark.repeated(checksum),
+                None => benchmark,
+            }
+        })
+        .collect()
+}
+
+/// The ETL config as `parameters`, plus the buffer sizes it reads with:
+/// `io_buffer_kb` is unset under the library defaults, which still differ
+/// between the two buffers.
+fn etl_parameters(configs: &BenchmarkConfigs) -> Value {
+    let mut parameters = configs.params_for(DATA_PIPELINE_ETL_MINIBATCH);
+    let buffers = IoBuffers::from_kb(configs.etl.io_buffer_kb);
+    parameters["read_buffer_kb"] = json!(buffers.read_kb);
+    parameters["gzip_buffer_kb"] = json!(buffers.gzip_kb);
+    parameters
+}
+
+/// `--list`: every registered benchmark with its category and the variables
+/// that influence it. Runs nothing and reads no files.
+fn benchmark_list() -> Value {
+    let entries: Vec<Value> = benchmark_ids::REGISTRY
+        .iter()
+        .map(|descriptor| {
+            let mut env_vars: Vec<String> =
+                descriptor.env_vars.iter().map(|var| var.to_string()).collect();
+            if config::is_configurable(descriptor.id) {
+                env_vars.push(config::params_env_var(descriptor.id));
+            }
+            json!({
+                "benchmark_id": descriptor.id,
+                "category": descriptor.category,
+                "env_vars": env_vars,
+            })
+        })
+        .collect();
+    Value::Array(entries)
+}
+
+/// The runner's own stage times as a record; `stages` must have been
+/// marked up to the results write.
+fn harness_overhead_record(stages: &StageClock, ctx: &RunContext) -> Value {
+    make_record(HARNESS_OVERHEAD, "quality", stages.metrics(), Map::new(), ctx).to_value()
+}
+
+fn run_context(configs: &BenchmarkConfigs, data_dir: DataDir, live: Option<LiveMetrics>) -> RunContext {
+    // Milliseconds since the Unix epoch, which no time zone shifts.
+    let run_id = format!(
+        "rust-{}-{}",
+        std::process::id(),
+        Utc::now().timestamp_millis()
+    );
+    let clock_suspect = clock::clock_suspect(Utc::now(), clock::build_time());
+    if clock_suspect {
+        eprintln!(
+            "warning: the system clock ({}) is behind this binary's build time ({}); \
+             timestamps in this run are suspect",
+            clock::now(),
+            clock::build_time().map(clock::utc_timestamp).unwrap_or_default()
+        );
+    }
+    let mem_available_kb = platform::mem_available_kb();
+    let ctx = RunContext {
+        run_id,
+        environment: environment_block(
+            mem_available_kb,
+            bootstrap::is_bootstrapped(&bootstrap::marker_path(data_dir.cache_dir())),
+        ),
+        harness_fingerprint: fingerprint::harness_fingerprint(),
+        data_dir,
+        live,
+        calibration: None,
+        repeats: Repeats::from_env(),
+        namespace: None,
+        clock_suspect,
+        replay_overrides: Vec::new(),
+    };
+    warn_on_memory_pressure(
+        mem_available_kb,
+        estimated_workload_bytes(configs.json_transform.records, configs.etl.rows),
+    );
+    ctx
+}
+
+/// Runs the benchmarks in `selection`, or all of them when it is empty.
+/// `on_records` sees each benchmark's attempts as soon as they finish.
+fn run(
+    configs: &BenchmarkConfigs,
+    selection: &[&str],
+    ctx: &RunContext,
+    retry: &RetryPolicy,
+    on_records: &mut dyn FnMut(&[Value]),
+) -> Vec<Value> {
+    let mut records = Vec::new();
+    let registry = benchmarks(configs, &ctx.data_dir);
+    for benchmark in registry
+        .iter()
+        .filter(|benchmark| benchmark_ids::is_selected(selection, benchmark.id))
+    {
+        let attempts = retry.run(|| benchmark.record(ctx));
+        if attempts.len() > 1 {
+            eprintln!("{}: retried {} time(s)", benchmark.id, attempts.len() - 1);
+        }
+        on_records(&attempts);
+        records.extend(attempts);
+    }
+    insert_target_ratios(&mut records);
+    records
+}
+
+/// One run per cell, each with that cell's overrides applied and its
+/// coordinates attached as `matrix_cell`.
+fn run_matrix(
+    configs: &BenchmarkConfigs,
+    cells: &[Cell],
+    ctx: &RunContext,
+    retry: &RetryPolicy,
+    on_records: &mut dyn FnMut(&[Value]),
+) -> Vec<Value> {
+    let mut records = Vec::new();
+    for cell in cells {
+        // Validated by `load_matrix` before anything ran.
+        let Ok(cell_configs) = configs.with_overrides(&cell.benchmark_id, &cell.params) else {
+            continue;
+        };
+        eprintln!(
+            "matrix cell {} ({}/{}): {} {}",
+            cell.index,
+            cell.index + 1,
+            cells.len(),
+            cell.benchmark_id,
+            Value::Object(cell.params.clone())
+        );
+        // More than one for a multi-target benchmark.
+        let registry = benchmarks(&cell_configs, &ctx.data_dir);
+        for benchmark in registry.iter().filter(|benchmark| benchmark.id == cell.benchmark_id) {
+            let attempts = retry.run(|| {
+                let mut record = benchmark.record(ctx);
+                record["matrix_cell"] = cell.to_value();
+                record
+            });
+            if attempts.len() > 1 {
+                eprintln!("{}: retried {} time(s)", benchmark.id, attempts.len() - 1);
+            }
+            on_records(&attempts);
+            records.extend(attempts);
+        }
+    }
+    insert_target_ratios(&mut records);
+    records
+}
+
+/// Expands `--matrix` and validates every cell's overrides, so a bad cell
+/// fails before anything runs.
+fn load_matrix(path: &Path, configs: &BenchmarkConfigs) -> Result<(Matrix, Vec<Cell>), String> {
+    let matrix = matrix::load(path)?;
+    for benchmark_id in matrix.benchmarks.keys() {
+        benchmark_ids::check(benchmark_id).map_err(|err| format!("matrix: {err}"))?;
+    }
+    let cells = matrix::expand(&matrix)?;
+    for cell in &cells {
+        configs
+            .with_overrides(&cell.benchmark_id, &cell.params)
+            .map_err(|err| format!("matrix cell {}: {err}", cell.index))?;
+    }
+    Ok((matrix, cells))
+}
+
+/// The records of an earlier run at `output`, for runtime estimates; empty
+/// when there is none.
+fn previous_results(output: &Path) -> Vec<Value> {
+    fs::read_to_string(output)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// `--interactive`: replaces `options.benchmarks` and `options.auto_preset`
+/// with what the user picked. `false` when the picker did not run because
+/// stdout or stdin is not a terminal. Quitting the picker exits.
+fn pick_interactively(
+    options: &mut CliOptions,
+    configs: &BenchmarkConfigs,
+    data_dir: &DataDir,
+) -> bool {
+    if !interactive::available() {
+        eprintln!("--interactive: not a terminal; running the usual way");
+        return false;
+    }
+    let mut choices: Vec<(&'static str, &'static str)> = Vec::new();
+    for benchmark in benchmarks(configs, data_dir) {
+        if !choices.iter().any(|(id, _)| *id == benchmark.id) {
+            choices.push((benchmark.id, benchmark.category));
+        }
+    }
+    let previous = options.output.as_deref().map(previous_results).unwrap_or_default();
+    let picker =
+        interactive::Picker::new(&choices, &options.benchmarks, options.auto_preset, &previous);
+    match interactive::pick(picker) {
+        Ok(interactive::Outcome::Run(selection)) => {
+            apply_selection(options, selection);
+            true
+        }
+        Ok(interactive::Outcome::Cancelled) => {
+            eprintln!("--interactive: cancelled; nothing ran");
+            std::process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("--interactive: terminal error: {err}; running the usual way");
+            false
+        }
+    }
+}
+
+/// The picked selection as the flags a scripted run would pass.
+fn apply_selection(options: &mut CliOptions, selection: interactive::Selection) {
+    options.benchmarks = selection.benchmarks;
+    options.auto_preset = selection.auto_preset;
+}
+
+/// One progress row per benchmark run about to execute, in run order.
+fn progress_rows(
+    configs: &BenchmarkConfigs,
+    data_dir: &DataDir,
+    selection: &[&str],
+    output: Option<&Path>,
+) -> Vec<(&'static str, Option<f64>)> {
+    let previous = output.map(previous_results).unwrap_or_default();
+    benchmarks(configs, data_dir)
+        .iter()
+        .filter(|benchmark| benchmark_ids::is_selected(selection, benchmark.id))
+        .map(|benchmark| (benchmark.id, matrix::previous_runtime(benchmark.id, &previous)))
+        .collect()
+}
+
+/// Prints the cell count and a runtime estimate from the previous results
+/// at `output`, then asks on stdin unless `--yes` was given. EOF declines.
+fn confirm_matrix(cells: &[Cell], output: &Path, yes: bool) -> bool {
+    let previous = previous_results(output);
+    let (seconds, unknown) = matrix::estimate_seconds(cells, &previous);
+    eprintln!(
+        "matrix: {} cell(s), estimated {seconds:.1}s from previous results in {}",
+        cells.len(),
+        output.display()
+    );
+    if !unknown.is_empty() {
+        eprintln!("matrix: no previous runtime for {}; not in the estimate", unknown.join(", "));
+    }
+    if yes {
+        return true;
+    }
+    eprint!("run {} cell(s)? [y/N] ", cells.len());
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+const USAGE: &str = "Usage: rust-benchmarks [run] [--output <path>] [--print <benchmark_id>.<metric>]...
+                      [--thresholds <rules.json>] [--reset-checksum-store]
+                      [--retry-failed <n>] [--retry-error-threshold <count>]
+                      [--live-metrics <unix:path|file:path>] [--live-metrics-interval <seconds>] [--clean] [--anonymize]
+                      [--no-precheck] [--low-priority] [--on-fingerprint-mismatch <warn|refuse>]
+                      [--matrix <matrix.json> [--yes]]
+                      [--upload <url> [--upload-batch-size <n>] [--upload-flush-interval <seconds>]]
+                      [--benchmarks <id>[,<id>...]] [--preset <default|auto>] [--seed <n>]
+                      [--harness-overhead]
+                      [--interactive] [--backtraces] [--namespace <name>]
+                      [--repeat <n>] [--trim <none|iqr|pct:<n>>]
+                      [--emit-samples [--max-samples <n>]] [--strict-config] [--serve]
+                      [--run-id <id>] [--append [--append-policy <keep|replace|error>]]
+                      [--sink <kind>=<target>]... [--fail-on <degraded|warnings>]...
+                      [--replay <repro.json> [--replay-force]]
+       rust-benchmarks --list
+       rust-benchmarks bootstrap
+       rust-benchmarks calibrate
+       rust-benchmarks clean [--older-than <days>]
+       rust-benchmarks view <results.json>
+       rust-benchmarks compare <results.json> --chart-data <chart.json> [--ignore-namespace]
+       rust-benchmarks bisect <good> <bad> --metric <benchmark_id>.<metric> (--max|--min) <value>";
+const EXIT_USAGE: i32 = 2;
+const EXIT_THRESHOLD_VIOLATION: i32 = 3;
+const EXIT_THRESHOLD_RULE_ERROR: i32 = 4;
+const EXIT_BOOTSTRAP_FAILED: i32 = 5;
+const EXIT_PRECHECK_FAILED: i32 = 6;
+const EXIT_PRINT_UNRESOLVED: i32 = 7;
+const EXIT_DEGRADED: i32 = 8;
+const EXIT_WARNINGS: i32 = 9;
+
+#[derive(Debug, PartialEq)]
+struct CliOptions {
+    /// Where records are written; optional with `--print`.
+    output: Option<PathBuf>,
+    /// Metrics printed to stdout after the run, in this order.
+    print: Vec<MetricPath>,
+    thresholds: Option<PathBuf>,
+    reset_checksum_store: bool,
+    retry: RetryPolicy,
+    live_metrics: Option<live_metrics::Target>,
+    live_metrics_interval: Duration,
+    /// Delete what the run created under the data dir once results are written.
+    clean: bool,
+    /// Run the parameter grid in this file instead of the default suite.
+    matrix: Option<PathBuf>,
+    /// Skip the matrix confirmation prompt.
+    yes: bool,
+    /// Hash hosts and strip home directories from everything written.
+    anonymize: bool,
+    /// Skip the micro run of each performance benchmark before the suite.
+    no_precheck: bool,
+    /// Run niced (and `SCHED_BATCH` on Linux) so the machine stays usable.
+    low_priority: bool,
+    /// Checksum baselines recorded by a different harness build.
+    fingerprint_mismatch: MismatchPolicy,
+    /// Results collector that receives records in batches during the run.
+    upload: Option<String>,
+    upload_config: UploadConfig,
+    /// Benchmarks to run, in registry order; empty runs them all.
+    benchmarks: Vec<&'static str>,
+    /// `--preset auto`: scale workloads by the stored machine calibration.
+    auto_preset: bool,
+    /// Append a `harness_overhead` record timing the runner's own stages.
+    harness_overhead: bool,
+    /// TOML file of benchmark parameters, below env vars in precedence.
+    config: Option<PathBuf>,
+    /// `--fail-on degraded`, or its alias `--fail-on-degraded`: exit with
+    /// `EXIT_DEGRADED` when a record has a failed phase.
+    fail_on_degraded: bool,
+    /// `--fail-on warnings`: exit with `EXIT_WARNINGS` when a record has a
+    /// warning.
+    fail_on_warnings: bool,
+    /// Pick benchmarks and a preset in the terminal, then show progress.
+    interactive: bool,
+    /// Capture a backtrace for every benchmark panic, into its record.
+    backtraces: bool,
+    /// Recorded on every record and the summary, to keep results from
+    /// different harnesses apart in a shared store.
+    namespace: Option<String>,
+    /// Repeats of the repeated benchmarks; overrides `BENCHMARK_REPEATS`.
+    repeat: Option<usize>,
+    /// Outliers dropped from the repeats' runtime summary.
+    trim: Trim,
+    /// Record each repeat's values in `samples`.
+    emit_samples: bool,
+    /// Repeats kept in `samples`, beyond which they are downsampled.
+    max_samples: usize,
+    /// Fail on deprecated env vars and flags instead of warning.
+    strict_config: bool,
+    /// Run the HTTP benchmarks against an embedded server.
+    serve: bool,
+    /// Stamped on every record instead of a generated one, so a re-invoked
+    /// run keeps its id.
+    run_id: Option<String>,
+    /// Add the records to the `--output` file instead of overwriting it.
+    append: bool,
+    /// Existing records with this run's `run_id`, under `--append`.
+    append_policy: AppendPolicy,
+    /// Where else records go as each benchmark finishes.
+    sinks: Vec<SinkSpec>,
+    /// Deprecated flags on the command line, already renamed.
+    deprecations: Vec<Notice>,
+    /// A `repro.json` whose settings the run uses instead of resolving its
+    /// own.
+    replay: Option<PathBuf>,
+    /// Replay even when the harness fingerprint or seed differs.
+    replay_force: bool,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            output: None,
+            print: Vec::new(),
+            thresholds: None,
+            reset_checksum_store: false,
+            retry: RetryPolicy::default(),
+            live_metrics: None,
+            live_metrics_interval: Duration::from_secs(1),
+            clean: false,
+            matrix: None,
+            yes: false,
+            anonymize: false,
+            no_precheck: false,
+            low_priority: false,
+            fingerprint_mismatch: MismatchPolicy::default(),
+            upload: None,
+            upload_config: UploadConfig::default(),
+            benchmarks: Vec::new(),
+            auto_preset: false,
+            harness_overhead: false,
+            config: None,
+            fail_on_degraded: false,
+            fail_on_warnings: false,
+            interactive: false,
+            backtraces: false,
+            namespace: None,
+            repeat: None,
+            trim: Trim::None,
+            emit_samples: false,
+            max_samples: repeats::DEFAULT_MAX_SAMPLES,
+            strict_config: false,
+            serve: false,
+            run_id: None,
+            append: false,
+            append_policy: AppendPolicy::default(),
+            sinks: Vec::new(),
+            deprecations: Vec::new(),
+            replay: None,
+            replay_force: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Cli {
+    Noop,
+    /// Print the benchmark registry as JSON.
+    List,
+    Bootstrap,
+    /// Measure and store the machine score for `--preset auto`.
+    Calibrate,
+    /// Remove data dir files older than the given age.
+    Clean(Duration),
+    /// Serve a results file on a loopback port.
+    View(PathBuf),
+    /// Write chart data for a merged results file.
+    Compare {
+        results: PathBuf,
+        chart_data: PathBuf,
+        ignore_namespace: bool,
+    },
+    /// Find the commit that pushed a metric past a bound.
+    Bisect(BisectArgs),
+    Run(Box<CliOptions>),
+}
+
+/// `clean` without `--older-than`.
+const DEFAULT_CLEAN_AGE_DAYS: f64 = 7.0;
+
+fn parse_clean_args(args: &[String]) -> Result<Cli, String> {
+    let days = match args {
+        [] => DEFAULT_CLEAN_AGE_DAYS,
+        [flag, value] if flag == "--older-than" => parse_flag_value(flag, value)?,
+        _ => return Err("clean takes only --older-than <days>".to_string()),
+    };
+    Duration::try_from_secs_f64(days * 86_400.0)
+        .map(Cli::Clean)
+        .map_err(|_| format!("--older-than: invalid value {days:?}"))
+}
+
+fn parse_compare_args(args: &[String]) -> Result<Cli, String> {
+    let ignore_namespace = args.iter().any(|arg| arg == "--ignore-namespace");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--ignore-namespace").collect();
+    match args[..] {
+        [results, flag, chart] | [flag, chart, results] if flag == "--chart-data" => {
+            Ok(Cli::Compare {
+                results: PathBuf::from(results),
+                chart_data: PathBuf::from(chart),
+                ignore_namespace,
+            })
+        }
+        _ => Err("compare takes one results file and --chart-data <path>".to_string()),
+    }
+}
+
+fn parse_bisect_args(args: &[String]) -> Result<Cli, String> {
+    const SHAPE: &str =
+        "bisect takes <good> <bad> --metric <benchmark_id>.<metric> and --max or --min <value>";
+    let mut commits = Vec::new();
+    let mut metric = None;
+    let mut bound = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            commits.push(arg.clone());
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        match arg.as_str() {
+            "--metric" => {
+                let path = MetricPath::parse(value)
+                    .map_err(|err| err.replacen("--print", "--metric", 1))?;
+                metric = Some(path);
+            }
+            "--max" if bound.is_none() => bound = Some(Bound::Max(parse_flag_value(arg, value)?)),
+            "--min" if bound.is_none() => bound = Some(Bound::Min(parse_flag_value(arg, value)?)),
+            _ => return Err(SHAPE.to_string()),
+        }
+    }
+    match (<[String; 2]>::try_from(commits), metric, bound) {
+        (Ok([good, bad]), Some(metric), Some(bound)) => Ok(Cli::Bisect(BisectArgs {
+            good,
+            bad,
+            metric,
+            bound,
+        })),
+        _ => Err(SHAPE.to_string()),
+    }
+}
+
+const VALUE_FLAGS: [&str; 25] = [
+    "--output",
+    "--print",
+    "--thresholds",
+    "--retry-failed",
+    "--retry-error-threshold",
+    "--live-metrics",
+    "--live-metrics-interval",
+    "--matrix",
+    "--on-fingerprint-mismatch",
+    "--upload",
+    "--upload-batch-size",
+    "--upload-flush-interval",
+    "--benchmarks",
+    "--preset",
+    "--seed",
+    "--config",
+    "--namespace",
+    "--repeat",
+    "--trim",
+    "--max-samples",
+    "--run-id",
+    "--append-policy",
+    "--sink",
+    "--fail-on",
+    "--replay",
+];
+
+fn parse_flag_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: invalid value {value:?}"))
+}
+
+fn parse_args(args: &[String]) -> Result<Cli, String> {
+    let args = match args.first().map(String::as_str) {
+        Some("--noop") if args.len() == 1 => return Ok(Cli::Noop),
+        Some("--list") if args.len() == 1 => return Ok(Cli::List),
+        Some("--list") => return Err("--list takes no other arguments".to_string()),
+        Some("bootstrap") if args.len() == 1 => return Ok(Cli::Bootstrap),
+        Some("bootstrap") => return Err("bootstrap takes no arguments".to_string()),
+        Some("calibrate") if args.len() == 1 => return Ok(Cli::Calibrate),
+        Some("calibrate") => return Err("calibrate takes no arguments".to_string()),
+        Some("clean") => return parse_clean_args(&args[1..]),
+        Some("compare") => return parse_compare_args(&args[1..]),
+        Some("bisect") => return parse_bisect_args(&args[1..]),
+        Some("view") => {
+            return match &args[1..] {
+                [path] => Ok(Cli::View(PathBuf::from(path))),
+                _ => Err("view takes one results file".to_string()),
+            }
+        }
+        Some("run") => &args[1..],
+        _ => args,
+    };
+    let mut options = CliOptions::default();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let flag = match deprecations::renamed_flag(&DEPRECATIONS, flag) {
+            Some((renamed, notice)) => {
+                options.deprecations.push(notice);
+                renamed
+            }
+            None => flag.as_str(),
+        };
+        if flag == "--reset-checksum-store" {
+            options.reset_checksum_store = true;
+            continue;
+        }
+        if flag == "--clean" {
+            options.clean = true;
+            continue;
+        }
+        if flag == "--yes" {
+            options.yes = true;
+            continue;
+        }
+        if flag == "--anonymize" {
+            options.anonymize = true;
+            continue;
+        }
+        if flag == "--no-precheck" {
+            options.no_precheck = true;
+            continue;
+        }
+        if flag == "--low-priority" {
+            options.low_priority = true;
+            continue;
+        }
+        if flag == "--harness-overhead" {
+            options.harness_overhead = true;
+            continue;
+        }
+        if flag == "--fail-on-degraded" {
+            options.fail_on_degraded = true;
+            continue;
+        }
+        if flag == "--interactive" {
+            options.interactive = true;
+            continue;
+        }
+        if flag == "--backtraces" {
+            options.backtraces = true;
+            continue;
+        }
+        if flag == "--emit-samples" {
+            options.emit_samples = true;
+            continue;
+        }
+        if flag == "--strict-config" {
+            options.strict_config = true;
+            continue;
+        }
+        if flag == "--serve" {
+            options.serve = true;
+            continue;
+        }
+        if flag == "--append" {
+            options.append = true;
+            continue;
+        }
+        if flag == "--replay-force" {
+            options.replay_force = true;
+            continue;
+        }
+        if !VALUE_FLAGS.contains(&flag) {
+            return Err(format!("unknown argument: {flag}"));
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{flag} requires a value"))?;
+        match flag {
+            "--output" => options.output = Some(PathBuf::from(value)),
+            "--print" => options.print.push(MetricPath::parse(value)?),
+            "--thresholds" => options.thresholds = Some(PathBuf::from(value)),
+            "--retry-failed" => options.retry.max_retries = parse_flag_value(flag, value)?,
+            "--retry-error-threshold" => options.retry.error_threshold = parse_flag_value(flag, value)?,
+            "--live-metrics" => options.live_metrics = Some(live_metrics::Target::parse(value)?),
+            "--matrix" => options.matrix = Some(PathBuf::from(value)),
+            "--on-fingerprint-mismatch" => {
+                options.fingerprint_mismatch =
+                    MismatchPolicy::parse(value).map_err(|err| format!("{flag}: {err}"))?;
+            }
+            "--upload" => {
+                HttpCollector::new(value)?;
+                options.upload = Some(value.clone());
+            }
+            "--upload-batch-size" => {
+                options.upload_config.batch_size = parse_flag_value(flag, value)?;
+                if options.upload_config.batch_size == 0 {
+                    return Err(format!("{flag}: must be at least 1"));
+                }
+            }
+            "--upload-flush-interval" => {
+                options.upload_config.flush_interval =
+                    Duration::try_from_secs_f64(parse_flag_value(flag, value)?)
+                        .map_err(|_| format!("{flag}: invalid value {value:?}"))?;
+            }
+            "--benchmarks" => options.benchmarks = benchmark_ids::parse_selection(value)?,
+            "--config" => options.config = Some(PathBuf::from(value)),
+            "--namespace" => {
+                record::check_namespace(value)?;
+                options.namespace = Some(value.clone());
+            }
+            "--repeat" | "--max-samples" => {
+                let count: usize = parse_flag_value(flag, value)?;
+                if count == 0 {
+                    return Err(format!("{flag}: must be at least 1"));
+                }
+                if flag == "--repeat" {
+                    options.repeat = Some(count);
+                } else {
+                    options.max_samples = count;
+                }
+            }
+            "--run-id" if value.trim().is_empty() => return Err(format!("{flag}: empty id")),
+            "--run-id" => options.run_id = Some(value.clone()),
+            "--append-policy" => {
+                options.append_policy =
+                    AppendPolicy::parse(value).map_err(|err| format!("{flag}: {err}"))?;
+            }
+            "--sink" => {
+                let spec = SinkSpec::parse(value).map_err(|err| format!("{flag}: {err}"))?;
+                options.sinks.push(spec);
+            }
+            "--trim" => options.trim = Trim::parse(value).map_err(|err| format!("{flag}: {err}"))?,
+            "--replay" => options.replay = Some(PathBuf::from(value)),
+            "--fail-on" if value == "degraded" => options.fail_on_degraded = true,
+            "--fail-on" if value == "warnings" => options.fail_on_warnings = true,
+            "--fail-on" => {
+                return Err(format!("{flag}: expected degraded or warnings, got {value:?}"));
+            }
+            "--preset" if value == "auto" => options.auto_preset = true,
+            "--preset" if value == "default" => options.auto_preset = false,
+            "--preset" => {
+                return Err(format!(
+                    "{flag}: unknown preset {value:?}; valid presets: default, auto"
+                ));
+            }
+            // The seed is fixed at build time; naming it lets a caller insist
+            // on the one it expects instead of trusting the build.
+            "--seed" => {
+                let seed: u64 = parse_flag_value(flag, value)?;
+                if seed != DEFAULT_SEED {
+                    return Err(format!(
+                        "{flag}: this build runs seed {DEFAULT_SEED} only, got {seed}"
+                    ));
+                }
+            }
+            _ => {
+                options.live_metrics_interval = Duration::try_from_secs_f64(parse_flag_value(flag, value)?)
+                    .map_err(|_| format!("{flag}: invalid value {value:?}"))?;
+            }
+        }
+    }
+    if options.output.is_none()
+        && options.print.is_empty()
+        && options.upload.is_none()
+        && options.sinks.is_empty()
+    {
+        return Err("--output, --print, --upload or --sink is required".to_string());
+    }
+    if !options.emit_samples && options.max_samples != repeats::DEFAULT_MAX_SAMPLES {
+        return Err("--max-samples needs --emit-samples".to_string());
+    }
+    if options.upload.is_none() && options.upload_config != UploadConfig::default() {
+        return Err("--upload-batch-size and --upload-flush-interval need --upload".to_string());
+    }
+    if options.append && options.output.is_none() {
+        return Err("--append requires --output".to_string());
+    }
+    if !options.append && options.append_policy != AppendPolicy::default() {
+        return Err("--append-policy needs --append".to_string());
+    }
+    if options.matrix.is_some() && options.output.is_none() {
+        return Err("--matrix requires --output".to_string());
+    }
+    if options.yes && options.matrix.is_none() {
+        return Err("--yes only applies to --matrix".to_string());
+    }
+    if options.matrix.is_some() && options.auto_preset {
+        return Err("--preset does not apply to --matrix; the matrix sets its sizes".to_string());
+    }
+    if options.matrix.is_some() && !options.benchmarks.is_empty() {
+        return Err("--benchmarks does not apply to --matrix; the matrix names its own".to_string());
+    }
+    if options.matrix.is_some() && options.interactive {
+        return Err("--interactive does not apply to --matrix".to_string());
+    }
+    if options.replay_force && options.replay.is_none() {
+        return Err("--replay-force needs --replay".to_string());
+    }
+    if options.replay.is_some() {
+        let conflicting = [
+            ("--matrix", options.matrix.is_some()),
+            ("--preset", options.auto_preset),
+            ("--benchmarks", !options.benchmarks.is_empty()),
+            ("--repeat", options.repeat.is_some()),
+            ("--trim", options.trim != Trim::None),
+            ("--emit-samples", options.emit_samples),
+            ("--namespace", options.namespace.is_some()),
+            ("--interactive", options.interactive),
+        ];
+        if let Some((flag, _)) = conflicting.iter().find(|(_, given)| *given) {
+            return Err(format!("{flag} does not apply to --replay; the recording sets it"));
+        }
+    }
+    if let Some(path) = options
+        .print
+        .iter()
+        .find(|path| !benchmark_ids::is_selected(&options.benchmarks, path.benchmark_id))
+    {
+        return Err(format!("--print: {} is not in --benchmarks", path.benchmark_id));
+    }
+    Ok(Cli::Run(Box::new(options)))
+}
+
+/// Deterministic correctness metrics that must not change for a fixed seed
+/// and workload.
+const CORRECTNESS_METRICS: [(&str, &str); 4] = [
+    (CPU_MONTE_CARLO_PI, "pi_estimate"),
+    (STRING_JSON_PARSE_TRANSFORM, "checksum"),
+    (IO_CONCURRENT_HTTP_CLIENT, "checksum"),
+    (DATA_PIPELINE_ETL_MINIBATCH, "aggregate_value"),
+];
+
+/// A baseline recorded by a different harness build is compared with a
+/// warning, or with `MismatchPolicy::Refuse` replaced by this run's value
+/// uncompared; either way the record gets a `harness_fingerprint_mismatch`
+/// warning.
+fn check_checksum_stability(
+    records: &mut [Value],
+    configs: &BenchmarkConfigs,
+    store: &mut ChecksumStore,
+    policy: MismatchPolicy,
+) {
+    for record in records.iter_mut().filter(|record| retry::is_selected(record)) {
+        let benchmark_id = record["benchmark_id"].as_str().unwrap_or_default().to_string();
+        let run_id = record["run_id"].as_str().unwrap_or_default().to_string();
+        let fingerprint = record["harness_fingerprint"].as_str().unwrap_or_default().to_string();
+        // Transport failures change the HTTP checksum without implying a bug.
+        if record["metrics"]["request_errors"]["value"].as_f64().unwrap_or(0.0) > 0.0 {
+            continue;
+        }
+        let hash = checksum_store::params_hash(&configs.params_for(&benchmark_id));
+        for (id, metric_name) in CORRECTNESS_METRICS {
+            if id != benchmark_id {
+                continue;
+            }
+            let Some(value) = record["metrics"][metric_name]["value"].as_f64() else {
+                continue;
+            };
+            let key = checksum_store::store_key(id, metric_name, &hash, DEFAULT_SEED);
+            let baseline = store.baseline_fingerprint(&key).map(str::to_string);
+            if let Some(baseline) = baseline.filter(|baseline| *baseline != fingerprint) {
+                let skew = format!("{id}.{metric_name} baseline is from harness {baseline}");
+                let details = json!({"baseline": baseline, "current": fingerprint});
+                let code = WarningCode::HarnessFingerprintMismatch;
+                warnings::push(record, Warning::new(code, &skew, details));
+                // Deprecated: the warning replaces this flag; dropped in 0.3.0.
+                if let Some(metrics) = record["metrics"].as_object_mut() {
+                    metrics.insert(
+                        "harness_fingerprint_mismatch".to_string(),
+                        Metric::new(1.0, "flag").into(),
+                    );
+                }
+                if policy == MismatchPolicy::Refuse {
+                    eprintln!("warning: {skew}; not compared, this run is the new baseline");
+                    store.rebaseline(&key, value, &run_id, &fingerprint);
+                    continue;
+                }
+                eprintln!("warning: {skew}, this run is {fingerprint}; comparing anyway");
+            }
+            let Some(metrics) = record["metrics"].as_object_mut() else {
+                continue;
+            };
+            match store.observe(&key, value, &run_id, &fingerprint) {
+                Observation::First | Observation::Stable => {
+                    metrics.insert(
+                        "checksum_stable".to_string(),
+                        Metric::new(1.0, "flag").into(),
+                    );
+                }
+                Observation::Diverged { previous } => {
+                    eprintln!(
+                        "warning: {id}.{metric_name} = {value} differs from previously observed {previous}"
+                    );
+                    metrics.insert(
+                        "checksum_stable".to_string(),
+                        Metric::new(0.0, "flag").into(),
+                    );
+                    metrics.insert(
+                        "checksum_previous_value".to_string(),
+                        Metric::new(previous, "count").into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RunSummary {
+    run_id: String,
+    records: usize,
+    /// Records from an earlier invocation of this run that `--append`
+    /// replaced; absent without `--append`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    records_replaced: Option<usize>,
+    /// Each `--sink` with what it took and its errors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sinks: Vec<SinkStatus>,
+    disk_usage: DiskUsage,
+    /// Winning cell per matrix target; absent outside `--matrix` runs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    matrix_best: Vec<BestCell>,
+    /// What reached the results collector; absent without `--upload`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upload: Option<UploadCounts>,
+    /// The `harness_overhead` record's `total_seconds`; absent without
+    /// `--harness-overhead`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_seconds: Option<f64>,
+    /// Benchmarks whose selected record has a failed phase.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    degraded: Vec<String>,
+    /// Warnings on the selected records, counted by code.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    warnings: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    /// The system clock was behind the build timestamp at startup.
+    clock_suspect: bool,
+    /// Deprecated env vars and flags the run was given.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deprecations: Vec<Notice>,
+    execution_context: ExecutionContext,
+    /// Selected benchmarks this layout cannot run, with why.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<Skipped>,
+    #[serde(flatten)]
+    thresholds: Evaluation,
+}
+
+/// Ids of benchmarks whose selected record has a failed phase, each failure
+/// reported on stderr.
+fn degraded_benchmarks(records: &[Value]) -> Vec<String> {
+    let mut degraded = Vec::new();
+    for record in records.iter().filter(|record| retry::is_selected(record)) {
+        let failures = phases::failures(record);
+        if failures.is_empty() {
+            continue;
+        }
+        let benchmark_id = record["benchmark_id"].as_str().unwrap_or_default();
+        for failure in &failures {
+            eprintln!("degraded: {benchmark_id}: {failure}");
+        }
+        degraded.push(benchmark_id.to_string());
+    }
+    degraded
+}
+
+/// Redacts the user's home directory and the configured HTTP hosts.
+fn anonymizer(configs: &BenchmarkConfigs) -> Anonymizer {
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok();
+    let hosts = configs
+        .http_client
+        .targets()
+        .into_iter()
+        .chain([configs.http_churn.base_url.as_str()])
+        .filter_map(|url| parse_base_url(url).ok())
+        .map(|target| target.host);
+    Anonymizer::new(home, hosts)
+}
+
+fn summary_path(output: &Path) -> PathBuf {
+    output.with_extension("summary.json")
+}
+
+/// The `--replay` file written next to `output`.
+fn repro_path(output: &Path) -> PathBuf {
+    output.with_extension("repro.json")
+}
+
+/// `--replay`: the recorded run's settings, resolved against `current`;
+/// exits with a usage error when this build cannot replay them.
+fn load_replay(
+    path: &Path,
+    current: &BenchmarkConfigs,
+    force: bool,
+) -> (Repro, repro::Resolved) {
+    let loaded = Repro::read(path).and_then(|repro| {
+        let fingerprint = fingerprint::harness_fingerprint();
+        if let Some(mismatch) = repro.check_harness(&fingerprint, DEFAULT_SEED, force)? {
+            eprintln!("warning: {mismatch}");
+        }
+        let resolved = repro.resolve(current)?;
+        Ok((repro, resolved))
+    });
+    let (repro, resolved) = loaded.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(EXIT_USAGE);
+    });
+    let commit = git_sha();
+    if repro.commit_sha != commit {
+        eprintln!("--replay: recorded at commit {}, running at {commit}", repro.commit_sha);
+    }
+    for key in &resolved.ignored {
+        eprintln!("--replay: {key} is set here but the recorded value is used");
+    }
+    for applied in &resolved.overrides {
+        eprintln!("warning: {}", applied.warning().message);
+    }
+    (repro, resolved)
+}
+
+fn load_configs(config: Option<&Path>) -> BenchmarkConfigs {
+    config
+        .map(ConfigFile::read)
+        .unwrap_or_else(|| Ok(ConfigFile::default()))
+        .and_then(|file| BenchmarkConfigs::from_env(&file))
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(EXIT_USAGE);
+        })
+}
+
+/// Exits with a usage error naming the path when the data dir is read-only.
+fn writable_data_dir() -> DataDir {
+    let data_dir = DataDir::from_env(&repo_root());
+    if let Err(err) = data_dir.ensure_writable() {
+        eprintln!("{err}");
+        std::process::exit(EXIT_USAGE);
+    }
+    data_dir
+}
+
+/// Where `calibrate` stores the machine score.
+fn calibration_path(data_dir: &DataDir) -> PathBuf {
+    data_dir.cache_dir().join("calibration.json")
+}
+
+/// Measures and stores a calibration; the data dir tracks the file only
+/// when this call created it.
+fn calibrate(data_dir: &DataDir) -> Result<calibration::Calibration, String> {
+    let scratch = data_dir.cache_dir().join("calibration-scratch");
+    let measured = calibration::measure(&scratch)
+        .map_err(|err| format!("calibrate: {}: {err}", scratch.display()))?;
+    let path = calibration_path(data_dir);
+    let existed = path.exists();
+    calibration::store(&path, &measured)
+        .map_err(|err| format!("calibrate: cannot write {}: {err}", path.display()))?;
+    if !existed {
+        data_dir.track(&path);
+    }
+    if !measured.stable {
+        eprintln!("warning: calibration was noisy; rerun `calibrate` on an idle machine");
+    }
+    Ok(measured)
+}
+
+fn run_calibrate() -> ! {
+    let data_dir = writable_data_dir();
+    let measured = calibrate(&data_dir).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    for kernel in &measured.kernels {
+        println!(
+            "{}: {:.4}s median, score {:.2}",
+            kernel.name, kernel.median_seconds, kernel.score
+        );
+    }
+    println!(
+        "machine score {:.2}, stored in {} until {}",
+        measured.score,
+        calibration_path(&data_dir).display(),
+        clock::utc_timestamp(measured.expires_at())
+    );
+    std::process::exit(0);
+}
+
+/// `configs` scaled by the stored calibration, which is measured first when
+/// it is missing or stale.
+fn auto_preset(
+    configs: &BenchmarkConfigs,
+    data_dir: &DataDir,
+) -> (BenchmarkConfigs, calibration::Applied) {
+    let stored = calibration::load_fresh(&calibration_path(data_dir), Utc::now());
+    let measured = match stored {
+        Some(stored) => stored,
+        None => {
+            eprintln!("--preset auto: no fresh calibration; calibrating");
+            calibrate(data_dir).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                std::process::exit(EXIT_USAGE);
+            })
+        }
+    };
+    let (configs, applied) = calibration::apply(configs, &measured).unwrap_or_else(|err| {
+        eprintln!("--preset auto: {err}");
+        std::process::exit(EXIT_USAGE);
+    });
+    eprintln!("--preset auto: machine score {:.2}", measured.score);
+    (configs, applied)
+}
+
+fn run_bootstrap() -> ! {
+    let data_dir = writable_data_dir();
+    let steps = bootstrap::bootstrap(&load_configs(None), &data_dir);
+    let marker = bootstrap::marker_path(data_dir.cache_dir());
+    let ready = bootstrap::update_marker(&marker, &steps).unwrap_or_else(|err| {
+        eprintln!("warning: cannot update {}: {err}", marker.display());
+        false
+    });
+    bootstrap::print_summary(&steps, ready);
+    std::process::exit(if ready { 0 } else { EXIT_BOOTSTRAP_FAILED });
+}
+
+fn run_clean(max_age: Duration) -> ! {
+    let repo = repo_root();
+    let data_dir = DataDir::from_env(&repo);
+    match data_dir.remove_older_than(max_age, SystemTime::now(), &repo) {
+        Ok(removed) => {
+            println!("removed {} file(s), {} bytes", removed.paths, removed.bytes);
+            std::process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+}
+
+fn run_view(path: &Path) -> ! {
+    let records = viewer::load(path).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(EXIT_USAGE);
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap_or_else(|err| {
+        eprintln!("view: cannot bind a loopback port: {err}");
+        std::process::exit(EXIT_USAGE);
+    });
+    let count = records.as_array().map_or(0, Vec::len);
+    if let Ok(addr) = listener.local_addr() {
+        println!("serving {count} record(s) at http://{addr}/");
+    }
+    eprintln!("press Ctrl-C to stop");
+    viewer::serve(listener, &viewer::Site::new(&records));
+    std::process::exit(0);
+}
+
+/// Benchmarks without a headline metric or a Rust value are listed on
+/// stderr and left out of the chart.
+fn run_compare(results: &Path, chart_data: &Path, ignore_namespace: bool) -> ! {
+    let records = viewer::load(results).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(EXIT_USAGE);
+    });
+    let records = records.as_array().map_or(&[][..], Vec::as_slice);
+    if let (false, Err(err)) = (ignore_namespace, record::shared_namespace(records)) {
+        eprintln!("compare: {err}; pass --ignore-namespace to compare them by benchmark id");
+        std::process::exit(EXIT_USAGE);
+    }
+    let chart = chart_data::chart(records);
+    for skipped in chart["skipped"].as_array().into_iter().flatten() {
+        let id = skipped["benchmark_id"].as_str().unwrap_or("?");
+        eprintln!("compare: skipped {id}: {}", skipped["note"].as_str().unwrap_or(""));
+    }
+    let text = serde_json::to_string_pretty(&chart).unwrap_or_default();
+    if let Err(err) = fs::write(chart_data, text + "\n") {
+        eprintln!("compare: cannot write {}: {err}", chart_data.display());
+        std::process::exit(1);
+    }
+    let count = chart["benchmarks"].as_array().map_or(0, Vec::len);
+    println!("wrote {count} benchmark series to {}", chart_data.display());
+    std::process::exit(0);
+}
+
+/// Prints each measurement as it lands and the report as JSON at the end.
+/// Exits 0 with a culprit, 1 without one.
+fn run_bisect(args: &BisectArgs) -> ! {
+    let data_dir = writable_data_dir();
+    let repo = repo_root();
+    let workspace = bisect::Workspace {
+        repo: &repo,
+        worktree: data_dir.cache_dir().join(format!("bisect-{}", std::process::id())),
+        target_dir: data_dir.cache_dir().join("bisect-target"),
+    };
+    let report = bisect::bisect(&SystemRunner, &workspace, args, &mut |measured| {
+        let value = measured.value.map_or_else(|| "-".to_string(), |value| value.to_string());
+        let verdict = serde_json::to_value(measured.verdict).unwrap_or_default();
+        eprintln!("bisect: {} {value} {}", measured.commit, verdict.as_str().unwrap_or("?"));
+    });
+    let report = report.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    std::process::exit(if report.culprit.is_some() { 0 } else { 1 });
+}
+
+fn main() {
+    let mut stages = StageClock::starting_at(Instant::now());
+    let args: Vec<String> = env::args().skip(1).collect();
+    let cli = match parse_args(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!("{USAGE}");
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    let mut options = match cli {
+        Cli::Noop => return,
+        Cli::List => {
+            println!("{}", serde_json::to_string_pretty(&benchmark_list()).unwrap_or_default());
+            return;
+        }
+        Cli::Bootstrap => run_bootstrap(),
+        Cli::Calibrate => run_calibrate(),
+        Cli::Clean(max_age) => run_clean(max_age),
+        Cli::View(path) => run_view(&path),
+        Cli::Compare {
+            results,
+            chart_data,
+            ignore_namespace,
+        } => run_compare(&results, &chart_data, ignore_namespace),
+        Cli::Bisect(args) => run_bisect(&args),
+        Cli::Run(options) => *options,
+    };
+    let thresholds = match options.thresholds.as_deref().map(thresholds::load_rules) {
+        Some(Ok(thresholds)) => Some(thresholds),
+        Some(Err(err)) => {
+            eprintln!("{err}");
+            std::process::exit(EXIT_USAGE);
+        }
+        None => None,
+    };
+    // Before any thread starts: on Linux, threads inherit the priority of
+    // the thread that spawns them.
+    if options.low_priority {
+        if let Err(err) = platform::lower_priority() {
+            eprintln!("warning: --low-priority: {err}; running at normal priority");
+        }
+    }
+    if options.backtraces {
+        failure::capture_backtraces();
+    }
+    stages.mark(Stage::Startup);
+
+    let data_dir = writable_data_dir();
+    let mut configs = load_configs(options.config.as_deref());
+    let replay = options.replay.as_deref().map(|path| {
+        let (repro, resolved) = load_replay(path, &configs, options.replay_force);
+        configs = resolved.configs.clone();
+        options.benchmarks = resolved.benchmarks.clone();
+        (repro, resolved)
+    });
+    if options.serve {
+        configs = configs.with_embedded_server();
+    }
+    let deprecations = [options.deprecations.as_slice(), &configs.deprecations].concat();
+    if let Err(err) = deprecations::report(&deprecations, options.strict_config) {
+        eprintln!("{err}");
+        std::process::exit(EXIT_USAGE);
+    }
+    let show_progress =
+        options.interactive && pick_interactively(&mut options, &configs, &data_dir);
+    let (configs, calibration) = if options.auto_preset {
+        let (configs, applied) = auto_preset(&configs, &data_dir);
+        (configs, Some(applied))
+    } else {
+        (configs, None)
+    };
+    let output = options.output.as_deref();
+    // Read before anything runs, so a file that cannot be appended to stops
+    // the run and is left as it was.
+    let existing = output
+        .filter(|_| options.append)
+        .map(append::load)
+        .transpose()
+        .unwrap_or_else(|err| {
+            eprintln!("--append: {err}");
+            std::process::exit(EXIT_USAGE);
+        });
+    let mut matrix = options.matrix.as_deref().map(|path| {
+        let (matrix, cells) = load_matrix(path, &configs).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(EXIT_USAGE);
+        });
+        // `parse_args` only accepts `--matrix` together with `--output`.
+        let previous = output.unwrap_or(Path::new(""));
+        if !confirm_matrix(&cells, previous, options.yes) {
+            eprintln!("matrix run not confirmed; pass --yes to skip the prompt");
+            std::process::exit(EXIT_USAGE);
+        }
+        (matrix, cells)
+    });
+    let layout = layout::current();
+    if layout.context != ExecutionContext::Full {
+        eprintln!(
+            "execution context: {} at {} (missing {})",
+            layout.context.name(),
+            layout.root.display(),
+            layout.missing.join(", ")
+        );
+    }
+    let (runnable, skipped) = match &matrix {
+        Some((_, cells)) => {
+            let ids: Vec<&'static str> = benchmark_ids::ALL
+                .into_iter()
+                .filter(|id| cells.iter().any(|cell| cell.benchmark_id == *id))
+                .collect();
+            layout.narrow(&ids)
+        }
+        None => layout.narrow(&options.benchmarks),
+    };
+    for skip in &skipped {
+        eprintln!("skipped {}: {}", skip.benchmark_id, skip.reason);
+    }
+    if !skipped.is_empty() {
+        if runnable.is_empty() {
+            eprintln!("no benchmarks ran: every selected benchmark needs what this layout lacks");
+            std::process::exit(EXIT_USAGE);
+        }
+        match &mut matrix {
+            Some((_, cells)) => cells.retain(|cell| runnable.contains(&cell.benchmark_id.as_str())),
+            None => options.benchmarks = runnable,
+        }
+    }
+
+    let store_path = data_dir.cache_dir().join("checksum_store.json");
+    if options.reset_checksum_store {
+        if let Err(err) = checksum_store::reset(&store_path) {
+            eprintln!("warning: cannot reset {}: {err}", store_path.display());
+        }
+    }
+
+    if !options.no_precheck {
+        let scratch = data_dir.cache_dir().join("precheck");
+        match precheck::run(&configs, &options.benchmarks, &scratch) {
+            Ok(passed) => eprintln!("precheck: {passed} benchmark(s) passed"),
+            Err(failure) => {
+                eprintln!("precheck failed: {}: {}", failure.benchmark_id, failure.error);
+                eprintln!("fix the configuration or pass --no-precheck to run anyway");
+                std::process::exit(EXIT_PRECHECK_FAILED);
+            }
+        }
+    }
+
+    let live = options.live_metrics.as_ref().map(|target| {
+        LiveMetrics::open(target, options.live_metrics_interval).unwrap_or_else(|err| {
+            eprintln!("--live-metrics: cannot open {target:?}: {err}");
+            std::process::exit(EXIT_USAGE);
+        })
+    });
+    let anonymizer = options.anonymize.then(|| anonymizer(&configs));
+    let mut collector = options.upload.as_deref().and_then(|url| HttpCollector::new(url).ok());
+    let spool = data_dir.cache_dir().join("upload-spool");
+    let mut uploads = collector.as_mut().map(|collector| {
+        let mut queue = UploadQueue::new(collector, options.upload_config, &spool);
+        queue.replay();
+        queue
+    });
+    let progress = show_progress.then(|| {
+        let rows = progress_rows(&configs, &data_dir, &options.benchmarks, output);
+        interactive::Progress::start(interactive::ProgressState::new(rows))
+    });
+    let mut sinks = Fanout::open(&options.sinks);
+    stages.mark(Stage::Setup);
+    let mut ctx = run_context(&configs, data_dir.clone(), live);
+    if let Some(run_id) = &options.run_id {
+        ctx.run_id = run_id.clone();
+    }
+    if let (Some(existing), Some(output)) = (&existing, output) {
+        let conflicts = append::conflicts(existing, &ctx.run_id);
+        if conflicts > 0 && options.append_policy == AppendPolicy::Error {
+            eprintln!(
+                "--append: {} already has {conflicts} record(s) with run_id {}",
+                output.display(),
+                ctx.run_id
+            );
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+    ctx.calibration = calibration;
+    ctx.namespace = options.namespace.clone();
+    if let Some(count) = options.repeat {
+        ctx.repeats.count = count;
+    }
+    ctx.repeats.trim = options.trim;
+    ctx.repeats.emit_samples = options.emit_samples.then_some(options.max_samples);
+    if let Some((repro, resolved)) = &replay {
+        ctx.repeats = resolved.repeats;
+        ctx.namespace = repro.namespace.clone();
+        ctx.replay_overrides = resolved.overrides.clone();
+    }
+    sinks.begin_run(&ctx.run_id);
+    let mut on_records = |attempts: &[Value]| {
+        if let Some(progress) = &progress {
+            progress.advance();
+        }
+        if uploads.is_none() && sinks.is_empty() {
+            return;
+        }
+        for record in attempts {
+            let mut record = record.clone();
+            if let Some(anonymizer) = &anonymizer {
+                anonymizer.apply(&mut record);
+                record["environment"]["anonymized"] = json!(true);
+            }
+            sinks.record(&record);
+            if let Some(queue) = uploads.as_mut() {
+                queue.push(record);
+            }
+        }
+    };
+    stages.mark(Stage::Metadata);
+    let mut records = match &matrix {
+        Some((_, cells)) => run_matrix(&configs, cells, &ctx, &options.retry, &mut on_records),
+        None => run(&configs, &options.benchmarks, &ctx, &options.retry, &mut on_records),
+    };
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    stages.mark(Stage::Measurement);
+    if records.is_empty() && !options.benchmarks.is_empty() {
+        eprintln!("no benchmarks ran: --benchmarks selected none this platform runs");
+        std::process::exit(EXIT_USAGE);
+    }
+    let upload = uploads.map(UploadQueue::finish);
+    if let Some(counts) = &upload {
+        eprintln!(
+            "upload: {} record(s) in {} batch(es), {} replayed, {} failed, {} spooled",
+            counts.records_uploaded,
+            counts.batches_uploaded,
+            counts.batches_replayed,
+            counts.batches_failed,
+            counts.batches_spooled
+        );
+    }
+    let mut store = ChecksumStore::load(&store_path);
+    // Store keys hash the suite's params, which matrix cells override.
+    if matrix.is_none() {
+        check_checksum_stability(&mut records, &configs, &mut store, options.fingerprint_mismatch);
+    }
+    let store_existed = store_path.exists();
+    match store.save(&store_path) {
+        Ok(()) if !store_existed => data_dir.track(&store_path),
+        Ok(()) => {}
+        Err(err) => eprintln!("warning: cannot write {}: {err}", store_path.display()),
+    }
+    if let Some(anonymizer) = &anonymizer {
+        for record in &mut records {
+            anonymizer.apply(record);
+            record["environment"]["anonymized"] = json!(true);
+        }
+    }
+    let mut records_replaced = None;
+    let mut merged = existing.map(|existing| {
+        let merged = append::merge(existing, &records, options.append_policy)
+            .unwrap_or_else(|err| {
+                eprintln!("--append: {err}");
+                std::process::exit(EXIT_USAGE);
+            });
+        if merged.replaced > 0 {
+            eprintln!(
+                "--append: replaced {} earlier record(s) with run_id {}",
+                merged.replaced, ctx.run_id
+            );
+        }
+        records_replaced = Some(merged.replaced);
+        merged.records
+    });
+    let mut output_status =
+        output.map(|output| sinks::write_output(output, merged.as_deref().unwrap_or(&records)));
+    stages.mark(Stage::Output);
+    if options.harness_overhead {
+        let mut record = harness_overhead_record(&stages, &ctx);
+        if let Some(anonymizer) = &anonymizer {
+            anonymizer.apply(&mut record);
+            record["environment"]["anonymized"] = json!(true);
+        }
+        sinks.record(&record);
+        if let Some(merged) = merged.as_mut() {
+            merged.push(record.clone());
+        }
+        records.push(record);
+        // The record times the write above, so the file is written once
+        // more to hold it; that second write is not timed.
+        output_status = output
+            .map(|output| sinks::write_output(output, merged.as_deref().unwrap_or(&records)));
+    }
+    let mut sinks = sinks.finish();
+    sinks.extend(output_status);
+    for status in &sinks {
+        match &status.first_error {
+            None => eprintln!("sink {}: {} record(s)", status.sink, status.records),
+            Some(err) => eprintln!(
+                "sink {}: {} record(s), {} error(s), first: {err}",
+                status.sink, status.records, status.errors
+            ),
+        }
+    }
+    // The results file is the run's point; without it the summary would
+    // describe records that are nowhere.
+    if output.is_some() && sinks.last().is_some_and(|status| !status.ok()) {
+        std::process::exit(1);
+    }
+    if options.clean {
+        match data_dir.remove_created() {
+            Ok(removed) => eprintln!("--clean: removed {} path(s), {} bytes", removed.paths, removed.bytes),
+            Err(err) => eprintln!("warning: --clean: {err}"),
+        }
+    }
+
+    let evaluation = thresholds
+        .as_ref()
+        .map(|thresholds| {
+            let selected: Vec<Value> = records
+                .iter()
+                .filter(|record| retry::is_selected(record))
+                .cloned()
+                .collect();
+            thresholds::evaluate(thresholds, &selected)
+        })
+        .unwrap_or_default();
+    for violation in &evaluation.violations {
+        eprintln!(
+            "threshold violation: {} = {} ({} {})",
+            violation.rule, violation.value, violation.bound, violation.limit
+        );
+    }
+    for rule_error in &evaluation.rule_errors {
+        eprintln!("threshold rule error: {rule_error}");
+    }
+    let degraded = degraded_benchmarks(&records);
+    let warnings = warnings::counts(&records);
+    let exit_code = if !evaluation.violations.is_empty() {
+        EXIT_THRESHOLD_VIOLATION
+    } else if !evaluation.rule_errors.is_empty() {
+        EXIT_THRESHOLD_RULE_ERROR
+    } else if options.fail_on_degraded && !degraded.is_empty() {
+        EXIT_DEGRADED
+    } else if options.fail_on_warnings && !warnings.is_empty() {
+        EXIT_WARNINGS
+    } else {
+        0
+    };
+
+    let matrix_best = match &matrix {
+        Some((matrix, _)) => matrix::best_cells(matrix, &records),
+        None => Vec::new(),
+    };
+    for best in &matrix_best {
+        eprintln!(
+            "matrix best: {}.{} ({:?}) = {} at cell {} {}",
+            best.benchmark_id,
+            best.metric,
+            best.goal,
+            best.value,
+            best.cell,
+            Value::Object(best.params.clone())
+        );
+    }
+
+    let summary = RunSummary {
+        run_id: records
+            .first()
+            .and_then(|record| record["run_id"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        records: records.len(),
+        records_replaced,
+        sinks,
+        disk_usage: data_dir.usage(),
+        matrix_best,
+        upload,
+        total_seconds: options.harness_overhead.then(|| stages.total().as_secs_f64()),
+        degraded,
+        warnings,
+        namespace: ctx.namespace.clone(),
+        clock_suspect: ctx.clock_suspect,
+        deprecations,
+        execution_context: layout.context,
+        skipped,
+        thresholds: evaluation,
+    };
+    let mut summary = serde_json::to_value(&summary).expect("serialize summary");
+    if let Some(anonymizer) = &anonymizer {
+        anonymizer.apply(&mut summary);
+    }
+    if let Some(output) = output {
+        let summary_payload = serde_json::to_string_pretty(&summary).expect("serialize summary");
+        fs::write(summary_path(output), summary_payload).expect("write summary");
+    }
+    // Matrix cells override the configs per cell, which one file cannot
+    // replay; the matrix file is the recording.
+    if let (Some(output), Some(_)) = (output, &matrix) {
+        eprintln!(
+            "note: no {} for a --matrix run; rerun the matrix file instead",
+            repro_path(output).display()
+        );
+    } else if let Some(output) = output {
+        let preset = match &replay {
+            Some((repro, _)) => repro.preset.as_deref(),
+            None => ctx.calibration.is_some().then_some("auto"),
+        };
+        let repro = Repro::capture(
+            &configs,
+            &options.benchmarks,
+            preset,
+            &ctx.repeats,
+            ctx.namespace.as_deref(),
+            (&ctx.harness_fingerprint, &git_sha(), DEFAULT_SEED),
+        );
+        let mut repro = serde_json::to_value(&repro).expect("serialize repro");
+        if let Some(anonymizer) = &anonymizer {
+            anonymizer.apply(&mut repro);
+        }
+        let repro_payload = serde_json::to_string_pretty(&repro).expect("serialize repro");
+        fs::write(repro_path(output), repro_payload).expect("write repro");
+    }
+
+    // Stdout carries nothing but these values, so `$(...)` captures them.
+    if !options.print.is_empty() {
+        if let Err(err) = print_metrics::write(&options.print, &records, &mut std::io::stdout()) {
+            eprintln!("{err}");
+            std::process::exit(EXIT_PRINT_UNRESOLVED);
+        }
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command_runner::tests::ScriptedRunner;
+
+    const GET: &SendItem = &HttpConnection::get_item;
+
+    /// Keys every record must carry on every platform; platform-dependent
+    /// values are covered by their `*_supported` flag instead.
+    const DECLARED_METRICS: [(&str, &[&str]); 15] = [
+        (
+            "cpu_monte_carlo_pi",
+            &[
+                "runtime_seconds",
+                "pi_estimate",
+                "absolute_error",
+                "samples_per_second",
+                "scalar_runtime_seconds",
+                "vectorized_runtime_seconds",
+                "simd_speedup",
+                "portable_simd_enabled",
+            ],
+        ),
+        (
+            "cpu_monte_carlo_pi_parallel",
+            &[
+                "runtime_seconds",
+                "parallel_runtime_seconds",
+                "single_thread_runtime_seconds",
+                "parallel_speedup",
+                "threads",
+                "pi_estimate",
+                "absolute_error",
+                "samples_per_second",
+                "parallel_mismatch",
+            ],
+        ),
+        (
+            "cpu_sleep_precision",
+            &[
+                "runtime_seconds",
+                "samples_per_duration",
+                "warmup_discarded",
+                "timer_resolution_supported",
+                "scheduler_policy_supported",
+                "sleep_1ms_mean_overshoot_us",
+                "sleep_1ms_p95_overshoot_us",
+            ],
+        ),
+        (
+            "string_json_parse_transform",
+            &["runtime_seconds", "checksum", "swap_sampling_supported"],
+        ),
+        (
+            "io_concurrent_http_client",
+            &[
+                "runtime_seconds",
+                "requests_completed",
+                "checksum",
+                "request_errors",
+                "requests_per_second",
+                "bytes_sent",
+                "bytes_received",
+                "goodput_mb_s",
+                "max_error_burst",
+                "steady_state_rps",
+            ],
+        ),
+        (
+            "io_concurrent_http_post",
+            &[
+                "runtime_seconds",
+                "requests_completed",
+                "checksum",
+                "request_errors",
+                "requests_per_second",
+                "latency_p50_ms",
+                "latency_p99_ms",
+            ],
+        ),
+        (
+            "io_http_connection_churn",
+            &[
+                "runtime_seconds",
+                "per_request_rps",
+                "persistent_rps",
+                "connection_reuse_speedup",
+                "request_errors",
+            ],
+        ),
+        (
+            "io_http_jitter_sensitivity",
+            &[
+                "runtime_seconds",
+                "low_jitter_rps",
+                "high_jitter_rps",
+                "low_jitter_p99_latency_us",
+                "high_jitter_p99_latency_us",
+                "throughput_degradation_ratio",
+                "p99_inflation_ratio",
+                "request_errors",
+            ],
+        ),
+        (
+            "io_http_streaming",
+            &["runtime_seconds", "server_unavailable", "request_errors"],
+        ),
+        (
+            "data_pipeline_etl_minibatch",
+            &[
+                "runtime_seconds",
+                "records_processed",
+                "aggregate_value",
+                "throughput_mb_s",
+                "io_errors",
+                "oversized_lines",
+                "dataset_lock_wait_seconds",
+                "dataset_lock_timeouts",
+                "phase_read_pct",
+                "phase_parse_pct",
+                "phase_aggregate_pct",
+                "phase_sampled_rows",
+                "swap_sampling_supported",
+                "input_mode_predecompressed",
+                "parse_comparison_errors",
+            ],
+        ),
+        (
+            "concurrency_async_tasks",
+            &[
+                "runtime_seconds",
+                "worker_threads",
+                "tasks_per_second",
+                "tasks_missing",
+                "round_trip_us",
+                "round_trip_p99_us",
+                "echo_errors",
+                "messages_per_second",
+                "sequence_errors",
+            ],
+        ),
+        (
+            "dependency_vulnerability_scan_scorecard",
+            &[
+                "runtime_seconds",
+                "tool_runtime_seconds",
+                "processing_seconds",
+                "vulnerability_findings",
+                "outdated_dependencies",
+                "audit_exit_code",
+                "outdated_exit_code",
+                "tool_available",
+                "vulnerability_scanner_builtin",
+                "vulnerability_scan_skipped",
+                "outdated_scanner_builtin",
+                "scan_errors",
+                "expected_nonzero_exits",
+                "advisory_db_found",
+                "advisory_db_age_days",
+                "locale_suspect",
+                "advisory_db_stale",
+            ],
+        ),
+        (
+            "static_security_lint_benchmark",
+            &[
+                "runtime_seconds",
+                "tool_runtime_seconds",
+                "processing_seconds",
+                "finding_count",
+                "lint_exit_code",
+                "tool_available",
+                "scan_errors",
+                "expected_nonzero_exits",
+                "crates_scanned",
+                "dependency_findings_excluded",
+                "auto_fixable_findings",
+                "fix_verification_enabled",
+                "fix_verified_count",
+            ],
+        ),
+        (
+            "test_robustness_reliability",
+            &[
+                "runtime_seconds",
+                "tool_runtime_seconds",
+                "processing_seconds",
+                "iterations",
+                "failed_iterations",
+                "flaky_rate",
+            ],
+        ),
+        (
+            "build_startup_feedback_loop",
+            &[
+                "runtime_seconds",
+                "tool_runtime_seconds",
+                "processing_seconds",
+                "build_seconds",
+                "startup_seconds",
+                "operation_errors",
+            ],
+        ),
+    ];
+
+    fn smoke_configs(dataset: &Path) -> BenchmarkConfigs {
+        // Nothing listens on a freshly released port, so every request
+        // fails fast.
+        let closed = format!("http://127.0.0.1:{}", fixtures::closed_port());
+        BenchmarkConfigs {
+            monte_carlo: MonteCarloConfig { samples: 1_000 },
+            sleep_precision: SleepPrecisionConfig {
+                durations_ms: vec![1],
+                samples: 3,
+                warmup: 1,
+            },
+            json_transform: JsonTransformConfig { records: 100 },
+            http_client: HttpClientConfig {
+                base_url: closed.clone(),
+                requests: 4,
+                rows: 10,
+                concurrency: 2,
+                ready_timeout_seconds: 0.0,
+                ..HttpClientConfig::default()
+            },
+            http_post: HttpClientConfig {
+                base_url: closed.clone(),
+                requests: 4,
+                rows: 10,
+                concurrency: 2,
+                ready_timeout_seconds: 0.0,
+                ..HttpClientConfig::default()
+            },
+            http_churn: HttpClientConfig {
+                base_url: closed.clone(),
+                requests: 4,
+                rows: 10,
+                concurrency: 2,
+                ready_timeout_seconds: 0.0,
+                ..HttpClientConfig::default()
+            },
+            http_jitter: JitterSensitivityConfig {
+                requests: 20,
+                rows: 10,
+                concurrency: 2,
+                low_jitter: ChaosConfig::default(),
+                high_jitter: ChaosConfig {
+                    latency: sampling::LatencyDistribution::Fixed { ms: 2.0 },
+                    error_rate: 0.0,
+                    ..ChaosConfig::default()
+                },
+            },
+            http_streaming: HttpStreamingConfig {
+                base_url: closed,
+                megabytes: 1,
+                ready_timeout_seconds: 0.0,
+                ..HttpStreamingConfig::default()
+            },
+            etl: EtlConfig {
+                dataset: dataset.to_string_lossy().into_owned(),
+                rows: 50,
+                phase_sample_every: 5,
+                ..EtlConfig::default()
+            },
+            ..BenchmarkConfigs::default()
+        }
+    }
+
+    fn assert_declared_metrics(benchmark_id: &str, metrics: &Metrics) {
+        let (_, declared) = DECLARED_METRICS
+            .iter()
+            .find(|(id, _)| *id == benchmark_id)
+            .unwrap_or_else(|| panic!("{benchmark_id} has no declared metrics"));
+        for key in *declared {
+            assert!(metrics.contains_key(key), "{benchmark_id} is missing {key}");
+        }
+        for (key, metric) in metrics.iter() {
+            assert!(
+                metric.value.is_finite(),
+                "{benchmark_id}.{key} is not numeric"
+            );
+        }
+        // Every sampled metric carries `_n`, and `_rse` exactly when it is
+        // a mean or a median. Only a `-1` (nothing to summarize) has no
+        // samples.
+        let sampled = stats::SAMPLED_METRICS.iter().find(|(id, _)| *id == benchmark_id);
+        for (pattern, statistic) in sampled.map_or(&[][..], |(_, keys)| *keys) {
+            let keys = metrics.iter().map(|(key, _)| key);
+            for key in keys.filter(|key| stats::key_matches(pattern, key)) {
+                let companion = |suffix: &str| metrics.value(&format!("{key}{suffix}"));
+                let count = companion(stats::COUNT_SUFFIX);
+                let empty = metrics[key].value == -1.0;
+                assert!(count > Some(0.0) || empty, "{benchmark_id}.{key} has no sample count");
+                let rse = companion(stats::RSE_SUFFIX);
+                assert_eq!(rse.is_some(), statistic.has_rse(), "{benchmark_id}.{key} rse");
+            }
+        }
+    }
+
+    /// Runs everything that does not need the cargo build lock (held by the
+    /// `cargo test` that is running us); this is the gate for Windows CI.
+    #[test]
+    fn smoke_benchmarks_emit_declared_metrics() {
+        let dir = env::temp_dir().join(format!("smoke-{}", std::process::id()));
+        let configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        let mut checks = Preconditions::default();
+        let live = LiveContext::new(None, "smoke", "smoke");
+        // Scripted, so the scan neither needs cargo-audit installed nor
+        // reaches the network for its advisory database.
+        let report = fixtures::audit_report(1, None);
+        let scanner = ScriptedRunner::default()
+            .on("cargo * --version", 0, "", "")
+            .on("cargo audit --json", 0, &report, "")
+            .on("cargo outdated", 0, r#"{"dependencies": []}"#, "");
+        let results = [
+            ("cpu_monte_carlo_pi", monte_carlo_metrics(&configs.monte_carlo)),
+            (
+                "cpu_sleep_precision",
+                sleep_precision_metrics(&configs.sleep_precision, &mut checks),
+            ),
+            (
+                "string_json_parse_transform",
+                json_transform_metrics(&configs.json_transform, &mut checks, &live),
+            ),
+            (
+                "io_concurrent_http_client",
+                io_http_metrics(&configs.http_client, &mut checks, &live, GET),
+            ),
+            (
+                "io_concurrent_http_post",
+                io_http_metrics(&configs.http_post, &mut checks, &live, &post_json_row),
+            ),
+            (
+                "io_http_connection_churn",
+                http_connection_churn_metrics(&configs.http_churn, &mut checks, &live),
+            ),
+            (
+                "io_http_jitter_sensitivity",
+                jitter_sensitivity_metrics(&configs.http_jitter),
+            ),
+            (
+                "io_http_streaming",
+                http_streaming_metrics(&configs.http_streaming, &mut checks, &live),
+            ),
+            (
+                "data_pipeline_etl_minibatch",
+                etl_metrics(&configs.etl, &DataDir::new(dir.clone()), &mut checks, &live),
+            ),
+            (
+                "dependency_vulnerability_scan_scorecard",
+                dependency_scan_metrics(
+                    &configs.dependency_scan,
+                    &mut checks,
+                    &live,
+                    &mut PhaseLog::default(),
+                    &scanner,
+                ),
+            ),
+        ];
+        for (benchmark_id, metrics) in &results {
+            assert_declared_metrics(benchmark_id, metrics);
+        }
+        assert_eq!(results[7].1["server_unavailable"].value, 1.0);
+        assert_eq!(results[8].1["records_processed"].value, 50.0);
+        let scan = &results[9].1;
+        let seconds = |key: &str| scan[key].value;
+        let split = seconds("tool_runtime_seconds") + seconds("processing_seconds");
+        assert!((split - seconds("runtime_seconds")).abs() < 1e-6, "{split}");
+        assert_eq!(scan["vulnerability_findings"].value, 1.0);
+        assert_eq!(scan["tool_available"].value, 1.0);
+        assert_eq!(results[8].1["phase_sampled_rows"].value, 10.0);
+        let jitter = &results[6].1;
+        assert_eq!(jitter["request_errors"].value, 0.0);
+        assert!(jitter["p99_inflation_ratio"].value > 1.0);
+
+        let log = checks.to_value();
+        let find = |check: &str, name: &str| {
+            log.as_array()
+                .unwrap()
+                .iter()
+                .find(|outcome| outcome["check"] == check && outcome["name"] == name)
+                .cloned()
+                .unwrap_or_else(|| panic!("no {check} check for {name}"))
+        };
+        let dataset = dir.join("etl.jsonl.gz");
+        assert_eq!(find("dataset", &dataset.display().to_string())["decision"], "fallback");
+        assert_eq!(find("base_url", &configs.http_client.base_url)["decision"], "run");
+        find("cargo_subcommand", "audit");
+        find("cargo_subcommand", "outdated");
+        find("platform_probe", "swap_counters");
+        find("platform_probe", "timer_resolution");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn writes_only_inside_the_data_dir() {
+        if !fixtures::permissions_enforced() {
+            return;
+        }
+        let base = env::temp_dir().join(format!("read-only-repo-{}", std::process::id()));
+        let repo = base.join("repo");
+        let data = base.join("data");
+        fs::create_dir_all(&repo).unwrap();
+        let mut permissions = fs::metadata(&repo).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&repo, permissions.clone()).unwrap();
+
+        let data_dir = DataDir::from_vars(&repo, Some(data.display().to_string()), None);
+        data_dir.ensure_writable().unwrap();
+        let etl = EtlConfig {
+            rows: 50,
+            ..EtlConfig::default()
+        };
+        let live = LiveContext::new(None, "run", "data_pipeline_etl_minibatch");
+        let metrics = etl_metrics(&etl, &data_dir, &mut Preconditions::default(), &live);
+        assert_eq!(metrics["records_processed"].value, 50.0);
+        let dataset = data.join("benchmarks/shared/datasets/etl_input.jsonl.gz");
+        assert!(dataset.exists());
+        let usage = data_dir.usage();
+        assert_eq!(usage.files_created, 1);
+        assert_eq!(usage.bytes_written, fs::metadata(&dataset).unwrap().len());
+
+        let store_path = data_dir.cache_dir().join("checksum_store.json");
+        let mut store = ChecksumStore::load(&store_path);
+        store.observe("key", 1.0, "run", "harness");
+        store.save(&store_path).unwrap();
+        let marker = bootstrap::marker_path(data_dir.cache_dir());
+        assert!(bootstrap::update_marker(&marker, &[]).unwrap());
+        assert!(marker.starts_with(&data));
+
+        assert_eq!(fs::read_dir(&repo).unwrap().count(), 0);
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&repo, permissions).unwrap();
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[test]
+    fn a_second_worktree_takes_the_etl_dataset_from_the_cache() {
+        let base = env::temp_dir().join(format!("dataset-cache-run-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let cache = DatasetCache::new(base.join("cache"), 64 * 1024 * 1024);
+        let etl = EtlConfig {
+            rows: 50,
+            ..EtlConfig::default()
+        };
+        let live = LiveContext::new(None, "run", "data_pipeline_etl_minibatch");
+        let run_in = |worktree: &str| {
+            let root = base.join(worktree);
+            let data_dir = DataDir::new(root).with_dataset_cache(Some(cache.clone()));
+            etl_metrics(&etl, &data_dir, &mut Preconditions::default(), &live)
+        };
+        let first = run_in("a");
+        assert_eq!(first["dataset_cache_hit"].value, 0.0);
+        let second = run_in("b");
+        assert_eq!(second["dataset_cache_hit"].value, 1.0);
+        assert_eq!(second["aggregate_value"], first["aggregate_value"]);
+        // Already in place: nothing was generated or fetched.
+        assert_eq!(run_in("b")["dataset_cache_hit"].value, 0.0);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn etl_records_carry_dataset_provenance() {
+        let base = env::temp_dir().join(format!("dataset-provenance-{}", std::process::id()));
+        let real = base.join("real");
+        fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, base.join("link")).unwrap();
+        let mut configs = smoke_configs(Path::new("link/etl.jsonl.gz"));
+        configs.etl.rows = 50;
+        let data_dir = DataDir::new(base.clone());
+        let ctx = RunContext {
+            run_id: "provenance".to_string(),
+            environment: environment_block(None, false),
+            harness_fingerprint: fingerprint::harness_fingerprint(),
+            data_dir: data_dir.clone(),
+            live: None,
+            calibration: None,
+            repeats: Repeats::default(),
+            namespace: None,
+            clock_suspect: false,
+            replay_overrides: Vec::new(),
+        };
+        let registry = benchmarks(&configs, &data_dir);
+        let etl = registry.iter().find(|b| b.id == DATA_PIPELINE_ETL_MINIBATCH).unwrap();
+        let record = etl.record(&ctx);
+
+        let generated = fs::canonicalize(real.join("etl.jsonl.gz")).unwrap();
+        assert_eq!(record["dataset"]["configured_path"], "link/etl.jsonl.gz");
+        assert_eq!(record["dataset"]["canonical_path"], generated.display().to_string());
+        assert!(record["dataset"]["inode"].is_u64());
+        assert_eq!(record["metrics"]["records_processed"]["value"], 50.0);
+        assert_eq!(record["metrics"]["dataset_lock_timeouts"]["value"], 0.0);
+        assert_eq!(data_dir.usage().files_created, 1);
+        let _ = fs::remove_dir_all(base);
+    }
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn harness_overhead_stages_sum_to_the_summary_total() {
+        let mut stages = StageClock::starting_at(Instant::now());
+        let dir = env::temp_dir().join(format!("harness-overhead-{}", std::process::id()));
+        let configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        stages.mark(Stage::Startup);
+        let data_dir = DataDir::new(dir.clone());
+        stages.mark(Stage::Setup);
+        let ctx = run_context(&configs, data_dir, None);
+        stages.mark(Stage::Metadata);
+        let selection = [CPU_MONTE_CARLO_PI, STRING_JSON_PARSE_TRANSFORM];
+        let mut seen = 0;
+        let records = run(&configs, &selection, &ctx, &RetryPolicy::default(), &mut |attempts| {
+            seen += attempts.len()
+        });
+        stages.mark(Stage::Measurement);
+        let written = sinks::write_output(&dir.join("rust.json"), &records);
+        stages.mark(Stage::Output);
+        assert_eq!((records.len(), seen, written.records), (2, 2, 2));
+
+        let record = harness_overhead_record(&stages, &ctx);
+        assert_eq!(record["benchmark_id"], "harness_overhead");
+        assert_eq!(record["category"], "quality");
+        let value = |name: &str| record["metrics"][name]["value"].as_f64().unwrap();
+        let stage_sum: f64 = [
+            "startup_seconds",
+            "metadata_seconds",
+            "setup_seconds_total",
+            "measurement_seconds_total",
+            "output_seconds",
+        ]
+        .into_iter()
+        .map(value)
+        .sum();
+        let summary_total = stages.total().as_secs_f64();
+        assert!((stage_sum - summary_total).abs() < 1e-6, "{stage_sum} vs {summary_total}");
+        assert_eq!(value("total_seconds"), summary_total);
+        // Both benchmarks ran inside the measurement stage.
+        assert!(value("measurement_seconds_total") > 0.0);
+        assert!(value("measurement_seconds_total") < summary_total);
+        // The results write is part of the output stage.
+        assert!(value("output_seconds") > 0.0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn namespaced_records_still_chart_against_plain_ones_when_asked() {
+        let dir = env::temp_dir().join(format!("namespace-{}", std::process::id()));
+        let configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        let mut ctx = run_context(&configs, DataDir::new(dir), None);
+        ctx.namespace = Some("team-a".to_string());
+        let policy = RetryPolicy::default();
+        let mut records = run(&configs, &[CPU_MONTE_CARLO_PI], &ctx, &policy, &mut |_| {});
+        assert_eq!(records[0]["namespace"], "team-a");
+        assert_eq!(records[0]["benchmark_id"], CPU_MONTE_CARLO_PI);
+        let written = record::to_json_pretty(&records);
+        assert!(written.find("\"namespace\"") < written.find("\"benchmark_id\""));
+
+        let python: Vec<Value> = serde_json::from_str(fixtures::python_records()).unwrap();
+        records.extend(python);
+        assert!(record::shared_namespace(&records).is_err());
+        let chart = chart_data::chart(&records);
+        let monte_carlo = &chart["benchmarks"][0];
+        assert_eq!(monte_carlo["benchmark_id"], CPU_MONTE_CARLO_PI);
+        assert_eq!(monte_carlo["series"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn replay_overrides_warn_on_the_records_sinks_see() {
+        let dir = env::temp_dir().join(format!("replay-warnings-{}", std::process::id()));
+        let configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        let mut ctx = run_context(&configs, DataDir::new(dir), None);
+        ctx.replay_overrides = vec![repro::Override {
+            benchmark_id: CPU_MONTE_CARLO_PI.to_string(),
+            key: "samples",
+            recorded: json!(5),
+            used: json!(1_000),
+        }];
+        let policy = RetryPolicy::default();
+        let mut streamed = Vec::new();
+        let selection = [CPU_MONTE_CARLO_PI, STRING_JSON_PARSE_TRANSFORM];
+        let records = run(&configs, &selection, &ctx, &policy, &mut |attempts| {
+            streamed.extend_from_slice(attempts);
+        });
+        assert_eq!(streamed, records);
+        assert_eq!(streamed[0]["warnings"][0]["code"], "replay_override");
+        assert_eq!(streamed[0]["warnings"].as_array().unwrap().len(), 1);
+        assert!(streamed[1].get("warnings").is_none());
+
+        ctx.replay_overrides.clear();
+        ctx.clock_suspect = true;
+        let records = run(&configs, &selection, &ctx, &policy, &mut |_| {});
+        for record in &records {
+            let warnings = record["warnings"].as_array().unwrap();
+            assert!(warnings.iter().any(|w| w["code"] == "clock_suspect"), "{record}");
+        }
+    }
+
+    #[test]
+    fn rust_records_round_trip_through_the_typed_envelope() {
+        let dir = env::temp_dir().join(format!("record-round-trip-{}", std::process::id()));
+        let configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        let mut ctx = run_context(&configs, DataDir::new(dir.clone()), None);
+        ctx.environment.extra.insert("anonymized".to_string(), json!(true));
+        let selection = [
+            CPU_MONTE_CARLO_PI,
+            IO_CONCURRENT_HTTP_CLIENT,
+            DATA_PIPELINE_ETL_MINIBATCH,
+        ];
+        let mut records = run(&configs, &selection, &ctx, &RetryPolicy::default(), &mut |_| {});
+        let stages = StageClock::starting_at(Instant::now());
+        records.push(harness_overhead_record(&stages, &ctx));
+        assert!(records.iter().any(|record| record.get("preconditions").is_some()));
+        assert!(records.iter().any(|record| record.get("dataset").is_some()));
+        for record in records.iter().filter(|record| record["benchmark_id"] != HARNESS_OVERHEAD) {
+            let peak = record["metrics"]["peak_rss_kb"]["value"].as_f64().unwrap();
+            assert_eq!(peak > 0.0, platform::peak_rss_kb().is_some(), "{peak}");
+        }
+        let etl = records
+            .iter()
+            .find(|record| record["benchmark_id"] == DATA_PIPELINE_ETL_MINIBATCH)
+            .unwrap();
+        assert_eq!(etl["parameters"], etl_parameters(&configs));
+        assert_eq!(etl["parameters"]["read_buffer_kb"], 8);
+        assert_eq!(etl["parameters"]["gzip_buffer_kb"], 32);
+        assert!(etl["metrics"].get("read_buffer_kb").is_none());
+        assert_eq!(etl["metrics"]["runtime_seconds_n"]["value"], repeats::DEFAULT_REPEATS as f64);
+        assert_eq!(etl["metrics"]["repeat_checksum_mismatch"]["value"], 0.0);
+        let http = records
+            .iter()
+            .find(|record| record["benchmark_id"] == IO_CONCURRENT_HTTP_CLIENT)
+            .unwrap();
+        assert!(http["diagnostics"]["timeline_completed"].is_array(), "{http}");
+        assert!(http["metrics"].get("diagnostics").is_none());
+
+        for original in records {
+            let text = serde_json::to_string(&original).unwrap();
+            let typed: BenchmarkRecord = serde_json::from_str(&text).unwrap();
+            assert_eq!(typed.language, Language::Rust);
+            assert_eq!(serde_json::to_string(&typed.to_value()).unwrap(), text);
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn list_describes_the_registry_that_runs() {
+        let list = benchmark_list();
+        let entries = list.as_array().unwrap();
+        let ids: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry["benchmark_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, benchmark_ids::ALL);
+        let http = &entries[4];
+        assert_eq!(http["benchmark_id"], IO_CONCURRENT_HTTP_CLIENT);
+        assert_eq!(http["category"], "performance");
+        let vars = http["env_vars"].as_array().unwrap();
+        assert!(vars.contains(&json!("BENCHMARK_HTTP_REQUESTS")), "{vars:?}");
+        assert!(vars.contains(&json!("BENCHMARK_PARAMS_IO_CONCURRENT_HTTP_CLIENT")), "{vars:?}");
+        let build = entries.last().unwrap();
+        assert_eq!(build["category"], "quality");
+        assert!(!build["env_vars"].to_string().contains("BENCHMARK_PARAMS_"));
+
+        let mut configs = BenchmarkConfigs::default();
+        configs.etl_async.enabled = true;
+        let data_dir = DataDir::new(env::temp_dir());
+        for benchmark in benchmarks(&configs, &data_dir) {
+            let entry = entries.iter().find(|entry| entry["benchmark_id"] == benchmark.id).unwrap();
+            assert_eq!(entry["category"], benchmark.category, "{}", benchmark.id);
+        }
+    }
+
+    #[test]
+    fn parses_cli_flags() {
+        assert_eq!(parse_args(&args(&["--noop"])), Ok(Cli::Noop));
+        assert_eq!(
+            parse_args(&args(&["--output", "out.json", "--thresholds", "rules.json"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                thresholds: Some(PathBuf::from("rules.json")),
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(
+            parse_args(&args(&["--reset-checksum-store", "--output", "out.json"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                reset_checksum_store: true,
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(
+            parse_args(&args(&[
+                "--output",
+                "out.json",
+                "--retry-failed",
+                "2",
+                "--retry-error-threshold",
+                "5"
+            ])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                retry: RetryPolicy {
+                    max_retries: 2,
+                    error_threshold: 5.0,
+                },
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(
+            parse_args(&args(&[
+                "--output",
+                "out.json",
+                "--live-metrics",
+                "unix:/tmp/bench.sock",
+                "--live-metrics-interval",
+                "0.5"
+            ])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                live_metrics: Some(live_metrics::Target::Unix(PathBuf::from("/tmp/bench.sock"))),
+                live_metrics_interval: Duration::from_millis(500),
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&["--output", "a", "--live-metrics-interval", "-1"])).is_err());
+        assert!(parse_args(&args(&["--output", "a", "--retry-failed", "many"])).is_err());
+        assert_eq!(parse_args(&args(&["bootstrap"])), Ok(Cli::Bootstrap));
+        assert!(parse_args(&args(&["bootstrap", "--output", "out.json"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["--output", "out.json", "--clean"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                clean: true,
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(parse_args(&args(&["clean"])), Ok(Cli::Clean(Duration::from_secs(7 * 86_400))));
+        assert_eq!(
+            parse_args(&args(&["clean", "--older-than", "0.5"])),
+            Ok(Cli::Clean(Duration::from_secs(43_200)))
+        );
+        assert_eq!(
+            parse_args(&args(&["view", "out.json"])),
+            Ok(Cli::View(PathBuf::from("out.json")))
+        );
+        assert!(parse_args(&args(&["view"])).is_err());
+        assert!(parse_args(&args(&["view", "a.json", "b.json"])).is_err());
+        let compare = Ok(Cli::Compare {
+            results: PathBuf::from("latest.json"),
+            chart_data: PathBuf::from("chart.json"),
+            ignore_namespace: false,
+        });
+        assert_eq!(
+            parse_args(&args(&["compare", "latest.json", "--chart-data", "chart.json"])),
+            compare
+        );
+        assert_eq!(
+            parse_args(&args(&["compare", "--chart-data", "chart.json", "latest.json"])),
+            compare
+        );
+        assert!(parse_args(&args(&["compare", "latest.json"])).is_err());
+        assert_eq!(
+            parse_args(&args(&[
+                "compare",
+                "--ignore-namespace",
+                "latest.json",
+                "--chart-data",
+                "chart.json"
+            ])),
+            Ok(Cli::Compare {
+                results: PathBuf::from("latest.json"),
+                chart_data: PathBuf::from("chart.json"),
+                ignore_namespace: true,
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["--namespace", "team-a", "--output", "out.json"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                namespace: Some("team-a".to_string()),
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&["--namespace", "Team_A", "--output", "out.json"])).is_err());
+        let append = ["--output", "out.json", "--run-id", "ci-42", "--append"];
+        assert_eq!(
+            parse_args(&args(&[&append[..], &["--append-policy", "error"]].concat())),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                run_id: Some("ci-42".to_string()),
+                append: true,
+                append_policy: AppendPolicy::Error,
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&[&append[..], &["--append-policy", "skip"]].concat())).is_err());
+        assert!(parse_args(&args(&["--output", "o.json", "--append-policy", "keep"])).is_err());
+        let print = ["--print", "cpu_monte_carlo_pi.pi_estimate", "--append"];
+        assert!(parse_args(&args(&print)).is_err());
+        assert!(parse_args(&args(&["--output", "o.json", "--run-id", " "])).is_err());
+        let sinks = ["--sink", "jsonl=run.jsonl", "--sink", "csv=run.csv"];
+        let Ok(Cli::Run(options)) = parse_args(&args(&sinks)) else {
+            panic!("--sink alone is a run");
+        };
+        let labels: Vec<String> = options.sinks.iter().map(SinkSpec::label).collect();
+        assert_eq!(labels, ["jsonl=run.jsonl", "csv=run.csv"]);
+        assert!(parse_args(&args(&["--sink", "sqlite=runs.db"])).is_err());
+        let metric = "cpu_monte_carlo_pi.runtime_seconds";
+        assert_eq!(
+            parse_args(&args(&["bisect", "v1", "--metric", metric, "main", "--max", "0.5"])),
+            Ok(Cli::Bisect(BisectArgs {
+                good: "v1".to_string(),
+                bad: "main".to_string(),
+                metric: MetricPath::parse(metric).unwrap(),
+                bound: Bound::Max(0.5),
+            }))
+        );
+        for bad in [
+            &["bisect", "v1", "main", "--metric", metric][..],
+            &["bisect", "v1", "--metric", metric, "--max", "1", "--min", "0"],
+            &["bisect", "v1", "main", "extra", "--metric", metric, "--min", "1"],
+            &["bisect", "v1", "main", "--metric", metric, "--max"],
+        ] {
+            assert!(parse_args(&args(bad)).is_err(), "{bad:?}");
+        }
+        let unknown = parse_args(&args(&["bisect", "a", "b", "--metric", "nope.x", "--max", "1"]));
+        assert!(unknown.unwrap_err().starts_with("--metric: "));
+        let upload = ["--upload", "http://collector:8080/ingest", "--upload-batch-size", "10"];
+        assert_eq!(
+            parse_args(&args(&upload)),
+            Ok(Cli::Run(Box::new(CliOptions {
+                upload: Some("http://collector:8080/ingest".to_string()),
+                upload_config: UploadConfig {
+                    batch_size: 10,
+                    ..UploadConfig::default()
+                },
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&["--upload", "collector:8080"])).is_err());
+        assert!(parse_args(&args(&[upload[0], upload[1], upload[2], "0"])).is_err());
+        let no_upload = ["--output", "o.json", "--upload-flush-interval", "5"];
+        assert!(parse_args(&args(&no_upload)).is_err());
+        let pi = "cpu_monte_carlo_pi.pi_estimate";
+        let selection = "cpu_monte_carlo_pi,data_pipeline_etl_minibatch";
+        assert_eq!(
+            parse_args(&args(&["--print", pi, "--benchmarks", selection])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                print: vec![MetricPath::parse(pi).unwrap()],
+                benchmarks: vec![CPU_MONTE_CARLO_PI, DATA_PIPELINE_ETL_MINIBATCH],
+                ..CliOptions::default()
+            })))
+        );
+        let etl_only = ["--print", pi, "--benchmarks", "data_pipeline_etl_minibatch"];
+        assert!(parse_args(&args(&etl_only)).is_err());
+        assert!(parse_args(&args(&["--print", pi, "--benchmarks", "cpu_monte"])).is_err());
+        assert!(parse_args(&args(&["--print", pi, "--benchmarks", ","])).is_err());
+        let matrix = ["--output", "o.json", "--matrix", "grid.json", "--benchmarks", selection];
+        assert!(parse_args(&args(&matrix)).is_err());
+        assert_eq!(parse_args(&args(&["calibrate"])), Ok(Cli::Calibrate));
+        assert_eq!(parse_args(&args(&["--list"])), Ok(Cli::List));
+        assert!(parse_args(&args(&["--list", "--output", "o.json"])).is_err());
+        assert!(parse_args(&args(&["calibrate", "--output", "o.json"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["--print", pi, "--preset", "auto"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                print: vec![MetricPath::parse(pi).unwrap()],
+                auto_preset: true,
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&["--print", pi, "--preset", "laptop"])).is_err());
+        let pinned = ["--print", pi, "--preset", "auto", "--preset", "default", "--seed", "42"];
+        assert_eq!(
+            parse_args(&args(&pinned)),
+            Ok(Cli::Run(Box::new(CliOptions {
+                print: vec![MetricPath::parse(pi).unwrap()],
+                ..CliOptions::default()
+            })))
+        );
+        let err = parse_args(&args(&["--print", pi, "--seed", "7"])).unwrap_err();
+        assert_eq!(err, "--seed: this build runs seed 42 only, got 7");
+        assert_eq!(
+            parse_args(&args(&["--print", pi, "--config", "bench.toml"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                print: vec![MetricPath::parse(pi).unwrap()],
+                config: Some(PathBuf::from("bench.toml")),
+                ..CliOptions::default()
+            })))
+        );
+        let matrix = ["--output", "o.json", "--matrix", "grid.json", "--preset", "auto"];
+        assert!(parse_args(&args(&matrix)).is_err());
+        assert_eq!(
+            parse_args(&args(&["--output", "out.json", "--matrix", "grid.json", "--yes"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                matrix: Some(PathBuf::from("grid.json")),
+                yes: true,
+                ..CliOptions::default()
+            })))
+        );
+        assert!(parse_args(&args(&["--output", "out.json", "--yes"])).is_err());
+        let err = parse_args(&args(&["--output", "out.json", "--fail-on", "errors"])).unwrap_err();
+        assert_eq!(err, "--fail-on: expected degraded or warnings, got \"errors\"");
+        let alias = parse_args(&args(&["--output", "out.json", "--fail-on-degraded"]));
+        let spelled_out = parse_args(&args(&["--output", "out.json", "--fail-on", "degraded"]));
+        assert_eq!(alias, spelled_out);
+        assert_eq!(
+            parse_args(&args(&["--output", "o.json", "--replay", "r.json", "--replay-force"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("o.json")),
+                replay: Some(PathBuf::from("r.json")),
+                replay_force: true,
+                ..CliOptions::default()
+            })))
+        );
+        let err = parse_args(&args(&["--output", "o.json", "--replay-force"])).unwrap_err();
+        assert_eq!(err, "--replay-force needs --replay");
+        let replay = ["--output", "o.json", "--replay", "r.json", "--repeat", "3"];
+        let err = parse_args(&args(&replay)).unwrap_err();
+        assert_eq!(err, "--repeat does not apply to --replay; the recording sets it");
+        let replay = ["--output", "o.json", "--replay", "r.json", "--trim", "iqr"];
+        let err = parse_args(&args(&replay)).unwrap_err();
+        assert_eq!(err, "--trim does not apply to --replay; the recording sets it");
+        assert_eq!(
+            parse_args(&args(&[
+                "--anonymize",
+                "--no-precheck",
+                "--low-priority",
+                "--harness-overhead",
+                "--fail-on-degraded",
+                "--fail-on",
+                "warnings",
+                "--backtraces",
+                "--strict-config",
+                "--serve",
+                "--output",
+                "out.json"
+            ])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                anonymize: true,
+                no_precheck: true,
+                low_priority: true,
+                harness_overhead: true,
+                fail_on_degraded: true,
+                fail_on_warnings: true,
+                backtraces: true,
+                strict_config: true,
+                serve: true,
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(
+            parse_args(&args(&["--output", "out.json", "--on-fingerprint-mismatch", "refuse"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                fingerprint_mismatch: MismatchPolicy::Refuse,
+                ..CliOptions::default()
+            })))
+        );
+        assert_eq!(
+            parse_args(&args(&["--output", "out.json", "--interactive"])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("out.json")),
+                interactive: true,
+                ..CliOptions::default()
+            })))
+        );
+        let matrix = ["--output", "o.json", "--matrix", "g.json", "--interactive"];
+        assert!(parse_args(&args(&matrix)).is_err());
+        let repeat = [
+            "--output", "o.json", "--repeat", "20", "--trim", "pct:10", "--emit-samples",
+            "--max-samples", "8",
+        ];
+        assert_eq!(
+            parse_args(&args(&repeat)),
+            Ok(Cli::Run(Box::new(CliOptions {
+                output: Some(PathBuf::from("o.json")),
+                repeat: Some(20),
+                trim: Trim::Percent(10),
+                emit_samples: true,
+                max_samples: 8,
+                ..CliOptions::default()
+            })))
+        );
+        for bad in [
+            &["--output", "o.json", "--repeat", "0"][..],
+            &["--output", "o.json", "--trim", "pct:60"],
+            &["--output", "o.json", "--max-samples", "8"],
+            &["--output", "o.json", "--emit-samples", "--max-samples", "0"],
+        ] {
+            assert!(parse_args(&args(bad)).is_err(), "{bad:?}");
+        }
+        let unknown_policy = ["--output", "a", "--on-fingerprint-mismatch", "skip"];
+        assert!(parse_args(&args(&unknown_policy)).is_err());
+        assert!(parse_args(&args(&["clean", "--older-than", "-1"])).is_err());
+        assert!(parse_args(&args(&["clean", "--output", "out.json"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["run", "--output", "out.json"])),
+            parse_args(&args(&["--output", "out.json"]))
+        );
+        assert_eq!(
+            parse_args(&args(&[
+                "--print",
+                "data_pipeline_etl_minibatch.throughput_mb_s",
+                "--print",
+                "cpu_monte_carlo_pi.pi_estimate"
+            ])),
+            Ok(Cli::Run(Box::new(CliOptions {
+                print: vec![
+                    MetricPath::parse("data_pipeline_etl_minibatch.throughput_mb_s").unwrap(),
+                    MetricPath::parse("cpu_monte_carlo_pi.pi_estimate").unwrap(),
+                ],
+                ..CliOptions::default()
+            })))
+        );
+        let pi = "cpu_monte_carlo_pi.pi_estimate";
+        let printed = parse_args(&args(&["--print", pi, "--output", "out.json"]));
+        assert!(matches!(printed, Ok(Cli::Run(options)) if options.output.is_some()));
+        assert!(parse_args(&args(&["--print", "cpu_monte_carlo.pi_estimate"])).is_err());
+        assert!(parse_args(&args(&["--print", pi, "--matrix", "grid.json"])).is_err());
+        assert!(parse_args(&args(&["--thresholds", "rules.json"])).is_err());
+        assert!(parse_args(&args(&["--output"])).is_err());
+        assert!(parse_args(&args(&["--output", "a", "--bogus"])).is_err());
+    }
+
+    #[test]
+    fn environment_reports_effective_priority() {
+        let environment = environment_block(None, false).extra;
+        assert_eq!(environment["nice"], json!(platform::nice_value()));
+        assert_eq!(
+            environment["scheduler_policy"],
+            json!(platform::scheduler_policy_name())
+        );
+        assert_eq!(environment["priority_class"], json!(platform::priority_class()));
+        #[cfg(unix)]
+        assert!(environment["nice"].is_i64());
+        assert!(environment["utc_offset_seconds"].is_i64());
+        assert!(environment.contains_key("time_zone"));
+    }
+
+    #[test]
+    fn registry_runs_every_registered_id_in_order() {
+        let mut configs = BenchmarkConfigs::default();
+        let data_dir = DataDir::new(env::temp_dir());
+        let ids = |configs: &BenchmarkConfigs| -> Vec<&str> {
+            benchmarks(configs, &data_dir).iter().map(|benchmark| benchmark.id).collect()
+        };
+        assert!(!ids(&configs).contains(&DATA_PIPELINE_ETL_ASYNC));
+        configs.etl_async.enabled = true;
+        // The async task benchmark exists only in builds with tokio.
+        let built: Vec<&str> = benchmark_ids::ALL
+            .into_iter()
+            .filter(|id| *id != CONCURRENCY_ASYNC_TASKS || async_tasks::supported())
+            .collect();
+        assert_eq!(ids(&configs), built);
+    }
+
+    #[test]
+    fn http_client_runs_and_labels_each_target() {
+        let fast = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        let slow = MockServer::start(
+            ChaosConfig {
+                latency: sampling::LatencyDistribution::Fixed { ms: 5.0 },
+                ..ChaosConfig::default()
+            },
+            7,
+        )
+        .unwrap();
+        let config = HttpClientConfig {
+            base_url: format!("{},{}", fast.base_url(), slow.base_url()),
+            requests: 20,
+            rows: 10,
+            concurrency: 1,
+            ..HttpClientConfig::default()
+        };
+        let ctx = RunContext {
+            run_id: "targets".to_string(),
+            environment: environment_block(None, false),
+            harness_fingerprint: fingerprint::harness_fingerprint(),
+            data_dir: DataDir::new(env::temp_dir()),
+            live: None,
+            calibration: None,
+            repeats: Repeats::default(),
+            namespace: None,
+            clock_suspect: false,
+            replay_overrides: Vec::new(),
+        };
+        let mut records: Vec<Value> = http_client_benchmarks(&config)
+            .iter()
+            .map(|benchmark| benchmark.record(&ctx))
+            .collect();
+        insert_target_ratios(&mut records);
+
+        assert_eq!(records.len(), 2);
+        for (index, (record, server)) in records.iter().zip([&fast, &slow]).enumerate() {
+            assert_eq!(record["target"], json!({"index": index, "base_url": server.base_url()}));
+            assert_eq!(record["preconditions"][0]["name"], server.base_url());
+            assert_eq!(record["metrics"]["requests_completed"]["value"], 20.0);
+            assert_eq!(record["metrics"]["request_errors"]["value"], 0.0);
+            assert_eq!(record["metrics"]["http_error_count"]["value"], 0.0);
+            // One worker, one keep-alive connection for all 20 requests.
+            assert_eq!(record["metrics"]["connections_opened"]["value"], 1.0);
+            assert_eq!(record["metrics"]["reconnects"]["value"], 0.0);
+        }
+        // Every request to the slow target waits its 5 ms of latency.
+        let p50 = |record: &Value| record["metrics"]["latency_p50_ms"]["value"].as_f64().unwrap();
+        assert!(p50(&records[1]) >= 5.0, "{}", records[1]["metrics"]);
+        assert!(p50(&records[0]) < p50(&records[1]));
+        let ratio =
+            |record: &Value| record["metrics"]["rps_ratio_to_first_target"]["value"].as_f64();
+        assert_eq!(ratio(&records[0]), Some(1.0));
+        assert!(ratio(&records[1]).unwrap() < 1.0, "{}", records[1]["metrics"]);
+
+        // A single target stays unlabelled.
+        let single = http_client_benchmarks(&config.for_target(&fast.base_url()));
+        let mut record = [single[0].record(&ctx)];
+        insert_target_ratios(&mut record);
+        assert!(record[0].get("target").is_none());
+        assert!(record[0]["metrics"].get("rps_ratio_to_first_target").is_none());
+    }
+
+    #[test]
+    fn http_client_runs_each_sweep_level_per_target() {
+        let first = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        let second = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        let config = HttpClientConfig {
+            base_url: format!("{},{}", first.base_url(), second.base_url()),
+            requests: 12,
+            rows: 10,
+            concurrency: 16,
+            concurrency_sweep: vec![1, 3],
+            ..HttpClientConfig::default()
+        };
+        let ctx = RunContext {
+            run_id: "sweep".to_string(),
+            environment: environment_block(None, false),
+            harness_fingerprint: fingerprint::harness_fingerprint(),
+            data_dir: DataDir::new(env::temp_dir()),
+            live: None,
+            calibration: None,
+            repeats: Repeats::default(),
+            namespace: None,
+            clock_suspect: false,
+            replay_overrides: Vec::new(),
+        };
+        let mut records: Vec<Value> = http_client_benchmarks(&config)
+            .iter()
+            .map(|benchmark| benchmark.record(&ctx))
+            .collect();
+        insert_target_ratios(&mut records);
+
+        let cells: Vec<(Value, Value)> = records
+            .iter()
+            .map(|record| {
+                (record["target"]["index"].clone(), record["parameters"]["concurrency"].clone())
+            })
+            .collect();
+        let expected =
+            [(0, 1), (0, 3), (1, 1), (1, 3)].map(|(index, level)| (json!(index), json!(level)));
+        assert_eq!(cells, expected);
+        for record in &records {
+            assert!(record["parameters"].get("concurrency_sweep").is_none());
+            // Every level makes all the requests.
+            assert_eq!(record["metrics"]["requests_completed"]["value"], 12.0);
+            assert!(record["metrics"]["rps_ratio_to_first_target"]["value"].is_f64());
+        }
+        // Each level is compared with the first target at the same level.
+        assert_eq!(records[1]["metrics"]["rps_ratio_to_first_target"]["value"], 1.0);
+        assert_eq!(records[1]["metrics"]["connections_opened"]["value"], 3.0);
+    }
+
+    #[test]
+    fn serve_runs_the_http_benchmarks_against_an_embedded_server() {
+        // Nothing listens at the configured URL; `--serve` never uses it.
+        let configs = BenchmarkConfigs {
+            http_client: HttpClientConfig {
+                base_url: "http://127.0.0.1:9,http://127.0.0.1:10".to_string(),
+                requests: 30,
+                rows: 10,
+                concurrency: 2,
+                ready_timeout_seconds: 0.0,
+                ..HttpClientConfig::default()
+            },
+            ..BenchmarkConfigs::default()
+        }
+        .with_embedded_server();
+        let ctx = RunContext {
+            run_id: "serve".to_string(),
+            environment: environment_block(None, false),
+            harness_fingerprint: fingerprint::harness_fingerprint(),
+            data_dir: DataDir::new(env::temp_dir()),
+            live: None,
+            calibration: None,
+            repeats: Repeats::default(),
+            namespace: None,
+            clock_suspect: false,
+            replay_overrides: Vec::new(),
+        };
+        let benchmarks = http_client_benchmarks(&configs.http_client);
+        assert_eq!(benchmarks.len(), 1);
+        let record = benchmarks[0].record(&ctx);
+        assert_eq!(record["parameters"]["server"], "embedded");
+        // The URL the embedded server listened on, not the configured list.
+        let base_url = record["parameters"]["base_url"].as_str().unwrap();
+        assert!(base_url.starts_with("http://127.0.0.1:") && !base_url.contains(','), "{base_url}");
+        assert!(record.get("target").is_none());
+        let metrics = &record["metrics"];
+        assert_eq!(metrics["server_unavailable"]["value"], 0.0);
+        assert_eq!(metrics["requests_completed"]["value"], 30.0);
+        assert_eq!(metrics["checksum"]["value"], 3.0 * (0..10).sum::<u64>() as f64);
+        // One keep-alive connection per worker; the probe's are not counted.
+        assert_eq!(metrics["connections_opened"]["value"], 2.0);
+        assert_eq!(record["diagnostics"]["fixture_locality"], "loopback");
+        assert!(metrics["fixture_rtt_ms"]["value"].as_f64() > Some(0.0));
+
+        let mut checks = Preconditions::default();
+        let live = LiveContext::new(None, "serve", IO_HTTP_CONNECTION_CHURN);
+        let churn = http_connection_churn_metrics(&configs.http_churn, &mut checks, &live);
+        assert_eq!(churn["request_errors"].value, 0.0);
+        assert_eq!(live.diagnostics().unwrap()["fixture_locality"], "loopback");
+
+        let live = LiveContext::new(None, "serve", IO_CONCURRENT_HTTP_POST);
+        let posts = io_http_metrics(&configs.http_post, &mut checks, &live, &post_json_row);
+        assert_eq!(posts["requests_completed"].value, 400.0);
+        assert_eq!(posts["request_errors"].value, 0.0);
+        assert_eq!(live.diagnostics().unwrap()["status_counts"], json!({"201": 400}));
+        // Echoed values, which are the GET checksum's item values.
+        let rows = HttpClientConfig::default().rows as u64;
+        let expected: u64 = (0..400).map(|id| id % rows % 17).sum();
+        assert_eq!(posts["checksum"].value, expected as f64);
+
+        let streaming = HttpStreamingConfig {
+            megabytes: 2,
+            ..configs.http_streaming
+        };
+        let streamed = http_streaming_metrics(&streaming, &mut checks, &live);
+        assert_eq!(streamed["request_errors"].value, 0.0);
+        assert_eq!(streamed["hash_mismatches"].value, 0.0);
+        for key in ["bytes_uploaded", "bytes_downloaded"] {
+            assert_eq!(streamed[key].value, 2.0 * 1024.0 * 1024.0, "{key}");
+        }
+        assert!(streamed["upload_mb_s"].value > 0.0);
+        assert!(streamed["download_mb_s"].value > 0.0);
+    }
+
+    #[test]
+    fn localized_counts_are_flagged_not_misread() {
+        let mut suspect = false;
+        let plain: Value = serde_json::from_str(&fixtures::audit_report(3, Some("3"))).unwrap();
+        assert_eq!(count_vulnerability_findings(&plain, &mut suspect), 3);
+        assert!(!suspect);
+
+        let localized: Value =
+            serde_json::from_str(&fixtures::audit_report(2, Some("1.204"))).unwrap();
+        assert_eq!(count_vulnerability_findings(&localized, &mut suspect), 2);
+        assert!(suspect);
+
+        let mut suspect = false;
+        let listed = json!({"count": "2", "dependencies": [{}, {}]});
+        assert_eq!(count_outdated_dependencies(&listed, &mut suspect), 2);
+        assert!(!suspect);
+        let localized = json!({"count": "1.204", "dependencies": [{}, {}]});
+        assert_eq!(count_outdated_dependencies(&localized, &mut suspect), 2);
+        assert!(suspect);
+    }
+
+    #[test]
+    fn flags_checksum_divergence() {
+        let configs = BenchmarkConfigs::default();
+        let record = |aggregate: f64| {
+            json!({
+                "benchmark_id": "data_pipeline_etl_minibatch",
+                "run_id": "run",
+                "metrics": {"aggregate_value": {"value": aggregate, "unit": "count"}},
+            })
+        };
+        let mut store = ChecksumStore::default();
+        let mut first = vec![record(100.0)];
+        check_checksum_stability(&mut first, &configs, &mut store, MismatchPolicy::Warn);
+        assert_eq!(first[0]["metrics"]["checksum_stable"]["value"], 1.0);
+
+        let mut diverged = vec![record(101.0)];
+        check_checksum_stability(&mut diverged, &configs, &mut store, MismatchPolicy::Warn);
+        assert_eq!(diverged[0]["metrics"]["checksum_stable"]["value"], 0.0);
+        assert_eq!(diverged[0]["metrics"]["checksum_previous_value"]["value"], 100.0);
+
+        // Superseded retry attempts are neither checked nor stored.
+        let mut superseded = vec![record(102.0)];
+        superseded[0]["selected_for_comparison"] = json!(false);
+        check_checksum_stability(&mut superseded, &configs, &mut store, MismatchPolicy::Warn);
+        assert!(superseded[0]["metrics"].get("checksum_stable").is_none());
+    }
+
+    #[test]
+    fn baselines_from_another_harness_warn_or_rebaseline() {
+        let configs = BenchmarkConfigs::default();
+        let record = |aggregate: f64, harness: &str| {
+            json!({
+                "benchmark_id": "data_pipeline_etl_minibatch",
+                "run_id": "run",
+                "harness_fingerprint": harness,
+                "metrics": {"aggregate_value": {"value": aggregate, "unit": "count"}},
+            })
+        };
+        let mut store = ChecksumStore::default();
+        let mut baseline = vec![record(100.0, "old")];
+        check_checksum_stability(&mut baseline, &configs, &mut store, MismatchPolicy::Warn);
+
+        let mut warned = vec![record(101.0, "new")];
+        check_checksum_stability(&mut warned, &configs, &mut store, MismatchPolicy::Warn);
+        assert_eq!(warned[0]["warnings"][0]["code"], "harness_fingerprint_mismatch");
+        let details = json!({"baseline": "old", "current": "new"});
+        assert_eq!(warned[0]["warnings"][0]["details"], details);
+        assert_eq!(warned[0]["metrics"]["harness_fingerprint_mismatch"]["value"], 1.0);
+        assert_eq!(warned[0]["metrics"]["checksum_stable"]["value"], 0.0);
+
+        let mut refused = vec![record(101.0, "new")];
+        check_checksum_stability(&mut refused, &configs, &mut store, MismatchPolicy::Refuse);
+        assert_eq!(refused[0]["warnings"][0]["code"], "harness_fingerprint_mismatch");
+        assert!(refused[0]["metrics"].get("checksum_stable").is_none());
+
+        let mut rebaselined = vec![record(101.0, "new")];
+        check_checksum_stability(&mut rebaselined, &configs, &mut store, MismatchPolicy::Refuse);
+        assert!(rebaselined[0].get("warnings").is_none());
+        assert_eq!(rebaselined[0]["metrics"]["checksum_stable"]["value"], 1.0);
+    }
+
+    #[test]
+    fn summary_sits_next_to_output() {
+        assert_eq!(
+            summary_path(Path::new("results/raw/rust_perf.json")),
+            PathBuf::from("results/raw/rust_perf.summary.json")
+        );
+    }
+
+    #[test]
+    fn monte_carlo_reasonable() {
+        let estimate = monte_carlo_pi(10_000);
+        assert!(estimate > 3.0 && estimate < 3.3);
+        // The seed is fixed, so the same sample count gives the same estimate.
+        assert_eq!(monte_carlo_pi(10_000).to_bits(), estimate.to_bits());
+        assert_eq!(monte_carlo_pi(25_000).to_bits(), monte_carlo_pi(25_000).to_bits());
+
+        let metrics = monte_carlo_metrics(&MonteCarloConfig { samples: 10_000 });
+        let error = metrics["absolute_error"].value;
+        assert_eq!(error, (estimate - std::f64::consts::PI).abs());
+        assert!(metrics["samples_per_second"].value > 0.0);
+    }
+
+    #[test]
+    fn vectorized_monte_carlo_is_bit_identical() {
+        for samples in [1, 3, 4, 5, 1_001, 10_000] {
+            assert_eq!(
+                monte_carlo_pi(samples).to_bits(),
+                monte_carlo_pi_vectorized(samples).to_bits(),
+                "{samples} samples"
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_monte_carlo_sums_its_streams() {
+        assert_eq!(stream_shares(10, 4), [3, 3, 2, 2]);
+        assert_eq!(stream_shares(2, 3), [1, 1, 0]);
+        assert_eq!(stream_shares(7, 0), [7]);
+        for (samples, streams) in [(1, 1), (10_001, 3), (40_000, 4), (3, 8)] {
+            let inside: usize = stream_shares(samples, streams)
+                .into_iter()
+                .enumerate()
+                .map(|(stream, share)| {
+                    monte_carlo_inside(rng::stream(DEFAULT_SEED, stream as u64), share)
+                })
+                .sum();
+            let expected = 4.0 * inside as f64 / samples as f64;
+            let parallel = monte_carlo_pi_streams(samples, streams, true);
+            assert_eq!(parallel.to_bits(), expected.to_bits(), "{samples} over {streams}");
+            let serial = monte_carlo_pi_streams(samples, streams, false);
+            assert_eq!(serial.to_bits(), expected.to_bits(), "{samples} over {streams}");
+        }
+
+        let metrics = monte_carlo_parallel_metrics(&MonteCarloConfig { samples: 20_000 }, 2);
+        assert_eq!(metrics["threads"].value, 2.0);
+        assert_eq!(metrics["parallel_mismatch"].value, 0.0);
+        assert!(metrics["parallel_speedup"].value > 0.0);
+        let estimate = monte_carlo_pi_streams(20_000, 2, false);
+        assert_eq!(metrics["pi_estimate"].value, estimate);
+    }
+
+    #[test]
+    fn sleep_overshoots_discard_warmup() {
+        let overshoots = sleep_overshoots_us(1, 4, 2);
+        assert_eq!(overshoots.len(), 4);
+        assert!(overshoots.min() >= 0.0);
+    }
+
+    #[test]
+    fn parse_checksum() {
+        let checksum = json_parse_transform(100);
+        let expected: u64 = (0..100).map(|i| (i % 17) as u64).sum();
+        assert_eq!(checksum, expected);
+    }
+
+    #[test]
+    fn typed_and_untyped_etl_aggregates_match() {
+        let buffer: String = (0..500)
+            .map(|idx| {
+                format!(
+                    "{{\"id\":{idx},\"group\":{},\"value\":{},\"score\":{}}}\n",
+                    idx % 50,
+                    (idx * 7 + 11) % 10_000,
+                    (idx * 13 + 17) % 10_000
+                )
+            })
+            .collect();
+        let typed = etl_aggregate_typed(&buffer);
+        assert_eq!(typed.0, 500);
+        assert_eq!(typed, etl_aggregate_untyped(&buffer));
+    }
+
+    #[test]
+    fn hugepage_advice_applies_where_thp_allows_it() {
+        let bytes: Vec<u8> = (0..3 * 1024 * 1024).map(|idx| (idx % 251) as u8).collect();
+        let (storage, range, applied) = hugepage_copy(&bytes);
+        assert_eq!(&storage[range.clone()], &bytes[..]);
+        assert!((storage.as_ptr() as usize + range.start).is_multiple_of(platform::HUGEPAGE_BYTES));
+        // The check reads the kernel's verdict back from smaps, so it
+        // follows the machine's THP mode rather than madvise's return code.
+        let expected = matches!(platform::thp_mode().as_deref(), Some("always" | "madvise"));
+        assert_eq!(applied, expected);
+    }
+
+    #[test]
+    fn etl_input_modes_read_the_same_rows() {
+        let dir = env::temp_dir().join(format!("etl-input-modes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dataset = dir.join("giant.jsonl.gz");
+        fs::write(&dataset, fixtures::etl_gzip_with_giant_line(3000, 1500, 64 * 1024)).unwrap();
+        let data_dir = DataDir::new(dir.clone());
+        let live = LiveContext::new(None, "test", "etl");
+        let outcome = |mode: InputMode| {
+            let config = EtlConfig {
+                dataset: dataset.to_string_lossy().into_owned(),
+                max_line_bytes: 4096,
+                input_mode: mode,
+                ..EtlConfig::default()
+            };
+            let metrics = etl_metrics(&config, &data_dir, &mut Preconditions::default(), &live);
+            let value = |name: &str| metrics.value(name);
+            let read = ["records_processed", "aggregate_value", "oversized_lines", "io_errors"];
+            let faults = ["page_faults_major", "page_faults_minor"].map(value);
+            (read.map(value), faults, value("throughput_mb_s").is_some())
+        };
+
+        let (gzip, no_faults, _) = outcome(InputMode::Gzip);
+        assert_eq!((gzip[0], gzip[2], gzip[3]), (Some(2999.0), Some(1.0), Some(0.0)));
+        assert_eq!(no_faults, [None, None]);
+        for mode in [InputMode::Buffered, InputMode::Mmap] {
+            let (read, faults, throughput) = outcome(mode);
+            assert_eq!(read, gzip, "{mode:?}");
+            assert!(faults.iter().all(Option::is_some), "{mode:?}");
+            assert!(throughput);
+        }
+        // The decompressed copy is scratch.
+        let scratch = fs::read_dir(data_dir.cache_dir()).unwrap().flatten().count();
+        assert_eq!(scratch, 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn etl_skips_oversized_lines_or_fails_when_strict() {
+        let dir = env::temp_dir().join(format!("etl-giant-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dataset = dir.join("giant.jsonl.gz");
+        fs::write(&dataset, fixtures::etl_gzip_with_giant_line(200, 100, 64 * 1024)).unwrap();
+        let live = LiveContext::new(None, "test", "etl");
+        let config = EtlConfig {
+            dataset: dataset.to_string_lossy().into_owned(),
+            max_line_bytes: 4096,
+            ..EtlConfig::default()
+        };
+        let expected: u64 = (0..200)
+            .filter(|idx| *idx != 100)
+            .map(|idx| etl_transform((idx % 50) as u64, ((idx * 7 + 11) % 10_000) as u64))
+            .sum();
+
+        let run = etl_benchmark(&dataset, &config, None, &live);
+        assert_eq!((run.rows, run.oversized_lines, run.io_errors), (199, 1, 0));
+        assert_eq!(run.aggregate, expected);
+        let comparison = etl_parse_comparison(&dataset, &config).unwrap();
+        assert_eq!(comparison.typed_aggregate, expected);
+
+        let strict = EtlConfig {
+            strict_line_length: true,
+            ..config.clone()
+        };
+        let run = etl_benchmark(&dataset, &strict, None, &live);
+        assert_eq!((run.rows, run.oversized_lines, run.io_errors), (100, 1, 1));
+
+        let uncapped = etl_benchmark(&dataset, &EtlConfig::default(), None, &live);
+        assert_eq!((uncapped.rows, uncapped.oversized_lines), (200, 0));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn buffer_sizes_reach_the_reader_and_never_change_results() {
+        assert_eq!(IoBuffers::from_kb(None), IoBuffers { read_kb: 8, gzip_kb: 32 });
+        assert_eq!(IoBuffers::from_kb(Some(0)), IoBuffers { read_kb: 1, gzip_kb: 1 });
+        let buffers = IoBuffers::from_kb(Some(64));
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let reader = buffers.gzip_lines(File::open(manifest).unwrap());
+        assert_eq!(reader.capacity(), 64 * 1024);
+        assert_eq!(reader.get_ref().get_ref().capacity(), 64 * 1024);
+
+        let dir = env::temp_dir().join(format!("etl-buffers-{}", std::process::id()));
+        let dataset = dir.join("etl.jsonl.gz");
+        let config = EtlConfig {
+            rows: 300,
+            io_buffer_kb: Some(1),
+            ..EtlConfig::default()
+        };
+        let live = LiveContext::new(None, "test", "etl");
+        let main = etl_benchmark(&dataset, &config, None, &live);
+        assert_eq!((main.rows, main.io_errors), (300, 0));
+        let default = etl_read(&dataset, &config, IoBuffers::default(), &live);
+        assert_eq!((default.rows, default.aggregate), (main.rows, main.aggregate));
+
+        let sweep = etl_buffer_sweep(&dataset, &config);
+        let sizes: Vec<usize> = sweep.iter().map(|(kb, _, _)| *kb).collect();
+        assert_eq!(sizes, BUFFER_SWEEP_KB);
+        let mut map = Metrics::new();
+        insert_buffer_sweep_metrics(&mut map, &main, &sweep);
+        assert_eq!(map["buffer_sweep_aggregate_mismatch"].value, 0.0);
+        assert!(map["buffer_sweep_1024kb_throughput_mb_s"].value > 0.0);
+
+        let short = EtlRun {
+            rows: 299,
+            ..EtlRun::default()
+        };
+        insert_buffer_sweep_metrics(&mut map, &short, &sweep);
+        assert_eq!(map["buffer_sweep_aggregate_mismatch"].value, 1.0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn read_ahead_aggregates_match_the_sync_path() {
+        let dir = env::temp_dir().join(format!("etl-async-{}", std::process::id()));
+        let dataset = dir.join("etl.jsonl.gz");
+        let config = EtlConfig {
+            rows: 2_000,
+            ..EtlConfig::default()
+        };
+        let live = LiveContext::new(None, "test", "etl");
+        let sync = etl_benchmark(&dataset, &config, None, &live);
+        assert_eq!((sync.rows, sync.io_errors), (2_000, 0));
+
+        let bytes = fs::read(&dataset).unwrap();
+        let file_len = bytes.len() as u64;
+        for (depth, chunk) in [(1, 4096), (4, 512), (16, 97)] {
+            let queue = etl_async::tests::ShuffledQueue::new(bytes.clone(), 61);
+            let read_ahead = ReadAhead::new(queue, file_len, depth, chunk).unwrap();
+            let (run, max_in_flight) = etl_read_ahead(read_ahead, &config, &live);
+            assert_eq!((run.rows, run.aggregate, run.io_errors), (sync.rows, sync.aggregate, 0));
+            assert_eq!(max_in_flight, depth);
+        }
+
+        let async_config = EtlAsyncConfig {
+            enabled: true,
+            ..EtlAsyncConfig::default()
+        };
+        let etl = EtlConfig {
+            dataset: dataset.to_string_lossy().into_owned(),
+            ..config
+        };
+        let mut checks = Preconditions::default();
+        let data_dir = DataDir::new(dir.clone());
+        let map = etl_async_metrics(&etl, &async_config, &data_dir, &mut checks, &live);
+        if etl_async::supported() {
+            assert_eq!(map["aggregate_value"].value, sync.aggregate as f64);
+            assert_eq!(map["io_errors"].value, 0.0);
+            assert!(map["max_reads_in_flight"].value >= 1.0);
+        } else {
+            assert_eq!(map["async_io_supported"].value, 0.0);
+            assert!(!map.contains_key("aggregate_value"));
+            assert_eq!(checks.first_skip().map(|outcome| outcome.name.as_str()), Some("io_uring"));
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn async_tasks_count_all_work_missing_without_a_runtime() {
+        let config = AsyncTasksConfig {
+            tasks: 500,
+            round_trips: 20,
+            producers: 3,
+            messages_per_producer: 40,
+            worker_threads: 2,
+            ..AsyncTasksConfig::default()
+        };
+        let map = async_tasks_metrics(&config);
+        assert_eq!(map["worker_threads"].value, 2.0);
+        let counts = ["tasks_missing", "echo_errors", "sequence_errors"].map(|key| map[key].value);
+        if async_tasks::supported() {
+            assert_declared_metrics(CONCURRENCY_ASYNC_TASKS, &map);
+            assert_eq!(counts, [0.0; 3]);
+            assert!(map["tasks_per_second"].value > 0.0);
+        } else {
+            assert_eq!(counts, [500.0, 20.0, 120.0]);
+            assert_eq!(map["round_trip_us"].value, -1.0);
+        }
+    }
+
+    #[test]
+    fn builtin_scanner_reads_the_lockfile_and_snapshot() {
+        let dir = env::temp_dir().join(format!("builtin-scan-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Cargo.lock"), fixtures::scan_lockfile()).unwrap();
+        let (findings, advisories) =
+            builtin_vulnerability_scan(&dir, &fixtures::scan_osv_path()).unwrap();
+        assert_eq!((findings.len(), advisories), (2, 2));
+        assert!(builtin_vulnerability_scan(&dir, &dir.join("missing.json")).is_err());
+
+        let report = builtin_outdated_scan(&dir, &dir.join("no-cargo-home")).unwrap();
+        assert_eq!((report.outdated.len(), report.unresolved), (0, 3));
+
+        let mut map = Metrics::new();
+        BuiltinScan::default().insert_metrics(&mut map);
+        assert_eq!(map.iter().count(), 2);
+        let scan = BuiltinScan {
+            vulnerabilities: true,
+            advisories_loaded: advisories,
+            ..BuiltinScan::default()
+        };
+        scan.insert_metrics(&mut map);
+        assert_eq!(map["vulnerability_scanner_builtin"].value, 1.0);
+        assert_eq!(map["builtin_advisories_loaded"].value, 2.0);
+        assert!(!map.contains_key("outdated_unresolved"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn crate_names_become_metric_keys() {
+        assert_eq!(crate_metric_key("rust-benchmarks"), "crate_rust_benchmarks_finding_count");
+        assert_eq!(crate_metric_key("Bench.Core"), "crate_bench_core_finding_count");
+    }
+
+    fn scorecard_value(map: &Metrics, key: &str) -> f64 {
+        map.value(key).unwrap_or_else(|| panic!("{key} missing"))
+    }
+
+    #[test]
+    fn dependency_scan_handles_scripted_tool_outcomes() {
+        let config = DependencyScanConfig::default();
+        let scan = |runner: &ScriptedRunner| {
+            dependency_scan_metrics(
+                &config,
+                &mut Preconditions::default(),
+                &LiveContext::new(None, "test", DEPENDENCY_VULNERABILITY_SCAN_SCORECARD),
+                &mut PhaseLog::default(),
+                runner,
+            )
+        };
+        let report = fixtures::audit_report(3, None);
+        let outdated = r#"{"dependencies": [{"name": "serde"}, {"name": "libc"}]}"#;
+
+        let happy = ScriptedRunner::default()
+            .on("cargo * --version", 0, "", "")
+            .on("cargo audit --json", 0, &report, "")
+            .on("cargo outdated", 0, outdated, "");
+        let map = scan(&happy);
+        assert_eq!(scorecard_value(&map, "tool_available"), 1.0);
+        assert_eq!(scorecard_value(&map, "vulnerability_findings"), 3.0);
+        assert_eq!(scorecard_value(&map, "outdated_dependencies"), 2.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 0.0);
+        assert_eq!(scorecard_value(&map, "expected_nonzero_exits"), 0.0);
+        assert_eq!(scorecard_value(&map, "vulnerability_scanner_builtin"), 0.0);
+
+        // cargo-audit exits 1 when it finds something, but still reports:
+        // that is findings, not a broken scan. cargo-outdated has no such code.
+        let nonzero = ScriptedRunner::default()
+            .on("cargo * --version", 0, "", "")
+            .on("cargo audit --json", 1, &report, "")
+            .on("cargo outdated", 2, "", "error: failed to resolve\n");
+        let map = scan(&nonzero);
+        assert_eq!(scorecard_value(&map, "vulnerability_findings"), 3.0);
+        assert_eq!(scorecard_value(&map, "audit_exit_code"), 1.0);
+        assert_eq!(scorecard_value(&map, "outdated_exit_code"), 2.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 1.0);
+        assert_eq!(scorecard_value(&map, "expected_nonzero_exits"), 1.0);
+        let mut phases = PhaseLog::default();
+        let live = LiveContext::new(None, "test", DEPENDENCY_VULNERABILITY_SCAN_SCORECARD);
+        let mut checks = Preconditions::default();
+        dependency_scan_metrics(&config, &mut checks, &live, &mut phases, &nonzero);
+        assert_eq!(phases.status("vulnerabilities"), Some(PhaseStatus::Ok));
+        assert_eq!(phases.status("outdated"), Some(PhaseStatus::Failed));
+
+        // Exit 1 without a report, and a panic, are both unexpected.
+        let broken = ScriptedRunner::default()
+            .on("cargo * --version", 0, "", "")
+            .on("cargo audit --json", 1, "", "error: couldn't load Cargo.lock\n")
+            .on("cargo outdated", 101, "", "thread 'main' panicked\n");
+        let map = scan(&broken);
+        // Unparseable output and the exit code each count for cargo-audit.
+        assert_eq!(scorecard_value(&map, "scan_errors"), 3.0);
+        assert_eq!(scorecard_value(&map, "expected_nonzero_exits"), 0.0);
+
+        let malformed = ScriptedRunner::default()
+            .on("cargo * --version", 0, "", "")
+            .on("cargo audit --json", 0, "{\"vulnerabilities\": [", "")
+            .on("cargo outdated", 0, "not json", "");
+        let map = scan(&malformed);
+        assert_eq!(scorecard_value(&map, "vulnerability_findings"), 0.0);
+        assert_eq!(scorecard_value(&map, "outdated_dependencies"), 0.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 2.0);
+
+        // Without cargo-audit or a snapshot, the vulnerability half is skipped.
+        let missing = ScriptedRunner::default();
+        let mut phases = PhaseLog::default();
+        let map = dependency_scan_metrics(
+            &config,
+            &mut Preconditions::default(),
+            &LiveContext::new(None, "test", DEPENDENCY_VULNERABILITY_SCAN_SCORECARD),
+            &mut phases,
+            &missing,
+        );
+        assert_eq!(scorecard_value(&map, "tool_available"), 0.0);
+        assert_eq!(scorecard_value(&map, "audit_exit_code"), -1.0);
+        assert_eq!(scorecard_value(&map, "vulnerability_scanner_builtin"), 0.0);
+        assert_eq!(scorecard_value(&map, "vulnerability_scan_skipped"), 1.0);
+        assert_eq!(scorecard_value(&map, "outdated_scanner_builtin"), 1.0);
+        assert_eq!(phases.status("vulnerabilities"), Some(PhaseStatus::Skipped));
+        assert_eq!(
+            missing.calls(),
+            ["cargo audit --version", "cargo outdated --version"]
+        );
+
+        let configured = DependencyScanConfig {
+            osv_snapshot: Some(fixtures::scan_osv_path().display().to_string()),
+            ..DependencyScanConfig::default()
+        };
+        let map = dependency_scan_metrics(
+            &configured,
+            &mut Preconditions::default(),
+            &LiveContext::new(None, "test", DEPENDENCY_VULNERABILITY_SCAN_SCORECARD),
+            &mut PhaseLog::default(),
+            &ScriptedRunner::default(),
+        );
+        assert_eq!(scorecard_value(&map, "vulnerability_scanner_builtin"), 1.0);
+        assert_eq!(scorecard_value(&map, "vulnerability_scan_skipped"), 0.0);
+        assert_eq!(scorecard_value(&map, "builtin_advisories_loaded"), 2.0);
+    }
+
+    #[test]
+    fn static_lint_handles_scripted_tool_outcomes() {
+        let config = StaticLintConfig::default();
+        let data_dir = DataDir::new(env::temp_dir());
+        let lint = |runner: &ScriptedRunner| {
+            static_lint_metrics(
+                &config,
+                &data_dir,
+                &mut Preconditions::default(),
+                &mut PhaseLog::default(),
+                runner,
+            )
+        };
+        let metadata = fixtures::clippy_metadata();
+        let messages = fixtures::clippy_workspace_messages();
+        let workspace = clippy::parse_metadata(metadata).unwrap();
+        let expected = parse_clippy_messages(messages.lines(), &workspace.members);
+
+        let happy = ScriptedRunner::default()
+            .on("cargo clippy --version", 0, "clippy 0.1.95\n", "")
+            .on("cargo metadata", 0, metadata, "")
+            .on("cargo clippy", 0, messages, "");
+        let map = lint(&happy);
+        assert_eq!(scorecard_value(&map, "tool_available"), 1.0);
+        assert_eq!(scorecard_value(&map, "lint_exit_code"), 0.0);
+        let mut phases = PhaseLog::default();
+        static_lint_metrics(&config, &data_dir, &mut Preconditions::default(), &mut phases, &happy);
+        assert_eq!(phases.status("clippy"), Some(PhaseStatus::Ok));
+        let verify = &phases.to_value()[1];
+        assert_eq!(verify["name"], "verify_fixes");
+        assert_eq!(verify["status"], "skipped");
+        assert_eq!(verify["error"], "not requested");
+        assert_eq!(scorecard_value(&map, "finding_count"), expected.totals.total());
+        assert_eq!(scorecard_value(&map, "crates_scanned"), expected.per_crate.len() as f64);
+        assert_eq!(scorecard_value(&map, "scan_errors"), expected.parse_errors);
+        assert_eq!(
+            scorecard_value(&map, "output_bytes_captured"),
+            messages.len() as f64
+        );
+
+        let nonzero = ScriptedRunner::default()
+            .on("cargo clippy --version", 0, "", "")
+            .on("cargo metadata", 0, metadata, "")
+            .on("cargo clippy", 101, "", "error: could not compile\n");
+        let map = lint(&nonzero);
+        assert_eq!(scorecard_value(&map, "lint_exit_code"), 101.0);
+        assert_eq!(scorecard_value(&map, "finding_count"), 0.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 1.0);
+        assert_eq!(scorecard_value(&map, "expected_nonzero_exits"), 0.0);
+
+        // Denied lints fail the build with 101, alongside their findings.
+        let denied = ScriptedRunner::default()
+            .on("cargo clippy --version", 0, "", "")
+            .on("cargo metadata", 0, metadata, "")
+            .on("cargo clippy", 101, messages, "error: could not compile\n");
+        let map = lint(&denied);
+        assert_eq!(scorecard_value(&map, "lint_exit_code"), 101.0);
+        assert_eq!(scorecard_value(&map, "finding_count"), expected.totals.total());
+        assert_eq!(scorecard_value(&map, "scan_errors"), expected.parse_errors);
+        assert_eq!(scorecard_value(&map, "expected_nonzero_exits"), 1.0);
+
+        let malformed = ScriptedRunner::default()
+            .on("cargo clippy --version", 0, "", "")
+            .on("cargo metadata", 0, "{\"packages\": ", "")
+            .on("cargo clippy", 0, messages, "");
+        let map = lint(&malformed);
+        assert_eq!(scorecard_value(&map, "lint_exit_code"), -1.0);
+        assert_eq!(scorecard_value(&map, "finding_count"), 0.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 1.0);
+        assert!(!malformed.calls().iter().any(|call| call.starts_with("cargo clippy --workspace")));
+
+        let map = lint(&ScriptedRunner::default());
+        assert_eq!(scorecard_value(&map, "tool_available"), 0.0);
+        assert_eq!(scorecard_value(&map, "lint_exit_code"), -1.0);
+        assert_eq!(scorecard_value(&map, "scan_errors"), 1.0);
+    }
+
+    #[test]
+    fn test_reliability_counts_scripted_failures() {
+        let passing = ScriptedRunner::default().on("cargo test", 0, "test result: ok\n", "");
+        let live = LiveContext::new(None, "test", TEST_ROBUSTNESS_RELIABILITY);
+        let map = test_reliability_metrics(&passing, 3, &live);
+        assert_eq!(scorecard_value(&map, "failed_iterations"), 0.0);
+        assert_eq!(scorecard_value(&map, "flaky_rate"), 0.0);
+        let runs = |runner: &ScriptedRunner| {
+            let calls = runner.calls();
+            calls.into_iter().filter(|call| call.starts_with("cargo test")).collect::<Vec<_>>()
+        };
+        assert_eq!(runs(&passing).len(), 3);
+        // A result line without a time names no binary.
+        assert_eq!(scorecard_value(&map, "slowest_binary_seconds"), -1.0);
+
+        // Stable output times binaries only; nightly is asked for JSON.
+        let stable = ScriptedRunner::default()
+            .on("rustc --version", 0, "rustc 1.80.0 (051478957 2024-07-21)\n", "")
+            .on("cargo test", 0, fixtures::cargo_test_stable(), "");
+        let map = test_reliability_metrics(&stable, 2, &live);
+        assert_eq!(scorecard_value(&map, "slowest_binary_seconds"), 12.48);
+        assert_eq!(scorecard_value(&map, "total_test_binaries"), 3.0);
+        assert_eq!(scorecard_value(&map, "timed_tests"), 0.0);
+        assert!(!runs(&stable)[0].contains("--format"));
+        let nightly = ScriptedRunner::default()
+            .on("rustc --version", 0, "rustc 1.83.0-nightly (eb4e23467 2024-10-09)\n", "")
+            .on("cargo test", 0, fixtures::cargo_test_json(), "");
+        let map = test_reliability_metrics(&nightly, 2, &live);
+        assert_eq!(scorecard_value(&map, "timed_tests"), 5.0);
+        let timing = &live.diagnostics().unwrap()["test_timing"];
+        assert_eq!(timing["slowest_tests"][0]["name"], "end_to_end_run_writes_records");
+        assert_eq!(timing["slowest_tests"][0]["seconds"], 11.620334);
+        assert!(runs(&nightly)[0].ends_with("-- -Z unstable-options --format json --report-time"));
+
+        let failing = ScriptedRunner::default().on("cargo test", 101, "test result: FAILED\n", "");
+        let map = test_reliability_metrics(&failing, 2, &live);
+        assert_eq!(scorecard_value(&map, "failed_iterations"), 2.0);
+        assert_eq!(scorecard_value(&map, "flaky_rate"), 1.0);
+
+        // Without cargo every iteration fails to spawn.
+        let map = test_reliability_metrics(&ScriptedRunner::default(), 2, &live);
+        assert_eq!(scorecard_value(&map, "failed_iterations"), 2.0);
+        assert_eq!(scorecard_value(&map, "output_bytes_captured"), 0.0);
+        // No child ran, so there is no peak to report.
+        assert_eq!(scorecard_value(&map, "peak_rss_kb"), -1.0);
+    }
+
+    #[test]
+    fn build_startup_counts_scripted_build_failures() {
+        // `--noop` startup still spawns this test binary, which rejects the
+        // flag; only the build's share of operation_errors is compared.
+        let build_errors = |runner: &ScriptedRunner| {
+            let map = build_startup_metrics(runner, &mut PhaseLog::default());
+            assert_eq!(scorecard_value(&map, "static_variant_available"), 0.0);
+            scorecard_value(&map, "operation_errors")
+        };
+        let happy = build_errors(&ScriptedRunner::default().on("cargo build", 0, "", ""));
+        let failed = build_errors(&ScriptedRunner::default().on("cargo build", 101, "", "error\n"));
+        let missing = build_errors(&ScriptedRunner::default());
+        assert_eq!(failed, happy + 1.0);
+        assert_eq!(missing, happy + 1.0);
+
+        // A failed build is its own phase; startup is still measured.
+        let mut phases = PhaseLog::default();
+        let runner = ScriptedRunner::default().on("cargo build", 101, "", "error\n");
+        let map = build_startup_metrics(&runner, &mut phases);
+        assert_eq!(phases.status("build"), Some(PhaseStatus::Failed));
+        assert!(phases.status("startup").is_some());
+        assert!(scorecard_value(&map, "startup_seconds") >= 0.0);
+        assert_eq!(phases.to_value()[0]["error"], "cargo build exited with 101");
+
+        // A musl sysroot whose build fails counts as one more error.
+        let triple = format!("{}-unknown-linux-musl", env::consts::ARCH);
+        let sysroot = env::temp_dir().join(format!("scripted-sysroot-{}", std::process::id()));
+        fs::create_dir_all(sysroot.join("lib/rustlib").join(&triple)).unwrap();
+        let runner = ScriptedRunner::default()
+            .on("rustc --print sysroot", 0, &format!("{}\n", sysroot.display()), "")
+            .on("cargo build * * --target", 101, "", "error: can't find crate for `std`\n")
+            .on("cargo build", 0, "", "");
+        let map = build_startup_metrics(&runner, &mut PhaseLog::default());
+        if env::consts::OS == "linux" {
+            assert_eq!(scorecard_value(&map, "static_variant_available"), 1.0);
+            assert_eq!(scorecard_value(&map, "operation_errors"), happy + 1.0);
+            assert!(!map.contains_key("static_startup_seconds"));
+        }
+        let _ = fs::remove_dir_all(sysroot);
+    }
+
+    #[test]
+    fn only_selected_records_with_failed_phases_are_degraded() {
+        let failed = json!([{"name": "build", "status": "failed", "duration_seconds": 1.0}]);
+        let skipped = json!([{"name": "verify", "status": "skipped", "duration_seconds": 0.0}]);
+        let records = [
+            json!({"benchmark_id": "build_startup_feedback_loop", "phases": failed}),
+            json!({"benchmark_id": "static_lint_scorecard", "phases": skipped}),
+            json!({
+                "benchmark_id": "dependency_vulnerability_scan_scorecard",
+                "selected_for_comparison": false,
+                "phases": failed,
+            }),
+            json!({"benchmark_id": "cpu_monte_carlo_pi"}),
+        ];
+        assert_eq!(degraded_benchmarks(&records), ["build_startup_feedback_loop"]);
+    }
+
+    #[test]
+    fn failed_records_carry_their_failure_context() {
+        let ctx = RunContext {
+            run_id: "failure".to_string(),
+            environment: environment_block(None, false),
+            harness_fingerprint: fingerprint::harness_fingerprint(),
+            data_dir: DataDir::new(env::temp_dir()),
+            live: None,
+            calibration: None,
+            repeats: Repeats::default(),
+            namespace: None,
+            clock_suspect: false,
+            replay_overrides: Vec::new(),
+        };
+        let parameters = json!({"samples": 3});
+        let panicking = Benchmark::new(BUILD_STARTUP_FEEDBACK_LOOP, |_, _, phases| {
+            phases.enter("build");
+            phases.record("build", Duration::ZERO, Ok(()));
+            phases.enter("startup");
+            panic!("deliberate failure");
+        });
+        let record = panicking.with_parameters(parameters.clone()).record(&ctx);
+        assert_eq!(record["failure"]["phase"], "startup");
+        assert_eq!(record["failure"]["parameters"], parameters);
+        assert_eq!(record["failure"]["panic"]["message"], "deliberate failure");
+        assert_eq!(record["failure"]["recent_progress"], json!([]));
+        assert_eq!(record["metrics"]["benchmark_panicked"]["value"], 1.0);
+        assert_eq!(phases::failures(&record), ["startup: panicked: deliberate failure"]);
+        assert_eq!(degraded_benchmarks(&[record]), [BUILD_STARTUP_FEEDBACK_LOOP]);
+
+        let failing = Benchmark::new(BUILD_STARTUP_FEEDBACK_LOOP, |_, _, phases| {
+            phases.record("build", Duration::ZERO, Err("cargo build exited with 101".to_string()));
+            Metrics::new()
+        });
+        let record = failing.with_parameters(parameters.clone()).record(&ctx);
+        assert_eq!(record["failure"]["phase"], "build");
+        assert_eq!(record["failure"]["parameters"], parameters);
+        assert!(record["failure"].get("panic").is_none());
+
+        let passing = Benchmark::new(BUILD_STARTUP_FEEDBACK_LOOP, |_, _, phases| {
+            phases.record("build", Duration::ZERO, Ok(()));
+            Metrics::new()
+        });
+        assert!(passing.record(&ctx).get("failure").is_none());
+    }
+
+    #[test]
+    fn interactive_selection_matches_the_equivalent_flags() {
+        let Ok(Cli::Run(mut picked)) = parse_args(&args(&["--output", "o.json", "--interactive"]))
+        else {
+            panic!("--interactive did not parse");
+        };
+        let selection = interactive::Selection {
+            benchmarks: vec![CPU_MONTE_CARLO_PI, BUILD_STARTUP_FEEDBACK_LOOP],
+            auto_preset: true,
+        };
+        apply_selection(&mut picked, selection);
+        let flags = [
+            "--output",
+            "o.json",
+            "--interactive",
+            "--benchmarks",
+            "build_startup_feedback_loop,cpu_monte_carlo_pi",
+            "--preset",
+            "auto",
+        ];
+        assert_eq!(Ok(Cli::Run(picked)), parse_args(&args(&flags)));
+
+        let configs = BenchmarkConfigs::default();
+        let data_dir = DataDir::new(env::temp_dir());
+        let rows = progress_rows(&configs, &data_dir, &[CPU_MONTE_CARLO_PI], None);
+        assert_eq!(rows, [(CPU_MONTE_CARLO_PI, None)]);
+    }
+
+    #[test]
+    fn cpu_time_is_reported_with_its_share_of_wall_time() {
+        let mut map = Metrics::new();
+        insert_cpu_time_metrics(&mut map, Some(1.5), Some(3.5), Duration::from_secs(1));
+        assert_eq!(map["cpu_time_seconds"].value, 2.0);
+        assert_eq!(map["cpu_time_seconds"].unit, "s");
+        assert_eq!(map["cpu_utilization"].value, 2.0);
+        assert_eq!(map["cpu_utilization"].unit, "ratio");
+        insert_cpu_time_metrics(&mut map, None, Some(3.5), Duration::from_secs(1));
+        assert_eq!(map["cpu_time_seconds"].value, -1.0);
+        assert_eq!(map["cpu_utilization"].value, -1.0);
+        insert_cpu_time_metrics(&mut map, Some(1.0), Some(1.0), Duration::ZERO);
+        assert_eq!(map["cpu_time_seconds"].value, 0.0);
+        assert_eq!(map["cpu_utilization"].value, -1.0);
+    }
+
+    /// Process CPU time counts every test thread, so the check reruns this
+    /// test alone in a child process.
+    #[cfg(unix)]
+    #[test]
+    fn monte_carlo_cpu_time_tracks_wall_time() {
+        const NAME: &str = "tests::monte_carlo_cpu_time_tracks_wall_time";
+        if env::var_os("BENCHMARK_CPU_TIME_PROBE").is_none() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", NAME, "--test-threads=1"])
+                .env("BENCHMARK_CPU_TIME_PROBE", "1")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{stdout}");
+            assert!(stdout.contains("1 passed"), "{stdout}");
+            return;
+        }
+        let dir = env::temp_dir().join(format!("cpu-time-{}", std::process::id()));
+        let mut configs = smoke_configs(&dir.join("etl.jsonl.gz"));
+        configs.monte_carlo.samples = 5_000_000;
+        let ctx = run_context(&configs, DataDir::new(dir), None);
+        let policy = RetryPolicy::default();
+        let records = run(&configs, &[CPU_MONTE_CARLO_PI], &ctx, &policy, &mut |_| {});
+        let metrics = &records[0]["metrics"];
+        let cpu = metrics["cpu_time_seconds"]["value"].as_f64().unwrap();
+        let utilization = metrics["cpu_utilization"]["value"].as_f64().unwrap();
+        assert!(cpu > 0.0, "{cpu}");
+        assert!((0.8..=1.2).contains(&utilization), "{utilization}");
+    }
+
+    #[test]
+    fn latency_percentiles_are_reported_in_ms_or_minus_one() {
+        let mut stats = WorkerStats::default();
+        for ms in (1..100).chain([250]) {
+            stats.latencies_us.push(Duration::from_millis(ms));
+        }
+        let mut map = Metrics::new();
+        insert_latency_metrics(&mut map, &mut stats);
+        assert_eq!(map["latency_p50_ms"].value, 50.0);
+        assert_eq!(map["latency_p95_ms"].value, 95.0);
+        assert_eq!(map["latency_p99_ms"].value, 99.0);
+        assert_eq!(map["latency_p99_ms_n"].value, 100.0);
+        assert_eq!(map["latency_max_ms"].value, 250.0);
+        assert_eq!(map["latency_max_ms"].unit, "ms");
+
+        stats = WorkerStats {
+            errors: 4,
+            ..WorkerStats::default()
+        };
+        let mut map = Metrics::new();
+        insert_latency_metrics(&mut map, &mut stats);
+        for key in ["latency_p50_ms", "latency_p95_ms", "latency_p99_ms", "latency_max_ms"] {
+            assert_eq!(map[key].value, -1.0, "{key}");
+        }
+        assert_eq!(map["latency_p50_ms_n"].value, 0.0);
+    }
+
+    #[test]
+    fn throughput_and_error_rate_guard_against_zero() {
+        let stats = WorkerStats {
+            completed: 30,
+            errors: 10,
+            ..WorkerStats::default()
+        };
+        let mut map = Metrics::new();
+        insert_throughput_metrics(&mut map, &stats, 2.0);
+        assert_eq!(map["throughput_rps"].value, 15.0);
+        assert_eq!(map["throughput_rps"].unit, "req/s");
+        assert_eq!(map["error_rate"].value, 0.25);
+        assert_eq!(map["error_rate"].unit, "ratio");
+
+        let mut map = Metrics::new();
+        insert_throughput_metrics(&mut map, &WorkerStats::default(), 0.0);
+        assert_eq!(map["throughput_rps"].value, 0.0);
+        assert_eq!(map["error_rate"].value, 0.0);
+    }
+
+    #[test]
+    fn peak_rss_reports_the_mark_and_its_growth() {
+        let mut map = Metrics::new();
+        insert_peak_rss_metrics(&mut map, Some(40_000), Some(187_344));
+        assert_eq!(map["peak_rss_kb"].value, 187_344.0);
+        assert_eq!(map["peak_rss_delta_kb"].value, 147_344.0);
+        assert_eq!(map["peak_rss_kb"].unit, "kb");
+
+        insert_peak_rss_metrics(&mut map, None, Some(187_344));
+        assert_eq!(map["peak_rss_delta_kb"].value, -1.0);
+        insert_peak_rss_metrics(&mut map, None, None);
+        assert_eq!(map["peak_rss_kb"].value, -1.0);
+
+        let mut peak = None;
+        let usage = |max_rss_kb| Some(ChildUsage { max_rss_kb, ..ChildUsage::default() });
+        for child in [usage(900), None, usage(1_200), usage(300)] {
+            fold_child_peak_rss(&mut peak, child);
+        }
+        assert_eq!(peak, Some(1_200));
+    }
+
+    #[test]
+    fn swap_metrics_warn_on_a_nonzero_delta() {
+        let before = SwapCounters {
+            swap_in_pages: 5,
+            swap_out_pages: 9,
+        };
+        let after = SwapCounters {
+            swap_in_pages: 5,
+            swap_out_pages: 12,
+        };
+        let live = LiveContext::new(None, "test", STRING_JSON_PARSE_TRANSFORM);
+        let mut map = Metrics::new();
+        insert_swap_metrics(&mut map, &live, Some(before), Some(after));
+        assert_eq!(map["swap_out_pages"].value, 3.0);
+        let warnings = live.warnings().unwrap();
+        assert_eq!(warnings[0]["code"], "swapped");
+        assert_eq!(warnings[0]["details"], json!({"swap_in_pages": 0, "swap_out_pages": 3}));
+        assert_eq!(map["swapped"].value, 1.0);
+
+        let quiet = LiveContext::new(None, "test", STRING_JSON_PARSE_TRANSFORM);
+        insert_swap_metrics(&mut map, &quiet, Some(before), Some(before));
+        assert_eq!(quiet.warnings(), None);
+        assert_eq!(map["swapped"].value, 0.0);
+        let mut unsupported = Metrics::new();
+        insert_swap_metrics(&mut unsupported, &quiet, None, Some(after));
+        assert_eq!(unsupported["swap_sampling_supported"].value, 0.0);
+        assert_eq!(quiet.warnings(), None);
     }
 }
@@ -0,0 +1,334 @@
+//! `--matrix`: runs the cartesian product of per-benchmark parameter lists,
+//! one record per cell, and picks the best cell per target metric.
+//!
+//! ```json
+//! {
+//!   "max_cells": 64,
+//!   "benchmarks": {
+//!     "io_concurrent_http_client": {
+//!       "params": {"request_count": [100, 400], "concurrency": [1, 4, 16]},
+//!       "targets": {"goodput_mb_s": "max", "runtime_seconds": "min"}
+//!     }
+//!   }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::retry;
+
+pub(crate) const DEFAULT_MAX_CELLS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Goal {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MatrixEntry {
+    /// Values per config field; every combination becomes a cell.
+    pub(crate) params: BTreeMap<String, Vec<Value>>,
+    #[serde(default)]
+    pub(crate) targets: BTreeMap<String, Goal>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Matrix {
+    #[serde(default = "default_max_cells")]
+    pub(crate) max_cells: usize,
+    pub(crate) benchmarks: BTreeMap<String, MatrixEntry>,
+}
+
+fn default_max_cells() -> usize {
+    DEFAULT_MAX_CELLS
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Cell {
+    pub(crate) benchmark_id: String,
+    /// Position in the whole matrix, stable for a given file.
+    pub(crate) index: usize,
+    /// One value per field, applied like `BENCHMARK_PARAMS_<ID>`.
+    pub(crate) params: Map<String, Value>,
+}
+
+impl Cell {
+    /// Attached to the cell's record as `matrix_cell`.
+    pub(crate) fn to_value(&self) -> Value {
+        json!({"index": self.index, "params": self.params})
+    }
+}
+
+pub(crate) fn parse(text: &str) -> Result<Matrix, String> {
+    let matrix: Matrix =
+        serde_json::from_str(text).map_err(|err| format!("invalid matrix file: {err}"))?;
+    for (benchmark_id, entry) in &matrix.benchmarks {
+        if let Some((field, _)) = entry.params.iter().find(|(_, values)| values.is_empty()) {
+            return Err(format!("matrix: {benchmark_id}.{field} has no values"));
+        }
+    }
+    Ok(matrix)
+}
+
+pub(crate) fn load(path: &Path) -> Result<Matrix, String> {
+    let text =
+        fs::read_to_string(path).map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+    parse(&text)
+}
+
+/// Cells in benchmark order, fields in name order with the last varying
+/// fastest. Fails before anything runs when the product exceeds
+/// `max_cells`.
+pub(crate) fn expand(matrix: &Matrix) -> Result<Vec<Cell>, String> {
+    let total: usize = matrix
+        .benchmarks
+        .values()
+        .map(|entry| entry.params.values().map(Vec::len).product::<usize>())
+        .sum();
+    if total > matrix.max_cells {
+        return Err(format!(
+            "matrix: {total} cells exceed the cap of {}; raise max_cells to run them",
+            matrix.max_cells
+        ));
+    }
+    let mut cells = Vec::with_capacity(total);
+    for (benchmark_id, entry) in &matrix.benchmarks {
+        let mut combinations = vec![Map::new()];
+        for (field, values) in &entry.params {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|partial| {
+                    values.iter().map(move |value| {
+                        let mut next = partial.clone();
+                        next.insert(field.clone(), value.clone());
+                        next
+                    })
+                })
+                .collect();
+        }
+        for params in combinations {
+            cells.push(Cell {
+                benchmark_id: benchmark_id.clone(),
+                index: cells.len(),
+                params,
+            });
+        }
+    }
+    Ok(cells)
+}
+
+/// Expected wall time: each cell costs its benchmark's `runtime_seconds` in
+/// `previous` (an earlier results file). Benchmarks with no timing there are
+/// returned instead of guessed.
+pub(crate) fn estimate_seconds(cells: &[Cell], previous: &[Value]) -> (f64, Vec<String>) {
+    let mut seconds = 0.0;
+    let mut unknown: Vec<String> = Vec::new();
+    for cell in cells {
+        match previous_runtime(&cell.benchmark_id, previous) {
+            Some(runtime) => seconds += runtime,
+            None if !unknown.contains(&cell.benchmark_id) => {
+                unknown.push(cell.benchmark_id.clone())
+            }
+            None => {}
+        }
+    }
+    (seconds, unknown)
+}
+
+/// `runtime_seconds` of the first selected record of `benchmark_id` in
+/// `previous`.
+pub(crate) fn previous_runtime(benchmark_id: &str, previous: &[Value]) -> Option<f64> {
+    previous
+        .iter()
+        .filter(|record| record["benchmark_id"] == benchmark_id)
+        .filter(|record| retry::is_selected(record))
+        .find_map(|record| record["metrics"]["runtime_seconds"]["value"].as_f64())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct BestCell {
+    pub(crate) benchmark_id: String,
+    pub(crate) metric: String,
+    pub(crate) goal: Goal,
+    pub(crate) cell: usize,
+    pub(crate) value: f64,
+    pub(crate) params: Map<String, Value>,
+}
+
+/// The winning cell per benchmark and target among the selected `records`
+/// carrying `matrix_cell`; targets no cell reported are left out.
+pub(crate) fn best_cells(matrix: &Matrix, records: &[Value]) -> Vec<BestCell> {
+    let mut best = Vec::new();
+    for (benchmark_id, entry) in &matrix.benchmarks {
+        for (metric, goal) in &entry.targets {
+            let candidates = records.iter().filter_map(|record| {
+                if record["benchmark_id"] != benchmark_id.as_str() || !retry::is_selected(record) {
+                    return None;
+                }
+                let cell = record.get("matrix_cell")?;
+                let value = record["metrics"][metric]["value"].as_f64()?;
+                Some((cell, value))
+            });
+            let winner = candidates.reduce(|current, candidate| {
+                let better = match goal {
+                    Goal::Min => candidate.1 < current.1,
+                    Goal::Max => candidate.1 > current.1,
+                };
+                if better {
+                    candidate
+                } else {
+                    current
+                }
+            });
+            if let Some((cell, value)) = winner {
+                best.push(BestCell {
+                    benchmark_id: benchmark_id.clone(),
+                    metric: metric.clone(),
+                    goal: *goal,
+                    cell: cell["index"].as_u64().unwrap_or(0) as usize,
+                    value,
+                    params: cell["params"].as_object().cloned().unwrap_or_default(),
+                });
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: &str = r#"{
+        "benchmarks": {
+            "io_concurrent_http_client": {
+                "params": {"request_count": [100, 400], "concurrency": [1, 4, 16]},
+                "targets": {"goodput_mb_s": "max", "runtime_seconds": "min"}
+            },
+            "cpu_monte_carlo_pi": {"params": {"samples": [1000]}}
+        }
+    }"#;
+
+    #[test]
+    fn parses_and_rejects_malformed_files() {
+        let matrix = parse(GRID).unwrap();
+        assert_eq!(matrix.max_cells, DEFAULT_MAX_CELLS);
+        assert_eq!(
+            matrix.benchmarks["io_concurrent_http_client"].targets["goodput_mb_s"],
+            Goal::Max
+        );
+        assert!(parse(r#"{"benchmarks": {}, "cells": 3}"#)
+            .unwrap_err()
+            .contains("unknown field"));
+        assert!(parse(r#"{"benchmarks": {"x": {"params": {"rows": []}}}}"#)
+            .unwrap_err()
+            .contains("x.rows has no values"));
+        assert!(
+            parse(r#"{"benchmarks": {"x": {"params": {}, "targets": {"m": "best"}}}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn expands_the_cartesian_product_in_order() {
+        let cells = expand(&parse(GRID).unwrap()).unwrap();
+        assert_eq!(cells.len(), 7);
+        assert_eq!(cells[0].benchmark_id, "cpu_monte_carlo_pi");
+        let http: Vec<Value> = cells[1..]
+            .iter()
+            .map(|cell| Value::Object(cell.params.clone()))
+            .collect();
+        assert_eq!(
+            http,
+            [
+                json!({"concurrency": 1, "request_count": 100}),
+                json!({"concurrency": 1, "request_count": 400}),
+                json!({"concurrency": 4, "request_count": 100}),
+                json!({"concurrency": 4, "request_count": 400}),
+                json!({"concurrency": 16, "request_count": 100}),
+                json!({"concurrency": 16, "request_count": 400}),
+            ]
+        );
+        assert_eq!(
+            cells.iter().map(|cell| cell.index).collect::<Vec<_>>(),
+            (0..7).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            cells[3].to_value(),
+            json!({"index": 3, "params": {"concurrency": 4, "request_count": 100}})
+        );
+
+        let no_fields = parse(r#"{"benchmarks": {"cpu_monte_carlo_pi": {"params": {}}}}"#).unwrap();
+        assert_eq!(expand(&no_fields).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn refuses_grids_over_the_cap() {
+        let mut matrix = parse(GRID).unwrap();
+        matrix.max_cells = 6;
+        let err = expand(&matrix).unwrap_err();
+        assert!(err.contains("7 cells exceed the cap of 6"), "{err}");
+        matrix.max_cells = 7;
+        assert!(expand(&matrix).is_ok());
+
+        let big = r#"{"benchmarks": {"x": {"params": {"a": [1,2,3,4,5,6,7,8,9], "b": [1,2,3,4,5,6,7,8]}}}}"#;
+        assert!(expand(&parse(big).unwrap()).is_err());
+    }
+
+    #[test]
+    fn picks_the_best_cell_per_target() {
+        let matrix = parse(GRID).unwrap();
+        let cells = expand(&matrix).unwrap();
+        let record = |cell: &Cell, goodput: f64, runtime: f64| {
+            json!({
+                "benchmark_id": cell.benchmark_id,
+                "matrix_cell": cell.to_value(),
+                "metrics": {
+                    "goodput_mb_s": {"value": goodput, "unit": "mb/s"},
+                    "runtime_seconds": {"value": runtime, "unit": "s"}
+                }
+            })
+        };
+        let records = [
+            record(&cells[1], 100.0, 1.0),
+            record(&cells[4], 900.0, 0.4),
+            record(&cells[5], 800.0, 0.2),
+        ];
+        let best = best_cells(&matrix, &records);
+        assert_eq!(best.len(), 2);
+        assert_eq!(
+            (best[0].metric.as_str(), best[0].cell, best[0].value),
+            ("goodput_mb_s", 4, 900.0)
+        );
+        assert_eq!(
+            (best[1].metric.as_str(), best[1].cell, best[1].value),
+            ("runtime_seconds", 5, 0.2)
+        );
+        assert_eq!(
+            Value::Object(best[1].params.clone()),
+            json!({"concurrency": 16, "request_count": 100})
+        );
+    }
+
+    #[test]
+    fn estimates_from_previous_runtimes() {
+        let cells = expand(&parse(GRID).unwrap()).unwrap();
+        let previous = [
+            json!({"benchmark_id": "io_concurrent_http_client", "selected_for_comparison": false,
+                   "metrics": {"runtime_seconds": {"value": 9.0, "unit": "s"}}}),
+            json!({"benchmark_id": "io_concurrent_http_client",
+                   "metrics": {"runtime_seconds": {"value": 1.5, "unit": "s"}}}),
+        ];
+        let (seconds, unknown) = estimate_seconds(&cells, &previous);
+        assert_eq!(seconds, 9.0);
+        assert_eq!(unknown, ["cpu_monte_carlo_pi"]);
+        assert_eq!(estimate_seconds(&cells, &[]).1.len(), 2);
+    }
+}
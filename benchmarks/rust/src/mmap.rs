@@ -0,0 +1,235 @@
+//! Read-only file mappings for the ETL `input_mode=mmap` pass.
+//!
+//! Touching a mapped page past the end of a file that shrank after it was
+//! mapped raises `SIGBUS` instead of returning an error. `MappedReader`
+//! hands the mapping out in `CHECK_BYTES` chunks and checks the file still
+//! covers a chunk before the first byte of it is read, so a truncated file
+//! ends the scan with `UnexpectedEof`. The check narrows the window rather
+//! than closing it: a file cut short between the check and the read still
+//! faults. The runner maps only files it wrote itself, so nothing else is
+//! expected to truncate them.
+
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+
+/// Bytes handed out per length check; one `fstat` per MiB is noise next to
+/// parsing it.
+pub(crate) const CHECK_BYTES: usize = 1024 * 1024;
+
+/// A whole file mapped read-only, at the length it had when mapped.
+pub(crate) struct Mapping {
+    file: File,
+    /// Null for an empty file, which cannot be mapped.
+    ptr: *const u8,
+    len: usize,
+}
+
+impl Mapping {
+    #[cfg(unix)]
+    pub(crate) fn open(path: &Path) -> io::Result<Mapping> {
+        use std::os::fd::AsRawFd;
+
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        if len == 0 {
+            return Ok(Mapping {
+                file,
+                ptr: std::ptr::null(),
+                len,
+            });
+        }
+        // SAFETY: a fresh private read-only mapping of `len` bytes of an open
+        // file; the kernel picks the address and nothing else aliases it.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            file,
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn open(_path: &Path) -> io::Result<Mapping> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "memory-mapped input needs a unix build",
+        ))
+    }
+
+    /// Errs when the file no longer holds the mapping's first `end` bytes.
+    fn check(&self, end: usize) -> io::Result<()> {
+        let file_len = self.file.metadata()?.len();
+        if file_len < end as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("file truncated to {file_len} bytes after {} were mapped", self.len),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes `start..end` of the mapping; `end` must have passed `check`.
+    fn slice(&self, start: usize, end: usize) -> &[u8] {
+        assert!(start <= end && end <= self.len, "{start}..{end} outside {}", self.len);
+        if start == end {
+            return &[];
+        }
+        // SAFETY: `start..end` lies inside the `len` bytes mapped in `open`,
+        // which stay mapped until `self` drops, and the file backed them
+        // when checked.
+        unsafe { std::slice::from_raw_parts(self.ptr.add(start), end - start) }
+    }
+
+    /// The mapping from the start, `CHECK_BYTES` at a time.
+    pub(crate) fn reader(&self) -> MappedReader<'_> {
+        self.reader_with_chunk(CHECK_BYTES)
+    }
+
+    fn reader_with_chunk(&self, chunk: usize) -> MappedReader<'_> {
+        MappedReader {
+            mapping: self,
+            chunk: chunk.max(1),
+            pos: 0,
+            checked_end: 0,
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if !self.ptr.is_null() {
+            // SAFETY: `ptr` and `len` are exactly what `mmap` returned, and no
+            // slice of the mapping outlives `self`.
+            unsafe {
+                libc::munmap(self.ptr as *mut libc::c_void, self.len);
+            }
+        }
+    }
+}
+
+/// `BufRead` over a `Mapping` that never copies: each `fill_buf` returns
+/// the mapped bytes themselves, up to the end of the last checked chunk.
+pub(crate) struct MappedReader<'a> {
+    mapping: &'a Mapping,
+    chunk: usize,
+    pos: usize,
+    /// Bytes before this offset have passed the length check.
+    checked_end: usize,
+}
+
+impl BufRead for MappedReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.checked_end && self.pos < self.mapping.len {
+            let end = (self.pos + self.chunk).min(self.mapping.len);
+            self.mapping.check(end)?;
+            self.checked_end = end;
+        }
+        Ok(self.mapping.slice(self.pos, self.checked_end))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.checked_end);
+    }
+}
+
+impl Read for MappedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::line_reader::{self, Line};
+    use std::env;
+    use std::fs::{self, OpenOptions};
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = env::temp_dir().join(format!("mmap-{name}-{}", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn lines(count: usize) -> Vec<u8> {
+        (0..count).flat_map(|idx| format!("row {idx}\n").into_bytes()).collect()
+    }
+
+    #[test]
+    fn reads_the_same_bytes_as_the_file() {
+        let bytes = lines(500);
+        let path = temp_file("same", &bytes);
+        let mapping = Mapping::open(&path).unwrap();
+        let mut whole = Vec::new();
+        mapping.reader().read_to_end(&mut whole).unwrap();
+        assert_eq!(whole, bytes);
+
+        // Lines split across chunk boundaries come back whole.
+        let mut reader = mapping.reader_with_chunk(7);
+        let mut line = Vec::new();
+        let mut read = Vec::new();
+        while line_reader::read_line(&mut reader, &mut line, 64).unwrap() != Line::Eof {
+            read.extend_from_slice(&line);
+        }
+        assert_eq!(read, bytes);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn an_empty_file_reads_as_empty() {
+        let path = temp_file("empty", b"");
+        let mapping = Mapping::open(&path).unwrap();
+        let mut reader = mapping.reader();
+        assert!(reader.fill_buf().unwrap().is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_file_truncated_after_mapping_ends_the_read_with_an_error() {
+        let page = 4096;
+        let bytes = vec![b'x'; page * 4];
+        let path = temp_file("truncated", &bytes);
+        let mapping = Mapping::open(&path).unwrap();
+        let mut reader = mapping.reader_with_chunk(page);
+        let mut first = vec![0u8; page];
+        reader.read_exact(&mut first).unwrap();
+
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(page as u64).unwrap();
+        let err = reader.fill_buf().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().contains("truncated to 4096 bytes"), "{err}");
+        // The mapping's length is fixed when mapped; growing the file
+        // afterwards adds nothing to it.
+        fs::write(&path, vec![b'y'; page * 8]).unwrap();
+        let mut rest = Vec::new();
+        mapping.reader().read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), page * 4);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_missing_file_is_an_error() {
+        let path = env::temp_dir().join(format!("mmap-missing-{}", std::process::id()));
+        assert_eq!(Mapping::open(&path).err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,431 @@
+//! In-process stand-in for the Python mock server (`run_all.py`), serving
+//! the same `/item/{id}` payloads with optional latency and error injection,
+//! plus `POST /items` for `io_concurrent_http_post` and the `/upload` and
+//! `/stream/{bytes}` endpoints of `io_http_streaming`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::checksum_store::Fnv1a;
+use crate::chunked;
+use crate::rng::{self, next_f64};
+use crate::sampling::{bernoulli, LatencyDistribution};
+use crate::streaming::{fill_payload, format_hash, HASH_TRAILER};
+
+/// Data bytes per chunk of a `/stream` response.
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ChaosConfig {
+    pub(crate) latency: LatencyDistribution,
+    /// Fraction of requests answered with an error status and an empty body.
+    pub(crate) error_rate: f64,
+    /// Injected statuses, picked uniformly; codes outside 100-599 are sent
+    /// as-is to exercise the client's malformed-status handling.
+    pub(crate) error_statuses: Vec<u16>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        ChaosConfig {
+            latency: LatencyDistribution::Fixed { ms: 0.0 },
+            error_rate: 0.0,
+            error_statuses: vec![500],
+        }
+    }
+}
+
+fn reason_phrase(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn injected_status(chaos: &ChaosConfig, state: &mut u64) -> u16 {
+    let count = chaos.error_statuses.len();
+    if count == 0 {
+        return 500;
+    }
+    chaos.error_statuses[((next_f64(state) * count as f64) as usize).min(count - 1)]
+}
+
+/// Same bytes as the Python handler's `json.dumps`.
+pub(crate) fn item_payload(item_id: u64) -> String {
+    format!(
+        "{{\"id\": {item_id}, \"value\": {}, \"name\": \"row-{item_id}\"}}",
+        item_id % 17
+    )
+}
+
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    stop: Sender<()>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+/// A connection being served: a handle on its socket to shut it down with,
+/// and the thread serving it.
+struct Connection {
+    stream: TcpStream,
+    thread: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Binds an ephemeral loopback port; each connection gets its own RNG
+    /// stream of `seed`, indexed in accept order.
+    pub(crate) fn start(chaos: ChaosConfig, seed: u64) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let (stop, stopped) = mpsc::channel();
+        let chaos = Arc::new(chaos);
+        let accept_thread = thread::spawn(move || {
+            let mut connections: Vec<Connection> = Vec::new();
+            let mut accepted = 0;
+            for stream in listener.incoming() {
+                if !matches!(stopped.try_recv(), Err(TryRecvError::Empty)) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let Ok(handle) = stream.try_clone() else {
+                    continue;
+                };
+                connections.retain(|connection| !connection.thread.is_finished());
+                let state = rng::stream(seed, accepted);
+                accepted += 1;
+                let chaos = Arc::clone(&chaos);
+                let thread = thread::spawn(move || {
+                    let _ = serve_connection(stream, &chaos, state);
+                });
+                connections.push(Connection {
+                    stream: handle,
+                    thread,
+                });
+            }
+            // Keep-alive clients would otherwise hold their threads open
+            // past the server.
+            for connection in connections {
+                let _ = connection.stream.shutdown(Shutdown::Both);
+                let _ = connection.thread.join();
+            }
+        });
+        Ok(MockServer {
+            addr,
+            stop,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    pub(crate) fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        // Wake the blocking accept so the loop sees the stop message.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.accept_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// What a request's body came to; bodies are consumed whole so the next
+/// request on the connection starts where it should.
+#[derive(Debug, Default)]
+struct RequestBody {
+    bytes: u64,
+    fnv1a: Fnv1a,
+    /// The body itself, when the route needs more than its hash.
+    kept: Vec<u8>,
+}
+
+impl RequestBody {
+    fn add(&mut self, data: &[u8], keep: bool) {
+        self.bytes += data.len() as u64;
+        self.fnv1a.update(data);
+        if keep {
+            self.kept.extend_from_slice(data);
+        }
+    }
+}
+
+fn read_request_body<R: BufRead>(
+    reader: &mut R,
+    content_length: Option<u64>,
+    chunked: bool,
+    keep: bool,
+) -> io::Result<RequestBody> {
+    let mut body = RequestBody::default();
+    if chunked {
+        // Uploads come from this harness's own clients, so they go uncapped.
+        chunked::read_body(reader, usize::MAX, |data| body.add(data, keep))?;
+        return Ok(body);
+    }
+    let mut remaining = content_length.unwrap_or(0);
+    while remaining > 0 {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        let take = available.len().min(remaining as usize);
+        body.add(&available[..take], keep);
+        reader.consume(take);
+        remaining -= take as u64;
+    }
+    Ok(body)
+}
+
+/// Sends `bytes` of the streaming payload as a chunked body, with its hash
+/// in a trailer.
+fn write_stream<W: Write>(writer: &mut W, bytes: u64, connection: &str) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\
+         Transfer-Encoding: chunked\r\nTrailer: {HASH_TRAILER}\r\nConnection: {connection}\r\n\r\n"
+    );
+    writer.write_all(head.as_bytes())?;
+    let mut hasher = Fnv1a::default();
+    let mut data = vec![0u8; STREAM_CHUNK_BYTES];
+    let mut frame = Vec::with_capacity(STREAM_CHUNK_BYTES + 16);
+    let mut offset = 0;
+    while offset < bytes {
+        let take = (bytes - offset).min(STREAM_CHUNK_BYTES as u64) as usize;
+        fill_payload(offset, &mut data[..take]);
+        hasher.update(&data[..take]);
+        frame.clear();
+        chunked::write_chunk(&mut frame, &data[..take])?;
+        writer.write_all(&frame)?;
+        offset += take as u64;
+    }
+    let hash = format_hash(hasher.finish());
+    chunked::write_last_chunk(writer, &[(HASH_TRAILER, &hash)])?;
+    Ok(())
+}
+
+/// `POST /items`: echoes back the item it was sent, as created, or `400`
+/// for a body that is not one.
+fn created_item(body: &[u8]) -> (u16, String) {
+    let item: Result<Value, _> = serde_json::from_slice(body);
+    match item {
+        Ok(item) if item["value"].is_u64() => (201, item.to_string()),
+        _ => (400, String::new()),
+    }
+}
+
+fn serve_connection(stream: TcpStream, chaos: &ChaosConfig, mut state: u64) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        let mut head_bytes = 0;
+        let read = chunked::read_framing_line(&mut reader, &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        chunked::add_head_bytes(&mut head_bytes, read)?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let path = parts.next().unwrap_or("").to_string();
+        let mut keep_alive = line.trim_end().ends_with("HTTP/1.1");
+        let mut content_length = None;
+        let mut chunked = false;
+        loop {
+            let read = chunked::read_framing_line(&mut reader, &mut line)?;
+            if read == 0 {
+                return Ok(());
+            }
+            chunked::add_head_bytes(&mut head_bytes, read)?;
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let value = value.trim();
+                if name.eq_ignore_ascii_case("connection") {
+                    keep_alive = value.eq_ignore_ascii_case("keep-alive");
+                } else if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.parse::<u64>().ok();
+                } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                    chunked = value.to_ascii_lowercase().ends_with("chunked");
+                }
+            }
+        }
+        let creates_item = method == "POST" && path == "/items";
+        let request_body = read_request_body(&mut reader, content_length, chunked, creates_item)?;
+
+        thread::sleep(chaos.latency.sample(&mut state));
+        let connection = if keep_alive { "keep-alive" } else { "close" };
+        let injected = bernoulli(chaos.error_rate, &mut state);
+        let stream_bytes = path
+            .strip_prefix("/stream/")
+            .and_then(|bytes| bytes.parse::<u64>().ok());
+        if let (false, "GET", Some(bytes)) = (injected, method.as_str(), stream_bytes) {
+            write_stream(reader.get_mut(), bytes, connection)?;
+            if !keep_alive {
+                return Ok(());
+            }
+            continue;
+        }
+        let item_id = path
+            .strip_prefix("/item/")
+            .and_then(|id| id.parse::<u64>().ok());
+        let (code, body) = if injected {
+            (injected_status(chaos, &mut state), String::new())
+        } else if creates_item {
+            created_item(&request_body.kept)
+        } else if method == "POST" && path == "/upload" {
+            let hash = format_hash(request_body.fnv1a.finish());
+            (200, format!("{{\"bytes\": {}, \"fnv1a\": \"{hash}\"}}", request_body.bytes))
+        } else {
+            match item_id {
+                Some(item_id) => (200, item_payload(item_id)),
+                None => (404, String::new()),
+            }
+        };
+        let status = format!("{code} {}", reason_phrase(code));
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: {connection}\r\n\r\n{body}",
+            body.len()
+        );
+        reader.get_mut().write_all(response.as_bytes())?;
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{
+        parse_base_url, run_workers, run_workers_with, ConnectionMode, StatusBucket, WorkerHooks,
+    };
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn serves_python_compatible_items() {
+        let server = MockServer::start(ChaosConfig::default(), 42).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        for mode in [ConnectionMode::PerRequest, ConnectionMode::Persistent] {
+            let stats = run_workers(&target, 40, 20, 4, mode);
+            assert_eq!(stats.errors, 0);
+            assert_eq!(stats.completed, 40);
+            assert_eq!(stats.checksum, 2 * (0..20).map(|id| id % 17).sum::<u64>());
+        }
+        assert_eq!(item_payload(18), r#"{"id": 18, "value": 1, "name": "row-18"}"#);
+    }
+
+    #[test]
+    fn reports_run_wide_progress() {
+        let server = MockServer::start(ChaosConfig::default(), 42).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        let calls = AtomicUsize::new(0);
+        let highest = AtomicUsize::new(0);
+        let on_progress = |done| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            highest.fetch_max(done, Ordering::Relaxed);
+        };
+        let hooks = WorkerHooks {
+            on_progress: &on_progress,
+            ..WorkerHooks::default()
+        };
+        let stats = run_workers_with(&target, 30, 10, 3, ConnectionMode::Persistent, &hooks);
+        assert_eq!(stats.completed, 30);
+        assert_eq!(calls.load(Ordering::Relaxed), 30);
+        assert_eq!(highest.load(Ordering::Relaxed), 30);
+    }
+
+    #[test]
+    fn injects_errors_and_latency() {
+        let chaos = ChaosConfig {
+            latency: LatencyDistribution::Fixed { ms: 2.0 },
+            error_rate: 1.0,
+            ..ChaosConfig::default()
+        };
+        let server = MockServer::start(chaos, 42).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        let stats = run_workers(&target, 5, 5, 1, ConnectionMode::Persistent);
+        assert_eq!(stats.completed, 0);
+        assert_eq!(stats.errors, 5);
+        assert_eq!(stats.statuses.get(&StatusBucket::Code(500)), Some(&5));
+
+        let slow = MockServer::start(
+            ChaosConfig {
+                latency: LatencyDistribution::Fixed { ms: 20.0 },
+                error_rate: 0.0,
+                ..ChaosConfig::default()
+            },
+            42,
+        )
+        .unwrap();
+        let target = parse_base_url(&slow.base_url()).unwrap();
+        let mut stats = run_workers(&target, 3, 3, 1, ConnectionMode::Persistent);
+        assert_eq!(stats.latencies_us.len(), 3);
+        assert!(stats.latencies_us.percentiles(&[0.0])[0] >= 20_000.0);
+    }
+
+    #[test]
+    fn histograms_mixed_statuses_across_workers() {
+        let chaos = ChaosConfig {
+            error_rate: 0.5,
+            error_statuses: vec![404, 503, 999],
+            ..ChaosConfig::default()
+        };
+        let server = MockServer::start(chaos, 7).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        let stats = run_workers(&target, 200, 20, 4, ConnectionMode::Persistent);
+        let ok = stats.statuses.get(&StatusBucket::Code(200)).copied().unwrap_or(0);
+        assert_eq!(ok, stats.completed);
+        assert_eq!(stats.statuses.values().sum::<usize>(), 200);
+        assert_eq!(stats.completed + stats.errors, 200);
+        let buckets: Vec<_> = stats.statuses.keys().copied().collect();
+        assert_eq!(
+            buckets,
+            [
+                StatusBucket::Code(200),
+                StatusBucket::Code(404),
+                StatusBucket::Code(503),
+                StatusBucket::Malformed
+            ]
+        );
+    }
+
+    #[test]
+    fn dropping_the_server_closes_its_port_and_connections() {
+        let server = MockServer::start(ChaosConfig::default(), 42).unwrap();
+        let addr = server.addr;
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /item/1 HTTP/1.1\r\nHost: x\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        assert!(status.starts_with("HTTP/1.1 200"), "{status}");
+
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+        // The rest of the response, then end of stream instead of a hang.
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert!(rest.ends_with(item_payload(1).as_bytes()));
+    }
+}
@@ -0,0 +1,175 @@
+//! Sampled per-phase accounting for row-at-a-time loops: every Nth row is
+//! timed phase by phase, so the split between reading, parsing, and
+//! aggregation is known without a profiler. With sampling off the only cost
+//! per row is one branch.
+
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Read,
+    Parse,
+    Aggregate,
+}
+
+pub(crate) trait Clock {
+    fn now_ns(&self) -> u64;
+}
+
+pub(crate) struct SystemClock(Instant);
+
+impl SystemClock {
+    pub(crate) fn new() -> Self {
+        SystemClock(Instant::now())
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_ns(&self) -> u64 {
+        self.0.elapsed().as_nanos() as u64
+    }
+}
+
+pub(crate) struct PhaseTimer<C> {
+    clock: C,
+    /// Time one row in every `every`; 0 disables sampling.
+    every: usize,
+    row: usize,
+    sampling: bool,
+    last_ns: u64,
+    totals_ns: [u64; 3],
+    sampled_rows: usize,
+}
+
+impl<C: Clock> PhaseTimer<C> {
+    pub(crate) fn new(clock: C, every: usize) -> Self {
+        PhaseTimer {
+            clock,
+            every,
+            row: 0,
+            sampling: false,
+            last_ns: 0,
+            totals_ns: [0; 3],
+            sampled_rows: 0,
+        }
+    }
+
+    /// Call before the row's read; decides whether this row is timed.
+    pub(crate) fn begin_row(&mut self) {
+        if self.every == 0 {
+            return;
+        }
+        self.sampling = self.row.is_multiple_of(self.every);
+        self.row += 1;
+        if self.sampling {
+            self.last_ns = self.clock.now_ns();
+        }
+    }
+
+    /// Call when `phase` of the current row has finished.
+    pub(crate) fn end(&mut self, phase: Phase) {
+        if !self.sampling {
+            return;
+        }
+        let now = self.clock.now_ns();
+        self.totals_ns[phase as usize] += now.saturating_sub(self.last_ns);
+        self.last_ns = now;
+        // A loop ending at EOF begins a row it never reads.
+        if phase == Phase::Read {
+            self.sampled_rows += 1;
+        }
+    }
+
+    /// Sampled rows whose read completed.
+    pub(crate) fn sampled_rows(&self) -> usize {
+        self.sampled_rows
+    }
+
+    /// Share of sampled time per phase, in `Phase` order; zeros when
+    /// nothing was timed.
+    pub(crate) fn percentages(&self) -> [f64; 3] {
+        let total: u64 = self.totals_ns.iter().sum();
+        if total == 0 {
+            return [0.0; 3];
+        }
+        self.totals_ns.map(|ns| ns as f64 * 100.0 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Advances by `step` on every reading and counts the readings.
+    struct FakeClock {
+        now: Cell<u64>,
+        step: Cell<u64>,
+        reads: Cell<usize>,
+    }
+
+    impl FakeClock {
+        fn new() -> Self {
+            FakeClock {
+                now: Cell::new(0),
+                step: Cell::new(0),
+                reads: Cell::new(0),
+            }
+        }
+    }
+
+    impl Clock for &FakeClock {
+        fn now_ns(&self) -> u64 {
+            self.reads.set(self.reads.get() + 1);
+            self.now.set(self.now.get() + self.step.get());
+            self.now.get()
+        }
+    }
+
+    fn run_row(timer: &mut PhaseTimer<&FakeClock>, clock: &FakeClock, steps: [u64; 3]) {
+        clock.step.set(0);
+        timer.begin_row();
+        for (phase, step) in [Phase::Read, Phase::Parse, Phase::Aggregate].into_iter().zip(steps) {
+            clock.step.set(step);
+            timer.end(phase);
+        }
+    }
+
+    #[test]
+    fn samples_every_nth_row() {
+        let clock = FakeClock::new();
+        let mut timer = PhaseTimer::new(&clock, 3);
+        // Rows 0 and 3 are sampled; the others would skew the split if counted.
+        for row in 0..5 {
+            let steps = if row % 3 == 0 { [20, 70, 10] } else { [1_000, 0, 0] };
+            run_row(&mut timer, &clock, steps);
+        }
+        assert_eq!(timer.sampled_rows(), 2);
+        assert_eq!(clock.reads.get(), 2 * 4);
+        assert_eq!(timer.percentages(), [20.0, 70.0, 10.0]);
+    }
+
+    #[test]
+    fn percentages_cover_the_sampled_time() {
+        let clock = FakeClock::new();
+        let mut timer = PhaseTimer::new(&clock, 1);
+        assert_eq!(timer.percentages(), [0.0; 3]);
+        run_row(&mut timer, &clock, [1, 1, 2]);
+        run_row(&mut timer, &clock, [5, 3, 0]);
+        let pct = timer.percentages();
+        assert_eq!(pct, [50.0, 33.333333333333336, 16.666666666666668]);
+        assert!((pct.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn disabled_timer_never_reads_the_clock() {
+        let clock = FakeClock::new();
+        let mut timer = PhaseTimer::new(&clock, 0);
+        for _ in 0..100 {
+            run_row(&mut timer, &clock, [1, 1, 1]);
+        }
+        assert_eq!(clock.reads.get(), 0);
+        assert_eq!(timer.sampled_rows(), 0);
+        assert_eq!(timer.percentages(), [0.0; 3]);
+    }
+}
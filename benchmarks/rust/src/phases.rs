@@ -0,0 +1,190 @@
+//! Per-phase outcomes of benchmarks that do several independent things in
+//! one record (build then start, audit then outdated check). A failed phase
+//! leaves the other phases' metrics valid; the record's `phases` array says
+//! which ones to trust. A record with any failed phase is degraded.
+//!
+//! A benchmark that panics is logged as failing in the phase it had
+//! `enter`ed, or in `measure` when it never entered one.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// The phase a panic is charged to when no phase was entered.
+const MEASURE_PHASE: &str = "measure";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PhaseStatus {
+    Ok,
+    Failed,
+    /// Not attempted: not requested, no tool, or an earlier phase it needs
+    /// failed.
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct PhaseOutcome {
+    pub(crate) name: &'static str,
+    pub(crate) status: PhaseStatus,
+    pub(crate) duration_seconds: f64,
+    /// Why it failed or was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub(crate) struct PhaseLog {
+    phases: Vec<PhaseOutcome>,
+    /// Entered and not yet recorded.
+    #[serde(skip)]
+    current: Option<(&'static str, Instant)>,
+}
+
+impl PhaseLog {
+    /// Marks `name` as running until it is recorded or skipped.
+    pub(crate) fn enter(&mut self, name: &'static str) {
+        self.current = Some((name, Instant::now()));
+    }
+
+    /// Records a phase that ran for `duration`.
+    pub(crate) fn record(
+        &mut self,
+        name: &'static str,
+        duration: Duration,
+        result: Result<(), String>,
+    ) {
+        let (status, error) = match result {
+            Ok(()) => (PhaseStatus::Ok, None),
+            Err(error) => (PhaseStatus::Failed, Some(error)),
+        };
+        self.current = None;
+        self.phases.push(PhaseOutcome {
+            name,
+            status,
+            duration_seconds: duration.as_secs_f64(),
+            error,
+        });
+    }
+
+    pub(crate) fn skip(&mut self, name: &'static str, reason: impl Into<String>) {
+        self.current = None;
+        self.phases.push(PhaseOutcome {
+            name,
+            status: PhaseStatus::Skipped,
+            duration_seconds: 0.0,
+            error: Some(reason.into()),
+        });
+    }
+
+    pub(crate) fn status(&self, name: &str) -> Option<PhaseStatus> {
+        self.phases.iter().find(|phase| phase.name == name).map(|phase| phase.status)
+    }
+
+    /// Records the entered phase, or `measure`, as failed with `error`.
+    pub(crate) fn fail_unfinished(&mut self, error: String) {
+        let (name, duration) = match self.current {
+            Some((name, started)) => (name, started.elapsed()),
+            None => (MEASURE_PHASE, Duration::ZERO),
+        };
+        self.record(name, duration, Err(error));
+    }
+
+    /// The first phase that failed.
+    pub(crate) fn first_failed(&self) -> Option<&'static str> {
+        self.phases
+            .iter()
+            .find(|phase| phase.status == PhaseStatus::Failed)
+            .map(|phase| phase.name)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|_| Value::Array(Vec::new()))
+    }
+}
+
+/// `<phase>: <error>` for each failed phase of `record`; a record with any
+/// is degraded.
+pub(crate) fn failures(record: &Value) -> Vec<String> {
+    let Some(phases) = record["phases"].as_array() else {
+        return Vec::new();
+    };
+    phases
+        .iter()
+        .filter(|phase| phase["status"] == "failed")
+        .map(|phase| {
+            let name = phase["name"].as_str().unwrap_or_default();
+            format!("{name}: {}", phase["error"].as_str().unwrap_or("failed"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn outcomes_serialize_in_order_with_errors_only_when_not_ok() {
+        let mut log = PhaseLog::default();
+        log.record("build", Duration::from_millis(1500), Ok(()));
+        log.record("startup", Duration::ZERO, Err("exited with 1".to_string()));
+        log.skip("static_variant", "no static target installed");
+        assert_eq!(log.status("startup"), Some(PhaseStatus::Failed));
+        assert_eq!(log.status("release"), None);
+
+        let value = log.to_value();
+        let names: Vec<&str> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|phase| phase["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["build", "startup", "static_variant"]);
+        assert_eq!(value[0]["status"], "ok");
+        assert_eq!(value[0]["duration_seconds"], 1.5);
+        assert!(value[0].get("error").is_none());
+        assert_eq!(value[1]["error"], "exited with 1");
+        let skipped = json!({
+            "name": "static_variant",
+            "status": "skipped",
+            "duration_seconds": 0.0,
+            "error": "no static target installed",
+        });
+        assert_eq!(value[2], skipped);
+    }
+
+    #[test]
+    fn only_failed_phases_degrade_a_record() {
+        let mut log = PhaseLog::default();
+        log.record("audit", Duration::ZERO, Ok(()));
+        log.skip("outdated", "cargo-outdated not installed");
+        let mut record = json!({"phases": log.to_value()});
+        assert!(failures(&record).is_empty());
+        log.record("verify", Duration::ZERO, Err("no report".to_string()));
+        record["phases"] = log.to_value();
+        assert_eq!(failures(&record), ["verify: no report"]);
+        assert!(failures(&json!({"metrics": {}})).is_empty());
+    }
+
+    #[test]
+    fn an_unfinished_phase_fails_where_it_was_entered() {
+        let mut log = PhaseLog::default();
+        log.fail_unfinished("boom".to_string());
+        assert_eq!(log.first_failed(), Some(MEASURE_PHASE));
+
+        let mut log = PhaseLog::default();
+        log.enter("build");
+        log.record("build", Duration::ZERO, Ok(()));
+        log.enter("startup");
+        log.fail_unfinished("boom".to_string());
+        assert_eq!(log.status("build"), Some(PhaseStatus::Ok));
+        assert_eq!(log.first_failed(), Some("startup"));
+        assert_eq!(log.to_value()[1]["error"], "boom");
+    }
+}
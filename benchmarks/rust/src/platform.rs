@@ -0,0 +1,453 @@
+//! Every OS-specific probe lives here. Each function returns `None` where the
+//! platform cannot answer, and callers turn that into a `*_supported: 0`
+//! metric instead of omitting it.
+
+use crate::procfs::SwapCounters;
+
+/// A `/proc` or `/sys` file through its `procfs` parser; `None` when the
+/// file cannot be read, as in a container that hides it.
+#[cfg(target_os = "linux")]
+fn read_proc<T>(
+    path: impl AsRef<std::path::Path>,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    parse(&std::fs::read_to_string(path).ok()?)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn swap_counters() -> Option<SwapCounters> {
+    read_proc("/proc/vmstat", crate::procfs::parse_vmstat)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn swap_counters() -> Option<SwapCounters> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn mem_available_kb() -> Option<u64> {
+    read_proc("/proc/meminfo", crate::procfs::parse_mem_available_kb)
+}
+
+#[cfg(windows)]
+pub(crate) fn mem_available_kb() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    // SAFETY: MEMORYSTATUSEX is plain data; dwLength must be set before the call.
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    // SAFETY: `status` is a valid, writable MEMORYSTATUSEX.
+    let ok = unsafe { GlobalMemoryStatusEx(&mut status) };
+    (ok != 0).then_some(status.ullAvailPhys / 1024)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(crate) fn mem_available_kb() -> Option<u64> {
+    None
+}
+
+/// This process's peak resident set so far.
+#[cfg(target_os = "linux")]
+pub(crate) fn peak_rss_kb() -> Option<u64> {
+    read_proc("/proc/self/status", crate::procfs::parse_peak_rss_kb)
+}
+
+/// `ru_maxrss`, which macOS reports in bytes and the BSDs in kilobytes.
+#[cfg(all(unix, not(target_os = "linux")))]
+pub(crate) fn peak_rss_kb() -> Option<u64> {
+    // SAFETY: rusage is plain data, filled in by the call.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a valid, writable rusage.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    let maxrss = u64::try_from(usage.ru_maxrss).ok()?;
+    Some(if cfg!(target_os = "macos") { maxrss / 1024 } else { maxrss })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn peak_rss_kb() -> Option<u64> {
+    None
+}
+
+/// User plus system CPU time of the whole process so far, every thread
+/// included.
+#[cfg(unix)]
+pub(crate) fn process_cpu_seconds() -> Option<f64> {
+    // SAFETY: rusage is plain data, filled in by the call.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a valid, writable rusage.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    let seconds = |time: libc::timeval| time.tv_sec as f64 + time.tv_usec as f64 / 1e6;
+    Some(seconds(usage.ru_utime) + seconds(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn process_cpu_seconds() -> Option<f64> {
+    None
+}
+
+/// Page faults the process has taken so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PageFaults {
+    /// Faults that had to read from disk.
+    pub(crate) major: u64,
+    /// Faults served from memory, the page cache included.
+    pub(crate) minor: u64,
+}
+
+impl PageFaults {
+    /// Faults taken between `before` and `self`.
+    pub(crate) fn since(self, before: PageFaults) -> PageFaults {
+        PageFaults {
+            major: self.major.saturating_sub(before.major),
+            minor: self.minor.saturating_sub(before.minor),
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn page_faults() -> Option<PageFaults> {
+    // SAFETY: rusage is plain data, filled in by the call.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a valid, writable rusage.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    Some(PageFaults {
+        major: u64::try_from(usage.ru_majflt).ok()?,
+        minor: u64::try_from(usage.ru_minflt).ok()?,
+    })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn page_faults() -> Option<PageFaults> {
+    None
+}
+
+/// Transparent hugepage size on x86_64 and 4K-page aarch64 kernels.
+pub(crate) const HUGEPAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// System-wide THP mode: `always`, `madvise`, or `never`.
+#[cfg(target_os = "linux")]
+pub(crate) fn thp_mode() -> Option<String> {
+    read_proc("/sys/kernel/mm/transparent_hugepage/enabled", |contents| {
+        crate::procfs::parse_thp_mode(contents).map(str::to_string)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn thp_mode() -> Option<String> {
+    None
+}
+
+/// This process's anonymous memory currently backed by hugepages.
+#[cfg(target_os = "linux")]
+pub(crate) fn anon_huge_pages_kb() -> Option<u64> {
+    read_proc("/proc/self/smaps_rollup", crate::procfs::parse_anon_huge_pages_kb)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn anon_huge_pages_kb() -> Option<u64> {
+    None
+}
+
+/// `madvise(MADV_HUGEPAGE)` over `buf`, then whether the kernel now counts
+/// its mapping as THP-eligible; the call succeeds under `never` too, so
+/// only the second answer says the advice applied. `buf` must start on a
+/// page boundary.
+#[cfg(target_os = "linux")]
+pub(crate) fn advise_hugepages(buf: &mut [u8]) -> Option<bool> {
+    // SAFETY: the range is memory this process owns for the whole call;
+    // MADV_HUGEPAGE only changes how it may be backed, never its contents.
+    let rc = unsafe { libc::madvise(buf.as_mut_ptr().cast(), buf.len(), libc::MADV_HUGEPAGE) };
+    if rc != 0 {
+        return None;
+    }
+    read_proc("/proc/self/smaps", |smaps| {
+        crate::procfs::parse_thp_eligible(smaps, buf.as_ptr() as usize)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn advise_hugepages(_buf: &mut [u8]) -> Option<bool> {
+    None
+}
+
+/// Path MTU the kernel holds for a connected socket's route, in bytes.
+#[cfg(target_os = "linux")]
+pub(crate) fn socket_mtu(stream: &std::net::TcpStream) -> Option<u32> {
+    use std::os::fd::AsRawFd;
+
+    let (level, name) = match stream.peer_addr().ok()? {
+        std::net::SocketAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_MTU),
+        std::net::SocketAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_MTU),
+    };
+    let mut mtu: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: `mtu` and `len` outlive the call and `len` is `mtu`'s size.
+    let value = (&mut mtu as *mut libc::c_int).cast();
+    let rc = unsafe { libc::getsockopt(stream.as_raw_fd(), level, name, value, &mut len) };
+    (rc == 0 && mtu > 0).then_some(mtu as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn socket_mtu(_stream: &std::net::TcpStream) -> Option<u32> {
+    None
+}
+
+/// Resolution of the monotonic clock in nanoseconds, where the OS exposes it.
+#[cfg(unix)]
+pub(crate) fn timer_resolution_ns() -> Option<f64> {
+    let mut res = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `res` is a valid, writable timespec for the duration of the call.
+    let rc = unsafe { libc::clock_getres(libc::CLOCK_MONOTONIC, &mut res) };
+    if rc != 0 {
+        return None;
+    }
+    Some(res.tv_sec as f64 * 1e9 + res.tv_nsec as f64)
+}
+
+/// `Instant` is backed by QueryPerformanceCounter on Windows.
+#[cfg(windows)]
+pub(crate) fn timer_resolution_ns() -> Option<f64> {
+    use windows_sys::Win32::System::Performance::QueryPerformanceFrequency;
+
+    let mut frequency = 0i64;
+    // SAFETY: `frequency` is a valid, writable i64.
+    let ok = unsafe { QueryPerformanceFrequency(&mut frequency) };
+    (ok != 0 && frequency > 0).then(|| 1e9 / frequency as f64)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn timer_resolution_ns() -> Option<f64> {
+    None
+}
+
+/// Scheduling policy of the current process (`SCHED_OTHER` is 0 on Linux).
+#[cfg(target_os = "linux")]
+pub(crate) fn scheduler_policy() -> Option<i32> {
+    // SAFETY: sched_getscheduler(0) only queries the calling process.
+    let policy = unsafe { libc::sched_getscheduler(0) };
+    (policy >= 0).then_some(policy)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn scheduler_policy() -> Option<i32> {
+    None
+}
+
+/// Human-readable name of the current scheduling policy.
+#[cfg(target_os = "linux")]
+pub(crate) fn scheduler_policy_name() -> Option<&'static str> {
+    scheduler_policy().map(policy_name)
+}
+
+#[cfg(target_os = "linux")]
+fn policy_name(policy: i32) -> &'static str {
+    match policy {
+        libc::SCHED_OTHER => "other",
+        libc::SCHED_FIFO => "fifo",
+        libc::SCHED_RR => "rr",
+        libc::SCHED_BATCH => "batch",
+        libc::SCHED_IDLE => "idle",
+        _ => "unknown",
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn scheduler_policy_name() -> Option<&'static str> {
+    None
+}
+
+/// Nice value `--low-priority` moves the process to.
+pub(crate) const LOW_PRIORITY_NICE: i32 = 10;
+
+/// Nice value of the calling thread (Linux) or process (other unixes).
+#[cfg(unix)]
+pub(crate) fn nice_value() -> Option<i32> {
+    // SAFETY: getpriority only queries the caller. It cannot fail for
+    // `who == 0`, so -1 is a real nice value rather than an error.
+    Some(unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn nice_value() -> Option<i32> {
+    None
+}
+
+/// Windows priority class of the process.
+#[cfg(windows)]
+pub(crate) fn priority_class() -> Option<&'static str> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, GetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS,
+        BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS,
+        NORMAL_PRIORITY_CLASS, REALTIME_PRIORITY_CLASS,
+    };
+
+    // SAFETY: the pseudo-handle from GetCurrentProcess needs no cleanup.
+    let class = unsafe { GetPriorityClass(GetCurrentProcess()) };
+    Some(match class {
+        0 => return None,
+        IDLE_PRIORITY_CLASS => "idle",
+        BELOW_NORMAL_PRIORITY_CLASS => "below_normal",
+        NORMAL_PRIORITY_CLASS => "normal",
+        ABOVE_NORMAL_PRIORITY_CLASS => "above_normal",
+        HIGH_PRIORITY_CLASS => "high",
+        REALTIME_PRIORITY_CLASS => "realtime",
+        _ => "unknown",
+    })
+}
+
+#[cfg(not(windows))]
+pub(crate) fn priority_class() -> Option<&'static str> {
+    None
+}
+
+/// `--low-priority`: nice `LOW_PRIORITY_NICE` (never raising an already
+/// lower priority) plus `SCHED_BATCH` on Linux, or the below-normal priority
+/// class on Windows. On Linux both apply to the calling thread and are
+/// inherited by threads it starts, so this runs before any are spawned.
+#[cfg(unix)]
+pub(crate) fn lower_priority() -> Result<(), String> {
+    let current = nice_value().ok_or("cannot read the nice value")?;
+    if current < LOW_PRIORITY_NICE {
+        // SAFETY: setpriority only changes the caller's own priority.
+        let rc = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICE) };
+        if rc != 0 {
+            return Err(format!("setpriority: {}", std::io::Error::last_os_error()));
+        }
+    }
+    #[cfg(target_os = "linux")]
+    if scheduler_policy() == Some(libc::SCHED_OTHER) {
+        let param = libc::sched_param { sched_priority: 0 };
+        // SAFETY: `param` is valid for the call; pid 0 is the caller.
+        let rc = unsafe { libc::sched_setscheduler(0, libc::SCHED_BATCH, &param) };
+        if rc != 0 {
+            return Err(format!("sched_setscheduler: {}", std::io::Error::last_os_error()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn lower_priority() -> Result<(), String> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, SetPriorityClass, BELOW_NORMAL_PRIORITY_CLASS,
+    };
+
+    // SAFETY: the pseudo-handle from GetCurrentProcess needs no cleanup.
+    let ok = unsafe { SetPriorityClass(GetCurrentProcess(), BELOW_NORMAL_PRIORITY_CLASS) };
+    if ok == 0 {
+        return Err(format!("SetPriorityClass: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn lower_priority() -> Result<(), String> {
+    Err("not supported on this platform".to_string())
+}
+
+/// Vector extensions detected at runtime; empty on other architectures.
+pub(crate) fn cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("sse4.2") {
+            features.push("sse4.2");
+        }
+        if std::arch::is_x86_feature_detected!("avx2") {
+            features.push("avx2");
+        }
+        if std::arch::is_x86_feature_detected!("fma") {
+            features.push("fma");
+        }
+        if std::arch::is_x86_feature_detected!("avx512f") {
+            features.push("avx512f");
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            features.push("neon");
+        }
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// On fixture text rather than the host's files, which a container may
+    /// hide or trim.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_probes_read_their_files_through_the_parsers() {
+        use crate::{fixtures, procfs};
+        use std::fs;
+
+        let dir = std::env::temp_dir().join(format!("platform-proc-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str, text: &str| {
+            let path = dir.join(name);
+            fs::write(&path, text).unwrap();
+            path
+        };
+        let vmstat = read_proc(file("vmstat", fixtures::proc_vmstat()), procfs::parse_vmstat);
+        assert_eq!(vmstat.map(|swap| swap.swap_out_pages), Some(5_871));
+        let meminfo = file("meminfo", fixtures::proc_meminfo());
+        assert_eq!(read_proc(meminfo, procfs::parse_mem_available_kb), Some(6_283_412));
+        let status = file("status", fixtures::proc_status());
+        assert_eq!(read_proc(status, procfs::parse_peak_rss_kb), Some(187_344));
+        let enabled = file("enabled", fixtures::proc_thp_enabled());
+        let mode = read_proc(enabled, |text| procfs::parse_thp_mode(text).map(str::to_string));
+        assert_eq!(mode.as_deref(), Some("madvise"));
+        assert_eq!(read_proc(dir.join("missing"), procfs::parse_vmstat), None);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(policy_name(libc::SCHED_OTHER), "other");
+        assert_eq!(policy_name(libc::SCHED_BATCH), "batch");
+        assert_eq!(policy_name(libc::SCHED_IDLE), "idle");
+        assert_eq!(policy_name(42), "unknown");
+        // Syscalls every Linux kernel answers.
+        assert!(timer_resolution_ns().is_some_and(|ns| ns > 0.0));
+        assert!(scheduler_policy_name().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn low_priority_is_what_gets_reported() {
+        // Its own thread: on Linux the change stays with it.
+        std::thread::spawn(|| {
+            lower_priority().unwrap();
+            assert!(nice_value().is_some_and(|nice| nice >= LOW_PRIORITY_NICE));
+            #[cfg(target_os = "linux")]
+            assert!(matches!(scheduler_policy_name(), Some("batch" | "idle")));
+            assert_eq!(priority_class(), None);
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_probes_answer_or_decline() {
+        assert!(swap_counters().is_none());
+        assert!(mem_available_kb().is_some_and(|kb| kb > 0));
+        assert!(timer_resolution_ns().is_some_and(|ns| ns > 0.0));
+        assert!(scheduler_policy().is_none());
+        // The class is inherited from whatever started the tests, which
+        // need not be "normal" (a CI agent may run below normal).
+        let known = ["idle", "below_normal", "normal", "above_normal", "high", "realtime"];
+        assert!(priority_class().is_some_and(|class| known.contains(&class)));
+        assert_eq!(nice_value(), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! A few-second micro run of every performance benchmark before the real
+//! suite, so a wrong base URL or an unwritable cache fails in seconds rather
+//! than minutes in. Micro results only decide pass/fail and are never
+//! recorded. The tool-driven scorecards have no workload to shrink and
+//! already degrade to `skip` when their tool is missing, so they are not
+//! prechecked.
+
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::benchmark_ids;
+use crate::config::{
+    AsyncTasksConfig, BenchmarkConfigs, EtlConfig, HttpClientConfig, HttpStreamingConfig,
+    JitterSensitivityConfig, JsonTransformConfig, MonteCarloConfig, SleepPrecisionConfig,
+};
+use crate::data_dir::DataDir;
+use crate::live_metrics::LiveContext;
+use crate::mock_server::ChaosConfig;
+use crate::phases::PhaseLog;
+use crate::preconditions::Preconditions;
+use crate::record::Metrics;
+
+/// Metrics that must be zero after a micro run.
+const ERROR_METRICS: [&str; 7] = [
+    "request_errors",
+    "server_unavailable",
+    "hash_mismatches",
+    "io_errors",
+    "tasks_missing",
+    "echo_errors",
+    "sequence_errors",
+];
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Failure {
+    pub(crate) benchmark_id: &'static str,
+    pub(crate) error: String,
+}
+
+/// The same configs with every workload cut to a smoke-test size. Targets
+/// (base URLs) stay as configured; that is what is being checked. The ETL
+/// dataset is a fresh throwaway file under the precheck data dir.
+pub(crate) fn micro_configs(configs: &BenchmarkConfigs) -> BenchmarkConfigs {
+    let micro_http = |config: &HttpClientConfig| HttpClientConfig {
+        requests: 5,
+        rows: 10,
+        concurrency: 1,
+        concurrency_sweep: Vec::new(),
+        ..config.clone()
+    };
+    // Injected errors are the point of a chaos config but would fail the
+    // check, so the micro run turns them off.
+    let without_errors = |chaos: &ChaosConfig| ChaosConfig {
+        error_rate: 0.0,
+        ..chaos.clone()
+    };
+    BenchmarkConfigs {
+        monte_carlo: MonteCarloConfig { samples: 100 },
+        sleep_precision: SleepPrecisionConfig {
+            durations_ms: vec![1],
+            samples: 1,
+            warmup: 0,
+        },
+        json_transform: JsonTransformConfig { records: 100 },
+        http_client: micro_http(&configs.http_client),
+        http_post: micro_http(&configs.http_post),
+        http_churn: micro_http(&configs.http_churn),
+        http_jitter: JitterSensitivityConfig {
+            requests: 5,
+            rows: 10,
+            concurrency: 1,
+            low_jitter: without_errors(&configs.http_jitter.low_jitter),
+            high_jitter: without_errors(&configs.http_jitter.high_jitter),
+        },
+        http_streaming: HttpStreamingConfig {
+            megabytes: 1,
+            ..configs.http_streaming.clone()
+        },
+        etl: EtlConfig {
+            dataset: "precheck-etl.jsonl.gz".to_string(),
+            rows: 100,
+            ..configs.etl.clone()
+        },
+        async_tasks: AsyncTasksConfig {
+            tasks: 100,
+            round_trips: 10,
+            producers: 2,
+            messages_per_producer: 10,
+            ..configs.async_tasks.clone()
+        },
+        ..configs.clone()
+    }
+}
+
+/// Why a micro run failed: a precondition that made it skip its work, or a
+/// nonzero error count.
+pub(crate) fn failure(metrics: &Metrics, checks: &Preconditions) -> Option<String> {
+    if let Some(outcome) = checks.first_skip() {
+        let failed = format!("{} check failed for {}", outcome.check, outcome.name);
+        return Some(match &outcome.reason {
+            Some(reason) => format!("{failed}: {reason}"),
+            None => failed,
+        });
+    }
+    ERROR_METRICS.iter().find_map(|key| {
+        let count = metrics.value(key)?;
+        (count > 0.0).then(|| format!("{key} = {count}"))
+    })
+}
+
+/// Micro-runs each selected performance benchmark (all with an empty
+/// `selection`) with its data under `scratch`, which is removed afterwards;
+/// stops at the first failure. Returns how many benchmarks passed.
+pub(crate) fn run(
+    configs: &BenchmarkConfigs,
+    selection: &[&str],
+    scratch: &Path,
+) -> Result<usize, Failure> {
+    let micro = micro_configs(configs);
+    let data_dir = DataDir::new(scratch.to_path_buf());
+    let mut passed = 0;
+    let mut result = Ok(());
+    for benchmark in crate::benchmarks(&micro, &data_dir) {
+        if benchmark.category != "performance"
+            || !benchmark_ids::is_selected(selection, benchmark.id)
+        {
+            continue;
+        }
+        let mut checks = Preconditions::default();
+        let live = LiveContext::new(None, "precheck", benchmark.id);
+        let mut phases = PhaseLog::default();
+        let measured = panic::catch_unwind(AssertUnwindSafe(|| {
+            (benchmark.measure)(&mut checks, &live, &mut phases)
+        }));
+        let error = match measured {
+            Ok(metrics) => failure(&metrics, &checks),
+            Err(_) => Some("panicked".to_string()),
+        };
+        // Each target of a multi-target run is checked on its own.
+        let error = match &benchmark.target {
+            Some((_, base_url)) => error.map(|error| format!("{base_url}: {error}")),
+            None => error,
+        };
+        if let Some(error) = error {
+            result = Err(Failure {
+                benchmark_id: benchmark.id,
+                error,
+            });
+            break;
+        }
+        passed += 1;
+    }
+    let _ = fs::remove_dir_all(scratch);
+    result.map(|()| passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_tasks;
+    use crate::fixtures;
+    use crate::mock_server::MockServer;
+    use std::env;
+    use std::path::PathBuf;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("precheck-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn configs_against(base_url: &str) -> BenchmarkConfigs {
+        let http = HttpClientConfig {
+            base_url: base_url.to_string(),
+            ready_timeout_seconds: 0.0,
+            ..HttpClientConfig::default()
+        };
+        BenchmarkConfigs {
+            http_client: http.clone(),
+            http_post: http.clone(),
+            http_churn: http,
+            http_streaming: HttpStreamingConfig {
+                base_url: base_url.to_string(),
+                ready_timeout_seconds: 0.0,
+                ..HttpStreamingConfig::default()
+            },
+            ..BenchmarkConfigs::default()
+        }
+    }
+
+    #[test]
+    fn shrinks_workloads_but_keeps_targets() {
+        let mut configs = configs_against("http://bench.example:8080");
+        configs.http_jitter.high_jitter.error_rate = 0.3;
+        let micro = micro_configs(&configs);
+        assert_eq!(micro.monte_carlo.samples, 100);
+        assert_eq!(micro.etl.rows, 100);
+        assert_eq!(
+            (micro.http_client.requests, micro.http_churn.requests),
+            (5, 5)
+        );
+        assert_eq!(micro.http_client.base_url, "http://bench.example:8080");
+        assert_eq!(micro.http_jitter.high_jitter.error_rate, 0.0);
+        assert_eq!(micro.dependency_scan, configs.dependency_scan);
+    }
+
+    #[test]
+    fn passes_against_a_live_server() {
+        let server = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        let dir = scratch("ok");
+        let configs = configs_against(&server.base_url());
+        // The async task benchmark only exists in builds with tokio.
+        let registered = 10 + async_tasks::supported() as usize;
+        assert_eq!(run(&configs, &[], &dir), Ok(registered));
+        assert!(!dir.exists());
+        // Scorecards are never prechecked, selected or not.
+        let selection = benchmark_ids::parse_selection(
+            "cpu_monte_carlo_pi,build_startup_feedback_loop",
+        )
+        .unwrap();
+        assert_eq!(run(&configs, &selection, &dir), Ok(1));
+    }
+
+    #[test]
+    fn catches_an_unparseable_base_url() {
+        let dir = scratch("bad-url");
+        let failure = run(&configs_against("htp:/nowhere"), &[], &dir).unwrap_err();
+        assert_eq!(failure.benchmark_id, "io_concurrent_http_client");
+        assert_eq!(
+            failure.error,
+            "base_url check failed for htp:/nowhere: no http:// or https:// scheme"
+        );
+    }
+
+    #[test]
+    fn catches_an_unreachable_server() {
+        let dir = scratch("refused");
+        let closed = format!("http://127.0.0.1:{}", fixtures::closed_port());
+        let failure = run(&configs_against(&closed), &[], &dir).unwrap_err();
+        assert_eq!(failure.benchmark_id, "io_concurrent_http_client");
+        assert!(
+            failure.error.starts_with("server_unavailable = "),
+            "{}",
+            failure.error
+        );
+    }
+
+    #[test]
+    fn checks_every_target() {
+        let server = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        let dir = scratch("targets");
+        let closed = format!("http://127.0.0.1:{}", fixtures::closed_port());
+        let configs = configs_against(&format!("{},{closed}", server.base_url()));
+        let failure = run(&configs, &[], &dir).unwrap_err();
+        assert_eq!(failure.benchmark_id, "io_concurrent_http_client");
+        assert!(
+            failure.error.starts_with(&format!("{closed}: server_unavailable = ")),
+            "{}",
+            failure.error
+        );
+    }
+
+    #[test]
+    fn catches_an_unwritable_data_dir() {
+        let server = MockServer::start(ChaosConfig::default(), 7).unwrap();
+        // A regular file where the data dir should be: nothing under it can
+        // be created.
+        let blocker = scratch("blocked");
+        fs::write(&blocker, b"").unwrap();
+        let configs = configs_against(&server.base_url());
+        let failure = run(&configs, &[], &blocker.join("data")).unwrap_err();
+        assert_eq!(failure.benchmark_id, "data_pipeline_etl_minibatch");
+        assert_eq!(failure.error, "io_errors = 1");
+        let _ = fs::remove_file(blocker);
+    }
+}
@@ -0,0 +1,261 @@
+//! Every condition a benchmark checks before (or instead of) doing work is
+//! evaluated through `Preconditions::check`, which records what was checked,
+//! what was observed, and what the benchmark did about it. The log lands in
+//! the record's `preconditions` array.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::http_client::{parse_base_url, HTTPS_NEEDS_TLS};
+use crate::command_runner::{has_cargo_subcommand, CommandRunner, SystemRunner};
+use crate::{etl_async, platform};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Probe {
+    SwapCounters,
+    TimerResolution,
+    SchedulerPolicy,
+    /// An io_uring instance can be created (`data_pipeline_etl_async`).
+    IoUring,
+}
+
+impl Probe {
+    fn name(self) -> &'static str {
+        match self {
+            Probe::SwapCounters => "swap_counters",
+            Probe::TimerResolution => "timer_resolution",
+            Probe::SchedulerPolicy => "scheduler_policy",
+            Probe::IoUring => "io_uring",
+        }
+    }
+
+    fn supported(self) -> bool {
+        match self {
+            Probe::SwapCounters => platform::swap_counters().is_some(),
+            Probe::TimerResolution => platform::timer_resolution_ns().is_some(),
+            Probe::SchedulerPolicy => platform::scheduler_policy().is_some(),
+            Probe::IoUring => etl_async::supported(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Precondition<'a> {
+    /// `cargo <name>` is installed; the tool is skipped otherwise.
+    CargoSubcommand(&'a str),
+    /// `cargo <name>` is installed; a built-in replacement runs otherwise.
+    ReplaceableSubcommand(&'a str),
+    /// The HTTP base URL parses, with a scheme this build speaks; requests
+    /// are skipped otherwise.
+    BaseUrl(&'a str),
+    /// The dataset file exists; it is generated otherwise.
+    Dataset(&'a Path),
+    /// The advisory database directory exists; its age is not reported
+    /// otherwise.
+    AdvisoryDb(&'a Path),
+    /// The OSV snapshot for the built-in scanner exists; the scan is
+    /// skipped otherwise.
+    AdvisorySnapshot(&'a Path),
+    /// The OS answers the probe; the metric is reported unsupported
+    /// otherwise, or for `Probe::IoUring` the benchmark is skipped.
+    Platform(Probe),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Decision {
+    Run,
+    Skip,
+    Fallback,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Outcome {
+    pub(crate) check: &'static str,
+    pub(crate) name: String,
+    pub(crate) found: bool,
+    pub(crate) decision: Decision,
+    /// Why the check failed, when there is more to say than `found`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<String>,
+}
+
+/// What keeps `url` from being a usable base URL in this build.
+fn base_url_problem(url: &str) -> Option<String> {
+    match parse_base_url(url) {
+        Ok(target) if target.scheme.supported() => None,
+        Ok(_) => Some(HTTPS_NEEDS_TLS.to_string()),
+        Err(err) => Some(err),
+    }
+}
+
+impl Precondition<'_> {
+    fn check_name(&self) -> &'static str {
+        match self {
+            Precondition::CargoSubcommand(_) | Precondition::ReplaceableSubcommand(_) => {
+                "cargo_subcommand"
+            }
+            Precondition::BaseUrl(_) => "base_url",
+            Precondition::Dataset(_) => "dataset",
+            Precondition::AdvisoryDb(_) => "advisory_db",
+            Precondition::AdvisorySnapshot(_) => "advisory_snapshot",
+            Precondition::Platform(_) => "platform_probe",
+        }
+    }
+
+    fn subject(&self) -> String {
+        match self {
+            Precondition::CargoSubcommand(name)
+            | Precondition::ReplaceableSubcommand(name)
+            | Precondition::BaseUrl(name) => name.to_string(),
+            Precondition::Dataset(path)
+            | Precondition::AdvisoryDb(path)
+            | Precondition::AdvisorySnapshot(path) => path.display().to_string(),
+            Precondition::Platform(probe) => probe.name().to_string(),
+        }
+    }
+
+    fn on_failure(&self) -> Decision {
+        match self {
+            Precondition::CargoSubcommand(_)
+            | Precondition::BaseUrl(_)
+            | Precondition::AdvisoryDb(_)
+            | Precondition::AdvisorySnapshot(_)
+            | Precondition::Platform(Probe::IoUring) => Decision::Skip,
+            Precondition::ReplaceableSubcommand(_) | Precondition::Dataset(_) | Precondition::Platform(_) => {
+                Decision::Fallback
+            }
+        }
+    }
+
+    fn evaluate(&self, runner: &dyn CommandRunner) -> bool {
+        match self {
+            Precondition::CargoSubcommand(name) | Precondition::ReplaceableSubcommand(name) => {
+                has_cargo_subcommand(runner, name)
+            }
+            Precondition::BaseUrl(url) => base_url_problem(url).is_none(),
+            Precondition::Dataset(path) => path.exists(),
+            Precondition::AdvisoryDb(path) => path.is_dir(),
+            Precondition::AdvisorySnapshot(path) => path.exists(),
+            Precondition::Platform(probe) => probe.supported(),
+        }
+    }
+
+    pub(crate) fn outcome(&self, found: bool) -> Outcome {
+        Outcome {
+            check: self.check_name(),
+            name: self.subject(),
+            found,
+            decision: if found { Decision::Run } else { self.on_failure() },
+            reason: None,
+        }
+    }
+
+    /// Why the check failed, for the checks that can tell.
+    fn reason(&self) -> Option<String> {
+        match self {
+            Precondition::BaseUrl(url) => base_url_problem(url),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub(crate) struct Preconditions(Vec<Outcome>);
+
+impl Preconditions {
+    /// Evaluates and logs `precondition`; true when it holds.
+    pub(crate) fn check(&mut self, precondition: Precondition) -> bool {
+        self.check_with(precondition, &SystemRunner)
+    }
+
+    /// `check`, with cargo subcommands probed through `runner`.
+    pub(crate) fn check_with(
+        &mut self,
+        precondition: Precondition,
+        runner: &dyn CommandRunner,
+    ) -> bool {
+        let found = precondition.evaluate(runner);
+        let mut outcome = precondition.outcome(found);
+        if !found {
+            outcome.reason = precondition.reason();
+        }
+        self.record(outcome)
+    }
+
+    pub(crate) fn record(&mut self, outcome: Outcome) -> bool {
+        let found = outcome.found;
+        self.0.push(outcome);
+        found
+    }
+
+    /// The first check that made the benchmark skip its work.
+    pub(crate) fn first_skip(&self) -> Option<&Outcome> {
+        self.0.iter().find(|outcome| outcome.decision == Decision::Skip)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|_| Value::Array(Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn failed_checks_map_to_their_decision() {
+        let mut log = Preconditions::default();
+        assert!(!log.record(Precondition::CargoSubcommand("audit").outcome(false)));
+        assert!(log.record(Precondition::BaseUrl("http://127.0.0.1:8000").outcome(true)));
+        assert!(!log.record(Precondition::Dataset(Path::new("data/etl.jsonl.gz")).outcome(false)));
+        assert!(!log.record(Precondition::Platform(Probe::SwapCounters).outcome(false)));
+        assert!(!log.record(Precondition::AdvisoryDb(Path::new("/home/ci/.cargo/advisory-db")).outcome(false)));
+        assert!(!log.record(Precondition::ReplaceableSubcommand("outdated").outcome(false)));
+        assert!(!log.record(Precondition::AdvisorySnapshot(Path::new("osv.json")).outcome(false)));
+        assert!(!log.record(Precondition::Platform(Probe::IoUring).outcome(false)));
+        assert_eq!(
+            log.to_value(),
+            json!([
+                {"check": "cargo_subcommand", "name": "audit", "found": false, "decision": "skip"},
+                {"check": "base_url", "name": "http://127.0.0.1:8000", "found": true, "decision": "run"},
+                {"check": "dataset", "name": "data/etl.jsonl.gz", "found": false, "decision": "fallback"},
+                {"check": "platform_probe", "name": "swap_counters", "found": false, "decision": "fallback"},
+                {"check": "advisory_db", "name": "/home/ci/.cargo/advisory-db", "found": false, "decision": "skip"},
+                {"check": "cargo_subcommand", "name": "outdated", "found": false, "decision": "fallback"},
+                {"check": "advisory_snapshot", "name": "osv.json", "found": false, "decision": "skip"},
+                {"check": "platform_probe", "name": "io_uring", "found": false, "decision": "skip"}
+            ])
+        );
+    }
+
+    #[test]
+    fn evaluates_cheap_checks() {
+        let mut log = Preconditions::default();
+        assert_eq!(log.to_value(), json!([]));
+        assert!(!log.check(Precondition::BaseUrl("ftp://example.com")));
+        assert!(log.check(Precondition::BaseUrl("http://localhost:8000/items")));
+        assert!(!log.check(Precondition::Dataset(Path::new("/nonexistent/etl.jsonl.gz"))));
+        assert!(log.check(Precondition::Dataset(Path::new(env!("CARGO_MANIFEST_DIR")))));
+        assert_eq!(log.0[0].decision, Decision::Skip);
+        assert_eq!(log.0[2].decision, Decision::Fallback);
+        let https = log.check(Precondition::BaseUrl("https://example.com"));
+        assert_eq!(https, crate::tls::supported());
+        let reasons: Vec<_> = log.0.iter().map(|outcome| outcome.reason.as_deref()).collect();
+        let https_reason = (!crate::tls::supported()).then_some(HTTPS_NEEDS_TLS);
+        let ftp = "unsupported scheme ftp://, expected http:// or https://";
+        assert_eq!(reasons, [Some(ftp), None, None, None, https_reason]);
+        assert_eq!(
+            log.to_value()[0]["reason"],
+            "unsupported scheme ftp://, expected http:// or https://"
+        );
+        assert!(log.to_value()[1].get("reason").is_none());
+    }
+}
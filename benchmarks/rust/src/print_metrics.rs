@@ -0,0 +1,153 @@
+//! `--print <benchmark_id>.<metric>`: after the run, the requested values
+//! go to stdout one per line in the order requested, so wrapper scripts can
+//! read a number without picking it out of the results file.
+
+use serde_json::Value;
+use std::io::Write;
+
+use crate::{benchmark_ids, retry};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MetricPath {
+    pub(crate) benchmark_id: &'static str,
+    pub(crate) metric: String,
+}
+
+impl MetricPath {
+    /// `<benchmark_id>.<metric>`; the id must be registered, the metric is
+    /// checked against the record once the run has produced it.
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let (benchmark_id, metric) = raw
+            .split_once('.')
+            .filter(|(id, metric)| !id.is_empty() && !metric.is_empty())
+            .ok_or_else(|| format!("--print: expected <benchmark_id>.<metric>, got {raw:?}"))?;
+        let benchmark_id = benchmark_ids::check(benchmark_id).map_err(|err| {
+            format!(
+                "--print: {err}; valid benchmarks: {}",
+                benchmark_ids::ALL.join(", ")
+            )
+        })?;
+        Ok(MetricPath {
+            benchmark_id,
+            metric: metric.to_string(),
+        })
+    }
+}
+
+/// The value of each path, in order, from the selected attempt of each
+/// benchmark. A multi-target benchmark resolves to its first target. Fails
+/// on the first path without a value, naming the metrics that do exist.
+pub(crate) fn resolve(paths: &[MetricPath], records: &[Value]) -> Result<Vec<f64>, String> {
+    paths
+        .iter()
+        .map(|path| {
+            let record = records
+                .iter()
+                .filter(|record| retry::is_selected(record))
+                .find(|record| record["benchmark_id"].as_str() == Some(path.benchmark_id))
+                .ok_or_else(|| format!("--print: no {} record in this run", path.benchmark_id))?;
+            if let Some(value) = record["metrics"][path.metric.as_str()]["value"].as_f64() {
+                return Ok(value);
+            }
+            let valid: Vec<&str> = record["metrics"]
+                .as_object()
+                .map(|metrics| metrics.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            Err(format!(
+                "--print: unknown metric {} for {}; valid metrics: {}",
+                path.metric,
+                path.benchmark_id,
+                valid.join(", ")
+            ))
+        })
+        .collect()
+}
+
+/// One value per line, or nothing at all when any path fails to resolve,
+/// so a script never reads a partial list.
+pub(crate) fn write(
+    paths: &[MetricPath],
+    records: &[Value],
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let values = resolve(paths, records)?;
+    let text: String = values.iter().map(|value| format!("{value}\n")).collect();
+    out.write_all(text.as_bytes())
+        .and_then(|()| out.flush())
+        .map_err(|err| format!("--print: cannot write to stdout: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(raw: &str) -> MetricPath {
+        MetricPath::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn parses_registered_paths() {
+        assert_eq!(
+            path("data_pipeline_etl_minibatch.throughput_mb_s"),
+            MetricPath {
+                benchmark_id: "data_pipeline_etl_minibatch",
+                metric: "throughput_mb_s".to_string(),
+            }
+        );
+        for raw in ["throughput_mb_s", ".throughput_mb_s", "cpu_monte_carlo_pi."] {
+            assert!(MetricPath::parse(raw).is_err(), "{raw}");
+        }
+        let err = MetricPath::parse("cpu_monte_carlo.pi_estimate").unwrap_err();
+        assert!(err.contains("did you mean cpu_monte_carlo_pi?"), "{err}");
+        assert!(
+            err.contains("valid benchmarks: cpu_monte_carlo_pi, "),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn resolves_in_request_order_from_selected_records() {
+        let records = [
+            json!({
+                "benchmark_id": "data_pipeline_etl_minibatch",
+                "selected_for_comparison": false,
+                "metrics": {"throughput_mb_s": {"value": 1.0, "unit": "mb/s"}},
+            }),
+            json!({
+                "benchmark_id": "cpu_monte_carlo_pi",
+                "metrics": {"pi_estimate": {"value": 3.0, "unit": "ratio"}},
+            }),
+            json!({
+                "benchmark_id": "data_pipeline_etl_minibatch",
+                "selected_for_comparison": true,
+                "metrics": {
+                    "throughput_mb_s": {"value": 42.5, "unit": "mb/s"},
+                    "io_errors": {"value": 0.0, "unit": "count"},
+                },
+            }),
+        ];
+        let paths = [
+            path("data_pipeline_etl_minibatch.throughput_mb_s"),
+            path("cpu_monte_carlo_pi.pi_estimate"),
+            path("data_pipeline_etl_minibatch.throughput_mb_s"),
+        ];
+        assert_eq!(resolve(&paths, &records), Ok(vec![42.5, 3.0, 42.5]));
+
+        let err = resolve(&[path("data_pipeline_etl_minibatch.rows")], &records).unwrap_err();
+        assert!(
+            err.ends_with("valid metrics: io_errors, throughput_mb_s"),
+            "{err}"
+        );
+        let err = resolve(&[path("io_concurrent_http_client.checksum")], &records).unwrap_err();
+        assert!(err.contains("no io_concurrent_http_client record"), "{err}");
+
+        let mut out = Vec::new();
+        write(&paths, &records, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "42.5\n3\n42.5\n");
+        let mut out = Vec::new();
+        let partly_unknown = [paths[0].clone(), path("cpu_monte_carlo_pi.samples")];
+        assert!(write(&partly_unknown, &records, &mut out).is_err());
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SwapCounters {
+    pub(crate) swap_in_pages: u64,
+    pub(crate) swap_out_pages: u64,
+}
+
+impl SwapCounters {
+    pub(crate) fn delta(&self, later: &SwapCounters) -> SwapCounters {
+        SwapCounters {
+            swap_in_pages: later.swap_in_pages.saturating_sub(self.swap_in_pages),
+            swap_out_pages: later.swap_out_pages.saturating_sub(self.swap_out_pages),
+        }
+    }
+}
+
+fn field_value(contents: &str, key: &str) -> Option<u64> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let name = parts.next()?.trim_end_matches(':');
+        if name != key {
+            return None;
+        }
+        parts.next()?.parse::<u64>().ok()
+    })
+}
+
+pub(crate) fn parse_vmstat(contents: &str) -> Option<SwapCounters> {
+    Some(SwapCounters {
+        swap_in_pages: field_value(contents, "pswpin")?,
+        swap_out_pages: field_value(contents, "pswpout")?,
+    })
+}
+
+pub(crate) fn parse_mem_available_kb(contents: &str) -> Option<u64> {
+    field_value(contents, "MemAvailable")
+}
+
+/// The process's resident set high-water mark, `VmHWM` in `/proc/self/status`.
+pub(crate) fn parse_peak_rss_kb(contents: &str) -> Option<u64> {
+    field_value(contents, "VmHWM")
+}
+
+/// The bracketed choice of `transparent_hugepage/enabled`, e.g. `madvise`
+/// from `always [madvise] never`.
+pub(crate) fn parse_thp_mode(contents: &str) -> Option<&str> {
+    let start = contents.find('[')? + 1;
+    let len = contents[start..].find(']')?;
+    Some(&contents[start..start + len]).filter(|mode| !mode.is_empty())
+}
+
+/// Anonymous memory backed by transparent hugepages, from `smaps_rollup`.
+pub(crate) fn parse_anon_huge_pages_kb(contents: &str) -> Option<u64> {
+    field_value(contents, "AnonHugePages")
+}
+
+/// `THPeligible` of the `/proc/self/smaps` mapping containing `addr`.
+pub(crate) fn parse_thp_eligible(contents: &str, addr: usize) -> Option<bool> {
+    let mut in_mapping = false;
+    for line in contents.lines() {
+        let Some(first) = line.split_whitespace().next() else {
+            continue;
+        };
+        if let Some((start, end)) = first.split_once('-') {
+            let start = usize::from_str_radix(start, 16).ok();
+            let end = usize::from_str_radix(end, 16).ok();
+            if let Some((start, end)) = start.zip(end) {
+                in_mapping = (start..end).contains(&addr);
+                continue;
+            }
+        }
+        if in_mapping && first == "THPeligible:" {
+            return Some(field_value(line, "THPeligible")? == 1);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_vmstat_fixture() {
+        let counters = parse_vmstat(fixtures::proc_vmstat()).unwrap();
+        assert_eq!(counters.swap_in_pages, 1_204);
+        assert_eq!(counters.swap_out_pages, 5_871);
+    }
+
+    #[test]
+    fn parses_meminfo_fixture() {
+        let available = parse_mem_available_kb(fixtures::proc_meminfo());
+        assert_eq!(available, Some(6_283_412));
+    }
+
+    #[test]
+    fn parses_status_fixture() {
+        assert_eq!(parse_peak_rss_kb(fixtures::proc_status()), Some(187_344));
+    }
+
+    #[test]
+    fn parses_hugepage_fixtures() {
+        assert_eq!(parse_thp_mode(fixtures::proc_thp_enabled()), Some("madvise"));
+        assert_eq!(parse_thp_mode("[always] madvise never\n"), Some("always"));
+        assert_eq!(parse_anon_huge_pages_kb(fixtures::proc_smaps_rollup()), Some(376_832));
+
+        let smaps = fixtures::proc_smaps();
+        assert_eq!(parse_thp_eligible(smaps, 0x55d0_c1a0_0100), Some(false));
+        assert_eq!(parse_thp_eligible(smaps, 0x7f3a_0000_0000), Some(true));
+        assert_eq!(parse_thp_eligible(smaps, 0x7f3a_009f_ffff), Some(true));
+        assert_eq!(parse_thp_eligible(smaps, 0x7f3a_00a0_0000), Some(false));
+        assert_eq!(parse_thp_eligible(smaps, 0x1000), None);
+    }
+
+    #[test]
+    fn missing_fields_are_unsupported() {
+        assert_eq!(parse_vmstat("nr_free_pages 12\npswpin 3\n"), None);
+        assert_eq!(parse_mem_available_kb("MemTotal: 100 kB\n"), None);
+        assert_eq!(parse_peak_rss_kb("VmRSS:\t42816 kB\n"), None);
+        assert_eq!(parse_thp_mode("always madvise never\n"), None);
+        assert_eq!(parse_anon_huge_pages_kb("Rss: 10 kB\n"), None);
+        // Kernels before 4.19 have no THPeligible line.
+        let old_kernel = "7f3a00000000-7f3a00a00000 rw-p 00000000 00:00 0\nRss: 8 kB\n";
+        assert_eq!(parse_thp_eligible(old_kernel, 0x7f3a_0000_0000), None);
+    }
+
+    #[test]
+    fn delta_saturates_on_counter_reset() {
+        let before = SwapCounters {
+            swap_in_pages: 10,
+            swap_out_pages: 4,
+        };
+        let after = SwapCounters {
+            swap_in_pages: 12,
+            swap_out_pages: 1,
+        };
+        let delta = before.delta(&after);
+        assert_eq!(delta.swap_in_pages, 2);
+        assert_eq!(delta.swap_out_pages, 0);
+    }
+}
@@ -0,0 +1,527 @@
+//! The record envelope both runners emit, typed. `make_record` builds a
+//! `BenchmarkRecord`, so a misspelled key is a compile error rather than a
+//! schema mismatch with the Python harness. Unknown fields are rejected at
+//! every level the two languages share, so a key added on one side only
+//! fails the round-trip tests instead of drifting into `result.schema.json`
+//! unnoticed. Blocks that only the Rust runner attaches (`preconditions`,
+//! `dataset`, ...) stay as JSON; their own modules own their shape.
+//!
+//! Every output that writes records (the results file, upload batches, the
+//! viewer) goes through `to_json`/`to_json_pretty`, so the same records are
+//! always the same bytes: top-level fields in `FIELD_ORDER`, every nested
+//! object (metrics, environment, parameters, ...) sorted by key.
+
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::cpus::CpuSource;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Language {
+    Python,
+    Rust,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Variant {
+    pub(crate) runtime: String,
+    pub(crate) version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Metric {
+    #[serde(deserialize_with = "nan_for_null")]
+    pub(crate) value: f64,
+    pub(crate) unit: String,
+}
+
+impl Metric {
+    pub(crate) fn new(value: f64, unit: &str) -> Metric {
+        Metric {
+            value,
+            unit: unit.to_string(),
+        }
+    }
+
+    /// `{"value": ..., "unit": ...}`; a non-finite value becomes null.
+    pub(crate) fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("metric serializes")
+    }
+}
+
+/// A record's metrics by name. Benchmarks build them with `insert`, so an
+/// entry the schema would reject cannot be written at all.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Metrics(BTreeMap<String, Metric>);
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        Metrics::default()
+    }
+
+    /// Sets `name`, replacing an earlier value.
+    pub(crate) fn insert(&mut self, name: impl Into<String>, value: f64, unit: &str) {
+        self.0.insert(name.into(), Metric::new(value, unit));
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Metric> {
+        self.0.get(name)
+    }
+
+    /// The value of `name`, if it was set.
+    pub(crate) fn value(&self, name: &str) -> Option<f64> {
+        self.0.get(name).map(|metric| metric.value)
+    }
+
+    pub(crate) fn contains_key(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&String, &Metric)> {
+        self.0.iter()
+    }
+}
+
+/// Panics when `name` is not set, like indexing a map.
+impl std::ops::Index<&str> for Metrics {
+    type Output = Metric;
+
+    fn index(&self, name: &str) -> &Metric {
+        self.0
+            .get(name)
+            .unwrap_or_else(|| panic!("no metric {name}"))
+    }
+}
+
+impl From<Metric> for Value {
+    fn from(metric: Metric) -> Value {
+        metric.to_value()
+    }
+}
+
+/// A non-finite value is written as null; reading it back gives NaN.
+fn nan_for_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+/// The keys both runners detect the same way; everything else a runner or
+/// platform reports (locale, scheduler, THP mode, ...) is kept in `extra`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Environment {
+    pub(crate) os: String,
+    pub(crate) cpu_count: usize,
+    pub(crate) cpus_detected: Option<usize>,
+    pub(crate) cpu_count_source: CpuSource,
+    #[serde(flatten)]
+    pub(crate) extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BenchmarkRecord {
+    /// `--namespace`: whose harness produced the record, kept apart from
+    /// `benchmark_id` so ids still match across namespaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) namespace: Option<String>,
+    pub(crate) benchmark_id: String,
+    pub(crate) category: String,
+    pub(crate) language: Language,
+    pub(crate) variant: Variant,
+    pub(crate) environment: Environment,
+    pub(crate) metrics: Metrics,
+    pub(crate) timestamp: String,
+    pub(crate) commit_sha: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) harness_fingerprint: Option<String>,
+    pub(crate) run_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) attempt: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) selected_for_comparison: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) preconditions: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) target: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) dataset: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) calibration: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) matrix_cell: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) phases: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) failure: Option<Value>,
+    /// A sample message per error category, for benchmarks that count
+    /// errors by category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) diagnostics: Option<Value>,
+    /// Conditions that leave the record usable but worth a second look;
+    /// see `warnings::WarningCode`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) warnings: Option<Value>,
+    /// `--trim`: the policy and how many repeats it dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) trim: Option<Value>,
+    /// `--emit-samples`: per-repeat metric values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) samples: Option<Value>,
+    /// Workload inputs, with the key names of the Rust configs; `{}` when
+    /// there are none.
+    pub(crate) parameters: Map<String, Value>,
+}
+
+impl BenchmarkRecord {
+    pub(crate) fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("record serializes")
+    }
+}
+
+/// Top-level fields in the order they are written: `BenchmarkRecord`'s
+/// field order. A field a record does not carry is left out; a field not
+/// listed here goes after these, alphabetically.
+pub(crate) const FIELD_ORDER: [&str; 25] = [
+    "namespace",
+    "benchmark_id",
+    "category",
+    "language",
+    "variant",
+    "environment",
+    "metrics",
+    "timestamp",
+    "commit_sha",
+    "harness_fingerprint",
+    "run_id",
+    "attempt",
+    "selected_for_comparison",
+    "preconditions",
+    "target",
+    "dataset",
+    "calibration",
+    "matrix_cell",
+    "phases",
+    "failure",
+    "diagnostics",
+    "warnings",
+    "trim",
+    "samples",
+    "parameters",
+];
+
+/// `--namespace` values: lowercase alphanumeric words joined by single
+/// dashes, like `payments-perf`.
+pub(crate) fn check_namespace(namespace: &str) -> Result<(), String> {
+    let valid = !namespace.is_empty()
+        && namespace.split('-').all(|word| {
+            !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("--namespace: {namespace:?} is not lowercase and dash-separated"))
+    }
+}
+
+/// The one namespace `records` share, `None` when none carries one. Records
+/// from different namespaces (a namespaced file merged with one that is
+/// not) are an error, since their ids would be compared as if they were
+/// the same benchmarks.
+pub(crate) fn shared_namespace(records: &[Value]) -> Result<Option<&str>, String> {
+    let mut seen: Vec<Option<&str>> = Vec::new();
+    for record in records {
+        let namespace = record["namespace"].as_str();
+        if !seen.contains(&namespace) {
+            seen.push(namespace);
+        }
+    }
+    if seen.len() > 1 {
+        let names: Vec<&str> = seen.iter().map(|name| name.unwrap_or("(none)")).collect();
+        return Err(format!("records span namespaces {}", names.join(", ")));
+    }
+    Ok(seen.first().copied().flatten())
+}
+
+/// Records in canonical order, for embedding in a larger payload.
+pub(crate) struct Canonical<'a>(pub(crate) &'a [Value]);
+
+impl Serialize for Canonical<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(CanonicalRecord))
+    }
+}
+
+struct CanonicalRecord<'a>(&'a Value);
+
+impl Serialize for CanonicalRecord<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Value::Object(fields) = self.0 else {
+            return Sorted(self.0).serialize(serializer);
+        };
+        let mut rest: Vec<&String> = fields
+            .keys()
+            .filter(|key| !FIELD_ORDER.contains(&key.as_str()))
+            .collect();
+        rest.sort();
+        let known = FIELD_ORDER.iter().filter_map(|key| fields.get_key_value(*key));
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for (key, value) in known.chain(rest.into_iter().map(|key| (key, &fields[key]))) {
+            map.serialize_entry(key, &Sorted(value))?;
+        }
+        map.end()
+    }
+}
+
+/// Any JSON value with its object keys sorted at every level, whatever
+/// order the `Map` holds them in.
+struct Sorted<'a>(&'a Value);
+
+impl Serialize for Sorted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Object(fields) => {
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+                let mut map = serializer.serialize_map(Some(keys.len()))?;
+                for key in keys {
+                    map.serialize_entry(key, &Sorted(&fields[key]))?;
+                }
+                map.end()
+            }
+            Value::Array(items) => serializer.collect_seq(items.iter().map(Sorted)),
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+/// Records as one line of canonical JSON.
+pub(crate) fn to_json(records: &[Value]) -> String {
+    serde_json::to_string(&Canonical(records)).expect("records serialize")
+}
+
+/// One record as a line of canonical JSON, without the array around it.
+pub(crate) fn record_to_json(record: &Value) -> String {
+    serde_json::to_string(&CanonicalRecord(record)).expect("record serializes")
+}
+
+/// Records as indented canonical JSON, for the results file.
+pub(crate) fn to_json_pretty(records: &[Value]) -> String {
+    serde_json::to_string_pretty(&Canonical(records)).expect("records serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use serde_json::json;
+
+    fn python_records() -> Vec<Value> {
+        serde_json::from_str(fixtures::python_records()).unwrap()
+    }
+
+    #[test]
+    fn python_records_deserialize_without_loss() {
+        let records = python_records();
+        assert_eq!(records.len(), 4);
+        for original in records {
+            let typed: BenchmarkRecord = serde_json::from_value(original.clone()).unwrap();
+            assert_eq!(typed.language, Language::Python);
+            assert!(typed.harness_fingerprint.is_none());
+            assert_eq!(typed.to_value(), original, "{}", typed.benchmark_id);
+        }
+    }
+
+    #[test]
+    fn fields_added_on_one_side_are_rejected() {
+        let original = python_records().remove(0);
+        let additions = [
+            ("", "duration"),
+            ("/variant", "implementation"),
+            ("/metrics/runtime_seconds", "stddev"),
+        ];
+        for (pointer, key) in additions {
+            let mut record = original.clone();
+            record.pointer_mut(pointer).unwrap()[key] = json!(1);
+            let error = serde_json::from_value::<BenchmarkRecord>(record).unwrap_err();
+            assert!(error.to_string().contains(key), "{pointer} {key}: {error}");
+        }
+        let mut record = original.clone();
+        record["language"] = json!("go");
+        assert!(serde_json::from_value::<BenchmarkRecord>(record).is_err());
+        for required in ["commit_sha", "parameters"] {
+            let mut record = original.clone();
+            record.as_object_mut().unwrap().remove(required);
+            assert!(serde_json::from_value::<BenchmarkRecord>(record).is_err(), "{required}");
+        }
+    }
+
+    #[test]
+    fn the_environment_block_stays_open_beyond_the_shared_keys() {
+        let mut record = python_records().remove(0);
+        record["environment"]["memory_mb"] = json!(2048.0);
+        let typed: BenchmarkRecord = serde_json::from_value(record.clone()).unwrap();
+        assert_eq!(typed.environment.cpu_count_source, CpuSource::Detected);
+        assert_eq!(typed.environment.extra["memory_mb"], 2048.0);
+        assert_eq!(typed.to_value(), record);
+
+        let mut unknown = record.clone();
+        unknown["environment"]["cpu_count_source"] = json!("guessed");
+        assert!(serde_json::from_value::<BenchmarkRecord>(unknown).is_err());
+        let mut missing = record;
+        missing["environment"].as_object_mut().unwrap().remove("os");
+        assert!(serde_json::from_value::<BenchmarkRecord>(missing).is_err());
+    }
+
+    #[test]
+    fn metrics_serialize_as_value_and_unit() {
+        assert_eq!(Metric::new(1.5, "s").to_value(), json!({"value": 1.5, "unit": "s"}));
+        let nan = Metric::new(f64::NAN, "ratio").to_value();
+        assert_eq!(nan, json!({"value": null, "unit": "ratio"}));
+        // A null read back is NaN and is written as null again.
+        let read: Metric = serde_json::from_value(nan.clone()).unwrap();
+        assert!(read.value.is_nan());
+        assert_eq!(read.to_value(), nan);
+
+        let malformed = [json!(1.5), json!({"value": "fast", "unit": "s"}), json!({"value": 1.0})];
+        for malformed in malformed {
+            assert!(
+                serde_json::from_value::<Metric>(malformed.clone()).is_err(),
+                "{malformed}"
+            );
+        }
+        let mut metrics = Metrics::new();
+        metrics.insert("runtime_seconds", 1.5, "s");
+        metrics.insert("rps_ratio", f64::NAN, "ratio");
+        let expected = json!({
+            "rps_ratio": {"value": null, "unit": "ratio"},
+            "runtime_seconds": {"value": 1.5, "unit": "s"},
+        });
+        assert_eq!(serde_json::to_value(&metrics).unwrap(), expected);
+        assert_eq!(metrics.value("runtime_seconds"), Some(1.5));
+    }
+
+    #[test]
+    fn the_same_records_always_serialize_to_the_same_bytes() {
+        let records = python_records();
+        let first = to_json_pretty(&records);
+        assert_eq!(to_json_pretty(&records), first);
+        let reread: Vec<Value> = serde_json::from_str(&first).unwrap();
+        assert_eq!(to_json_pretty(&reread), first);
+
+        // Built in a different order, written the same.
+        let mut shuffled = Map::new();
+        for (key, value) in records[0].as_object().unwrap().iter().rev() {
+            shuffled.insert(key.clone(), value.clone());
+        }
+        let mut metrics = Map::new();
+        for (name, metric) in records[0]["metrics"].as_object().unwrap().iter().rev() {
+            metrics.insert(name.clone(), metric.clone());
+        }
+        shuffled.insert("metrics".to_string(), Value::Object(metrics));
+        let original = to_json(&records[..1]);
+        assert_eq!(to_json(&[Value::Object(shuffled)]), original);
+
+        #[derive(Deserialize)]
+        struct Written {
+            metrics: Keys,
+        }
+        let [Written { metrics: Keys(names) }] = serde_json::from_str(&original).unwrap();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn field_order_follows_the_struct_and_the_docs() {
+        let original = python_records().remove(0);
+        let mut record: BenchmarkRecord = serde_json::from_value(original).unwrap();
+        record.namespace = Some("team-a".to_string());
+        record.harness_fingerprint = Some("f".to_string());
+        record.attempt = Some(1);
+        record.selected_for_comparison = Some(true);
+        for block in [
+            &mut record.preconditions,
+            &mut record.target,
+            &mut record.dataset,
+            &mut record.calibration,
+            &mut record.matrix_cell,
+            &mut record.phases,
+            &mut record.failure,
+            &mut record.diagnostics,
+            &mut record.warnings,
+            &mut record.trim,
+            &mut record.samples,
+        ] {
+            *block = Some(json!(0));
+        }
+        // Serializing the struct itself keeps its declaration order.
+        let direct = serde_json::to_string(&record).unwrap();
+        let Keys(declared) = serde_json::from_str(&direct).unwrap();
+        assert_eq!(declared, FIELD_ORDER);
+
+        let mut value = record.to_value();
+        value["unlisted_b"] = json!(0);
+        value["unlisted_a"] = json!(0);
+        let [Keys(written)] = serde_json::from_str(&to_json(&[value])).unwrap();
+        assert_eq!(written[..FIELD_ORDER.len()], FIELD_ORDER);
+        assert_eq!(written[FIELD_ORDER.len()..], ["unlisted_a", "unlisted_b"]);
+
+        let documented = format!("`{}`", FIELD_ORDER.join("`, `"));
+        let methodology = include_str!("../../../docs/methodology.md");
+        assert!(methodology.contains(&documented), "{documented}");
+    }
+
+    #[test]
+    fn namespaces_are_validated_and_must_agree() {
+        for valid in ["payments", "payments-perf", "team-2"] {
+            assert_eq!(check_namespace(valid), Ok(()), "{valid}");
+        }
+        for invalid in ["", "Payments", "payments_perf", "-payments", "payments--perf", "a b"] {
+            assert!(check_namespace(invalid).is_err(), "{invalid:?}");
+        }
+
+        let plain = json!({"benchmark_id": "cpu_monte_carlo_pi"});
+        let team = json!({"namespace": "team-a", "benchmark_id": "cpu_monte_carlo_pi"});
+        assert_eq!(shared_namespace(&[]), Ok(None));
+        assert_eq!(shared_namespace(&[plain.clone(), plain.clone()]), Ok(None));
+        assert_eq!(shared_namespace(&[team.clone(), team.clone()]), Ok(Some("team-a")));
+        let error = shared_namespace(&[team, plain]).unwrap_err();
+        assert_eq!(error, "records span namespaces team-a, (none)");
+    }
+
+    /// The keys of a JSON object in the order they were written, which a
+    /// parsed `Value` does not keep.
+    struct Keys(Vec<String>);
+
+    impl<'de> Deserialize<'de> for Keys {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Keys, D::Error> {
+            struct Visitor;
+
+            impl<'de> serde::de::Visitor<'de> for Visitor {
+                type Value = Keys;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("an object")
+                }
+
+                fn visit_map<A>(self, mut map: A) -> Result<Keys, A::Error>
+                where
+                    A: serde::de::MapAccess<'de>,
+                {
+                    let mut keys = Vec::new();
+                    while let Some((key, serde::de::IgnoredAny)) = map.next_entry()? {
+                        keys.push(key);
+                    }
+                    Ok(Keys(keys))
+                }
+            }
+
+            deserializer.deserialize_map(Visitor)
+        }
+    }
+}
@@ -0,0 +1,427 @@
+//! Repetition of the timed performance benchmarks (Monte Carlo, JSON
+//! transform, HTTP client, ETL). One sample is too noisy to compare against
+//! the Python harness, so each of them runs `BENCHMARK_REPEATS` times
+//! (default 5) and its record reports the spread: `runtime_seconds` becomes
+//! the mean, with `_mean`, `_stddev`, `_min` and `_max` beside it and the
+//! usual `_n`/`_rse` companions. Every other metric in `SAMPLED_METRICS`
+//! likewise becomes the mean over the repeats that had samples for it, with
+//! `_n` counting those repeats. Every remaining metric is the last repeat's.
+//! With `BENCHMARK_WARMUP=1` one extra repeat runs first and is discarded.
+//!
+//! The workloads are deterministic, so their correctness metric must come
+//! out the same every time; a repeat that disagrees with the first sets
+//! `repeat_checksum_mismatch`. Repeats with transient errors (a refused
+//! HTTP request changes the HTTP checksum) are left out of that comparison.
+//!
+//! `--repeat N` overrides `BENCHMARK_REPEATS`. With `--trim` every sampled
+//! metric is summarized after dropping its outlying repeats, and the
+//! record's `trim` block names the policy and how many repeats it dropped,
+//! per metric. With `--emit-samples` the record's `samples` array holds
+//! each repeat's runtime and its other sampled metrics.
+//! Past `--max-samples` repeats the array is a uniform reservoir draw, in
+//! repeat order, so long runs keep records small; trimming always sees
+//! every repeat.
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::env;
+
+use crate::record::{Metric, Metrics};
+use crate::retry;
+use crate::rng::next_f64;
+use crate::stats::{self, insert_sampled, Samples, Trim, COUNT_SUFFIX, REPEATED_RUNTIME};
+use crate::DEFAULT_SEED;
+
+pub(crate) const DEFAULT_REPEATS: usize = 5;
+pub(crate) const DEFAULT_MAX_SAMPLES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Repeats {
+    /// Repeats that count, warm-up excluded.
+    pub(crate) count: usize,
+    pub(crate) warmup: bool,
+    pub(crate) trim: Trim,
+    /// `--emit-samples`: keep at most this many repeats in `samples`.
+    pub(crate) emit_samples: Option<usize>,
+}
+
+impl Default for Repeats {
+    fn default() -> Self {
+        Repeats {
+            count: DEFAULT_REPEATS,
+            warmup: false,
+            trim: Trim::None,
+            emit_samples: None,
+        }
+    }
+}
+
+/// A repeated benchmark's metrics, and the record blocks beside them.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Repeated {
+    pub(crate) metrics: Metrics,
+    /// The `trim` block; `None` without a trim policy.
+    pub(crate) trim: Option<Value>,
+    /// The `samples` array; `None` without `--emit-samples`.
+    pub(crate) samples: Option<Value>,
+}
+
+impl Repeats {
+    pub(crate) fn from_env() -> Self {
+        Repeats::parse(
+            env::var("BENCHMARK_REPEATS").ok().as_deref(),
+            env::var("BENCHMARK_WARMUP").ok().as_deref(),
+        )
+    }
+
+    /// Anything but a positive count keeps the default.
+    fn parse(repeats: Option<&str>, warmup: Option<&str>) -> Self {
+        Repeats {
+            count: repeats
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .filter(|count| *count > 0)
+                .unwrap_or(DEFAULT_REPEATS),
+            warmup: warmup == Some("1"),
+            ..Repeats::default()
+        }
+    }
+
+    /// Calls `measure` once per repeat, warm-up first, telling it whether
+    /// this is the first call. Returns the last repeat's metrics with the
+    /// runtime summary and the checksum comparison over `checksum` added.
+    pub(crate) fn run(&self, checksum: &str, mut measure: impl FnMut(bool) -> Metrics) -> Repeated {
+        if self.warmup {
+            measure(true);
+        }
+        let mut runtimes = Samples::new();
+        // Each sampled metric's values, with its unit from the first repeat
+        // that had it.
+        let mut sampled: BTreeMap<String, (String, Samples)> = BTreeMap::new();
+        let mut reference: Option<f64> = None;
+        let mut mismatch = false;
+        let mut last = Metrics::new();
+        let mut samples = SampleReservoir::new(self.emit_samples.unwrap_or(0));
+        for index in 0..self.count {
+            let metrics = measure(index == 0 && !self.warmup);
+            if let Some(runtime) = metrics.value("runtime_seconds") {
+                runtimes.push(runtime);
+            }
+            for (key, metric) in sampled_values(&metrics) {
+                let (_, values) = sampled
+                    .entry(key.clone())
+                    .or_insert_with(|| (metric.unit.clone(), Samples::new()));
+                values.push(metric.value);
+            }
+            if self.emit_samples.is_some() {
+                samples.push(iteration_sample(index, &metrics));
+            }
+            if !retry::has_transient_errors(&metrics) {
+                let value = metrics.value(checksum);
+                match reference {
+                    Some(reference) => mismatch |= value != Some(reference),
+                    None => reference = value,
+                }
+            }
+            last = metrics;
+        }
+        let (kept, trimmed) = self.trim.apply(&runtimes);
+        insert_runtime_summary(&mut last, &kept);
+        let mut trimmed_by_metric = BTreeMap::from([(REPEATED_RUNTIME.0.to_string(), trimmed)]);
+        for (key, (first_unit, values)) in sampled {
+            let (kept, trimmed) = self.trim.apply(&values);
+            // The last repeat may not have sampled this metric at all.
+            let unit = last.get(&key).map_or(first_unit, |metric| metric.unit.clone());
+            insert_sampled(&mut last, &key, kept.mean(), &unit, &kept);
+            trimmed_by_metric.insert(key, trimmed);
+        }
+        last.insert("repeat_checksum_mismatch", mismatch as u8 as f64, "flag");
+        last.insert("repeat_warmup_discarded", self.warmup as u8 as f64, "flag");
+        Repeated {
+            metrics: last,
+            trim: (self.trim != Trim::None).then(|| {
+                json!({
+                    "policy": self.trim.label(),
+                    "trimmed": trimmed,
+                    "trimmed_by_metric": trimmed_by_metric,
+                })
+            }),
+            samples: self.emit_samples.map(|_| Value::Array(samples.into_sorted())),
+        }
+    }
+}
+
+/// One repeat's sampled metrics other than the runtime, by key: those
+/// `SAMPLED_METRICS` lists that had at least one sample this repeat.
+fn sampled_values(metrics: &Metrics) -> Vec<(&String, &Metric)> {
+    metrics
+        .iter()
+        .filter(|(key, _)| *key != REPEATED_RUNTIME.0 && stats::sampled(key).is_some())
+        .filter(|(key, _)| metrics.value(&format!("{key}{COUNT_SUFFIX}")) > Some(0.0))
+        .collect()
+}
+
+/// One repeat's `samples` entry: its index, `runtime_seconds`, and every
+/// other metric `SAMPLED_METRICS` lists.
+fn iteration_sample(index: usize, metrics: &Metrics) -> Value {
+    let mut sample = Map::new();
+    sample.insert("iteration".to_string(), json!(index));
+    for (key, metric) in metrics.iter().filter(|(key, _)| stats::sampled(key).is_some()) {
+        sample.insert(key.clone(), json!(metric.value));
+    }
+    Value::Object(sample)
+}
+
+/// A uniform draw of at most `cap` samples, like `Samples::reservoir`.
+struct SampleReservoir {
+    kept: Vec<Value>,
+    seen: usize,
+    cap: usize,
+    rng: u64,
+}
+
+impl SampleReservoir {
+    fn new(cap: usize) -> Self {
+        SampleReservoir {
+            kept: Vec::new(),
+            seen: 0,
+            cap,
+            rng: DEFAULT_SEED,
+        }
+    }
+
+    fn push(&mut self, sample: Value) {
+        self.seen += 1;
+        if self.kept.len() < self.cap {
+            self.kept.push(sample);
+            return;
+        }
+        let slot = (next_f64(&mut self.rng) * self.seen as f64) as usize;
+        if slot < self.cap {
+            self.kept[slot] = sample;
+        }
+    }
+
+    /// The kept samples in repeat order.
+    fn into_sorted(mut self) -> Vec<Value> {
+        self.kept.sort_by_key(|sample| sample["iteration"].as_u64());
+        self.kept
+    }
+}
+
+fn insert_runtime_summary(map: &mut Metrics, runtimes: &Samples) {
+    let (key, _) = REPEATED_RUNTIME;
+    insert_sampled(map, key, runtimes.mean(), "s", runtimes);
+    let summary = [
+        ("mean", runtimes.mean()),
+        ("stddev", runtimes.stddev()),
+        ("min", runtimes.min()),
+        ("max", runtimes.max()),
+    ];
+    for (name, value) in summary {
+        map.insert(format!("runtime_seconds_{name}"), value, "s");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_metrics(runtime: f64, checksum: f64, errors: f64) -> Metrics {
+        let mut map = Metrics::new();
+        map.insert("runtime_seconds", runtime, "s");
+        map.insert("checksum", checksum, "count");
+        map.insert("request_errors", errors, "count");
+        map
+    }
+
+    #[test]
+    fn repeats_parse_from_the_environment_values() {
+        assert_eq!(Repeats::parse(None, None), Repeats::default());
+        let parsed = Repeats::parse(Some("3"), Some("1"));
+        assert_eq!((parsed.count, parsed.warmup), (3, true));
+        assert_eq!(Repeats::parse(Some("0"), Some("yes")).count, DEFAULT_REPEATS);
+        assert_eq!(Repeats::parse(Some("many"), None).count, DEFAULT_REPEATS);
+    }
+
+    #[test]
+    fn runtimes_are_summarized_and_the_warmup_is_dropped() {
+        let repeats = Repeats {
+            count: 4,
+            warmup: true,
+            ..Repeats::default()
+        };
+        let runtimes = [9.0, 1.0, 2.0, 3.0, 6.0];
+        let mut calls = Vec::new();
+        let repeated = repeats.run("checksum", |first| {
+            calls.push(first);
+            run_metrics(runtimes[calls.len() - 1], 7.0, 0.0)
+        });
+        assert_eq!((repeated.trim, repeated.samples), (None, None));
+        let map = repeated.metrics;
+        assert_eq!(calls, [true, false, false, false, false]);
+        assert_eq!(map["runtime_seconds"].value, 3.0);
+        assert_eq!(map["runtime_seconds_mean"].value, 3.0);
+        assert_eq!(map["runtime_seconds_min"].value, 1.0);
+        assert_eq!(map["runtime_seconds_max"].value, 6.0);
+        let stddev = map["runtime_seconds_stddev"].value;
+        assert!((stddev - (14.0f64 / 3.0).sqrt()).abs() < 1e-12, "{stddev}");
+        assert_eq!(map["runtime_seconds_n"].value, 4.0);
+        assert_eq!(map["repeat_checksum_mismatch"].value, 0.0);
+        assert_eq!(map["repeat_warmup_discarded"].value, 1.0);
+    }
+
+    #[test]
+    fn a_diverging_checksum_is_flagged_unless_the_repeat_had_errors() {
+        let repeats = Repeats {
+            count: 3,
+            ..Repeats::default()
+        };
+        let mut calls = 0;
+        let map = repeats.run("checksum", |_| {
+            calls += 1;
+            run_metrics(1.0, if calls == 2 { 8.0 } else { 7.0 }, 0.0)
+        })
+        .metrics;
+        assert_eq!(map["repeat_checksum_mismatch"].value, 1.0);
+
+        let mut calls = 0;
+        let map = repeats.run("checksum", |_| {
+            calls += 1;
+            let failed = calls == 2;
+            run_metrics(1.0, if failed { 8.0 } else { 7.0 }, failed as u8 as f64)
+        })
+        .metrics;
+        assert_eq!(map["repeat_checksum_mismatch"].value, 0.0);
+        assert_eq!(map["runtime_seconds_n"].value, 3.0);
+    }
+
+    #[test]
+    fn trimming_drops_outliers_from_the_runtime_summary() {
+        let repeats = Repeats {
+            count: 10,
+            trim: Trim::Percent(10),
+            ..Repeats::default()
+        };
+        let runtimes = [9.0, 1.0, 5.0, 3.0, 7.0, 2.0, 8.0, 4.0, 6.0, 100.0];
+        let mut calls = 0;
+        let repeated = repeats.run("checksum", |_| {
+            calls += 1;
+            run_metrics(runtimes[calls - 1], 7.0, 0.0)
+        });
+        let trim = json!({
+            "policy": "pct:10",
+            "trimmed": 2,
+            "trimmed_by_metric": {"runtime_seconds": 2},
+        });
+        assert_eq!(repeated.trim, Some(trim));
+        assert_eq!(repeated.metrics["runtime_seconds"].value, 5.5);
+        assert_eq!(repeated.metrics["runtime_seconds_max"].value, 9.0);
+        assert_eq!(repeated.metrics["runtime_seconds_n"].value, 8.0);
+    }
+
+    #[test]
+    fn every_listed_sampled_metric_is_trimmed_and_averaged_over_repeats() {
+        let repeats = Repeats {
+            count: 10,
+            trim: Trim::Percent(10),
+            ..Repeats::default()
+        };
+        let p50s = [4.0, 1.0, 5.0, 3.0, 7.0, 2.0, 8.0, 6.0, 9.0, 500.0];
+        let mut calls = 0;
+        let repeated = repeats.run("checksum", |_| {
+            calls += 1;
+            let mut map = run_metrics(1.0, 7.0, 0.0);
+            let p50 = p50s[calls - 1];
+            insert_sampled(&mut map, "latency_p50_ms", p50, "ms", &Samples::from(vec![p50; 3]));
+            // No successful request this repeat: nothing to average.
+            let empty = if calls == 3 { Samples::new() } else { Samples::from(vec![1.0]) };
+            insert_sampled(&mut map, "latency_p99_ms", calls as f64, "ms", &empty);
+            // Not in `SAMPLED_METRICS`, so the last repeat's value stands.
+            map.insert("tasks_n", calls as f64, "count");
+            map
+        });
+        let metrics = &repeated.metrics;
+        assert_eq!(metrics["latency_p50_ms"].value, 5.5);
+        assert_eq!(metrics["latency_p50_ms"].unit, "ms");
+        assert_eq!(metrics["latency_p50_ms_n"].value, 8.0);
+        assert!(metrics.contains_key("latency_p50_ms_rse"));
+        // Nine repeats had samples, too few for 10% to drop any.
+        assert_eq!(metrics["latency_p99_ms_n"].value, 9.0);
+        assert!(!metrics.contains_key("latency_p99_ms_rse"));
+        assert_eq!(metrics["tasks_n"].value, 10.0);
+        let trimmed = &repeated.trim.unwrap()["trimmed_by_metric"];
+        let expected = json!({"latency_p50_ms": 2, "latency_p99_ms": 0, "runtime_seconds": 2});
+        assert_eq!(*trimmed, expected);
+    }
+
+    #[test]
+    fn a_metric_the_last_repeat_lacks_keeps_its_first_unit() {
+        let repeats = Repeats {
+            count: 3,
+            ..Repeats::default()
+        };
+        let mut calls = 0;
+        let repeated = repeats.run("checksum", |_| {
+            calls += 1;
+            let mut map = run_metrics(1.0, 7.0, 0.0);
+            if calls < 3 {
+                let p50 = calls as f64;
+                insert_sampled(&mut map, "latency_p50_ms", p50, "ms", &Samples::from(vec![p50]));
+            }
+            map
+        });
+        let metrics = &repeated.metrics;
+        assert_eq!(metrics["latency_p50_ms"].value, 1.5);
+        assert_eq!(metrics["latency_p50_ms"].unit, "ms");
+        assert_eq!(metrics["latency_p50_ms_n"].value, 2.0);
+    }
+
+    #[test]
+    fn samples_hold_each_repeat_up_to_the_cap() {
+        let repeats = Repeats {
+            count: 3,
+            emit_samples: Some(10),
+            ..Repeats::default()
+        };
+        let mut calls = 0;
+        let repeated = repeats.run("checksum", |_| {
+            calls += 1;
+            let mut map = run_metrics(calls as f64, 7.0, 0.0);
+            let latencies = Samples::from(vec![calls as f64 * 10.0]);
+            insert_sampled(&mut map, "latency_p50_ms", calls as f64 * 10.0, "ms", &latencies);
+            map
+        });
+        assert_eq!(
+            repeated.samples.unwrap(),
+            json!([
+                {"iteration": 0, "runtime_seconds": 1.0, "latency_p50_ms": 10.0},
+                {"iteration": 1, "runtime_seconds": 2.0, "latency_p50_ms": 20.0},
+                {"iteration": 2, "runtime_seconds": 3.0, "latency_p50_ms": 30.0},
+            ])
+        );
+
+        let capped = Repeats {
+            count: 200,
+            emit_samples: Some(20),
+            ..Repeats::default()
+        };
+        let mut calls = 0;
+        let repeated = capped.run("checksum", |_| {
+            calls += 1;
+            run_metrics(calls as f64, 7.0, 0.0)
+        });
+        let samples = repeated.samples.unwrap();
+        let iterations: Vec<u64> = samples
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|sample| sample["iteration"].as_u64().unwrap())
+            .collect();
+        assert_eq!(iterations.len(), 20);
+        assert!(iterations.windows(2).all(|pair| pair[0] < pair[1]));
+        // A uniform draw reaches past the first cap's worth of repeats.
+        assert!(*iterations.last().unwrap() >= 20, "{iterations:?}");
+        // The summary still covers every repeat.
+        assert_eq!(repeated.metrics["runtime_seconds_n"].value, 200.0);
+    }
+}
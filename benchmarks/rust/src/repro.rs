@@ -0,0 +1,427 @@
+//! `repro.json` and `--replay`: a run's fully resolved settings, written
+//! next to its results, and a later run with exactly those settings.
+//!
+//! Configs are recorded after defaults, `--config`, env vars and
+//! `--preset auto` have all been applied, so a replay reads none of them
+//! again. The exceptions are the `MACHINE_SPECIFIC` keys, paths and base
+//! URLs that seldom hold on another machine: one this machine resolves to
+//! something other than its default (from an env var or `--config`)
+//! replaces the recorded value, and the benchmark's records get a
+//! `replay_override` warning saying so.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::benchmark_ids::{
+    self, DATA_PIPELINE_ETL_MINIBATCH, DEPENDENCY_VULNERABILITY_SCAN_SCORECARD,
+    IO_CONCURRENT_HTTP_CLIENT, IO_CONCURRENT_HTTP_POST, IO_HTTP_CONNECTION_CHURN,
+    IO_HTTP_STREAMING, STATIC_SECURITY_LINT_BENCHMARK,
+};
+use crate::config::{self, BenchmarkConfigs};
+use crate::record;
+use crate::repeats::Repeats;
+use crate::stats::Trim;
+use crate::warnings::{Warning, WarningCode};
+
+/// Bumped when a field changes meaning; older files are refused.
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// Config keys that name something on the machine that ran, by benchmark.
+const MACHINE_SPECIFIC: [(&str, &[&str]); 7] = [
+    (IO_CONCURRENT_HTTP_CLIENT, &["base_url"]),
+    (IO_CONCURRENT_HTTP_POST, &["base_url"]),
+    (IO_HTTP_CONNECTION_CHURN, &["base_url"]),
+    (IO_HTTP_STREAMING, &["base_url"]),
+    (DATA_PIPELINE_ETL_MINIBATCH, &["dataset"]),
+    (DEPENDENCY_VULNERABILITY_SCAN_SCORECARD, &["advisory_db_path", "osv_snapshot"]),
+    (STATIC_SECURITY_LINT_BENCHMARK, &["manifests"]),
+];
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Repro {
+    pub(crate) format_version: u32,
+    pub(crate) harness_fingerprint: String,
+    /// The checkout's commit, as on the records' `commit_sha`.
+    pub(crate) commit_sha: String,
+    pub(crate) seed: u64,
+    /// `auto` when `--preset auto` scaled `configs`; a replay runs the
+    /// scaled sizes as recorded rather than calibrating again.
+    pub(crate) preset: Option<String>,
+    /// The benchmarks run, in registry order; empty ran them all.
+    pub(crate) benchmarks: Vec<String>,
+    pub(crate) repeats: usize,
+    pub(crate) warmup: bool,
+    /// `--trim`, as `Trim::parse` reads it.
+    #[serde(default = "no_trim")]
+    pub(crate) trim: String,
+    /// `--emit-samples`: the most repeats kept per record.
+    #[serde(default)]
+    pub(crate) emit_samples: Option<usize>,
+    #[serde(default)]
+    pub(crate) namespace: Option<String>,
+    /// Each configurable benchmark's parameters, by id.
+    pub(crate) configs: BTreeMap<String, Value>,
+}
+
+fn no_trim() -> String {
+    Trim::None.label()
+}
+
+/// A machine-specific value a replay took from this machine instead of
+/// the file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Override {
+    pub(crate) benchmark_id: String,
+    pub(crate) key: &'static str,
+    /// `null` when the recording left the key out.
+    pub(crate) recorded: Value,
+    pub(crate) used: Value,
+}
+
+impl Override {
+    pub(crate) fn warning(&self) -> Warning {
+        Warning::new(
+            WarningCode::ReplayOverride,
+            format!(
+                "--replay: {}.{} is {} here; the recorded run used {}",
+                self.benchmark_id, self.key, self.used, self.recorded
+            ),
+            json!({"key": self.key, "recorded": self.recorded, "used": self.used}),
+        )
+    }
+}
+
+/// What a replay runs.
+#[derive(Debug)]
+pub(crate) struct Resolved {
+    pub(crate) benchmarks: Vec<&'static str>,
+    pub(crate) configs: BenchmarkConfigs,
+    pub(crate) repeats: Repeats,
+    pub(crate) overrides: Vec<Override>,
+    /// Other keys this machine sets differently, as `<id>.<key>`; the
+    /// recorded values win for these.
+    pub(crate) ignored: Vec<String>,
+}
+
+impl Repro {
+    pub(crate) fn capture(
+        configs: &BenchmarkConfigs,
+        benchmarks: &[&str],
+        preset: Option<&str>,
+        repeats: &Repeats,
+        namespace: Option<&str>,
+        (harness_fingerprint, commit_sha, seed): (&str, &str, u64),
+    ) -> Repro {
+        let configs = benchmark_ids::ALL
+            .into_iter()
+            .filter(|id| config::is_configurable(id))
+            .map(|id| (id.to_string(), configs.params_for(id)))
+            .collect();
+        Repro {
+            format_version: FORMAT_VERSION,
+            harness_fingerprint: harness_fingerprint.to_string(),
+            commit_sha: commit_sha.to_string(),
+            seed,
+            preset: preset.map(str::to_string),
+            benchmarks: benchmarks.iter().map(|id| id.to_string()).collect(),
+            repeats: repeats.count,
+            warmup: repeats.warmup,
+            trim: repeats.trim.label(),
+            emit_samples: repeats.emit_samples,
+            namespace: namespace.map(str::to_string),
+            configs,
+        }
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<Repro, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("--replay: cannot read {}: {err}", path.display()))?;
+        let repro: Repro = serde_json::from_str(&text)
+            .map_err(|err| format!("--replay: {}: {err}", path.display()))?;
+        if repro.format_version != FORMAT_VERSION {
+            return Err(format!(
+                "--replay: {} has format_version {}; this build reads {FORMAT_VERSION}",
+                path.display(),
+                repro.format_version
+            ));
+        }
+        Ok(repro)
+    }
+
+    /// Refuses a replay by a harness that would not run the recorded
+    /// workloads the same way, unless `force`; a forced mismatch comes
+    /// back as a message to warn with.
+    pub(crate) fn check_harness(
+        &self,
+        harness_fingerprint: &str,
+        seed: u64,
+        force: bool,
+    ) -> Result<Option<String>, String> {
+        let mut differences = Vec::new();
+        if self.harness_fingerprint != harness_fingerprint {
+            differences.push(format!(
+                "harness fingerprint {} (recorded {})",
+                harness_fingerprint, self.harness_fingerprint
+            ));
+        }
+        if self.seed != seed {
+            differences.push(format!("seed {seed} (recorded {})", self.seed));
+        }
+        if differences.is_empty() {
+            return Ok(None);
+        }
+        let message = format!("--replay: this build has {}", differences.join(" and "));
+        if force {
+            Ok(Some(message))
+        } else {
+            Err(format!("{message}; pass --replay-force to run anyway"))
+        }
+    }
+
+    /// The benchmark selection, checked against the registry.
+    fn selection(&self) -> Result<Vec<&'static str>, String> {
+        self.benchmarks
+            .iter()
+            .map(|id| {
+                benchmark_ids::ALL
+                    .into_iter()
+                    .find(|known| known == id)
+                    .ok_or_else(|| format!("--replay: unknown benchmark {id:?}"))
+            })
+            .collect()
+    }
+
+    /// The recorded repeat policy, checked as the flags that set it are.
+    fn repeats(&self) -> Result<Repeats, String> {
+        if self.repeats == 0 || self.emit_samples == Some(0) {
+            return Err("--replay: repeats and emit_samples must be positive".to_string());
+        }
+        if let Some(namespace) = &self.namespace {
+            record::check_namespace(namespace).map_err(|err| format!("--replay: {err}"))?;
+        }
+        Ok(Repeats {
+            count: self.repeats,
+            warmup: self.warmup,
+            trim: Trim::parse(&self.trim).map_err(|err| format!("--replay: trim: {err}"))?,
+            emit_samples: self.emit_samples,
+        })
+    }
+
+    /// The recorded selection and configs, with this machine's
+    /// machine-specific values where `current` (resolved from env vars and
+    /// `--config`) sets them.
+    pub(crate) fn resolve(&self, current: &BenchmarkConfigs) -> Result<Resolved, String> {
+        let defaults = BenchmarkConfigs::default();
+        let mut resolved = Resolved {
+            benchmarks: self.selection()?,
+            repeats: self.repeats()?,
+            configs: BenchmarkConfigs {
+                deprecations: current.deprecations.clone(),
+                ..BenchmarkConfigs::default()
+            },
+            overrides: Vec::new(),
+            ignored: Vec::new(),
+        };
+        for (benchmark_id, params) in &self.configs {
+            if !config::is_configurable(benchmark_id) {
+                return Err(format!("--replay: no configurable benchmark named {benchmark_id:?}"));
+            }
+            let Some(recorded) = params.as_object() else {
+                return Err(format!("--replay: {benchmark_id}: expected an object"));
+            };
+            let here = current.params_for(benchmark_id);
+            let default = defaults.params_for(benchmark_id);
+            let machine_specific = MACHINE_SPECIFIC
+                .iter()
+                .find(|(id, _)| id == benchmark_id)
+                .map_or(&[][..], |(_, keys)| keys);
+            let mut params = recorded.clone();
+            for (key, value) in here.as_object().into_iter().flatten() {
+                if default.get(key) == Some(value) || recorded.get(key) == Some(value) {
+                    continue;
+                }
+                match machine_specific.iter().find(|machine_key| *machine_key == key) {
+                    Some(machine_key) => {
+                        resolved.overrides.push(Override {
+                            benchmark_id: benchmark_id.clone(),
+                            key: machine_key,
+                            recorded: recorded.get(key).cloned().unwrap_or(Value::Null),
+                            used: value.clone(),
+                        });
+                        params.insert(key.clone(), value.clone());
+                    }
+                    None => resolved.ignored.push(format!("{benchmark_id}.{key}")),
+                }
+            }
+            resolved.configs = resolved
+                .configs
+                .with_overrides(benchmark_id, &Map::from_iter(params))
+                .map_err(|err| format!("--replay: {err}"))?;
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark_ids::{CPU_MONTE_CARLO_PI, TEST_ROBUSTNESS_RELIABILITY};
+    use crate::config::HttpClientConfig;
+
+    const REPEATS: Repeats =
+        Repeats { count: 5, warmup: true, trim: Trim::Percent(10), emit_samples: Some(3) };
+
+    fn capture(configs: &BenchmarkConfigs) -> Repro {
+        let benchmarks = [CPU_MONTE_CARLO_PI, IO_CONCURRENT_HTTP_CLIENT];
+        let harness = ("fp1", "abc1234", 42);
+        Repro::capture(configs, &benchmarks, Some("auto"), &REPEATS, Some("nightly"), harness)
+    }
+
+    fn recorded_configs() -> BenchmarkConfigs {
+        let mut configs = BenchmarkConfigs::default();
+        configs.monte_carlo.samples = 12_345;
+        configs.http_client = HttpClientConfig {
+            base_url: "http://10.0.0.2:9000".to_string(),
+            requests: 77,
+            ..HttpClientConfig::default()
+        };
+        configs.test_reliability.iterations = 9;
+        configs
+    }
+
+    #[test]
+    fn a_recorded_run_resolves_to_the_same_configs_through_a_file() {
+        let configs = recorded_configs();
+        let repro = capture(&configs);
+        assert_eq!(repro.configs[TEST_ROBUSTNESS_RELIABILITY], json!({"iterations": 9}));
+
+        let path = std::env::temp_dir().join(format!("repro-{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_string_pretty(&repro).unwrap()).unwrap();
+        let read = Repro::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, repro);
+
+        // Defaults here: nothing of this machine's replaces the recording.
+        let resolved = read.resolve(&BenchmarkConfigs::default()).unwrap();
+        assert_eq!(resolved.benchmarks, [CPU_MONTE_CARLO_PI, IO_CONCURRENT_HTTP_CLIENT]);
+        assert_eq!(resolved.configs, configs);
+        assert_eq!(resolved.repeats, REPEATS);
+        assert_eq!(read.namespace.as_deref(), Some("nightly"));
+        assert!(resolved.overrides.is_empty());
+        assert!(resolved.ignored.is_empty());
+
+        let mut older = json!(read);
+        older["format_version"] = json!(0);
+        fs::write(&path, older.to_string()).unwrap();
+        let err = Repro::read(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(err.ends_with("has format_version 0; this build reads 1"), "{err}");
+    }
+
+    #[test]
+    fn the_harness_fingerprint_and_seed_must_match_unless_forced() {
+        let repro = capture(&BenchmarkConfigs::default());
+        assert_eq!(repro.check_harness("fp1", 42, false), Ok(None));
+
+        let err = repro.check_harness("fp2", 42, false).unwrap_err();
+        assert_eq!(
+            err,
+            "--replay: this build has harness fingerprint fp2 (recorded fp1); \
+             pass --replay-force to run anyway"
+        );
+        let forced = repro.check_harness("fp2", 7, true).unwrap().unwrap();
+        assert_eq!(
+            forced,
+            "--replay: this build has harness fingerprint fp2 (recorded fp1) \
+             and seed 7 (recorded 42)"
+        );
+        assert!(repro.check_harness("fp1", 7, false).is_err());
+    }
+
+    #[test]
+    fn machine_specific_values_set_here_override_with_a_warning() {
+        let repro = capture(&recorded_configs());
+        let mut here = BenchmarkConfigs::default();
+        here.http_client.base_url = "http://192.168.1.5:8000".to_string();
+        // Not machine-specific: the recorded request count stands.
+        here.http_client.requests = 5;
+        // The same as recorded: nothing to override.
+        here.monte_carlo.samples = 12_345;
+
+        let resolved = repro.resolve(&here).unwrap();
+        assert_eq!(resolved.configs.http_client.base_url, "http://192.168.1.5:8000");
+        assert_eq!(resolved.configs.http_client.requests, 77);
+        assert_eq!(resolved.configs.monte_carlo.samples, 12_345);
+        assert_eq!(resolved.ignored, ["io_concurrent_http_client.request_count"]);
+        assert_eq!(
+            resolved.overrides,
+            [Override {
+                benchmark_id: IO_CONCURRENT_HTTP_CLIENT.to_string(),
+                key: "base_url",
+                recorded: json!("http://10.0.0.2:9000"),
+                used: json!("http://192.168.1.5:8000"),
+            }]
+        );
+        let warning = json!(resolved.overrides[0].warning());
+        assert_eq!(warning["code"], "replay_override");
+        assert_eq!(
+            warning["message"],
+            "--replay: io_concurrent_http_client.base_url is \"http://192.168.1.5:8000\" here; \
+             the recorded run used \"http://10.0.0.2:9000\""
+        );
+        assert_eq!(
+            warning["details"],
+            json!({
+                "key": "base_url",
+                "recorded": "http://10.0.0.2:9000",
+                "used": "http://192.168.1.5:8000"
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_benchmarks_and_bad_params_are_refused() {
+        let mut repro = capture(&BenchmarkConfigs::default());
+        repro.configs.insert(CPU_MONTE_CARLO_PI.to_string(), json!({"samplez": 1}));
+        let err = repro.resolve(&BenchmarkConfigs::default()).unwrap_err();
+        assert!(err.starts_with("--replay: "), "{err}");
+        assert!(err.contains("samplez"), "{err}");
+
+        repro.configs.remove(CPU_MONTE_CARLO_PI);
+        repro.configs.insert("cpu_montecarlo_pi".to_string(), json!({}));
+        assert!(repro.resolve(&BenchmarkConfigs::default()).is_err());
+
+        repro.configs.remove("cpu_montecarlo_pi");
+        repro.benchmarks = vec!["cpu_montecarlo_pi".to_string()];
+        assert_eq!(
+            repro.resolve(&BenchmarkConfigs::default()).unwrap_err(),
+            "--replay: unknown benchmark \"cpu_montecarlo_pi\""
+        );
+    }
+
+    #[test]
+    fn recordings_without_a_repeat_policy_read_as_the_defaults() {
+        let mut older = json!(capture(&BenchmarkConfigs::default()));
+        let older = older.as_object_mut().unwrap();
+        for key in ["trim", "emit_samples", "namespace"] {
+            older.remove(key);
+        }
+        let older: Repro = serde_json::from_value(json!(older)).unwrap();
+        let resolved = older.resolve(&BenchmarkConfigs::default()).unwrap();
+        assert_eq!(resolved.repeats.trim, Trim::None);
+        assert_eq!(resolved.repeats.emit_samples, None);
+        assert_eq!(older.namespace, None);
+
+        let mut bad = capture(&BenchmarkConfigs::default());
+        bad.trim = "pct:90".to_string();
+        let err = bad.resolve(&BenchmarkConfigs::default()).unwrap_err();
+        assert!(err.starts_with("--replay: trim"), "{err}");
+        bad.trim = "none".to_string();
+        bad.namespace = Some("Night Ly".to_string());
+        assert!(bad.resolve(&BenchmarkConfigs::default()).unwrap_err().starts_with("--replay: "));
+    }
+}
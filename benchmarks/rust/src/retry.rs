@@ -0,0 +1,154 @@
+//! Opt-in reruns for benchmarks whose records show transient infrastructure
+//! errors (a restarted HTTP fixture, a disk hiccup). Correctness mismatches
+//! are bugs, not noise, and are never retried.
+
+use serde_json::{json, Value};
+
+use crate::record::Metrics;
+
+/// Error counters that can be caused by the environment rather than the code.
+const TRANSIENT_ERROR_METRICS: [&str; 3] = ["request_errors", "operation_errors", "io_errors"];
+
+/// Any nonzero value here rules a record out of retrying.
+const CORRECTNESS_FLAGS: [&str; 5] = [
+    "checksum_mismatch",
+    "repeat_checksum_mismatch",
+    "vectorized_mismatch",
+    "parse_comparison_errors",
+    "buffer_sweep_aggregate_mismatch",
+];
+
+/// Whether a benchmark's metrics count any transient error.
+pub(crate) fn has_transient_errors(metrics: &Metrics) -> bool {
+    TRANSIENT_ERROR_METRICS
+        .iter()
+        .any(|name| metrics.value(name).is_some_and(|value| value > 0.0))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct RetryPolicy {
+    /// Extra attempts per benchmark; 0 disables retrying.
+    pub(crate) max_retries: usize,
+    /// A record is retried when a transient error count exceeds this.
+    pub(crate) error_threshold: f64,
+}
+
+fn metric_value(record: &Value, name: &str) -> f64 {
+    record["metrics"][name]["value"].as_f64().unwrap_or(0.0)
+}
+
+impl RetryPolicy {
+    pub(crate) fn needs_retry(&self, record: &Value) -> bool {
+        if CORRECTNESS_FLAGS
+            .iter()
+            .any(|name| metric_value(record, name) != 0.0)
+        {
+            return false;
+        }
+        TRANSIENT_ERROR_METRICS
+            .iter()
+            .any(|name| metric_value(record, name) > self.error_threshold)
+    }
+
+    /// Calls `attempt` (a full rerun, setup included) until a record needs
+    /// no retry or the retries are used up. With retrying enabled every
+    /// record is numbered by `attempt` and only the last one is
+    /// `selected_for_comparison`.
+    pub(crate) fn run(&self, mut attempt: impl FnMut() -> Value) -> Vec<Value> {
+        let mut records = vec![attempt()];
+        while records.len() <= self.max_retries
+            && records.last().is_some_and(|record| self.needs_retry(record))
+        {
+            records.push(attempt());
+        }
+        if self.max_retries > 0 {
+            let selected = records.len() - 1;
+            for (index, record) in records.iter_mut().enumerate() {
+                record["attempt"] = json!(index + 1);
+                record["selected_for_comparison"] = json!(index == selected);
+            }
+        }
+        records
+    }
+}
+
+/// Records without the flag predate retrying and always count.
+pub(crate) fn is_selected(record: &Value) -> bool {
+    record
+        .get("selected_for_comparison")
+        .and_then(Value::as_bool)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(metrics: &[(&str, f64)]) -> Value {
+        let metrics: serde_json::Map<String, Value> = metrics
+            .iter()
+            .map(|(name, value)| (name.to_string(), json!({"value": value, "unit": "count"})))
+            .collect();
+        json!({"benchmark_id": "io_concurrent_http_client", "metrics": metrics})
+    }
+
+    fn policy(max_retries: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            error_threshold: 0.0,
+        }
+    }
+
+    #[test]
+    fn transient_errors_above_threshold_are_retried() {
+        assert!(policy(1).needs_retry(&record(&[("request_errors", 2.0)])));
+        assert!(policy(1).needs_retry(&record(&[("io_errors", 1.0)])));
+        assert!(!policy(1).needs_retry(&record(&[("request_errors", 0.0)])));
+        assert!(!policy(1).needs_retry(&record(&[("scan_errors", 3.0)])));
+        let lenient = RetryPolicy {
+            max_retries: 1,
+            error_threshold: 2.0,
+        };
+        assert!(!lenient.needs_retry(&record(&[("request_errors", 2.0)])));
+    }
+
+    #[test]
+    fn correctness_mismatches_are_never_retried() {
+        for flag in CORRECTNESS_FLAGS {
+            let failing = record(&[("request_errors", 5.0), (flag, 1.0)]);
+            assert!(!policy(3).needs_retry(&failing), "{flag}");
+        }
+    }
+
+    #[test]
+    fn keeps_every_attempt_and_selects_the_last() {
+        let mut errors = vec![0.0, 4.0, 4.0];
+        let records = policy(5).run(|| record(&[("request_errors", errors.pop().unwrap())]));
+        assert_eq!(records.len(), 3);
+        let attempts: Vec<_> = records.iter().map(|r| r["attempt"].clone()).collect();
+        assert_eq!(attempts, [json!(1), json!(2), json!(3)]);
+        let selected: Vec<bool> = records.iter().map(is_selected).collect();
+        assert_eq!(selected, [false, false, true]);
+        assert_eq!(records[2]["metrics"]["request_errors"]["value"], 0.0);
+    }
+
+    #[test]
+    fn stops_when_retries_are_exhausted() {
+        let mut calls = 0;
+        let records = policy(2).run(|| {
+            calls += 1;
+            record(&[("operation_errors", 1.0)])
+        });
+        assert_eq!(calls, 3);
+        assert!(is_selected(&records[2]));
+        assert!(!is_selected(&records[0]));
+    }
+
+    #[test]
+    fn disabled_policy_leaves_records_untouched() {
+        let records = policy(0).run(|| record(&[("request_errors", 1.0)]));
+        assert_eq!(records.len(), 1);
+        assert!(records[0].get("attempt").is_none());
+        assert!(is_selected(&records[0]));
+    }
+}
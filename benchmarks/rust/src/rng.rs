@@ -0,0 +1,68 @@
+//! The runner's random streams. Every benchmark draws from xorshift64
+//! (`next_f64`), the same generator the Python runner implements. Code that
+//! needs one stream per worker thread derives it from the run seed with
+//! `stream`, never from the clock or thread id, so a fixed seed reproduces
+//! the same draws per worker however the threads are scheduled.
+
+/// splitmix64's increment (2^64 over the golden ratio).
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Uniform in `(0, 1]` for a nonzero `state`; advances `state`.
+pub(crate) fn next_f64(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state as f64) / (u64::MAX as f64)
+}
+
+/// splitmix64's output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Initial `next_f64` state for stream `index` of `seed`: output
+/// `index + 1` of a splitmix64 generator seeded with `seed`, i.e.
+/// `mix(seed + (index + 1) * GAMMA)` in wrapping u64 arithmetic. Distinct
+/// indexes never share a state, since `mix` is a bijection. The one input
+/// mixing to 0 yields 1 instead, as xorshift never leaves 0.
+pub(crate) fn stream(seed: u64, index: u64) -> u64 {
+    mix(seed.wrapping_add(index.wrapping_add(1).wrapping_mul(GAMMA))).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shared with `benchmarks/python/tests/test_runner.py`; a change here
+    /// must change both runners.
+    const VECTORS: [(u64, u64, u64); 6] = [
+        (42, 0, 0xbdd7_3226_2feb_6e95),
+        (42, 1, 0x28ef_e333_b266_f103),
+        (42, 2, 0x4752_6757_130f_9f52),
+        (42, 3, 0x581c_e1ff_0e4a_e394),
+        (0, 0, 0xe220_a839_7b1d_cdaf),
+        (7, 0, 0x63cb_e1e4_5932_0dd7),
+    ];
+
+    #[test]
+    fn streams_match_the_pinned_vectors() {
+        for (seed, index, state) in VECTORS {
+            assert_eq!(stream(seed, index), state, "seed {seed} stream {index}");
+        }
+        // splitmix64's published first output for seed 1234567.
+        assert_eq!(stream(1_234_567, 0), 6_457_827_717_110_365_317);
+    }
+
+    #[test]
+    fn streams_diverge_from_the_first_draw() {
+        let mut first: Vec<u64> = (0..64).map(|index| stream(42, index)).collect();
+        let draws: Vec<f64> = first.iter_mut().map(next_f64).collect();
+        for (index, draw) in draws.iter().enumerate() {
+            assert!(*draw > 0.0 && *draw <= 1.0);
+            assert!(!draws[..index].contains(draw), "stream {index} repeats an earlier draw");
+        }
+        assert_ne!(stream(42, 0), stream(43, 0));
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::rng::next_f64;
+
+/// Per-request latency injected by the mock server.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub(crate) enum LatencyDistribution {
+    Fixed { ms: f64 },
+    Uniform { min_ms: f64, max_ms: f64 },
+    /// Heavy tail above `scale_ms`; samples are clamped to `cap_ms` so a
+    /// single draw cannot stall a run.
+    Pareto { scale_ms: f64, shape: f64, cap_ms: f64 },
+}
+
+impl LatencyDistribution {
+    pub(crate) fn sample_ms(&self, state: &mut u64) -> f64 {
+        match *self {
+            LatencyDistribution::Fixed { ms } => ms,
+            LatencyDistribution::Uniform { min_ms, max_ms } => {
+                min_ms + (max_ms - min_ms) * next_f64(state)
+            }
+            // Inverse CDF; `next_f64` never returns 0 for a nonzero state.
+            LatencyDistribution::Pareto {
+                scale_ms,
+                shape,
+                cap_ms,
+            } => (scale_ms / next_f64(state).powf(1.0 / shape)).min(cap_ms),
+        }
+        .max(0.0)
+    }
+
+    pub(crate) fn sample(&self, state: &mut u64) -> Duration {
+        Duration::from_secs_f64(self.sample_ms(state) / 1e3)
+    }
+}
+
+/// True with probability `rate`.
+pub(crate) fn bernoulli(rate: f64, state: &mut u64) -> bool {
+    rate > 0.0 && next_f64(state) < rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::{Rank, Samples};
+
+    fn draw(distribution: &LatencyDistribution, count: usize) -> Samples {
+        let mut state = 42;
+        let values: Vec<f64> = (0..count).map(|_| distribution.sample_ms(&mut state)).collect();
+        Samples::from(values)
+    }
+
+    #[test]
+    fn fixed_and_uniform_summaries() {
+        let fixed = draw(&LatencyDistribution::Fixed { ms: 2.5 }, 100);
+        assert_eq!((fixed.min(), fixed.max()), (2.5, 2.5));
+
+        let uniform = draw(
+            &LatencyDistribution::Uniform {
+                min_ms: 1.0,
+                max_ms: 3.0,
+            },
+            20_000,
+        );
+        assert!(uniform.min() >= 1.0 && uniform.max() <= 3.0);
+        assert!((uniform.mean() - 2.0).abs() < 0.02);
+        assert!((uniform.percentile(90.0, Rank::Nearest) - 2.8).abs() < 0.03);
+    }
+
+    #[test]
+    fn pareto_tail_matches_closed_form() {
+        let pareto = draw(
+            &LatencyDistribution::Pareto {
+                scale_ms: 1.0,
+                shape: 3.0,
+                cap_ms: 1e9,
+            },
+            50_000,
+        );
+        assert!(pareto.min() >= 1.0);
+        // Mean is shape * scale / (shape - 1); median is scale * 2^(1/shape).
+        assert!((pareto.mean() - 1.5).abs() < 0.03);
+        assert!((pareto.percentile(50.0, Rank::Nearest) - 2f64.powf(1.0 / 3.0)).abs() < 0.02);
+
+        let capped = draw(
+            &LatencyDistribution::Pareto {
+                scale_ms: 1.0,
+                shape: 0.5,
+                cap_ms: 10.0,
+            },
+            1_000,
+        );
+        assert_eq!(capped.max(), 10.0);
+    }
+
+    #[test]
+    fn bernoulli_rate() {
+        let mut state = 7;
+        let hits = (0..20_000).filter(|_| bernoulli(0.1, &mut state)).count();
+        assert!((hits as f64 / 20_000.0 - 0.1).abs() < 0.01);
+        assert!(!(0..100).any(|_| bernoulli(0.0, &mut state)));
+    }
+}
@@ -0,0 +1,761 @@
+//! `--sink kind=target`: extra places a run's records go, as each benchmark
+//! finishes, next to `--output`. Several sinks can be given; each record is
+//! handed to all of them. `--output` itself is a `file-json` sink that gets
+//! the final records once the run ends.
+//!
+//! A sink that fails does not stop the others or the run: its errors are
+//! counted, the first is kept, and every sink's status goes into the run
+//! summary. A sink that cannot start (an unwritable path, say) gets no
+//! records at all.
+//!
+//! Kinds:
+//!
+//! - `file-json=<path>`: one JSON array, written when the run ends.
+//! - `jsonl=<path>`: one canonical record per line, flushed as it arrives.
+//! - `csv=<path>`: one row per metric, flushed as each record arrives.
+//! - `prometheus-file=<path>`: gauges in the Prometheus text format, for
+//!   the node exporter's textfile collector; written when the run ends.
+//! - `http-push=<url>`: each record POSTed as a batch of one, in the
+//!   `--upload` batch format, without batching, retries or a spool.
+//!
+//! `sqlite` is recognised but refused: the build has no SQLite library.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::record;
+use crate::retry;
+use crate::upload::{self, Collector, HttpCollector};
+
+/// What receives a run's records.
+pub(crate) trait OutputSink {
+    /// Called once, before any record.
+    fn begin_run(&mut self, run_id: &str) -> Result<(), String>;
+    /// One finished record.
+    fn record(&mut self, record: &Value) -> Result<(), String>;
+    /// Called once, after the last record.
+    fn end_run(&mut self) -> Result<(), String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SinkKind {
+    FileJson,
+    Jsonl,
+    Csv,
+    PrometheusFile,
+    HttpPush,
+}
+
+impl SinkKind {
+    fn name(self) -> &'static str {
+        match self {
+            SinkKind::FileJson => "file-json",
+            SinkKind::Jsonl => "jsonl",
+            SinkKind::Csv => "csv",
+            SinkKind::PrometheusFile => "prometheus-file",
+            SinkKind::HttpPush => "http-push",
+        }
+    }
+}
+
+/// One `--sink` flag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SinkSpec {
+    pub(crate) kind: SinkKind,
+    pub(crate) target: String,
+}
+
+impl SinkSpec {
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        const KINDS: &str = "file-json, jsonl, csv, prometheus-file or http-push";
+        let Some((kind, target)) = value.split_once('=') else {
+            return Err(format!("expected <kind>=<target>, got {value:?}"));
+        };
+        let kind = match kind {
+            "file-json" => SinkKind::FileJson,
+            "jsonl" => SinkKind::Jsonl,
+            "csv" => SinkKind::Csv,
+            "prometheus-file" => SinkKind::PrometheusFile,
+            "http-push" => SinkKind::HttpPush,
+            "sqlite" => return Err("sqlite is not supported by this build".to_string()),
+            _ => return Err(format!("unknown kind {kind:?}; expected {KINDS}")),
+        };
+        if target.is_empty() {
+            return Err(format!("{} needs a target", kind.name()));
+        }
+        if kind == SinkKind::HttpPush {
+            HttpCollector::new(target).map_err(|err| err.replacen("--upload: ", "", 1))?;
+        }
+        Ok(SinkSpec {
+            kind,
+            target: target.to_string(),
+        })
+    }
+
+    /// How the sink is named in messages and the run summary.
+    pub(crate) fn label(&self) -> String {
+        format!("{}={}", self.kind.name(), self.target)
+    }
+
+    pub(crate) fn open(&self) -> Result<Box<dyn OutputSink>, String> {
+        let path = PathBuf::from(&self.target);
+        Ok(match self.kind {
+            SinkKind::FileJson => Box::new(FileJsonSink::new(path)),
+            SinkKind::Jsonl => Box::new(JsonlSink::new(path)),
+            SinkKind::Csv => Box::new(CsvSink::new(path)),
+            SinkKind::PrometheusFile => Box::new(PrometheusFileSink::new(path)),
+            SinkKind::HttpPush => Box::new(HttpPushSink::new(HttpCollector::new(&self.target)?)),
+        })
+    }
+}
+
+/// A sink's outcome, reported under `sinks` in the run summary.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct SinkStatus {
+    pub(crate) sink: String,
+    /// Records the sink accepted.
+    pub(crate) records: usize,
+    /// Failed calls, including a failed start or end.
+    pub(crate) errors: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) first_error: Option<String>,
+}
+
+impl SinkStatus {
+    pub(crate) fn ok(&self) -> bool {
+        self.errors == 0
+    }
+
+    fn fail(&mut self, err: String) {
+        self.errors += 1;
+        self.first_error.get_or_insert(err);
+    }
+}
+
+struct Entry {
+    /// `None` once it failed to open or to begin the run; it gets nothing
+    /// more.
+    sink: Option<Box<dyn OutputSink>>,
+    status: SinkStatus,
+}
+
+/// Every sink of a run, each record handed to all of them.
+#[derive(Default)]
+pub(crate) struct Fanout {
+    entries: Vec<Entry>,
+}
+
+impl Fanout {
+    /// Opens the sinks in `specs`; one that cannot be opened is reported
+    /// as failed and skipped.
+    pub(crate) fn open(specs: &[SinkSpec]) -> Self {
+        let mut fanout = Fanout::default();
+        for spec in specs {
+            match spec.open() {
+                Ok(sink) => fanout.add(spec.label(), sink),
+                Err(err) => {
+                    eprintln!("warning: sink {}: {err}; it gets no records", spec.label());
+                    let mut status = SinkStatus {
+                        sink: spec.label(),
+                        ..SinkStatus::default()
+                    };
+                    status.fail(err);
+                    fanout.entries.push(Entry { sink: None, status });
+                }
+            }
+        }
+        fanout
+    }
+
+    pub(crate) fn add(&mut self, label: String, sink: Box<dyn OutputSink>) {
+        self.entries.push(Entry {
+            sink: Some(sink),
+            status: SinkStatus {
+                sink: label,
+                ..SinkStatus::default()
+            },
+        });
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn begin_run(&mut self, run_id: &str) {
+        for entry in &mut self.entries {
+            let Some(sink) = entry.sink.as_mut() else {
+                continue;
+            };
+            if let Err(err) = sink.begin_run(run_id) {
+                eprintln!("warning: sink {}: {err}; it gets no records", entry.status.sink);
+                entry.status.fail(err);
+                entry.sink = None;
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, record: &Value) {
+        for entry in &mut self.entries {
+            let Some(sink) = entry.sink.as_mut() else {
+                continue;
+            };
+            match sink.record(record) {
+                Ok(()) => entry.status.records += 1,
+                Err(err) => {
+                    if entry.status.ok() {
+                        eprintln!("warning: sink {}: {err}", entry.status.sink);
+                    }
+                    entry.status.fail(err);
+                }
+            }
+        }
+    }
+
+    /// Ends the run on every sink that started it.
+    pub(crate) fn finish(self) -> Vec<SinkStatus> {
+        self.entries
+            .into_iter()
+            .map(|mut entry| {
+                if let Some(Err(err)) = entry.sink.as_mut().map(|sink| sink.end_run()) {
+                    entry.status.fail(err);
+                }
+                entry.status
+            })
+            .collect()
+    }
+}
+
+/// Writes `--output` through a `file-json` sink, once the run's records are
+/// final: checksum flags added and any `--append` merge done.
+pub(crate) fn write_output(path: &Path, records: &[Value]) -> SinkStatus {
+    let mut fanout = Fanout::default();
+    let spec = SinkSpec {
+        kind: SinkKind::FileJson,
+        target: path.display().to_string(),
+    };
+    fanout.add(format!("{} (--output)", spec.label()), Box::new(FileJsonSink::new(path.into())));
+    fanout.begin_run("");
+    for record in records {
+        fanout.record(record);
+    }
+    fanout.finish().remove(0)
+}
+
+fn path_error(path: &Path, err: std::io::Error) -> String {
+    format!("{}: {err}", path.display())
+}
+
+/// Creates `path`, and its parent dir when missing.
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|err| path_error(parent, err))?;
+    }
+    File::create(path).map(BufWriter::new).map_err(|err| path_error(path, err))
+}
+
+/// Written under a temporary name and renamed, so a reader never sees half
+/// a file.
+fn replace_file(path: &Path, contents: &str) -> Result<(), String> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, contents).map_err(|err| path_error(&temp, err))?;
+    fs::rename(&temp, path).map_err(|err| path_error(path, err))
+}
+
+/// `file-json`: the records as one array, like `--output`.
+struct FileJsonSink {
+    path: PathBuf,
+    records: Vec<Value>,
+}
+
+impl FileJsonSink {
+    fn new(path: PathBuf) -> Self {
+        FileJsonSink {
+            path,
+            records: Vec::new(),
+        }
+    }
+}
+
+impl OutputSink for FileJsonSink {
+    /// Creates the file now, so a path that cannot be written fails before
+    /// the benchmarks run rather than after.
+    fn begin_run(&mut self, _: &str) -> Result<(), String> {
+        create(&self.path).map(drop)
+    }
+
+    fn record(&mut self, record: &Value) -> Result<(), String> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn end_run(&mut self) -> Result<(), String> {
+        replace_file(&self.path, &record::to_json_pretty(&self.records))
+    }
+}
+
+/// `jsonl`: one record per line.
+struct JsonlSink {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl JsonlSink {
+    fn new(path: PathBuf) -> Self {
+        JsonlSink { path, writer: None }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Err("run not started".to_string());
+        };
+        writeln!(writer, "{line}")
+            .and_then(|()| writer.flush())
+            .map_err(|err| path_error(&self.path, err))
+    }
+}
+
+impl OutputSink for JsonlSink {
+    fn begin_run(&mut self, _: &str) -> Result<(), String> {
+        self.writer = Some(create(&self.path)?);
+        Ok(())
+    }
+
+    fn record(&mut self, record: &Value) -> Result<(), String> {
+        self.write_line(&record::record_to_json(record))
+    }
+
+    fn end_run(&mut self) -> Result<(), String> {
+        self.writer.take();
+        Ok(())
+    }
+}
+
+const CSV_HEADER: &str = "run_id,namespace,benchmark_id,target,attempt,metric,value,unit";
+
+/// `csv`: one row per metric, so records with different metrics share
+/// the same columns.
+struct CsvSink {
+    lines: JsonlSink,
+}
+
+impl CsvSink {
+    fn new(path: PathBuf) -> Self {
+        CsvSink {
+            lines: JsonlSink::new(path),
+        }
+    }
+}
+
+/// Quoted when it holds a comma, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The record's rows, metrics in name order; a null value is left empty.
+fn csv_rows(record: &Value) -> Vec<String> {
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    let prefix = [
+        &record["run_id"],
+        &record["namespace"],
+        &record["benchmark_id"],
+        &record["target"]["base_url"],
+        &record["attempt"],
+    ]
+    .map(|value| csv_field(&text(value)))
+    .join(",");
+    let Some(metrics) = record["metrics"].as_object() else {
+        return Vec::new();
+    };
+    let mut names: Vec<&String> = metrics.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let metric = &metrics[name];
+            format!(
+                "{prefix},{},{},{}",
+                csv_field(name),
+                text(&metric["value"]),
+                csv_field(&text(&metric["unit"]))
+            )
+        })
+        .collect()
+}
+
+impl OutputSink for CsvSink {
+    fn begin_run(&mut self, run_id: &str) -> Result<(), String> {
+        self.lines.begin_run(run_id)?;
+        self.lines.write_line(CSV_HEADER)
+    }
+
+    fn record(&mut self, record: &Value) -> Result<(), String> {
+        let rows = csv_rows(record);
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.lines.write_line(&rows.join("\n"))
+    }
+
+    fn end_run(&mut self) -> Result<(), String> {
+        self.lines.end_run()
+    }
+}
+
+const PROMETHEUS_GAUGE: &str = "benchmark_metric_value";
+
+/// `prometheus-file`: the selected records' metrics as one gauge, labelled
+/// by benchmark, metric and unit, plus the `--namespace` and whatever tells
+/// records of the same benchmark apart: target, matrix cell and concurrency.
+struct PrometheusFileSink {
+    path: PathBuf,
+    run_id: String,
+    records: Vec<Value>,
+}
+
+impl PrometheusFileSink {
+    fn new(path: PathBuf) -> Self {
+        PrometheusFileSink {
+            path,
+            run_id: String::new(),
+            records: Vec::new(),
+        }
+    }
+}
+
+/// Backslash, quote and line feed escaped, as label values need.
+fn label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn prometheus_text(run_id: &str, records: &[Value]) -> String {
+    let mut text = format!(
+        "# HELP {PROMETHEUS_GAUGE} Benchmark metrics from run {}.\n\
+         # TYPE {PROMETHEUS_GAUGE} gauge\n",
+        run_id.replace('\\', "\\\\").replace('\n', "\\n")
+    );
+    for record in records.iter().filter(|record| retry::is_selected(record)) {
+        let mut labels = vec![(
+            "benchmark_id",
+            record["benchmark_id"].as_str().unwrap_or_default().to_string(),
+        )];
+        if let Some(namespace) = record["namespace"].as_str() {
+            labels.push(("namespace", namespace.to_string()));
+        }
+        let distinguishing = [
+            ("target", &record["target"]["index"]),
+            ("matrix_cell", &record["matrix_cell"]["index"]),
+            ("concurrency", &record["parameters"]["concurrency"]),
+        ];
+        for (name, value) in distinguishing {
+            if !value.is_null() {
+                labels.push((name, value.to_string()));
+            }
+        }
+        let Some(metrics) = record["metrics"].as_object() else {
+            continue;
+        };
+        let mut names: Vec<&String> = metrics.keys().collect();
+        names.sort();
+        for name in names {
+            let metric = &metrics[name];
+            let mut series = labels.clone();
+            series.push(("metric", name.clone()));
+            series.push(("unit", metric["unit"].as_str().unwrap_or_default().to_string()));
+            let series: Vec<String> = series
+                .iter()
+                .map(|(label, value)| format!("{label}=\"{}\"", label_value(value)))
+                .collect();
+            let value = metric["value"].as_f64().unwrap_or(f64::NAN);
+            let value = if value.is_nan() { "NaN".to_string() } else { value.to_string() };
+            let _ = writeln!(text, "{PROMETHEUS_GAUGE}{{{}}} {value}", series.join(","));
+        }
+    }
+    text
+}
+
+impl OutputSink for PrometheusFileSink {
+    fn begin_run(&mut self, run_id: &str) -> Result<(), String> {
+        self.run_id = run_id.to_string();
+        create(&self.path).map(drop)
+    }
+
+    fn record(&mut self, record: &Value) -> Result<(), String> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn end_run(&mut self) -> Result<(), String> {
+        replace_file(&self.path, &prometheus_text(&self.run_id, &self.records))
+    }
+}
+
+/// `http-push`: each record sent on its own, keyed `<run_id>-sink-<n>` so
+/// a collector that also gets `--upload` batches keeps them apart.
+struct HttpPushSink<C: Collector> {
+    collector: C,
+    run_id: String,
+    sent: usize,
+}
+
+impl<C: Collector> HttpPushSink<C> {
+    fn new(collector: C) -> Self {
+        HttpPushSink {
+            collector,
+            run_id: String::new(),
+            sent: 0,
+        }
+    }
+}
+
+impl<C: Collector> OutputSink for HttpPushSink<C> {
+    fn begin_run(&mut self, run_id: &str) -> Result<(), String> {
+        self.run_id = run_id.to_string();
+        Ok(())
+    }
+
+    fn record(&mut self, record: &Value) -> Result<(), String> {
+        let key = format!("{}-sink-{:06}", self.run_id, self.sent);
+        self.sent += 1;
+        let body = upload::batch_body(&key, std::slice::from_ref(record));
+        self.collector.post(&key, &body)
+    }
+
+    fn end_run(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("sinks-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(benchmark_id: &str, runtime: Value) -> Value {
+        json!({
+            "run_id": "rust-1-2",
+            "benchmark_id": benchmark_id,
+            "attempt": 1,
+            "metrics": {
+                "runtime_seconds": {"value": runtime, "unit": "s"},
+                "checksum": {"value": 42.0, "unit": "count"},
+            },
+            "parameters": {},
+        })
+    }
+
+    fn run(sink: &mut dyn OutputSink, records: &[Value]) {
+        sink.begin_run("rust-1-2").unwrap();
+        for record in records {
+            sink.record(record).unwrap();
+        }
+        sink.end_run().unwrap();
+    }
+
+    #[test]
+    fn specs_parse() {
+        let spec = SinkSpec::parse("jsonl=out/run.jsonl").unwrap();
+        assert_eq!((spec.kind, spec.target.as_str()), (SinkKind::Jsonl, "out/run.jsonl"));
+        assert_eq!(spec.label(), "jsonl=out/run.jsonl");
+        let push = SinkSpec::parse("http-push=http://collector:8080/a=b").unwrap();
+        assert_eq!(push.target, "http://collector:8080/a=b");
+        for (bad, expected) in [
+            ("jsonl", "expected <kind>=<target>"),
+            ("parquet=x", "unknown kind \"parquet\""),
+            ("csv=", "csv needs a target"),
+            ("sqlite=runs.db", "sqlite is not supported"),
+            ("http-push=collector:8080", "no http:// or https:// scheme"),
+        ] {
+            let err = SinkSpec::parse(bad).unwrap_err();
+            assert!(err.contains(expected), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn file_sinks_write_what_they_get() {
+        let dir = temp_dir("files");
+        let mut namespaced = record("a,b", Value::Null);
+        namespaced["namespace"] = json!("payments-perf");
+        let records = [record("cpu_monte_carlo_pi", json!(1.5)), namespaced];
+
+        let path = dir.join("nested/run.json");
+        run(&mut FileJsonSink::new(path.clone()), &records);
+        assert_eq!(fs::read_to_string(&path).unwrap(), record::to_json_pretty(&records));
+
+        let path = dir.join("run.jsonl");
+        run(&mut JsonlSink::new(path.clone()), &records);
+        let lines: Vec<Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, records);
+
+        let path = dir.join("run.csv");
+        run(&mut CsvSink::new(path.clone()), &records);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "{CSV_HEADER}\n\
+                 rust-1-2,,cpu_monte_carlo_pi,,1,checksum,42.0,count\n\
+                 rust-1-2,,cpu_monte_carlo_pi,,1,runtime_seconds,1.5,s\n\
+                 rust-1-2,payments-perf,\"a,b\",,1,checksum,42.0,count\n\
+                 rust-1-2,payments-perf,\"a,b\",,1,runtime_seconds,,s\n"
+            )
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prometheus_series_tell_records_apart() {
+        let mut retried = record("cpu_monte_carlo_pi", json!(9.0));
+        retried["selected_for_comparison"] = json!(false);
+        let mut first = record("io_concurrent_http_client", json!(2.0));
+        first["target"] = json!({"index": 0, "base_url": "http://a"});
+        first["parameters"] = json!({"concurrency": 4});
+        let mut second = first.clone();
+        second["target"]["index"] = json!(1);
+        second["metrics"]["runtime_seconds"]["value"] = Value::Null;
+        second["namespace"] = json!("payments-perf");
+        let text = prometheus_text("rust-1-2", &[retried, first, second]);
+        let series: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        let labels = "benchmark_id=\"io_concurrent_http_client\",target=\"0\",concurrency=\"4\"";
+        assert_eq!(series.len(), 4, "{text}");
+        assert_eq!(
+            series[1],
+            format!("{PROMETHEUS_GAUGE}{{{labels},metric=\"runtime_seconds\",unit=\"s\"}} 2")
+        );
+        assert!(series[3].contains("target=\"1\"") && series[3].ends_with("} NaN"), "{text}");
+        let namespaced = "_id=\"io_concurrent_http_client\",namespace=\"payments-perf\",";
+        assert!(series[3].contains(namespaced), "{text}");
+        assert!(!series[1].contains("namespace"), "{text}");
+        assert!(text.starts_with("# HELP benchmark_metric_value"));
+        assert_eq!(label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[derive(Default)]
+    struct Posts(Vec<(String, Value)>);
+
+    impl Collector for Posts {
+        fn post(&mut self, idempotency_key: &str, body: &str) -> Result<(), String> {
+            self.0.push((idempotency_key.to_string(), serde_json::from_str(body).unwrap()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn http_push_sends_each_record_as_a_batch_of_one() {
+        let mut sink = HttpPushSink::new(Posts::default());
+        let records = [record("x", json!(1.0)), record("y", json!(2.0))];
+        run(&mut sink, &records);
+        let keys: Vec<&str> = sink.collector.0.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["rust-1-2-sink-000000", "rust-1-2-sink-000001"]);
+        assert_eq!(sink.collector.0[1].1["records"], json!([records[1]]));
+        assert_eq!(sink.collector.0[1].1["idempotency_key"], keys[1]);
+    }
+
+    /// Fails on the records whose benchmark id is in `failing`.
+    struct Failing {
+        failing: &'static [&'static str],
+        start: Result<(), String>,
+    }
+
+    impl OutputSink for Failing {
+        fn begin_run(&mut self, _: &str) -> Result<(), String> {
+            self.start.clone()
+        }
+
+        fn record(&mut self, record: &Value) -> Result<(), String> {
+            let id = record["benchmark_id"].as_str().unwrap();
+            if self.failing.contains(&id) {
+                Err(format!("cannot take {id}"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn end_run(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn one_failing_sink_leaves_the_others_alone() {
+        let dir = temp_dir("fanout");
+        let path = dir.join("run.jsonl");
+        let mut fanout = Fanout::open(&[
+            SinkSpec::parse(&format!("jsonl={}", path.display())).unwrap(),
+            // A directory cannot be created where a file already is.
+            SinkSpec::parse(&format!("csv={}", path.join("run.csv").display())).unwrap(),
+        ]);
+        fanout.add(
+            "flaky".to_string(),
+            Box::new(Failing {
+                failing: &["b", "c"],
+                start: Ok(()),
+            }),
+        );
+        fanout.add(
+            "down".to_string(),
+            Box::new(Failing {
+                failing: &[],
+                start: Err("refused".to_string()),
+            }),
+        );
+        assert!(!fanout.is_empty());
+        fanout.begin_run("rust-1-2");
+        for id in ["a", "b", "c"] {
+            fanout.record(&record(id, json!(1.0)));
+        }
+        let statuses = fanout.finish();
+
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+        let summary: Vec<(&str, usize, usize)> = statuses
+            .iter()
+            .map(|status| (status.sink.as_str(), status.records, status.errors))
+            .collect();
+        assert_eq!((summary[0].1, summary[0].2), (3, 0));
+        assert_eq!((summary[1].1, summary[1].2), (0, 1));
+        assert_eq!(summary[2..], [("flaky", 1, 2), ("down", 0, 1)]);
+        assert!(statuses[0].ok() && !statuses[2].ok());
+        assert_eq!(statuses[2].first_error.as_deref(), Some("cannot take b"));
+        assert_eq!(statuses[3].first_error.as_deref(), Some("refused"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn output_is_written_by_a_file_json_sink() {
+        let dir = temp_dir("output");
+        let records = [record("a", json!(1.0)), record("b", json!(2.0))];
+        let path = dir.join("results/rust.json");
+        let status = write_output(&path, &records);
+        assert_eq!(status.sink, format!("file-json={} (--output)", path.display()));
+        assert_eq!((status.records, status.errors), (2, 0));
+        assert_eq!(fs::read_to_string(&path).unwrap(), record::to_json_pretty(&records));
+
+        let status = write_output(&path.join("under-a-file.json"), &records);
+        assert_eq!((status.records, status.errors), (0, 1));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
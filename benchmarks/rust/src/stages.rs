@@ -0,0 +1,146 @@
+//! Wall time of the runner's own stages, for the `harness_overhead` record
+//! the cross-language driver asks for with `--harness-overhead`. Every
+//! interval between two marks is charged to exactly one stage, so the
+//! stages always add up to the time since start.
+
+use std::time::{Duration, Instant};
+
+use crate::record::Metrics;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stage {
+    /// Process start through argument parsing and priority changes.
+    Startup,
+    /// The environment block, harness fingerprint and run id.
+    Metadata,
+    /// Configs, data dir, precheck, live metrics and upload replay.
+    Setup,
+    /// Every benchmark run, retries included.
+    Measurement,
+    /// Checksum checks, anonymizing and uploads, through the results write.
+    Output,
+}
+
+impl Stage {
+    const ALL: [Stage; 5] = [
+        Stage::Startup,
+        Stage::Metadata,
+        Stage::Setup,
+        Stage::Measurement,
+        Stage::Output,
+    ];
+
+    fn metric_name(self) -> &'static str {
+        match self {
+            Stage::Startup => "startup_seconds",
+            Stage::Metadata => "metadata_seconds",
+            Stage::Setup => "setup_seconds_total",
+            Stage::Measurement => "measurement_seconds_total",
+            Stage::Output => "output_seconds",
+        }
+    }
+}
+
+pub(crate) struct StageClock {
+    start: Instant,
+    last: Instant,
+    totals: [Duration; 5],
+}
+
+impl StageClock {
+    pub(crate) fn starting_at(start: Instant) -> Self {
+        StageClock {
+            start,
+            last: start,
+            totals: [Duration::ZERO; 5],
+        }
+    }
+
+    /// Charges the time since the previous mark to `stage`.
+    pub(crate) fn mark(&mut self, stage: Stage) {
+        self.mark_at(stage, Instant::now());
+    }
+
+    /// A mark at `now`; one earlier than the previous mark charges nothing,
+    /// so no interval is counted twice.
+    fn mark_at(&mut self, stage: Stage, now: Instant) {
+        self.totals[stage as usize] += now.saturating_duration_since(self.last);
+        self.last = self.last.max(now);
+    }
+
+    pub(crate) fn elapsed(&self, stage: Stage) -> Duration {
+        self.totals[stage as usize]
+    }
+
+    /// From start to the latest mark.
+    pub(crate) fn total(&self) -> Duration {
+        self.last.saturating_duration_since(self.start)
+    }
+
+    pub(crate) fn metrics(&self) -> Metrics {
+        let mut map = Metrics::new();
+        for stage in Stage::ALL {
+            let seconds = self.elapsed(stage).as_secs_f64();
+            map.insert(stage.metric_name(), seconds, "s");
+        }
+        map.insert("total_seconds", self.total().as_secs_f64(), "s");
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn stages_accumulate_and_sum_to_the_total() {
+        let mut clock = StageClock::starting_at(Instant::now());
+        thread::sleep(Duration::from_millis(5));
+        clock.mark(Stage::Startup);
+        clock.mark(Stage::Metadata);
+        thread::sleep(Duration::from_millis(5));
+        clock.mark(Stage::Measurement);
+        thread::sleep(Duration::from_millis(5));
+        clock.mark(Stage::Measurement);
+        assert!(clock.elapsed(Stage::Startup) >= Duration::from_millis(5));
+        assert!(clock.elapsed(Stage::Measurement) >= Duration::from_millis(10));
+        assert_eq!(clock.elapsed(Stage::Output), Duration::ZERO);
+        let sum: Duration = Stage::ALL.iter().map(|stage| clock.elapsed(*stage)).sum();
+        assert_eq!(sum, clock.total());
+
+        let metrics = clock.metrics();
+        let names: Vec<&str> = metrics.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(names.len(), 6);
+        assert_eq!(metrics["output_seconds"].value, 0.0);
+        assert_eq!(metrics["setup_seconds_total"].unit, "s");
+    }
+
+    #[test]
+    fn each_interval_goes_to_the_stage_marked_at_its_end() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut clock = StageClock::starting_at(start);
+        // The order `run_benchmarks` marks them in.
+        clock.mark_at(Stage::Startup, at(1));
+        clock.mark_at(Stage::Setup, at(3));
+        clock.mark_at(Stage::Metadata, at(6));
+        clock.mark_at(Stage::Measurement, at(10));
+        clock.mark_at(Stage::Output, at(15));
+        // A late mark adds to its stage; an out-of-order one adds nothing.
+        clock.mark_at(Stage::Output, at(16));
+        clock.mark_at(Stage::Startup, at(12));
+        let millis: Vec<u128> = Stage::ALL
+            .iter()
+            .map(|stage| clock.elapsed(*stage).as_millis())
+            .collect();
+        assert_eq!(millis, [1, 3, 2, 4, 6]);
+        assert_eq!(clock.total(), Duration::from_millis(16));
+
+        let names: Vec<String> = clock.metrics().iter().map(|(key, _)| key.clone()).collect();
+        let mut expected: Vec<&str> = Stage::ALL.iter().map(|stage| stage.metric_name()).collect();
+        expected.push("total_seconds");
+        expected.sort_unstable();
+        assert_eq!(names, expected);
+    }
+}
@@ -0,0 +1,647 @@
+//! Summary statistics over benchmark samples. Every benchmark that reports a
+//! mean, spread, or percentile goes through `Samples`, so the definitions
+//! (sample stddev, nearest-rank vs interpolated percentiles) cannot drift
+//! between features. Request latencies, of which a run can have millions,
+//! go through `Micros` instead: every one kept, four bytes each.
+//!
+//! A metric computed from samples goes into a record through
+//! `insert_sampled`, which adds companions next to it: `<key>_n`, the
+//! sample count, and for a mean or a median `<key>_rse`, the relative
+//! standard error of the mean. Which metrics are sampled, and what each
+//! reports about its samples, is listed once in `SAMPLED_METRICS`.
+//! Consumers find companions by suffix and show them with their base metric.
+//!
+//! `Trim` drops outliers before aggregating, for summaries over few samples
+//! (the repeated benchmarks) where one descheduled iteration moves the mean.
+
+use std::time::Duration;
+
+use crate::benchmark_ids::{
+    CONCURRENCY_ASYNC_TASKS, CPU_SLEEP_PRECISION, IO_CONCURRENT_HTTP_CLIENT,
+    IO_CONCURRENT_HTTP_POST, IO_HTTP_JITTER_SENSITIVITY,
+};
+use crate::record::Metrics;
+use crate::rng::next_f64;
+
+/// Companion suffixes `insert_sampled` appends to a sampled metric's key.
+pub(crate) const COUNT_SUFFIX: &str = "_n";
+pub(crate) const RSE_SUFFIX: &str = "_rse";
+
+/// What a sampled metric reports about its samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Statistic {
+    Mean,
+    Median,
+    Stddev,
+    /// A tail percentile such as p95 or p99.
+    Percentile,
+    /// The minimum or the maximum.
+    Extreme,
+}
+
+impl Statistic {
+    /// The standard error describes a central estimate; next to a tail,
+    /// an extreme or the spread it would read as a precision they lack.
+    pub(crate) fn has_rse(self) -> bool {
+        matches!(self, Statistic::Mean | Statistic::Median)
+    }
+}
+
+/// Every metric a benchmark computes from samples. A `*` in a key stands
+/// for a configured value (each of `cpu_sleep_precision`'s durations).
+pub(crate) const SAMPLED_METRICS: [(&str, &[(&str, Statistic)]); 5] = [
+    (
+        CPU_SLEEP_PRECISION,
+        &[
+            ("sleep_*ms_mean_overshoot_us", Statistic::Mean),
+            ("sleep_*ms_stddev_overshoot_us", Statistic::Stddev),
+            ("sleep_*ms_min_overshoot_us", Statistic::Extreme),
+            ("sleep_*ms_median_overshoot_us", Statistic::Median),
+            ("sleep_*ms_p95_overshoot_us", Statistic::Percentile),
+            ("sleep_*ms_max_overshoot_us", Statistic::Extreme),
+        ],
+    ),
+    (
+        IO_CONCURRENT_HTTP_CLIENT,
+        &[
+            ("latency_p50_ms", Statistic::Median),
+            ("latency_p95_ms", Statistic::Percentile),
+            ("latency_p99_ms", Statistic::Percentile),
+            ("schedule_lag_p99_ms", Statistic::Percentile),
+        ],
+    ),
+    (
+        IO_CONCURRENT_HTTP_POST,
+        &[
+            ("latency_p50_ms", Statistic::Median),
+            ("latency_p95_ms", Statistic::Percentile),
+            ("latency_p99_ms", Statistic::Percentile),
+            ("schedule_lag_p99_ms", Statistic::Percentile),
+        ],
+    ),
+    (
+        IO_HTTP_JITTER_SENSITIVITY,
+        &[
+            ("low_jitter_p99_latency_us", Statistic::Percentile),
+            ("high_jitter_p99_latency_us", Statistic::Percentile),
+        ],
+    ),
+    (CONCURRENCY_ASYNC_TASKS, &[("round_trip_p99_us", Statistic::Percentile)]),
+];
+
+/// Whether `key` is `pattern` with its `*`, if any, standing for a number.
+pub(crate) fn key_matches(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == key,
+        Some((prefix, suffix)) => key
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .is_some_and(|value| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())),
+    }
+}
+
+/// What a repeated benchmark's record adds on top of `SAMPLED_METRICS`:
+/// its `runtime_seconds` becomes the mean over the repeats.
+pub(crate) const REPEATED_RUNTIME: (&str, Statistic) = ("runtime_seconds", Statistic::Mean);
+
+/// The statistic `key` reports, if it is sampled anywhere.
+pub(crate) fn sampled(key: &str) -> Option<Statistic> {
+    SAMPLED_METRICS
+        .iter()
+        .flat_map(|(_, keys)| keys.iter())
+        .chain([&REPEATED_RUNTIME])
+        .find(|(pattern, _)| key_matches(pattern, key))
+        .map(|(_, statistic)| *statistic)
+}
+
+/// How `Samples::percentile` picks a value between ranks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Rank {
+    /// The smallest sample with at least `pct`% of samples at or below it;
+    /// always an observed value.
+    Nearest,
+    /// Linear interpolation between the two closest ranks (numpy's default).
+    Linear,
+}
+
+/// A set of samples, optionally capped by reservoir sampling so unbounded
+/// streams keep a uniform subset in bounded memory. Empty sets summarize as
+/// 0.0 throughout.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Samples {
+    values: Vec<f64>,
+    /// Every pushed sample, retained or not.
+    seen: usize,
+    /// `None` keeps everything.
+    cap: Option<usize>,
+    rng: u64,
+}
+
+impl Samples {
+    pub(crate) fn new() -> Self {
+        Samples::default()
+    }
+
+    /// Keeps at most `cap` samples, a uniform draw from all pushed so far.
+    pub(crate) fn reservoir(cap: usize, seed: u64) -> Self {
+        Samples {
+            cap: Some(cap),
+            rng: seed.max(1),
+            ..Samples::default()
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: f64) {
+        self.seen += 1;
+        match self.cap {
+            Some(cap) if self.values.len() >= cap => {
+                let slot = (next_f64(&mut self.rng) * self.seen as f64) as usize;
+                if slot < cap {
+                    self.values[slot] = value;
+                }
+            }
+            _ => self.values.push(value),
+        }
+    }
+
+    /// Combines per-worker sets. Over a cap, each side contributes in
+    /// proportion to how many samples it saw, so the result stays a uniform
+    /// draw from the combined stream.
+    pub(crate) fn merge(&mut self, other: Samples) {
+        let seen = self.seen + other.seen;
+        match self.cap {
+            Some(cap) if self.values.len() + other.values.len() > cap => {
+                let share = (cap as f64 * self.seen as f64 / seen as f64).round() as usize;
+                let from_other = (cap - share.min(cap)).min(other.values.len());
+                let from_self = (cap - from_other).min(self.values.len());
+                let mut other_values = other.values;
+                choose(&mut self.values, from_self, &mut self.rng);
+                choose(&mut other_values, from_other, &mut self.rng);
+                self.values.extend(other_values);
+            }
+            _ => self.values.extend(other.values),
+        }
+        self.seen = seen;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub(crate) fn mean(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().sum::<f64>() / self.len() as f64
+    }
+
+    /// Sample (n - 1) standard deviation; 0.0 below two samples.
+    pub(crate) fn stddev(&self) -> f64 {
+        if self.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let squares: f64 = self.values.iter().map(|value| (value - mean).powi(2)).sum();
+        (squares / (self.len() - 1) as f64).sqrt()
+    }
+
+    /// Standard error of the mean over the mean's magnitude; 0.0 below two
+    /// samples or when the mean is zero.
+    pub(crate) fn rse(&self) -> f64 {
+        let mean = self.mean().abs();
+        if self.len() < 2 || mean == 0.0 {
+            return 0.0;
+        }
+        self.stddev() / (self.len() as f64).sqrt() / mean
+    }
+
+    pub(crate) fn min(&self) -> f64 {
+        self.values.iter().copied().reduce(f64::min).unwrap_or(0.0)
+    }
+
+    pub(crate) fn max(&self) -> f64 {
+        self.values.iter().copied().reduce(f64::max).unwrap_or(0.0)
+    }
+
+    /// `pct` in 0..=100.
+    pub(crate) fn percentile(&self, pct: f64, rank: Rank) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.values.clone();
+        sorted.sort_by(f64::total_cmp);
+        let fraction = (pct / 100.0).clamp(0.0, 1.0);
+        match rank {
+            Rank::Nearest => {
+                let rank = (fraction * sorted.len() as f64).ceil() as usize;
+                sorted[rank.clamp(1, sorted.len()) - 1]
+            }
+            Rank::Linear => {
+                let position = fraction * (sorted.len() - 1) as f64;
+                let lower = position.floor() as usize;
+                let upper = position.ceil() as usize;
+                sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+            }
+        }
+    }
+}
+
+impl From<Vec<f64>> for Samples {
+    fn from(values: Vec<f64>) -> Self {
+        Samples {
+            seen: values.len(),
+            values,
+            ..Samples::default()
+        }
+    }
+}
+
+/// Durations as whole microseconds in a `u32`, so a long run keeps every
+/// one in a quarter of what `Samples` would need, and percentiles are exact.
+/// A duration past `u32::MAX` microseconds (71 minutes) is kept as that.
+/// Empty sets summarize as 0.0, like `Samples`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Micros(Vec<u32>);
+
+impl Micros {
+    pub(crate) fn push(&mut self, elapsed: Duration) {
+        self.0.push(u32::try_from(elapsed.as_micros()).unwrap_or(u32::MAX));
+    }
+
+    pub(crate) fn merge(&mut self, other: Micros) {
+        self.0.extend(other.0);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn max(&self) -> f64 {
+        self.0.iter().max().map_or(0.0, |&us| f64::from(us))
+    }
+
+    /// Nearest rank for each of `pcts`, each in 0..=100. Sorts the
+    /// durations in place, once for all of them.
+    pub(crate) fn percentiles(&mut self, pcts: &[f64]) -> Vec<f64> {
+        if self.is_empty() {
+            return vec![0.0; pcts.len()];
+        }
+        self.0.sort_unstable();
+        let sorted = &self.0;
+        pcts.iter()
+            .map(|pct| {
+                let rank = ((pct / 100.0).clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+                f64::from(sorted[rank.clamp(1, sorted.len()) - 1])
+            })
+            .collect()
+    }
+}
+
+/// What `insert_sampled` reports about a metric's samples.
+pub(crate) trait SampleSet {
+    fn count(&self) -> usize;
+    fn rse(&self) -> f64;
+}
+
+impl SampleSet for Samples {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn rse(&self) -> f64 {
+        Samples::rse(self)
+    }
+}
+
+impl SampleSet for Micros {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    /// As `Samples::rse`.
+    fn rse(&self) -> f64 {
+        let len = self.len() as f64;
+        let mean = self.0.iter().map(|&us| f64::from(us)).sum::<f64>() / len.max(1.0);
+        if self.len() < 2 || mean == 0.0 {
+            return 0.0;
+        }
+        let squares: f64 = self.0.iter().map(|&us| (f64::from(us) - mean).powi(2)).sum();
+        (squares / (len - 1.0)).sqrt() / len.sqrt() / mean
+    }
+}
+
+/// Which samples `Trim::apply` drops before aggregating.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Trim {
+    #[default]
+    None,
+    /// The lowest and the highest `pct`% each, rounded down to whole
+    /// samples; `pct` is below 50.
+    Percent(u32),
+    /// Samples outside Tukey's fences: more than 1.5 interquartile ranges
+    /// beyond the (linearly interpolated) quartiles.
+    Iqr,
+}
+
+impl Trim {
+    /// `none`, `iqr`, or `pct:<n>` with `n` in 0..50.
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "none" => Ok(Trim::None),
+            "iqr" => Ok(Trim::Iqr),
+            _ => {
+                let pct = value
+                    .strip_prefix("pct:")
+                    .and_then(|pct| pct.parse::<u32>().ok())
+                    .filter(|pct| *pct < 50)
+                    .ok_or_else(|| format!("expected none, iqr or pct:<0-49>, got {value:?}"))?;
+                Ok(Trim::Percent(pct))
+            }
+        }
+    }
+
+    /// The policy as `parse` reads it.
+    pub(crate) fn label(self) -> String {
+        match self {
+            Trim::None => "none".to_string(),
+            Trim::Percent(pct) => format!("pct:{pct}"),
+            Trim::Iqr => "iqr".to_string(),
+        }
+    }
+
+    /// The samples this policy keeps, and how many it dropped.
+    pub(crate) fn apply(self, samples: &Samples) -> (Samples, usize) {
+        let mut sorted = samples.values.clone();
+        sorted.sort_by(f64::total_cmp);
+        let kept: Vec<f64> = match self {
+            Trim::None => sorted,
+            Trim::Percent(pct) => {
+                let each_side = sorted.len() * pct as usize / 100;
+                sorted[each_side..sorted.len() - each_side].to_vec()
+            }
+            Trim::Iqr => {
+                let q1 = samples.percentile(25.0, Rank::Linear);
+                let q3 = samples.percentile(75.0, Rank::Linear);
+                let fence = 1.5 * (q3 - q1);
+                sorted.retain(|value| (q1 - fence..=q3 + fence).contains(value));
+                sorted
+            }
+        };
+        let trimmed = samples.len() - kept.len();
+        (Samples::from(kept), trimmed)
+    }
+}
+
+/// Inserts `key` with its `_n` companion from `samples`, and its `_rse`
+/// one when `SAMPLED_METRICS` lists it as a mean or a median.
+pub(crate) fn insert_sampled(
+    map: &mut Metrics,
+    key: &str,
+    value: f64,
+    unit: &str,
+    samples: &dyn SampleSet,
+) {
+    let statistic = sampled(key);
+    debug_assert!(statistic.is_some(), "{key} is not in SAMPLED_METRICS");
+    map.insert(key, value, unit);
+    map.insert(
+        format!("{key}{COUNT_SUFFIX}"),
+        samples.count() as f64,
+        "count",
+    );
+    if statistic.is_some_and(Statistic::has_rse) {
+        map.insert(format!("{key}{RSE_SUFFIX}"), samples.rse(), "ratio");
+    }
+}
+
+/// Keeps a uniform random `k` of `values` (partial Fisher-Yates).
+fn choose(values: &mut Vec<f64>, k: usize, rng: &mut u64) {
+    let len = values.len();
+    for idx in 0..k.min(len) {
+        let offset = (next_f64(rng) * (len - idx) as f64) as usize;
+        values.swap(idx, (idx + offset).min(len - 1));
+    }
+    values.truncate(k);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(samples: &Samples) -> Vec<f64> {
+        let mut values = samples.values.clone();
+        values.sort_by(f64::total_cmp);
+        values
+    }
+
+    #[test]
+    fn empty_and_single_sample() {
+        let empty = Samples::new();
+        assert!(empty.is_empty());
+        for value in [
+            empty.mean(),
+            empty.stddev(),
+            empty.min(),
+            empty.max(),
+            empty.percentile(95.0, Rank::Nearest),
+            empty.percentile(95.0, Rank::Linear),
+        ] {
+            assert_eq!(value, 0.0);
+        }
+
+        let single = Samples::from(vec![7.0]);
+        assert_eq!(single.mean(), 7.0);
+        assert_eq!(single.stddev(), 0.0);
+        assert_eq!((single.min(), single.max()), (7.0, 7.0));
+        assert_eq!(single.percentile(99.0, Rank::Nearest), 7.0);
+        assert_eq!(single.percentile(99.0, Rank::Linear), 7.0);
+    }
+
+    #[test]
+    fn moments_and_extremes() {
+        let samples = Samples::from(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(samples.mean(), 5.0);
+        assert!((samples.stddev() - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        assert_eq!((samples.min(), samples.max()), (2.0, 9.0));
+        assert_eq!(samples.seen, 8);
+    }
+
+    #[test]
+    fn relative_standard_error_and_companions() {
+        let samples = Samples::from(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        let expected = (32.0f64 / 7.0).sqrt() / 8.0f64.sqrt() / 5.0;
+        assert!((samples.rse() - expected).abs() < 1e-12);
+        assert_eq!(Samples::from(vec![3.0]).rse(), 0.0);
+        assert_eq!(Samples::from(vec![-1.0, 1.0]).rse(), 0.0);
+
+        let mut map = Metrics::new();
+        insert_sampled(&mut map, "latency_p50_ms", samples.mean(), "ms", &samples);
+        let p99 = samples.percentile(99.0, Rank::Nearest);
+        insert_sampled(&mut map, "latency_p99_ms", p99, "ms", &samples);
+        let keys: Vec<&str> = map.iter().map(|(key, _)| key.as_str()).collect();
+        let expected = [
+            "latency_p50_ms",
+            "latency_p50_ms_n",
+            "latency_p50_ms_rse",
+            "latency_p99_ms",
+            "latency_p99_ms_n",
+        ];
+        assert_eq!(keys, expected);
+        assert_eq!(map["latency_p99_ms"].value, 9.0);
+        assert_eq!(map["latency_p99_ms_n"].value, 8.0);
+        assert_eq!(map["latency_p50_ms_rse"].unit, "ratio");
+    }
+
+    #[test]
+    fn sampled_metrics_are_looked_up_by_key_pattern() {
+        assert_eq!(sampled("sleep_10ms_median_overshoot_us"), Some(Statistic::Median));
+        assert_eq!(sampled("sleep_1ms_p95_overshoot_us"), Some(Statistic::Percentile));
+        assert_eq!(sampled("sleep_ms_p95_overshoot_us"), None);
+        assert_eq!(sampled("sleep_1xms_p95_overshoot_us"), None);
+        assert_eq!(sampled("runtime_seconds"), Some(Statistic::Mean));
+        assert_eq!(sampled("latency_max_ms"), None);
+        for (_, keys) in SAMPLED_METRICS {
+            for (key, statistic) in keys {
+                let has_rse = key.contains("mean") || key.contains("median") || key.contains("p50");
+                assert_eq!(statistic.has_rse(), has_rse, "{key}");
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let samples = Samples::from((1..=20).rev().map(f64::from).collect::<Vec<_>>());
+        assert_eq!(samples.percentile(50.0, Rank::Nearest), 10.0);
+        assert_eq!(samples.percentile(95.0, Rank::Nearest), 19.0);
+        assert_eq!(samples.percentile(100.0, Rank::Nearest), 20.0);
+        assert_eq!(samples.percentile(0.0, Rank::Nearest), 1.0);
+    }
+
+    #[test]
+    fn linear_percentiles_interpolate() {
+        let samples = Samples::from(vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(samples.percentile(50.0, Rank::Linear), 2.5);
+        assert_eq!(samples.percentile(0.0, Rank::Linear), 1.0);
+        assert_eq!(samples.percentile(100.0, Rank::Linear), 4.0);
+        assert!((samples.percentile(90.0, Rank::Linear) - 3.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn percent_trim_drops_each_tail() {
+        let samples = Samples::from(vec![9.0, 1.0, 5.0, 3.0, 7.0, 2.0, 8.0, 4.0, 6.0, 100.0]);
+        let (kept, trimmed) = Trim::Percent(10).apply(&samples);
+        assert_eq!(trimmed, 2);
+        assert_eq!(sorted(&kept), [2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_eq!(kept.mean(), 5.5);
+        // 15% of 10 rounds down to one sample each side.
+        assert_eq!(Trim::Percent(15).apply(&samples).1, 2);
+        assert_eq!(Trim::Percent(49).apply(&samples).0.len(), 2);
+        // Too few samples to drop any.
+        assert_eq!(Trim::Percent(10).apply(&Samples::from(vec![1.0, 50.0])).1, 0);
+        let (all, none) = Trim::None.apply(&samples);
+        assert_eq!((all.len(), none), (10, 0));
+        assert_eq!(Trim::Percent(10).apply(&Samples::new()).1, 0);
+    }
+
+    #[test]
+    fn iqr_trim_drops_beyond_the_fences() {
+        // Quartiles 2.75 and 5.875: fences at -1.9375 and 10.5625.
+        let samples = Samples::from(vec![1.0, 2.0, 3.0, 3.0, 4.0, 5.0, 8.5, 40.0]);
+        let (kept, trimmed) = Trim::Iqr.apply(&samples);
+        assert_eq!(trimmed, 1);
+        assert_eq!(kept.max(), 8.5);
+        let (kept, trimmed) = Trim::Iqr.apply(&Samples::from(vec![-30.0, 1.0, 1.0, 1.0, 1.0]));
+        assert_eq!((kept.min(), trimmed), (1.0, 1));
+        assert_eq!(Trim::Iqr.apply(&Samples::from(vec![4.0, 4.0, 4.0])).1, 0);
+    }
+
+    #[test]
+    fn trim_policies_parse_and_label() {
+        for label in ["none", "iqr", "pct:0", "pct:10", "pct:49"] {
+            assert_eq!(Trim::parse(label).unwrap().label(), label);
+        }
+        assert_eq!(Trim::parse("pct:10"), Ok(Trim::Percent(10)));
+        for bad in ["pct:50", "pct:", "pct:-1", "10", "IQR", ""] {
+            assert!(Trim::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn merge_is_associative() {
+        let part = |range: std::ops::Range<u32>| Samples::from(range.map(f64::from).collect::<Vec<_>>());
+        let mut left = part(0..10);
+        left.merge(part(10..25));
+        left.merge(part(25..30));
+        let mut tail = part(10..25);
+        tail.merge(part(25..30));
+        let mut right = part(0..10);
+        right.merge(tail);
+        assert_eq!(sorted(&left), sorted(&right));
+        assert_eq!(left.seen, 30);
+        assert_eq!(left.mean(), right.mean());
+        assert_eq!(left.percentile(90.0, Rank::Nearest), 26.0);
+
+        let mut empty = Samples::new();
+        empty.merge(Samples::new());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn reservoir_keeps_a_uniform_subset() {
+        let mut reservoir = Samples::reservoir(1_000, 42);
+        for value in 0..100_000 {
+            reservoir.push(f64::from(value));
+        }
+        assert_eq!(reservoir.len(), 1_000);
+        assert_eq!(reservoir.seen, 100_000);
+        // A uniform draw of 1000 from 0..100000 has a mean stddev of ~913.
+        assert!((reservoir.mean() - 49_999.5).abs() < 4_000.0, "{}", reservoir.mean());
+        assert!((reservoir.percentile(90.0, Rank::Linear) - 90_000.0).abs() < 3_000.0);
+        assert!(reservoir.max() > 99_000.0 && reservoir.min() < 1_000.0);
+
+        let mut small = Samples::reservoir(10, 42);
+        (0..5).for_each(|value| small.push(f64::from(value)));
+        assert_eq!(sorted(&small), [0.0, 1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn reservoir_merge_weights_by_samples_seen() {
+        let mut zeros = Samples::reservoir(1_000, 1);
+        (0..30_000).for_each(|_| zeros.push(0.0));
+        let mut ones = Samples::reservoir(1_000, 2);
+        (0..10_000).for_each(|_| ones.push(1.0));
+        zeros.merge(ones);
+        assert_eq!(zeros.len(), 1_000);
+        assert_eq!(zeros.seen, 40_000);
+        assert_eq!(zeros.mean(), 0.25);
+    }
+
+    #[test]
+    fn micros_keep_every_duration_in_whole_microseconds() {
+        let mut first = Micros::default();
+        first.push(Duration::from_nanos(1_999));
+        first.push(Duration::from_secs(5_000));
+        let mut second = Micros::default();
+        (1..=98).for_each(|us| second.push(Duration::from_micros(us)));
+        first.merge(second);
+        assert_eq!(first.len(), 100);
+        assert_eq!(first.percentiles(&[0.0, 50.0, 99.0]), [1.0, 49.0, 98.0]);
+        assert_eq!(first.max(), f64::from(u32::MAX));
+
+        let values: Vec<f64> = (1..=10).map(f64::from).collect();
+        let mut micros = Micros::default();
+        values.iter().for_each(|&us| micros.push(Duration::from_micros(us as u64)));
+        let rse = SampleSet::rse(&Samples::from(values));
+        assert!((SampleSet::rse(&micros) - rse).abs() < 1e-12);
+        assert_eq!(Micros::default().percentiles(&[50.0, 99.0]), [0.0, 0.0]);
+        assert_eq!(Micros::default().max(), 0.0);
+    }
+}
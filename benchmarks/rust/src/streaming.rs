@@ -0,0 +1,185 @@
+//! `io_http_streaming`: request and response bodies too large to buffer
+//! whole, against the fixture's streaming endpoints.
+//!
+//! - `POST /upload` takes a chunked body and answers with what it got:
+//!   `{"bytes": <count>, "fnv1a": "<16 hex digits>"}`.
+//! - `GET /stream/{bytes}` answers with `bytes` bytes of payload in a chunked
+//!   body, followed by an `X-Content-Fnv1a` trailer.
+//!
+//! The payload is the byte sequence `0, 1, ..., 250, 0, 1, ...`, so both
+//! ends can produce or check any stretch of it without sharing a file; 251
+//! is prime, so no power-of-two chunk size lines up with it. Hashes are
+//! 64-bit FNV-1a, which Python computes in a few lines. A transfer whose
+//! count or hash disagrees is a mismatch, not an error: the bytes moved,
+//! they just were not the right ones.
+
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+use crate::checksum_store::Fnv1a;
+use crate::http_client::{HttpConnection, ParsedUrl, StatusBucket};
+
+/// Length of the repeating payload pattern.
+const PAYLOAD_PERIOD: u64 = 251;
+
+/// Trailer carrying the hash of a `/stream` body.
+pub(crate) const HASH_TRAILER: &str = "X-Content-Fnv1a";
+
+/// Fills `buf` with the payload starting `offset` bytes in.
+pub(crate) fn fill_payload(offset: u64, buf: &mut [u8]) {
+    let mut value = offset % PAYLOAD_PERIOD;
+    for byte in buf {
+        *byte = value as u8;
+        value = if value + 1 == PAYLOAD_PERIOD { 0 } else { value + 1 };
+    }
+}
+
+/// FNV-1a of the first `len` payload bytes.
+pub(crate) fn payload_hash(len: u64) -> u64 {
+    let mut hasher = Fnv1a::default();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut offset = 0;
+    while offset < len {
+        let take = (len - offset).min(buf.len() as u64) as usize;
+        fill_payload(offset, &mut buf[..take]);
+        hasher.update(&buf[..take]);
+        offset += take as u64;
+    }
+    hasher.finish()
+}
+
+pub(crate) fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// One direction of the benchmark.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Transfer {
+    /// Payload bytes sent, or received.
+    pub(crate) bytes: u64,
+    /// From the request's first byte to the response's last.
+    pub(crate) seconds: f64,
+    /// The other end saw a different byte count or hash.
+    pub(crate) mismatch: bool,
+}
+
+impl Transfer {
+    pub(crate) fn mb_per_second(&self) -> f64 {
+        self.bytes as f64 / (1024.0 * 1024.0) / self.seconds.max(1e-9)
+    }
+}
+
+fn check_status(status: StatusBucket) -> Result<(), String> {
+    match status {
+        StatusBucket::Code(200..=299) => Ok(()),
+        StatusBucket::Code(code) => Err(format!("answered {code}")),
+        StatusBucket::Malformed => Err("malformed status line".to_string()),
+    }
+}
+
+/// Uploads `bytes` of payload to `/upload` in chunks of `chunk_bytes`.
+pub(crate) fn upload(
+    target: &ParsedUrl,
+    bytes: u64,
+    chunk_bytes: usize,
+    timeout: Duration,
+) -> Result<Transfer, String> {
+    let mut connection = HttpConnection::connect(target, timeout)?;
+    let chunk_bytes = chunk_bytes.max(1) as u64;
+    let mut offset = 0;
+    let next_chunk = |data: &mut Vec<u8>| {
+        let take = (bytes - offset).min(chunk_bytes) as usize;
+        data.resize(take, 0);
+        fill_payload(offset, data);
+        offset += take as u64;
+    };
+    let mut reply = Vec::new();
+    let start = Instant::now();
+    let path = target.request_path("/upload");
+    let streamed = connection.post_chunked(&target.host_header(), &path, next_chunk, |data| {
+        reply.extend_from_slice(data)
+    })?;
+    let seconds = start.elapsed().as_secs_f64();
+    check_status(streamed.status).map_err(|err| format!("{path} {err}"))?;
+    let reply: Value = serde_json::from_slice(&reply).map_err(|err| format!("{path}: {err}"))?;
+    let received = reply["bytes"].as_u64();
+    let hash = reply["fnv1a"].as_str();
+    Ok(Transfer {
+        bytes,
+        seconds,
+        mismatch: received != Some(bytes) || hash != Some(&format_hash(payload_hash(bytes))),
+    })
+}
+
+/// Downloads `bytes` of payload from `/stream/{bytes}`, checking it
+/// against the expected payload and the trailer's hash as it arrives.
+pub(crate) fn download(
+    target: &ParsedUrl,
+    bytes: u64,
+    timeout: Duration,
+) -> Result<Transfer, String> {
+    let mut connection = HttpConnection::connect(target, timeout)?;
+    let mut hasher = Fnv1a::default();
+    let mut received = 0u64;
+    let mut expected = Vec::new();
+    let mut wrong_bytes = false;
+    let start = Instant::now();
+    let path = target.request_path(&format!("/stream/{bytes}"));
+    let streamed = connection.get_streamed(&target.host_header(), &path, |data| {
+        expected.resize(data.len(), 0);
+        fill_payload(received, &mut expected);
+        wrong_bytes |= data != expected.as_slice();
+        hasher.update(data);
+        received += data.len() as u64;
+    })?;
+    let seconds = start.elapsed().as_secs_f64();
+    check_status(streamed.status).map_err(|err| format!("{path} {err}"))?;
+    let hash = format_hash(hasher.finish());
+    let trailer_agrees = streamed.end.trailer(HASH_TRAILER).is_none_or(|sent| sent == hash);
+    Ok(Transfer {
+        bytes: received,
+        seconds,
+        mismatch: wrong_bytes || received != bytes || !trailer_agrees,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum_store::fnv1a;
+    use crate::http_client::{parse_base_url, DEFAULT_TIMEOUT};
+    use crate::mock_server::{ChaosConfig, MockServer};
+    use crate::DEFAULT_SEED;
+
+    #[test]
+    fn payload_is_the_same_however_it_is_split() {
+        let mut whole = vec![0u8; 1000];
+        fill_payload(0, &mut whole);
+        assert_eq!(&whole[..3], [0, 1, 2]);
+        assert_eq!(&whole[250..253], [250, 0, 1]);
+        let mut piece = vec![0u8; 300];
+        fill_payload(400, &mut piece);
+        assert_eq!(piece, whole[400..700]);
+        assert_eq!(payload_hash(1000), fnv1a(&whole));
+        assert_eq!(payload_hash(0), fnv1a(&[]));
+        assert_eq!(format_hash(0xab), "00000000000000ab");
+    }
+
+    #[test]
+    fn round_trips_against_the_mock_server() {
+        let server = MockServer::start(ChaosConfig::default(), DEFAULT_SEED).unwrap();
+        let target = parse_base_url(&server.base_url()).unwrap();
+        // Not a multiple of the chunk size, so the last chunk is short.
+        let bytes = 300_000;
+        let up = upload(&target, bytes, 64 * 1024, DEFAULT_TIMEOUT).unwrap();
+        assert_eq!((up.bytes, up.mismatch), (bytes, false));
+        let down = download(&target, bytes, DEFAULT_TIMEOUT).unwrap();
+        assert_eq!((down.bytes, down.mismatch), (bytes, false));
+        assert!(down.mb_per_second() > 0.0);
+
+        let empty = download(&target, 0, DEFAULT_TIMEOUT).unwrap();
+        assert_eq!((empty.bytes, empty.mismatch), (0, false));
+        let empty = upload(&target, 0, 1024, DEFAULT_TIMEOUT).unwrap();
+        assert!(!empty.mismatch);
+    }
+}
@@ -0,0 +1,413 @@
+use serde::Serialize;
+use std::env;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::child_usage::{self, ChildUsage};
+use crate::env_usize;
+use crate::line_reader::{self, DEFAULT_MAX_LINE_BYTES};
+
+const DEFAULT_OUTPUT_CAP_MB: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CaptureStats {
+    pub(crate) bytes_captured: u64,
+    pub(crate) bytes_discarded: u64,
+    /// Lines over the per-line limit, skipped without being buffered.
+    pub(crate) oversized_lines: u64,
+}
+
+impl CaptureStats {
+    pub(crate) fn truncated(&self) -> bool {
+        self.bytes_discarded > 0
+    }
+
+    pub(crate) fn add(&mut self, other: CaptureStats) {
+        self.bytes_captured += other.bytes_captured;
+        self.bytes_discarded += other.bytes_discarded;
+        self.oversized_lines += other.oversized_lines;
+    }
+}
+
+pub(crate) struct CommandOutput {
+    pub(crate) code: i32,
+    pub(crate) stdout: String,
+    pub(crate) capture: CaptureStats,
+    /// Spawn to exit, minus the time a streaming consumer spent on its own
+    /// work between lines; zero when the spawn failed.
+    pub(crate) tool_time: Duration,
+    /// The child's rusage; `None` off Linux or when the spawn failed.
+    pub(crate) usage: Option<ChildUsage>,
+}
+
+/// Per-stream capture limit, from `BENCHMARK_OUTPUT_CAP_MB`.
+pub(crate) fn output_cap_bytes() -> u64 {
+    env_usize("BENCHMARK_OUTPUT_CAP_MB", DEFAULT_OUTPUT_CAP_MB) as u64 * 1024 * 1024
+}
+
+/// Per-line limit on child output, from `BENCHMARK_MAX_LINE_MB`.
+pub(crate) fn max_line_bytes() -> usize {
+    let default_mb = DEFAULT_MAX_LINE_BYTES / (1024 * 1024);
+    env_usize("BENCHMARK_MAX_LINE_MB", default_mb) * 1024 * 1024
+}
+
+/// `BENCHMARK_STRICT_LINE_LENGTH=1`: an oversized line ends the capture
+/// (and so truncates the output) instead of being skipped.
+pub(crate) fn strict_line_length() -> bool {
+    env::var("BENCHMARK_STRICT_LINE_LENGTH").is_ok_and(|value| value == "1")
+}
+
+/// Yields lines (without the trailing newline) until `cap` bytes have been
+/// read; everything past the cap is drained and counted as discarded so the
+/// child never blocks on a full pipe. Lines longer than `max_line` are
+/// skipped and counted, or in strict mode drained like the overflow.
+pub(crate) struct CappedLines<R> {
+    reader: R,
+    cap: u64,
+    max_line: usize,
+    strict: bool,
+    stats: CaptureStats,
+    buf: Vec<u8>,
+    done: bool,
+    /// When the last line was handed out; the gap until the next call is
+    /// the consumer's time, not the child's.
+    yielded_at: Option<Instant>,
+    consumer_time: Duration,
+}
+
+impl<R: BufRead> CappedLines<R> {
+    pub(crate) fn new(reader: R, cap: u64) -> Self {
+        CappedLines {
+            reader,
+            cap,
+            max_line: DEFAULT_MAX_LINE_BYTES,
+            strict: false,
+            stats: CaptureStats::default(),
+            buf: Vec::new(),
+            done: false,
+            yielded_at: None,
+            consumer_time: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn with_line_limit(mut self, max_line: usize, strict: bool) -> Self {
+        self.max_line = max_line;
+        self.strict = strict;
+        self
+    }
+
+    pub(crate) fn stats(&self) -> CaptureStats {
+        self.stats
+    }
+
+    fn drain(&mut self, partial: u64) {
+        let rest = io::copy(&mut self.reader, &mut io::sink()).unwrap_or(0);
+        self.stats.bytes_discarded += partial + rest;
+        self.done = true;
+    }
+}
+
+impl<R: BufRead> Iterator for CappedLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if let Some(yielded_at) = self.yielded_at.take() {
+            self.consumer_time += yielded_at.elapsed();
+        }
+        if self.done {
+            return None;
+        }
+        loop {
+            let remaining = self.cap.saturating_sub(self.stats.bytes_captured);
+            let limit = remaining.min(self.max_line as u64 + 1);
+            self.buf.clear();
+            let read = match (&mut self.reader)
+                .take(limit)
+                .read_until(b'\n', &mut self.buf)
+            {
+                Ok(read) => read as u64,
+                Err(_) => {
+                    self.done = true;
+                    return None;
+                }
+            };
+            if read == 0 {
+                self.drain(0);
+                return None;
+            }
+            if self.buf.ends_with(b"\n") || read < limit {
+                break;
+            }
+            let at_eof = self
+                .reader
+                .fill_buf()
+                .map(|rest| rest.is_empty())
+                .unwrap_or(true);
+            if limit == remaining {
+                if at_eof {
+                    break;
+                }
+                self.drain(read);
+                return None;
+            }
+            // Over the per-line limit, with room left under the total cap.
+            self.stats.oversized_lines += 1;
+            if self.strict {
+                self.drain(read);
+                return None;
+            }
+            let skipped = line_reader::skip_line(&mut self.reader).unwrap_or(0);
+            self.stats.bytes_discarded += read + skipped;
+        }
+        let read = self.buf.len() as u64;
+        self.stats.bytes_captured += read;
+        let line = String::from_utf8_lossy(&self.buf);
+        let line = line.trim_end_matches(['\n', '\r']).to_string();
+        self.yielded_at = Some(Instant::now());
+        Some(line)
+    }
+}
+
+/// The runner's own locale variables, recorded in the environment block;
+/// children never see them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Locale {
+    pub(crate) lc_all: Option<String>,
+    pub(crate) lc_numeric: Option<String>,
+    pub(crate) lang: Option<String>,
+}
+
+pub(crate) fn parent_locale() -> Locale {
+    Locale {
+        lc_all: env::var("LC_ALL").ok(),
+        lc_numeric: env::var("LC_NUMERIC").ok(),
+        lang: env::var("LANG").ok(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NumberError {
+    /// Digits with separators (`1,5`, `1.234`, `1 234`): a tool that
+    /// ignored the C locale.
+    LocaleSuspect,
+    Invalid,
+}
+
+/// A count as tools print it under the C locale: plain ASCII digits.
+pub(crate) fn parse_count(raw: &str) -> Result<u64, NumberError> {
+    let raw = raw.trim();
+    if let Ok(count) = raw.parse::<u64>() {
+        return Ok(count);
+    }
+    let separators = [',', '.', '\'', ' ', '\u{a0}', '\u{202f}'];
+    let localized = raw.chars().any(|c| c.is_ascii_digit())
+        && raw.chars().all(|c| c.is_ascii_digit() || separators.contains(&c));
+    Err(if localized {
+        NumberError::LocaleSuspect
+    } else {
+        NumberError::Invalid
+    })
+}
+
+/// Runs `cmd`, handing its stdout to `consume` as capped lines while stderr
+/// is drained (and counted against its own cap) on a helper thread. The
+/// child runs in the C locale so the numbers it prints parse the same on
+/// every runner.
+pub(crate) fn stream_command<T: Default>(
+    mut cmd: Command,
+    consume: impl FnOnce(&mut CappedLines<BufReader<ChildStdout>>) -> T,
+) -> (CommandOutput, T) {
+    let cap = output_cap_bytes();
+    let (max_line, strict) = (max_line_bytes(), strict_line_length());
+    let start = Instant::now();
+    let spawned = cmd
+        .env("LC_ALL", "C")
+        .env("LANG", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(_) => {
+            let output = CommandOutput {
+                code: 1,
+                stdout: String::new(),
+                capture: CaptureStats::default(),
+                tool_time: Duration::ZERO,
+                usage: None,
+            };
+            return (output, T::default());
+        }
+    };
+
+    let stderr_handle = child.stderr.take().map(|stderr| {
+        thread::spawn(move || {
+            let mut lines = CappedLines::new(BufReader::new(stderr), cap)
+                .with_line_limit(max_line, strict);
+            lines.by_ref().for_each(drop);
+            lines.stats()
+        })
+    });
+
+    let mut capture = CaptureStats::default();
+    let mut consumer_time = Duration::ZERO;
+    let consumed = match child.stdout.take() {
+        Some(stdout) => {
+            let mut lines = CappedLines::new(BufReader::new(stdout), cap)
+                .with_line_limit(max_line, strict);
+            let consumed = consume(&mut lines);
+            // Drain anything the consumer left unread; the first call also
+            // closes the consumer's last gap.
+            lines.by_ref().for_each(drop);
+            capture.add(lines.stats());
+            consumer_time = lines.consumer_time;
+            consumed
+        }
+        None => T::default(),
+    };
+
+    if let Some(Ok(stats)) = stderr_handle.map(thread::JoinHandle::join) {
+        capture.add(stats);
+    }
+    let (code, usage) = match child_usage::wait(&mut child) {
+        Some((code, usage)) => (code, Some(usage)),
+        None => {
+            let code = child
+                .wait()
+                .ok()
+                .and_then(|status| status.code())
+                .unwrap_or(1);
+            (code, None)
+        }
+    };
+
+    let output = CommandOutput {
+        code,
+        stdout: String::new(),
+        capture,
+        tool_time: start.elapsed().saturating_sub(consumer_time),
+        usage,
+    };
+    (output, consumed)
+}
+
+pub(crate) fn command_output(cmd: Command) -> CommandOutput {
+    let (mut output, stdout) = stream_command(cmd, |lines| {
+        let mut text = String::new();
+        for line in lines {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    });
+    output.stdout = stdout;
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(input: &str, cap: u64) -> (Vec<String>, CaptureStats) {
+        let mut lines = CappedLines::new(input.as_bytes(), cap);
+        let collected = lines.by_ref().collect();
+        (collected, lines.stats())
+    }
+
+    #[test]
+    fn keeps_everything_under_cap() {
+        let (lines, stats) = collect("a\nbb\nccc", 64);
+        assert_eq!(lines, vec!["a", "bb", "ccc"]);
+        assert_eq!(stats.bytes_captured, 8);
+        assert!(!stats.truncated());
+    }
+
+    #[test]
+    fn drops_line_crossing_cap_and_counts_rest() {
+        let (lines, stats) = collect("aaaa\nbbbb\ncccc\n", 7);
+        assert_eq!(lines, vec!["aaaa"]);
+        assert_eq!(stats.bytes_captured, 5);
+        assert_eq!(stats.bytes_discarded, 10);
+        assert!(stats.truncated());
+    }
+
+    #[test]
+    fn exact_cap_is_not_truncated() {
+        let (lines, stats) = collect("aaaa\nbbbb", 9);
+        assert_eq!(lines, vec!["aaaa", "bbbb"]);
+        assert_eq!(stats.bytes_discarded, 0);
+    }
+
+    #[test]
+    fn skips_lines_over_the_line_limit() {
+        let input = format!("a\n{}\nbb\n", "x".repeat(100));
+        let mut lines = CappedLines::new(input.as_bytes(), 64).with_line_limit(4, false);
+        assert_eq!(lines.by_ref().collect::<Vec<_>>(), vec!["a", "bb"]);
+        let stats = lines.stats();
+        assert_eq!((stats.oversized_lines, stats.bytes_captured), (1, 5));
+        assert_eq!(stats.bytes_discarded, 101);
+
+        let mut strict = CappedLines::new(input.as_bytes(), 64).with_line_limit(4, true);
+        assert_eq!(strict.by_ref().collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(strict.stats().oversized_lines, 1);
+        assert_eq!(strict.stats().bytes_discarded, 104);
+    }
+
+    #[test]
+    fn zero_cap_discards_all() {
+        let (lines, stats) = collect("x\ny\n", 0);
+        assert!(lines.is_empty());
+        assert_eq!(stats.bytes_discarded, 4);
+    }
+
+    #[test]
+    fn counts_reject_localized_numbers() {
+        assert_eq!(parse_count(" 12\n"), Ok(12));
+        for raw in ["1,5", "1.234", "1 234", "1'234", "1\u{a0}234"] {
+            assert_eq!(parse_count(raw), Err(NumberError::LocaleSuspect), "{raw}");
+        }
+        for raw in ["", "twelve", "-3", ",."] {
+            assert_eq!(parse_count(raw), Err(NumberError::Invalid), "{raw}");
+        }
+    }
+
+    #[test]
+    fn consumer_gaps_are_measured_between_lines() {
+        let mut lines = CappedLines::new("a\nb\n".as_bytes(), 64);
+        for _ in lines.by_ref() {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(lines.consumer_time >= Duration::from_millis(40));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_time_excludes_the_consumer() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo a; echo b; echo c"]);
+        let start = Instant::now();
+        let (output, count) = stream_command(cmd, |lines| {
+            lines
+                .map(|_| thread::sleep(Duration::from_millis(50)))
+                .count()
+        });
+        assert_eq!((output.code, count), (0, 3));
+        assert!(output.tool_time + Duration::from_millis(150) <= start.elapsed());
+        assert!(output.tool_time > Duration::ZERO);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn children_run_in_the_c_locale() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo \"$LC_ALL $LANG\"; printf '%.1f\\n' 1.5"])
+            .env("LC_ALL", "de_DE.UTF-8")
+            .env("LANG", "de_DE.UTF-8");
+        let output = command_output(cmd);
+        assert_eq!(output.code, 0);
+        assert_eq!(output.stdout, "C C\n1.5\n");
+    }
+}
@@ -0,0 +1,198 @@
+//! Where the test suite spends its time, for the reliability scorecard. One
+//! slow integration test can dominate the total, so every `cargo test`
+//! iteration is parsed for per-binary and, where the toolchain reports it,
+//! per-test wall time.
+//!
+//! On stable, libtest prints one `test result: ... finished in X.XXs` line
+//! per binary (doc tests included) and nothing per test. On nightly the
+//! runner asks for `--format json --report-time`, whose `suite` and `test`
+//! events carry `exec_time` for both. Cargo names the binaries on stderr,
+//! which is not captured, so they are only counted.
+//!
+//! Across iterations every time is the median, which one cold or contended
+//! iteration does not move: `slowest_binary_seconds` is the median of each
+//! iteration's slowest binary, and a test's time is its median over the
+//! iterations that ran it. The slowest tests go into the record's
+//! `diagnostics` as `test_timing`, which names the median rule with them.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::record::Metrics;
+use crate::stats::{Rank, Samples};
+
+/// How many of the slowest tests the record names.
+pub(crate) const SLOWEST_TESTS: usize = 5;
+
+/// How `median` treats an even count: the two middle times interpolated,
+/// as `Rank::Linear` does.
+const MEDIAN_RULE: &str = "linear";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TestTimings {
+    /// Seconds per test binary, in the order cargo ran them.
+    pub(crate) binaries: Vec<f64>,
+    /// Seconds per test by name; empty unless the output was JSON.
+    pub(crate) tests: BTreeMap<String, f64>,
+}
+
+/// Reads either format from one `cargo test` stdout; lines of neither,
+/// test output included, are skipped.
+pub(crate) fn parse_test_output(stdout: &str) -> TestTimings {
+    let mut timings = TestTimings::default();
+    for line in stdout.lines() {
+        let line = line.trim();
+        if line.starts_with('{') {
+            if let Ok(event) = serde_json::from_str::<Value>(line) {
+                add_json_event(&mut timings, &event);
+            }
+        } else if let Some(seconds) = stable_suite_seconds(line) {
+            timings.binaries.push(seconds);
+        }
+    }
+    timings
+}
+
+/// `test result: ok. 4 passed; ...; finished in 3.13s`
+fn stable_suite_seconds(line: &str) -> Option<f64> {
+    let rest = line.strip_prefix("test result: ")?;
+    let (_, finished) = rest.rsplit_once("finished in ")?;
+    finished.strip_suffix('s')?.parse().ok()
+}
+
+/// Only finished events carry `exec_time`; `started` ones are skipped.
+fn add_json_event(timings: &mut TestTimings, event: &Value) {
+    let Some(seconds) = event["exec_time"].as_f64() else {
+        return;
+    };
+    match event["type"].as_str() {
+        Some("suite") => timings.binaries.push(seconds),
+        Some("test") => {
+            if let Some(name) = event["name"].as_str() {
+                timings.tests.insert(name.to_string(), seconds);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TimingSummary {
+    slowest_binary: Samples,
+    binaries: usize,
+    tests: BTreeMap<String, Samples>,
+}
+
+impl TimingSummary {
+    /// An iteration that ran no binary (the build failed) only counts
+    /// towards the tests it timed, which is none.
+    pub(crate) fn add(&mut self, timings: TestTimings) {
+        if let Some(slowest) = timings.binaries.iter().copied().reduce(f64::max) {
+            self.slowest_binary.push(slowest);
+        }
+        self.binaries = self.binaries.max(timings.binaries.len());
+        for (name, seconds) in timings.tests {
+            self.tests.entry(name).or_default().push(seconds);
+        }
+    }
+
+    /// The `SLOWEST_TESTS` slowest by median, slowest first; ties by name.
+    pub(crate) fn slowest_tests(&self) -> Vec<(&str, f64)> {
+        let mut tests: Vec<(&str, f64)> = self
+            .tests
+            .iter()
+            .map(|(name, samples)| (name.as_str(), median(samples)))
+            .collect();
+        tests.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        tests.truncate(SLOWEST_TESTS);
+        tests
+    }
+
+    /// `slowest_binary_seconds` is `-1` when no iteration ran a binary.
+    pub(crate) fn insert_metrics(&self, map: &mut Metrics) {
+        let slowest = if self.slowest_binary.is_empty() {
+            -1.0
+        } else {
+            median(&self.slowest_binary)
+        };
+        map.insert("slowest_binary_seconds", slowest, "s");
+        map.insert("total_test_binaries", self.binaries as f64, "count");
+        map.insert("timed_tests", self.tests.len() as f64, "count");
+    }
+
+    /// `test_timing` for the record's `diagnostics`: the slowest tests,
+    /// slowest first, and the median rule their times went through.
+    pub(crate) fn diagnostics(&self) -> Value {
+        let slowest: Vec<Value> = self
+            .slowest_tests()
+            .into_iter()
+            .map(|(name, seconds)| json!({"name": name, "seconds": seconds}))
+            .collect();
+        json!({"median": MEDIAN_RULE, "slowest_tests": slowest})
+    }
+}
+
+fn median(samples: &Samples) -> f64 {
+    samples.percentile(50.0, Rank::Linear)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn both_output_formats_parse_from_fixtures() {
+        let stable = parse_test_output(fixtures::cargo_test_stable());
+        assert_eq!(stable.binaries, [3.13, 12.48, 0.0]);
+        assert!(stable.tests.is_empty());
+
+        let json = parse_test_output(fixtures::cargo_test_json());
+        assert_eq!(json.binaries, [2.915546, 11.624781]);
+        assert_eq!(json.tests.len(), 5);
+        assert_eq!(json.tests["end_to_end_compare_exits_cleanly"], 0.731002);
+        assert_eq!(json.tests["tests::monte_carlo_cpu_time_tracks_wall_time"], 2.908127);
+    }
+
+    #[test]
+    fn iterations_aggregate_by_median() {
+        let mut summary = TimingSummary::default();
+        for scale in [1.0, 3.0, 2.0] {
+            let mut timings = parse_test_output(fixtures::cargo_test_json());
+            timings.binaries.iter_mut().for_each(|seconds| *seconds *= scale);
+            timings.tests.values_mut().for_each(|seconds| *seconds *= scale);
+            summary.add(timings);
+        }
+        // A failed build runs nothing and leaves the medians alone.
+        summary.add(TestTimings::default());
+
+        let mut map = Metrics::new();
+        summary.insert_metrics(&mut map);
+        assert_eq!(map["slowest_binary_seconds"].value, 11.624781 * 2.0);
+        assert_eq!(map["total_test_binaries"].value, 2.0);
+        assert_eq!(map["timed_tests"].value, 5.0);
+        let slowest = summary.slowest_tests();
+        assert_eq!(slowest.len(), SLOWEST_TESTS);
+        assert_eq!(slowest[0], ("end_to_end_run_writes_records", 11.620334 * 2.0));
+        assert_eq!(slowest[4].0, "stats::tests::percentiles_by_rank");
+        assert!(map.iter().all(|(key, _)| !key.starts_with("slowest_test_")), "{map:?}");
+        let diagnostics = summary.diagnostics();
+        assert_eq!(diagnostics["median"], "linear");
+        let listed = diagnostics["slowest_tests"].as_array().unwrap();
+        assert_eq!(listed.len(), SLOWEST_TESTS);
+        assert_eq!(listed[0]["name"], "end_to_end_run_writes_records");
+        assert_eq!(listed[0]["seconds"], 11.620334 * 2.0);
+        let monte_carlo = listed
+            .iter()
+            .find(|test| test["name"] == "tests::monte_carlo_cpu_time_tracks_wall_time")
+            .unwrap();
+        assert_eq!(monte_carlo["seconds"], 2.908127 * 2.0);
+
+        let mut map = Metrics::new();
+        TimingSummary::default().insert_metrics(&mut map);
+        assert_eq!(map["slowest_binary_seconds"].value, -1.0);
+        assert_eq!(map["total_test_binaries"].value, 0.0);
+        let empty = TimingSummary::default().diagnostics();
+        assert_eq!(empty, json!({"median": "linear", "slowest_tests": []}));
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::benchmark_ids;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bounds {
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ThresholdRule {
+    pub(crate) benchmark_id: String,
+    pub(crate) metric: String,
+    pub(crate) min: Option<f64>,
+    pub(crate) max: Option<f64>,
+}
+
+impl ThresholdRule {
+    fn key(&self) -> String {
+        format!("{}.{}", self.benchmark_id, self.metric)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Thresholds {
+    pub(crate) rules: Vec<ThresholdRule>,
+    pub(crate) rule_errors: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Violation {
+    pub(crate) rule: String,
+    pub(crate) bound: &'static str,
+    pub(crate) limit: f64,
+    pub(crate) value: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Evaluation {
+    pub(crate) violations: Vec<Violation>,
+    pub(crate) rule_errors: Vec<String>,
+}
+
+/// Parses `{"<benchmark_id>.<metric>": {"min": x, "max": y}}`. Malformed
+/// JSON fails the whole file; individually broken rules become rule errors.
+pub(crate) fn parse_rules(text: &str) -> Result<Thresholds, String> {
+    let raw: BTreeMap<String, Bounds> =
+        serde_json::from_str(text).map_err(|err| format!("invalid thresholds file: {err}"))?;
+    let mut thresholds = Thresholds::default();
+    for (key, bounds) in raw {
+        let Some((benchmark_id, metric)) = key.split_once('.') else {
+            thresholds
+                .rule_errors
+                .push(format!("{key}: expected <benchmark_id>.<metric>"));
+            continue;
+        };
+        if benchmark_id.is_empty() || metric.is_empty() {
+            thresholds
+                .rule_errors
+                .push(format!("{key}: expected <benchmark_id>.<metric>"));
+            continue;
+        }
+        if let Err(err) = benchmark_ids::check(benchmark_id) {
+            thresholds.rule_errors.push(format!("{key}: {err}"));
+            continue;
+        }
+        match (bounds.min, bounds.max) {
+            (None, None) => {
+                thresholds
+                    .rule_errors
+                    .push(format!("{key}: rule needs min and/or max"));
+                continue;
+            }
+            (Some(min), Some(max)) if min > max => {
+                thresholds
+                    .rule_errors
+                    .push(format!("{key}: min {min} is greater than max {max}"));
+                continue;
+            }
+            _ => {}
+        }
+        thresholds.rules.push(ThresholdRule {
+            benchmark_id: benchmark_id.to_string(),
+            metric: metric.to_string(),
+            min: bounds.min,
+            max: bounds.max,
+        });
+    }
+    Ok(thresholds)
+}
+
+pub(crate) fn load_rules(path: &Path) -> Result<Thresholds, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read thresholds file {}: {err}", path.display()))?;
+    parse_rules(&text)
+}
+
+/// Bounds are inclusive: a value equal to `min` or `max` passes.
+pub(crate) fn evaluate(thresholds: &Thresholds, records: &[Value]) -> Evaluation {
+    let mut evaluation = Evaluation {
+        violations: Vec::new(),
+        rule_errors: thresholds.rule_errors.clone(),
+    };
+    for rule in &thresholds.rules {
+        let key = rule.key();
+        let Some(record) = records
+            .iter()
+            .find(|record| record["benchmark_id"].as_str() == Some(rule.benchmark_id.as_str()))
+        else {
+            evaluation
+                .rule_errors
+                .push(format!("{key}: no {} record in this run", rule.benchmark_id));
+            continue;
+        };
+        let Some(value) = record["metrics"][rule.metric.as_str()]["value"].as_f64() else {
+            evaluation.rule_errors.push(format!(
+                "{key}: unknown metric {} for {}",
+                rule.metric, rule.benchmark_id
+            ));
+            continue;
+        };
+        if let Some(min) = rule.min.filter(|min| value < *min) {
+            evaluation.violations.push(Violation {
+                rule: key.clone(),
+                bound: "min",
+                limit: min,
+                value,
+            });
+        }
+        if let Some(max) = rule.max.filter(|max| value > *max) {
+            evaluation.violations.push(Violation {
+                rule: key,
+                bound: "max",
+                limit: max,
+                value,
+            });
+        }
+    }
+    evaluation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn records() -> Vec<Value> {
+        vec![
+            json!({
+                "benchmark_id": "io_concurrent_http_client",
+                "metrics": {"request_errors": {"value": 5.0, "unit": "count"}},
+            }),
+            json!({
+                "benchmark_id": "data_pipeline_etl_minibatch",
+                "metrics": {"throughput_mb_s": {"value": 42.0, "unit": "mb/s"}},
+            }),
+        ]
+    }
+
+    #[test]
+    fn parses_min_and_max_rules() {
+        let thresholds = parse_rules(
+            r#"{
+                "io_concurrent_http_client.request_errors": {"max": 5},
+                "data_pipeline_etl_minibatch.throughput_mb_s": {"min": 50, "max": 900}
+            }"#,
+        )
+        .unwrap();
+        assert!(thresholds.rule_errors.is_empty());
+        assert_eq!(thresholds.rules.len(), 2);
+        let etl = &thresholds.rules[0];
+        assert_eq!(etl.benchmark_id, "data_pipeline_etl_minibatch");
+        assert_eq!(etl.metric, "throughput_mb_s");
+        assert_eq!((etl.min, etl.max), (Some(50.0), Some(900.0)));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(parse_rules("[1, 2]").is_err());
+        assert!(parse_rules("{\"a.b\": {\"max\": \"five\"}}").is_err());
+        let err = parse_rules("{\"a.b\": {\"maximum\": 5}}").unwrap_err();
+        assert!(err.contains("maximum"), "{err}");
+    }
+
+    #[test]
+    fn reports_structurally_broken_rules() {
+        let thresholds = parse_rules(
+            r#"{
+                "no_metric": {"max": 1},
+                ".metric": {"max": 1},
+                "a.b": {},
+                "a.c": {"min": 3, "max": 2}
+            }"#,
+        )
+        .unwrap();
+        assert!(thresholds.rules.is_empty());
+        assert_eq!(thresholds.rule_errors.len(), 4);
+    }
+
+    #[test]
+    fn boundary_equality_passes() {
+        let thresholds = parse_rules(
+            r#"{
+                "io_concurrent_http_client.request_errors": {"min": 5, "max": 5},
+                "data_pipeline_etl_minibatch.throughput_mb_s": {"min": 42}
+            }"#,
+        )
+        .unwrap();
+        let evaluation = evaluate(&thresholds, &records());
+        assert!(evaluation.violations.is_empty());
+        assert!(evaluation.rule_errors.is_empty());
+    }
+
+    #[test]
+    fn values_outside_bounds_violate() {
+        let thresholds = parse_rules(
+            r#"{
+                "io_concurrent_http_client.request_errors": {"max": 4.999},
+                "data_pipeline_etl_minibatch.throughput_mb_s": {"min": 50}
+            }"#,
+        )
+        .unwrap();
+        let evaluation = evaluate(&thresholds, &records());
+        assert_eq!(
+            evaluation.violations,
+            vec![
+                Violation {
+                    rule: "data_pipeline_etl_minibatch.throughput_mb_s".to_string(),
+                    bound: "min",
+                    limit: 50.0,
+                    value: 42.0,
+                },
+                Violation {
+                    rule: "io_concurrent_http_client.request_errors".to_string(),
+                    bound: "max",
+                    limit: 4.999,
+                    value: 5.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_rule_errors() {
+        let thresholds = parse_rules(
+            r#"{
+                "missing_benchmark.runtime_seconds": {"max": 1},
+                "io_concurent_http_client.runtime_seconds": {"max": 1},
+                "io_concurrent_http_client.p99_ms": {"max": 1},
+                "cpu_monte_carlo_pi.runtime_seconds": {"max": 1}
+            }"#,
+        )
+        .unwrap();
+        let evaluation = evaluate(&thresholds, &records());
+        assert!(evaluation.violations.is_empty());
+        assert_eq!(
+            evaluation.rule_errors,
+            [
+                "io_concurent_http_client.runtime_seconds: unknown benchmark \
+                 io_concurent_http_client (did you mean io_concurrent_http_client?)",
+                "missing_benchmark.runtime_seconds: unknown benchmark missing_benchmark",
+                "cpu_monte_carlo_pi.runtime_seconds: no cpu_monte_carlo_pi record in this run",
+                "io_concurrent_http_client.p99_ms: unknown metric p99_ms for io_concurrent_http_client",
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! When an HTTP run's requests finished and failed: the run split into a
+//! fixed number of equal time buckets, with completions and errors counted
+//! per bucket. Error totals alone cannot tell a steady trickle from a burst
+//! while the server recycles its workers; the timeline can.
+
+use std::time::{Duration, Instant};
+
+/// Buckets per run unless `timeline_buckets` says otherwise.
+pub(crate) const DEFAULT_BUCKETS: usize = 20;
+
+/// Slots a `Tally` keeps at most.
+const TALLY_SLOTS: usize = 4096;
+/// A `Tally`'s slot width until the run outlasts its slots.
+const FIRST_SLOT: Duration = Duration::from_micros(1);
+
+/// When a worker's requests ended, counted as they end: completions and
+/// errors per slot of fixed width from an origin. A run that outlasts
+/// `TALLY_SLOTS` slots has neighbouring slots merged and the width doubled,
+/// so a worker holds the same few counters however long it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Tally {
+    /// The run's start; the first outcome's time when not given one.
+    origin: Option<Instant>,
+    slot: Duration,
+    completed: Vec<usize>,
+    errors: Vec<usize>,
+}
+
+impl Default for Tally {
+    fn default() -> Self {
+        Tally::starting(None)
+    }
+}
+
+impl Tally {
+    pub(crate) fn starting(origin: Option<Instant>) -> Self {
+        Tally {
+            origin,
+            slot: FIRST_SLOT,
+            completed: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Counts a request that ended at `at`.
+    pub(crate) fn record(&mut self, at: Instant, failed: bool) {
+        let (completed, errors) = if failed { (0, 1) } else { (1, 0) };
+        self.add(at, completed, errors);
+    }
+
+    /// Adds `other`'s counts, each slot at the time it starts. An empty
+    /// tally takes `other` whole, origin included.
+    pub(crate) fn merge(&mut self, other: Tally) {
+        let Some(origin) = other.origin else {
+            return;
+        };
+        if self.origin.is_none() {
+            *self = other;
+            return;
+        }
+        let counts = other.completed.into_iter().zip(other.errors);
+        for (index, (completed, errors)) in counts.enumerate() {
+            if completed + errors > 0 {
+                self.add(origin + other.slot * index as u32, completed, errors);
+            }
+        }
+    }
+
+    fn add(&mut self, at: Instant, completed: usize, errors: usize) {
+        let origin = *self.origin.get_or_insert(at);
+        let offset = at.saturating_duration_since(origin).as_nanos();
+        let mut index = (offset / self.slot.as_nanos()) as usize;
+        while index >= TALLY_SLOTS {
+            self.coarsen();
+            index /= 2;
+        }
+        if index >= self.completed.len() {
+            self.completed.resize(index + 1, 0);
+            self.errors.resize(index + 1, 0);
+        }
+        self.completed[index] += completed;
+        self.errors[index] += errors;
+    }
+
+    /// Merges each pair of slots into one twice as wide.
+    fn coarsen(&mut self) {
+        for counts in [&mut self.completed, &mut self.errors] {
+            *counts = counts.chunks(2).map(|pair| pair.iter().sum()).collect();
+        }
+        self.slot *= 2;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Timeline {
+    pub(crate) bucket_seconds: f64,
+    pub(crate) completed: Vec<usize>,
+    pub(crate) errors: Vec<usize>,
+}
+
+impl Timeline {
+    /// `tally` in `buckets` equal buckets (at least one) spanning `span`
+    /// from `start`, each of its slots placed by its midpoint; a slot at or
+    /// past the end counts in the last bucket.
+    pub(crate) fn bucket(
+        tally: &Tally,
+        start: Instant,
+        span: Duration,
+        buckets: usize,
+    ) -> Timeline {
+        let buckets = buckets.max(1);
+        let mut timeline = Timeline {
+            bucket_seconds: span.as_secs_f64() / buckets as f64,
+            completed: vec![0; buckets],
+            errors: vec![0; buckets],
+        };
+        let Some(origin) = tally.origin else {
+            return timeline;
+        };
+        for (slot, (completed, errors)) in tally.completed.iter().zip(&tally.errors).enumerate() {
+            let index = if span.is_zero() {
+                0
+            } else {
+                let at = origin + tally.slot * slot as u32 + tally.slot / 2;
+                let offset = at.saturating_duration_since(start);
+                let fraction = offset.as_secs_f64() / span.as_secs_f64();
+                ((fraction * buckets as f64) as usize).min(buckets - 1)
+            };
+            timeline.completed[index] += completed;
+            timeline.errors[index] += errors;
+        }
+        timeline
+    }
+
+    /// The most errors in any one bucket; 0 without errors.
+    pub(crate) fn max_error_burst(&self) -> usize {
+        self.errors.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Completions per second over the buckets between the first and the
+    /// last, leaving out ramp-up and drain; over every bucket when there
+    /// are fewer than three. `None` for an empty span.
+    pub(crate) fn steady_state_rps(&self) -> Option<f64> {
+        let steady = match self.completed.len() {
+            len if len >= 3 => &self.completed[1..len - 1],
+            _ => &self.completed[..],
+        };
+        let seconds = steady.len() as f64 * self.bucket_seconds;
+        (seconds > 0.0).then(|| steady.iter().sum::<usize>() as f64 / seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tally from `start` of requests that ended `at_ms` in, failed or not.
+    fn tally(start: Instant, outcomes: impl IntoIterator<Item = (u64, bool)>) -> Tally {
+        let mut tally = Tally::starting(Some(start));
+        for (at_ms, failed) in outcomes {
+            tally.record(start + Duration::from_millis(at_ms), failed);
+        }
+        tally
+    }
+
+    #[test]
+    fn outcomes_land_in_the_bucket_of_their_time() {
+        let start = Instant::now();
+        let outcomes = tally(
+            start,
+            [
+                (0, false),
+                (99, false),
+                (100, true),
+                (250, false),
+                (399, true),
+                // At the end of the span, and past it: the last bucket.
+                (400, false),
+                (450, true),
+            ],
+        );
+        let timeline = Timeline::bucket(&outcomes, start, Duration::from_millis(400), 4);
+        assert_eq!(timeline.bucket_seconds, 0.1);
+        assert_eq!(timeline.completed, [2, 0, 1, 1]);
+        assert_eq!(timeline.errors, [0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn a_burst_is_the_fullest_error_bucket() {
+        let start = Instant::now();
+        // A steady trickle, then a recycle that fails twelve requests at once.
+        let trickle = (0..20).map(|n| (n * 50, n % 5 == 0));
+        let outcomes = tally(start, trickle.chain((0..12).map(|n| (605 + n, true))));
+        let second = Duration::from_secs(1);
+        let timeline = Timeline::bucket(&outcomes, start, second, DEFAULT_BUCKETS);
+        assert_eq!(timeline.errors.len(), DEFAULT_BUCKETS);
+        assert_eq!(timeline.errors.iter().sum::<usize>(), 16);
+        assert_eq!(timeline.completed.iter().sum::<usize>(), 16);
+        assert_eq!(timeline.max_error_burst(), 12);
+        assert_eq!(timeline.errors[12], 12);
+
+        let clean = tally(start, [(10, false), (20, false)]);
+        assert_eq!(Timeline::bucket(&clean, start, second, 5).max_error_burst(), 0);
+    }
+
+    #[test]
+    fn steady_state_leaves_out_the_first_and_last_bucket() {
+        let start = Instant::now();
+        // A slow start and a drain around 100 ms of 10 completions each.
+        let steady = (0..20).map(|n| (100 + n * 10, false));
+        let outcomes = tally(start, [(10, false)].into_iter().chain(steady).chain([(390, true)]));
+        let timeline = Timeline::bucket(&outcomes, start, Duration::from_millis(400), 4);
+        assert_eq!(timeline.completed, [1, 10, 10, 0]);
+        assert_eq!(timeline.steady_state_rps(), Some(100.0));
+
+        let two = Timeline::bucket(&outcomes, start, Duration::from_millis(400), 2);
+        assert_eq!(two.steady_state_rps(), Some(21.0 / 0.4));
+        let empty = Timeline::bucket(&outcomes, start, Duration::ZERO, 4);
+        assert_eq!(empty.steady_state_rps(), None);
+    }
+
+    #[test]
+    fn an_empty_span_or_bucket_count_still_counts_everything() {
+        let start = Instant::now();
+        let outcomes = tally(start, [(0, false), (0, true), (5, true)]);
+        let timeline = Timeline::bucket(&outcomes, start, Duration::ZERO, 3);
+        assert_eq!(timeline.completed, [1, 0, 0]);
+        assert_eq!(timeline.errors, [2, 0, 0]);
+        assert_eq!(timeline.bucket_seconds, 0.0);
+
+        let timeline = Timeline::bucket(&outcomes, start, Duration::from_millis(10), 0);
+        assert_eq!((timeline.completed, timeline.errors), (vec![1], vec![2]));
+        let empty = Timeline::bucket(&Tally::default(), start, Duration::from_secs(1), 2);
+        assert_eq!((empty.completed, empty.errors), (vec![0, 0], vec![0, 0]));
+        assert_eq!(empty.bucket_seconds, 0.5);
+    }
+
+    #[test]
+    fn a_long_run_keeps_a_fixed_number_of_slots() {
+        let start = Instant::now();
+        // 100 s at one request per 10 ms, every tenth failing.
+        let outcomes = tally(start, (0..10_000).map(|n| (n * 10, n % 10 == 0)));
+        assert!(outcomes.completed.len() <= TALLY_SLOTS, "{}", outcomes.completed.len());
+        assert_eq!(outcomes.slot, Duration::from_micros(32_768));
+        let timeline = Timeline::bucket(&outcomes, start, Duration::from_secs(100), 10);
+        assert_eq!(timeline.completed.iter().sum::<usize>(), 9_000);
+        assert_eq!(timeline.errors.iter().sum::<usize>(), 1_000);
+        // A slot straddling a bucket boundary counts where its middle is.
+        for completed in &timeline.completed {
+            assert!(completed.abs_diff(900) <= 4, "{:?}", timeline.completed);
+        }
+    }
+
+    #[test]
+    fn merged_tallies_count_like_one() {
+        let start = Instant::now();
+        let even = tally(start, (0..50).map(|n| (n * 20, n % 7 == 0)));
+        let odd = tally(start, (0..50).map(|n| (n * 20 + 10, false)));
+        let mut merged = Tally::default();
+        merged.merge(even.clone());
+        merged.merge(odd);
+        merged.merge(Tally::default());
+        let whole = tally(start, (0..100).map(|n| (n * 10, n % 2 == 0 && (n / 2) % 7 == 0)));
+        assert_eq!(merged, whole);
+        assert_ne!(merged, even);
+    }
+}
@@ -0,0 +1,103 @@
+//! TLS for `https://` base URLs, through rustls with the Mozilla root
+//! store. The handshake completes inside `HttpConnection::connect`, so its
+//! cost lands in the connect time, as it does in Python's `http.client`.
+//! A certificate the roots do not vouch for is its own error category
+//! rather than a generic transport failure: a benchmark pointed at a host
+//! with a self-signed certificate should say so, not look like a flaky
+//! network. Built without the `tls` feature, `https://` targets are
+//! reported unsupported instead.
+
+use std::io;
+#[cfg(feature = "tls")]
+use std::net::TcpStream;
+
+#[cfg(feature = "tls")]
+use crate::http_client::TransportError;
+
+#[cfg(feature = "tls")]
+pub(crate) type TlsStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+/// True when `handshake` can work in this build.
+pub(crate) fn supported() -> bool {
+    cfg!(feature = "tls")
+}
+
+#[cfg(feature = "tls")]
+fn client_config() -> std::sync::Arc<rustls::ClientConfig> {
+    use std::sync::{Arc, OnceLock};
+
+    static CONFIG: OnceLock<Arc<rustls::ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let roots = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let config = rustls::ClientConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .expect("the ring provider supports the default protocol versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
+
+/// Runs the TLS handshake with `host` over `tcp`, verifying its certificate.
+#[cfg(feature = "tls")]
+pub(crate) fn handshake(host: &str, mut tcp: TcpStream) -> Result<TlsStream, TransportError> {
+    let name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|err| TransportError::Connect(format!("{host}: {err}")))?;
+    let mut connection = rustls::ClientConnection::new(client_config(), name)
+        .map_err(|err| TransportError::Connect(err.to_string()))?;
+    while connection.is_handshaking() {
+        connection
+            .complete_io(&mut tcp)
+            .map_err(|err| TransportError::from_io(err, TransportError::Connect))?;
+    }
+    Ok(rustls::StreamOwned::new(connection, tcp))
+}
+
+/// Why a handshake failed, when the cause was the server's certificate.
+#[cfg(feature = "tls")]
+pub(crate) fn certificate_error(err: &io::Error) -> Option<String> {
+    match err.get_ref()?.downcast_ref::<rustls::Error>()? {
+        err @ (rustls::Error::InvalidCertificate(_) | rustls::Error::NoCertificatesPresented) => {
+            Some(err.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+pub(crate) fn certificate_error(_err: &io::Error) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::TransportError;
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn certificate_failures_are_told_apart_from_other_tls_errors() {
+        use rustls::{CertificateError, Error};
+
+        let rejected = Error::InvalidCertificate(CertificateError::UnknownIssuer);
+        let err = io::Error::new(io::ErrorKind::InvalidData, rejected);
+        assert!(certificate_error(&err).is_some_and(|message| message.contains("UnknownIssuer")));
+        assert!(matches!(TransportError::from(err), TransportError::Certificate(_)));
+
+        let other = io::Error::new(io::ErrorKind::InvalidData, Error::DecryptError);
+        assert_eq!(certificate_error(&other), None);
+        assert!(matches!(TransportError::from(other), TransportError::Read(_)));
+    }
+
+    #[test]
+    fn plain_io_errors_are_never_certificate_errors() {
+        let err = io::Error::from(io::ErrorKind::ConnectionRefused);
+        assert_eq!(certificate_error(&err), None);
+        assert!(matches!(TransportError::from(err), TransportError::Read(_)));
+    }
+}
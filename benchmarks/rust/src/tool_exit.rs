@@ -0,0 +1,109 @@
+//! Which nonzero exits of the scanned tools are their way of reporting
+//! findings, and which mean the scan itself broke. cargo-audit exits 1 when
+//! it finds an advisory, which is its job; only codes outside each tool's
+//! table (panics, usage errors, failed builds) count as scan errors.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Tool {
+    CargoAudit,
+    CargoOutdated,
+    Clippy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExitClass {
+    Clean,
+    /// Nonzero because the tool found what it looks for; reported as
+    /// `expected_nonzero_exits`.
+    Findings,
+    /// Counted as a scan error.
+    Unexpected,
+}
+
+/// The nonzero codes each tool documents for "found something".
+/// - cargo-audit exits 1 when vulnerabilities are found.
+/// - cargo-outdated only exits nonzero for outdated dependencies with
+///   `--exit-code`, which the scorecard does not pass.
+/// - clippy exits 101 when denied lints (`-D`, or `deny` in the crate)
+///   fail the build. A panicking tool also exits 101.
+pub(crate) const FINDINGS_EXIT_CODES: [(Tool, &[i32]); 3] = [
+    (Tool::CargoAudit, &[1]),
+    (Tool::CargoOutdated, &[]),
+    (Tool::Clippy, &[101]),
+];
+
+impl Tool {
+    /// Classifies `code`. A findings code only counts as findings when the
+    /// tool's report actually has some; otherwise, as after a panic or a
+    /// compile error, it is unexpected.
+    pub(crate) fn classify(self, code: i32, has_findings: bool) -> ExitClass {
+        if code == 0 {
+            return ExitClass::Clean;
+        }
+        let documented = FINDINGS_EXIT_CODES
+            .iter()
+            .any(|(tool, codes)| *tool == self && codes.contains(&code));
+        if documented && has_findings {
+            ExitClass::Findings
+        } else {
+            ExitClass::Unexpected
+        }
+    }
+}
+
+/// Running counts of classified exits for one scorecard.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ExitTally {
+    pub(crate) unexpected: f64,
+    pub(crate) expected_nonzero: f64,
+}
+
+impl ExitTally {
+    pub(crate) fn add(&mut self, class: ExitClass) {
+        match class {
+            ExitClass::Clean => {}
+            ExitClass::Findings => self.expected_nonzero += 1.0,
+            ExitClass::Unexpected => self.unexpected += 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_known_exit_codes_per_tool() {
+        use ExitClass::{Clean, Findings, Unexpected};
+        let cases = [
+            (Tool::CargoAudit, 0, false, Clean),
+            (Tool::CargoAudit, 1, true, Findings),
+            // An error without a report, e.g. a missing lockfile.
+            (Tool::CargoAudit, 1, false, Unexpected),
+            (Tool::CargoAudit, 2, true, Unexpected),
+            (Tool::CargoAudit, 101, true, Unexpected),
+            (Tool::CargoAudit, -1, false, Unexpected),
+            (Tool::CargoOutdated, 0, true, Clean),
+            (Tool::CargoOutdated, 1, true, Unexpected),
+            (Tool::CargoOutdated, 2, false, Unexpected),
+            (Tool::Clippy, 0, true, Clean),
+            (Tool::Clippy, 101, true, Findings),
+            // A compile error or an ICE, with no lint findings.
+            (Tool::Clippy, 101, false, Unexpected),
+            (Tool::Clippy, 1, true, Unexpected),
+        ];
+        for (tool, code, has_findings, expected) in cases {
+            assert_eq!(tool.classify(code, has_findings), expected, "{tool:?} {code}");
+        }
+    }
+
+    #[test]
+    fn tally_counts_nonzero_exits_by_class() {
+        let mut tally = ExitTally::default();
+        for class in [ExitClass::Clean, ExitClass::Findings, ExitClass::Unexpected] {
+            tally.add(class);
+        }
+        tally.add(ExitClass::Findings);
+        assert_eq!(tally, ExitTally { unexpected: 1.0, expected_nonzero: 2.0 });
+    }
+}
@@ -0,0 +1,466 @@
+//! `--upload <url>`: records go to a results collector while the run is
+//! still going, so a crash in an hour-long soak or matrix run loses at most
+//! one batch. Records are batched, each batch is POSTed with retries and an
+//! `Idempotency-Key`, and a batch that still fails is written to a spool dir
+//! and replayed at the start of the next run.
+//!
+//! A batch is `{"idempotency_key": ..., "records": [...]}`, both on the wire
+//! and as a spool file named `<idempotency_key>.json`.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::http_client::{
+    parse_base_url, HttpConnection, ParsedUrl, StatusBucket, DEFAULT_TIMEOUT, HTTPS_NEEDS_TLS,
+};
+use crate::record::Canonical;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct UploadConfig {
+    /// Records per POST.
+    pub(crate) batch_size: usize,
+    /// A partial batch is sent once this much time has passed since the
+    /// last send; checked as each record arrives.
+    pub(crate) flush_interval: Duration,
+    /// Tries per batch before it is spooled.
+    pub(crate) max_attempts: usize,
+    /// Wait before the first retry, doubled for each one after.
+    pub(crate) backoff: Duration,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            batch_size: 50,
+            flush_interval: Duration::from_secs(30),
+            max_attempts: 4,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Reported as `upload` in the run summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct UploadCounts {
+    pub(crate) records_uploaded: usize,
+    pub(crate) batches_uploaded: usize,
+    /// Spooled batches from earlier runs that were delivered this time.
+    pub(crate) batches_replayed: usize,
+    /// Batches that used up their attempts this run.
+    pub(crate) batches_failed: usize,
+    /// Batches waiting in the spool dir when the run ended.
+    pub(crate) batches_spooled: usize,
+}
+
+pub(crate) trait Collector {
+    /// Sends one serialized batch; `Ok` only when the collector accepted it.
+    fn post(&mut self, idempotency_key: &str, body: &str) -> Result<(), String>;
+}
+
+/// A collector at an `http(s)://host[:port][/path]` URL, over one keep-alive
+/// connection that is reopened after an error or a server close.
+pub(crate) struct HttpCollector {
+    target: ParsedUrl,
+    path: String,
+    connection: Option<HttpConnection>,
+}
+
+impl HttpCollector {
+    pub(crate) fn new(url: &str) -> Result<Self, String> {
+        let target = parse_base_url(url).map_err(|err| format!("--upload: {url:?}: {err}"))?;
+        if !target.scheme.supported() {
+            return Err(format!("--upload: {url:?}: {HTTPS_NEEDS_TLS}"));
+        }
+        // The collector's path is an endpoint, not a base: kept as given,
+        // trailing slash included.
+        let after_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let path = after_scheme.find('/').map_or("/", |start| &after_scheme[start..]);
+        Ok(HttpCollector {
+            target,
+            path: path.to_string(),
+            connection: None,
+        })
+    }
+}
+
+impl Collector for HttpCollector {
+    fn post(&mut self, idempotency_key: &str, body: &str) -> Result<(), String> {
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => HttpConnection::connect(&self.target, DEFAULT_TIMEOUT)?,
+        };
+        let headers = [("Idempotency-Key", idempotency_key)];
+        let host = self.target.host_header();
+        let (status, reusable) = connection.post_json(&host, &self.path, &headers, body)?;
+        if reusable {
+            self.connection = Some(connection);
+        }
+        match status {
+            StatusBucket::Code(code) if (200..300).contains(&code) => Ok(()),
+            StatusBucket::Code(code) => Err(format!("collector answered {code}")),
+            StatusBucket::Malformed => Err("malformed status line from collector".to_string()),
+        }
+    }
+}
+
+/// One batch as sent and spooled, with its records written canonically.
+#[derive(Serialize)]
+struct Batch<'a> {
+    idempotency_key: &'a str,
+    records: Canonical<'a>,
+}
+
+/// The body of a batch of `records` sent under `idempotency_key`.
+pub(crate) fn batch_body(idempotency_key: &str, records: &[Value]) -> String {
+    serde_json::to_string(&Batch {
+        idempotency_key,
+        records: Canonical(records),
+    })
+    .expect("batch serializes")
+}
+
+pub(crate) struct UploadQueue<'a> {
+    collector: &'a mut dyn Collector,
+    config: UploadConfig,
+    spool: PathBuf,
+    pending: Vec<Value>,
+    /// Numbers the batches of this run for their idempotency keys.
+    next_batch: usize,
+    last_flush: Instant,
+    counts: UploadCounts,
+}
+
+impl<'a> UploadQueue<'a> {
+    pub(crate) fn new(
+        collector: &'a mut dyn Collector,
+        config: UploadConfig,
+        spool: &Path,
+    ) -> Self {
+        UploadQueue {
+            collector,
+            config,
+            spool: spool.to_path_buf(),
+            pending: Vec::new(),
+            next_batch: 0,
+            last_flush: Instant::now(),
+            counts: UploadCounts::default(),
+        }
+    }
+
+    /// Sends the batches earlier runs left in the spool dir, oldest name
+    /// first, deleting each once it is accepted. Stops at the first one
+    /// that fails, since the collector is likely still down.
+    pub(crate) fn replay(&mut self) {
+        for path in spooled_batches(&self.spool) {
+            let Ok(body) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if self.send(key, &body).is_err() {
+                return;
+            }
+            let records = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|batch| batch["records"].as_array().map(Vec::len))
+                .unwrap_or(0);
+            self.counts.records_uploaded += records;
+            self.counts.batches_replayed += 1;
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    /// Queues one record, sending the batch once it is full or the flush
+    /// interval has passed.
+    pub(crate) fn push(&mut self, record: Value) {
+        self.pending.push(record);
+        if self.pending.len() >= self.config.batch_size
+            || self.last_flush.elapsed() >= self.config.flush_interval
+        {
+            self.flush();
+        }
+    }
+
+    /// Sends whatever is queued as one batch; a batch that fails every
+    /// attempt is spooled right away.
+    pub(crate) fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if self.pending.is_empty() {
+            return;
+        }
+        let records = std::mem::take(&mut self.pending);
+        let run_id = records[0]["run_id"].as_str().unwrap_or("run").to_string();
+        let key = format!("{run_id}-{:06}", self.next_batch);
+        self.next_batch += 1;
+        let body = batch_body(&key, &records);
+        match self.send(&key, &body) {
+            Ok(()) => {
+                self.counts.records_uploaded += records.len();
+                self.counts.batches_uploaded += 1;
+            }
+            Err(err) => {
+                eprintln!("warning: upload of batch {key} failed: {err}; spooling it");
+                self.counts.batches_failed += 1;
+                if let Err(err) = write_spooled(&self.spool, &key, &body) {
+                    eprintln!("warning: cannot spool batch {key}: {err}; its records are lost");
+                }
+            }
+        }
+    }
+
+    /// Sends the last partial batch and reports what happened to every
+    /// record this run saw.
+    pub(crate) fn finish(mut self) -> UploadCounts {
+        self.flush();
+        self.counts.batches_spooled = spooled_batches(&self.spool).len();
+        self.counts
+    }
+
+    fn send(&mut self, key: &str, body: &str) -> Result<(), String> {
+        let mut delay = self.config.backoff;
+        let mut attempt = 1;
+        loop {
+            match self.collector.post(key, body) {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= self.config.max_attempts => return Err(err),
+                Err(_) => {
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Spool files in name order.
+fn spooled_batches(spool: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(spool)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Written under a temporary name and renamed, so a crash mid-write never
+/// leaves a truncated batch to replay.
+fn write_spooled(spool: &Path, key: &str, body: &str) -> std::io::Result<()> {
+    fs::create_dir_all(spool)?;
+    let temp = spool.join(format!("{key}.json.tmp"));
+    fs::write(&temp, body)?;
+    fs::rename(temp, spool.join(format!("{key}.json")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Fails the first `failures` posts, then accepts everything; every
+    /// accepted batch is kept by key.
+    #[derive(Default)]
+    struct FlakyCollector {
+        failures: usize,
+        attempts: Vec<String>,
+        accepted: Vec<(String, Value)>,
+    }
+
+    impl Collector for FlakyCollector {
+        fn post(&mut self, idempotency_key: &str, body: &str) -> Result<(), String> {
+            self.attempts.push(idempotency_key.to_string());
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err("collector answered 503".to_string());
+            }
+            self.accepted.push((idempotency_key.to_string(), serde_json::from_str(body).unwrap()));
+            Ok(())
+        }
+    }
+
+    fn config(batch_size: usize) -> UploadConfig {
+        UploadConfig {
+            batch_size,
+            flush_interval: Duration::from_secs(3600),
+            max_attempts: 3,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    fn record(index: usize) -> Value {
+        json!({"run_id": "rust-1-2", "benchmark_id": "cpu_monte_carlo_pi", "index": index})
+    }
+
+    fn spool_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("upload-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn batches_records_and_retries_failures() {
+        let spool = spool_dir("retry");
+        let mut collector = FlakyCollector {
+            failures: 2,
+            ..FlakyCollector::default()
+        };
+        let mut queue = UploadQueue::new(&mut collector, config(2), &spool);
+        for index in 0..5 {
+            queue.push(record(index));
+        }
+        let counts = queue.finish();
+        assert_eq!(
+            counts,
+            UploadCounts {
+                records_uploaded: 5,
+                batches_uploaded: 3,
+                ..UploadCounts::default()
+            }
+        );
+        // The first batch took three attempts under one key.
+        let first = "rust-1-2-000000";
+        assert_eq!(
+            collector.attempts,
+            [first, first, first, "rust-1-2-000001", "rust-1-2-000002"]
+        );
+        let sizes: Vec<usize> = collector
+            .accepted
+            .iter()
+            .map(|(key, batch)| {
+                assert_eq!(batch["idempotency_key"], key.as_str());
+                batch["records"].as_array().unwrap().len()
+            })
+            .collect();
+        assert_eq!(sizes, [2, 2, 1]);
+        assert!(!spool.exists());
+    }
+
+    #[test]
+    fn zero_flush_interval_sends_every_record() {
+        let spool = spool_dir("interval");
+        let mut collector = FlakyCollector::default();
+        let mut queue = UploadQueue::new(
+            &mut collector,
+            UploadConfig {
+                flush_interval: Duration::ZERO,
+                ..config(100)
+            },
+            &spool,
+        );
+        queue.push(record(0));
+        queue.push(record(1));
+        assert_eq!(queue.finish().batches_uploaded, 2);
+    }
+
+    #[test]
+    fn failed_batches_are_spooled_and_replayed_next_run() {
+        let spool = spool_dir("replay");
+        let mut down = FlakyCollector {
+            failures: usize::MAX,
+            ..FlakyCollector::default()
+        };
+        let mut queue = UploadQueue::new(&mut down, config(2), &spool);
+        for index in 0..3 {
+            queue.push(record(index));
+        }
+        let counts = queue.finish();
+        assert_eq!(counts.batches_failed, 2);
+        assert_eq!(counts.batches_spooled, 2);
+        assert_eq!(counts.records_uploaded, 0);
+        assert_eq!(down.attempts.len(), 6);
+        let spooled = spooled_batches(&spool);
+        assert_eq!(spooled.len(), 2);
+        let first: Value = serde_json::from_str(&fs::read_to_string(&spooled[0]).unwrap()).unwrap();
+        assert_eq!(first["idempotency_key"], "rust-1-2-000000");
+        assert_eq!(first["records"][1]["index"], 1);
+
+        // Still down at the next start: replay gives up after one batch.
+        let mut still_down = FlakyCollector {
+            failures: usize::MAX,
+            ..FlakyCollector::default()
+        };
+        let mut queue = UploadQueue::new(&mut still_down, config(2), &spool);
+        queue.replay();
+        assert_eq!(queue.finish().batches_spooled, 2);
+        assert_eq!(still_down.attempts, ["rust-1-2-000000"; 3]);
+
+        let mut back = FlakyCollector::default();
+        let mut queue = UploadQueue::new(&mut back, config(2), &spool);
+        queue.replay();
+        let counts = queue.finish();
+        assert_eq!(counts.batches_replayed, 2);
+        assert_eq!(counts.records_uploaded, 3);
+        assert_eq!(counts.batches_spooled, 0);
+        let keys: Vec<&str> = back.accepted.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["rust-1-2-000000", "rust-1-2-000001"]);
+        let _ = fs::remove_dir_all(spool);
+    }
+
+    /// Serves one connection: answers each POST with the next status in
+    /// `statuses` and reports the idempotency keys it saw.
+    fn mock_collector(statuses: Vec<u16>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut keys = Vec::new();
+            for status in statuses {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                assert_eq!(line, "POST /ingest HTTP/1.1\r\n");
+                let mut length = 0;
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(": ") else {
+                        break;
+                    };
+                    match name {
+                        "Content-Length" => length = value.parse().unwrap(),
+                        "Idempotency-Key" => keys.push(value.to_string()),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let response = format!("HTTP/1.1 {status} Status\r\nContent-Length: 0\r\n\r\n");
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+            keys
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn http_collector_reuses_one_connection_across_retries() {
+        let (url, server) = mock_collector(vec![503, 200, 200]);
+        let mut collector = HttpCollector::new(&url).unwrap();
+        assert_eq!(collector.path, "/ingest");
+        let spool = spool_dir("http");
+        let mut queue = UploadQueue::new(&mut collector, config(1), &spool);
+        queue.push(record(0));
+        queue.push(record(1));
+        let counts = queue.finish();
+        assert_eq!(counts.batches_uploaded, 2);
+        assert_eq!(counts.batches_failed, 0);
+        assert_eq!(
+            server.join().unwrap(),
+            ["rust-1-2-000000", "rust-1-2-000000", "rust-1-2-000001"]
+        );
+        let https = HttpCollector::new("https://collector.example/ingest");
+        assert_eq!(https.is_ok(), crate::tls::supported());
+        assert!(HttpCollector::new("ftp://collector.example").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! `view <results.json>`: a single-page results viewer on a loopback port,
+//! for demos where a browser is handier than `jq`. The page is one embedded
+//! template with the records substituted in, so it works offline; the raw
+//! records are also served as JSON. Requests are handled one at a time and
+//! the server runs until the process is interrupted.
+
+use serde_json::Value;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+
+use crate::record;
+
+const TEMPLATE: &str = include_str!("../assets/viewer.html");
+/// Replaced with the records; valid JavaScript on its own so the template
+/// can be opened and edited directly.
+const PLACEHOLDER: &str = "/*RECORDS*/null";
+
+/// The rendered page and the raw records, built once at startup.
+pub(crate) struct Site {
+    page: String,
+    records: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+impl Site {
+    pub(crate) fn new(records: &Value) -> Self {
+        Site {
+            page: render(records),
+            records: canonical(records),
+        }
+    }
+
+    pub(crate) fn route(&self, method: &str, path: &str) -> Response {
+        let path = path.split('?').next().unwrap_or(path);
+        let (status, content_type, body) = match (method, path) {
+            ("GET", "/" | "/index.html") => (200, "text/html; charset=utf-8", self.page.clone()),
+            ("GET", "/records.json") => (200, "application/json", self.records.clone()),
+            ("GET", _) => (404, "text/plain; charset=utf-8", "not found\n".to_string()),
+            _ => (405, "text/plain; charset=utf-8", "method not allowed\n".to_string()),
+        };
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+}
+
+/// Reads a results file; it must hold an array of records.
+pub(crate) fn load(path: &Path) -> Result<Value, String> {
+    let text =
+        fs::read_to_string(path).map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+    let records: Value = serde_json::from_str(&text)
+        .map_err(|err| format!("{} is not valid JSON: {err}", path.display()))?;
+    if !records.is_array() {
+        return Err(format!("{} is not an array of records", path.display()));
+    }
+    Ok(records)
+}
+
+/// The template with the records inlined. `<` only occurs inside JSON
+/// strings, where `\u003c` means the same thing, so a record containing
+/// `</script>` cannot end the script element early.
+pub(crate) fn render(records: &Value) -> String {
+    let blob = canonical(records).replace('<', "\\u003c");
+    TEMPLATE.replacen(PLACEHOLDER, &blob, 1)
+}
+
+/// `load` only accepts arrays, so anything else is written as no records.
+fn canonical(records: &Value) -> String {
+    record::to_json(records.as_array().map_or(&[], Vec::as_slice))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    }
+}
+
+/// Answers one request and closes the connection.
+pub(crate) fn handle(stream: TcpStream, site: &Site) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+    let response = site.route(method, path);
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    let stream = reader.get_mut();
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())
+}
+
+/// Serves `site` on `listener` until the process exits.
+pub(crate) fn serve(listener: TcpListener, site: &Site) {
+    for stream in listener.incoming().flatten() {
+        let _ = handle(stream, site);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use std::thread;
+
+    fn records() -> Value {
+        json!([
+            {
+                "benchmark_id": "cpu_monte_carlo_pi",
+                "language": "rust",
+                "metrics": {"runtime_seconds": {"value": 0.25, "unit": "s"}},
+                "note": "</script><script>alert(1)</script>"
+            }
+        ])
+    }
+
+    /// The JSON between `const RECORDS = ` and the end of that line.
+    fn inlined(page: &str) -> Value {
+        let start = page.find("const RECORDS = ").unwrap() + "const RECORDS = ".len();
+        let line = &page[start..start + page[start..].find('\n').unwrap()];
+        serde_json::from_str(line.trim_end_matches(';')).unwrap()
+    }
+
+    #[test]
+    fn inlines_records_without_breaking_the_script() {
+        assert!(TEMPLATE.contains(PLACEHOLDER));
+        let page = render(&records());
+        assert!(!page.contains(PLACEHOLDER));
+        assert_eq!(page.matches("</script>").count(), 1);
+        assert_eq!(inlined(&page), records());
+        assert_eq!(inlined(&render(&json!([]))), json!([]));
+    }
+
+    #[test]
+    fn routes_page_records_and_errors() {
+        let site = Site::new(&records());
+        let page = site.route("GET", "/");
+        assert_eq!((page.status, page.content_type), (200, "text/html; charset=utf-8"));
+        assert_eq!(site.route("GET", "/index.html?x=1"), page);
+        let raw = site.route("GET", "/records.json");
+        assert_eq!(raw.content_type, "application/json");
+        assert_eq!(serde_json::from_str::<Value>(&raw.body).unwrap(), records());
+        assert_eq!(site.route("GET", "/results.csv").status, 404);
+        assert_eq!(site.route("POST", "/records.json").status, 405);
+    }
+
+    #[test]
+    fn serves_records_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let site = Site::new(&records());
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle(stream, &site).unwrap();
+        });
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /records.json HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        assert_eq!(serde_json::from_str::<Value>(body).unwrap(), records());
+    }
+
+    #[test]
+    fn loads_only_record_arrays() {
+        let dir = std::env::temp_dir().join(format!("viewer-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let array = dir.join("results.json");
+        fs::write(&array, records().to_string()).unwrap();
+        assert_eq!(load(&array).unwrap(), records());
+        let summary = dir.join("summary.json");
+        fs::write(&summary, r#"{"run_id": "x"}"#).unwrap();
+        assert!(load(&summary).unwrap_err().contains("not an array"));
+        assert!(load(&dir.join("missing.json")).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,178 @@
+//! Structured warnings: conditions that leave a record usable but worth a
+//! second look, like a stale advisory database or a run that swapped.
+//!
+//! A benchmark raises them through `LiveContext::warn` while it runs; the
+//! harness adds its own to finished records with `push`. Each lands in the
+//! record's `warnings` array, and the run summary counts them by code.
+//! Every code is listed in `WarningCode`, so consumers can match on them.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::retry;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WarningCode {
+    /// The advisory database `cargo audit` read is older than
+    /// `advisory_db_max_age_days`.
+    AdvisoryDbStale,
+    /// A tool printed a count that only parses under another locale's
+    /// separators, so it was not counted.
+    LocaleSuspect,
+    /// The system swapped pages in or out while the benchmark ran.
+    Swapped,
+    /// The checksum baseline was recorded by a different harness build.
+    HarnessFingerprintMismatch,
+    /// `--replay` ran with this machine's value for a machine-specific
+    /// setting instead of the recorded one.
+    ReplayOverride,
+    /// The system clock read earlier than the build timestamp at startup,
+    /// so the record's timestamps are suspect.
+    ClockSuspect,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Warning {
+    pub(crate) code: WarningCode,
+    pub(crate) message: String,
+    /// What the condition was decided on; left out when `null`.
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub(crate) details: Value,
+}
+
+impl Warning {
+    pub(crate) fn new(code: WarningCode, message: impl Into<String>, details: Value) -> Self {
+        Warning {
+            code,
+            message: message.into(),
+            details,
+        }
+    }
+}
+
+/// The warnings of one benchmark attempt, in the order they were raised.
+/// Safe to share with worker threads.
+#[derive(Debug, Default)]
+pub(crate) struct Warnings {
+    raised: Mutex<Vec<Warning>>,
+}
+
+impl Warnings {
+    pub(crate) fn warn(&self, warning: Warning) {
+        if let Ok(mut raised) = self.raised.lock() {
+            raised.push(warning);
+        }
+    }
+
+    /// The record's `warnings` array; `None` when nothing was raised.
+    pub(crate) fn to_value(&self) -> Option<Value> {
+        let raised = self.raised.lock().ok()?;
+        (!raised.is_empty()).then(|| json!(*raised))
+    }
+}
+
+/// Appends `warning` to a finished record's `warnings`.
+pub(crate) fn push(record: &mut Value, warning: Warning) {
+    let Some(fields) = record.as_object_mut() else {
+        return;
+    };
+    let warnings = fields.entry("warnings").or_insert_with(|| json!([]));
+    if let Some(warnings) = warnings.as_array_mut() {
+        warnings.push(json!(warning));
+    }
+}
+
+/// Warnings on the selected records, counted by code.
+pub(crate) fn counts(records: &[Value]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for record in records.iter().filter(|record| retry::is_selected(record)) {
+        let warnings = record["warnings"].as_array().map_or(&[][..], Vec::as_slice);
+        for code in warnings.iter().filter_map(|warning| warning["code"].as_str()) {
+            *counts.entry(code.to_string()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every code, in declaration order.
+    const CODES: [WarningCode; 6] = [
+        WarningCode::AdvisoryDbStale,
+        WarningCode::LocaleSuspect,
+        WarningCode::Swapped,
+        WarningCode::HarnessFingerprintMismatch,
+        WarningCode::ReplayOverride,
+        WarningCode::ClockSuspect,
+    ];
+
+    /// A new variant fails to compile here until it is added to `CODES`.
+    fn position(code: WarningCode) -> usize {
+        match code {
+            WarningCode::AdvisoryDbStale => 0,
+            WarningCode::LocaleSuspect => 1,
+            WarningCode::Swapped => 2,
+            WarningCode::HarnessFingerprintMismatch => 3,
+            WarningCode::ReplayOverride => 4,
+            WarningCode::ClockSuspect => 5,
+        }
+    }
+
+    #[test]
+    fn schema_lists_every_code() {
+        let positions: Vec<usize> = CODES.into_iter().map(position).collect();
+        assert_eq!(positions, (0..CODES.len()).collect::<Vec<_>>());
+        let schema: Value =
+            serde_json::from_str(include_str!("../../shared/schemas/result.schema.json")).unwrap();
+        let listed = &schema["properties"]["warnings"]["items"]["properties"]["code"]["enum"];
+        assert_eq!(*listed, json!(CODES));
+    }
+
+    #[test]
+    fn warnings_serialize_with_their_code_and_optional_details() {
+        let warnings = Warnings::default();
+        assert_eq!(warnings.to_value(), None);
+        warnings.warn(Warning::new(WarningCode::Swapped, "swapped 3 pages", json!({"pages": 3})));
+        let suspect = Warning::new(WarningCode::LocaleSuspect, "1.234 is not a count", Value::Null);
+        warnings.warn(suspect);
+        assert_eq!(
+            warnings.to_value(),
+            Some(json!([
+                {"code": "swapped", "message": "swapped 3 pages", "details": {"pages": 3}},
+                {"code": "locale_suspect", "message": "1.234 is not a count"},
+            ]))
+        );
+        let names = json!([
+            "advisory_db_stale",
+            "locale_suspect",
+            "swapped",
+            "harness_fingerprint_mismatch",
+            "replay_override",
+            "clock_suspect"
+        ]);
+        assert_eq!(json!(CODES), names);
+    }
+
+    #[test]
+    fn summary_counts_cover_selected_records_only() {
+        let stale = || Warning::new(WarningCode::AdvisoryDbStale, "old", Value::Null);
+        let mut first = json!({"benchmark_id": "a"});
+        push(&mut first, stale());
+        push(&mut first, Warning::new(WarningCode::Swapped, "swapped", Value::Null));
+        let mut retried = json!({"benchmark_id": "b", "selected_for_comparison": false});
+        push(&mut retried, stale());
+        let mut second = json!({"benchmark_id": "b", "selected_for_comparison": true});
+        push(&mut second, stale());
+        let clean = json!({"benchmark_id": "c"});
+        assert_eq!(first["warnings"].as_array().unwrap().len(), 2);
+
+        let counts = counts(&[first, retried, second, clean]);
+        let expected = [("advisory_db_stale".to_string(), 2), ("swapped".to_string(), 1)];
+        assert_eq!(counts, BTreeMap::from(expected));
+    }
+}
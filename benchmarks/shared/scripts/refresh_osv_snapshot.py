@@ -0,0 +1,52 @@
+"""Refreshes the RustSec snapshot the Rust runner's built-in vulnerability
+scanner reads when `cargo audit` is not installed.
+
+Downloads OSV's crates.io export (or reads one given with `--zip`) and
+writes every RUSTSEC advisory, sorted by id, as one JSON array.
+"""
+
+from __future__ import annotations
+
+import argparse
+import io
+import json
+import urllib.request
+import zipfile
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parents[3]
+EXPORT_URL = "https://osv-vulnerabilities.storage.googleapis.com/crates.io/all.zip"
+SNAPSHOT = ROOT / "benchmarks" / "shared" / "advisories" / "rustsec-osv.json"
+
+
+def read_export(zip_path: Path | None) -> bytes:
+    if zip_path is not None:
+        return zip_path.read_bytes()
+    with urllib.request.urlopen(EXPORT_URL, timeout=60) as response:
+        return response.read()
+
+
+def rustsec_entries(export: bytes) -> list[dict]:
+    entries: list[dict] = []
+    with zipfile.ZipFile(io.BytesIO(export)) as archive:
+        for name in archive.namelist():
+            if not name.startswith("RUSTSEC-") or not name.endswith(".json"):
+                continue
+            entries.append(json.loads(archive.read(name)))
+    return sorted(entries, key=lambda entry: entry["id"])
+
+
+def main() -> None:
+    parser = argparse.ArgumentParser(description=__doc__)
+    parser.add_argument("--zip", type=Path, help="a downloaded all.zip instead of fetching one")
+    parser.add_argument("--output", type=Path, default=SNAPSHOT)
+    args = parser.parse_args()
+
+    entries = rustsec_entries(read_export(args.zip))
+    args.output.parent.mkdir(parents=True, exist_ok=True)
+    args.output.write_text(json.dumps(entries, indent=1) + "\n", encoding="utf-8")
+    print(f"wrote {len(entries)} advisories to {args.output}")
+
+
+if __name__ == "__main__":
+    main()
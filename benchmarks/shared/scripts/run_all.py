@@ -11,9 +11,12 @@ from pathlib import Path
 from time import sleep
 
 ROOT = Path(__file__).resolve().parents[3]
-RAW = ROOT / "results" / "raw"
-NORM = ROOT / "results" / "normalized"
-DATASETS = ROOT / "benchmarks" / "shared" / "datasets"
+# Everything this script writes lives under the data dir so the repo can be
+# mounted read-only; relative values resolve against the repo root.
+DATA_DIR = ROOT / os.environ.get("BENCHMARK_DATA_DIR", "")
+RAW = DATA_DIR / "results" / "raw"
+NORM = DATA_DIR / "results" / "normalized"
+DATASETS = DATA_DIR / "benchmarks" / "shared" / "datasets"
 
 HTTP_REQUESTS = 400
 HTTP_CONCURRENCY = 16
@@ -23,9 +26,17 @@ TEST_REPEAT = 3
 
 
 class _Handler(BaseHTTPRequestHandler):
+    # HTTP/1.1 keeps connections open for clients that ask for keep-alive;
+    # clients sending `Connection: close` still get one request per connection.
+    protocol_version = "HTTP/1.1"
+    # Headers and body go out in separate writes; without TCP_NODELAY a reused
+    # connection stalls on delayed ACKs.
+    disable_nagle_algorithm = True
+
     def do_GET(self) -> None:  # noqa: N802
         if not self.path.startswith("/item/"):
             self.send_response(404)
+            self.send_header("Content-Length", "0")
             self.end_headers()
             return
 
@@ -33,6 +44,7 @@ class _Handler(BaseHTTPRequestHandler):
             item_id = int(self.path.split("/")[-1])
         except ValueError:
             self.send_response(400)
+            self.send_header("Content-Length", "0")
             self.end_headers()
             return
 
@@ -87,6 +99,18 @@ def ensure_prereqs() -> None:
         raise SystemExit(f"Missing required tool(s): {missing_joined}")
 
 
+def ensure_writable(path: Path) -> None:
+    probe = path / f".write-probe-{os.getpid()}"
+    try:
+        path.mkdir(parents=True, exist_ok=True)
+        probe.write_bytes(b"")
+        probe.unlink()
+    except OSError as exc:
+        raise SystemExit(
+            f"data dir {path} is not writable ({exc}); set BENCHMARK_DATA_DIR to a writable path"
+        ) from exc
+
+
 def configure_linker_env(env: dict[str, str]) -> None:
     if shutil.which("cc"):
         return
@@ -108,6 +132,7 @@ def configure_linker_env(env: dict[str, str]) -> None:
 
 def main() -> None:
     ensure_prereqs()
+    ensure_writable(DATA_DIR)
     RAW.mkdir(parents=True, exist_ok=True)
     NORM.mkdir(parents=True, exist_ok=True)
     DATASETS.mkdir(parents=True, exist_ok=True)
@@ -126,13 +151,23 @@ def main() -> None:
     env["BENCHMARK_HTTP_CONCURRENCY"] = str(HTTP_CONCURRENCY)
     env["BENCHMARK_HTTP_ROWS"] = str(HTTP_ROWS)
     env["BENCHMARK_ETL_DATASET"] = str(etl_dataset)
-    env["BENCHMARK_TEST_REPEAT"] = str(TEST_REPEAT)
+    env["BENCHMARK_DATA_DIR"] = str(DATA_DIR)
+    env["BENCHMARK_TEST_ITERATIONS"] = str(TEST_REPEAT)
     configure_linker_env(env)
 
     try:
         run_cmd(["python3", "benchmarks/python/perf/runner.py", "--output", str(py_raw)], env=env)
         run_cmd(
-            ["cargo", "run", "--manifest-path", "benchmarks/rust/Cargo.toml", "--", "--output", str(rs_raw)],
+            [
+                "cargo",
+                "run",
+                "--manifest-path",
+                "benchmarks/rust/Cargo.toml",
+                "--",
+                "--output",
+                str(rs_raw),
+                "--harness-overhead",
+            ],
             env=env,
         )
     finally:
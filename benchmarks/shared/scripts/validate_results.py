@@ -36,6 +36,38 @@ def metric_value(item: dict, name: str) -> float | None:
         return None
 
 
+COMPANION_SUFFIXES = ("_n", "_rse")
+
+
+def sampled_metrics(metrics: dict) -> dict[str, dict[str, object]]:
+    """Groups `<key>_n` / `<key>_rse` companions under their base metric."""
+    grouped: dict[str, dict[str, object]] = {}
+    for name in metrics:
+        for suffix in COMPANION_SUFFIXES:
+            base = name[: -len(suffix)]
+            if name.endswith(suffix) and base in metrics:
+                grouped.setdefault(base, {})[suffix] = metrics[name]
+    return grouped
+
+
+def validate_companions(idx: int, metrics: dict) -> list[str]:
+    """Every sampled metric has `_n`; only means and medians have `_rse`."""
+    errors: list[str] = []
+    for base, companions in sampled_metrics(metrics).items():
+        if "_n" not in companions:
+            errors.append(f"[{idx}] metric {base} is missing companion {base}_n")
+            continue
+        count = metric_value({"metrics": metrics}, f"{base}_n")
+        if count is None or count < 0 or count != int(count):
+            errors.append(f"[{idx}] metric {base}_n must be a non-negative integer count")
+        if "_rse" not in companions:
+            continue
+        rse = metric_value({"metrics": metrics}, f"{base}_rse")
+        if rse is None or rse < 0:
+            errors.append(f"[{idx}] metric {base}_rse must be non-negative")
+    return errors
+
+
 def validate_performance_parity(results: list[dict]) -> list[str]:
     errors: list[str] = []
     grouped: dict[str, dict[str, list[dict]]] = defaultdict(lambda: defaultdict(list))
@@ -43,6 +75,13 @@ def validate_performance_parity(results: list[dict]) -> list[str]:
     for item in results:
         if item.get("category") != "performance":
             continue
+        # Superseded retry attempts are kept for auditing but never compared.
+        if item.get("selected_for_comparison") is False:
+            continue
+        # Later targets of a multi-target run have no Python counterpart.
+        target = item.get("target")
+        if isinstance(target, dict) and target.get("index", 0) != 0:
+            continue
         benchmark_id = item.get("benchmark_id")
         language = item.get("language")
         if isinstance(benchmark_id, str) and isinstance(language, str):
@@ -84,6 +123,40 @@ def validate_performance_parity(results: list[dict]) -> list[str]:
     return errors
 
 
+def fingerprint_skew(results: list[dict]) -> list[str]:
+    """One message per language whose records carry more than one
+    `harness_fingerprint`: they came from harness builds whose workloads or
+    formulas may differ, so comparing them may be meaningless."""
+    fingerprints: dict[str, set[str]] = defaultdict(set)
+    for item in results:
+        fingerprint = item.get("harness_fingerprint")
+        language = item.get("language")
+        if isinstance(fingerprint, str) and isinstance(language, str):
+            fingerprints[language].add(fingerprint)
+    return [
+        f"[{language}] records come from {len(found)} harness builds: {', '.join(sorted(found))}"
+        for language, found in sorted(fingerprints.items())
+        if len(found) > 1
+    ]
+
+
+def thp_skew(results: list[dict]) -> list[str]:
+    """One message per benchmark whose records were measured under different
+    transparent hugepage modes; memory-heavy results are not comparable
+    across them. Records without `environment.thp_enabled` are left out."""
+    modes: dict[str, set[str]] = defaultdict(set)
+    for item in results:
+        mode = item.get("environment", {}).get("thp_enabled")
+        benchmark_id = item.get("benchmark_id")
+        if isinstance(mode, str) and isinstance(benchmark_id, str):
+            modes[benchmark_id].add(mode)
+    return [
+        f"[{benchmark_id}] records were measured under THP modes: {', '.join(sorted(found))}"
+        for benchmark_id, found in sorted(modes.items())
+        if len(found) > 1
+    ]
+
+
 def validate(results_path: Path, _schema_path: Path | None = None) -> list[str]:
     results = json.loads(results_path.read_text(encoding="utf-8"))
     errors: list[str] = []
@@ -96,12 +169,15 @@ def validate(results_path: Path, _schema_path: Path | None = None) -> list[str]:
             errors.append(f"[{idx}] invalid category")
         if item["language"] not in {"python", "rust"}:
             errors.append(f"[{idx}] invalid language")
+        if not isinstance(item.get("parameters", {}), dict):
+            errors.append(f"[{idx}] parameters must be an object")
         if not isinstance(item["metrics"], dict) or not item["metrics"]:
             errors.append(f"[{idx}] metrics must be a non-empty object")
         else:
             for name, metric in item["metrics"].items():
                 if not isinstance(metric, dict) or "value" not in metric or "unit" not in metric:
                     errors.append(f"[{idx}] metric {name} malformed")
+            errors.extend(validate_companions(idx, item["metrics"]))
         try:
             datetime.fromisoformat(item["timestamp"].replace("Z", "+00:00"))
         except Exception:
@@ -114,9 +190,24 @@ def main() -> None:
     parser = argparse.ArgumentParser()
     parser.add_argument("results")
     parser.add_argument("--schema", default="benchmarks/shared/schemas/result.schema.json")
+    parser.add_argument(
+        "--on-fingerprint-mismatch",
+        choices=["warn", "refuse"],
+        default="warn",
+        help="what to do when records of one language come from different harness builds",
+    )
     args = parser.parse_args()
 
     errors = validate(Path(args.results), Path(args.schema))
+    results = json.loads(Path(args.results).read_text(encoding="utf-8"))
+    skew = fingerprint_skew(results)
+    if args.on_fingerprint_mismatch == "refuse":
+        errors.extend(skew)
+    else:
+        for message in skew:
+            print(f"warning: {message}")
+    for message in thp_skew(results):
+        print(f"warning: {message}")
     if errors:
         for error in errors:
             print(error)